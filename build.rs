@@ -0,0 +1,19 @@
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    #[cfg(feature = "grpc")]
+    grpc();
+    #[cfg(feature = "node")]
+    napi_build::setup();
+}
+
+/// Generate the gRPC service and client from `proto/page_indexer.proto`, using a
+/// vendored `protoc` so no system install is needed.
+#[cfg(feature = "grpc")]
+fn grpc() {
+    let protoc = protoc_bin_vendored::protoc_bin_path().expect("vendored protoc not available");
+    let mut config = tonic_prost_build::Config::new();
+    config.protoc_executable(protoc);
+    tonic_prost_build::configure()
+        .compile_with_config(config, &["proto/page_indexer.proto"], &["proto"])
+        .expect("failed to compile proto/page_indexer.proto");
+}
@@ -0,0 +1,59 @@
+/*
+ * C interface to rust_page_indexer (build with `cargo build --release --features ffi`,
+ * then link against target/release/librust_page_indexer.so / .dylib / .dll).
+ *
+ * Functions returning a pointer return NULL on failure, including an internal
+ * panic; pi_last_error() then describes the failure. Strings returned by the library are UTF-8 and must be
+ * freed with pi_string_free(); indexes with pi_index_free().
+ */
+#ifndef PAGE_INDEXER_H
+#define PAGE_INDEXER_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/* A loaded index: tree, optional source document and LLM client. */
+typedef struct PiIndex PiIndex;
+
+/*
+ * Open a saved index (.json or .bin).
+ *
+ * document_path (nullable): the text file the index was built from; with it,
+ *   search results include each section's content.
+ * llm_config (nullable): JSON object with "api_base", "api_key" and "model"
+ *   (optionally "max_tokens", "temperature"). When NULL, configuration is read
+ *   from LLM_* environment variables and the config file, as for the CLI.
+ */
+PiIndex *pi_index_open(const char *tree_path, const char *document_path, const char *llm_config);
+
+/*
+ * Search an index. Returns a JSON array of results ({"title", "start_index",
+ * "end_index", "relevance", "reason", "content"?}). top_k of 0 uses the
+ * default. Blocks until the LLM calls finish; do not search one index from
+ * two threads at once.
+ */
+char *pi_index_search(const PiIndex *index, const char *query, size_t top_k);
+
+/* The index's tree as JSON. */
+char *pi_index_tree_json(const PiIndex *index);
+
+/* Free an index. NULL is ignored. */
+void pi_index_free(PiIndex *index);
+
+/* Free a string returned by the library. NULL is ignored. */
+void pi_string_free(char *s);
+
+/*
+ * The last error on the calling thread, or NULL. Valid until the next call
+ * into the library on the same thread; do not free.
+ */
+const char *pi_last_error(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* PAGE_INDEXER_H */
@@ -0,0 +1,69 @@
+/*
+ * TypeScript declarations for the Node.js addon (build with
+ * `cargo build --release --features node`, then copy
+ * target/release/librust_page_indexer.so / .dylib / rust_page_indexer.dll
+ * next to this file as rust_page_indexer.node).
+ *
+ * Every method returns a promise; LLM calls run off the event loop.
+ */
+
+export interface LlmConfig {
+  api_base: string;
+  api_key: string;
+  model: string;
+  max_tokens?: number;
+  temperature?: number;
+}
+
+export interface TreeNode {
+  title: string;
+  node_id?: string;
+  structure?: string;
+  start_index: number;
+  end_index: number;
+  summary?: string;
+  nodes?: TreeNode[];
+}
+
+export interface DocumentTree {
+  name: string;
+  nodes: TreeNode[];
+  total_pages: number;
+}
+
+export interface SearchResult {
+  title: string;
+  start_index: number;
+  end_index: number;
+  relevance: "high" | "medium" | "low";
+  reason: string;
+  /** Section text, when the source document is loaded. */
+  content?: string;
+}
+
+export interface Answer {
+  answer: string;
+  sources: SearchResult[];
+}
+
+export interface UsageStats {
+  calls: number;
+  prompt_tokens: number;
+  completion_tokens: number;
+  total_tokens: number;
+}
+
+export class PageIndex {
+  /** Without a config, LLM_* environment variables and the config file are used. */
+  constructor(llmConfig?: LlmConfig);
+  /** Index a text file, replacing any loaded tree. */
+  indexFile(path: string): Promise<DocumentTree>;
+  /** Load a saved tree (.json or .bin). */
+  load(path: string): Promise<DocumentTree>;
+  /** Attach the source text of the loaded tree, so results include section text. */
+  loadDocument(path: string): Promise<void>;
+  save(path: string): Promise<void>;
+  search(query: string, topK?: number): Promise<SearchResult[]>;
+  ask(question: string, topK?: number): Promise<Answer>;
+  usage(): Promise<UsageStats>;
+}
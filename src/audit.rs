@@ -0,0 +1,227 @@
+//! Append-only audit log of retrievals and answers.
+//!
+//! Teams deploying against regulated documents need a record of who asked
+//! what and what they were shown. An [`AuditLog`] appends one JSON line per
+//! search or answer to a file: when it happened, the tenant and index, the
+//! query, the IDs of the sections retrieved (see [`section_key`]), a hash of
+//! the answer text and the tokens spent. Lines are only ever appended, and
+//! each is written whole, so the file can be shipped to a log store as it
+//! grows.
+//!
+//! ```no_run
+//! use rust_page_indexer::audit::{AuditLog, AuditRecord};
+//!
+//! # fn run() -> rust_page_indexer::Result<()> {
+//! let log = AuditLog::open("data/audit.jsonl")?;
+//! log.record(&AuditRecord::search("What were the results?", &[]))?;
+//! for record in AuditLog::read(log.path())? {
+//!     println!("{} {:?} {}", record.timestamp, record.operation, record.query);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{PageIndexError, Result};
+use crate::llm::UsageStats;
+use crate::page_index::Answer;
+use crate::retriever::{RetrieverDocument, result_documents};
+use crate::search::SearchResult;
+use crate::stats::section_key;
+use crate::tree::{DocumentTree, fnv1a_hex};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// What an audited request did.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditOperation {
+    /// Retrieved sections for a query.
+    #[default]
+    Search,
+    /// Answered a question from retrieved sections.
+    Ask,
+}
+
+/// One line of the audit log.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    /// When the request completed, in seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub operation: AuditOperation,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    /// The index searched: its name when served, its path otherwise.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub index: Option<String>,
+    pub query: String,
+    /// Sections retrieved, best first, keyed by [`section_key`].
+    pub node_ids: Vec<String>,
+    /// FNV-1a hash of the answer text, for answers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub answer_hash: Option<String>,
+    /// Served from a cache without calling the LLM.
+    #[serde(default)]
+    pub cached: bool,
+    /// Tokens spent on the request, when they can be told apart from
+    /// concurrent requests'.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub usage: Option<UsageStats>,
+}
+
+impl AuditRecord {
+    /// A search that retrieved `documents`.
+    pub fn search(query: impl Into<String>, documents: &[RetrieverDocument]) -> Self {
+        Self {
+            timestamp: now(),
+            operation: AuditOperation::Search,
+            query: query.into(),
+            node_ids: documents.iter().map(section_key).collect(),
+            ..Self::default()
+        }
+    }
+
+    /// An answer to `question` from sections of `tree`.
+    pub fn ask(question: impl Into<String>, answer: &Answer, tree: &DocumentTree) -> Self {
+        Self {
+            timestamp: now(),
+            operation: AuditOperation::Ask,
+            query: question.into(),
+            node_ids: section_ids(tree, &answer.sources),
+            answer_hash: Some(fnv1a_hex(answer.answer.as_bytes())),
+            ..Self::default()
+        }
+    }
+}
+
+/// [`section_key`]s of search results from `tree`.
+pub fn section_ids(tree: &DocumentTree, results: &[SearchResult]) -> Vec<String> {
+    result_documents(tree, results)
+        .iter()
+        .map(section_key)
+        .collect()
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// An audit log file, shared by the requests of a server.
+pub struct AuditLog {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl AuditLog {
+    /// Append to the log at `path`, creating it if needed.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        if let Some(dir) = path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(|e| PageIndexError::io(dir, e))?;
+        }
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| PageIndexError::io(&path, e))?;
+        Ok(Self {
+            path,
+            file: Mutex::new(file),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append a record as one line.
+    pub fn record(&self, record: &AuditRecord) -> Result<()> {
+        let mut line = serde_json::to_string(record)
+            .map_err(|e| PageIndexError::Serialization(e.to_string()))?;
+        line.push('\n');
+        let mut file = self.file.lock().expect("audit log lock poisoned");
+        file.write_all(line.as_bytes())
+            .and_then(|()| file.flush())
+            .map_err(|e| PageIndexError::io(&self.path, e))
+    }
+
+    /// Read the records of a log, oldest first.
+    pub fn read(path: &Path) -> Result<Vec<AuditRecord>> {
+        let content = std::fs::read_to_string(path).map_err(|e| PageIndexError::io(path, e))?;
+        content
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                serde_json::from_str(line).map_err(|e| PageIndexError::Serialization(e.to_string()))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::page_index::AnswerOutcome;
+    use crate::search::Relevance;
+    use crate::tree::TreeNode;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_audit_log() {
+        let mut methods = TreeNode::new("Methods", 3, 5);
+        methods.node_id = Some("0001".to_string());
+        let tree = DocumentTree::new("report", vec![methods], 5);
+        let sources = vec![SearchResult {
+            title: "Methods".to_string(),
+            start_index: 3,
+            end_index: 5,
+            relevance: Relevance::High,
+            reason: String::new(),
+            content: None,
+            snippet: None,
+            snippet_span: None,
+        }];
+        let answer = Answer {
+            answer: "A survey.".to_string(),
+            sources: sources.clone(),
+            outcome: AnswerOutcome::default(),
+        };
+
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("logs/audit.jsonl");
+        let log = AuditLog::open(&path).unwrap();
+        let mut search = AuditRecord::search("methods", &result_documents(&tree, &sources));
+        search.tenant = Some("research".to_string());
+        search.usage = Some(UsageStats {
+            calls: 1,
+            ..UsageStats::default()
+        });
+        log.record(&search).unwrap();
+
+        // Reopening appends
+        let log = AuditLog::open(&path).unwrap();
+        let mut ask = AuditRecord::ask("How was it done?", &answer, &tree);
+        ask.cached = true;
+        log.record(&ask).unwrap();
+
+        let records = AuditLog::read(&path).unwrap();
+        assert_eq!(records, vec![search, ask]);
+        assert_eq!(records[0].node_ids, vec!["0001"]);
+        assert_eq!(records[1].operation, AuditOperation::Ask);
+        assert_eq!(
+            records[1].answer_hash.as_deref(),
+            Some(fnv1a_hex(b"A survey.").as_str())
+        );
+        assert!(records[0].answer_hash.is_none());
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content.lines().count(), 2);
+        assert!(content.contains("\"operation\":\"ask\""));
+        assert!(!content.lines().next().unwrap().contains("answer_hash"));
+    }
+}
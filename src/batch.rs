@@ -0,0 +1,364 @@
+//! Batch query execution against a single index.
+//!
+//! Queries are read from a file (plain text, one per line, or JSONL with a
+//! `query` field and optional `id`), run concurrently, and written to a JSON
+//! or CSV report for checking an index against a known question set.
+
+use crate::document::Document;
+use crate::error::{PageIndexError, Result};
+use crate::export::csv_escape;
+use crate::outcome::FailureSummary;
+use crate::progress::{ProgressCallback, ProgressEvent, emit};
+use crate::search::{SearchResult, TreeSearcher};
+use crate::tree::DocumentTree;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+use tokio::sync::Semaphore;
+use tokio::task::{JoinError, JoinSet};
+
+/// Default number of queries in flight at once.
+const DEFAULT_CONCURRENCY: usize = 4;
+
+/// A query to run in a batch.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BatchQuery {
+    /// Query identifier (defaults to the 1-based line number).
+    #[serde(default)]
+    pub id: String,
+    /// The query text.
+    pub query: String,
+}
+
+/// Outcome of one batch query.
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchResult {
+    pub id: String,
+    pub query: String,
+    pub results: Vec<SearchResult>,
+    /// Error message if the search failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// [`PageIndexError::kind`] of the error if the search failed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_kind: Option<String>,
+    pub search_time_ms: u64,
+}
+
+/// Parse a queries file: JSONL if lines are JSON objects, otherwise one query per line.
+///
+/// Blank lines and lines starting with `#` are skipped.
+pub fn parse_queries(content: &str) -> Result<Vec<BatchQuery>> {
+    let mut queries = Vec::new();
+
+    for (i, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut query = if line.starts_with('{') {
+            serde_json::from_str::<BatchQuery>(line).map_err(|e| {
+                PageIndexError::Serialization(format!("queries line {}: {}", i + 1, e))
+            })?
+        } else {
+            BatchQuery {
+                id: String::new(),
+                query: line.to_string(),
+            }
+        };
+        if query.id.is_empty() {
+            query.id = (i + 1).to_string();
+        }
+        queries.push(query);
+    }
+
+    Ok(queries)
+}
+
+/// Load queries from a file.
+pub fn load_queries(path: &Path) -> Result<Vec<BatchQuery>> {
+    let content = std::fs::read_to_string(path).map_err(|e| PageIndexError::io(path, e))?;
+    parse_queries(&content)
+}
+
+/// Runs many queries against one tree with bounded concurrency.
+pub struct BatchRunner {
+    searcher: Arc<TreeSearcher>,
+    concurrency: usize,
+    progress: Option<ProgressCallback>,
+}
+
+impl BatchRunner {
+    /// Create a runner that searches with the given searcher.
+    pub fn new(searcher: TreeSearcher) -> Self {
+        Self {
+            searcher: Arc::new(searcher),
+            concurrency: DEFAULT_CONCURRENCY,
+            progress: None,
+        }
+    }
+
+    /// Set the maximum number of queries in flight.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Report an `ItemCompleted` event per finished query.
+    pub fn with_progress(mut self, callback: ProgressCallback) -> Self {
+        self.progress = Some(callback);
+        self
+    }
+
+    /// Run all queries, returning results in input order.
+    ///
+    /// A failed query is recorded in its result's `error` instead of aborting
+    /// the batch, as is one whose task panicked. With a document, results
+    /// include section content.
+    pub async fn run(
+        &self,
+        tree: Arc<DocumentTree>,
+        document: Option<Arc<Document>>,
+        queries: Vec<BatchQuery>,
+    ) -> Vec<BatchResult> {
+        let total = queries.len();
+        let semaphore = Arc::new(Semaphore::new(self.concurrency));
+        let completed = Arc::new(AtomicUsize::new(0));
+        let mut tasks = JoinSet::new();
+        let mut spawned = HashMap::new();
+
+        for (index, query) in queries.into_iter().enumerate() {
+            let queued = query.clone();
+            let searcher = self.searcher.clone();
+            let tree = tree.clone();
+            let document = document.clone();
+            let semaphore = semaphore.clone();
+            let completed = completed.clone();
+            let progress = self.progress.clone();
+
+            let handle = tasks.spawn(async move {
+                let _permit = semaphore.acquire_owned().await.expect("semaphore closed");
+                let start = Instant::now();
+                let outcome = match &document {
+                    Some(document) => {
+                        searcher
+                            .search_with_content(&tree, document.as_ref(), &query.query)
+                            .await
+                    }
+                    None => searcher.search(&tree, &query.query).await,
+                };
+
+                let (results, error, error_kind) = match outcome {
+                    Ok(results) => (results, None, None),
+                    Err(e) => (Vec::new(), Some(e.to_string()), Some(e.kind().to_string())),
+                };
+                emit(
+                    &progress,
+                    ProgressEvent::ItemCompleted {
+                        completed: completed.fetch_add(1, Ordering::SeqCst) + 1,
+                        total,
+                        item_id: query.id.clone(),
+                    },
+                );
+
+                let result = BatchResult {
+                    id: query.id,
+                    query: query.query,
+                    results,
+                    error,
+                    error_kind,
+                    search_time_ms: start.elapsed().as_millis() as u64,
+                };
+                (index, result)
+            });
+            spawned.insert(handle.id(), (index, queued));
+        }
+
+        let mut slots: Vec<Option<BatchResult>> = (0..total).map(|_| None).collect();
+        while let Some(joined) = tasks.join_next().await {
+            match joined {
+                Ok((index, result)) => slots[index] = Some(result),
+                Err(e) => {
+                    let Some((index, query)) = spawned.remove(&e.id()) else {
+                        continue;
+                    };
+                    emit(
+                        &self.progress,
+                        ProgressEvent::ItemCompleted {
+                            completed: completed.fetch_add(1, Ordering::SeqCst) + 1,
+                            total,
+                            item_id: query.id.clone(),
+                        },
+                    );
+                    slots[index] = Some(task_failure(query, &e));
+                }
+            }
+        }
+        slots.into_iter().flatten().collect()
+    }
+}
+
+/// The result of a query whose task panicked or was cancelled.
+fn task_failure(query: BatchQuery, error: &JoinError) -> BatchResult {
+    let kind = if error.is_panic() {
+        "panic"
+    } else {
+        "cancelled"
+    };
+    BatchResult {
+        id: query.id,
+        query: query.query,
+        results: Vec::new(),
+        error: Some(format!("Query task failed: {}", error)),
+        error_kind: Some(kind.to_string()),
+        search_time_ms: 0,
+    }
+}
+
+/// Failed queries of a batch, by error kind.
+pub fn failure_summary(results: &[BatchResult]) -> FailureSummary {
+    FailureSummary::from_kinds(
+        results.len(),
+        results
+            .iter()
+            .filter(|r| r.error.is_some())
+            .map(|r| r.error_kind.as_deref().unwrap_or("unknown")),
+    )
+}
+
+/// Render batch results as CSV, one row per (query, result).
+///
+/// Queries with no results (or an error) get a single row with empty result columns.
+pub fn to_csv(results: &[BatchResult]) -> String {
+    let mut out =
+        String::from("id,query,rank,title,start_index,end_index,relevance,reason,error\n");
+
+    for batch in results {
+        let error = batch.error.as_deref().unwrap_or("");
+        if batch.results.is_empty() {
+            out.push_str(&format!(
+                "{},{},,,,,,,{}\n",
+                csv_escape(&batch.id),
+                csv_escape(&batch.query),
+                csv_escape(error)
+            ));
+            continue;
+        }
+        for (rank, result) in batch.results.iter().enumerate() {
+            out.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{}\n",
+                csv_escape(&batch.id),
+                csv_escape(&batch.query),
+                rank + 1,
+                csv_escape(&result.title),
+                result.start_index,
+                result.end_index,
+                format!("{:?}", result.relevance).to_lowercase(),
+                csv_escape(&result.reason),
+                csv_escape(error)
+            ));
+        }
+    }
+
+    out
+}
+
+/// Write a report as CSV if the path ends in `.csv`, otherwise as JSON.
+pub fn write_report(results: &[BatchResult], path: &Path) -> Result<()> {
+    let is_csv = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("csv"));
+
+    let content = if is_csv {
+        to_csv(results)
+    } else {
+        serde_json::to_string_pretty(results)
+            .map_err(|e| PageIndexError::Serialization(e.to_string()))?
+    };
+    std::fs::write(path, content).map_err(|e| PageIndexError::io(path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::Relevance;
+
+    #[test]
+    fn test_parse_plain_and_jsonl_queries() {
+        let content = "# smoke tests\nWhat is ownership?\n\n{\"id\": \"q7\", \"query\": \"How do traits work?\"}\n{\"query\": \"Lifetimes?\"}\n";
+        let queries = parse_queries(content).unwrap();
+
+        assert_eq!(queries.len(), 3);
+        assert_eq!(queries[0].id, "2");
+        assert_eq!(queries[0].query, "What is ownership?");
+        assert_eq!(queries[1].id, "q7");
+        assert_eq!(queries[2].id, "5");
+
+        assert!(parse_queries("{\"id\": 1}").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_task_failure() {
+        let query = BatchQuery {
+            id: "q1".to_string(),
+            query: "What panics?".to_string(),
+        };
+        let panicked = tokio::spawn(async { panic!("boom") }).await.unwrap_err();
+        let result = task_failure(query, &panicked);
+
+        assert_eq!(result.id, "q1");
+        assert!(result.error.as_deref().unwrap().contains("panic"));
+        let summary = failure_summary(&[result]);
+        assert_eq!(summary.by_kind.get("panic"), Some(&1));
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let results = vec![
+            BatchResult {
+                id: "1".to_string(),
+                query: "ownership, borrowing".to_string(),
+                results: vec![SearchResult {
+                    title: "Ownership".to_string(),
+                    start_index: 3,
+                    end_index: 5,
+                    relevance: Relevance::High,
+                    reason: "Defines it".to_string(),
+                    content: None,
+                    snippet: None,
+                    snippet_span: None,
+                }],
+                error: None,
+                error_kind: None,
+                search_time_ms: 10,
+            },
+            BatchResult {
+                id: "2".to_string(),
+                query: "traits".to_string(),
+                results: Vec::new(),
+                error: Some("timeout".to_string()),
+                error_kind: Some("timeout".to_string()),
+                search_time_ms: 0,
+            },
+        ];
+
+        let csv = to_csv(&results);
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[1],
+            "1,\"ownership, borrowing\",1,Ownership,3,5,high,Defines it,"
+        );
+        assert_eq!(lines[2], "2,traits,,,,,,,timeout");
+
+        assert_eq!(
+            failure_summary(&results).to_string(),
+            "1 of 2 failed (timeout: 1)"
+        );
+    }
+}
@@ -2,25 +2,80 @@
 //!
 //! Usage:
 //!   eval sample              # Run on built-in sample dataset
-//!   eval quality <path>      # Run on QuALITY dataset
+//!   eval quality <path>      # Run on QuALITY dataset (file, or data dir with --split train|dev)
+//!   eval quality <path> --hard --max-articles 20 # Focused slice: hard questions from 20 articles
+//!   eval narrativeqa <dir>   # Run on NarrativeQA (see `eval download --dataset narrativeqa`)
+//!   eval hotpotqa <path>     # Run on HotpotQA-style multi-hop JSON
 //!   eval custom <path>       # Run on custom JSON dataset
+//!   eval generate <index>... # Write a Q&A dataset generated from indexed documents (to --output)
+//!   eval regress <baseline> <candidate> --queries <file> # Diff two versions of an index
 //!
 //! Options:
 //!   --max-items <N>          # Limit number of items
+//!   --sample <N>             # Evaluate a random (seeded) subset of N items
+//!   --shuffle                # Shuffle item order (seeded) before --max-items
+//!   --seed <SEED>            # Seed for --sample and --shuffle (default: 0)
 //!   --top-k <N>              # Number of results to retrieve (default: 3)
 //!   --pageindex-only         # Only run PageIndex
 //!   --vector-only            # Only run vector search
-//!   --verbose                # Verbose output
-//!   --output <path>          # Save results to JSON file
-
-use anyhow::Result;
-use clap::{Parser, Subcommand};
-use rust_page_indexer::config::Config;
+//!   --bm25                   # Also run a BM25 keyword baseline (three-way comparison)
+//!   --hybrid                 # Also run a BM25 + vector fusion baseline
+//!   --rrf-k <K>              # Reciprocal rank fusion constant (default: 60)
+//!   --context-budget <N>     # Token budget for PageIndex's answer context (default: no limit)
+//!   --rerank                 # Also run vector search reranked by a cross-encoder
+//!   --rerank-pageindex       # Rerank PageIndex's sections with the cross-encoder
+//!   --reranker-model <name>  # Cross-encoder (minilm, minilm-l12, tinybert or HF id)
+//!   --rerank-candidates <N>  # Vector candidates passed to the reranker (default: 20)
+//!   --multiple-choice        # Pick options and report accuracy (QuALITY)
+//!   --swap-judge-order       # Judge in both A/B orders to cancel position bias
+//!   --judge-model <model>    # Add a judge model to an ensemble (repeatable)
+//!   --rubric <path>          # Judge with custom weighted criteria
+//!   --grade-answers          # Also grade each answer against the gold answer (works with one system)
+//!   --check-groundedness     # Also check each answer is supported by its own retrieved content
+//!   --tournament             # Judge every pair of systems and rank them (Bradley–Terry leaderboard)
+//!   --judge-cache <path>     # Reuse judge verdicts across runs (JSONL cache file)
+//!   --embedding-backend <local|api> # Local candle model or the provider's embeddings API
+//!   --embedding-model <name> # Vector baseline model (minilm, bge-small, e5-small, gte-small, HF or API id)
+//!   --device <dev>           # Embedding device: auto, cpu, cuda[:N], metal (default: auto)
+//!   --embed-batch-size <N>   # Texts per embedding forward pass (default: 32)
+//!   --hnsw                   # Use HNSW approximate search for the vector baseline
+//!   --hnsw-ef <N>            # HNSW search beam width (default: 50)
+//!   --chunk-unit <chars|tokens> # Measure --chunk-size/--chunk-overlap in characters or tokens
+//!   --vector-cache <dir>     # Reuse persisted vector indexes across runs
+//!   --tree-dir <dir>         # Load prebuilt PageIndex trees (by document hash or item id), saving new ones
+//!   --results-log <path>     # Append per-item results to a JSONL file
+//!   --resume <path>          # Continue an interrupted run from its results log
+//!   -v, --verbose            # Verbose output (repeat for debug logs)
+//!   -q, --quiet              # No progress bar (for CI logs)
+//!   --log-format <text|json> # Log output format
+//!   --output <path>          # Save results to JSON file (generate: the dataset; regress: the report)
+//!   --report <path>          # Write an HTML (.html) or Markdown report
+//!   --export <path>          # Export item-level results as CSV (or .parquet, `parquet` feature)
+//!   --usage-report <path>    # Save LLM usage/cost report to JSON file
+
+use anyhow::{Context, Result};
+use clap::{ArgAction, Parser, Subcommand, ValueEnum};
+use indicatif::{ProgressBar, ProgressStyle};
+use rust_page_indexer::config::{Config, LlmConfig};
+use rust_page_indexer::eval::synthetic::{DEFAULT_QUESTIONS_PER_SECTION, SYNTHETIC_SOURCE};
 use rust_page_indexer::eval::{
-    Benchmark, BenchmarkConfig, BenchmarkResults, ChunkConfig, create_sample_dataset,
-    load_quality_dataset, load_simple_dataset,
+    Benchmark, BenchmarkConfig, BenchmarkResults, ChunkConfig, ChunkUnit, Dataset,
+    EmbeddingBackend, EmbeddingDevice, HnswConfig, JudgeCache, LlmJudge, QualityFilter,
+    QuestionGenerator, RegressionQuery, RegressionRun, Rubric, create_sample_dataset,
+    download_narrativeqa, export, load_hotpotqa_dataset, load_narrativeqa_dataset,
+    load_quality_dataset_filtered, load_simple_dataset, quality_split_path, report,
 };
-use std::path::PathBuf;
+use rust_page_indexer::llm::LlmClient;
+use rust_page_indexer::loader;
+use rust_page_indexer::logging::{self, LogFormat};
+use rust_page_indexer::persistence::{index_files, load_tree};
+use rust_page_indexer::progress::{ProgressCallback, ProgressEvent};
+use rust_page_indexer::tree::DocumentTree;
+use rust_page_indexer::usage::UsageTracker;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::info;
 
 #[derive(Parser)]
 #[command(name = "eval")]
@@ -33,6 +88,18 @@ struct Cli {
     #[arg(long, global = true)]
     max_items: Option<usize>,
 
+    /// Evaluate a random subset of N items, reproducible with --seed
+    #[arg(long, global = true, value_name = "N")]
+    sample: Option<usize>,
+
+    /// Shuffle item order before applying --max-items, reproducible with --seed
+    #[arg(long, global = true)]
+    shuffle: bool,
+
+    /// Seed for --sample and --shuffle
+    #[arg(long, global = true, default_value = "0")]
+    seed: u64,
+
     /// Number of top results to retrieve
     #[arg(long, global = true, default_value = "3")]
     top_k: usize,
@@ -45,14 +112,150 @@ struct Cli {
     #[arg(long, global = true)]
     vector_only: bool,
 
-    /// Verbose output
-    #[arg(short, long, global = true)]
-    verbose: bool,
+    /// Also run a BM25 keyword baseline, judged against PageIndex
+    #[arg(long, global = true)]
+    bm25: bool,
+
+    /// Also run a hybrid baseline fusing BM25 and vector rankings (reciprocal rank fusion)
+    #[arg(long, global = true)]
+    hybrid: bool,
+
+    /// Reciprocal rank fusion constant for the hybrid baseline
+    #[arg(long, global = true, default_value = "60")]
+    rrf_k: f32,
+
+    /// Token budget for the context PageIndex answers from (default: no limit)
+    #[arg(long, global = true, value_name = "TOKENS")]
+    context_budget: Option<usize>,
+
+    /// Also run a baseline reranking vector search candidates with a cross-encoder
+    /// (needs the local-embeddings feature)
+    #[arg(long, global = true)]
+    rerank: bool,
+
+    /// Order PageIndex's retrieved sections by cross-encoder score
+    #[arg(long, global = true)]
+    rerank_pageindex: bool,
+
+    /// Cross-encoder model: minilm (default), minilm-l12, tinybert, or a BERT-based
+    /// Hugging Face id
+    #[arg(long, global = true, value_name = "NAME")]
+    reranker_model: Option<String>,
+
+    /// Number of vector search candidates passed to the reranker
+    #[arg(long, global = true, default_value = "20", value_name = "N")]
+    rerank_candidates: usize,
+
+    /// Answer multiple-choice items (e.g. QuALITY) by picking an option and report accuracy
+    #[arg(long, global = true)]
+    multiple_choice: bool,
+
+    /// Judge each comparison in both A/B orders to cancel position bias (2x judge calls)
+    #[arg(long, global = true)]
+    swap_judge_order: bool,
+
+    /// Judge model for an ensemble judge (repeat for several; default: the answering model)
+    #[arg(long = "judge-model", global = true, value_name = "MODEL")]
+    judge_models: Vec<String>,
+
+    /// Judging rubric (YAML/JSON list of weighted criteria)
+    #[arg(long, global = true, value_name = "FILE")]
+    rubric: Option<PathBuf>,
+
+    /// Grade each system's answer against the gold answer on its own, so
+    /// single-system runs (e.g. --pageindex-only) still get a quality score
+    #[arg(long, global = true)]
+    grade_answers: bool,
+
+    /// Check whether each answer is supported by its own retrieved content,
+    /// flagging answers that are right only from the model's prior knowledge
+    #[arg(long, global = true)]
+    check_groundedness: bool,
+
+    /// Judge every pair of systems, not just each against PageIndex, and rank
+    /// them on a Bradley–Terry leaderboard
+    #[arg(long, global = true)]
+    tournament: bool,
+
+    /// Embedding backend: a local candle model (needs the local-embeddings feature) or
+    /// the LLM provider's embeddings API (default: local when available)
+    #[arg(long, global = true, value_enum)]
+    embedding_backend: Option<EmbeddingBackend>,
+
+    /// Embedding model for vector search. Local: minilm (default), bge-small, bge-base,
+    /// e5-small, e5-base, gte-small, gte-base, or a BERT-based Hugging Face id.
+    /// API: the provider's model id (default: text-embedding-3-small)
+    #[arg(long, global = true, value_name = "NAME")]
+    embedding_model: Option<String>,
+
+    /// Embedding device: auto, cpu, cuda, cuda:N or metal (GPUs need the cuda/metal feature)
+    #[arg(long, global = true, default_value = "auto", value_name = "DEVICE")]
+    device: EmbeddingDevice,
+
+    /// Number of texts per embedding forward pass
+    #[arg(long, global = true, default_value = "32", value_name = "N")]
+    embed_batch_size: usize,
+
+    /// Use an HNSW approximate nearest-neighbor index for vector search
+    #[arg(long, global = true)]
+    hnsw: bool,
+
+    /// HNSW search beam width (higher is more accurate and slower)
+    #[arg(long, global = true, default_value = "50")]
+    hnsw_ef: usize,
+
+    /// Directory to persist vector indexes in, reused across runs with the same chunking
+    #[arg(long, global = true, value_name = "DIR")]
+    vector_cache: Option<PathBuf>,
+
+    /// Directory of prebuilt PageIndex trees (`<document hash>` or `<item id>`,
+    /// `.json` or `.bin`); items with a tree there skip indexing, and trees built
+    /// during the run are saved there
+    #[arg(long, global = true, value_name = "DIR")]
+    tree_dir: Option<PathBuf>,
+
+    /// Cache judge verdicts in this JSONL file and reuse them when a comparison's
+    /// question, answers and judge model are unchanged
+    #[arg(long, global = true, value_name = "FILE")]
+    judge_cache: Option<PathBuf>,
+
+    /// Append per-item results to this JSONL file as they complete
+    #[arg(long, global = true, value_name = "FILE")]
+    results_log: Option<PathBuf>,
+
+    /// Resume an interrupted run from its results log, skipping completed items
+    #[arg(long, global = true, value_name = "FILE")]
+    resume: Option<PathBuf>,
+
+    /// Increase verbosity (-v shows per-item output, -vv debug logs)
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Hide the progress bar (for CI logs)
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Log output format (text or json)
+    #[arg(long, global = true, default_value = "text")]
+    log_format: LogFormat,
 
     /// Save results to JSON file
     #[arg(short, long, global = true)]
     output: Option<PathBuf>,
 
+    /// Write an HTML (.html) or Markdown report of the results to this file
+    #[arg(long, global = true, value_name = "FILE")]
+    report: Option<PathBuf>,
+
+    /// Export item-level results as CSV, or as Parquet if the file ends in .parquet
+    /// (requires the `parquet` feature)
+    #[arg(long, global = true, value_name = "FILE")]
+    export: Option<PathBuf>,
+
+    /// Save a JSON usage report (LLM calls, tokens, cost, timings) to this file
+    #[arg(long, global = true, value_name = "FILE")]
+    usage_report: Option<PathBuf>,
+
     /// Chunk size for vector search
     #[arg(long, global = true, default_value = "512")]
     chunk_size: usize,
@@ -60,6 +263,11 @@ struct Cli {
     /// Chunk overlap for vector search
     #[arg(long, global = true, default_value = "50")]
     chunk_overlap: usize,
+
+    /// Unit of --chunk-size and --chunk-overlap: characters, or estimated tokens
+    /// (chunks then break between words)
+    #[arg(long, global = true, value_enum, default_value = "chars")]
+    chunk_unit: ChunkUnit,
 }
 
 #[derive(Subcommand)]
@@ -69,7 +277,35 @@ enum Commands {
 
     /// Run on QuALITY dataset (JSONL format)
     Quality {
-        /// Path to QuALITY dataset file
+        /// Path to a QuALITY dataset file, or a directory of downloaded splits
+        path: PathBuf,
+
+        /// Split to load when `path` is a directory (train or dev)
+        #[arg(long, default_value = "dev")]
+        split: String,
+
+        /// Only questions from the hard subset
+        #[arg(long)]
+        hard: bool,
+
+        /// Only questions about the first N distinct articles
+        #[arg(long, value_name = "N")]
+        max_articles: Option<usize>,
+    },
+
+    /// Run on NarrativeQA (directory prepared by `download --dataset narrativeqa`)
+    Narrativeqa {
+        /// NarrativeQA data directory
+        path: PathBuf,
+
+        /// Split to evaluate (train, valid, or test)
+        #[arg(long, default_value = "test")]
+        split: String,
+    },
+
+    /// Run on a HotpotQA-style multi-hop dataset (JSON)
+    Hotpotqa {
+        /// Path to HotpotQA JSON file
         path: PathBuf,
     },
 
@@ -79,48 +315,215 @@ enum Commands {
         path: PathBuf,
     },
 
-    /// Download QuALITY dataset
+    /// Generate a Q&A dataset from indexed documents, with questions written
+    /// by the LLM from each section's text (saved to --output)
+    Generate {
+        /// Tree index files, or directories of them
+        #[arg(required = true)]
+        indexes: Vec<PathBuf>,
+
+        /// Source document of a single index (default: the path recorded when
+        /// it was indexed)
+        #[arg(long)]
+        document: Option<PathBuf>,
+
+        /// Questions asked per section
+        #[arg(long, default_value_t = DEFAULT_QUESTIONS_PER_SECTION)]
+        questions_per_section: usize,
+
+        /// Ask about at most N sections per document, picked with --seed
+        #[arg(long, value_name = "N")]
+        max_sections: Option<usize>,
+
+        /// Maximum LLM calls in flight
+        #[arg(long, default_value_t = 4)]
+        concurrency: usize,
+    },
+
+    /// Run the same queries against two versions of an index (e.g. built
+    /// with different prompts or models) and report per-query differences
+    /// and the judge's quality delta
+    Regress {
+        /// Baseline tree index
+        baseline: PathBuf,
+
+        /// Candidate tree index
+        candidate: PathBuf,
+
+        /// Queries: a dataset JSON file (.json, reference answers are given
+        /// to the judge) or one query per line
+        #[arg(long)]
+        queries: PathBuf,
+
+        /// Source document, for answering from section text (default: the
+        /// path recorded in the baseline, if found; otherwise answers come
+        /// from the trees' summaries)
+        #[arg(long)]
+        document: Option<PathBuf>,
+
+        /// Exit with an error if the judge preferred the baseline on any query
+        #[arg(long)]
+        fail_on_regression: bool,
+    },
+
+    /// Download a benchmark dataset
     Download {
         /// Output directory
         #[arg(default_value = "data")]
         output_dir: PathBuf,
+
+        /// Dataset to download
+        #[arg(long, value_enum, default_value = "quality")]
+        dataset: DownloadDataset,
+
+        /// Split to download: train or dev for QuALITY (default: dev), train,
+        /// valid or test for NarrativeQA stories (default: test)
+        #[arg(long)]
+        split: Option<String>,
+
+        /// Maximum number of NarrativeQA stories to download
+        #[arg(long)]
+        max_documents: Option<usize>,
     },
 }
 
+#[derive(Clone, Copy, ValueEnum)]
+enum DownloadDataset {
+    Quality,
+    Narrativeqa,
+    Hotpotqa,
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
     let cli = Cli::parse();
+    logging::init(cli.verbose, cli.log_format, "eval");
 
     // Handle download command separately
-    if let Commands::Download { output_dir } = &cli.command {
-        return download_quality(output_dir).await;
+    if let Commands::Download {
+        output_dir,
+        dataset,
+        split,
+        max_documents,
+    } = &cli.command
+    {
+        return match dataset {
+            DownloadDataset::Quality => {
+                download_quality(output_dir, split.as_deref().unwrap_or("dev")).await
+            }
+            DownloadDataset::Hotpotqa => download_hotpotqa(output_dir).await,
+            DownloadDataset::Narrativeqa => {
+                let split = split.as_deref().unwrap_or("test");
+                let dir = output_dir.join("narrativeqa");
+                let count = download_narrativeqa(&dir, split, *max_documents).await?;
+                println!("{} NarrativeQA stories available in {:?}", count, dir);
+                println!("\nTo run evaluation:");
+                println!("  eval narrativeqa {:?} --split {}", dir, split);
+                Ok(())
+            }
+        };
+    }
+
+    if let Commands::Generate {
+        indexes,
+        document,
+        questions_per_section,
+        max_sections,
+        concurrency,
+    } = &cli.command
+    {
+        let client = LlmClient::new(Config::load()?.llm);
+        let mut generator = QuestionGenerator::new(client.clone())
+            .with_questions_per_section(*questions_per_section)
+            .with_concurrency(*concurrency);
+        if let Some(max) = max_sections {
+            generator = generator.with_max_sections(*max, cli.seed);
+        }
+        return generate_dataset(&cli, indexes, document.as_deref(), &generator, &client).await;
+    }
+
+    if let Commands::Regress {
+        baseline,
+        candidate,
+        queries,
+        document,
+        fail_on_regression,
+    } = &cli.command
+    {
+        return run_regression(
+            &cli,
+            (baseline, candidate),
+            queries,
+            document.as_deref(),
+            *fail_on_regression,
+        )
+        .await;
+    }
+
+    // Fail before the run rather than after it
+    if !cfg!(feature = "parquet") && cli.export.as_deref().is_some_and(export::is_parquet) {
+        anyhow::bail!("Parquet export requires the `parquet` feature; use a .csv path instead");
     }
 
     // Load LLM config
     let config = Config::load()?;
     let llm_config = config.llm;
+    let pricing = llm_config.pricing;
+    let mut tracker = UsageTracker::new("eval", &llm_config.model);
 
-    println!("LLM API Base: {}", llm_config.api_base);
-    println!("LLM Model: {}", llm_config.model);
+    info!("LLM API Base: {}", llm_config.api_base);
+    info!("LLM Model: {}", llm_config.model);
 
     // Load dataset
     let dataset = match &cli.command {
         Commands::Sample => {
-            println!("Using sample dataset...");
+            info!("Using sample dataset");
             create_sample_dataset()
         }
-        Commands::Quality { path } => {
-            println!("Loading QuALITY dataset from {:?}...", path);
-            load_quality_dataset(path)?
+        Commands::Quality {
+            path,
+            split,
+            hard,
+            max_articles,
+        } => {
+            let path = if path.is_dir() {
+                quality_split_path(path, split)
+            } else {
+                path.clone()
+            };
+            info!("Loading QuALITY dataset from {:?}", path);
+            let filter = QualityFilter {
+                hard_only: *hard,
+                max_articles: *max_articles,
+            };
+            load_quality_dataset_filtered(&path, &filter)?
+        }
+        Commands::Narrativeqa { path, split } => {
+            info!("Loading NarrativeQA ({} split) from {:?}", split, path);
+            load_narrativeqa_dataset(path, split)?
+        }
+        Commands::Hotpotqa { path } => {
+            info!("Loading HotpotQA dataset from {:?}", path);
+            load_hotpotqa_dataset(path)?
         }
         Commands::Custom { path } => {
-            println!("Loading custom dataset from {:?}...", path);
+            info!("Loading custom dataset from {:?}", path);
             load_simple_dataset(path)?
         }
-        Commands::Download { .. } => unreachable!(),
+        Commands::Download { .. } | Commands::Generate { .. } | Commands::Regress { .. } => {
+            unreachable!()
+        }
+    };
+    let mut dataset = match cli.sample {
+        Some(n) => dataset.sample(n, cli.seed),
+        None => dataset,
     };
+    if cli.shuffle {
+        dataset.shuffle(cli.seed);
+    }
 
-    println!("Dataset: {} ({} items)", dataset.name, dataset.len());
+    info!("Dataset: {} ({} items)", dataset.name, dataset.len());
+    tracker.phase("load_dataset", Default::default());
 
     // Build benchmark config
     let benchmark_config = BenchmarkConfig {
@@ -128,11 +531,40 @@ async fn main() -> Result<()> {
         chunk_config: ChunkConfig {
             chunk_size: cli.chunk_size,
             chunk_overlap: cli.chunk_overlap,
+            unit: cli.chunk_unit,
         },
         run_pageindex: !cli.vector_only,
         run_vector: !cli.pageindex_only,
+        run_bm25: cli.bm25,
+        run_hybrid: cli.hybrid,
+        rrf_k: cli.rrf_k,
+        context_token_budget: cli.context_budget,
+        run_reranked: cli.rerank,
+        rerank_pageindex: cli.rerank_pageindex,
+        reranker_model: cli.reranker_model.clone(),
+        rerank_candidates: cli.rerank_candidates,
         max_items: cli.max_items,
-        verbose: cli.verbose,
+        verbose: cli.verbose > 0,
+        multiple_choice: cli.multiple_choice,
+        swap_judge_order: cli.swap_judge_order,
+        judge_models: cli.judge_models.clone(),
+        rubric: cli.rubric.as_deref().map(Rubric::load).transpose()?,
+        grade_answers: cli.grade_answers,
+        check_groundedness: cli.check_groundedness,
+        tournament: cli.tournament,
+        embedding_backend: cli.embedding_backend.unwrap_or_default(),
+        embedding_model: cli.embedding_model.clone(),
+        embedding_device: cli.device,
+        embedding_batch_size: cli.embed_batch_size,
+        hnsw: cli.hnsw.then(|| HnswConfig {
+            ef_search: cli.hnsw_ef,
+            ..Default::default()
+        }),
+        vector_cache_dir: cli.vector_cache.clone(),
+        tree_dir: cli.tree_dir.clone(),
+        judge_cache: cli.judge_cache.clone(),
+        results_log: cli.results_log.clone(),
+        resume_from: cli.resume.clone(),
     };
 
     // Validate config
@@ -146,12 +578,32 @@ async fn main() -> Result<()> {
         std::process::exit(1);
     }
 
-    // Run benchmark
-    let benchmark = Benchmark::new(llm_config, benchmark_config);
+    // Run benchmark (verbose mode prints per-item progress instead of a bar)
+    let (bar, progress) = progress_bar(cli.quiet || cli.verbose > 0);
+    let benchmark = Benchmark::new(llm_config, benchmark_config).with_progress(progress);
     let results: BenchmarkResults = benchmark.run(&dataset).await?;
+    bar.finish_and_clear();
+
+    tracker.phase("benchmark", results.usage);
 
     // Print summary
     results.print_summary();
+    let report = tracker.finish(pricing.as_ref());
+    report.print_summary();
+    if let Some(path) = &cli.usage_report {
+        report.save(path)?;
+        println!("Usage report saved to {:?}", path);
+    }
+
+    if let Some(path) = &cli.report {
+        report::write_report(&results, path)?;
+        println!("Report saved to {:?}", path);
+    }
+
+    if let Some(path) = &cli.export {
+        export::write_export(&results, path)?;
+        println!("Item results exported to {:?}", path);
+    }
 
     // Save results if requested
     if let Some(output_path) = cli.output {
@@ -163,22 +615,294 @@ async fn main() -> Result<()> {
     Ok(())
 }
 
-/// Download QuALITY dataset from the official NYU source.
-async fn download_quality(output_dir: &PathBuf) -> Result<()> {
+/// Progress bar over benchmark items showing the current item, the running
+/// PageIndex win rate and the ETA.
+fn progress_bar(hidden: bool) -> (ProgressBar, ProgressCallback) {
+    let bar = if hidden {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::new(0)
+    };
+    bar.set_style(
+        ProgressStyle::with_template(
+            "{spinner:.green} [{bar:30.cyan/blue}] {pos}/{len} {msg} ({elapsed}, eta {eta})",
+        )
+        .expect("valid progress template")
+        .progress_chars("=> "),
+    );
+    bar.enable_steady_tick(Duration::from_millis(120));
+
+    let handle = bar.clone();
+    let tally = Mutex::new(String::new());
+    let callback: ProgressCallback = Arc::new(move |event: &ProgressEvent| match event {
+        ProgressEvent::ItemStarted {
+            completed,
+            total,
+            item_id,
+        } => {
+            handle.set_length(*total as u64);
+            handle.set_position(*completed as u64);
+            handle.set_message(format!("{}{}", item_id, tally.lock().unwrap()));
+        }
+        ProgressEvent::WinsTally {
+            pageindex_wins,
+            vector_wins,
+            ties,
+        } => {
+            let judged = pageindex_wins + vector_wins + ties;
+            if judged > 0 {
+                *tally.lock().unwrap() = format!(
+                    " | PI {} / Vec {} / tie {} ({:.0}% PI wins)",
+                    pageindex_wins,
+                    vector_wins,
+                    ties,
+                    *pageindex_wins as f64 * 100.0 / judged as f64
+                );
+            }
+        }
+        ProgressEvent::ItemCompleted {
+            completed, total, ..
+        } => {
+            handle.set_length(*total as u64);
+            handle.set_position(*completed as u64);
+        }
+        _ => {}
+    });
+
+    (bar, callback)
+}
+
+/// Generate questions for every index given (files, or directories of
+/// them) and save them as one dataset.
+async fn generate_dataset(
+    cli: &Cli,
+    indexes: &[PathBuf],
+    document: Option<&Path>,
+    generator: &QuestionGenerator,
+    client: &LlmClient,
+) -> Result<()> {
+    let mut paths = Vec::new();
+    for path in indexes {
+        if path.is_dir() {
+            paths.extend(index_files(path)?);
+        } else {
+            paths.push(path.clone());
+        }
+    }
+    if document.is_some() && paths.len() != 1 {
+        anyhow::bail!("--document needs exactly one index");
+    }
+
+    let config = Config::load()?;
+    let mut tracker = UsageTracker::new("generate", &config.llm.model);
+    let mut dataset = Dataset::new(SYNTHETIC_SOURCE);
+    for path in &paths {
+        let tree = load_tree(path).with_context(|| format!("Failed to load {:?}", path))?;
+        let source = match document {
+            Some(document) => document.to_path_buf(),
+            None => source_document(path, &tree)?,
+        };
+        let loaded =
+            loader::load(&source).with_context(|| format!("Failed to load {:?}", source))?;
+        let generated = generator.generate(&tree, &loaded).await?;
+        println!("{}: {} questions", tree.name, generated.len());
+        if paths.len() == 1 {
+            dataset.name = generated.name.clone();
+        }
+        dataset.items.extend(generated.items);
+    }
+    tracker.phase("generate", client.usage());
+
+    let output = cli
+        .output
+        .clone()
+        .unwrap_or_else(|| PathBuf::from("synthetic_dataset.json"));
+    dataset.save_json(&output)?;
+    println!("\n{} questions saved to {:?}", dataset.len(), output);
+    println!("\nTo run evaluation:");
+    println!("  eval custom {:?}", output);
+
+    let report = tracker.finish(config.llm.pricing.as_ref());
+    report.print_summary();
+    if let Some(path) = &cli.usage_report {
+        report.save(path)?;
+        println!("Usage report saved to {:?}", path);
+    }
+    Ok(())
+}
+
+/// Run a query set against two versions of an index and report the
+/// differences.
+async fn run_regression(
+    cli: &Cli,
+    (baseline_path, candidate_path): (&Path, &Path),
+    queries_path: &Path,
+    document: Option<&Path>,
+    fail_on_regression: bool,
+) -> Result<()> {
+    let baseline =
+        load_tree(baseline_path).with_context(|| format!("Failed to load {:?}", baseline_path))?;
+    let candidate = load_tree(candidate_path)
+        .with_context(|| format!("Failed to load {:?}", candidate_path))?;
+    let queries = if queries_path.extension().is_some_and(|ext| ext == "json") {
+        RegressionQuery::from_dataset(&load_simple_dataset(queries_path)?)
+    } else {
+        let text = std::fs::read_to_string(queries_path)
+            .with_context(|| format!("Failed to read {:?}", queries_path))?;
+        RegressionQuery::from_lines(&text)
+    };
+    if queries.is_empty() {
+        anyhow::bail!("No queries in {:?}", queries_path);
+    }
+    let source = match document {
+        Some(document) => Some(document.to_path_buf()),
+        None => source_document(baseline_path, &baseline).ok(),
+    };
+    let loaded = match &source {
+        Some(source) => {
+            Some(loader::load(source).with_context(|| format!("Failed to load {:?}", source))?)
+        }
+        None => {
+            info!("Source document not found; answering from the trees' summaries");
+            None
+        }
+    };
+
+    let config = Config::load()?;
+    let llm_config = config.llm;
+    let client = LlmClient::new(llm_config.clone());
+    let judge_clients: Vec<LlmClient> = cli
+        .judge_models
+        .iter()
+        .map(|model| {
+            LlmClient::new(LlmConfig {
+                model: model.clone(),
+                ..llm_config.clone()
+            })
+        })
+        .collect();
+    let judge = match judge_clients.split_first() {
+        Some((first, rest)) => rest
+            .iter()
+            .fold(LlmJudge::new(first.clone()), |judge, client| {
+                judge.with_judge(client.clone())
+            }),
+        None => LlmJudge::new(LlmClient::new(llm_config.clone())),
+    }
+    .with_position_swap(cli.swap_judge_order);
+    let judge = match &cli.rubric {
+        Some(path) => judge.with_rubric(Rubric::load(path)?),
+        None => judge,
+    };
+    let judge = match &cli.judge_cache {
+        Some(path) => judge.with_cache(JudgeCache::open(path)?),
+        None => judge,
+    };
+
+    let mut tracker = UsageTracker::new("regress", &llm_config.model);
+    let baseline_name = baseline_path.display().to_string();
+    let candidate_name = candidate_path.display().to_string();
+    let run = RegressionRun::new(client.clone(), judge).with_top_k(cli.top_k);
+    let report = run
+        .run(
+            (&baseline_name, &baseline),
+            (&candidate_name, &candidate),
+            loaded.as_ref(),
+            &queries,
+        )
+        .await;
+    tracker.phase("regress", client.usage() + run.judge_usage());
+
+    report.print_summary();
+    if let Some(path) = &cli.output {
+        let json = serde_json::to_string_pretty(&report)?;
+        std::fs::write(path, json).with_context(|| format!("Failed to write {:?}", path))?;
+        println!("\nReport saved to {:?}", path);
+    }
+
+    let usage = tracker.finish(llm_config.pricing.as_ref());
+    usage.print_summary();
+    if let Some(path) = &cli.usage_report {
+        usage.save(path)?;
+        println!("Usage report saved to {:?}", path);
+    }
+
+    if fail_on_regression && report.regressed > 0 {
+        anyhow::bail!(
+            "{} of {} queries regressed",
+            report.regressed,
+            report.queries.len()
+        );
+    }
+    Ok(())
+}
+
+/// The document an index was built from, as recorded when indexing: the
+/// path as given, or relative to the index's directory.
+fn source_document(index: &Path, tree: &DocumentTree) -> Result<PathBuf> {
+    let Some(source) = &tree.source else {
+        anyhow::bail!(
+            "{:?} does not record its source document; pass it with --document",
+            index
+        );
+    };
+    let path = PathBuf::from(&source.path);
+    let beside = index.parent().map(|dir| dir.join(&path));
+    match beside {
+        _ if path.exists() => Ok(path),
+        Some(beside) if beside.exists() => Ok(beside),
+        _ => anyhow::bail!(
+            "Source document {:?} of {:?} not found; pass it with --document",
+            source.path,
+            index
+        ),
+    }
+}
+
+/// Download the HotpotQA dev set (distractor setting) from the official source.
+async fn download_hotpotqa(output_dir: &Path) -> Result<()> {
+    std::fs::create_dir_all(output_dir)?;
+
+    let url = "http://curtis.ml.cmu.edu/datasets/hotpot/hotpot_dev_distractor_v1.json";
+    let output_path = output_dir.join("hotpot_dev_distractor.json");
+
+    println!("Downloading HotpotQA dataset...");
+    println!("URL: {}", url);
+
+    let response = reqwest::get(url).await?.error_for_status()?;
+    std::fs::write(&output_path, response.bytes().await?)?;
+
+    println!("Downloaded to {:?}", output_path);
+    println!("\nTo run evaluation:");
+    println!("  eval hotpotqa {:?}", output_path);
+
+    Ok(())
+}
+
+/// Download a QuALITY split (train or dev) from the official NYU source.
+async fn download_quality(output_dir: &Path, split: &str) -> Result<()> {
     use std::fs;
 
+    // The test split has no gold labels
+    if !matches!(split, "train" | "dev") {
+        anyhow::bail!("Unknown QuALITY split '{}' (expected train or dev)", split);
+    }
+
     // Create output directory
     fs::create_dir_all(output_dir)?;
 
     // Official NYU QuALITY dataset source
-    let url = "https://raw.githubusercontent.com/nyu-mll/quality/main/data/v1.0.1/QuALITY.v1.0.1.htmlstripped.dev";
-    let output_path = output_dir.join("quality_dev.jsonl");
+    let url = format!(
+        "https://raw.githubusercontent.com/nyu-mll/quality/main/data/v1.0.1/QuALITY.v1.0.1.htmlstripped.{}",
+        split
+    );
+    let output_path = quality_split_path(output_dir, split);
 
     println!("Downloading QuALITY dataset...");
     println!("URL: {}", url);
     println!("Output: {:?}", output_path);
 
-    let response = reqwest::get(url).await?;
+    let response = reqwest::get(&url).await?;
 
     if !response.status().is_success() {
         eprintln!("Failed to download: HTTP {}", response.status());
@@ -193,7 +917,7 @@ async fn download_quality(output_dir: &PathBuf) -> Result<()> {
 
     println!("Downloaded {} items to {:?}", item_count, output_path);
     println!("\nTo run evaluation:");
-    println!("  eval quality {:?}", output_path);
+    println!("  eval quality {:?} --split {}", output_dir, split);
 
     Ok(())
 }
@@ -0,0 +1,211 @@
+//! Synchronous API (`blocking` feature).
+//!
+//! Wrappers around [`PageIndex`](crate::PageIndex),
+//! [`TreeIndexer`](crate::TreeIndexer) and [`TreeSearcher`](crate::TreeSearcher)
+//! whose methods block until done, for scripts and tools that would
+//! otherwise set up tokio just to index or search once. Each wrapper owns a
+//! single-threaded runtime that drives its LLM calls.
+//!
+//! ```no_run
+//! use rust_page_indexer::blocking::PageIndex;
+//!
+//! # fn run() -> rust_page_indexer::Result<()> {
+//! let mut index = PageIndex::new()?;
+//! index.index_file("document.txt")?;
+//! let answer = index.ask("What were the main findings?")?;
+//! println!("{}", answer.answer);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Like any blocking call, these methods must not be used from inside an
+//! async runtime; they panic there.
+
+use crate::document::Document;
+use crate::error::{PageIndexError, Result};
+use crate::llm::LlmClient;
+use crate::page_index::{Answer, AnswerStyle};
+use crate::search::{SearchOptions, SearchResult};
+use crate::tree::DocumentTree;
+use std::path::Path;
+use tokio::runtime::Runtime;
+
+fn runtime() -> Result<Runtime> {
+    tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .map_err(|e| PageIndexError::Config(format!("Failed to start runtime: {}", e)))
+}
+
+/// Blocking [`crate::PageIndex`].
+pub struct PageIndex {
+    inner: crate::PageIndex,
+    runtime: Runtime,
+}
+
+impl PageIndex {
+    /// An index configured from the environment and config file.
+    pub fn new() -> Result<Self> {
+        Self::from_async(crate::PageIndex::builder().build()?)
+    }
+
+    /// Wrap an index built with [`crate::PageIndex::builder`].
+    pub fn from_async(inner: crate::PageIndex) -> Result<Self> {
+        Ok(Self {
+            inner,
+            runtime: runtime()?,
+        })
+    }
+
+    /// The wrapped index.
+    pub fn inner(&self) -> &crate::PageIndex {
+        &self.inner
+    }
+
+    /// The loaded tree, if any.
+    pub fn tree(&self) -> Option<&DocumentTree> {
+        self.inner.tree()
+    }
+
+    /// See [`crate::PageIndex::index_file`].
+    pub fn index_file(&mut self, path: impl AsRef<Path>) -> Result<&DocumentTree> {
+        self.runtime.block_on(self.inner.index_file(path))
+    }
+
+    /// See [`crate::PageIndex::index_document`].
+    pub fn index_document(&mut self, document: Document) -> Result<&DocumentTree> {
+        self.runtime.block_on(self.inner.index_document(document))
+    }
+
+    /// See [`crate::PageIndex::load`].
+    pub fn load(&mut self, path: impl AsRef<Path>) -> Result<&DocumentTree> {
+        self.inner.load(path)
+    }
+
+    /// See [`crate::PageIndex::load_document`].
+    pub fn load_document(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        self.inner.load_document(path)
+    }
+
+    /// See [`crate::PageIndex::save`].
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        self.inner.save(path)
+    }
+
+    /// See [`crate::PageIndex::search`].
+    pub fn search(&self, query: &str) -> Result<Vec<SearchResult>> {
+        self.runtime.block_on(self.inner.search(query))
+    }
+
+    /// See [`crate::PageIndex::search_with`].
+    pub fn search_with(&self, query: &str, options: &SearchOptions) -> Result<Vec<SearchResult>> {
+        self.runtime
+            .block_on(self.inner.search_with(query, options))
+    }
+
+    /// See [`crate::PageIndex::ask`].
+    pub fn ask(&self, question: &str) -> Result<Answer> {
+        self.runtime.block_on(self.inner.ask(question))
+    }
+
+    /// See [`crate::PageIndex::ask_with`].
+    pub fn ask_with(&self, question: &str, style: &AnswerStyle) -> Result<Answer> {
+        self.runtime.block_on(self.inner.ask_with(question, style))
+    }
+}
+
+/// Blocking [`crate::TreeIndexer`].
+pub struct TreeIndexer {
+    inner: crate::TreeIndexer,
+    runtime: Runtime,
+}
+
+impl TreeIndexer {
+    /// An indexer with default options.
+    pub fn new(client: LlmClient) -> Result<Self> {
+        Self::from_async(crate::TreeIndexer::new(client))
+    }
+
+    /// Wrap a configured indexer (options, progress, checkpoints).
+    pub fn from_async(inner: crate::TreeIndexer) -> Result<Self> {
+        Ok(Self {
+            inner,
+            runtime: runtime()?,
+        })
+    }
+
+    /// See [`crate::TreeIndexer::index`].
+    pub fn index(&self, document: &Document) -> Result<DocumentTree> {
+        self.runtime.block_on(self.inner.index(document))
+    }
+}
+
+/// Blocking [`crate::TreeSearcher`].
+pub struct TreeSearcher {
+    inner: crate::TreeSearcher,
+    runtime: Runtime,
+}
+
+impl TreeSearcher {
+    /// A searcher with default options.
+    pub fn new(client: LlmClient) -> Result<Self> {
+        Self::from_async(crate::TreeSearcher::new(client))
+    }
+
+    /// Wrap a configured searcher (options, progress).
+    pub fn from_async(inner: crate::TreeSearcher) -> Result<Self> {
+        Ok(Self {
+            inner,
+            runtime: runtime()?,
+        })
+    }
+
+    /// See [`crate::TreeSearcher::search`].
+    pub fn search(&self, tree: &DocumentTree, query: &str) -> Result<Vec<SearchResult>> {
+        self.runtime.block_on(self.inner.search(tree, query))
+    }
+
+    /// See [`crate::TreeSearcher::search_with_content`].
+    pub fn search_with_content(
+        &self,
+        tree: &DocumentTree,
+        document: &Document,
+        query: &str,
+    ) -> Result<Vec<SearchResult>> {
+        self.runtime
+            .block_on(self.inner.search_with_content(tree, document, query))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::offline_client;
+    use crate::tree::TreeNode;
+
+    #[test]
+    fn test_blocking_calls() {
+        let inner = crate::PageIndex::builder()
+            .with_client(offline_client())
+            .build()
+            .unwrap();
+        let mut index = PageIndex::from_async(inner).unwrap();
+        assert!(matches!(
+            index.search("intro"),
+            Err(PageIndexError::TreeError(_))
+        ));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tree.json");
+        let tree = DocumentTree::new("doc", vec![TreeNode::new("Introduction", 1, 1)], 1);
+        crate::save_tree(&tree, &path).unwrap();
+        index.load(&path).unwrap();
+        assert!(matches!(index.ask("intro"), Err(PageIndexError::Http(_))));
+
+        let searcher = TreeSearcher::new(offline_client()).unwrap();
+        assert!(matches!(
+            searcher.search(&tree, "intro"),
+            Err(PageIndexError::Http(_))
+        ));
+    }
+}
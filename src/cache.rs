@@ -0,0 +1,267 @@
+//! Answer caching for repeated questions.
+//!
+//! Generating an answer costs a search and an answer call. An
+//! [`AnswerCache`] keeps recent answers keyed by the normalized question,
+//! the [version](DocumentTree::content_hash) of the tree they were answered
+//! from and the model, so a common question is answered once per index
+//! version. Re-indexing or editing a tree changes its version, so stale
+//! answers are never returned; they age out with the TTL or can be dropped
+//! explicitly.
+//!
+//! ```no_run
+//! use rust_page_indexer::PageIndex;
+//! use rust_page_indexer::cache::AnswerCache;
+//! use std::time::Duration;
+//!
+//! # async fn run() -> rust_page_indexer::Result<()> {
+//! let cache = AnswerCache::new(256).with_ttl(Duration::from_secs(600));
+//! let mut index = PageIndex::builder().with_answer_cache(cache.clone()).build()?;
+//! index.load("data/tree_index.json")?;
+//! index.ask("What were the main findings?").await?;
+//! index.ask("what were the main  findings").await?; // from the cache
+//! cache.clear();
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::page_index::{Answer, AnswerStyle};
+use crate::tree::DocumentTree;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// Default number of cached answers.
+pub const DEFAULT_ANSWER_CACHE_SIZE: usize = 256;
+
+/// Default time an answer stays cached.
+pub const DEFAULT_ANSWER_TTL: Duration = Duration::from_secs(3600);
+
+/// Normalize a question for cache lookups: lowercase, single spaces and no
+/// trailing punctuation, so trivially different phrasings share an answer.
+pub fn normalize_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .trim_end_matches(['?', '!', '.'])
+        .trim_end()
+        .to_lowercase()
+}
+
+/// What an answer depends on.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct AnswerKey {
+    /// The question, [normalized](normalize_query).
+    pub query: String,
+    /// [`DocumentTree::content_hash`] of the tree answered from.
+    pub version: String,
+    pub model: String,
+    /// Style the answer was written in.
+    pub style: AnswerStyle,
+}
+
+impl AnswerKey {
+    /// Key for a question about a tree version.
+    pub fn new(query: &str, version: impl Into<String>, model: impl Into<String>) -> Self {
+        Self {
+            query: normalize_query(query),
+            version: version.into(),
+            model: model.into(),
+            style: AnswerStyle::default(),
+        }
+    }
+
+    /// Key for an answer in a style other than the default.
+    pub fn with_style(mut self, style: AnswerStyle) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Key for a question about a tree.
+    pub fn for_tree(query: &str, tree: &DocumentTree, model: impl Into<String>) -> Self {
+        Self::new(query, tree.content_hash(), model)
+    }
+}
+
+struct Entry {
+    answer: Answer,
+    inserted: Instant,
+}
+
+#[derive(Default)]
+struct Entries {
+    answers: HashMap<AnswerKey, Entry>,
+    /// Keys in insertion order, oldest first.
+    order: VecDeque<AnswerKey>,
+}
+
+impl Entries {
+    fn remove(&mut self, key: &AnswerKey) -> bool {
+        self.order.retain(|k| k != key);
+        self.answers.remove(key).is_some()
+    }
+}
+
+/// Recent answers, shared by every clone. Evicts the oldest answer when
+/// full.
+#[derive(Clone)]
+pub struct AnswerCache {
+    capacity: usize,
+    ttl: Option<Duration>,
+    entries: Arc<Mutex<Entries>>,
+}
+
+impl Default for AnswerCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_ANSWER_CACHE_SIZE).with_ttl(DEFAULT_ANSWER_TTL)
+    }
+}
+
+impl AnswerCache {
+    /// Cache up to `capacity` answers (0 disables the cache), without
+    /// expiry.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            ttl: None,
+            entries: Arc::new(Mutex::new(Entries::default())),
+        }
+    }
+
+    /// Expire answers this long after they were cached.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Entries> {
+        self.entries.lock().expect("answer cache lock poisoned")
+    }
+
+    /// The cached answer for a key, unless it has expired.
+    pub fn get(&self, key: &AnswerKey) -> Option<Answer> {
+        let mut entries = self.lock();
+        let entry = entries.answers.get(key)?;
+        if self.ttl.is_some_and(|ttl| entry.inserted.elapsed() >= ttl) {
+            entries.remove(key);
+            return None;
+        }
+        Some(entry.answer.clone())
+    }
+
+    /// Cache an answer, replacing any for the same key.
+    pub fn insert(&self, key: AnswerKey, answer: Answer) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut entries = self.lock();
+        entries.remove(&key);
+        while entries.order.len() >= self.capacity {
+            let Some(oldest) = entries.order.pop_front() else {
+                break;
+            };
+            entries.answers.remove(&oldest);
+        }
+        entries.order.push_back(key.clone());
+        entries.answers.insert(
+            key,
+            Entry {
+                answer,
+                inserted: Instant::now(),
+            },
+        );
+    }
+
+    /// Drop the answer for a key. Returns whether there was one.
+    pub fn invalidate(&self, key: &AnswerKey) -> bool {
+        self.lock().remove(key)
+    }
+
+    /// Drop every answer from a tree version. Returns how many there were.
+    pub fn invalidate_version(&self, version: &str) -> usize {
+        self.invalidate_versions(|v| v == version)
+    }
+
+    /// Drop every answer from the versions matching `predicate`. Returns
+    /// how many there were.
+    pub fn invalidate_versions(&self, predicate: impl Fn(&str) -> bool) -> usize {
+        let mut entries = self.lock();
+        let stale: Vec<AnswerKey> = entries
+            .order
+            .iter()
+            .filter(|k| predicate(&k.version))
+            .cloned()
+            .collect();
+        for key in &stale {
+            entries.remove(key);
+        }
+        stale.len()
+    }
+
+    /// Drop every answer.
+    pub fn clear(&self) {
+        *self.lock() = Entries::default();
+    }
+
+    /// Number of cached answers, including expired ones not yet dropped.
+    pub fn len(&self) -> usize {
+        self.lock().answers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::page_index::AnswerOutcome;
+
+    fn answer(text: &str) -> Answer {
+        Answer {
+            answer: text.to_string(),
+            sources: Vec::new(),
+            outcome: AnswerOutcome::Answered,
+        }
+    }
+
+    #[test]
+    fn test_normalize_query() {
+        assert_eq!(
+            normalize_query("  How often should the   filter be cleaned?? "),
+            "how often should the filter be cleaned"
+        );
+        let key = |q| AnswerKey::new(q, "v1", "m");
+        assert_eq!(key("What is X?"), key("what is x"));
+        assert_ne!(key("What is X?"), AnswerKey::new("What is X?", "v2", "m"));
+    }
+
+    #[test]
+    fn test_answer_cache() {
+        let cache = AnswerCache::new(2);
+        let key = |q: &str, v: &str| AnswerKey::new(q, v, "m");
+        cache.insert(key("a", "v1"), answer("A"));
+        cache.insert(key("b", "v1"), answer("B"));
+        cache.insert(key("c", "v2"), answer("C"));
+        assert!(cache.get(&key("a", "v1")).is_none());
+        assert_eq!(cache.get(&key("b", "v1")).unwrap().answer, "B");
+
+        // Shared between clones
+        let clone = cache.clone();
+        assert_eq!(clone.invalidate_version("v1"), 1);
+        assert!(!clone.invalidate(&key("b", "v1")));
+        assert_eq!(cache.len(), 1);
+        cache.clear();
+        assert!(cache.is_empty());
+
+        let expiring = AnswerCache::new(2).with_ttl(Duration::ZERO);
+        expiring.insert(key("a", "v1"), answer("A"));
+        assert!(expiring.get(&key("a", "v1")).is_none());
+        assert!(expiring.is_empty());
+
+        let disabled = AnswerCache::new(0);
+        disabled.insert(key("a", "v1"), answer("A"));
+        assert!(disabled.is_empty());
+    }
+}
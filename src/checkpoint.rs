@@ -0,0 +1,117 @@
+//! Checkpointing for interrupted index runs.
+//!
+//! While indexing, the indexer records completed structure-extraction chunks
+//! and generated summaries in a checkpoint file. Resuming from that file skips
+//! the LLM calls that already succeeded. The checkpoint is removed once the
+//! tree is built.
+
+use crate::document::Document;
+use crate::error::{PageIndexError, Result};
+use crate::tree::{RawTocItem, fnv1a_hex};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Progress of an index run, as saved to disk.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct IndexCheckpoint {
+    /// Fingerprint of the document content and chunking the run was for.
+    pub fingerprint: String,
+    /// Number of structure-extraction chunks completed.
+    pub chunks_completed: usize,
+    /// TOC items extracted from the completed chunks.
+    pub toc_items: Vec<RawTocItem>,
+    /// Summaries generated so far, in depth-first node order.
+    #[serde(default)]
+    pub summaries: Vec<String>,
+}
+
+/// Fingerprint a document's pages together with the chunk size used to
+/// split them. The hash is stable across builds, so a checkpoint can be
+/// resumed by a newer binary.
+pub fn document_fingerprint(document: &Document, max_tokens_per_chunk: usize) -> String {
+    let mut bytes = (max_tokens_per_chunk as u64).to_le_bytes().to_vec();
+    for page in &document.pages {
+        // 0xff never occurs in UTF-8, so it separates pages unambiguously
+        bytes.push(0xff);
+        bytes.extend_from_slice(page.content.as_bytes());
+    }
+    fnv1a_hex(&bytes)
+}
+
+/// Default checkpoint path for an index output path (`tree.json` -> `tree.json.checkpoint`).
+pub fn checkpoint_path_for(output: &Path) -> PathBuf {
+    let mut name = output.as_os_str().to_owned();
+    name.push(".checkpoint");
+    PathBuf::from(name)
+}
+
+/// Load a checkpoint file.
+pub fn load_checkpoint(path: &Path) -> Result<IndexCheckpoint> {
+    let content = std::fs::read_to_string(path).map_err(|e| PageIndexError::io(path, e))?;
+    serde_json::from_str(&content).map_err(|e| PageIndexError::Serialization(e.to_string()))
+}
+
+/// Save a checkpoint file, replacing it atomically.
+pub fn save_checkpoint(checkpoint: &IndexCheckpoint, path: &Path) -> Result<()> {
+    let json = serde_json::to_string(checkpoint)
+        .map_err(|e| PageIndexError::Serialization(e.to_string()))?;
+
+    // `tree.json.checkpoint.tmp`, not `tree.json.tmp`, which other
+    // sidecars of the same index would share
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    std::fs::write(&tmp, json).map_err(|e| PageIndexError::io(&tmp, e))?;
+    std::fs::rename(&tmp, path).map_err(|e| PageIndexError::io(path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_checkpoint_roundtrip() {
+        let dir = TempDir::new().unwrap();
+        let path = checkpoint_path_for(&dir.path().join("tree.json"));
+        assert!(path.ends_with("tree.json.checkpoint"));
+
+        let checkpoint = IndexCheckpoint {
+            fingerprint: "abc".to_string(),
+            chunks_completed: 2,
+            toc_items: vec![RawTocItem {
+                structure: Some("1".to_string()),
+                title: "Intro".to_string(),
+                physical_index: Some(serde_json::json!(1)),
+                confidence: None,
+            }],
+            summaries: vec!["Summary".to_string()],
+        };
+        // Another sidecar's write in progress is left alone
+        let other = dir.path().join("tree.json.tmp");
+        std::fs::write(&other, "stats").unwrap();
+        save_checkpoint(&checkpoint, &path).unwrap();
+
+        assert_eq!(load_checkpoint(&path).unwrap(), checkpoint);
+        assert_eq!(std::fs::read_to_string(&other).unwrap(), "stats");
+    }
+
+    #[test]
+    fn test_fingerprint_tracks_content_and_chunking() {
+        let doc = Document::from_text("a", "hello".to_string());
+        let other = Document::from_text("a", "world".to_string());
+
+        assert_eq!(
+            document_fingerprint(&doc, 100),
+            document_fingerprint(&doc, 100)
+        );
+        assert_ne!(
+            document_fingerprint(&doc, 100),
+            document_fingerprint(&other, 100)
+        );
+        assert_ne!(
+            document_fingerprint(&doc, 100),
+            document_fingerprint(&doc, 200)
+        );
+    }
+}
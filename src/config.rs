@@ -4,6 +4,7 @@
 //! Environment variables take precedence over config file values.
 
 use crate::error::{PageIndexError, Result};
+use crate::indexer::DEFAULT_EXCLUDED_SECTIONS;
 use serde::{Deserialize, Serialize};
 use std::env;
 use std::path::PathBuf;
@@ -27,6 +28,29 @@ pub struct LlmConfig {
     /// Temperature for generation (optional)
     #[serde(default = "default_temperature")]
     pub temperature: f32,
+
+    /// Token pricing used to estimate cost in usage reports (optional)
+    #[serde(default)]
+    pub pricing: Option<ModelPricing>,
+}
+
+/// Per-token pricing for a model, in USD per million tokens.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPricing {
+    /// Price per million prompt (input) tokens
+    pub prompt_per_million: f64,
+
+    /// Price per million completion (output) tokens
+    pub completion_per_million: f64,
+}
+
+impl ModelPricing {
+    /// Estimate the cost in USD of the given token counts.
+    pub fn cost(&self, prompt_tokens: u64, completion_tokens: u64) -> f64 {
+        (prompt_tokens as f64 * self.prompt_per_million
+            + completion_tokens as f64 * self.completion_per_million)
+            / 1_000_000.0
+    }
 }
 
 fn default_max_tokens() -> u32 {
@@ -45,21 +69,116 @@ impl Default for LlmConfig {
             model: "claude-latest".to_string(),
             max_tokens: default_max_tokens(),
             temperature: default_temperature(),
+            pricing: None,
         }
     }
 }
 
+/// Indexing configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexerConfig {
+    /// Titles of non-content sections kept out of search prompts (see
+    /// [`IndexerOptions::excluded_sections`](crate::indexer::IndexerOptions::excluded_sections)).
+    pub excluded_sections: Vec<String>,
+    /// Score each node's confidence while indexing (see
+    /// [`IndexerOptions::score_confidence`](crate::indexer::IndexerOptions::score_confidence)).
+    #[serde(default)]
+    pub score_confidence: bool,
+    /// When scoring confidence, check the top-level sections and this share
+    /// of the leaves instead of every section (see
+    /// [`VerificationMode::Sample`](crate::indexer::VerificationMode::Sample)).
+    #[serde(default)]
+    pub verify_sample: Option<f32>,
+    /// Seed picking the sampled leaves, to check a different sample or
+    /// reproduce one.
+    #[serde(default)]
+    pub verify_seed: u64,
+    /// Find each section's effective date while indexing (see
+    /// [`IndexerOptions::extract_dates`](crate::indexer::IndexerOptions::extract_dates)).
+    #[serde(default)]
+    pub extract_dates: bool,
+}
+
+impl Default for IndexerConfig {
+    fn default() -> Self {
+        Self {
+            excluded_sections: DEFAULT_EXCLUDED_SECTIONS
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            score_confidence: false,
+            verify_sample: None,
+            verify_seed: 0,
+            extract_dates: false,
+        }
+    }
+}
+
+/// Models used for particular operations instead of [`LlmConfig::model`],
+/// on the same endpoint.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OperationModels {
+    /// Model navigating the tree in search: the broad pass over every
+    /// section. A cheap model is usually enough.
+    #[serde(default)]
+    pub navigate: Option<String>,
+    /// Model ranking the navigation shortlist and extracting snippets from
+    /// its content (see [`TreeSearcher::with_ranker`](crate::search::TreeSearcher::with_ranker)).
+    /// Setting it turns on two-stage search.
+    #[serde(default)]
+    pub rank: Option<String>,
+    /// Embedding model for sections refreshed with embeddings (see
+    /// [`HotSectionRefresher`](crate::summarize::HotSectionRefresher)).
+    #[serde(default)]
+    pub embed: Option<String>,
+}
+
+/// Where indexes and logs are stored.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct StorageConfig {
+    /// Directory relative index paths are resolved against instead of the
+    /// working directory (see
+    /// [`resolve_index_path`](crate::persistence::resolve_index_path)).
+    #[serde(default)]
+    pub data_root: Option<PathBuf>,
+    /// Append searches and answers to this audit log (see
+    /// [`crate::audit`]).
+    #[serde(default)]
+    pub audit_log: Option<PathBuf>,
+}
+
 /// Full application configuration.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Config {
     /// LLM settings
     pub llm: LlmConfig,
+    /// Indexing settings
+    #[serde(default)]
+    pub indexer: IndexerConfig,
+    /// Per-operation models
+    #[serde(default)]
+    pub models: OperationModels,
+    /// Index storage settings
+    #[serde(default)]
+    pub storage: StorageConfig,
 }
 
 /// Configuration file structure (YAML format).
 #[derive(Debug, Deserialize)]
 struct ConfigFile {
     llm: Option<LlmFileSection>,
+    indexer: Option<IndexerFileSection>,
+    models: Option<OperationModels>,
+    storage: Option<StorageConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+struct IndexerFileSection {
+    excluded_sections: Option<Vec<String>>,
+    score_confidence: Option<bool>,
+    verify_sample: Option<f32>,
+    verify_seed: Option<u64>,
+    extract_dates: Option<bool>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -69,23 +188,26 @@ struct LlmFileSection {
     model: Option<String>,
     max_tokens: Option<u32>,
     temperature: Option<f32>,
+    pricing: Option<ModelPricing>,
 }
 
 impl Config {
     /// Load configuration from environment variables and optional config file.
     ///
     /// Priority (highest to lowest):
-    /// 1. Environment variables (LLM_API_BASE, LLM_API_KEY, LLM_MODEL)
+    /// 1. Environment variables (LLM_API_BASE, LLM_API_KEY, LLM_MODEL,
+    ///    LLM_NAVIGATE_MODEL, LLM_RANK_MODEL, LLM_EMBED_MODEL,
+    ///    PAGE_INDEXER_DATA_ROOT)
     /// 2. Config file (~/.config/rust-page-indexer/config.yaml)
     /// 3. Default values
     pub fn load() -> Result<Self> {
         let mut config = Config::default();
 
         // Try to load from config file first
-        if let Some(config_path) = Self::config_file_path() {
-            if config_path.exists() {
-                config = Self::load_from_file(&config_path)?;
-            }
+        if let Some(config_path) = Self::config_file_path()
+            && config_path.exists()
+        {
+            config = Self::load_from_file(&config_path)?;
         }
 
         // Override with environment variables
@@ -101,16 +223,44 @@ impl Config {
             config.llm.model = model;
         }
 
-        if let Ok(max_tokens) = env::var("LLM_MAX_TOKENS") {
-            if let Ok(tokens) = max_tokens.parse() {
-                config.llm.max_tokens = tokens;
-            }
+        if let Ok(model) = env::var("LLM_NAVIGATE_MODEL") {
+            config.models.navigate = Some(model);
         }
 
-        if let Ok(temperature) = env::var("LLM_TEMPERATURE") {
-            if let Ok(temp) = temperature.parse() {
-                config.llm.temperature = temp;
-            }
+        if let Ok(model) = env::var("LLM_RANK_MODEL") {
+            config.models.rank = Some(model);
+        }
+
+        if let Ok(model) = env::var("LLM_EMBED_MODEL") {
+            config.models.embed = Some(model);
+        }
+
+        if let Some(root) = env::var_os("PAGE_INDEXER_DATA_ROOT") {
+            config.storage.data_root = Some(PathBuf::from(root));
+        }
+
+        if let Ok(max_tokens) = env::var("LLM_MAX_TOKENS")
+            && let Ok(tokens) = max_tokens.parse()
+        {
+            config.llm.max_tokens = tokens;
+        }
+
+        if let Ok(temperature) = env::var("LLM_TEMPERATURE")
+            && let Ok(temp) = temperature.parse()
+        {
+            config.llm.temperature = temp;
+        }
+
+        // Pricing can be set from the environment as a pair of prices
+        if let (Ok(prompt), Ok(completion)) = (
+            env::var("LLM_PROMPT_PRICE"),
+            env::var("LLM_COMPLETION_PRICE"),
+        ) && let (Ok(prompt), Ok(completion)) = (prompt.parse(), completion.parse())
+        {
+            config.llm.pricing = Some(ModelPricing {
+                prompt_per_million: prompt,
+                completion_per_million: completion,
+            });
         }
 
         Ok(config)
@@ -141,17 +291,53 @@ impl Config {
             if let Some(temperature) = llm.temperature {
                 config.llm.temperature = temperature;
             }
+            if let Some(pricing) = llm.pricing {
+                config.llm.pricing = Some(pricing);
+            }
+        }
+
+        if let Some(indexer) = file_config.indexer {
+            if let Some(excluded) = indexer.excluded_sections {
+                config.indexer.excluded_sections = excluded;
+            }
+            if let Some(score_confidence) = indexer.score_confidence {
+                config.indexer.score_confidence = score_confidence;
+            }
+            if let Some(rate) = indexer.verify_sample {
+                config.indexer.verify_sample = Some(rate);
+            }
+            if let Some(seed) = indexer.verify_seed {
+                config.indexer.verify_seed = seed;
+            }
+            if let Some(extract_dates) = indexer.extract_dates {
+                config.indexer.extract_dates = extract_dates;
+            }
+        }
+
+        if let Some(models) = file_config.models {
+            config.models = models;
+        }
+
+        if let Some(storage) = file_config.storage {
+            config.storage = storage;
         }
 
         Ok(config)
     }
 
     /// Get the default config file path.
+    #[cfg(not(target_arch = "wasm32"))]
     pub fn config_file_path() -> Option<PathBuf> {
         directories::ProjectDirs::from("", "", "rust-page-indexer")
             .map(|dirs| dirs.config_dir().join("config.yaml"))
     }
 
+    /// There is no config file in the browser.
+    #[cfg(target_arch = "wasm32")]
+    pub fn config_file_path() -> Option<PathBuf> {
+        None
+    }
+
     /// Validate that required configuration is present.
     pub fn validate(&self) -> Result<()> {
         if self.llm.api_base.is_empty() {
@@ -189,6 +375,7 @@ impl Config {
                 model: model.into(),
                 ..Default::default()
             },
+            ..Default::default()
         }
     }
 }
@@ -205,6 +392,57 @@ mod tests {
         assert_eq!(config.llm.model, "claude-latest");
         assert_eq!(config.llm.max_tokens, 4096);
         assert_eq!(config.llm.temperature, 0.0);
+        assert!(
+            config
+                .indexer
+                .excluded_sections
+                .contains(&"References".to_string())
+        );
+    }
+
+    #[test]
+    fn test_load_excluded_sections() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("config.yaml");
+        std::fs::write(
+            &path,
+            "llm:\n  model: m\nindexer:\n  excluded_sections: [Glossary]\n  score_confidence: true\n  verify_sample: 0.25\n  verify_seed: 7\n  extract_dates: true\n",
+        )
+        .unwrap();
+        let config = Config::load_from_file(&path).unwrap();
+        assert_eq!(config.indexer.excluded_sections, vec!["Glossary"]);
+        assert!(config.indexer.score_confidence);
+        assert_eq!(config.indexer.verify_sample, Some(0.25));
+        assert_eq!(config.indexer.verify_seed, 7);
+        assert!(config.indexer.extract_dates);
+
+        std::fs::write(
+            &path,
+            "llm:\n  model: m\nmodels:\n  rank: strong\n  embed: text-embedding-3-small\n",
+        )
+        .unwrap();
+        let config = Config::load_from_file(&path).unwrap();
+        assert_eq!(
+            config.indexer.excluded_sections,
+            IndexerConfig::default().excluded_sections
+        );
+        assert!(!config.indexer.score_confidence);
+        assert_eq!(config.models.navigate, None);
+        assert_eq!(config.models.rank.as_deref(), Some("strong"));
+        assert_eq!(
+            config.models.embed.as_deref(),
+            Some("text-embedding-3-small")
+        );
+        assert_eq!(config.storage.data_root, None);
+
+        std::fs::write(
+            &path,
+            "llm:\n  model: m\nstorage:\n  data_root: D:/indexes\n  audit_log: audit.jsonl\n",
+        )
+        .unwrap();
+        let config = Config::load_from_file(&path).unwrap();
+        assert_eq!(config.storage.data_root, Some(PathBuf::from("D:/indexes")));
+        assert_eq!(config.storage.audit_log, Some(PathBuf::from("audit.jsonl")));
     }
 
     #[test]
@@ -220,4 +458,14 @@ mod tests {
         assert_eq!(config.llm.api_key, "test-key");
         assert_eq!(config.llm.model, "gpt-4");
     }
+
+    #[test]
+    fn test_pricing_cost() {
+        let pricing = ModelPricing {
+            prompt_per_million: 3.0,
+            completion_per_million: 15.0,
+        };
+        let cost = pricing.cost(1_000_000, 100_000);
+        assert!((cost - 4.5).abs() < 1e-9);
+    }
 }
@@ -5,9 +5,21 @@
 //! is treated as a single page.
 
 use crate::error::{PageIndexError, Result};
+use crate::search::SearchResult;
 use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
 
+/// Document name for a file: its stem, read lossily if it is not valid
+/// UTF-8, or `untitled`.
+fn document_name(path: &Path) -> String {
+    path.file_stem().map_or_else(
+        || "untitled".to_string(),
+        |s| s.to_string_lossy().into_owned(),
+    )
+}
+
 /// A single page in a document.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Page {
@@ -64,11 +76,7 @@ impl Document {
     pub fn from_text_file(path: &Path) -> Result<Self> {
         let content = std::fs::read_to_string(path).map_err(|e| PageIndexError::io(path, e))?;
 
-        let name = path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("untitled")
-            .to_string();
+        let name = document_name(path);
 
         let pages = vec![Page::new(1, content)];
 
@@ -85,11 +93,7 @@ impl Document {
     pub fn from_text_file_with_delimiter(path: &Path, delimiter: &str) -> Result<Self> {
         let content = std::fs::read_to_string(path).map_err(|e| PageIndexError::io(path, e))?;
 
-        let name = path
-            .file_stem()
-            .and_then(|s| s.to_str())
-            .unwrap_or("untitled")
-            .to_string();
+        let name = document_name(path);
 
         let pages: Vec<Page> = content
             .split(delimiter)
@@ -165,10 +169,114 @@ impl Document {
             .collect::<Vec<_>>()
             .join("\n\n")
     }
+
+    /// Assemble one context string from search results, most relevant
+    /// first, within `token_budget` estimated tokens.
+    ///
+    /// Results keep their order within a relevance level. See
+    /// [`extract_ranked`](Self::extract_ranked) for the layout.
+    pub fn extract_for_results(&self, results: &[SearchResult], token_budget: usize) -> String {
+        let mut ranked: Vec<&SearchResult> = results.iter().collect();
+        ranked.sort_by_key(|r| Reverse(r.relevance.score()));
+        self.extract_ranked(ranked, token_budget)
+    }
+
+    /// Assemble one context string from search results in the given order,
+    /// within `token_budget` estimated tokens.
+    ///
+    /// Each result becomes a `[Section: title] (pages a-b)` header followed
+    /// by its pages with their `<physical_index_N>` tags, so answers can
+    /// cite pages. A page is included once: results whose pages all came
+    /// with an earlier section (e.g. a parent) are skipped. The page that
+    /// crosses the budget is cut short and marked `...[truncated]`, and
+    /// nothing follows it.
+    pub fn extract_ranked<'a>(
+        &self,
+        results: impl IntoIterator<Item = &'a SearchResult>,
+        token_budget: usize,
+    ) -> String {
+        let mut included = HashSet::new();
+        let mut remaining = token_budget;
+        let mut sections = Vec::new();
+
+        for result in results {
+            let header = section_header(result);
+            let Some(mut budget) = remaining.checked_sub(estimate_tokens(&header)) else {
+                break;
+            };
+
+            let mut section = String::new();
+            let mut truncated = false;
+            let pages = self
+                .pages
+                .iter()
+                .filter(|p| p.number >= result.start_index && p.number <= result.end_index);
+            for page in pages {
+                if !included.insert(page.number) {
+                    continue;
+                }
+                if page.token_count <= budget {
+                    section.push_str(&page.with_index_tags());
+                    budget -= page.token_count;
+                    continue;
+                }
+                // Words that fit in the remaining tokens (inverse of words / 0.75)
+                let words = budget * 3 / 4;
+                if words > 0 {
+                    let content = format!("{}...[truncated]", first_words(&page.content, words));
+                    section.push_str(&Page::new(page.number, content).with_index_tags());
+                }
+                budget = 0;
+                truncated = true;
+                break;
+            }
+
+            if !section.is_empty() {
+                sections.push(format!("{}{}", header, section.trim_end()));
+                remaining = budget;
+            }
+            if truncated {
+                break;
+            }
+        }
+
+        sections.join(SECTION_SEPARATOR)
+    }
+}
+
+/// What separates sections in an extracted context.
+pub(crate) const SECTION_SEPARATOR: &str = "\n\n---\n\n";
+
+/// The line [`Document::extract_ranked`] puts before a section's pages.
+pub(crate) fn section_header(result: &SearchResult) -> String {
+    format!(
+        "[Section: {}] (pages {}-{})\n",
+        result.title, result.start_index, result.end_index
+    )
+}
+
+/// The first `count` words of `text`, keeping its line breaks.
+fn first_words(text: &str, count: usize) -> &str {
+    let mut words = 0;
+    let mut in_word = false;
+    for (i, c) in text.char_indices() {
+        if c.is_whitespace() {
+            if in_word {
+                words += 1;
+                if words == count {
+                    return &text[..i];
+                }
+            }
+            in_word = false;
+        } else {
+            in_word = true;
+        }
+    }
+    text
 }
 
 /// Estimate token count from text (rough approximation: words / 0.75).
-fn estimate_tokens(text: &str) -> usize {
+pub(crate) fn estimate_tokens(text: &str) -> usize {
     let word_count = text.split_whitespace().count();
     (word_count as f64 / 0.75) as usize
 }
@@ -210,11 +318,61 @@ mod tests {
         assert!(doc.get_page(2).is_none()); // Out of range
     }
 
+    #[test]
+    fn test_extract_for_results() {
+        use crate::search::Relevance;
+
+        let doc = Document::new(
+            "manual",
+            vec![
+                Page::new(1, "Setup steps for the pump.".to_string()),
+                Page::new(2, "Clean the filter once a week.".to_string()),
+                Page::new(
+                    3,
+                    "one two three four five six seven eight nine ten".to_string(),
+                ),
+            ],
+        );
+        let result = |title: &str, start, end, relevance| SearchResult {
+            title: title.to_string(),
+            start_index: start,
+            end_index: end,
+            relevance,
+            reason: String::new(),
+            content: None,
+            snippet: None,
+            snippet_span: None,
+        };
+        let results = [
+            result("Specs", 3, 3, Relevance::Low),
+            result("Maintenance", 1, 2, Relevance::High),
+            result("Filter", 2, 2, Relevance::Medium),
+        ];
+
+        let context = doc.extract_for_results(&results, 1000);
+        let sections: Vec<&str> = context.split("\n\n---\n\n").collect();
+        assert_eq!(sections.len(), 2, "{}", context);
+        assert!(sections[0].starts_with("[Section: Maintenance] (pages 1-2)\n<physical_index_1>"));
+        assert!(sections[0].contains("<physical_index_2>\nClean the filter"));
+        assert!(sections[1].starts_with("[Section: Specs] (pages 3-3)"));
+
+        // The budget cuts the last page short and drops what follows
+        let budget = estimate_tokens("[Section: Specs] (pages 3-3)") + 4;
+        let context = doc.extract_ranked(&results, budget);
+        assert!(
+            context.contains("\none two three...[truncated]\n"),
+            "{}",
+            context
+        );
+        assert!(!context.contains("Maintenance"));
+        assert!(doc.extract_for_results(&results, 1).is_empty());
+    }
+
     #[test]
     fn test_estimate_tokens() {
         let text = "one two three four five six seven eight";
         let tokens = estimate_tokens(text);
         // 8 words / 0.75 ≈ 10-11 tokens
-        assert!(tokens >= 10 && tokens <= 12);
+        assert!((10..=12).contains(&tokens));
     }
 }
@@ -0,0 +1,376 @@
+//! Tree surgery: targeted edits to a saved tree index.
+//!
+//! Nodes are addressed by `node_id` or structure path (see
+//! [`DocumentTree::find_node`]). Edits keep node IDs and structure strings
+//! as they are, so existing references to a node stay valid.
+
+use crate::error::{PageIndexError, Result};
+use crate::tree::{DocumentTree, TreeNode, parse_effective_date};
+
+/// A single edit to apply to a tree.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TreeEdit {
+    /// Change a node's title.
+    Rename { node: String, title: String },
+    /// Change a node's page range.
+    SetPages {
+        node: String,
+        start_index: usize,
+        end_index: usize,
+    },
+    /// Delete a node and its subtree.
+    Delete { node: String },
+    /// Move a node's subtree under a new parent (or to the top level when
+    /// `parent` is `None`), at `position` among its new siblings (default: last).
+    Move {
+        node: String,
+        parent: Option<String>,
+        position: Option<usize>,
+    },
+    /// Set a document metadata value, or remove the key when `value` is
+    /// `None`.
+    SetMetadata { key: String, value: Option<String> },
+    /// Define a term of the document's jargon, or remove it when
+    /// `definition` is `None` (see [`DocumentTree::terms`]).
+    SetTerm {
+        term: String,
+        definition: Option<String>,
+    },
+    /// Mark a node as non-content, leaving it out of search prompts, or
+    /// clear the mark (see [`TreeNode::excluded`]).
+    SetExcluded { node: String, excluded: bool },
+    /// Set a node's access labels, or clear them with an empty list (see
+    /// [`TreeNode::acl`]).
+    SetAcl { node: String, labels: Vec<String> },
+    /// Set the date a node's content took effect (`YYYY`, `YYYY-MM` or
+    /// `YYYY-MM-DD`), or clear it (see [`TreeNode::effective_date`]).
+    SetEffectiveDate { node: String, date: Option<String> },
+}
+
+/// Apply an edit to a tree in place.
+pub fn apply_edit(tree: &mut DocumentTree, edit: &TreeEdit) -> Result<()> {
+    match edit {
+        TreeEdit::Rename { node, title } => {
+            if title.trim().is_empty() {
+                return Err(PageIndexError::TreeError(
+                    "title cannot be empty".to_string(),
+                ));
+            }
+            find_mut(tree, node)?.title = title.clone();
+        }
+        TreeEdit::SetPages {
+            node,
+            start_index,
+            end_index,
+        } => {
+            if *start_index == 0 || end_index < start_index {
+                return Err(PageIndexError::TreeError(format!(
+                    "invalid page range {}-{}",
+                    start_index, end_index
+                )));
+            }
+            let target = find_mut(tree, node)?;
+            target.start_index = *start_index;
+            target.end_index = *end_index;
+        }
+        TreeEdit::Delete { node } => {
+            tree.remove_node(node).ok_or_else(|| not_found(node))?;
+        }
+        TreeEdit::Move {
+            node,
+            parent,
+            position,
+        } => move_node(tree, node, parent.as_deref(), *position)?,
+        TreeEdit::SetMetadata { key, value } => {
+            if key.trim().is_empty() {
+                return Err(PageIndexError::TreeError(
+                    "metadata key cannot be empty".to_string(),
+                ));
+            }
+            match value {
+                Some(value) => tree.metadata.insert(key.clone(), value.clone()),
+                None => tree.metadata.remove(key),
+            };
+        }
+        TreeEdit::SetTerm { term, definition } => {
+            let term = term.trim();
+            if term.is_empty() {
+                return Err(PageIndexError::TreeError(
+                    "term cannot be empty".to_string(),
+                ));
+            }
+            match definition {
+                Some(definition) if !definition.trim().is_empty() => tree
+                    .terms
+                    .insert(term.to_string(), definition.trim().to_string()),
+                Some(_) => {
+                    return Err(PageIndexError::TreeError(
+                        "definition cannot be empty".to_string(),
+                    ));
+                }
+                None => tree.terms.remove(term),
+            };
+        }
+        TreeEdit::SetExcluded { node, excluded } => find_mut(tree, node)?.excluded = *excluded,
+        TreeEdit::SetAcl { node, labels } => {
+            if labels.iter().any(|l| l.trim().is_empty()) {
+                return Err(PageIndexError::TreeError(
+                    "access labels cannot be empty".to_string(),
+                ));
+            }
+            let mut labels = labels.clone();
+            labels.sort();
+            labels.dedup();
+            find_mut(tree, node)?.acl = labels;
+        }
+        TreeEdit::SetEffectiveDate { node, date } => {
+            let date = match date {
+                Some(date) => Some(parse_effective_date(date).ok_or_else(|| {
+                    PageIndexError::TreeError(format!("invalid date '{}'", date))
+                })?),
+                None => None,
+            };
+            find_mut(tree, node)?.effective_date = date;
+        }
+    }
+    // Titles and ancestry may have changed
+    if tree.has_prefix_summaries() {
+        tree.compose_prefix_summaries();
+    }
+    Ok(())
+}
+
+fn move_node(
+    tree: &mut DocumentTree,
+    node: &str,
+    parent: Option<&str>,
+    position: Option<usize>,
+) -> Result<()> {
+    let node_path = tree.path_to(node).ok_or_else(|| not_found(node))?;
+
+    if let Some(parent) = parent {
+        let parent_path = tree.path_to(parent).ok_or_else(|| not_found(parent))?;
+        // A node can't become a descendant of itself
+        if parent_path.starts_with(&node_path) {
+            return Err(PageIndexError::TreeError(format!(
+                "cannot move '{}' into its own subtree",
+                node
+            )));
+        }
+    }
+
+    let subtree = tree.remove_node(node).ok_or_else(|| not_found(node))?;
+    let siblings = match parent {
+        Some(parent) => &mut find_mut(tree, parent)?.nodes,
+        None => &mut tree.nodes,
+    };
+    let position = position.unwrap_or(siblings.len()).min(siblings.len());
+    siblings.insert(position, subtree);
+    Ok(())
+}
+
+fn find_mut<'a>(tree: &'a mut DocumentTree, address: &str) -> Result<&'a mut TreeNode> {
+    tree.find_node_mut(address)
+        .ok_or_else(|| not_found(address))
+}
+
+fn not_found(address: &str) -> PageIndexError {
+    PageIndexError::TreeError(format!("no node with node_id or structure '{}'", address))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(title: &str, structure: &str, id: &str, start: usize, end: usize) -> TreeNode {
+        let mut node = TreeNode::new(title, start, end).with_structure(structure);
+        node.node_id = Some(id.to_string());
+        node
+    }
+
+    fn sample_tree() -> DocumentTree {
+        let mut ch1 = node("Chapter 1", "1", "0000", 1, 10);
+        ch1.add_child(node("Section 1.1", "1.1", "0001", 1, 5));
+        ch1.add_child(node("Section 1.2", "1.2", "0002", 6, 10));
+        let ch2 = node("Chapter 2", "2", "0003", 11, 20);
+        DocumentTree::new("Doc", vec![ch1, ch2], 20)
+    }
+
+    #[test]
+    fn test_rename_and_set_pages() {
+        let mut tree = sample_tree();
+        apply_edit(
+            &mut tree,
+            &TreeEdit::Rename {
+                node: "1.2".to_string(),
+                title: "Methods".to_string(),
+            },
+        )
+        .unwrap();
+        apply_edit(
+            &mut tree,
+            &TreeEdit::SetPages {
+                node: "0003".to_string(),
+                start_index: 11,
+                end_index: 18,
+            },
+        )
+        .unwrap();
+
+        assert_eq!(tree.nodes[0].nodes[1].title, "Methods");
+        assert_eq!(tree.nodes[1].end_index, 18);
+
+        // Prefix summaries follow renames
+        tree.nodes[0].summary = Some("Basics.".to_string());
+        tree.nodes[0].nodes[1].summary = Some("How.".to_string());
+        tree.compose_prefix_summaries();
+        let rename = TreeEdit::Rename {
+            node: "1".to_string(),
+            title: "Overview".to_string(),
+        };
+        apply_edit(&mut tree, &rename).unwrap();
+        assert_eq!(
+            tree.nodes[0].nodes[1].prefix_summary.as_deref(),
+            Some("Overview > Methods: How.")
+        );
+
+        let bad_range = TreeEdit::SetPages {
+            node: "0003".to_string(),
+            start_index: 5,
+            end_index: 2,
+        };
+        assert!(apply_edit(&mut tree, &bad_range).is_err());
+    }
+
+    #[test]
+    fn test_delete_and_move() {
+        let mut tree = sample_tree();
+        apply_edit(
+            &mut tree,
+            &TreeEdit::Move {
+                node: "0002".to_string(),
+                parent: Some("0003".to_string()),
+                position: None,
+            },
+        )
+        .unwrap();
+        assert_eq!(tree.nodes[0].nodes.len(), 1);
+        assert_eq!(tree.nodes[1].nodes[0].title, "Section 1.2");
+
+        apply_edit(
+            &mut tree,
+            &TreeEdit::Delete {
+                node: "1".to_string(),
+            },
+        )
+        .unwrap();
+        assert_eq!(tree.nodes.len(), 1);
+        assert_eq!(tree.node_count(), 2);
+
+        assert!(
+            apply_edit(
+                &mut tree,
+                &TreeEdit::Delete {
+                    node: "0000".to_string()
+                }
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_move_into_own_subtree_is_rejected() {
+        let mut tree = sample_tree();
+        let edit = TreeEdit::Move {
+            node: "0000".to_string(),
+            parent: Some("0001".to_string()),
+            position: Some(0),
+        };
+        assert!(apply_edit(&mut tree, &edit).is_err());
+        assert_eq!(tree.node_count(), 4);
+    }
+
+    #[test]
+    fn test_set_metadata() {
+        let mut tree = sample_tree();
+        let set = |value: Option<&str>| TreeEdit::SetMetadata {
+            key: "type".to_string(),
+            value: value.map(str::to_string),
+        };
+        apply_edit(&mut tree, &set(Some("contract"))).unwrap();
+        assert_eq!(tree.metadata["type"], "contract");
+        apply_edit(&mut tree, &set(None)).unwrap();
+        assert!(tree.metadata.is_empty());
+
+        let empty = TreeEdit::SetMetadata {
+            key: " ".to_string(),
+            value: Some("x".to_string()),
+        };
+        assert!(apply_edit(&mut tree, &empty).is_err());
+    }
+
+    #[test]
+    fn test_set_term() {
+        let mut tree = sample_tree();
+        let set = |term: &str, definition: Option<&str>| TreeEdit::SetTerm {
+            term: term.to_string(),
+            definition: definition.map(str::to_string),
+        };
+        apply_edit(
+            &mut tree,
+            &set(" DSCR ", Some("Debt service coverage ratio. ")),
+        )
+        .unwrap();
+        assert_eq!(tree.terms["DSCR"], "Debt service coverage ratio.");
+        assert!(apply_edit(&mut tree, &set("DSCR", Some(" "))).is_err());
+        assert!(apply_edit(&mut tree, &set("", Some("x"))).is_err());
+        apply_edit(&mut tree, &set("DSCR", None)).unwrap();
+        assert!(tree.terms.is_empty());
+    }
+
+    #[test]
+    fn test_set_effective_date() {
+        let mut tree = sample_tree();
+        let set = |node: &str, date: Option<&str>| TreeEdit::SetEffectiveDate {
+            node: node.to_string(),
+            date: date.map(str::to_string),
+        };
+        apply_edit(&mut tree, &set("2", Some("2023-07"))).unwrap();
+        assert_eq!(
+            tree.find_node("2").unwrap().effective_date.as_deref(),
+            Some("2023-07-01")
+        );
+        assert!(apply_edit(&mut tree, &set("2", Some("July 2023"))).is_err());
+        assert!(apply_edit(&mut tree, &set("9", Some("2023"))).is_err());
+        apply_edit(&mut tree, &set("2", None)).unwrap();
+        assert_eq!(tree.find_node("2").unwrap().effective_date, None);
+    }
+
+    #[test]
+    fn test_set_excluded() {
+        let mut tree = sample_tree();
+        let set = |node: &str, excluded| TreeEdit::SetExcluded {
+            node: node.to_string(),
+            excluded,
+        };
+        apply_edit(&mut tree, &set("2", true)).unwrap();
+        assert!(tree.has_excluded());
+        apply_edit(&mut tree, &set("2", false)).unwrap();
+        assert!(!tree.has_excluded());
+        assert!(apply_edit(&mut tree, &set("9", true)).is_err());
+    }
+
+    #[test]
+    fn test_set_acl() {
+        let mut tree = sample_tree();
+        let set = |labels: &[&str]| TreeEdit::SetAcl {
+            node: "0003".to_string(),
+            labels: labels.iter().map(|l| l.to_string()).collect(),
+        };
+        apply_edit(&mut tree, &set(&["legal", "finance", "legal"])).unwrap();
+        assert_eq!(tree.nodes[1].acl, vec!["finance", "legal"]);
+        apply_edit(&mut tree, &set(&[])).unwrap();
+        assert!(!tree.has_acl());
+        assert!(apply_edit(&mut tree, &set(&[" "])).is_err());
+    }
+}
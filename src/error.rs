@@ -1,6 +1,7 @@
 //! Error types for the page indexer.
 
 use std::path::PathBuf;
+use std::time::Duration;
 use thiserror::Error;
 
 /// Result type alias using our custom error.
@@ -25,6 +26,10 @@ pub enum PageIndexError {
     #[error("Document not found at '{0}'")]
     DocumentNotFound(PathBuf),
 
+    /// No loader can read the document's format.
+    #[error("Unsupported document format: {0}")]
+    UnsupportedFormat(String),
+
     /// The corpus directory does not exist or is not a directory.
     #[error("Corpus path '{0}' does not exist or is not a directory")]
     InvalidCorpusPath(PathBuf),
@@ -41,10 +46,34 @@ pub enum PageIndexError {
     #[error("Invalid configuration: {0}")]
     InvalidConfig(String),
 
-    /// LLM API error.
+    /// LLM API error not covered by a more specific variant.
     #[error("LLM API error: {0}")]
     LlmApi(String),
 
+    /// The LLM provider is rate limiting requests.
+    #[error("LLM rate limited: {message}")]
+    RateLimited {
+        /// How long the provider asked to wait, if it said.
+        retry_after: Option<Duration>,
+        message: String,
+    },
+
+    /// The prompt does not fit in the model's context window.
+    #[error("LLM context length exceeded: {0}")]
+    ContextLengthExceeded(String),
+
+    /// The LLM provider rejected the API key or its permissions.
+    #[error("LLM authentication failed: {0}")]
+    AuthFailed(String),
+
+    /// The LLM request timed out.
+    #[error("LLM request timed out: {0}")]
+    Timeout(String),
+
+    /// The LLM provider failed with a 5xx status.
+    #[error("LLM server error ({status}): {message}")]
+    ServerError { status: u16, message: String },
+
     /// LLM response parsing error.
     #[error("Failed to parse LLM response: {0}")]
     LlmParse(String),
@@ -60,6 +89,22 @@ pub enum PageIndexError {
     /// Tree structure error.
     #[error("Tree structure error: {0}")]
     TreeError(String),
+
+    /// Checkpoint cannot be used for this run.
+    #[error("Checkpoint error: {0}")]
+    Checkpoint(String),
+
+    /// File watcher error.
+    #[error("File watcher error: {0}")]
+    Watch(String),
+
+    /// HTTP server error.
+    #[error("Server error: {0}")]
+    Server(String),
+
+    /// Telemetry exporter error.
+    #[error("Telemetry error: {0}")]
+    Telemetry(String),
 }
 
 impl PageIndexError {
@@ -70,11 +115,60 @@ impl PageIndexError {
             source,
         }
     }
+
+    /// Short, stable name of the error variant (for metrics and logs).
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Io { .. } => "io",
+            Self::Serialization(_) => "serialization",
+            Self::DocumentNotFound(_) => "document_not_found",
+            Self::UnsupportedFormat(_) => "unsupported_format",
+            Self::InvalidCorpusPath(_) => "invalid_corpus_path",
+            Self::EmptyCorpus(_) => "empty_corpus",
+            Self::IndexNotFound(_) => "index_not_found",
+            Self::InvalidConfig(_) => "invalid_config",
+            Self::LlmApi(_) => "llm_api",
+            Self::RateLimited { .. } => "rate_limited",
+            Self::ContextLengthExceeded(_) => "context_length_exceeded",
+            Self::AuthFailed(_) => "auth_failed",
+            Self::Timeout(_) => "timeout",
+            Self::ServerError { .. } => "server_error",
+            Self::LlmParse(_) => "llm_parse",
+            Self::Http(_) => "http",
+            Self::Config(_) => "config",
+            Self::TreeError(_) => "tree",
+            Self::Checkpoint(_) => "checkpoint",
+            Self::Watch(_) => "watch",
+            Self::Server(_) => "server",
+            Self::Telemetry(_) => "telemetry",
+        }
+    }
+
+    /// Whether the same request may succeed if sent again later: rate
+    /// limits, timeouts, server errors and connection failures.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Self::RateLimited { .. } | Self::Timeout(_) | Self::ServerError { .. } | Self::Http(_)
+        )
+    }
+
+    /// How long the provider asked to wait before retrying, if it said.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Self::RateLimited { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
 }
 
 impl From<reqwest::Error> for PageIndexError {
     fn from(err: reqwest::Error) -> Self {
-        PageIndexError::Http(err.to_string())
+        if err.is_timeout() {
+            PageIndexError::Timeout(err.to_string())
+        } else {
+            PageIndexError::Http(err.to_string())
+        }
     }
 }
 
@@ -83,3 +177,36 @@ impl From<serde_json::Error> for PageIndexError {
         PageIndexError::LlmParse(err.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_llm_error_kinds() {
+        let limited = PageIndexError::RateLimited {
+            retry_after: Some(Duration::from_secs(20)),
+            message: "slow down".to_string(),
+        };
+        assert_eq!(limited.kind(), "rate_limited");
+        assert_eq!(limited.retry_after(), Some(Duration::from_secs(20)));
+        assert!(limited.is_transient());
+        assert_eq!(limited.to_string(), "LLM rate limited: slow down");
+
+        let server = PageIndexError::ServerError {
+            status: 503,
+            message: "overloaded".to_string(),
+        };
+        assert!(server.is_transient());
+        assert_eq!(server.to_string(), "LLM server error (503): overloaded");
+
+        for permanent in [
+            PageIndexError::AuthFailed("bad key".to_string()),
+            PageIndexError::ContextLengthExceeded("too long".to_string()),
+            PageIndexError::LlmApi("no choices".to_string()),
+        ] {
+            assert!(!permanent.is_transient(), "{}", permanent);
+            assert_eq!(permanent.retry_after(), None);
+        }
+    }
+}
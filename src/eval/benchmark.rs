@@ -1,22 +1,40 @@
 //! Benchmark runner for comparing PageIndex vs Vector Search.
 
+use super::bm25::Bm25System;
 use super::dataset::{Dataset, DatasetItem};
-use super::embeddings::EmbeddingModel;
-use super::judge::{ComparisonResult, LlmJudge};
+use super::embeddings::{DEFAULT_BATCH_SIZE, EmbeddingBackend, EmbeddingDevice, EmbeddingModel};
+use super::hnsw::HnswConfig;
+use super::hybrid::{self, DEFAULT_RRF_K};
+use super::judge::{
+    AnswerGrade, ComparisonResult, GradeSummary, Groundedness, GroundednessSummary, JudgeCache,
+    LlmJudge,
+};
+use super::metrics::{AnswerMetrics, RetrievalMetrics, answer_metrics, retrieval_metrics};
+#[cfg(feature = "local-embeddings")]
+use super::rerank::DEFAULT_RERANKER_MODEL;
+use super::rerank::{DEFAULT_RERANK_CANDIDATES, Reranker};
+use super::rubric::Rubric;
+use super::stats::{JudgeAgreement, LatencyStats, LeaderboardEntry, Significance};
+use super::system::RetrievalSystem;
 use super::vector_search::{ChunkConfig, VectorIndex, VectorSearcher};
-use crate::config::LlmConfig;
+use crate::config::{LlmConfig, ModelPricing};
 use crate::document::Document;
 use crate::indexer::TreeIndexer;
-use crate::llm::{LlmClient, Prompts};
-use crate::search::{Relevance, TreeSearcher};
-use crate::tree::DocumentTree;
+use crate::llm::{LlmClient, Prompts, UsageStats};
+use crate::page_index::AnswerStyle;
+use crate::persistence::{load_tree, save_tree};
+use crate::progress::{ProgressCallback, ProgressEvent, emit};
+use crate::search::{Relevance, SearchResult, TreeSearcher};
+use crate::tree::{DocumentTree, fnv1a_hex};
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::hash::{Hash, Hasher};
+use std::collections::{BTreeMap, HashMap};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
 
 /// Truncate a string to a maximum length, adding "..." if truncated.
 fn truncate_str(s: &str, max_len: usize) -> String {
@@ -40,10 +58,83 @@ pub struct BenchmarkConfig {
     pub run_pageindex: bool,
     /// Whether to run vector search (requires embedding model).
     pub run_vector: bool,
+    /// Also run a BM25 keyword baseline, judged against PageIndex.
+    pub run_bm25: bool,
+    /// Also run a hybrid baseline fusing BM25 and vector rankings with
+    /// reciprocal rank fusion (requires embedding model).
+    pub run_hybrid: bool,
+    /// RRF constant for the hybrid baseline.
+    pub rrf_k: f32,
+    /// Token budget for PageIndex's answer context, assembled from the
+    /// selected sections' pages (`None`: no limit).
+    pub context_token_budget: Option<usize>,
+    /// Also run a reranked vector baseline: the top `rerank_candidates`
+    /// vector search results rescored by a cross-encoder (requires embedding
+    /// model and reranker).
+    pub run_reranked: bool,
+    /// Rerank PageIndex's retrieved sections with the cross-encoder before
+    /// taking the top `top_k`.
+    pub rerank_pageindex: bool,
+    /// Cross-encoder for reranking (`None`: MS MARCO MiniLM). Takes a
+    /// Hugging Face id or alias, see
+    /// [`resolve_reranker_model`](super::rerank::resolve_reranker_model).
+    pub reranker_model: Option<String>,
+    /// Vector search candidates passed to the reranker.
+    pub rerank_candidates: usize,
+    /// Judge every pair of systems' answers (round robin), not just each
+    /// system against PageIndex, for a fuller leaderboard.
+    pub tournament: bool,
     /// Maximum items to evaluate (for quick testing).
     pub max_items: Option<usize>,
     /// Verbose output.
     pub verbose: bool,
+    /// Answer items that have options by picking one, and report accuracy
+    /// instead of running the judge.
+    pub multiple_choice: bool,
+    /// Judge each comparison in both A/B orders and reconcile, to cancel the
+    /// judge's first-position bias (doubles judge calls).
+    pub swap_judge_order: bool,
+    /// Judge models for an ensemble judge (empty: judge with the answering
+    /// model). All use the same API endpoint as the main LLM config.
+    pub judge_models: Vec<String>,
+    /// Rubric replacing the judge's built-in criteria.
+    pub rubric: Option<Rubric>,
+    /// Also grade each system's answer on its own against the gold answer,
+    /// so runs with a single system still get a quality score.
+    pub grade_answers: bool,
+    /// Also check whether each answer is supported by its own retrieved
+    /// content, catching answers that are right only from model priors.
+    pub check_groundedness: bool,
+    /// Where vector search embeddings are computed.
+    pub embedding_backend: EmbeddingBackend,
+    /// Embedding model for vector search (`None`: the backend's default).
+    /// Local models take a Hugging Face id or alias such as `bge-small`, see
+    /// [`ModelSpec::resolve`](super::embeddings::ModelSpec::resolve); API
+    /// models use the LLM config's endpoint and key.
+    pub embedding_model: Option<String>,
+    /// Device for a local embedding model.
+    pub embedding_device: EmbeddingDevice,
+    /// Texts embedded per forward pass or API request.
+    pub embedding_batch_size: usize,
+    /// Search vector indexes with an HNSW graph instead of brute force.
+    pub hnsw: Option<HnswConfig>,
+    /// Directory for persisted vector indexes, so repeated runs don't
+    /// re-embed identical documents.
+    pub vector_cache_dir: Option<PathBuf>,
+    /// Directory of prebuilt PageIndex trees, named `<document hash>` or
+    /// `<item id>` with a `.json` or `.bin` extension. Items with a tree
+    /// there skip indexing (and its cost); trees built during the run are
+    /// saved there as `<document hash>.json` for later runs.
+    pub tree_dir: Option<PathBuf>,
+    /// JSONL file caching judge verdicts across runs, so comparisons whose
+    /// answers haven't changed are not judged (and paid for) again.
+    pub judge_cache: Option<PathBuf>,
+    /// Append each item's result to this JSONL file as soon as it completes.
+    pub results_log: Option<PathBuf>,
+    /// Skip items already completed in this results log (from an interrupted
+    /// run). Items that ended in an error are retried. New results are
+    /// appended to `results_log`, or to this file if that is unset.
+    pub resume_from: Option<PathBuf>,
 }
 
 impl Default for BenchmarkConfig {
@@ -53,8 +144,33 @@ impl Default for BenchmarkConfig {
             chunk_config: ChunkConfig::default(),
             run_pageindex: true,
             run_vector: true,
+            run_bm25: false,
+            run_hybrid: false,
+            rrf_k: DEFAULT_RRF_K,
+            context_token_budget: None,
+            run_reranked: false,
+            rerank_pageindex: false,
+            reranker_model: None,
+            rerank_candidates: DEFAULT_RERANK_CANDIDATES,
+            tournament: false,
             max_items: None,
             verbose: false,
+            multiple_choice: false,
+            swap_judge_order: false,
+            judge_models: Vec::new(),
+            rubric: None,
+            grade_answers: false,
+            check_groundedness: false,
+            embedding_backend: EmbeddingBackend::default(),
+            embedding_model: None,
+            embedding_device: EmbeddingDevice::default(),
+            embedding_batch_size: DEFAULT_BATCH_SIZE,
+            hnsw: None,
+            vector_cache_dir: None,
+            tree_dir: None,
+            judge_cache: None,
+            results_log: None,
+            resume_from: None,
         }
     }
 }
@@ -70,18 +186,384 @@ pub struct ItemResult {
     pub pageindex_answer: Option<String>,
     /// PageIndex retrieval time.
     pub pageindex_time_ms: Option<u64>,
+    /// Part of the PageIndex retrieval time spent building the tree (`None`
+    /// when a cached tree was reused).
+    #[serde(default)]
+    pub pageindex_build_time_ms: Option<u64>,
     /// Vector search retrieved content.
     pub vector_content: Option<String>,
     /// Vector search generated answer (RAG).
     pub vector_answer: Option<String>,
     /// Vector search retrieval time.
     pub vector_time_ms: Option<u64>,
+    /// PageIndex retrieval metrics (if the item has gold evidence).
+    #[serde(default)]
+    pub pageindex_retrieval: Option<RetrievalMetrics>,
+    /// Vector search retrieval metrics (if the item has gold evidence).
+    #[serde(default)]
+    pub vector_retrieval: Option<RetrievalMetrics>,
+    /// PageIndex answer EM/F1 (if the item has a gold answer).
+    #[serde(default)]
+    pub pageindex_answer_metrics: Option<AnswerMetrics>,
+    /// Vector RAG answer EM/F1 (if the item has a gold answer).
+    #[serde(default)]
+    pub vector_answer_metrics: Option<AnswerMetrics>,
+    /// Option picked by PageIndex's answerer (multiple-choice mode, 0-indexed).
+    #[serde(default)]
+    pub pageindex_choice: Option<usize>,
+    /// Option picked by the Vector RAG answerer (multiple-choice mode, 0-indexed).
+    #[serde(default)]
+    pub vector_choice: Option<usize>,
+    /// Whether PageIndex picked the correct option (multiple-choice mode).
+    #[serde(default)]
+    pub pageindex_correct: Option<bool>,
+    /// Whether Vector RAG picked the correct option (multiple-choice mode).
+    #[serde(default)]
+    pub vector_correct: Option<bool>,
+    /// Pointwise grade of PageIndex's answer (when grading answers).
+    #[serde(default)]
+    pub pageindex_grade: Option<AnswerGrade>,
+    /// Pointwise grade of Vector RAG's answer (when grading answers).
+    #[serde(default)]
+    pub vector_grade: Option<AnswerGrade>,
+    /// Whether PageIndex's answer is supported by its retrieved content
+    /// (when checking groundedness).
+    #[serde(default)]
+    pub pageindex_groundedness: Option<Groundedness>,
+    /// Whether Vector RAG's answer is supported by its retrieved content
+    /// (when checking groundedness).
+    #[serde(default)]
+    pub vector_groundedness: Option<Groundedness>,
     /// Comparison result from judge.
     pub comparison: Option<ComparisonResult>,
+    /// Additional baselines (e.g. BM25), each judged against PageIndex.
+    #[serde(default)]
+    pub baselines: Vec<BaselineResult>,
+    /// Judged pairs not involving PageIndex (tournament mode).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub matches: Vec<PairwiseMatch>,
+    /// PageIndex LLM usage (tree building and search + answering).
+    #[serde(default)]
+    pub pageindex_usage: Option<ItemUsage>,
+    /// Vector RAG usage (embeddings and answering).
+    #[serde(default)]
+    pub vector_usage: Option<ItemUsage>,
+    /// LLM usage judging this item's answers, across all comparisons.
+    #[serde(default)]
+    pub judge_usage: UsageStats,
+    /// The dataset item's tags.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
     /// Error message if any.
     pub error: Option<String>,
 }
 
+impl ItemResult {
+    /// Record an error, keeping any earlier ones.
+    fn add_error(&mut self, message: String) {
+        self.error = Some(match self.error.take() {
+            Some(existing) => format!("{}\n{}", existing, message),
+            None => message,
+        });
+    }
+
+    /// Every judged pair on this item as (system 1, system 2, verdict):
+    /// PageIndex vs. Vector, PageIndex vs. each baseline, then tournament
+    /// matches.
+    pub fn pairwise(&self) -> Vec<(&str, &str, &ComparisonResult)> {
+        let mut pairs: Vec<(&str, &str, &ComparisonResult)> = Vec::new();
+        if let Some(comparison) = &self.comparison {
+            pairs.push(("PageIndex", "Vector", comparison));
+        }
+        for baseline in &self.baselines {
+            if let Some(comparison) = &baseline.comparison {
+                pairs.push(("PageIndex", &baseline.system, comparison));
+            }
+        }
+        for m in &self.matches {
+            pairs.push((&m.system1, &m.system2, &m.comparison));
+        }
+        pairs
+    }
+}
+
+/// A judged comparison between two systems' answers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PairwiseMatch {
+    pub system1: String,
+    pub system2: String,
+    pub comparison: ComparisonResult,
+}
+
+/// One system's usage on a single item.
+///
+/// Index usage is only recorded on the item that built the index; items
+/// reusing a cached tree or vector index record none.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ItemUsage {
+    /// LLM usage building the index.
+    pub index: UsageStats,
+    /// LLM usage retrieving and answering.
+    pub query: UsageStats,
+    /// Embedding API tokens, for indexing and querying (not priced).
+    #[serde(default)]
+    pub embedding_tokens: u64,
+}
+
+/// An additional baseline's result for a single item.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BaselineResult {
+    /// Baseline name (e.g. "BM25").
+    pub system: String,
+    /// Retrieved content.
+    pub content: Option<String>,
+    /// Generated answer.
+    pub answer: Option<String>,
+    /// Retrieval time.
+    pub time_ms: Option<u64>,
+    /// Retrieval metrics (if the item has gold evidence).
+    #[serde(default)]
+    pub retrieval: Option<RetrievalMetrics>,
+    /// Answer EM/F1 (if the item has a gold answer).
+    #[serde(default)]
+    pub answer_metrics: Option<AnswerMetrics>,
+    /// Option picked by the answerer (multiple-choice mode, 0-indexed).
+    #[serde(default)]
+    pub choice: Option<usize>,
+    /// Whether the correct option was picked (multiple-choice mode).
+    #[serde(default)]
+    pub correct: Option<bool>,
+    /// Pointwise grade of the answer (when grading answers).
+    #[serde(default)]
+    pub grade: Option<AnswerGrade>,
+    /// Whether the answer is supported by the retrieved content (when
+    /// checking groundedness).
+    #[serde(default)]
+    pub groundedness: Option<Groundedness>,
+    /// Judge comparison with PageIndex as system 1 and this baseline as system 2.
+    pub comparison: Option<ComparisonResult>,
+    /// Embedding and LLM usage.
+    #[serde(default)]
+    pub usage: Option<ItemUsage>,
+}
+
+/// Aggregated results for an additional baseline.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BaselineSummary {
+    /// Baseline name.
+    pub system: String,
+    /// Items where PageIndex won against this baseline.
+    pub pageindex_wins: usize,
+    /// Items where this baseline won against PageIndex.
+    pub baseline_wins: usize,
+    /// Ties.
+    pub ties: usize,
+    /// Average PageIndex score in these comparisons.
+    pub avg_pageindex_score: f64,
+    /// Average baseline score.
+    pub avg_baseline_score: f64,
+    /// Average retrieval time (ms).
+    pub avg_time_ms: f64,
+    /// Retrieval time percentiles.
+    #[serde(default)]
+    pub latency: Option<LatencyStats>,
+    /// Mean retrieval metrics over items with gold evidence.
+    pub retrieval: Option<RetrievalMetrics>,
+    /// Mean answer EM/F1 over items with a gold answer.
+    pub answer_metrics: Option<AnswerMetrics>,
+    /// Multiple-choice accuracy (multiple-choice mode).
+    pub accuracy: Option<f64>,
+    /// Pointwise answer grades (when grading answers).
+    #[serde(default)]
+    pub grades: Option<GradeSummary>,
+    /// Groundedness checks (when checking groundedness).
+    #[serde(default)]
+    pub groundedness: Option<GroundednessSummary>,
+    /// Significance of PageIndex vs. this baseline.
+    pub significance: Option<Significance>,
+}
+
+impl BaselineSummary {
+    /// Aggregate one baseline's per-item results.
+    fn compute(system: &str, results: &[&BaselineResult]) -> Self {
+        let comparisons: Vec<&ComparisonResult> = results
+            .iter()
+            .filter_map(|r| r.comparison.as_ref())
+            .collect();
+        let count = |winner: u8| comparisons.iter().filter(|c| c.winner == winner).count();
+        let winners: Vec<u8> = comparisons.iter().map(|c| c.winner).collect();
+        let scores: Vec<(f64, f64)> = comparisons
+            .iter()
+            .map(|c| (c.score_system1 as f64, c.score_system2 as f64))
+            .collect();
+        let mean = |values: &[f64]| {
+            if values.is_empty() {
+                0.0
+            } else {
+                values.iter().sum::<f64>() / values.len() as f64
+            }
+        };
+        let times: Vec<f64> = results
+            .iter()
+            .filter_map(|r| r.time_ms)
+            .map(|t| t as f64)
+            .collect();
+        let retrieval: Vec<_> = results.iter().filter_map(|r| r.retrieval).collect();
+        let answers: Vec<_> = results.iter().filter_map(|r| r.answer_metrics).collect();
+
+        Self {
+            system: system.to_string(),
+            pageindex_wins: count(1),
+            baseline_wins: count(2),
+            ties: comparisons.len() - count(1) - count(2),
+            avg_pageindex_score: mean(&scores.iter().map(|s| s.0).collect::<Vec<_>>()),
+            avg_baseline_score: mean(&scores.iter().map(|s| s.1).collect::<Vec<_>>()),
+            avg_time_ms: mean(&times),
+            latency: LatencyStats::compute(&times),
+            retrieval: RetrievalMetrics::mean(&retrieval),
+            answer_metrics: AnswerMetrics::mean(&answers),
+            accuracy: accuracy(results.iter().map(|r| r.correct)),
+            grades: GradeSummary::compute(
+                &results
+                    .iter()
+                    .filter_map(|r| r.grade.as_ref())
+                    .collect::<Vec<_>>(),
+            ),
+            groundedness: GroundednessSummary::compute(
+                &results
+                    .iter()
+                    .filter_map(|r| {
+                        let correct = r.grade.as_ref().map(|g| g.correct).or(r.correct);
+                        Some((r.groundedness.as_ref()?, correct))
+                    })
+                    .collect::<Vec<_>>(),
+            ),
+            significance: Significance::compute(&winners, &scores),
+        }
+    }
+}
+
+/// PageIndex vs. Vector results for the items sharing one tag value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TagBreakdown {
+    /// Tag name (e.g. "difficulty").
+    pub tag: String,
+    /// Tag value (e.g. "hard").
+    pub value: String,
+    /// Items with this tag value.
+    pub items: usize,
+    /// Items where PageIndex won.
+    pub pageindex_wins: usize,
+    /// Items where Vector search won.
+    pub vector_wins: usize,
+    /// Ties.
+    pub ties: usize,
+    /// Average PageIndex score.
+    pub avg_pageindex_score: f64,
+    /// Average Vector score.
+    pub avg_vector_score: f64,
+    /// PageIndex multiple-choice accuracy (multiple-choice mode).
+    pub pageindex_accuracy: Option<f64>,
+    /// Vector RAG multiple-choice accuracy (multiple-choice mode).
+    pub vector_accuracy: Option<f64>,
+    /// Mean PageIndex answer F1 over items with a gold answer.
+    pub pageindex_f1: Option<f64>,
+    /// Mean Vector RAG answer F1 over items with a gold answer.
+    pub vector_f1: Option<f64>,
+}
+
+impl TagBreakdown {
+    /// Aggregate the items sharing a tag value.
+    fn compute(tag: &str, value: &str, results: &[&ItemResult]) -> Self {
+        let comparisons: Vec<&ComparisonResult> = results
+            .iter()
+            .filter_map(|r| r.comparison.as_ref())
+            .collect();
+        let count = |winner: u8| comparisons.iter().filter(|c| c.winner == winner).count();
+        let mean_score = |score: fn(&ComparisonResult) -> f32| {
+            if comparisons.is_empty() {
+                0.0
+            } else {
+                comparisons.iter().map(|c| score(c) as f64).sum::<f64>() / comparisons.len() as f64
+            }
+        };
+        let f1 = |metrics: fn(&ItemResult) -> Option<AnswerMetrics>| {
+            let metrics: Vec<_> = results.iter().filter_map(|r| metrics(r)).collect();
+            AnswerMetrics::mean(&metrics).map(|m| m.f1)
+        };
+
+        Self {
+            tag: tag.to_string(),
+            value: value.to_string(),
+            items: results.len(),
+            pageindex_wins: count(1),
+            vector_wins: count(2),
+            ties: comparisons.len() - count(1) - count(2),
+            avg_pageindex_score: mean_score(|c| c.score_system1),
+            avg_vector_score: mean_score(|c| c.score_system2),
+            pageindex_accuracy: accuracy(results.iter().map(|r| r.pageindex_correct)),
+            vector_accuracy: accuracy(results.iter().map(|r| r.vector_correct)),
+            pageindex_f1: f1(|r| r.pageindex_answer_metrics),
+            vector_f1: f1(|r| r.vector_answer_metrics),
+        }
+    }
+}
+
+/// A system's total usage and estimated cost over the benchmark.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SystemCost {
+    /// System name.
+    pub system: String,
+    /// Items the system answered.
+    pub queries: usize,
+    /// LLM usage building indexes.
+    pub index_usage: UsageStats,
+    /// LLM usage retrieving and answering.
+    pub query_usage: UsageStats,
+    /// Embedding API tokens.
+    pub embedding_tokens: u64,
+    /// Total indexing cost in USD (with pricing configured).
+    pub index_cost_usd: Option<f64>,
+    /// Mean retrieval + answering cost per query in USD.
+    pub query_cost_usd: Option<f64>,
+    /// Cost per query in USD with indexing amortized over all queries.
+    pub cost_per_query_usd: Option<f64>,
+}
+
+impl SystemCost {
+    /// Sum one system's per-item usage (`None` if it recorded none).
+    fn compute(system: &str, usages: &[ItemUsage], pricing: Option<&ModelPricing>) -> Option<Self> {
+        if usages.is_empty() {
+            return None;
+        }
+        let queries = usages.len();
+        let index_usage = usages
+            .iter()
+            .fold(UsageStats::default(), |total, u| total + u.index);
+        let query_usage = usages
+            .iter()
+            .fold(UsageStats::default(), |total, u| total + u.query);
+        let cost = |usage: &UsageStats| {
+            pricing.map(|p| p.cost(usage.prompt_tokens, usage.completion_tokens))
+        };
+        let index_cost_usd = cost(&index_usage);
+        let query_cost_usd = cost(&query_usage).map(|c| c / queries as f64);
+
+        Some(Self {
+            system: system.to_string(),
+            queries,
+            index_usage,
+            query_usage,
+            embedding_tokens: usages.iter().map(|u| u.embedding_tokens).sum(),
+            index_cost_usd,
+            query_cost_usd,
+            cost_per_query_usd: index_cost_usd
+                .zip(query_cost_usd)
+                .map(|(index, query)| index / queries as f64 + query),
+        })
+    }
+}
+
 /// Aggregated benchmark results.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BenchmarkResults {
@@ -103,10 +585,90 @@ pub struct BenchmarkResults {
     pub avg_pageindex_time_ms: f64,
     /// Average Vector retrieval time (ms).
     pub avg_vector_time_ms: f64,
+    /// PageIndex retrieval time percentiles, including tree building.
+    #[serde(default)]
+    pub pageindex_latency: Option<LatencyStats>,
+    /// PageIndex per-query search time percentiles, excluding tree building.
+    #[serde(default)]
+    pub pageindex_search_latency: Option<LatencyStats>,
+    /// Vector retrieval time percentiles, including index building.
+    #[serde(default)]
+    pub vector_latency: Option<LatencyStats>,
+    /// Total time spent building PageIndex trees (ms).
+    #[serde(default)]
+    pub pageindex_build_time_ms: f64,
+    /// Tree building time amortized over all PageIndex queries (ms).
+    #[serde(default)]
+    pub amortized_pageindex_build_ms: f64,
+    /// Mean PageIndex retrieval metrics over items with gold evidence.
+    #[serde(default)]
+    pub pageindex_retrieval: Option<RetrievalMetrics>,
+    /// Mean Vector retrieval metrics over items with gold evidence.
+    #[serde(default)]
+    pub vector_retrieval: Option<RetrievalMetrics>,
+    /// Mean PageIndex answer EM/F1 over items with a gold answer.
+    #[serde(default)]
+    pub pageindex_answer_metrics: Option<AnswerMetrics>,
+    /// Mean Vector RAG answer EM/F1 over items with a gold answer.
+    #[serde(default)]
+    pub vector_answer_metrics: Option<AnswerMetrics>,
+    /// PageIndex multiple-choice accuracy (multiple-choice mode).
+    #[serde(default)]
+    pub pageindex_accuracy: Option<f64>,
+    /// Vector RAG multiple-choice accuracy (multiple-choice mode).
+    #[serde(default)]
+    pub vector_accuracy: Option<f64>,
+    /// PageIndex pointwise answer grades (when grading answers).
+    #[serde(default)]
+    pub pageindex_grades: Option<GradeSummary>,
+    /// Vector RAG pointwise answer grades (when grading answers).
+    #[serde(default)]
+    pub vector_grades: Option<GradeSummary>,
+    /// PageIndex groundedness checks (when checking groundedness).
+    #[serde(default)]
+    pub pageindex_groundedness: Option<GroundednessSummary>,
+    /// Vector RAG groundedness checks (when checking groundedness).
+    #[serde(default)]
+    pub vector_groundedness: Option<GroundednessSummary>,
+    /// Confidence intervals and significance tests for the judge comparison.
+    #[serde(default)]
+    pub significance: Option<Significance>,
+    /// Fraction of comparisons where both judge orders agreed (order-swapped
+    /// judging only).
+    #[serde(default)]
+    pub judge_order_consistency: Option<f64>,
+    /// Inter-judge agreement (ensemble judging only).
+    #[serde(default)]
+    pub judge_agreement: Option<JudgeAgreement>,
+    /// Summaries for additional baselines (e.g. BM25).
+    #[serde(default)]
+    pub baselines: Vec<BaselineSummary>,
+    /// Results per tag value, sorted by tag then value.
+    #[serde(default)]
+    pub tag_breakdowns: Vec<TagBreakdown>,
+    /// Systems ranked by Bradley–Terry rating over all judged pairs (when
+    /// three or more systems were judged).
+    #[serde(default)]
+    pub leaderboard: Vec<LeaderboardEntry>,
     /// Individual item results.
     pub item_results: Vec<ItemResult>,
     /// Total benchmark time (seconds).
     pub total_time_secs: f64,
+    /// LLM usage across indexing, search, answering, and judging.
+    #[serde(default)]
+    pub usage: UsageStats,
+    /// Pricing used for cost estimates.
+    #[serde(default)]
+    pub pricing: Option<ModelPricing>,
+    /// Per-system usage and cost (PageIndex, Vector, then other baselines).
+    #[serde(default)]
+    pub costs: Vec<SystemCost>,
+    /// LLM usage judging answers.
+    #[serde(default)]
+    pub judge_usage: UsageStats,
+    /// Judge verdicts reused from the judge cache.
+    #[serde(default)]
+    pub judge_cache_hits: usize,
 }
 
 impl BenchmarkResults {
@@ -122,8 +684,34 @@ impl BenchmarkResults {
             avg_vector_score: 0.0,
             avg_pageindex_time_ms: 0.0,
             avg_vector_time_ms: 0.0,
+            pageindex_latency: None,
+            pageindex_search_latency: None,
+            vector_latency: None,
+            pageindex_build_time_ms: 0.0,
+            amortized_pageindex_build_ms: 0.0,
+            pageindex_retrieval: None,
+            vector_retrieval: None,
+            pageindex_answer_metrics: None,
+            vector_answer_metrics: None,
+            pageindex_accuracy: None,
+            vector_accuracy: None,
+            pageindex_grades: None,
+            vector_grades: None,
+            pageindex_groundedness: None,
+            vector_groundedness: None,
+            significance: None,
+            judge_order_consistency: None,
+            judge_agreement: None,
+            baselines: Vec::new(),
+            tag_breakdowns: Vec::new(),
+            leaderboard: Vec::new(),
             item_results: Vec::new(),
             total_time_secs: 0.0,
+            usage: UsageStats::default(),
+            pricing: None,
+            costs: Vec::new(),
+            judge_usage: UsageStats::default(),
+            judge_cache_hits: 0,
         }
     }
 
@@ -135,10 +723,13 @@ impl BenchmarkResults {
 
         self.total_items = self.item_results.len();
 
+        let mut winners = Vec::new();
         let mut pageindex_scores = Vec::new();
         let mut vector_scores = Vec::new();
         let mut pageindex_times = Vec::new();
+        let mut pageindex_search_times = Vec::new();
         let mut vector_times = Vec::new();
+        let mut build_time_ms = 0;
 
         for result in &self.item_results {
             if let Some(ref comparison) = result.comparison {
@@ -147,18 +738,41 @@ impl BenchmarkResults {
                     2 => self.vector_wins += 1,
                     _ => self.ties += 1,
                 }
+                winners.push(comparison.winner);
                 pageindex_scores.push(comparison.score_system1 as f64);
                 vector_scores.push(comparison.score_system2 as f64);
             }
 
             if let Some(time) = result.pageindex_time_ms {
+                let build = result.pageindex_build_time_ms.unwrap_or(0);
+                build_time_ms += build;
                 pageindex_times.push(time as f64);
+                pageindex_search_times.push(time.saturating_sub(build) as f64);
             }
             if let Some(time) = result.vector_time_ms {
                 vector_times.push(time as f64);
             }
         }
 
+        let paired_scores: Vec<(f64, f64)> = pageindex_scores
+            .iter()
+            .copied()
+            .zip(vector_scores.iter().copied())
+            .collect();
+        self.significance = Significance::compute(&winners, &paired_scores);
+        self.judge_order_consistency = accuracy(
+            self.item_results
+                .iter()
+                .map(|r| r.comparison.as_ref().and_then(|c| c.order_consistent)),
+        );
+        let ensemble_verdicts: Vec<Vec<u8>> = self
+            .item_results
+            .iter()
+            .filter_map(|r| r.comparison.as_ref())
+            .map(|c| c.judges.iter().map(|j| j.winner).collect())
+            .collect();
+        self.judge_agreement = JudgeAgreement::compute(&ensemble_verdicts);
+
         if !pageindex_scores.is_empty() {
             self.avg_pageindex_score =
                 pageindex_scores.iter().sum::<f64>() / pageindex_scores.len() as f64;
@@ -173,6 +787,141 @@ impl BenchmarkResults {
         if !vector_times.is_empty() {
             self.avg_vector_time_ms = vector_times.iter().sum::<f64>() / vector_times.len() as f64;
         }
+        self.pageindex_latency = LatencyStats::compute(&pageindex_times);
+        self.pageindex_search_latency = LatencyStats::compute(&pageindex_search_times);
+        self.vector_latency = LatencyStats::compute(&vector_times);
+        self.pageindex_build_time_ms = build_time_ms as f64;
+        self.amortized_pageindex_build_ms = if pageindex_times.is_empty() {
+            0.0
+        } else {
+            build_time_ms as f64 / pageindex_times.len() as f64
+        };
+
+        let pageindex_retrieval: Vec<_> = self
+            .item_results
+            .iter()
+            .filter_map(|r| r.pageindex_retrieval)
+            .collect();
+        let vector_retrieval: Vec<_> = self
+            .item_results
+            .iter()
+            .filter_map(|r| r.vector_retrieval)
+            .collect();
+        self.pageindex_retrieval = RetrievalMetrics::mean(&pageindex_retrieval);
+        self.vector_retrieval = RetrievalMetrics::mean(&vector_retrieval);
+
+        let pageindex_answers: Vec<_> = self
+            .item_results
+            .iter()
+            .filter_map(|r| r.pageindex_answer_metrics)
+            .collect();
+        let vector_answers: Vec<_> = self
+            .item_results
+            .iter()
+            .filter_map(|r| r.vector_answer_metrics)
+            .collect();
+        self.pageindex_answer_metrics = AnswerMetrics::mean(&pageindex_answers);
+        self.vector_answer_metrics = AnswerMetrics::mean(&vector_answers);
+
+        self.pageindex_accuracy = accuracy(self.item_results.iter().map(|r| r.pageindex_correct));
+        self.vector_accuracy = accuracy(self.item_results.iter().map(|r| r.vector_correct));
+        let grades = |grade: fn(&ItemResult) -> Option<&AnswerGrade>| {
+            GradeSummary::compute(
+                &self
+                    .item_results
+                    .iter()
+                    .filter_map(grade)
+                    .collect::<Vec<_>>(),
+            )
+        };
+        self.pageindex_grades = grades(|r| r.pageindex_grade.as_ref());
+        self.vector_grades = grades(|r| r.vector_grade.as_ref());
+        self.pageindex_groundedness = GroundednessSummary::compute(
+            &self
+                .item_results
+                .iter()
+                .filter_map(|r| {
+                    let correct = r.pageindex_grade.as_ref().map(|g| g.correct);
+                    Some((
+                        r.pageindex_groundedness.as_ref()?,
+                        correct.or(r.pageindex_correct),
+                    ))
+                })
+                .collect::<Vec<_>>(),
+        );
+        self.vector_groundedness = GroundednessSummary::compute(
+            &self
+                .item_results
+                .iter()
+                .filter_map(|r| {
+                    let correct = r.vector_grade.as_ref().map(|g| g.correct);
+                    Some((
+                        r.vector_groundedness.as_ref()?,
+                        correct.or(r.vector_correct),
+                    ))
+                })
+                .collect::<Vec<_>>(),
+        );
+
+        let mut systems: Vec<&str> = Vec::new();
+        for baseline in self.item_results.iter().flat_map(|r| &r.baselines) {
+            if !systems.contains(&baseline.system.as_str()) {
+                systems.push(&baseline.system);
+            }
+        }
+        self.baselines = systems
+            .iter()
+            .map(|system| {
+                let results: Vec<&BaselineResult> = self
+                    .item_results
+                    .iter()
+                    .flat_map(|r| &r.baselines)
+                    .filter(|b| b.system == *system)
+                    .collect();
+                BaselineSummary::compute(system, &results)
+            })
+            .collect();
+
+        let usages = |usage: fn(&ItemResult) -> Option<ItemUsage>| -> Vec<ItemUsage> {
+            self.item_results.iter().filter_map(usage).collect()
+        };
+        let mut costs = vec![
+            ("PageIndex", usages(|r| r.pageindex_usage)),
+            ("Vector", usages(|r| r.vector_usage)),
+        ];
+        for system in &systems {
+            let baseline_usages = self
+                .item_results
+                .iter()
+                .flat_map(|r| &r.baselines)
+                .filter(|b| b.system == *system)
+                .filter_map(|b| b.usage)
+                .collect();
+            costs.push((system, baseline_usages));
+        }
+        self.costs = costs
+            .iter()
+            .filter_map(|(system, usages)| {
+                SystemCost::compute(system, usages, self.pricing.as_ref())
+            })
+            .collect();
+        self.judge_usage = self
+            .item_results
+            .iter()
+            .fold(UsageStats::default(), |total, r| total + r.judge_usage);
+
+        let mut by_tag: BTreeMap<(&str, &str), Vec<&ItemResult>> = BTreeMap::new();
+        for result in &self.item_results {
+            for (tag, value) in &result.tags {
+                by_tag.entry((tag, value)).or_default().push(result);
+            }
+        }
+        self.tag_breakdowns = by_tag
+            .iter()
+            .map(|((tag, value), results)| TagBreakdown::compute(tag, value, results))
+            .collect();
+
+        self.leaderboard = leaderboard(&self.item_results);
     }
 
     /// Print summary to stdout.
@@ -211,21 +960,426 @@ impl BenchmarkResults {
         println!("----------------------------------------");
         println!("Avg PageIndex score: {:.2}/5", self.avg_pageindex_score);
         println!("Avg Vector score:    {:.2}/5", self.avg_vector_score);
+        if let Some(sig) = &self.significance {
+            let verdict = |significant: bool| {
+                if significant {
+                    "significant"
+                } else {
+                    "not significant"
+                }
+            };
+            println!(
+                "PageIndex win rate (excl. ties): {:.1}% [95% CI {:.1}-{:.1}%], p={:.3} ({})",
+                sig.win_rate.estimate * 100.0,
+                sig.win_rate.lower * 100.0,
+                sig.win_rate.upper * 100.0,
+                sig.win_p_value,
+                verdict(sig.wins_significant())
+            );
+            println!(
+                "Score difference: {:+.2} [95% CI {:+.2} to {:+.2}], p={:.3} ({})",
+                sig.score_difference.estimate,
+                sig.score_difference.lower,
+                sig.score_difference.upper,
+                sig.score_p_value,
+                verdict(sig.scores_significant())
+            );
+        }
+        if let Some(consistency) = self.judge_order_consistency {
+            println!(
+                "Judge order consistency: {:.1}% (disagreements counted as ties)",
+                consistency * 100.0
+            );
+        }
+        if let Some(agreement) = &self.judge_agreement {
+            println!(
+                "Judge agreement ({} judges): {:.1}% with majority, Fleiss' kappa {}",
+                agreement.judges,
+                agreement.mean_agreement * 100.0,
+                agreement
+                    .fleiss_kappa
+                    .map_or("n/a".to_string(), |k| format!("{:.3}", k))
+            );
+        }
         println!("----------------------------------------");
         println!("Avg PageIndex time: {:.0}ms", self.avg_pageindex_time_ms);
         println!("Avg Vector time:    {:.0}ms", self.avg_vector_time_ms);
+        for (name, latency) in [
+            ("PageIndex", &self.pageindex_latency),
+            ("Vector   ", &self.vector_latency),
+        ] {
+            if let Some(l) = latency {
+                println!(
+                    "{} p50/p95/p99: {:.0}/{:.0}/{:.0}ms (max {:.0}ms)",
+                    name, l.p50_ms, l.p95_ms, l.p99_ms, l.max_ms
+                );
+            }
+        }
+        if let Some(l) = &self.pageindex_search_latency {
+            println!(
+                "PageIndex tree build: {:.0}ms total, {:.0}ms/query amortized; search p50/p95/p99: {:.0}/{:.0}/{:.0}ms",
+                self.pageindex_build_time_ms,
+                self.amortized_pageindex_build_ms,
+                l.p50_ms,
+                l.p95_ms,
+                l.p99_ms
+            );
+        }
         println!("----------------------------------------");
+        for (name, metrics) in [
+            ("PageIndex", &self.pageindex_retrieval),
+            ("Vector   ", &self.vector_retrieval),
+        ] {
+            if let Some(m) = metrics {
+                println!(
+                    "{} recall@k: {:.3}  MRR: {:.3}  nDCG@k: {:.3}",
+                    name, m.recall_at_k, m.mrr, m.ndcg_at_k
+                );
+            }
+        }
+        if self.pageindex_retrieval.is_some() || self.vector_retrieval.is_some() {
+            println!("----------------------------------------");
+        }
+        for (name, metrics) in [
+            ("PageIndex", &self.pageindex_answer_metrics),
+            ("Vector   ", &self.vector_answer_metrics),
+        ] {
+            if let Some(m) = metrics {
+                println!("{} EM: {:.3}  F1: {:.3}", name, m.exact_match, m.f1);
+            }
+        }
+        if self.pageindex_answer_metrics.is_some() || self.vector_answer_metrics.is_some() {
+            println!("----------------------------------------");
+        }
+        if let Some(acc) = self.pageindex_accuracy {
+            println!("PageIndex accuracy: {:.1}%", acc * 100.0);
+        }
+        if let Some(acc) = self.vector_accuracy {
+            println!("Vector accuracy:    {:.1}%", acc * 100.0);
+        }
+        if self.pageindex_accuracy.is_some() || self.vector_accuracy.is_some() {
+            println!("----------------------------------------");
+        }
+        for (name, grades) in [
+            ("PageIndex", &self.pageindex_grades),
+            ("Vector   ", &self.vector_grades),
+        ] {
+            if let Some(g) = grades {
+                println!(
+                    "{} graded: {:.2}/5, {:.1}% correct ({} answers)",
+                    name,
+                    g.avg_score,
+                    g.correct_rate * 100.0,
+                    g.graded
+                );
+            }
+        }
+        if self.pageindex_grades.is_some() || self.vector_grades.is_some() {
+            println!("----------------------------------------");
+        }
+        for (name, groundedness) in [
+            ("PageIndex", &self.pageindex_groundedness),
+            ("Vector   ", &self.vector_groundedness),
+        ] {
+            if let Some(g) = groundedness {
+                println!(
+                    "{} grounded: {:.1}% ({:.2}/5), {} correct but unsupported ({} answers)",
+                    name,
+                    g.grounded_rate * 100.0,
+                    g.avg_score,
+                    g.correct_ungrounded,
+                    g.checked
+                );
+            }
+        }
+        if self.pageindex_groundedness.is_some() || self.vector_groundedness.is_some() {
+            println!("----------------------------------------");
+        }
+        for baseline in &self.baselines {
+            println!(
+                "PageIndex vs {}: {} wins, {} losses, {} ties (avg {:.2} vs {:.2}/5)",
+                baseline.system,
+                baseline.pageindex_wins,
+                baseline.baseline_wins,
+                baseline.ties,
+                baseline.avg_pageindex_score,
+                baseline.avg_baseline_score
+            );
+            if let Some(sig) = &baseline.significance {
+                println!(
+                    "  Score difference: {:+.2} [95% CI {:+.2} to {:+.2}], p={:.3}",
+                    sig.score_difference.estimate,
+                    sig.score_difference.lower,
+                    sig.score_difference.upper,
+                    sig.score_p_value
+                );
+            }
+            println!(
+                "  Avg {} time: {:.0}ms",
+                baseline.system, baseline.avg_time_ms
+            );
+            if let Some(l) = &baseline.latency {
+                println!(
+                    "  p50/p95/p99: {:.0}/{:.0}/{:.0}ms",
+                    l.p50_ms, l.p95_ms, l.p99_ms
+                );
+            }
+            if let Some(m) = &baseline.retrieval {
+                println!(
+                    "  recall@k: {:.3}  MRR: {:.3}  nDCG@k: {:.3}",
+                    m.recall_at_k, m.mrr, m.ndcg_at_k
+                );
+            }
+            if let Some(m) = &baseline.answer_metrics {
+                println!("  EM: {:.3}  F1: {:.3}", m.exact_match, m.f1);
+            }
+            if let Some(acc) = baseline.accuracy {
+                println!("  Accuracy: {:.1}%", acc * 100.0);
+            }
+            if let Some(g) = &baseline.grades {
+                println!(
+                    "  Graded: {:.2}/5, {:.1}% correct",
+                    g.avg_score,
+                    g.correct_rate * 100.0
+                );
+            }
+            if let Some(g) = &baseline.groundedness {
+                println!(
+                    "  Grounded: {:.1}% ({:.2}/5), {} correct but unsupported",
+                    g.grounded_rate * 100.0,
+                    g.avg_score,
+                    g.correct_ungrounded
+                );
+            }
+            println!("----------------------------------------");
+        }
+        if !self.leaderboard.is_empty() {
+            println!("Leaderboard (Bradley–Terry):");
+            for (rank, entry) in self.leaderboard.iter().enumerate() {
+                println!(
+                    "  {}. {}: {:.0} ({}W/{}L/{}T)",
+                    rank + 1,
+                    entry.system,
+                    entry.rating,
+                    entry.wins,
+                    entry.losses,
+                    entry.ties
+                );
+            }
+            println!("----------------------------------------");
+        }
+        for b in &self.tag_breakdowns {
+            println!(
+                "{}={} ({} items): PageIndex {} / Vector {} / ties {}, avg {:.2} vs {:.2}/5",
+                b.tag,
+                b.value,
+                b.items,
+                b.pageindex_wins,
+                b.vector_wins,
+                b.ties,
+                b.avg_pageindex_score,
+                b.avg_vector_score
+            );
+            if let (Some(pi), Some(vec)) = (b.pageindex_accuracy, b.vector_accuracy) {
+                println!("  Accuracy: {:.1}% vs {:.1}%", pi * 100.0, vec * 100.0);
+            }
+        }
+        if !self.tag_breakdowns.is_empty() {
+            println!("----------------------------------------");
+        }
+        for cost in &self.costs {
+            let usd = |cost: Option<f64>| cost.map_or("n/a".to_string(), |c| format!("${:.4}", c));
+            println!(
+                "{} cost: {}/query (indexing {} total, {}/query to search and answer)",
+                cost.system,
+                usd(cost.cost_per_query_usd),
+                usd(cost.index_cost_usd),
+                usd(cost.query_cost_usd)
+            );
+            println!(
+                "  Tokens: {} indexing, {} querying, {} embedding",
+                cost.index_usage.total_tokens, cost.query_usage.total_tokens, cost.embedding_tokens
+            );
+        }
+        if !self.costs.is_empty() {
+            println!(
+                "Judge: {} tokens{}",
+                self.judge_usage.total_tokens,
+                self.pricing.map_or(String::new(), |p| format!(
+                    " (${:.4})",
+                    p.cost(
+                        self.judge_usage.prompt_tokens,
+                        self.judge_usage.completion_tokens
+                    )
+                ))
+            );
+            println!("----------------------------------------");
+        }
+        if self.judge_cache_hits > 0 {
+            println!(
+                "Judge verdicts reused from cache: {}",
+                self.judge_cache_hits
+            );
+        }
         println!("Total time: {:.1}s", self.total_time_secs);
         println!("========================================\n");
     }
 }
 
-/// Compute a simple hash for a document to use as cache key.
-fn document_hash(doc: &str) -> u64 {
-    use std::collections::hash_map::DefaultHasher;
-    let mut hasher = DefaultHasher::new();
-    doc.hash(&mut hasher);
-    hasher.finish()
+/// Bradley–Terry leaderboard over every judged pair, or empty unless at
+/// least three systems were judged.
+fn leaderboard(results: &[ItemResult]) -> Vec<LeaderboardEntry> {
+    let mut systems: Vec<&str> = Vec::new();
+    let mut matches = Vec::new();
+    for (system1, system2, comparison) in results.iter().flat_map(ItemResult::pairwise) {
+        let mut index = |name| match systems.iter().position(|s| *s == name) {
+            Some(i) => i,
+            None => {
+                systems.push(name);
+                systems.len() - 1
+            }
+        };
+        let (a, b) = (index(system1), index(system2));
+        matches.push((a, b, comparison.winner));
+    }
+    if systems.len() < 3 {
+        return Vec::new();
+    }
+    LeaderboardEntry::rank(&systems, &matches)
+}
+
+/// Fraction of graded items that were correct (`None` if none were graded).
+fn accuracy(graded: impl Iterator<Item = Option<bool>>) -> Option<f64> {
+    let graded: Vec<bool> = graded.flatten().collect();
+    if graded.is_empty() {
+        return None;
+    }
+    Some(graded.iter().filter(|c| **c).count() as f64 / graded.len() as f64)
+}
+
+/// Letter label for an option index (0 -> 'A').
+fn option_letter(index: usize) -> char {
+    (b'A' + index as u8) as char
+}
+
+/// Parse the option an answerer picked from its reply ("B", "(b)", "Answer: B. ...").
+///
+/// Falls back to matching an option's full text. Returns `None` if no option
+/// can be identified.
+fn parse_choice(response: &str, options: &[String]) -> Option<usize> {
+    let response = response.trim();
+    let response = response
+        .strip_prefix("Answer:")
+        .or_else(|| response.strip_prefix("answer:"))
+        .unwrap_or(response)
+        .trim_start();
+
+    // A leading letter, optionally wrapped in brackets or followed by punctuation
+    let letter = response
+        .trim_start_matches(['(', '['])
+        .chars()
+        .next()
+        .map(|c| c.to_ascii_uppercase());
+    let next = response.trim_start_matches(['(', '[']).chars().nth(1);
+    if let Some(letter) = letter
+        && letter.is_ascii_uppercase()
+        && next.is_none_or(|c| !c.is_alphanumeric())
+    {
+        let index = (letter as u8 - b'A') as usize;
+        if index < options.len() {
+            return Some(index);
+        }
+    }
+
+    let lower = response.to_lowercase();
+    options
+        .iter()
+        .position(|o| !o.trim().is_empty() && lower.contains(&o.trim().to_lowercase()))
+}
+
+/// Load item results from a JSONL results log.
+///
+/// When an item appears more than once (a retried error), the last entry wins.
+/// A malformed final line, as left by a crash mid-write, is skipped.
+pub fn load_results_log(path: &Path) -> Result<Vec<ItemResult>> {
+    let content = std::fs::read_to_string(path)?;
+    let lines: Vec<&str> = content.lines().filter(|l| !l.trim().is_empty()).collect();
+
+    let mut results: Vec<ItemResult> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let result: ItemResult = match serde_json::from_str(line) {
+            Ok(result) => result,
+            Err(e) if i + 1 == lines.len() => {
+                warn!("Skipping truncated last line of {:?}: {}", path, e);
+                break;
+            }
+            Err(e) => return Err(anyhow::anyhow!("{:?} line {}: {}", path, i + 1, e)),
+        };
+        match results.iter_mut().find(|r| r.item_id == result.item_id) {
+            Some(existing) => *existing = result,
+            None => results.push(result),
+        }
+    }
+    Ok(results)
+}
+
+/// Append one item result to a JSONL results log.
+fn append_result(path: &Path, result: &ItemResult) -> Result<()> {
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?;
+    writeln!(file, "{}", serde_json::to_string(result)?)?;
+    Ok(())
+}
+
+/// Hash of a document, stable across builds, to use as cache key.
+fn document_hash(doc: &str) -> String {
+    fnv1a_hex(doc.as_bytes())
+}
+
+/// File a tree built for a document is saved to in a tree directory.
+pub fn tree_path(dir: &Path, document: &str) -> PathBuf {
+    dir.join(format!("{}.json", document_hash(document)))
+}
+
+/// Load an item's prebuilt tree from `dir`, matched by document hash first,
+/// then by item id. Unreadable files are skipped with a warning.
+fn load_prebuilt_tree(dir: &Path, item: &DatasetItem) -> Option<DocumentTree> {
+    let hash = document_hash(&item.document);
+    [hash.as_str(), item.id.as_str()]
+        .iter()
+        .flat_map(|stem| ["json", "bin"].map(|ext| dir.join(format!("{}.{}", stem, ext))))
+        .filter(|path| path.exists())
+        .find_map(|path| match load_tree(&path) {
+            Ok(tree) => {
+                debug!(item = %item.id, "Loaded prebuilt tree from {:?}", path);
+                Some(tree)
+            }
+            Err(e) => {
+                warn!("Ignoring unreadable tree {:?}: {}", path, e);
+                None
+            }
+        })
+}
+
+/// Embedding API tokens used so far by a model (zero for local models).
+fn embedding_tokens(model: &EmbeddingModel) -> u64 {
+    model.usage().map_or(0, |usage| usage.total_tokens)
+}
+
+/// Embedding tokens spent building an item's vector index.
+fn index_embedding_tokens(index: &Option<Result<(VectorIndex, Duration, u64), String>>) -> u64 {
+    match index {
+        Some(Ok((_, _, tokens))) => *tokens,
+        _ => 0,
+    }
+}
+
+/// Tree building done while running PageIndex on an item.
+struct TreeBuild {
+    time: Duration,
+    usage: UsageStats,
 }
 
 /// Cache entry for a document tree with the original document.
@@ -239,35 +1393,122 @@ pub struct Benchmark {
     config: BenchmarkConfig,
     llm_config: LlmConfig,
     /// Cache of document trees keyed by document hash.
-    tree_cache: Arc<RwLock<HashMap<u64, CachedTree>>>,
+    tree_cache: Arc<RwLock<HashMap<String, CachedTree>>>,
+    /// Optional progress callback (one event per completed item).
+    progress: Option<ProgressCallback>,
+    /// Additional systems judged against PageIndex (BM25 if enabled, then
+    /// any registered with [`Benchmark::with_system`]).
+    systems: Vec<Box<dyn RetrievalSystem>>,
+    /// Cross-encoder set with [`Benchmark::with_reranker`], used instead of
+    /// loading `reranker_model`.
+    reranker: Option<Reranker>,
 }
 
 impl Benchmark {
     /// Create a new benchmark runner.
     pub fn new(llm_config: LlmConfig, config: BenchmarkConfig) -> Self {
+        let mut systems: Vec<Box<dyn RetrievalSystem>> = Vec::new();
+        if config.run_bm25 {
+            systems.push(Box::new(Bm25System::new(
+                config.chunk_config.clone(),
+                config.top_k,
+            )));
+        }
         Self {
             config,
             llm_config,
             tree_cache: Arc::new(RwLock::new(HashMap::new())),
+            progress: None,
+            systems,
+            reranker: None,
         }
     }
 
+    /// Register another retrieval system to judge against PageIndex.
+    ///
+    /// Its results appear among the baselines, in registration order. The
+    /// built-in Vector RAG leg stays PageIndex's headline opponent, with its
+    /// own `vector_*` fields; register a [`VectorSystem`](super::VectorSystem) to judge another
+    /// embedding model or chunking alongside it.
+    pub fn with_system(mut self, system: impl RetrievalSystem + 'static) -> Self {
+        self.systems.push(Box::new(system));
+        self
+    }
+
+    /// Rerank with this model instead of loading the configured
+    /// cross-encoder (e.g. a hosted rerank API wrapped with
+    /// [`Reranker::from_fn`]).
+    pub fn with_reranker(mut self, reranker: Reranker) -> Self {
+        self.reranker = Some(reranker);
+        self
+    }
+
+    /// Report item completion events to a callback.
+    pub fn with_progress(mut self, callback: ProgressCallback) -> Self {
+        self.progress = Some(callback);
+        self
+    }
+
     /// Run the benchmark on a dataset.
     pub async fn run(&self, dataset: &Dataset) -> Result<BenchmarkResults> {
         let start_time = Instant::now();
         let mut results = BenchmarkResults::new(&dataset.name);
 
         // Load embedding model for vector search
-        let embedding_model = if self.config.run_vector {
-            println!("Loading embedding model...");
-            Some(EmbeddingModel::load_minilm()?)
+        let embedding_model = if self.uses_vector_index() {
+            Some(self.load_embedding_model()?)
+        } else {
+            None
+        };
+
+        // Load the cross-encoder for reranking
+        let loaded_reranker = if self.reranker.is_none()
+            && (self.config.run_reranked || self.config.rerank_pageindex)
+        {
+            Some(self.load_reranker()?)
         } else {
             None
         };
+        let reranker = self.reranker.as_ref().or(loaded_reranker.as_ref());
 
         // Create LLM client and judge
         let llm_client = LlmClient::new(self.llm_config.clone());
-        let judge = LlmJudge::new(llm_client.clone());
+        let judge_clients: Vec<LlmClient> = self
+            .config
+            .judge_models
+            .iter()
+            .map(|model| {
+                LlmClient::new(LlmConfig {
+                    model: model.clone(),
+                    ..self.llm_config.clone()
+                })
+            })
+            .collect();
+        let judge = match judge_clients.split_first() {
+            Some((first, rest)) => rest
+                .iter()
+                .fold(LlmJudge::new(first.clone()), |judge, client| {
+                    judge.with_judge(client.clone())
+                }),
+            None => LlmJudge::new(llm_client.clone()),
+        }
+        .with_position_swap(self.config.swap_judge_order);
+        let judge = match &self.config.rubric {
+            Some(rubric) => judge.with_rubric(rubric.clone()),
+            None => judge,
+        };
+        let judge = match &self.config.judge_cache {
+            Some(path) => {
+                let cache = JudgeCache::open(path)?;
+                info!(
+                    "Loaded {} cached judge verdicts from {:?}",
+                    cache.len(),
+                    path
+                );
+                judge.with_cache(cache)
+            }
+            None => judge,
+        };
         let indexer = TreeIndexer::new(llm_client.clone());
         let searcher = TreeSearcher::new(llm_client.clone());
 
@@ -278,16 +1519,47 @@ impl Benchmark {
             dataset.items.iter().collect()
         };
 
-        println!("Running benchmark on {} items...", items.len());
+        info!("Running benchmark on {} items", items.len());
+
+        // Results from an interrupted run, keyed by item ID
+        let mut previous: HashMap<String, ItemResult> = HashMap::new();
+        if let Some(path) = &self.config.resume_from {
+            if path.exists() {
+                for result in load_results_log(path)? {
+                    if result.error.is_none() {
+                        previous.insert(result.item_id.clone(), result);
+                    }
+                }
+                info!("Resuming: {} items already completed", previous.len());
+            } else {
+                warn!("No results log at {:?}; starting from scratch", path);
+            }
+        }
+        let results_log = self
+            .config
+            .results_log
+            .as_ref()
+            .or(self.config.resume_from.as_ref());
 
         for (idx, item) in items.iter().enumerate() {
+            if let Some(done) = previous.remove(&item.id) {
+                debug!(item = %item.id, "Skipping item completed in previous run");
+                results.item_results.push(done);
+                self.emit_item_completed(idx + 1, items.len(), item, &results.item_results);
+                continue;
+            }
+
             if self.config.verbose {
                 println!("\n[{}/{}] Processing: {}", idx + 1, items.len(), item.id);
-            } else {
-                print!(".");
-                use std::io::Write;
-                std::io::stdout().flush().ok();
             }
+            emit(
+                &self.progress,
+                ProgressEvent::ItemStarted {
+                    completed: idx,
+                    total: items.len(),
+                    item_id: item.id.clone(),
+                },
+            );
 
             let item_result = self
                 .process_item(
@@ -297,41 +1569,159 @@ impl Benchmark {
                     &judge,
                     &llm_client,
                     embedding_model.as_ref(),
+                    reranker,
                 )
                 .await;
 
+            if let Some(path) = results_log
+                && let Err(e) = append_result(path, &item_result)
+            {
+                warn!(item = %item.id, "Failed to write results log: {}", e);
+            }
             results.item_results.push(item_result);
-        }
-
-        if !self.config.verbose {
-            println!(); // Newline after dots
+            self.emit_item_completed(idx + 1, items.len(), item, &results.item_results);
         }
 
         results.total_time_secs = start_time.elapsed().as_secs_f64();
+        results.usage = judge_clients
+            .iter()
+            .fold(llm_client.usage(), |usage, client| usage + client.usage());
+        if let Some(usage) = embedding_model.as_ref().and_then(EmbeddingModel::usage) {
+            results.usage = results.usage + usage;
+        }
+        results.pricing = self.llm_config.pricing;
+        results.judge_cache_hits = judge.cache().map_or(0, JudgeCache::hits);
         results.calculate_summary();
 
         Ok(results)
     }
 
-    /// Process a single dataset item.
-    async fn process_item(
+    /// Report a finished item and the judge verdicts so far.
+    fn emit_item_completed(
         &self,
+        completed: usize,
+        total: usize,
         item: &DatasetItem,
-        indexer: &TreeIndexer,
-        searcher: &TreeSearcher,
-        judge: &LlmJudge,
-        llm_client: &LlmClient,
-        embedding_model: Option<&EmbeddingModel>,
-    ) -> ItemResult {
-        let mut result = ItemResult {
-            item_id: item.id.clone(),
-            pageindex_content: None,
-            pageindex_answer: None,
-            pageindex_time_ms: None,
-            vector_content: None,
-            vector_answer: None,
-            vector_time_ms: None,
-            comparison: None,
+        results: &[ItemResult],
+    ) {
+        if self.progress.is_none() {
+            return;
+        }
+        let count = |winner: u8| {
+            results
+                .iter()
+                .filter(|r| r.comparison.as_ref().is_some_and(|c| c.winner == winner))
+                .count()
+        };
+        emit(
+            &self.progress,
+            ProgressEvent::WinsTally {
+                pageindex_wins: count(1),
+                vector_wins: count(2),
+                ties: count(0),
+            },
+        );
+        emit(
+            &self.progress,
+            ProgressEvent::ItemCompleted {
+                completed,
+                total,
+                item_id: item.id.clone(),
+            },
+        );
+    }
+
+    /// Whether any system searches the vector index.
+    fn uses_vector_index(&self) -> bool {
+        self.config.run_vector || self.config.run_hybrid || self.config.run_reranked
+    }
+
+    /// Load the configured cross-encoder.
+    #[cfg(feature = "local-embeddings")]
+    fn load_reranker(&self) -> Result<Reranker> {
+        let name = self
+            .config
+            .reranker_model
+            .as_deref()
+            .unwrap_or(DEFAULT_RERANKER_MODEL);
+        info!("Loading reranker {}", name);
+        Ok(Reranker::load_on(name, self.config.embedding_device)?
+            .with_batch_size(self.config.embedding_batch_size))
+    }
+
+    #[cfg(not(feature = "local-embeddings"))]
+    fn load_reranker(&self) -> Result<Reranker> {
+        anyhow::bail!(
+            "Cross-encoder reranking requires the `local-embeddings` feature; \
+             use Benchmark::with_reranker to supply another scorer"
+        )
+    }
+
+    /// Load the configured embedding model for vector search.
+    fn load_embedding_model(&self) -> Result<EmbeddingModel> {
+        let backend = self.config.embedding_backend;
+        let name = self
+            .config
+            .embedding_model
+            .as_deref()
+            .unwrap_or(backend.default_model());
+        info!("Loading embedding model {} ({:?})", name, backend);
+
+        let model = match backend {
+            #[cfg(feature = "local-embeddings")]
+            EmbeddingBackend::Local => EmbeddingModel::load_on(name, self.config.embedding_device)?,
+            #[cfg(not(feature = "local-embeddings"))]
+            EmbeddingBackend::Local => anyhow::bail!(
+                "Local embeddings require the `local-embeddings` feature; use the API backend instead"
+            ),
+            EmbeddingBackend::Api => EmbeddingModel::api(LlmClient::new(LlmConfig {
+                model: name.to_string(),
+                ..self.llm_config.clone()
+            })),
+        };
+        Ok(model.with_batch_size(self.config.embedding_batch_size))
+    }
+
+    /// Process a single dataset item.
+    #[allow(clippy::too_many_arguments)]
+    async fn process_item(
+        &self,
+        item: &DatasetItem,
+        indexer: &TreeIndexer,
+        searcher: &TreeSearcher,
+        judge: &LlmJudge,
+        llm_client: &LlmClient,
+        embedding_model: Option<&EmbeddingModel>,
+        reranker: Option<&Reranker>,
+    ) -> ItemResult {
+        let mut result = ItemResult {
+            item_id: item.id.clone(),
+            pageindex_content: None,
+            pageindex_answer: None,
+            pageindex_time_ms: None,
+            pageindex_build_time_ms: None,
+            vector_content: None,
+            vector_answer: None,
+            vector_time_ms: None,
+            pageindex_retrieval: None,
+            vector_retrieval: None,
+            pageindex_answer_metrics: None,
+            vector_answer_metrics: None,
+            pageindex_choice: None,
+            vector_choice: None,
+            pageindex_correct: None,
+            vector_correct: None,
+            pageindex_grade: None,
+            vector_grade: None,
+            pageindex_groundedness: None,
+            vector_groundedness: None,
+            comparison: None,
+            baselines: Vec::new(),
+            matches: Vec::new(),
+            pageindex_usage: None,
+            vector_usage: None,
+            judge_usage: UsageStats::default(),
+            tags: item.tags.clone(),
             error: None,
         };
 
@@ -342,84 +1732,272 @@ impl Benchmark {
 
         // Run PageIndex retrieval
         if self.config.run_pageindex {
-            match self.run_pageindex(item, indexer, searcher).await {
-                Ok((content, duration)) => {
+            let usage_before = llm_client.usage();
+            match self
+                .run_pageindex(
+                    item,
+                    indexer,
+                    searcher,
+                    llm_client,
+                    reranker.filter(|_| self.config.rerank_pageindex),
+                )
+                .await
+            {
+                Ok((content, passages, duration, build)) => {
                     result.pageindex_content = Some(content.clone());
                     result.pageindex_time_ms = Some(duration.as_millis() as u64);
+                    result.pageindex_build_time_ms =
+                        build.as_ref().map(|b| b.time.as_millis() as u64);
+                    let index_usage = build.map_or(UsageStats::default(), |b| b.usage);
+                    result.pageindex_retrieval =
+                        retrieval_metrics(&passages, &item.evidence, self.config.top_k);
 
                     // Generate answer from retrieved content
-                    match self
-                        .generate_answer(llm_client, &item.question, &content)
-                        .await
-                    {
-                        Ok(answer) => {
+                    match self.generate_answer(llm_client, item, &content).await {
+                        Ok((answer, choice)) => {
                             if self.config.verbose {
                                 println!("  [PageIndex] Answer: {}", truncate_str(&answer, 500));
                             }
+                            result.pageindex_answer_metrics =
+                                answer_metrics(&answer, &item.gold_answers());
+                            result.pageindex_choice = choice;
+                            result.pageindex_correct = self.grade_choice(item, choice);
+                            result.pageindex_grade = self
+                                .grade_answer(
+                                    judge,
+                                    item,
+                                    "PageIndex",
+                                    &answer,
+                                    &mut result.judge_usage,
+                                )
+                                .await;
+                            result.pageindex_groundedness = self
+                                .check_groundedness(
+                                    judge,
+                                    item,
+                                    "PageIndex",
+                                    &answer,
+                                    &content,
+                                    &mut result.judge_usage,
+                                )
+                                .await;
                             result.pageindex_answer = Some(answer);
                         }
                         Err(e) => {
-                            if self.config.verbose {
-                                eprintln!("  PageIndex answer generation error: {}", e);
-                            }
+                            warn!(item = %item.id, "PageIndex answer generation error: {}", e);
                         }
                     }
+                    result.pageindex_usage = Some(ItemUsage {
+                        index: index_usage,
+                        query: llm_client.usage().since(&usage_before).since(&index_usage),
+                        embedding_tokens: 0,
+                    });
                 }
                 Err(e) => {
                     result.error = Some(format!("PageIndex error: {}", e));
-                    if self.config.verbose {
-                        eprintln!("  PageIndex error: {}", e);
-                    }
+                    warn!(item = %item.id, "PageIndex error: {}", e);
                 }
             }
         }
 
+        // Build the vector index once for vector search, hybrid and reranked
+        // retrieval
+        let vector_index = match embedding_model {
+            Some(model) if self.uses_vector_index() => Some(
+                self.build_vector_index(item, model)
+                    .await
+                    .map_err(|e| e.to_string()),
+            ),
+            _ => None,
+        };
+
         // Run Vector search retrieval
-        if self.config.run_vector {
-            if let Some(model) = embedding_model {
-                match self.run_vector_search(item, model).await {
-                    Ok((content, duration)) => {
-                        result.vector_content = Some(content.clone());
-                        result.vector_time_ms = Some(duration.as_millis() as u64);
-
-                        // Generate answer from retrieved content (standard RAG)
-                        match self
-                            .generate_answer(llm_client, &item.question, &content)
-                            .await
-                        {
-                            Ok(answer) => {
-                                if self.config.verbose {
-                                    println!("  [VectorRAG] Answer: {}", truncate_str(&answer, 500));
-                                }
-                                result.vector_answer = Some(answer);
-                            }
-                            Err(e) => {
-                                if self.config.verbose {
-                                    eprintln!("  Vector RAG answer generation error: {}", e);
-                                }
+        if self.config.run_vector
+            && let (Some(model), Some(index)) = (embedding_model, &vector_index)
+        {
+            let usage_before = (llm_client.usage(), embedding_tokens(model));
+            let retrieved = match index {
+                Ok((index, build_time, _)) => {
+                    self.run_vector_search(item, model, index, *build_time)
+                        .await
+                }
+                Err(e) => Err(anyhow::anyhow!("{}", e)),
+            };
+            match retrieved {
+                Ok((content, passages, duration)) => {
+                    result.vector_content = Some(content.clone());
+                    result.vector_time_ms = Some(duration.as_millis() as u64);
+                    result.vector_retrieval =
+                        retrieval_metrics(&passages, &item.evidence, self.config.top_k);
+
+                    // Generate answer from retrieved content (standard RAG)
+                    match self.generate_answer(llm_client, item, &content).await {
+                        Ok((answer, choice)) => {
+                            if self.config.verbose {
+                                println!("  [VectorRAG] Answer: {}", truncate_str(&answer, 500));
                             }
+                            result.vector_answer_metrics =
+                                answer_metrics(&answer, &item.gold_answers());
+                            result.vector_choice = choice;
+                            result.vector_correct = self.grade_choice(item, choice);
+                            result.vector_grade = self
+                                .grade_answer(
+                                    judge,
+                                    item,
+                                    "VectorRAG",
+                                    &answer,
+                                    &mut result.judge_usage,
+                                )
+                                .await;
+                            result.vector_groundedness = self
+                                .check_groundedness(
+                                    judge,
+                                    item,
+                                    "VectorRAG",
+                                    &answer,
+                                    &content,
+                                    &mut result.judge_usage,
+                                )
+                                .await;
+                            result.vector_answer = Some(answer);
                         }
-                    }
-                    Err(e) => {
-                        let err_msg = format!("Vector search error: {}", e);
-                        if result.error.is_some() {
-                            result.error = Some(format!("{}\n{}", result.error.unwrap(), err_msg));
-                        } else {
-                            result.error = Some(err_msg);
-                        }
-                        if self.config.verbose {
-                            eprintln!("  Vector search error: {}", e);
+                        Err(e) => {
+                            warn!(item = %item.id, "Vector RAG answer generation error: {}", e);
                         }
                     }
+                    result.vector_usage = Some(ItemUsage {
+                        index: UsageStats::default(),
+                        query: llm_client.usage().since(&usage_before.0),
+                        embedding_tokens: index_embedding_tokens(&vector_index)
+                            + embedding_tokens(model)
+                            - usage_before.1,
+                    });
+                }
+                Err(e) => {
+                    result.add_error(format!("Vector search error: {}", e));
+                    warn!(item = %item.id, "Vector search error: {}", e);
+                }
+            }
+        }
+
+        // Run registered retrieval systems (e.g. BM25)
+        for system in &self.systems {
+            let start = Instant::now();
+            match system.retrieve(&item.question, &item.document).await {
+                Ok(retrieval) => {
+                    let retrieved = (retrieval.content, retrieval.passages, start.elapsed());
+                    let baseline = self
+                        .evaluate_baseline(
+                            system.name(),
+                            item,
+                            retrieved,
+                            ItemUsage::default(),
+                            llm_client,
+                            judge,
+                            result.pageindex_answer.as_deref(),
+                            &mut result.judge_usage,
+                        )
+                        .await;
+                    result.baselines.push(baseline);
+                }
+                Err(e) => {
+                    result.add_error(format!("{} error: {}", system.name(), e));
+                    warn!(item = %item.id, "{} error: {}", system.name(), e);
+                }
+            }
+        }
+
+        // Run hybrid BM25 + vector retrieval
+        if self.config.run_hybrid
+            && let (Some(model), Some(index)) = (embedding_model, &vector_index)
+        {
+            let tokens_before = embedding_tokens(model);
+            let retrieved = match index {
+                Ok((index, build_time, _)) => {
+                    self.run_hybrid_search(item, model, index, *build_time)
+                        .await
+                }
+                Err(e) => Err(anyhow::anyhow!("{}", e)),
+            };
+            match retrieved {
+                Ok(retrieved) => {
+                    let usage = ItemUsage {
+                        embedding_tokens: index_embedding_tokens(&vector_index)
+                            + embedding_tokens(model)
+                            - tokens_before,
+                        ..ItemUsage::default()
+                    };
+                    let baseline = self
+                        .evaluate_baseline(
+                            "Hybrid",
+                            item,
+                            retrieved,
+                            usage,
+                            llm_client,
+                            judge,
+                            result.pageindex_answer.as_deref(),
+                            &mut result.judge_usage,
+                        )
+                        .await;
+                    result.baselines.push(baseline);
+                }
+                Err(e) => {
+                    result.add_error(format!("Hybrid search error: {}", e));
+                    warn!(item = %item.id, "Hybrid search error: {}", e);
+                }
+            }
+        }
+
+        // Run vector search reranked by the cross-encoder
+        if self.config.run_reranked
+            && let (Some(model), Some(index), Some(reranker)) =
+                (embedding_model, &vector_index, reranker)
+        {
+            let tokens_before = embedding_tokens(model);
+            let retrieved = match index {
+                Ok((index, build_time, _)) => {
+                    self.run_reranked_search(item, model, index, reranker, *build_time)
+                        .await
+                }
+                Err(e) => Err(anyhow::anyhow!("{}", e)),
+            };
+            match retrieved {
+                Ok(retrieved) => {
+                    let usage = ItemUsage {
+                        embedding_tokens: index_embedding_tokens(&vector_index)
+                            + embedding_tokens(model)
+                            - tokens_before,
+                        ..ItemUsage::default()
+                    };
+                    let baseline = self
+                        .evaluate_baseline(
+                            "VectorRAG+Rerank",
+                            item,
+                            retrieved,
+                            usage,
+                            llm_client,
+                            judge,
+                            result.pageindex_answer.as_deref(),
+                            &mut result.judge_usage,
+                        )
+                        .await;
+                    result.baselines.push(baseline);
+                }
+                Err(e) => {
+                    result.add_error(format!("Reranked search error: {}", e));
+                    warn!(item = %item.id, "Reranked search error: {}", e);
                 }
             }
         }
 
-        // Run comparison if both methods produced ANSWERS (not just content)
+        // Run comparison if both methods produced ANSWERS (not just content).
+        // Multiple-choice items are graded by accuracy instead.
         if let (Some(pi_answer), Some(vec_answer)) =
             (&result.pageindex_answer, &result.vector_answer)
+            && !self.is_multiple_choice(item)
         {
-            match judge
+            let judge_before = judge.usage();
+            let judged = judge
                 .compare_answers(
                     &item.question,
                     "PageIndex",
@@ -428,8 +2006,9 @@ impl Benchmark {
                     vec_answer,
                     item.answer.as_deref(),
                 )
-                .await
-            {
+                .await;
+            result.judge_usage = result.judge_usage + judge.usage().since(&judge_before);
+            match judged {
                 Ok(comparison) => {
                     if self.config.verbose {
                         let winner_str = match comparison.winner {
@@ -446,9 +2025,170 @@ impl Benchmark {
                     result.comparison = Some(comparison);
                 }
                 Err(e) => {
+                    warn!(item = %item.id, "Judge error: {}", e);
+                }
+            }
+        }
+
+        if self.config.tournament && !self.is_multiple_choice(item) {
+            self.run_tournament(item, judge, &mut result).await;
+        }
+
+        result
+    }
+
+    /// Judge every pair of answers not already judged against PageIndex:
+    /// Vector vs. each baseline and the baselines against each other.
+    async fn run_tournament(&self, item: &DatasetItem, judge: &LlmJudge, result: &mut ItemResult) {
+        let mut answers: Vec<(&str, &str)> = Vec::new();
+        if let Some(answer) = &result.vector_answer {
+            answers.push(("Vector", answer));
+        }
+        for baseline in &result.baselines {
+            if let Some(answer) = &baseline.answer {
+                answers.push((&baseline.system, answer));
+            }
+        }
+
+        let mut matches = Vec::new();
+        for (i, (system1, answer1)) in answers.iter().enumerate() {
+            for (system2, answer2) in &answers[i + 1..] {
+                let judge_before = judge.usage();
+                let judged = judge
+                    .compare_answers(
+                        &item.question,
+                        system1,
+                        answer1,
+                        system2,
+                        answer2,
+                        item.answer.as_deref(),
+                    )
+                    .await;
+                result.judge_usage = result.judge_usage + judge.usage().since(&judge_before);
+                match judged {
+                    Ok(comparison) => {
+                        if self.config.verbose {
+                            println!(
+                                "  {} vs {}: winner {} ({}/5 vs {}/5)",
+                                system1,
+                                system2,
+                                match comparison.winner {
+                                    1 => system1,
+                                    2 => system2,
+                                    _ => "Tie",
+                                },
+                                comparison.score_system1,
+                                comparison.score_system2
+                            );
+                        }
+                        matches.push(PairwiseMatch {
+                            system1: system1.to_string(),
+                            system2: system2.to_string(),
+                            comparison,
+                        });
+                    }
+                    Err(e) => {
+                        warn!(item = %item.id, "Judge error ({} vs {}): {}", system1, system2, e);
+                    }
+                }
+            }
+        }
+        result.matches = matches;
+    }
+
+    /// Answer from a baseline's retrieved content, score the answer, and judge
+    /// it against PageIndex's answer.
+    ///
+    /// `usage` is the retrieval's usage; answering is added to it, and judging
+    /// to `judge_usage`.
+    #[allow(clippy::too_many_arguments)]
+    async fn evaluate_baseline(
+        &self,
+        system: &str,
+        item: &DatasetItem,
+        (content, passages, duration): (String, Vec<String>, Duration),
+        usage: ItemUsage,
+        llm_client: &LlmClient,
+        judge: &LlmJudge,
+        pageindex_answer: Option<&str>,
+        judge_usage: &mut UsageStats,
+    ) -> BaselineResult {
+        let mut result = BaselineResult {
+            system: system.to_string(),
+            content: Some(content.clone()),
+            answer: None,
+            time_ms: Some(duration.as_millis() as u64),
+            retrieval: retrieval_metrics(&passages, &item.evidence, self.config.top_k),
+            answer_metrics: None,
+            choice: None,
+            correct: None,
+            grade: None,
+            groundedness: None,
+            comparison: None,
+            usage: None,
+        };
+
+        let usage_before = llm_client.usage();
+        match self.generate_answer(llm_client, item, &content).await {
+            Ok((answer, choice)) => {
+                if self.config.verbose {
+                    println!("  [{}] Answer: {}", system, truncate_str(&answer, 500));
+                }
+                result.answer_metrics = answer_metrics(&answer, &item.gold_answers());
+                result.choice = choice;
+                result.correct = self.grade_choice(item, choice);
+                result.grade = self
+                    .grade_answer(judge, item, system, &answer, judge_usage)
+                    .await;
+                result.groundedness = self
+                    .check_groundedness(judge, item, system, &answer, &content, judge_usage)
+                    .await;
+                result.answer = Some(answer);
+            }
+            Err(e) => {
+                warn!(item = %item.id, "{} answer generation error: {}", system, e);
+            }
+        }
+        result.usage = Some(ItemUsage {
+            query: usage.query + llm_client.usage().since(&usage_before),
+            ..usage
+        });
+
+        if let (Some(pi_answer), Some(answer)) = (pageindex_answer, &result.answer)
+            && !self.is_multiple_choice(item)
+        {
+            let judge_before = judge.usage();
+            let judged = judge
+                .compare_answers(
+                    &item.question,
+                    "PageIndex",
+                    pi_answer,
+                    system,
+                    answer,
+                    item.answer.as_deref(),
+                )
+                .await;
+            *judge_usage = *judge_usage + judge.usage().since(&judge_before);
+            match judged {
+                Ok(comparison) => {
                     if self.config.verbose {
-                        eprintln!("  Judge error: {}", e);
+                        println!(
+                            "  PageIndex vs {}: winner {} (PI: {}/5, {}: {}/5)",
+                            system,
+                            match comparison.winner {
+                                1 => "PageIndex",
+                                2 => system,
+                                _ => "Tie",
+                            },
+                            comparison.score_system1,
+                            system,
+                            comparison.score_system2
+                        );
                     }
+                    result.comparison = Some(comparison);
+                }
+                Err(e) => {
+                    warn!(item = %item.id, "Judge error ({}): {}", system, e);
                 }
             }
         }
@@ -456,28 +2196,164 @@ impl Benchmark {
         result
     }
 
+    /// Whether an item is answered by picking an option.
+    fn is_multiple_choice(&self, item: &DatasetItem) -> bool {
+        self.config.multiple_choice && item.options.as_ref().is_some_and(|o| !o.is_empty())
+    }
+
+    /// Grade a picked option against the item's correct option.
+    ///
+    /// An answer with no identifiable option counts as wrong.
+    fn grade_choice(&self, item: &DatasetItem, choice: Option<usize>) -> Option<bool> {
+        if !self.is_multiple_choice(item) {
+            return None;
+        }
+        let correct = item.correct_option?;
+        Some(choice == Some(correct))
+    }
+
+    /// Grade an answer on its own against the item's gold answer, if answer
+    /// grading is on and the item has one. Judging is added to `judge_usage`.
+    async fn grade_answer(
+        &self,
+        judge: &LlmJudge,
+        item: &DatasetItem,
+        system: &str,
+        answer: &str,
+        judge_usage: &mut UsageStats,
+    ) -> Option<AnswerGrade> {
+        if !self.config.grade_answers || self.is_multiple_choice(item) {
+            return None;
+        }
+        let gold = item.answer.as_deref()?;
+
+        let judge_before = judge.usage();
+        let graded = judge.grade_answer(&item.question, answer, gold).await;
+        *judge_usage = *judge_usage + judge.usage().since(&judge_before);
+        match graded {
+            Ok(grade) => {
+                if self.config.verbose {
+                    println!(
+                        "  [{}] Grade: {}/5 ({})",
+                        system,
+                        grade.score,
+                        if grade.correct {
+                            "correct"
+                        } else {
+                            "incorrect"
+                        }
+                    );
+                }
+                Some(grade)
+            }
+            Err(e) => {
+                warn!(item = %item.id, "Grading error ({}): {}", system, e);
+                None
+            }
+        }
+    }
+
+    /// Check an answer against the content it was generated from, if
+    /// groundedness checking is on. Judging is added to `judge_usage`.
+    async fn check_groundedness(
+        &self,
+        judge: &LlmJudge,
+        item: &DatasetItem,
+        system: &str,
+        answer: &str,
+        content: &str,
+        judge_usage: &mut UsageStats,
+    ) -> Option<Groundedness> {
+        if !self.config.check_groundedness {
+            return None;
+        }
+
+        let judge_before = judge.usage();
+        let checked = judge
+            .check_groundedness(&item.question, answer, content)
+            .await;
+        *judge_usage = *judge_usage + judge.usage().since(&judge_before);
+        match checked {
+            Ok(check) => {
+                if self.config.verbose {
+                    println!(
+                        "  [{}] Groundedness: {}/5 ({})",
+                        system,
+                        check.score,
+                        if check.grounded {
+                            "supported"
+                        } else {
+                            "unsupported"
+                        }
+                    );
+                }
+                Some(check)
+            }
+            Err(e) => {
+                warn!(item = %item.id, "Groundedness check error ({}): {}", system, e);
+                None
+            }
+        }
+    }
+
     /// Generate an answer from retrieved content using LLM.
+    ///
+    /// In multiple-choice mode, items with options are answered by picking one;
+    /// the answer is then the picked option's text, returned with its index.
     async fn generate_answer(
         &self,
         client: &LlmClient,
-        question: &str,
+        item: &DatasetItem,
         context: &str,
-    ) -> Result<String> {
-        let prompt = Prompts::rag_answer()
-            .replace("{question}", question)
+    ) -> Result<(String, Option<usize>)> {
+        let options = match &item.options {
+            Some(options) if self.is_multiple_choice(item) => options,
+            _ => {
+                let prompt = Prompts::rag_answer()
+                    .replace("{style}", &AnswerStyle::default().instructions())
+                    .replace("{question}", &item.question)
+                    .replace("{context}", context);
+
+                let answer = client.complete(None, &prompt).await?;
+                return Ok((answer.trim().to_string(), None));
+            }
+        };
+
+        let options_text = options
+            .iter()
+            .enumerate()
+            .map(|(i, option)| format!("{}. {}", option_letter(i), option))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let prompt = Prompts::multiple_choice_answer()
+            .replace("{question}", &item.question)
+            .replace("{options}", &options_text)
             .replace("{context}", context);
 
-        let answer = client.complete(None, &prompt).await?;
-        Ok(answer.trim().to_string())
+        let response = client.complete(None, &prompt).await?;
+        let choice = parse_choice(&response, options);
+        let answer = match choice {
+            Some(i) => options[i].clone(),
+            None => response.trim().to_string(),
+        };
+        Ok((answer, choice))
     }
 
     /// Run PageIndex on a single item.
+    ///
+    /// Returns the combined context, the retrieved passages in rank order, the
+    /// retrieval time (including any tree building), and the tree build if the
+    /// tree was not cached. `llm_client` must be the client the indexer was
+    /// created with. With a `reranker`, sections are ordered by its scores
+    /// instead of the LLM's relevance labels.
     async fn run_pageindex(
         &self,
         item: &DatasetItem,
         indexer: &TreeIndexer,
         searcher: &TreeSearcher,
-    ) -> Result<(String, Duration)> {
+        llm_client: &LlmClient,
+        reranker: Option<&Reranker>,
+    ) -> Result<(String, Vec<String>, Duration, Option<TreeBuild>)> {
         let start = Instant::now();
 
         // Compute document hash for caching
@@ -490,20 +2366,40 @@ impl Benchmark {
             .map(|c| (c.tree.clone(), c.document.clone()));
         drop(cache);
 
+        let mut build = None;
         let (tree, doc) = if let Some((tree, doc)) = cached {
-            if self.config.verbose {
-                println!("  [PageIndex] Using cached tree for document");
-            }
+            debug!(item = %item.id, "Using cached tree for document");
             (tree, doc)
         } else {
             // Create document from item
             let doc = Document::from_text(&item.id, item.document.clone());
 
-            // Build tree index
-            if self.config.verbose {
-                println!("  [PageIndex] Building tree index (not cached)...");
-            }
-            let tree = indexer.index(&doc).await?;
+            let prebuilt = self
+                .config
+                .tree_dir
+                .as_deref()
+                .and_then(|dir| load_prebuilt_tree(dir, item));
+            let tree = match prebuilt {
+                Some(tree) => tree,
+                None => {
+                    // Build tree index
+                    debug!(item = %item.id, "Building tree index (not cached)");
+                    let build_start = Instant::now();
+                    let usage_before = llm_client.usage();
+                    let tree = indexer.index(&doc).await?;
+                    build = Some(TreeBuild {
+                        time: build_start.elapsed(),
+                        usage: llm_client.usage().since(&usage_before),
+                    });
+                    if let Some(dir) = &self.config.tree_dir {
+                        let path = tree_path(dir, &item.document);
+                        if let Err(e) = save_tree(&tree, &path) {
+                            warn!("Failed to save tree to {:?}: {}", path, e);
+                        }
+                    }
+                    tree
+                }
+            };
 
             // Cache the tree
             let mut cache = self.tree_cache.write().await;
@@ -523,64 +2419,157 @@ impl Benchmark {
             .search_with_content(&tree, &doc, &item.question)
             .await?;
 
-        // Combine relevant content - now we actually have content!
-        let content = search_results
-            .iter()
-            .filter(|r| matches!(r.relevance, Relevance::High | Relevance::Medium))
-            .take(self.config.top_k)
-            .map(|r| {
-                format!(
-                    "[Section: {}] (pages {}-{})\n{}",
-                    r.title,
-                    r.start_index,
-                    r.end_index,
-                    r.content.as_deref().unwrap_or("(no content)")
-                )
-            })
-            .collect::<Vec<_>>()
-            .join("\n\n---\n\n");
-
-        // If no high/medium results, fall back to any results
-        let content = if content.is_empty() {
-            search_results
-                .iter()
-                .take(self.config.top_k)
-                .map(|r| {
-                    format!(
-                        "[Section: {}] (pages {}-{})\n{}",
-                        r.title,
-                        r.start_index,
-                        r.end_index,
-                        r.content.as_deref().unwrap_or("(no content)")
-                    )
-                })
-                .collect::<Vec<_>>()
-                .join("\n\n---\n\n")
-        } else {
-            content
+        let budget = self.config.context_token_budget.unwrap_or(usize::MAX);
+        let (selected, content) = match reranker {
+            Some(reranker) => {
+                let texts: Vec<&str> = search_results
+                    .iter()
+                    .map(|r| r.content.as_deref().unwrap_or(&r.title))
+                    .collect();
+                let selected: Vec<SearchResult> = reranker
+                    .rerank(&item.question, &texts, self.config.top_k)
+                    .await?
+                    .into_iter()
+                    .map(|(i, _)| search_results[i].clone())
+                    .collect();
+                let content = doc.extract_ranked(&selected, budget);
+                (selected, content)
+            }
+            None => {
+                // Prefer high/medium relevance results, falling back to any results
+                let mut selected: Vec<SearchResult> = search_results
+                    .iter()
+                    .filter(|r| matches!(r.relevance, Relevance::High | Relevance::Medium))
+                    .take(self.config.top_k)
+                    .cloned()
+                    .collect();
+                if selected.is_empty() {
+                    selected = search_results
+                        .iter()
+                        .take(self.config.top_k)
+                        .cloned()
+                        .collect();
+                }
+                let content = doc.extract_for_results(&selected, budget);
+                (selected, content)
+            }
         };
+        let passages = selected
+            .iter()
+            .map(|r| r.content.clone().unwrap_or_default())
+            .collect();
 
         let duration = start.elapsed();
-        Ok((content, duration))
+        Ok((content, passages, duration, build))
+    }
+
+    /// Build (or load from the vector cache) an item's vector index,
+    /// returning it with the build time and embedding tokens used.
+    async fn build_vector_index(
+        &self,
+        item: &DatasetItem,
+        model: &EmbeddingModel,
+    ) -> Result<(VectorIndex, Duration, u64)> {
+        let start = Instant::now();
+        let tokens_before = embedding_tokens(model);
+        let chunk_config = self.config.chunk_config.clone();
+        let mut index = match &self.config.vector_cache_dir {
+            Some(dir) => {
+                VectorIndex::build_cached(&item.document, model, chunk_config, dir).await?
+            }
+            None => VectorIndex::build(&item.document, model, chunk_config).await?,
+        };
+        if let Some(hnsw) = &self.config.hnsw {
+            index = index.with_hnsw(hnsw.clone());
+        }
+        Ok((
+            index,
+            start.elapsed(),
+            embedding_tokens(model) - tokens_before,
+        ))
     }
 
     /// Run vector search on a single item.
+    ///
+    /// The reported time includes building the index.
     async fn run_vector_search(
         &self,
         item: &DatasetItem,
         model: &EmbeddingModel,
-    ) -> Result<(String, Duration)> {
+        index: &VectorIndex,
+        build_time: Duration,
+    ) -> Result<(String, Vec<String>, Duration)> {
         let start = Instant::now();
 
-        // Build vector index
-        let index = VectorIndex::build(&item.document, model, self.config.chunk_config.clone())?;
-
         // Search
-        let vec_searcher = VectorSearcher::new(&index, model);
-        let content = vec_searcher.search_context(&item.question, self.config.top_k)?;
+        let retrieval = VectorSearcher::new(index, model)
+            .retrieve(&item.question, self.config.top_k)
+            .await?;
 
-        let duration = start.elapsed();
-        Ok((content, duration))
+        let duration = build_time + start.elapsed();
+        Ok((retrieval.content, retrieval.passages, duration))
+    }
+
+    /// Run vector search on a single item and rerank the top
+    /// `rerank_candidates` chunks with the cross-encoder.
+    ///
+    /// The reported time includes building the index.
+    async fn run_reranked_search(
+        &self,
+        item: &DatasetItem,
+        model: &EmbeddingModel,
+        index: &VectorIndex,
+        reranker: &Reranker,
+        build_time: Duration,
+    ) -> Result<(String, Vec<String>, Duration)> {
+        let start = Instant::now();
+
+        let candidates = VectorSearcher::new(index, model)
+            .search(
+                &item.question,
+                self.config.rerank_candidates.max(self.config.top_k),
+            )
+            .await?;
+        let texts: Vec<&str> = candidates.iter().map(|r| r.chunk.text.as_str()).collect();
+        let ranked = reranker
+            .rerank(&item.question, &texts, self.config.top_k)
+            .await?;
+        let content = ranked
+            .iter()
+            .map(|&(i, score)| format!("[Rerank: {:.3}]\n{}", score, texts[i]))
+            .collect::<Vec<_>>()
+            .join("\n\n---\n\n");
+        let passages = ranked.iter().map(|&(i, _)| texts[i].to_string()).collect();
+
+        let duration = build_time + start.elapsed();
+        Ok((content, passages, duration))
+    }
+
+    /// Run hybrid BM25 + vector retrieval on a single item.
+    ///
+    /// The reported time includes building the vector index.
+    async fn run_hybrid_search(
+        &self,
+        item: &DatasetItem,
+        model: &EmbeddingModel,
+        index: &VectorIndex,
+        build_time: Duration,
+    ) -> Result<(String, Vec<String>, Duration)> {
+        let start = Instant::now();
+
+        let results = hybrid::hybrid_search(
+            index,
+            model,
+            &item.question,
+            self.config.top_k,
+            self.config.rrf_k,
+        )
+        .await?;
+        let content = hybrid::format_context(&results);
+        let passages = results.into_iter().map(|r| r.chunk.text).collect();
+
+        let duration = build_time + start.elapsed();
+        Ok((content, passages, duration))
     }
 }
 
@@ -596,6 +2585,193 @@ mod tests {
         assert!(config.run_vector);
     }
 
+    fn item_result(id: &str, error: Option<&str>) -> ItemResult {
+        ItemResult {
+            item_id: id.to_string(),
+            pageindex_content: None,
+            pageindex_answer: Some("answer".to_string()),
+            pageindex_time_ms: Some(10),
+            pageindex_build_time_ms: None,
+            vector_content: None,
+            vector_answer: None,
+            vector_time_ms: None,
+            pageindex_retrieval: None,
+            vector_retrieval: None,
+            pageindex_answer_metrics: None,
+            vector_answer_metrics: None,
+            pageindex_choice: None,
+            vector_choice: None,
+            pageindex_correct: None,
+            vector_correct: None,
+            pageindex_grade: None,
+            vector_grade: None,
+            pageindex_groundedness: None,
+            vector_groundedness: None,
+            comparison: None,
+            baselines: Vec::new(),
+            matches: Vec::new(),
+            pageindex_usage: None,
+            vector_usage: None,
+            judge_usage: UsageStats::default(),
+            tags: BTreeMap::new(),
+            error: error.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_results_log_roundtrip() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("results.jsonl");
+
+        append_result(&path, &item_result("1", None)).unwrap();
+        append_result(&path, &item_result("2", Some("rate limited"))).unwrap();
+        append_result(&path, &item_result("2", None)).unwrap();
+        // A crash mid-write leaves a partial line
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        write!(file, "{{\"item_id\": \"3\", \"pagei").unwrap();
+
+        let results = load_results_log(&path).unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].item_id, "1");
+        assert_eq!(results[1].item_id, "2");
+        assert!(results[1].error.is_none());
+    }
+
+    struct FixedSystem;
+
+    #[async_trait::async_trait]
+    impl RetrievalSystem for FixedSystem {
+        fn name(&self) -> &str {
+            "Fixed"
+        }
+
+        async fn retrieve(
+            &self,
+            _question: &str,
+            _document: &str,
+        ) -> Result<crate::eval::Retrieval> {
+            Ok(Default::default())
+        }
+    }
+
+    #[test]
+    fn test_registered_systems() {
+        let config = BenchmarkConfig {
+            run_bm25: true,
+            ..Default::default()
+        };
+        let benchmark = Benchmark::new(LlmConfig::default(), config).with_system(FixedSystem);
+        let names: Vec<&str> = benchmark.systems.iter().map(|s| s.name()).collect();
+        assert_eq!(names, vec!["BM25", "Fixed"]);
+
+        let benchmark = Benchmark::new(LlmConfig::default(), BenchmarkConfig::default());
+        assert!(benchmark.systems.is_empty());
+    }
+
+    fn dataset_item(id: &str) -> DatasetItem {
+        DatasetItem {
+            id: id.to_string(),
+            document: "Some document text.".to_string(),
+            question: "What?".to_string(),
+            answer: None,
+            alt_answers: Vec::new(),
+            supporting_facts: Vec::new(),
+            evidence: Vec::new(),
+            options: None,
+            correct_option: None,
+            source: "test".to_string(),
+            tags: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_load_prebuilt_tree() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let item = dataset_item("doc-1");
+        assert!(load_prebuilt_tree(dir.path(), &item).is_none());
+
+        // Matched by item id
+        let by_id = DocumentTree::new("by id", Vec::new(), 1);
+        save_tree(&by_id, &dir.path().join("doc-1.bin")).unwrap();
+        assert_eq!(load_prebuilt_tree(dir.path(), &item).unwrap().name, "by id");
+
+        // The document hash takes precedence; unreadable files are skipped
+        let path = tree_path(dir.path(), &item.document);
+        std::fs::write(&path, "not a tree").unwrap();
+        assert_eq!(load_prebuilt_tree(dir.path(), &item).unwrap().name, "by id");
+        let by_hash = DocumentTree::new("by hash", Vec::new(), 1);
+        save_tree(&by_hash, &path).unwrap();
+        assert_eq!(
+            load_prebuilt_tree(dir.path(), &item).unwrap().name,
+            "by hash"
+        );
+    }
+
+    #[test]
+    fn test_progress_events() {
+        let events = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let benchmark = Benchmark::new(LlmConfig::default(), BenchmarkConfig::default())
+            .with_progress(Arc::new(move |event: &ProgressEvent| {
+                sink.lock().unwrap().push(event.clone());
+            }));
+
+        let mut results = vec![item_result("1", None), item_result("2", None)];
+        results[0].comparison = Some(ComparisonResult {
+            winner: 1,
+            score_system1: 4.0,
+            score_system2: 3.0,
+            explanation: String::new(),
+            order_consistent: None,
+            judges: Vec::new(),
+            agreement: None,
+        });
+        benchmark.emit_item_completed(2, 5, &dataset_item("2"), &results);
+
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                ProgressEvent::WinsTally {
+                    pageindex_wins: 1,
+                    vector_wins: 0,
+                    ties: 0,
+                },
+                ProgressEvent::ItemCompleted {
+                    completed: 2,
+                    total: 5,
+                    item_id: "2".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_choice() {
+        let options: Vec<String> = ["Paris", "London", "Rome", "Berlin"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        assert_eq!(parse_choice("B", &options), Some(1));
+        assert_eq!(parse_choice("(c) Rome", &options), Some(2));
+        assert_eq!(parse_choice("Answer: d.", &options), Some(3));
+        assert_eq!(parse_choice("The answer is London", &options), Some(1));
+        assert_eq!(parse_choice("E", &options), None);
+        assert_eq!(parse_choice("I don't know", &options), None);
+    }
+
+    #[test]
+    fn test_accuracy() {
+        assert_eq!(
+            accuracy([Some(true), None, Some(false), Some(true), Some(true)].into_iter()),
+            Some(0.75)
+        );
+        assert_eq!(accuracy([None, None].into_iter()), None);
+    }
+
     #[test]
     fn test_benchmark_results_summary() {
         let mut results = BenchmarkResults::new("test");
@@ -605,15 +2781,54 @@ mod tests {
             pageindex_content: Some("content".to_string()),
             pageindex_answer: Some("answer 1".to_string()),
             pageindex_time_ms: Some(100),
+            pageindex_build_time_ms: Some(40),
             vector_content: Some("content".to_string()),
             vector_answer: Some("answer 1".to_string()),
             vector_time_ms: Some(50),
+            pageindex_retrieval: Some(RetrievalMetrics {
+                recall_at_k: 1.0,
+                mrr: 1.0,
+                ndcg_at_k: 1.0,
+            }),
+            vector_retrieval: None,
+            pageindex_answer_metrics: Some(AnswerMetrics {
+                exact_match: 1.0,
+                f1: 1.0,
+            }),
+            vector_answer_metrics: None,
+            pageindex_choice: None,
+            vector_choice: None,
+            pageindex_correct: None,
+            vector_correct: None,
+            pageindex_grade: Some(AnswerGrade {
+                score: 5,
+                correct: true,
+                explanation: String::new(),
+            }),
+            vector_grade: None,
+            // Correct, but not from the retrieved content
+            pageindex_groundedness: Some(Groundedness {
+                score: 1,
+                grounded: false,
+                unsupported_claims: vec!["answer 1".to_string()],
+                explanation: String::new(),
+            }),
+            vector_groundedness: None,
             comparison: Some(ComparisonResult {
                 winner: 1,
-                score_system1: 4,
-                score_system2: 3,
+                score_system1: 4.0,
+                score_system2: 3.0,
                 explanation: "test".to_string(),
+                order_consistent: None,
+                judges: Vec::new(),
+                agreement: None,
             }),
+            baselines: Vec::new(),
+            matches: Vec::new(),
+            pageindex_usage: None,
+            vector_usage: None,
+            judge_usage: UsageStats::default(),
+            tags: BTreeMap::new(),
             error: None,
         });
 
@@ -622,15 +2837,48 @@ mod tests {
             pageindex_content: Some("content".to_string()),
             pageindex_answer: Some("answer 2".to_string()),
             pageindex_time_ms: Some(150),
+            pageindex_build_time_ms: None,
             vector_content: Some("content".to_string()),
             vector_answer: Some("answer 2".to_string()),
             vector_time_ms: Some(60),
+            pageindex_retrieval: Some(RetrievalMetrics {
+                recall_at_k: 0.5,
+                mrr: 0.5,
+                ndcg_at_k: 0.5,
+            }),
+            vector_retrieval: None,
+            pageindex_answer_metrics: Some(AnswerMetrics {
+                exact_match: 1.0,
+                f1: 1.0,
+            }),
+            vector_answer_metrics: None,
+            pageindex_choice: None,
+            vector_choice: None,
+            pageindex_correct: None,
+            vector_correct: None,
+            pageindex_grade: Some(AnswerGrade {
+                score: 2,
+                correct: false,
+                explanation: String::new(),
+            }),
+            vector_grade: None,
+            pageindex_groundedness: None,
+            vector_groundedness: None,
             comparison: Some(ComparisonResult {
                 winner: 2,
-                score_system1: 3,
-                score_system2: 5,
+                score_system1: 3.0,
+                score_system2: 5.0,
                 explanation: "test".to_string(),
+                order_consistent: None,
+                judges: Vec::new(),
+                agreement: None,
             }),
+            baselines: Vec::new(),
+            matches: Vec::new(),
+            pageindex_usage: None,
+            vector_usage: None,
+            judge_usage: UsageStats::default(),
+            tags: BTreeMap::new(),
             error: None,
         });
 
@@ -640,7 +2888,165 @@ mod tests {
         assert_eq!(results.pageindex_wins, 1);
         assert_eq!(results.vector_wins, 1);
         assert_eq!(results.ties, 0);
+        assert_eq!(results.significance.unwrap().n, 2);
         assert!((results.avg_pageindex_score - 3.5).abs() < 0.01);
         assert!((results.avg_vector_score - 4.0).abs() < 0.01);
+        assert_eq!(results.pageindex_latency.unwrap().p50_ms, 125.0);
+        assert_eq!(results.pageindex_search_latency.unwrap().max_ms, 150.0);
+        assert_eq!(results.pageindex_search_latency.unwrap().p50_ms, 105.0);
+        assert_eq!(results.amortized_pageindex_build_ms, 20.0);
+        assert_eq!(results.vector_latency.unwrap().max_ms, 60.0);
+        assert!((results.pageindex_retrieval.unwrap().mrr - 0.75).abs() < 0.01);
+        assert!(results.vector_retrieval.is_none());
+        assert_eq!(results.pageindex_answer_metrics.unwrap().exact_match, 1.0);
+        let grades = results.pageindex_grades.unwrap();
+        assert_eq!(grades.graded, 2);
+        assert_eq!((grades.avg_score, grades.correct_rate), (3.5, 0.5));
+        assert!(results.vector_grades.is_none());
+        let groundedness = results.pageindex_groundedness.unwrap();
+        assert_eq!(groundedness.checked, 1);
+        assert_eq!(groundedness.grounded_rate, 0.0);
+        assert_eq!(groundedness.correct_ungrounded, 1);
+        assert!(results.vector_groundedness.is_none());
+        assert!(results.baselines.is_empty());
+    }
+
+    #[test]
+    fn test_baseline_summary() {
+        let baseline = |system: &str, winner: u8, time_ms: u64| BaselineResult {
+            system: system.to_string(),
+            content: None,
+            answer: Some("answer".to_string()),
+            time_ms: Some(time_ms),
+            retrieval: None,
+            answer_metrics: None,
+            choice: None,
+            correct: None,
+            grade: None,
+            groundedness: None,
+            comparison: Some(ComparisonResult {
+                winner,
+                score_system1: 4.0,
+                score_system2: if winner == 2 { 5.0 } else { 2.0 },
+                explanation: String::new(),
+                order_consistent: None,
+                judges: Vec::new(),
+                agreement: None,
+            }),
+            usage: None,
+        };
+
+        let mut results = BenchmarkResults::new("test");
+        for (id, bm25_winner) in [("1", 1), ("2", 1), ("3", 2)] {
+            let mut item = item_result(id, None);
+            item.baselines.push(baseline("BM25", bm25_winner, 2));
+            item.baselines.push(baseline("Other", 0, 4));
+            results.item_results.push(item);
+        }
+        results.calculate_summary();
+
+        assert_eq!(results.baselines.len(), 2);
+        let bm25 = &results.baselines[0];
+        assert_eq!(bm25.system, "BM25");
+        assert_eq!(
+            (bm25.pageindex_wins, bm25.baseline_wins, bm25.ties),
+            (2, 1, 0)
+        );
+        assert!((bm25.avg_baseline_score - 3.0).abs() < 1e-9);
+        assert_eq!(bm25.avg_time_ms, 2.0);
+        assert_eq!(bm25.significance.unwrap().n, 3);
+        assert_eq!(results.baselines[1].ties, 3);
+    }
+
+    #[test]
+    fn test_system_costs() {
+        let usage = |prompt: u64, completion: u64| UsageStats {
+            calls: 1,
+            prompt_tokens: prompt,
+            completion_tokens: completion,
+            total_tokens: prompt + completion,
+        };
+
+        let mut results = BenchmarkResults::new("test");
+        results.pricing = Some(ModelPricing {
+            prompt_per_million: 1.0,
+            completion_per_million: 2.0,
+        });
+        for (id, index) in [("1", usage(9_000, 500)), ("2", UsageStats::default())] {
+            let mut item = item_result(id, None);
+            item.pageindex_usage = Some(ItemUsage {
+                index,
+                query: usage(2_000, 500),
+                embedding_tokens: 0,
+            });
+            item.vector_usage = Some(ItemUsage {
+                index: UsageStats::default(),
+                query: usage(1_000, 0),
+                embedding_tokens: 300,
+            });
+            item.judge_usage = usage(500, 250);
+            results.item_results.push(item);
+        }
+        results.calculate_summary();
+
+        assert_eq!(results.costs.len(), 2);
+        let pageindex = &results.costs[0];
+        assert_eq!(pageindex.system, "PageIndex");
+        assert_eq!(pageindex.queries, 2);
+        assert_eq!(pageindex.index_usage.total_tokens, 9_500);
+        assert!((pageindex.index_cost_usd.unwrap() - 0.01).abs() < 1e-9);
+        assert!((pageindex.query_cost_usd.unwrap() - 0.003).abs() < 1e-9);
+        assert!((pageindex.cost_per_query_usd.unwrap() - 0.008).abs() < 1e-9);
+
+        let vector = &results.costs[1];
+        assert_eq!(vector.embedding_tokens, 600);
+        assert!((vector.cost_per_query_usd.unwrap() - 0.001).abs() < 1e-9);
+        assert_eq!(results.judge_usage.total_tokens, 1_500);
+
+        results.pricing = None;
+        results.calculate_summary();
+        assert!(results.costs[0].cost_per_query_usd.is_none());
+    }
+
+    #[test]
+    fn test_tag_breakdowns() {
+        let mut results = BenchmarkResults::new("test");
+        for (id, difficulty, winner) in [("1", "hard", 2), ("2", "hard", 2), ("3", "easy", 1)] {
+            let mut item = item_result(id, None);
+            item.tags
+                .insert("difficulty".to_string(), difficulty.to_string());
+            if id == "1" {
+                item.tags.insert("type".to_string(), "bridge".to_string());
+            }
+            item.comparison = Some(ComparisonResult {
+                winner,
+                score_system1: 3.0,
+                score_system2: if winner == 2 { 5.0 } else { 2.0 },
+                explanation: String::new(),
+                order_consistent: None,
+                judges: Vec::new(),
+                agreement: None,
+            });
+            results.item_results.push(item);
+        }
+        results.calculate_summary();
+
+        let keys: Vec<(&str, &str, usize)> = results
+            .tag_breakdowns
+            .iter()
+            .map(|b| (b.tag.as_str(), b.value.as_str(), b.items))
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                ("difficulty", "easy", 1),
+                ("difficulty", "hard", 2),
+                ("type", "bridge", 1)
+            ]
+        );
+        let hard = &results.tag_breakdowns[1];
+        assert_eq!((hard.pageindex_wins, hard.vector_wins), (0, 2));
+        assert_eq!(hard.avg_vector_score, 5.0);
+        assert_eq!(results.tag_breakdowns[0].pageindex_wins, 1);
     }
 }
@@ -0,0 +1,251 @@
+//! BM25 keyword retrieval baseline over the same chunks as vector search.
+
+use super::system::{Retrieval, RetrievalSystem};
+use super::vector_search::{Chunk, ChunkConfig, VectorIndex};
+use anyhow::Result;
+use async_trait::async_trait;
+use std::collections::HashMap;
+
+/// BM25 scoring parameters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bm25Config {
+    /// Term frequency saturation.
+    pub k1: f32,
+    /// Document length normalization (0 = none, 1 = full).
+    pub b: f32,
+}
+
+impl Default for Bm25Config {
+    fn default() -> Self {
+        Self { k1: 1.2, b: 0.75 }
+    }
+}
+
+/// BM25 search result.
+#[derive(Debug, Clone)]
+pub struct Bm25SearchResult {
+    /// The matched chunk.
+    pub chunk: Chunk,
+    /// BM25 score (unbounded, higher is better).
+    pub score: f32,
+}
+
+/// Inverted term statistics over a document's chunks.
+pub struct Bm25Index {
+    chunks: Vec<Chunk>,
+    /// Term counts per chunk.
+    term_counts: Vec<HashMap<String, u32>>,
+    /// Number of chunks containing each term.
+    doc_freqs: HashMap<String, usize>,
+    avg_len: f32,
+    config: Bm25Config,
+}
+
+/// Lowercased alphanumeric tokens.
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+impl Bm25Index {
+    /// Chunk a document (as for vector search) and index the chunks.
+    pub fn build(text: &str, chunk_config: &ChunkConfig, config: Bm25Config) -> Self {
+        Self::from_chunks(VectorIndex::chunk_text(text, chunk_config), config)
+    }
+
+    /// Index pre-chunked text.
+    pub fn from_chunks(chunks: Vec<Chunk>, config: Bm25Config) -> Self {
+        let mut doc_freqs: HashMap<String, usize> = HashMap::new();
+        let term_counts: Vec<HashMap<String, u32>> = chunks
+            .iter()
+            .map(|chunk| {
+                let mut counts = HashMap::new();
+                for token in tokenize(&chunk.text) {
+                    *counts.entry(token).or_insert(0) += 1;
+                }
+                for term in counts.keys() {
+                    *doc_freqs.entry(term.clone()).or_insert(0) += 1;
+                }
+                counts
+            })
+            .collect();
+        let total_len: u32 = term_counts.iter().flat_map(|c| c.values()).sum();
+        let avg_len = total_len as f32 / chunks.len().max(1) as f32;
+
+        Self {
+            chunks,
+            term_counts,
+            doc_freqs,
+            avg_len,
+            config,
+        }
+    }
+
+    /// Number of chunks in the index.
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Check if index is empty.
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Score every chunk against a query (same order as the chunks).
+    pub fn scores(&self, query: &str) -> Vec<f32> {
+        let n = self.chunks.len() as f32;
+        let query_terms = tokenize(query);
+        let Bm25Config { k1, b } = self.config;
+
+        self.term_counts
+            .iter()
+            .map(|counts| {
+                let len: u32 = counts.values().sum();
+                let norm = k1 * (1.0 - b + b * len as f32 / self.avg_len.max(1.0));
+                query_terms
+                    .iter()
+                    .filter_map(|term| {
+                        let tf = *counts.get(term)? as f32;
+                        let df = self.doc_freqs[term] as f32;
+                        // Lucene-style IDF, always positive
+                        let idf = ((n - df + 0.5) / (df + 0.5) + 1.0).ln();
+                        Some(idf * tf * (k1 + 1.0) / (tf + norm))
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+
+    /// Top `top_k` chunks by BM25 score. Chunks sharing no terms with the
+    /// query are not returned.
+    pub fn search(&self, query: &str, top_k: usize) -> Vec<Bm25SearchResult> {
+        let mut results: Vec<Bm25SearchResult> = self
+            .scores(query)
+            .into_iter()
+            .zip(&self.chunks)
+            .filter(|(score, _)| *score > 0.0)
+            .map(|(score, chunk)| Bm25SearchResult {
+                chunk: chunk.clone(),
+                score,
+            })
+            .collect();
+        results.sort_by(|a, b| b.score.total_cmp(&a.score));
+        results.truncate(top_k);
+        results
+    }
+
+    /// Concatenate search results into a context string.
+    pub fn format_context(results: &[Bm25SearchResult]) -> String {
+        results
+            .iter()
+            .map(|r| format!("[BM25: {:.3}]\n{}", r.score, r.chunk.text))
+            .collect::<Vec<_>>()
+            .join("\n\n---\n\n")
+    }
+}
+
+/// BM25 over each document's chunks, as a benchmark baseline.
+pub struct Bm25System {
+    chunk_config: ChunkConfig,
+    config: Bm25Config,
+    top_k: usize,
+}
+
+impl Bm25System {
+    /// Retrieve the `top_k` best chunks with default BM25 parameters.
+    pub fn new(chunk_config: ChunkConfig, top_k: usize) -> Self {
+        Self {
+            chunk_config,
+            config: Bm25Config::default(),
+            top_k,
+        }
+    }
+
+    /// Use custom BM25 parameters.
+    pub fn with_config(mut self, config: Bm25Config) -> Self {
+        self.config = config;
+        self
+    }
+}
+
+#[async_trait]
+impl RetrievalSystem for Bm25System {
+    fn name(&self) -> &str {
+        "BM25"
+    }
+
+    async fn retrieve(&self, question: &str, document: &str) -> Result<Retrieval> {
+        let index = Bm25Index::build(document, &self.chunk_config, self.config.clone());
+        let hits = index.search(question, self.top_k);
+        Ok(Retrieval {
+            content: Bm25Index::format_context(&hits),
+            passages: hits.into_iter().map(|h| h.chunk.text).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(index: usize, text: &str) -> Chunk {
+        Chunk {
+            text: text.to_string(),
+            start_pos: 0,
+            end_pos: text.len(),
+            index,
+        }
+    }
+
+    #[test]
+    fn test_bm25_ranking() {
+        let index = Bm25Index::from_chunks(
+            vec![
+                chunk(0, "The cat sat on the mat."),
+                chunk(1, "Quarterly revenue grew by ten percent."),
+                chunk(2, "Revenue, revenue and more revenue: the revenue report."),
+                chunk(3, "Dogs chase cats."),
+            ],
+            Bm25Config::default(),
+        );
+        assert_eq!(index.len(), 4);
+
+        let results = index.search("What was the quarterly Revenue growth?", 3);
+        assert_eq!(results.len(), 3);
+        // "quarterly" is rarer than "revenue", so chunk 1 beats the repetitive chunk 2
+        assert_eq!(results[0].chunk.index, 1);
+        assert_eq!(results[1].chunk.index, 2);
+        assert!(results[0].score > results[1].score);
+
+        assert!(index.search("unrelated words", 3).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_bm25_system() {
+        let system = Bm25System::new(
+            ChunkConfig {
+                chunk_size: 40,
+                chunk_overlap: 0,
+                ..Default::default()
+            },
+            1,
+        );
+        let document = "The cat sat on the mat all day. Quarterly revenue grew by ten percent.";
+        let retrieval = system
+            .retrieve("How much did revenue grow?", document)
+            .await
+            .unwrap();
+        assert_eq!(system.name(), "BM25");
+        assert_eq!(retrieval.passages.len(), 1);
+        assert!(retrieval.passages[0].contains("revenue"));
+        assert!(retrieval.content.starts_with("[BM25: "));
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(tokenize("Hello, World! 42x"), vec!["hello", "world", "42x"]);
+        assert!(tokenize(" -- ").is_empty());
+    }
+}
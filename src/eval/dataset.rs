@@ -2,12 +2,19 @@
 //!
 //! Supports:
 //! - QuALITY (Question Answering with Long Input Texts, Yes!)
+//! - NarrativeQA (full stories with free-form questions and answers)
+//! - HotpotQA-style multi-hop QA (concatenated passages with supporting facts)
 //! - Custom JSON format for user-provided datasets
 
 use anyhow::{Context, Result};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::{SliceRandom, index};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use tracing::{info, warn};
 
 /// A single evaluation item with a document and question.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,12 +27,35 @@ pub struct DatasetItem {
     pub question: String,
     /// Ground truth answer (if available).
     pub answer: Option<String>,
+    /// Additional acceptable answers (e.g. NarrativeQA's second reference).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub alt_answers: Vec<String>,
+    /// Sentences needed to answer a multi-hop question (if annotated).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub supporting_facts: Vec<SupportingFact>,
+    /// Gold evidence spans (verbatim text from the document), used for
+    /// retrieval metrics.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub evidence: Vec<String>,
     /// Multiple choice options (if applicable).
     pub options: Option<Vec<String>>,
     /// Index of correct option (0-indexed, if applicable).
     pub correct_option: Option<usize>,
     /// Source dataset name.
     pub source: String,
+    /// Categories such as topic, difficulty, or question type (e.g.
+    /// `"difficulty": "hard"`); benchmark results are broken down by each.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub tags: BTreeMap<String, String>,
+}
+
+/// A sentence that supports the answer to a multi-hop question.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SupportingFact {
+    /// Title of the passage containing the sentence.
+    pub title: String,
+    /// Index of the sentence within the passage (0-indexed).
+    pub sentence: usize,
 }
 
 /// A collection of evaluation items.
@@ -37,6 +67,17 @@ pub struct Dataset {
     pub items: Vec<DatasetItem>,
 }
 
+impl DatasetItem {
+    /// All acceptable reference answers: `answer` followed by `alt_answers`.
+    pub fn gold_answers(&self) -> Vec<&str> {
+        self.answer
+            .iter()
+            .chain(&self.alt_answers)
+            .map(String::as_str)
+            .collect()
+    }
+}
+
 impl Dataset {
     /// Create a new empty dataset.
     pub fn new(name: &str) -> Self {
@@ -69,6 +110,24 @@ impl Dataset {
         }
     }
 
+    /// A random subset of `n` items (all items if `n` exceeds the dataset),
+    /// kept in dataset order. The same seed always picks the same items.
+    pub fn sample(&self, n: usize, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut picked =
+            index::sample(&mut rng, self.items.len(), n.min(self.items.len())).into_vec();
+        picked.sort_unstable();
+        Self {
+            name: self.name.clone(),
+            items: picked.into_iter().map(|i| self.items[i].clone()).collect(),
+        }
+    }
+
+    /// Shuffle the items in place. The same seed always gives the same order.
+    pub fn shuffle(&mut self, seed: u64) {
+        self.items.shuffle(&mut StdRng::seed_from_u64(seed));
+    }
+
     /// Load from a JSON file.
     pub fn load_json(path: &Path) -> Result<Self> {
         let content = fs::read_to_string(path)
@@ -95,6 +154,7 @@ struct QualityRawArticle {
     /// Article ID.
     article_id: String,
     /// Set ID.
+    #[allow(dead_code)]
     set_unique_id: String,
     /// Questions for this article.
     questions: Vec<QualityRawQuestion>,
@@ -112,7 +172,27 @@ struct QualityRawQuestion {
     gold_label: i32,
     /// Writer label.
     #[serde(default)]
+    #[allow(dead_code)]
     writer_label: Option<i32>,
+    /// 1 if the question is in the hard subset (annotators with limited time
+    /// mostly got it wrong).
+    #[serde(default)]
+    difficult: Option<i32>,
+}
+
+/// Which QuALITY questions to load.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QualityFilter {
+    /// Only questions in the hard subset.
+    pub hard_only: bool,
+    /// Only questions about the first N distinct articles (counting articles
+    /// with at least one question left after the other filters).
+    pub max_articles: Option<usize>,
+}
+
+/// File a QuALITY split (`train` or `dev`) is saved to in a data directory.
+pub fn quality_split_path(dir: &Path, split: &str) -> PathBuf {
+    dir.join(format!("quality_{}.jsonl", split))
 }
 
 /// Load QuALITY dataset from a JSONL file.
@@ -123,10 +203,16 @@ struct QualityRawQuestion {
 /// wget https://raw.githubusercontent.com/nyu-mll/quality/main/data/v1.0.1/QuALITY.v1.0.1.htmlstripped.dev
 /// ```
 pub fn load_quality_dataset(path: &Path) -> Result<Dataset> {
+    load_quality_dataset_filtered(path, &QualityFilter::default())
+}
+
+/// Load the QuALITY questions selected by `filter` from a JSONL file.
+pub fn load_quality_dataset_filtered(path: &Path, filter: &QualityFilter) -> Result<Dataset> {
     let content = fs::read_to_string(path)
         .with_context(|| format!("Failed to read QuALITY file: {:?}", path))?;
 
     let mut dataset = Dataset::new("QuALITY");
+    let mut articles: Vec<String> = Vec::new();
 
     // QuALITY is in JSONL format (one JSON per line, each containing an article with multiple questions)
     for (line_num, line) in content.lines().enumerate() {
@@ -140,6 +226,17 @@ pub fn load_quality_dataset(path: &Path) -> Result<Dataset> {
 
         // Create one DatasetItem per question
         for q in raw.questions {
+            if filter.hard_only && q.difficult != Some(1) {
+                continue;
+            }
+            // Several lines (annotation sets) can share an article
+            if !articles.contains(&raw.article_id) {
+                if filter.max_articles.is_some_and(|max| articles.len() >= max) {
+                    continue;
+                }
+                articles.push(raw.article_id.clone());
+            }
+
             // Convert 1-indexed gold_label to 0-indexed
             let correct_option = if q.gold_label >= 1 && q.gold_label <= 4 {
                 Some((q.gold_label - 1) as usize)
@@ -150,14 +247,24 @@ pub fn load_quality_dataset(path: &Path) -> Result<Dataset> {
             // Get the answer text from options
             let answer = correct_option.and_then(|idx| q.options.get(idx).cloned());
 
+            let mut tags = BTreeMap::new();
+            if let Some(difficult) = q.difficult {
+                let difficulty = if difficult == 1 { "hard" } else { "easy" };
+                tags.insert("difficulty".to_string(), difficulty.to_string());
+            }
+
             let item = DatasetItem {
                 id: q.question_unique_id,
                 document: raw.article.clone(),
                 question: q.question,
                 answer,
+                alt_answers: Vec::new(),
+                supporting_facts: Vec::new(),
+                evidence: Vec::new(),
                 options: Some(q.options),
                 correct_option,
                 source: "QuALITY".to_string(),
+                tags,
             };
 
             dataset.add_item(item);
@@ -167,6 +274,297 @@ pub fn load_quality_dataset(path: &Path) -> Result<Dataset> {
     Ok(dataset)
 }
 
+/// Base URL of the official NarrativeQA repository (metadata and questions).
+const NARRATIVEQA_BASE_URL: &str = "https://raw.githubusercontent.com/deepmind/narrativeqa/master";
+
+/// Row of NarrativeQA's `documents.csv`.
+#[derive(Debug, Deserialize)]
+struct NarrativeQaDocument {
+    document_id: String,
+    set: String,
+    #[allow(dead_code)]
+    kind: String,
+    story_url: String,
+    /// Text marking the start of the story inside the downloaded page.
+    story_start: String,
+    /// Text marking the end of the story inside the downloaded page.
+    story_end: String,
+}
+
+/// Row of NarrativeQA's `qaps.csv`.
+#[derive(Debug, Deserialize)]
+struct NarrativeQaQuestion {
+    document_id: String,
+    set: String,
+    question: String,
+    answer1: String,
+    answer2: String,
+}
+
+/// Path of a cached NarrativeQA story.
+fn narrativeqa_story_path(dir: &Path, document_id: &str) -> std::path::PathBuf {
+    dir.join("stories").join(format!("{}.content", document_id))
+}
+
+fn read_narrativeqa_documents(dir: &Path) -> Result<Vec<NarrativeQaDocument>> {
+    let path = dir.join("documents.csv");
+    let mut reader = csv::Reader::from_path(&path)
+        .with_context(|| format!("Failed to read NarrativeQA documents: {:?}", path))?;
+    reader
+        .deserialize()
+        .collect::<std::result::Result<_, _>>()
+        .with_context(|| format!("Failed to parse {:?}", path))
+}
+
+/// Cut a downloaded story page down to the story text.
+///
+/// Pages (Project Gutenberg text or IMSDb HTML) include headers and licenses
+/// around the story; `documents.csv` gives the text the story starts and ends
+/// with. HTML tags are stripped first.
+fn extract_story(raw: &str, start: &str, end: &str) -> String {
+    let text = if raw.contains("<html") || raw.contains("<HTML") {
+        strip_html_tags(raw)
+    } else {
+        raw.to_string()
+    };
+
+    let from = text.find(start.trim()).unwrap_or(0);
+    let to = text[from..]
+        .rfind(end.trim())
+        .map(|i| from + i + end.trim().len())
+        .unwrap_or(text.len());
+    text[from..to].trim().to_string()
+}
+
+fn strip_html_tags(html: &str) -> String {
+    let mut out = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => out.push(c),
+            _ => {}
+        }
+    }
+    out.replace("&nbsp;", " ")
+        .replace("&amp;", "&")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+}
+
+/// Load NarrativeQA from a directory prepared by [`download_narrativeqa`].
+///
+/// Expects `documents.csv`, `qaps.csv`, and cached stories under `stories/`.
+/// Only questions from `split` ("train", "valid", or "test") whose story has
+/// been downloaded are included. Both reference answers are kept: the first
+/// as `answer`, the second in `alt_answers`.
+pub fn load_narrativeqa_dataset(dir: &Path, split: &str) -> Result<Dataset> {
+    let mut stories: HashMap<String, String> = HashMap::new();
+    let mut missing = 0;
+    for doc in read_narrativeqa_documents(dir)? {
+        if doc.set != split {
+            continue;
+        }
+        let path = narrativeqa_story_path(dir, &doc.document_id);
+        match fs::read_to_string(&path) {
+            Ok(raw) => {
+                let story = extract_story(&raw, &doc.story_start, &doc.story_end);
+                stories.insert(doc.document_id, story);
+            }
+            Err(_) => missing += 1,
+        }
+    }
+    if missing > 0 {
+        warn!(
+            missing,
+            split, "skipping NarrativeQA documents whose story is not downloaded"
+        );
+    }
+
+    let qaps_path = dir.join("qaps.csv");
+    let mut reader = csv::Reader::from_path(&qaps_path)
+        .with_context(|| format!("Failed to read NarrativeQA questions: {:?}", qaps_path))?;
+
+    let mut dataset = Dataset::new("NarrativeQA");
+    let mut per_document: HashMap<String, usize> = HashMap::new();
+    for row in reader.deserialize() {
+        let q: NarrativeQaQuestion =
+            row.with_context(|| format!("Failed to parse {:?}", qaps_path))?;
+        if q.set != split {
+            continue;
+        }
+        let Some(story) = stories.get(&q.document_id) else {
+            continue;
+        };
+
+        let n = per_document.entry(q.document_id.clone()).or_insert(0);
+        *n += 1;
+
+        dataset.add_item(DatasetItem {
+            id: format!("{}_{}", q.document_id, n),
+            document: story.clone(),
+            question: q.question,
+            answer: Some(q.answer1),
+            alt_answers: vec![q.answer2],
+            supporting_facts: Vec::new(),
+            evidence: Vec::new(),
+            options: None,
+            correct_option: None,
+            source: "NarrativeQA".to_string(),
+            tags: BTreeMap::new(),
+        });
+    }
+
+    Ok(dataset)
+}
+
+/// Download NarrativeQA metadata, questions, and stories for one split.
+///
+/// Stories are fetched from their original source (Project Gutenberg or
+/// IMSDb) and cached under `dir/stories/`; already-cached stories are not
+/// downloaded again. `max_documents` limits how many stories are fetched.
+pub async fn download_narrativeqa(
+    dir: &Path,
+    split: &str,
+    max_documents: Option<usize>,
+) -> Result<usize> {
+    fs::create_dir_all(dir.join("stories"))?;
+
+    for file in ["documents.csv", "qaps.csv"] {
+        let path = dir.join(file);
+        if path.exists() {
+            continue;
+        }
+        let url = format!("{}/{}", NARRATIVEQA_BASE_URL, file);
+        info!("Downloading {}", url);
+        let response = reqwest::get(&url).await?.error_for_status()?;
+        fs::write(&path, response.bytes().await?)?;
+    }
+
+    let documents: Vec<_> = read_narrativeqa_documents(dir)?
+        .into_iter()
+        .filter(|d| d.set == split)
+        .take(max_documents.unwrap_or(usize::MAX))
+        .collect();
+
+    let mut available = 0;
+    for doc in &documents {
+        let path = narrativeqa_story_path(dir, &doc.document_id);
+        if path.exists() {
+            available += 1;
+            continue;
+        }
+
+        info!(
+            "Downloading story {} from {}",
+            doc.document_id, doc.story_url
+        );
+        let result = async {
+            let response = reqwest::get(&doc.story_url).await?.error_for_status()?;
+            let bytes = response.bytes().await?;
+            // Some sources aren't valid UTF-8; keep what we can
+            fs::write(&path, String::from_utf8_lossy(&bytes).as_bytes())?;
+            anyhow::Ok(())
+        }
+        .await;
+
+        match result {
+            Ok(()) => available += 1,
+            Err(e) => warn!("Failed to download story {}: {}", doc.document_id, e),
+        }
+    }
+
+    Ok(available)
+}
+
+/// HotpotQA question format.
+/// Structure: https://hotpotqa.github.io
+#[derive(Debug, Deserialize)]
+struct HotpotRawQuestion {
+    #[serde(rename = "_id")]
+    id: String,
+    question: String,
+    answer: String,
+    /// `[title, sentence index]` pairs.
+    supporting_facts: Vec<(String, usize)>,
+    /// `[title, sentences]` pairs, one per passage.
+    context: Vec<(String, Vec<String>)>,
+    /// Question type ("bridge" or "comparison").
+    #[serde(default, rename = "type")]
+    question_type: Option<String>,
+    /// Difficulty ("easy", "medium", or "hard").
+    #[serde(default)]
+    level: Option<String>,
+}
+
+/// Load a HotpotQA-style multi-hop dataset from a JSON file.
+///
+/// Each question's passages are concatenated into one document, with every
+/// passage under a markdown heading of its title so the indexer can recover
+/// passage boundaries. Supporting facts are kept on the item, and their
+/// sentences become its gold evidence. The question type and level become
+/// `type` and `difficulty` tags. Other datasets
+/// in the same format (e.g. 2WikiMultiHopQA) load the same way.
+///
+/// ```bash
+/// wget http://curtis.ml.cmu.edu/datasets/hotpot/hotpot_dev_distractor_v1.json
+/// ```
+pub fn load_hotpotqa_dataset(path: &Path) -> Result<Dataset> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read HotpotQA file: {:?}", path))?;
+    let questions: Vec<HotpotRawQuestion> =
+        serde_json::from_str(&content).with_context(|| "Failed to parse HotpotQA JSON")?;
+
+    let mut dataset = Dataset::new("HotpotQA");
+    for q in questions {
+        let document = q
+            .context
+            .iter()
+            .map(|(title, sentences)| format!("# {}\n\n{}", title, sentences.concat().trim()))
+            .collect::<Vec<_>>()
+            .join("\n\n");
+
+        let evidence = q
+            .supporting_facts
+            .iter()
+            .filter_map(|(title, sentence)| {
+                let (_, sentences) = q.context.iter().find(|(t, _)| t == title)?;
+                Some(sentences.get(*sentence)?.trim().to_string())
+            })
+            .collect();
+
+        let mut tags = BTreeMap::new();
+        if let Some(question_type) = q.question_type {
+            tags.insert("type".to_string(), question_type);
+        }
+        if let Some(level) = q.level {
+            tags.insert("difficulty".to_string(), level);
+        }
+
+        dataset.add_item(DatasetItem {
+            id: q.id,
+            document,
+            question: q.question,
+            answer: Some(q.answer),
+            alt_answers: Vec::new(),
+            supporting_facts: q
+                .supporting_facts
+                .into_iter()
+                .map(|(title, sentence)| SupportingFact { title, sentence })
+                .collect(),
+            evidence,
+            options: None,
+            correct_option: None,
+            source: "HotpotQA".to_string(),
+            tags,
+        });
+    }
+
+    Ok(dataset)
+}
+
 /// Load a simple Q&A dataset from JSON.
 ///
 /// Expected format:
@@ -178,11 +576,14 @@ pub fn load_quality_dataset(path: &Path) -> Result<Dataset> {
 ///       "id": "1",
 ///       "document": "Document text...",
 ///       "question": "What is X?",
-///       "answer": "X is Y"
+///       "answer": "X is Y",
+///       "tags": { "difficulty": "hard", "topic": "finance" }
 ///     }
 ///   ]
 /// }
 /// ```
+///
+/// `tags` is optional; results are broken down by each tag value.
 pub fn load_simple_dataset(path: &Path) -> Result<Dataset> {
     Dataset::load_json(path)
 }
@@ -202,9 +603,13 @@ The first stable release, Rust 1.0, was announced in May 2015.
 "#.to_string(),
         question: "What mechanism does Rust use to achieve memory safety?".to_string(),
         answer: Some("Rust uses an ownership system and borrow checker to achieve memory safety without garbage collection.".to_string()),
+        alt_answers: Vec::new(),
+        supporting_facts: Vec::new(),
+        evidence: Vec::new(),
         options: None,
         correct_option: None,
         source: "sample".to_string(),
+        tags: BTreeMap::new(),
     });
 
     dataset.add_item(DatasetItem {
@@ -218,9 +623,13 @@ Python is widely used in data science, machine learning, and web development.
 "#.to_string(),
         question: "Who created Python and when was it first released?".to_string(),
         answer: Some("Python was created by Guido van Rossum and first released in 1991.".to_string()),
+        alt_answers: Vec::new(),
+        supporting_facts: Vec::new(),
+        evidence: Vec::new(),
         options: None,
         correct_option: None,
         source: "sample".to_string(),
+        tags: BTreeMap::new(),
     });
 
     dataset.add_item(DatasetItem {
@@ -234,9 +643,13 @@ Common applications include image recognition, natural language processing, and
 "#.to_string(),
         question: "What is the difference between supervised and unsupervised learning?".to_string(),
         answer: Some("Supervised learning uses labeled data to train models, while unsupervised learning finds patterns in unlabeled data.".to_string()),
+        alt_answers: Vec::new(),
+        supporting_facts: Vec::new(),
+        evidence: Vec::new(),
         options: None,
         correct_option: None,
         source: "sample".to_string(),
+        tags: BTreeMap::new(),
     });
 
     dataset
@@ -256,9 +669,13 @@ mod tests {
             document: "Test doc".to_string(),
             question: "Test question?".to_string(),
             answer: Some("Test answer".to_string()),
+            alt_answers: Vec::new(),
+            supporting_facts: Vec::new(),
+            evidence: Vec::new(),
             options: None,
             correct_option: None,
             source: "test".to_string(),
+            tags: BTreeMap::new(),
         });
 
         assert_eq!(dataset.len(), 1);
@@ -274,6 +691,207 @@ mod tests {
         assert_eq!(subset.len(), 2);
     }
 
+    #[test]
+    fn test_dataset_sample_and_shuffle() {
+        let mut dataset = Dataset::new("test");
+        for i in 0..20 {
+            dataset.add_item(DatasetItem {
+                id: i.to_string(),
+                document: String::new(),
+                question: String::new(),
+                answer: None,
+                alt_answers: Vec::new(),
+                supporting_facts: Vec::new(),
+                evidence: Vec::new(),
+                options: None,
+                correct_option: None,
+                source: "test".to_string(),
+                tags: BTreeMap::new(),
+            });
+        }
+        let ids = |d: &Dataset| d.items.iter().map(|i| i.id.clone()).collect::<Vec<_>>();
+
+        let sample = dataset.sample(5, 7);
+        assert_eq!(sample.len(), 5);
+        assert_eq!(ids(&sample), ids(&dataset.sample(5, 7)));
+        assert_ne!(ids(&sample), ids(&dataset.sample(5, 8)));
+        assert_ne!(ids(&sample), ids(&dataset.take(5)));
+        // Sampled items keep dataset order
+        let positions: Vec<usize> = sample.items.iter().map(|i| i.id.parse().unwrap()).collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(dataset.sample(50, 7).len(), 20);
+
+        let mut shuffled = dataset.clone();
+        shuffled.shuffle(7);
+        let mut again = dataset.clone();
+        again.shuffle(7);
+        assert_eq!(ids(&shuffled), ids(&again));
+        assert_ne!(ids(&shuffled), ids(&dataset));
+        assert_eq!(shuffled.len(), dataset.len());
+    }
+
+    #[test]
+    fn test_load_narrativeqa_dataset() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(
+            dir.path().join("documents.csv"),
+            "document_id,set,kind,story_url,story_file_size,wiki_url,wiki_title,story_word_count,story_start,story_end\n\
+             doc1,test,gutenberg,http://x,10,http://w,Title,5,Once upon,The End\n\
+             doc2,test,movie,http://y,10,http://w,Title,5,INT.,FADE OUT\n\
+             doc3,train,gutenberg,http://z,10,http://w,Title,5,A,B\n",
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join("qaps.csv"),
+            "document_id,set,question,answer1,answer2,question_tokenized,answer1_tokenized,answer2_tokenized\n\
+             doc1,test,Who is the hero?,A knight,The knight,q,a,a\n\
+             doc2,test,Where?,Paris,\"Paris, France\",q,a,a\n\
+             doc3,train,Why?,Because,Reasons,q,a,a\n",
+        )
+        .unwrap();
+        fs::create_dir(dir.path().join("stories")).unwrap();
+        fs::write(
+            dir.path().join("stories/doc1.content"),
+            "Gutenberg header\nOnce upon a time a knight.\nThe End\nLicense text",
+        )
+        .unwrap();
+
+        let dataset = load_narrativeqa_dataset(dir.path(), "test").unwrap();
+
+        // doc2's story isn't downloaded and doc3 is another split
+        assert_eq!(dataset.len(), 1);
+        let item = &dataset.items[0];
+        assert_eq!(item.id, "doc1_1");
+        assert_eq!(item.document, "Once upon a time a knight.\nThe End");
+        assert_eq!(item.answer.as_deref(), Some("A knight"));
+        assert_eq!(item.alt_answers, vec!["The knight".to_string()]);
+    }
+
+    #[test]
+    fn test_load_quality_dataset_filtered() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = quality_split_path(dir.path(), "dev");
+        let question = |id: &str, difficult: i32| {
+            serde_json::json!({
+                "question": "Why?",
+                "question_unique_id": id,
+                "options": ["a", "b", "c", "d"],
+                "gold_label": 2,
+                "difficult": difficult,
+            })
+        };
+        let article = |id: &str, questions: Vec<serde_json::Value>| {
+            serde_json::json!({
+                "article": format!("Article {}", id),
+                "article_id": id,
+                "set_unique_id": format!("{}_set", id),
+                "questions": questions,
+            })
+            .to_string()
+        };
+        let lines = [
+            article("a1", vec![question("q1", 0), question("q2", 1)]),
+            article("a2", vec![question("q3", 0)]),
+            article("a1", vec![question("q4", 1)]),
+            article("a3", vec![question("q5", 1)]),
+        ];
+        fs::write(&path, lines.join("\n")).unwrap();
+        let ids = |filter: QualityFilter| -> Vec<String> {
+            load_quality_dataset_filtered(&path, &filter)
+                .unwrap()
+                .items
+                .into_iter()
+                .map(|item| item.id)
+                .collect()
+        };
+
+        assert_eq!(ids(QualityFilter::default()).len(), 5);
+        assert_eq!(
+            ids(QualityFilter {
+                hard_only: true,
+                ..Default::default()
+            }),
+            vec!["q2", "q4", "q5"]
+        );
+        // The second annotation set of a1 still belongs to the first article
+        assert_eq!(
+            ids(QualityFilter {
+                max_articles: Some(1),
+                ..Default::default()
+            }),
+            vec!["q1", "q2", "q4"]
+        );
+        // a2 has no hard questions, so it doesn't count towards the limit
+        assert_eq!(
+            ids(QualityFilter {
+                hard_only: true,
+                max_articles: Some(2),
+            }),
+            vec!["q2", "q4", "q5"]
+        );
+    }
+
+    #[test]
+    fn test_load_hotpotqa_dataset() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("hotpot.json");
+        let json = serde_json::json!([{
+            "_id": "q1",
+            "question": "Which band was formed first?",
+            "answer": "Alpha",
+            "type": "comparison",
+            "level": "hard",
+            "supporting_facts": [["Alpha", 0], ["Beta", 1]],
+            "context": [
+                ["Alpha", ["Alpha is a band.", " It formed in 1970."]],
+                ["Beta", ["Beta is a band.", " It formed in 1980."]]
+            ]
+        }]);
+        fs::write(&path, json.to_string()).unwrap();
+
+        let dataset = load_hotpotqa_dataset(&path).unwrap();
+        assert_eq!(dataset.len(), 1);
+
+        let item = &dataset.items[0];
+        assert_eq!(
+            item.document,
+            "# Alpha\n\nAlpha is a band. It formed in 1970.\n\n# Beta\n\nBeta is a band. It formed in 1980."
+        );
+        assert_eq!(
+            item.evidence,
+            vec![
+                "Alpha is a band.".to_string(),
+                "It formed in 1980.".to_string()
+            ]
+        );
+        assert_eq!(
+            item.supporting_facts[1],
+            SupportingFact {
+                title: "Beta".to_string(),
+                sentence: 1
+            }
+        );
+
+        // Supporting facts survive a save/load round trip
+        let saved = dir.path().join("saved.json");
+        dataset.save_json(&saved).unwrap();
+        let reloaded = Dataset::load_json(&saved).unwrap();
+        assert_eq!(reloaded.items[0].supporting_facts, item.supporting_facts);
+
+        assert_eq!(item.tags["type"], "comparison");
+        assert_eq!(item.tags["difficulty"], "hard");
+        assert_eq!(reloaded.items[0].tags, item.tags);
+    }
+
+    #[test]
+    fn test_extract_story_strips_html() {
+        let raw = "<html><body><pre>Menu\nINT. HOUSE - DAY\nHello &amp; bye\nFADE OUT</pre></body></html>";
+        assert_eq!(
+            extract_story(raw, "INT.", "FADE OUT"),
+            "INT. HOUSE - DAY\nHello & bye\nFADE OUT"
+        );
+    }
+
     #[test]
     fn test_sample_dataset() {
         let dataset = create_sample_dataset();
@@ -1,167 +1,272 @@
-//! Local embedding model using candle + sentence-transformers.
+//! Sentence embeddings for the vector baseline.
+//!
+//! Embeddings come either from a local BERT-family model run with candle
+//! (`local-embeddings` feature) or from an OpenAI-compatible `/v1/embeddings`
+//! endpoint, which needs no model download or native dependencies.
+
+#[cfg(feature = "local-embeddings")]
+use super::local_embeddings::LocalModel;
+use crate::llm::{LlmClient, UsageStats};
+use anyhow::Result;
+use clap::ValueEnum;
+use std::str::FromStr;
+
+/// Default local embedding model.
+pub const DEFAULT_EMBEDDING_MODEL: &str = "sentence-transformers/all-MiniLM-L6-v2";
+
+/// Default model for the embeddings API.
+pub const DEFAULT_API_EMBEDDING_MODEL: &str = "text-embedding-3-small";
+
+/// Default number of texts embedded per forward pass or API request.
+pub const DEFAULT_BATCH_SIZE: usize = 32;
+
+/// Where embeddings are computed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum EmbeddingBackend {
+    /// Local candle model (requires the `local-embeddings` feature).
+    Local,
+    /// The LLM provider's embeddings endpoint.
+    Api,
+}
+
+impl Default for EmbeddingBackend {
+    /// Local when built with `local-embeddings`, otherwise the API.
+    fn default() -> Self {
+        if cfg!(feature = "local-embeddings") {
+            Self::Local
+        } else {
+            Self::Api
+        }
+    }
+}
+
+impl EmbeddingBackend {
+    /// Model used when none is configured.
+    pub fn default_model(self) -> &'static str {
+        match self {
+            Self::Local => DEFAULT_EMBEDDING_MODEL,
+            Self::Api => DEFAULT_API_EMBEDDING_MODEL,
+        }
+    }
+}
+
+/// Device to run the embedding model on.
+///
+/// CUDA and Metal require building with the `cuda` or `metal` feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EmbeddingDevice {
+    /// CUDA if available, then Metal, then CPU.
+    #[default]
+    Auto,
+    Cpu,
+    /// CUDA device by ordinal.
+    Cuda(usize),
+    Metal,
+}
+
+impl FromStr for EmbeddingDevice {
+    type Err = String;
+
+    /// Parse `auto`, `cpu`, `metal`, `cuda` or `cuda:<ordinal>`.
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "auto" => Ok(Self::Auto),
+            "cpu" => Ok(Self::Cpu),
+            "metal" => Ok(Self::Metal),
+            "cuda" => Ok(Self::Cuda(0)),
+            other => other
+                .strip_prefix("cuda:")
+                .and_then(|n| n.parse().ok())
+                .map(Self::Cuda)
+                .ok_or_else(|| {
+                    format!(
+                        "unknown device '{}' (expected auto, cpu, metal, cuda or cuda:N)",
+                        s
+                    )
+                }),
+        }
+    }
+}
 
-use anyhow::{Context, Result};
-use candle_core::{Device, Tensor};
-use candle_nn::VarBuilder;
-use candle_transformers::models::bert::{BertModel, Config as BertConfig, DTYPE};
-use hf_hub::{Repo, RepoType, api::sync::Api};
-use tokenizers::Tokenizer;
+/// How token embeddings are pooled into one sentence embedding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pooling {
+    /// Mask-weighted mean over tokens (sentence-transformers, E5, GTE).
+    Mean,
+    /// The `[CLS]` token's embedding (BGE).
+    Cls,
+}
+
+/// Pooling and instruction prefixes for an embedding model.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModelSpec {
+    /// Hugging Face (local) or provider (API) model id.
+    pub model_id: String,
+    pub pooling: Pooling,
+    /// Prepended to search queries.
+    pub query_prefix: &'static str,
+    /// Prepended to indexed passages.
+    pub passage_prefix: &'static str,
+}
+
+impl ModelSpec {
+    /// Resolve a model name or short alias (`minilm`, `bge-small`,
+    /// `bge-base`, `e5-small`, `e5-base`, `gte-small`, `gte-base`) to its
+    /// Hugging Face id, inferring pooling and prefixes from the model family.
+    pub fn resolve(name: &str) -> Self {
+        let model_id = match name.to_lowercase().as_str() {
+            "minilm" => DEFAULT_EMBEDDING_MODEL,
+            "bge-small" => "BAAI/bge-small-en-v1.5",
+            "bge-base" => "BAAI/bge-base-en-v1.5",
+            "e5-small" => "intfloat/e5-small-v2",
+            "e5-base" => "intfloat/e5-base-v2",
+            "gte-small" => "thenlper/gte-small",
+            "gte-base" => "thenlper/gte-base",
+            _ => name,
+        }
+        .to_string();
+
+        let family = model_id.rsplit('/').next().unwrap_or("").to_lowercase();
+        let (pooling, query_prefix, passage_prefix) = if family.starts_with("bge-") {
+            (
+                Pooling::Cls,
+                "Represent this sentence for searching relevant passages: ",
+                "",
+            )
+        } else if family.starts_with("e5-") || family.starts_with("multilingual-e5-") {
+            (Pooling::Mean, "query: ", "passage: ")
+        } else {
+            (Pooling::Mean, "", "")
+        };
+
+        Self {
+            model_id,
+            pooling,
+            query_prefix,
+            passage_prefix,
+        }
+    }
+}
+
+enum Backend {
+    #[cfg(feature = "local-embeddings")]
+    Local(Box<LocalModel>),
+    Api(LlmClient),
+}
 
 /// Embedding model for generating text embeddings.
 pub struct EmbeddingModel {
-    model: BertModel,
-    tokenizer: Tokenizer,
-    device: Device,
-    normalize: bool,
+    backend: Backend,
+    spec: ModelSpec,
+    batch_size: usize,
 }
 
 impl EmbeddingModel {
     /// Load the all-MiniLM-L6-v2 model from Hugging Face Hub.
+    #[cfg(feature = "local-embeddings")]
     pub fn load_minilm() -> Result<Self> {
-        Self::load("sentence-transformers/all-MiniLM-L6-v2")
-    }
-
-    /// Load a sentence-transformers model by name.
-    pub fn load(model_id: &str) -> Result<Self> {
-        let device = Device::Cpu; // Use CPU for portability
-
-        let api = Api::new().context("Failed to create HF Hub API")?;
-        let repo = api.repo(Repo::new(model_id.to_string(), RepoType::Model));
-
-        // Download model files
-        let config_path = repo
-            .get("config.json")
-            .context("Failed to get config.json")?;
-        let tokenizer_path = repo
-            .get("tokenizer.json")
-            .context("Failed to get tokenizer.json")?;
-        let weights_path = repo
-            .get("model.safetensors")
-            .or_else(|_| repo.get("pytorch_model.bin"))
-            .context("Failed to get model weights")?;
-
-        // Load config
-        let config: BertConfig = serde_json::from_str(&std::fs::read_to_string(&config_path)?)
-            .context("Failed to parse config")?;
-
-        // Load tokenizer
-        let tokenizer = Tokenizer::from_file(&tokenizer_path)
-            .map_err(|e| anyhow::anyhow!("Failed to load tokenizer: {}", e))?;
-
-        // Load model weights
-        let vb = unsafe {
-            VarBuilder::from_mmaped_safetensors(&[weights_path], DTYPE, &device)
-                .context("Failed to load model weights")?
-        };
+        Self::load(DEFAULT_EMBEDDING_MODEL)
+    }
 
-        let model = BertModel::load(vb, &config).context("Failed to load BERT model")?;
+    /// Load a BERT-family sentence embedding model by Hugging Face id or
+    /// alias (see [`ModelSpec::resolve`]) on the CPU.
+    #[cfg(feature = "local-embeddings")]
+    pub fn load(name: &str) -> Result<Self> {
+        Self::load_on(name, EmbeddingDevice::Cpu)
+    }
 
+    /// Load a model on the given device.
+    #[cfg(feature = "local-embeddings")]
+    pub fn load_on(name: &str, device: EmbeddingDevice) -> Result<Self> {
+        let spec = ModelSpec::resolve(name);
         Ok(Self {
-            model,
-            tokenizer,
-            device,
-            normalize: true,
+            backend: Backend::Local(Box::new(LocalModel::load(&spec, device)?)),
+            spec,
+            batch_size: DEFAULT_BATCH_SIZE,
         })
     }
 
-    /// Generate embedding for a single text.
-    pub fn embed(&self, text: &str) -> Result<Vec<f32>> {
-        let embeddings = self.embed_batch(&[text])?;
-        Ok(embeddings.into_iter().next().unwrap())
+    /// Embed through the client's `/v1/embeddings` endpoint, using the
+    /// client's configured model.
+    pub fn api(client: LlmClient) -> Self {
+        Self {
+            spec: ModelSpec::resolve(client.model()),
+            backend: Backend::Api(client),
+            batch_size: DEFAULT_BATCH_SIZE,
+        }
     }
 
-    /// Generate embeddings for a batch of texts.
-    pub fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
-        if texts.is_empty() {
-            return Ok(Vec::new());
-        }
+    /// Set the number of texts embedded per forward pass or API request.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
 
-        // Tokenize
-        let encodings = self
-            .tokenizer
-            .encode_batch(texts.to_vec(), true)
-            .map_err(|e| anyhow::anyhow!("Tokenization failed: {}", e))?;
+    /// Number of texts embedded per forward pass or API request.
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
 
-        let max_len = encodings
-            .iter()
-            .map(|e| e.get_ids().len())
-            .max()
-            .unwrap_or(0);
-
-        // Prepare tensors
-        let mut input_ids_vec = Vec::new();
-        let mut attention_mask_vec = Vec::new();
-        let mut token_type_ids_vec = Vec::new();
-
-        for encoding in &encodings {
-            let ids = encoding.get_ids();
-            let mask = encoding.get_attention_mask();
-
-            // Pad to max_len
-            let mut padded_ids = ids.to_vec();
-            let mut padded_mask = mask.to_vec();
-            let mut padded_types = vec![0u32; ids.len()];
-
-            padded_ids.resize(max_len, 0);
-            padded_mask.resize(max_len, 0);
-            padded_types.resize(max_len, 0);
-
-            input_ids_vec.extend(padded_ids);
-            attention_mask_vec.extend(padded_mask);
-            token_type_ids_vec.extend(padded_types);
+    /// Model id this model was loaded from.
+    pub fn model_id(&self) -> &str {
+        &self.spec.model_id
+    }
+
+    /// Pooling and prefixes used by this model.
+    pub fn spec(&self) -> &ModelSpec {
+        &self.spec
+    }
+
+    /// Token usage of the embeddings API (`None` for local models).
+    pub fn usage(&self) -> Option<UsageStats> {
+        match &self.backend {
+            #[cfg(feature = "local-embeddings")]
+            Backend::Local(_) => None,
+            Backend::Api(client) => Some(client.usage()),
         }
+    }
 
-        let batch_size = texts.len();
-
-        let input_ids = Tensor::from_vec(input_ids_vec, (batch_size, max_len), &self.device)?;
-        let attention_mask =
-            Tensor::from_vec(attention_mask_vec, (batch_size, max_len), &self.device)?;
-        let token_type_ids =
-            Tensor::from_vec(token_type_ids_vec, (batch_size, max_len), &self.device)?;
-
-        // Run model
-        let output = self
-            .model
-            .forward(&input_ids, &token_type_ids, Some(&attention_mask))?;
-
-        // Mean pooling over sequence dimension (with attention mask)
-        let attention_mask_expanded = attention_mask
-            .unsqueeze(2)?
-            .to_dtype(output.dtype())?
-            .broadcast_as(output.shape())?;
-
-        let sum_embeddings = (output * &attention_mask_expanded)?.sum(1)?;
-        let sum_mask = attention_mask_expanded.sum(1)?.clamp(1e-9, f64::MAX)?;
-        let mean_embeddings = (sum_embeddings / sum_mask)?;
-
-        // Normalize if requested
-        let final_embeddings = if self.normalize {
-            let norms = mean_embeddings.sqr()?.sum_keepdim(1)?.sqrt()?;
-            let shape = mean_embeddings.shape().clone();
-            (mean_embeddings / norms.broadcast_as(&shape)?)?
-        } else {
-            mean_embeddings
-        };
+    /// Embed a search query, with the model's query prefix.
+    pub async fn embed_query(&self, query: &str) -> Result<Vec<f32>> {
+        self.embed(&format!("{}{}", self.spec.query_prefix, query))
+            .await
+    }
 
-        // Convert to Vec<Vec<f32>>
-        let embeddings_vec: Vec<f32> = final_embeddings
-            .to_vec2::<f32>()?
-            .into_iter()
-            .flatten()
+    /// Embed passages for indexing, with the model's passage prefix.
+    pub async fn embed_passages(&self, passages: &[&str]) -> Result<Vec<Vec<f32>>> {
+        if self.spec.passage_prefix.is_empty() {
+            return self.embed_batch(passages).await;
+        }
+        let prefixed: Vec<String> = passages
+            .iter()
+            .map(|p| format!("{}{}", self.spec.passage_prefix, p))
             .collect();
+        let refs: Vec<&str> = prefixed.iter().map(String::as_str).collect();
+        self.embed_batch(&refs).await
+    }
 
-        let embedding_dim = final_embeddings.dim(1)?;
-        let result: Vec<Vec<f32>> = embeddings_vec
-            .chunks(embedding_dim)
-            .map(|c| c.to_vec())
-            .collect();
+    /// Generate embedding for a single text.
+    pub async fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let embeddings = self.embed_batch(&[text]).await?;
+        Ok(embeddings.into_iter().next().unwrap())
+    }
 
-        Ok(result)
+    /// Generate embeddings for a batch of texts.
+    pub async fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        match &self.backend {
+            #[cfg(feature = "local-embeddings")]
+            Backend::Local(model) => model.embed_batch(texts),
+            Backend::Api(client) => Ok(client.embed(texts).await?),
+        }
     }
 
-    /// Get embedding dimension.
-    pub fn dimension(&self) -> usize {
-        384 // all-MiniLM-L6-v2 has 384 dimensions
+    /// Get embedding dimension (`None` for API models).
+    pub fn dimension(&self) -> Option<usize> {
+        match &self.backend {
+            #[cfg(feature = "local-embeddings")]
+            Backend::Local(model) => Some(model.dimension()),
+            Backend::Api(_) => None,
+        }
     }
 }
 
@@ -195,4 +300,40 @@ mod tests {
         let c = vec![0.0, 1.0, 0.0];
         assert!(cosine_similarity(&a, &c).abs() < 1e-6);
     }
+
+    #[test]
+    fn test_parse_device() {
+        assert_eq!("auto".parse(), Ok(EmbeddingDevice::Auto));
+        assert_eq!("CPU".parse(), Ok(EmbeddingDevice::Cpu));
+        assert_eq!("cuda".parse(), Ok(EmbeddingDevice::Cuda(0)));
+        assert_eq!("cuda:2".parse(), Ok(EmbeddingDevice::Cuda(2)));
+        assert_eq!("metal".parse(), Ok(EmbeddingDevice::Metal));
+        assert!("cuda:x".parse::<EmbeddingDevice>().is_err());
+        assert!("tpu".parse::<EmbeddingDevice>().is_err());
+    }
+
+    #[test]
+    fn test_resolve_model_spec() {
+        let minilm = ModelSpec::resolve("minilm");
+        assert_eq!(minilm.model_id, DEFAULT_EMBEDDING_MODEL);
+        assert_eq!(minilm.pooling, Pooling::Mean);
+        assert_eq!(minilm.query_prefix, "");
+
+        let bge = ModelSpec::resolve("bge-small");
+        assert_eq!(bge.model_id, "BAAI/bge-small-en-v1.5");
+        assert_eq!(bge.pooling, Pooling::Cls);
+        assert!(bge.query_prefix.starts_with("Represent"));
+        assert_eq!(bge.passage_prefix, "");
+
+        let e5 = ModelSpec::resolve("intfloat/e5-large-v2");
+        assert_eq!(e5.model_id, "intfloat/e5-large-v2");
+        assert_eq!(
+            (e5.query_prefix, e5.passage_prefix),
+            ("query: ", "passage: ")
+        );
+
+        let gte = ModelSpec::resolve("GTE-small");
+        assert_eq!(gte.model_id, "thenlper/gte-small");
+        assert_eq!((gte.pooling, gte.query_prefix), (Pooling::Mean, ""));
+    }
 }
@@ -0,0 +1,376 @@
+//! Flat, item-level export of benchmark results for pandas, Excel and the
+//! like.
+//!
+//! Each item becomes one row: judge verdict and scores, latencies, retrieval
+//! and answer metrics, token usage, one `tag_<name>` column per tag, and a
+//! group of columns per additional baseline. CSV is always available;
+//! Parquet requires the `parquet` feature.
+
+use super::benchmark::{BaselineResult, BenchmarkResults, ItemResult, ItemUsage};
+use super::judge::ComparisonResult;
+use anyhow::Result;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+/// One column's values, one per item.
+#[derive(Debug, Clone, PartialEq)]
+enum Values {
+    Int(Vec<Option<u64>>),
+    Float(Vec<Option<f64>>),
+    Bool(Vec<Option<bool>>),
+    Text(Vec<Option<String>>),
+}
+
+impl Values {
+    /// A value formatted for CSV (empty when missing).
+    fn cell(&self, row: usize) -> String {
+        match self {
+            Self::Int(v) => v[row].map_or(String::new(), |x| x.to_string()),
+            Self::Float(v) => v[row].map_or(String::new(), |x| x.to_string()),
+            Self::Bool(v) => v[row].map_or(String::new(), |x| x.to_string()),
+            Self::Text(v) => v[row].clone().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Column {
+    name: String,
+    values: Values,
+}
+
+/// Judge verdict label for a comparison.
+fn winner(comparison: &ComparisonResult, system2: &str) -> String {
+    match comparison.winner {
+        1 => "PageIndex".to_string(),
+        2 => system2.to_string(),
+        _ => "Tie".to_string(),
+    }
+}
+
+/// Total tokens a system used on an item.
+fn tokens(usage: Option<ItemUsage>) -> Option<u64> {
+    usage.map(|u| u.index.total_tokens + u.query.total_tokens)
+}
+
+fn int(items: &[ItemResult], name: &str, f: impl Fn(&ItemResult) -> Option<u64>) -> Column {
+    Column {
+        name: name.to_string(),
+        values: Values::Int(items.iter().map(f).collect()),
+    }
+}
+
+fn float(items: &[ItemResult], name: &str, f: impl Fn(&ItemResult) -> Option<f64>) -> Column {
+    Column {
+        name: name.to_string(),
+        values: Values::Float(items.iter().map(f).collect()),
+    }
+}
+
+fn boolean(items: &[ItemResult], name: &str, f: impl Fn(&ItemResult) -> Option<bool>) -> Column {
+    Column {
+        name: name.to_string(),
+        values: Values::Bool(items.iter().map(f).collect()),
+    }
+}
+
+fn text(items: &[ItemResult], name: &str, f: impl Fn(&ItemResult) -> Option<String>) -> Column {
+    Column {
+        name: name.to_string(),
+        values: Values::Text(items.iter().map(f).collect()),
+    }
+}
+
+/// An item's result for a baseline.
+fn baseline<'a>(result: &'a ItemResult, system: &str) -> Option<&'a BaselineResult> {
+    result.baselines.iter().find(|b| b.system == system)
+}
+
+/// Build the export columns.
+fn columns(results: &BenchmarkResults) -> Vec<Column> {
+    let items = &results.item_results;
+    let score = |r: &ItemResult, system: u8| {
+        r.comparison.as_ref().map(|c| match system {
+            1 => c.score_system1 as f64,
+            _ => c.score_system2 as f64,
+        })
+    };
+
+    let mut columns = vec![
+        text(items, "item_id", |r| Some(r.item_id.clone())),
+        text(items, "winner", |r| {
+            r.comparison.as_ref().map(|c| winner(c, "Vector"))
+        }),
+        float(items, "pageindex_score", |r| score(r, 1)),
+        float(items, "vector_score", |r| score(r, 2)),
+        int(items, "pageindex_time_ms", |r| r.pageindex_time_ms),
+        int(items, "pageindex_build_time_ms", |r| {
+            r.pageindex_build_time_ms
+        }),
+        int(items, "vector_time_ms", |r| r.vector_time_ms),
+        float(items, "pageindex_recall_at_k", |r| {
+            r.pageindex_retrieval.map(|m| m.recall_at_k)
+        }),
+        float(items, "pageindex_mrr", |r| {
+            r.pageindex_retrieval.map(|m| m.mrr)
+        }),
+        float(items, "pageindex_ndcg_at_k", |r| {
+            r.pageindex_retrieval.map(|m| m.ndcg_at_k)
+        }),
+        float(items, "vector_recall_at_k", |r| {
+            r.vector_retrieval.map(|m| m.recall_at_k)
+        }),
+        float(items, "vector_mrr", |r| r.vector_retrieval.map(|m| m.mrr)),
+        float(items, "vector_ndcg_at_k", |r| {
+            r.vector_retrieval.map(|m| m.ndcg_at_k)
+        }),
+        float(items, "pageindex_exact_match", |r| {
+            r.pageindex_answer_metrics.map(|m| m.exact_match)
+        }),
+        float(items, "pageindex_f1", |r| {
+            r.pageindex_answer_metrics.map(|m| m.f1)
+        }),
+        float(items, "vector_exact_match", |r| {
+            r.vector_answer_metrics.map(|m| m.exact_match)
+        }),
+        float(items, "vector_f1", |r| {
+            r.vector_answer_metrics.map(|m| m.f1)
+        }),
+        int(items, "pageindex_choice", |r| {
+            r.pageindex_choice.map(|c| c as u64)
+        }),
+        int(items, "vector_choice", |r| {
+            r.vector_choice.map(|c| c as u64)
+        }),
+        boolean(items, "pageindex_correct", |r| r.pageindex_correct),
+        boolean(items, "vector_correct", |r| r.vector_correct),
+        int(items, "pageindex_grade", |r| {
+            r.pageindex_grade.as_ref().map(|g| g.score as u64)
+        }),
+        boolean(items, "pageindex_grade_correct", |r| {
+            r.pageindex_grade.as_ref().map(|g| g.correct)
+        }),
+        int(items, "vector_grade", |r| {
+            r.vector_grade.as_ref().map(|g| g.score as u64)
+        }),
+        boolean(items, "vector_grade_correct", |r| {
+            r.vector_grade.as_ref().map(|g| g.correct)
+        }),
+        int(items, "pageindex_groundedness", |r| {
+            r.pageindex_groundedness.as_ref().map(|g| g.score as u64)
+        }),
+        boolean(items, "pageindex_grounded", |r| {
+            r.pageindex_groundedness.as_ref().map(|g| g.grounded)
+        }),
+        int(items, "vector_groundedness", |r| {
+            r.vector_groundedness.as_ref().map(|g| g.score as u64)
+        }),
+        boolean(items, "vector_grounded", |r| {
+            r.vector_groundedness.as_ref().map(|g| g.grounded)
+        }),
+        int(items, "pageindex_tokens", |r| tokens(r.pageindex_usage)),
+        int(items, "vector_tokens", |r| tokens(r.vector_usage)),
+        int(items, "judge_tokens", |r| Some(r.judge_usage.total_tokens)),
+    ];
+
+    let tags: BTreeSet<&String> = items.iter().flat_map(|r| r.tags.keys()).collect();
+    for tag in tags {
+        columns.push(text(items, &format!("tag_{}", tag), |r| {
+            r.tags.get(tag).cloned()
+        }));
+    }
+
+    let mut systems: Vec<&str> = Vec::new();
+    for baseline in items.iter().flat_map(|r| &r.baselines) {
+        if !systems.contains(&baseline.system.as_str()) {
+            systems.push(&baseline.system);
+        }
+    }
+    for system in systems {
+        let prefix = system.to_lowercase();
+        let name = |column: &str| format!("{}_{}", prefix, column);
+        columns.extend([
+            text(items, &name("winner"), |r| {
+                baseline(r, system)?
+                    .comparison
+                    .as_ref()
+                    .map(|c| winner(c, system))
+            }),
+            float(items, &name("score"), |r| {
+                baseline(r, system)?
+                    .comparison
+                    .as_ref()
+                    .map(|c| c.score_system2 as f64)
+            }),
+            int(items, &name("time_ms"), |r| baseline(r, system)?.time_ms),
+            float(items, &name("recall_at_k"), |r| {
+                baseline(r, system)?.retrieval.map(|m| m.recall_at_k)
+            }),
+            float(items, &name("f1"), |r| {
+                baseline(r, system)?.answer_metrics.map(|m| m.f1)
+            }),
+            boolean(items, &name("correct"), |r| baseline(r, system)?.correct),
+            int(items, &name("grade"), |r| {
+                baseline(r, system)?.grade.as_ref().map(|g| g.score as u64)
+            }),
+            boolean(items, &name("grounded"), |r| {
+                baseline(r, system)?
+                    .groundedness
+                    .as_ref()
+                    .map(|g| g.grounded)
+            }),
+            int(items, &name("tokens"), |r| {
+                tokens(baseline(r, system)?.usage)
+            }),
+            text(items, &name("answer"), |r| {
+                baseline(r, system)?.answer.clone()
+            }),
+        ]);
+    }
+
+    columns.extend([
+        text(items, "pageindex_answer", |r| r.pageindex_answer.clone()),
+        text(items, "vector_answer", |r| r.vector_answer.clone()),
+        text(items, "error", |r| r.error.clone()),
+    ]);
+    columns
+}
+
+/// Write item-level results as CSV.
+pub fn write_csv(results: &BenchmarkResults, path: &Path) -> Result<()> {
+    let columns = columns(results);
+    let mut writer = csv::Writer::from_path(path)?;
+    writer.write_record(columns.iter().map(|c| &c.name))?;
+    for row in 0..results.item_results.len() {
+        writer.write_record(columns.iter().map(|c| c.values.cell(row)))?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write item-level results as Parquet, with typed (nullable) columns.
+#[cfg(feature = "parquet")]
+pub fn write_parquet(results: &BenchmarkResults, path: &Path) -> Result<()> {
+    use arrow_array::{
+        ArrayRef, BooleanArray, Float64Array, RecordBatch, StringArray, UInt64Array,
+    };
+    use arrow_schema::{DataType, Field, Schema};
+    use parquet::arrow::ArrowWriter;
+    use std::sync::Arc;
+
+    let (fields, arrays): (Vec<Field>, Vec<ArrayRef>) = columns(results)
+        .into_iter()
+        .map(|column| {
+            let (data_type, array): (DataType, ArrayRef) = match column.values {
+                Values::Int(v) => (DataType::UInt64, Arc::new(UInt64Array::from(v))),
+                Values::Float(v) => (DataType::Float64, Arc::new(Float64Array::from(v))),
+                Values::Bool(v) => (DataType::Boolean, Arc::new(BooleanArray::from(v))),
+                Values::Text(v) => (DataType::Utf8, Arc::new(StringArray::from(v))),
+            };
+            (Field::new(column.name, data_type, true), array)
+        })
+        .unzip();
+    let schema = Arc::new(Schema::new(fields));
+    let batch = RecordBatch::try_new(schema.clone(), arrays)?;
+
+    let mut writer = ArrowWriter::try_new(std::fs::File::create(path)?, schema, None)?;
+    writer.write(&batch)?;
+    writer.close()?;
+    Ok(())
+}
+
+/// Whether [`write_export`] writes this path as Parquet.
+pub fn is_parquet(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("parquet"))
+}
+
+/// Write item-level results as Parquet if the path ends in `.parquet`,
+/// otherwise as CSV.
+pub fn write_export(results: &BenchmarkResults, path: &Path) -> Result<()> {
+    if !is_parquet(path) {
+        return write_csv(results, path);
+    }
+    #[cfg(feature = "parquet")]
+    return write_parquet(results, path);
+    #[cfg(not(feature = "parquet"))]
+    anyhow::bail!("Parquet export requires the `parquet` feature; use a .csv path instead")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_results() -> BenchmarkResults {
+        let mut results = BenchmarkResults::new("test");
+        for (id, winner) in [("a", 1), ("b", 2)] {
+            let mut item: ItemResult =
+                serde_json::from_value(serde_json::json!({ "item_id": id })).unwrap();
+            item.pageindex_time_ms = Some(100);
+            item.pageindex_answer = Some("Paris, \"France\"".to_string());
+            item.comparison = Some(ComparisonResult {
+                winner,
+                score_system1: 4.0,
+                score_system2: 3.5,
+                explanation: String::new(),
+                order_consistent: None,
+                judges: Vec::new(),
+                agreement: None,
+            });
+            results.item_results.push(item);
+        }
+        let first = &mut results.item_results[0];
+        first
+            .tags
+            .insert("difficulty".to_string(), "hard".to_string());
+        let mut baseline: BaselineResult =
+            serde_json::from_value(serde_json::json!({ "system": "BM25" })).unwrap();
+        baseline.time_ms = Some(3);
+        first.baselines.push(baseline);
+        results
+    }
+
+    #[test]
+    fn test_write_csv() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("items.csv");
+        write_export(&sample_results(), &path).unwrap();
+
+        let mut reader = csv::Reader::from_path(&path).unwrap();
+        let headers = reader.headers().unwrap().clone();
+        let column = |name: &str| headers.iter().position(|h| h == name).unwrap();
+        let rows: Vec<csv::StringRecord> = reader.records().map(|r| r.unwrap()).collect();
+
+        assert_eq!(rows.len(), 2);
+        assert_eq!(&headers[0], "item_id");
+        assert_eq!(&rows[0][column("winner")], "PageIndex");
+        assert_eq!(&rows[1][column("winner")], "Vector");
+        assert_eq!(&rows[0][column("vector_score")], "3.5");
+        assert_eq!(&rows[0][column("pageindex_time_ms")], "100");
+        assert_eq!(&rows[0][column("pageindex_answer")], "Paris, \"France\"");
+        assert_eq!(&rows[0][column("tag_difficulty")], "hard");
+        assert_eq!(&rows[1][column("tag_difficulty")], "");
+        assert_eq!(&rows[0][column("bm25_time_ms")], "3");
+        assert_eq!(&rows[1][column("bm25_time_ms")], "");
+    }
+
+    #[cfg(feature = "parquet")]
+    #[test]
+    fn test_write_parquet() {
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("items.parquet");
+        let results = sample_results();
+        write_export(&results, &path).unwrap();
+
+        let reader = ParquetRecordBatchReaderBuilder::try_new(std::fs::File::open(&path).unwrap())
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches: Vec<_> = reader.map(|b| b.unwrap()).collect();
+        assert_eq!(batches.iter().map(|b| b.num_rows()).sum::<usize>(), 2);
+        assert_eq!(batches[0].num_columns(), columns(&results).len());
+    }
+}
@@ -0,0 +1,298 @@
+//! Hierarchical Navigable Small World (HNSW) graph for approximate
+//! nearest-neighbor search over embeddings.
+//!
+//! Follows Malkov & Yashunin (2016): each vector is inserted at a random
+//! maximum layer, greedy search descends from the sparse top layer, and a
+//! beam search of width `ef` runs on the layers the vector belongs to. The
+//! graph only stores links; vectors stay in the owning
+//! [`VectorIndex`](super::VectorIndex) and are passed in on each call, in
+//! the same order they were inserted.
+
+use super::embeddings::cosine_similarity;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashSet};
+
+/// Seed for layer assignment, so the same vectors always give the same graph.
+const SEED: u64 = 0x45E5;
+
+/// HNSW construction and search parameters.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HnswConfig {
+    /// Links per node on upper layers (twice this on layer 0).
+    pub m: usize,
+    /// Beam width while inserting.
+    pub ef_construction: usize,
+    /// Beam width while searching (raised to `top_k` if smaller).
+    pub ef_search: usize,
+}
+
+impl Default for HnswConfig {
+    fn default() -> Self {
+        Self {
+            m: 16,
+            ef_construction: 100,
+            ef_search: 50,
+        }
+    }
+}
+
+/// A candidate node and its distance to the query.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Candidate {
+    distance: f32,
+    id: usize,
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.distance
+            .total_cmp(&other.distance)
+            .then(self.id.cmp(&other.id))
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// HNSW graph over a fixed set of vectors.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HnswIndex {
+    config: HnswConfig,
+    /// `links[node][layer]` are the node's neighbors on that layer.
+    links: Vec<Vec<Vec<usize>>>,
+    entry_point: Option<usize>,
+}
+
+impl HnswIndex {
+    /// Build a graph over `vectors`.
+    pub fn build<T: AsRef<[f32]>>(vectors: &[T], config: HnswConfig) -> Self {
+        let mut index = Self {
+            config,
+            links: Vec::with_capacity(vectors.len()),
+            entry_point: None,
+        };
+        let mut rng = StdRng::seed_from_u64(SEED);
+        let level_mult = 1.0 / (index.config.m.max(2) as f64).ln();
+
+        for id in 0..vectors.len() {
+            let r: f64 = rng.random_range(f64::EPSILON..1.0);
+            let level = (-r.ln() * level_mult).floor() as usize;
+            index.insert(vectors, id, level);
+        }
+        index
+    }
+
+    /// Parameters the graph was built with.
+    pub fn config(&self) -> &HnswConfig {
+        &self.config
+    }
+
+    /// Number of indexed vectors.
+    pub fn len(&self) -> usize {
+        self.links.len()
+    }
+
+    /// Check if the graph is empty.
+    pub fn is_empty(&self) -> bool {
+        self.links.is_empty()
+    }
+
+    /// Approximate `top_k` nearest vectors to `query`, as `(id, similarity)`
+    /// pairs ordered by descending cosine similarity.
+    pub fn search<T: AsRef<[f32]>>(
+        &self,
+        vectors: &[T],
+        query: &[f32],
+        top_k: usize,
+    ) -> Vec<(usize, f32)> {
+        let Some(entry) = self.entry_point else {
+            return Vec::new();
+        };
+
+        let mut nearest = vec![Self::candidate(vectors, query, entry)];
+        for layer in (1..=self.top_layer()).rev() {
+            nearest = self.search_layer(vectors, query, &nearest, 1, layer);
+        }
+        let ef = self.config.ef_search.max(top_k);
+        let mut results = self.search_layer(vectors, query, &nearest, ef, 0);
+        results.truncate(top_k);
+
+        results
+            .into_iter()
+            .map(|c| (c.id, 1.0 - c.distance))
+            .collect()
+    }
+
+    fn top_layer(&self) -> usize {
+        self.entry_point
+            .map(|e| self.links[e].len() - 1)
+            .unwrap_or(0)
+    }
+
+    fn candidate<T: AsRef<[f32]>>(vectors: &[T], query: &[f32], id: usize) -> Candidate {
+        Candidate {
+            distance: 1.0 - cosine_similarity(query, vectors[id].as_ref()),
+            id,
+        }
+    }
+
+    fn insert<T: AsRef<[f32]>>(&mut self, vectors: &[T], id: usize, level: usize) {
+        self.links.push(vec![Vec::new(); level + 1]);
+        let Some(entry) = self.entry_point else {
+            self.entry_point = Some(id);
+            return;
+        };
+        let query = vectors[id].as_ref();
+        let top_layer = self.top_layer();
+
+        let mut nearest = vec![Self::candidate(vectors, query, entry)];
+        for layer in (level + 1..=top_layer).rev() {
+            nearest = self.search_layer(vectors, query, &nearest, 1, layer);
+        }
+
+        for layer in (0..=level.min(top_layer)).rev() {
+            nearest =
+                self.search_layer(vectors, query, &nearest, self.config.ef_construction, layer);
+            let max_links = self.max_links(layer);
+            let neighbors: Vec<usize> = nearest.iter().take(self.config.m).map(|c| c.id).collect();
+
+            for &neighbor in &neighbors {
+                let links = &mut self.links[neighbor][layer];
+                links.push(id);
+                if links.len() > max_links {
+                    self.prune(vectors, neighbor, layer, max_links);
+                }
+            }
+            self.links[id][layer] = neighbors;
+        }
+
+        if level > top_layer {
+            self.entry_point = Some(id);
+        }
+    }
+
+    fn max_links(&self, layer: usize) -> usize {
+        if layer == 0 {
+            self.config.m * 2
+        } else {
+            self.config.m
+        }
+    }
+
+    /// Keep only a node's `max_links` closest neighbors on a layer.
+    fn prune<T: AsRef<[f32]>>(
+        &mut self,
+        vectors: &[T],
+        node: usize,
+        layer: usize,
+        max_links: usize,
+    ) {
+        let mut candidates: Vec<Candidate> = self.links[node][layer]
+            .iter()
+            .map(|&n| Self::candidate(vectors, vectors[node].as_ref(), n))
+            .collect();
+        candidates.sort();
+        self.links[node][layer] = candidates
+            .into_iter()
+            .take(max_links)
+            .map(|c| c.id)
+            .collect();
+    }
+
+    /// Beam search on one layer, returning up to `ef` candidates sorted by
+    /// ascending distance.
+    fn search_layer<T: AsRef<[f32]>>(
+        &self,
+        vectors: &[T],
+        query: &[f32],
+        entry_points: &[Candidate],
+        ef: usize,
+        layer: usize,
+    ) -> Vec<Candidate> {
+        let mut visited: HashSet<usize> = entry_points.iter().map(|c| c.id).collect();
+        let mut to_visit: BinaryHeap<Reverse<Candidate>> =
+            entry_points.iter().copied().map(Reverse).collect();
+        let mut found: BinaryHeap<Candidate> = entry_points.iter().copied().collect();
+
+        while let Some(Reverse(current)) = to_visit.pop() {
+            if found.len() >= ef && found.peek().is_some_and(|f| current.distance > f.distance) {
+                break;
+            }
+            for &neighbor in &self.links[current.id][layer] {
+                if !visited.insert(neighbor) {
+                    continue;
+                }
+                let candidate = Self::candidate(vectors, query, neighbor);
+                if found.len() < ef || found.peek().is_some_and(|f| candidate < *f) {
+                    to_visit.push(Reverse(candidate));
+                    found.push(candidate);
+                    if found.len() > ef {
+                        found.pop();
+                    }
+                }
+            }
+        }
+
+        found.into_sorted_vec()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn random_vectors(n: usize, dim: usize, seed: u64) -> Vec<Vec<f32>> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..n)
+            .map(|_| (0..dim).map(|_| rng.random_range(-1.0..1.0)).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_recall_against_brute_force() {
+        let vectors = random_vectors(1000, 16, 1);
+        let queries = random_vectors(20, 16, 2);
+        let index = HnswIndex::build(&vectors, HnswConfig::default());
+        assert_eq!(index.len(), 1000);
+
+        let k = 10;
+        let mut hits = 0;
+        for query in &queries {
+            let mut exact: Vec<(usize, f32)> = vectors
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (i, cosine_similarity(query, v)))
+                .collect();
+            exact.sort_by(|a, b| b.1.total_cmp(&a.1));
+            let exact: HashSet<usize> = exact.iter().take(k).map(|(i, _)| *i).collect();
+
+            let approx = index.search(&vectors, query, k);
+            assert_eq!(approx.len(), k);
+            assert!(approx.windows(2).all(|w| w[0].1 >= w[1].1));
+            hits += approx.iter().filter(|(i, _)| exact.contains(i)).count();
+        }
+        let recall = hits as f64 / (k * queries.len()) as f64;
+        assert!(recall > 0.9, "recall@{} = {}", k, recall);
+    }
+
+    #[test]
+    fn test_small_and_empty() {
+        let none: Vec<Vec<f32>> = Vec::new();
+        let empty = HnswIndex::build(&none, HnswConfig::default());
+        assert!(empty.search(&none, &[1.0, 0.0], 3).is_empty());
+
+        let vectors = vec![vec![1.0, 0.0], vec![0.0, 1.0], vec![0.7, 0.7]];
+        let index = HnswIndex::build(&vectors, HnswConfig::default());
+        let results = index.search(&vectors, &[1.0, 0.1], 5);
+        assert_eq!(results.len(), 3);
+        assert_eq!(results[0].0, 0);
+    }
+}
@@ -0,0 +1,116 @@
+//! Hybrid retrieval baseline: BM25 and vector rankings fused with
+//! reciprocal rank fusion (RRF).
+//!
+//! Each chunk scores `sum(1 / (k + rank))` over the rankings it appears in
+//! (ranks are 1-based), so chunks ranked well by both retrievers rise to the
+//! top without having to calibrate BM25 scores against cosine similarities.
+
+use super::bm25::{Bm25Config, Bm25Index};
+use super::embeddings::EmbeddingModel;
+use super::vector_search::{Chunk, VectorIndex, VectorSearcher};
+use anyhow::Result;
+use std::collections::HashMap;
+
+/// Default RRF constant, from Cormack et al. (2009).
+pub const DEFAULT_RRF_K: f32 = 60.0;
+
+/// Fuse rankings of item ids (best first) into `(id, score)` pairs sorted by
+/// descending RRF score. Ties keep first-seen order.
+pub fn reciprocal_rank_fusion(rankings: &[Vec<usize>], k: f32) -> Vec<(usize, f32)> {
+    let mut scores: HashMap<usize, f32> = HashMap::new();
+    let mut order: Vec<usize> = Vec::new();
+    for ranking in rankings {
+        for (rank, &id) in ranking.iter().enumerate() {
+            let score = scores.entry(id).or_insert_with(|| {
+                order.push(id);
+                0.0
+            });
+            *score += 1.0 / (k + rank as f32 + 1.0);
+        }
+    }
+
+    let mut fused: Vec<(usize, f32)> = order.into_iter().map(|id| (id, scores[&id])).collect();
+    fused.sort_by(|a, b| b.1.total_cmp(&a.1));
+    fused
+}
+
+/// Hybrid search result.
+#[derive(Debug, Clone)]
+pub struct HybridSearchResult {
+    /// The matched chunk.
+    pub chunk: Chunk,
+    /// Fused RRF score.
+    pub score: f32,
+    /// 1-based rank in the vector ranking.
+    pub vector_rank: Option<usize>,
+    /// 1-based rank in the BM25 ranking (`None` if no query term matched).
+    pub bm25_rank: Option<usize>,
+}
+
+/// Search a vector index's chunks with both embeddings and BM25 and fuse
+/// the rankings.
+pub async fn hybrid_search(
+    index: &VectorIndex,
+    model: &EmbeddingModel,
+    query: &str,
+    top_k: usize,
+    rrf_k: f32,
+) -> Result<Vec<HybridSearchResult>> {
+    let chunks: Vec<Chunk> = index.entries().iter().map(|e| e.chunk.clone()).collect();
+    let bm25 = Bm25Index::from_chunks(chunks.clone(), Bm25Config::default());
+
+    let vector_ranking: Vec<usize> = VectorSearcher::new(index, model)
+        .search(query, chunks.len())
+        .await?
+        .into_iter()
+        .map(|r| r.chunk.index)
+        .collect();
+    let bm25_ranking: Vec<usize> = bm25
+        .search(query, chunks.len())
+        .into_iter()
+        .map(|r| r.chunk.index)
+        .collect();
+
+    let rank_of =
+        |ranking: &[usize], id: usize| ranking.iter().position(|&i| i == id).map(|p| p + 1);
+    let by_index: HashMap<usize, &Chunk> = chunks.iter().map(|c| (c.index, c)).collect();
+
+    Ok(
+        reciprocal_rank_fusion(&[vector_ranking.clone(), bm25_ranking.clone()], rrf_k)
+            .into_iter()
+            .take(top_k)
+            .map(|(id, score)| HybridSearchResult {
+                chunk: by_index[&id].clone(),
+                score,
+                vector_rank: rank_of(&vector_ranking, id),
+                bm25_rank: rank_of(&bm25_ranking, id),
+            })
+            .collect(),
+    )
+}
+
+/// Concatenate search results into a context string.
+pub fn format_context(results: &[HybridSearchResult]) -> String {
+    results
+        .iter()
+        .map(|r| format!("[RRF: {:.4}]\n{}", r.score, r.chunk.text))
+        .collect::<Vec<_>>()
+        .join("\n\n---\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reciprocal_rank_fusion() {
+        // 2 is ranked well by both, 0 and 4 top only one ranking each
+        let fused = reciprocal_rank_fusion(&[vec![0, 2, 1], vec![4, 2, 3]], 60.0);
+        let ids: Vec<usize> = fused.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![2, 0, 4, 1, 3]);
+        assert!((fused[0].1 - 2.0 / 62.0).abs() < 1e-6);
+        assert!((fused[1].1 - 1.0 / 61.0).abs() < 1e-6);
+
+        assert!(reciprocal_rank_fusion(&[vec![], vec![]], 60.0).is_empty());
+    }
+}
@@ -1,9 +1,23 @@
 //! LLM-as-Judge evaluation framework.
+//!
+//! Pairwise comparisons can optionally be run in both A/B orders and
+//! reconciled, to cancel out the judge's preference for the first position,
+//! and by an ensemble of judge models whose verdicts are aggregated. A
+//! [`Rubric`] replaces the built-in judging criteria. A [`JudgeCache`]
+//! persists comparison verdicts so reruns with unchanged answers don't pay
+//! for judging again.
 
+use super::rubric::{CriterionScore, Rubric};
 use crate::config::LlmConfig;
-use crate::error::Result;
-use crate::llm::LlmClient;
+use crate::error::{PageIndexError, Result};
+use crate::llm::{LlmClient, UsageStats};
+use crate::tree::fnv1a_hex;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 /// Result from judging a single retrieval.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -14,6 +28,12 @@ pub struct JudgeResult {
     pub answerable: bool,
     /// Explanation from the judge.
     pub explanation: String,
+    /// Per-criterion scores (when judged with a rubric).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub criteria: Vec<CriterionScore>,
+    /// Weighted mean of the criterion scores (when judged with a rubric).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub weighted_score: Option<f64>,
 }
 
 /// Result comparing two systems.
@@ -21,23 +41,365 @@ pub struct JudgeResult {
 pub struct ComparisonResult {
     /// Which system won (1 = first, 2 = second, 0 = tie).
     pub winner: u8,
-    /// Score for first system (1-5).
-    pub score_system1: u8,
-    /// Score for second system (1-5).
-    pub score_system2: u8,
+    /// Score for first system (1-5; averaged across orders when swapped).
+    pub score_system1: f32,
+    /// Score for second system (1-5; averaged across orders when swapped).
+    pub score_system2: f32,
     /// Explanation of the comparison.
     pub explanation: String,
+    /// Whether both presentation orders picked the same winner
+    /// (`None` when judged in a single order).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub order_consistent: Option<bool>,
+    /// Individual verdicts when judged by an ensemble.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub judges: Vec<JudgeVerdict>,
+    /// Fraction of ensemble judges agreeing with the majority verdict.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agreement: Option<f64>,
+}
+
+/// Pointwise grade of one answer against the gold answer.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnswerGrade {
+    /// Correctness score (1-5).
+    pub score: u8,
+    /// Whether the answer is judged correct.
+    pub correct: bool,
+    /// Explanation from the judge.
+    pub explanation: String,
+}
+
+/// Aggregated pointwise grades for one system.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GradeSummary {
+    /// Number of graded answers.
+    pub graded: usize,
+    /// Mean correctness score (1-5).
+    pub avg_score: f64,
+    /// Fraction of answers judged correct.
+    pub correct_rate: f64,
+}
+
+impl GradeSummary {
+    /// Aggregate grades (`None` if there are none).
+    pub fn compute(grades: &[&AnswerGrade]) -> Option<Self> {
+        if grades.is_empty() {
+            return None;
+        }
+        let n = grades.len() as f64;
+        Some(Self {
+            graded: grades.len(),
+            avg_score: grades.iter().map(|g| g.score as f64).sum::<f64>() / n,
+            correct_rate: grades.iter().filter(|g| g.correct).count() as f64 / n,
+        })
+    }
+}
+
+/// Whether an answer is supported by the content it was generated from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Groundedness {
+    /// Support score (1-5): 5 = every claim supported, 1 = unsupported.
+    pub score: u8,
+    /// Whether every claim in the answer is supported by the content.
+    pub grounded: bool,
+    /// Claims in the answer that the content does not support.
+    #[serde(default)]
+    pub unsupported_claims: Vec<String>,
+    /// Explanation from the judge.
+    pub explanation: String,
+}
+
+/// Aggregated groundedness checks for one system.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GroundednessSummary {
+    /// Number of checked answers.
+    pub checked: usize,
+    /// Mean support score (1-5).
+    pub avg_score: f64,
+    /// Fraction of answers fully supported by their retrieved content.
+    pub grounded_rate: f64,
+    /// Answers judged correct that their retrieved content does not support,
+    /// i.e. answered from the model's prior knowledge.
+    pub correct_ungrounded: usize,
+}
+
+impl GroundednessSummary {
+    /// Aggregate checks, each paired with whether the answer was judged
+    /// correct (if known). `None` if there are none.
+    pub fn compute(checks: &[(&Groundedness, Option<bool>)]) -> Option<Self> {
+        if checks.is_empty() {
+            return None;
+        }
+        let n = checks.len() as f64;
+        Some(Self {
+            checked: checks.len(),
+            avg_score: checks.iter().map(|(g, _)| g.score as f64).sum::<f64>() / n,
+            grounded_rate: checks.iter().filter(|(g, _)| g.grounded).count() as f64 / n,
+            correct_ungrounded: checks
+                .iter()
+                .filter(|(g, correct)| !g.grounded && *correct == Some(true))
+                .count(),
+        })
+    }
+}
+
+/// One ensemble member's comparison verdict.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JudgeVerdict {
+    /// Judge model name.
+    pub model: String,
+    /// Which system this judge picked (1 = first, 2 = second, 0 = tie).
+    pub winner: u8,
+    pub score_system1: f32,
+    pub score_system2: f32,
+    pub explanation: String,
+}
+
+impl ComparisonResult {
+    /// The same result with the two systems' roles exchanged.
+    fn swapped(self) -> Self {
+        Self {
+            winner: match self.winner {
+                1 => 2,
+                2 => 1,
+                other => other,
+            },
+            score_system1: self.score_system2,
+            score_system2: self.score_system1,
+            ..self
+        }
+    }
+
+    /// Reconcile a judgment with one made in swapped order (already mapped
+    /// back to the original roles): scores are averaged and disagreeing
+    /// winners become a tie.
+    fn reconcile(self, swapped: ComparisonResult) -> Self {
+        let consistent = self.winner == swapped.winner;
+        Self {
+            winner: if consistent { self.winner } else { 0 },
+            score_system1: (self.score_system1 + swapped.score_system1) / 2.0,
+            score_system2: (self.score_system2 + swapped.score_system2) / 2.0,
+            explanation: format!(
+                "[A/B order] {}\n[B/A order] {}",
+                self.explanation, swapped.explanation
+            ),
+            order_consistent: Some(consistent),
+            judges: Vec::new(),
+            agreement: None,
+        }
+    }
+
+    /// Aggregate ensemble verdicts: majority vote on the winner (no strict
+    /// majority is a tie) and mean scores.
+    fn aggregate(verdicts: Vec<(String, ComparisonResult)>) -> Self {
+        let mut votes = [0usize; 3];
+        for (_, v) in &verdicts {
+            votes[(v.winner as usize).min(2)] += 1;
+        }
+        let top = *votes.iter().max().unwrap_or(&0);
+        let winner = match votes.iter().filter(|c| **c == top).count() {
+            1 => votes.iter().position(|c| *c == top).unwrap_or(0) as u8,
+            _ => 0,
+        };
+
+        let n = verdicts.len().max(1) as f32;
+        let consistency: Vec<bool> = verdicts
+            .iter()
+            .filter_map(|(_, v)| v.order_consistent)
+            .collect();
+        Self {
+            winner,
+            score_system1: verdicts.iter().map(|(_, v)| v.score_system1).sum::<f32>() / n,
+            score_system2: verdicts.iter().map(|(_, v)| v.score_system2).sum::<f32>() / n,
+            explanation: verdicts
+                .iter()
+                .map(|(model, v)| format!("[{}] {}", model, v.explanation))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            order_consistent: (!consistency.is_empty()).then(|| consistency.iter().all(|c| *c)),
+            agreement: Some(top as f64 / verdicts.len().max(1) as f64),
+            judges: verdicts
+                .into_iter()
+                .map(|(model, v)| JudgeVerdict {
+                    model,
+                    winner: v.winner,
+                    score_system1: v.score_system1,
+                    score_system2: v.score_system2,
+                    explanation: v.explanation,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// A cached verdict, as stored in the cache file.
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    key: String,
+    result: ComparisonResult,
+}
+
+/// Comparison verdicts persisted to a JSONL file.
+///
+/// Verdicts are keyed by judge model and the full comparison prompt, which
+/// covers the question, both systems' answers and names, the ground truth
+/// and any rubric, so changing any of them is a miss. Each presentation
+/// order of a position-swapped comparison is cached separately.
+pub struct JudgeCache {
+    path: PathBuf,
+    entries: Mutex<HashMap<String, ComparisonResult>>,
+    hits: AtomicUsize,
+}
+
+impl JudgeCache {
+    /// Open a cache file, loading its verdicts if it exists. Malformed lines
+    /// (such as a write cut short by a crash) are skipped.
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut entries = HashMap::new();
+        if path.exists() {
+            let content = std::fs::read_to_string(path).map_err(|e| PageIndexError::io(path, e))?;
+            for line in content.lines().filter(|l| !l.trim().is_empty()) {
+                match serde_json::from_str::<CacheEntry>(line) {
+                    Ok(entry) => {
+                        entries.insert(entry.key, entry.result);
+                    }
+                    Err(e) => {
+                        tracing::warn!("Skipping malformed judge cache line in {:?}: {}", path, e)
+                    }
+                }
+            }
+        }
+        Ok(Self {
+            path: path.to_path_buf(),
+            entries: Mutex::new(entries),
+            hits: AtomicUsize::new(0),
+        })
+    }
+
+    /// Number of cached verdicts.
+    pub fn len(&self) -> usize {
+        self.entries.lock().unwrap().len()
+    }
+
+    /// Check if the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of lookups answered from the cache since it was opened.
+    pub fn hits(&self) -> usize {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    fn key(model: &str, prompt: &str) -> String {
+        // 0xff never occurs in UTF-8, so it separates the two unambiguously
+        fnv1a_hex(&[model.as_bytes(), &[0xff], prompt.as_bytes()].concat())
+    }
+
+    fn get(&self, key: &str) -> Option<ComparisonResult> {
+        let result = self.entries.lock().unwrap().get(key).cloned();
+        if result.is_some() {
+            self.hits.fetch_add(1, Ordering::Relaxed);
+        }
+        result
+    }
+
+    /// Store a verdict and append it to the cache file.
+    fn insert(&self, key: String, result: ComparisonResult) -> Result<()> {
+        let entry = CacheEntry { key, result };
+        let line = serde_json::to_string(&entry)?;
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|e| PageIndexError::io(&self.path, e))?;
+        writeln!(file, "{}", line).map_err(|e| PageIndexError::io(&self.path, e))?;
+        self.entries.lock().unwrap().insert(entry.key, entry.result);
+        Ok(())
+    }
 }
 
 /// LLM-as-Judge for evaluating retrieval quality.
 pub struct LlmJudge {
     client: LlmClient,
+    /// Additional ensemble judges; comparisons are aggregated across all.
+    ensemble: Vec<LlmClient>,
+    /// Judge comparisons in both orders and reconcile.
+    swap_positions: bool,
+    /// Criteria replacing the built-in judging questions.
+    rubric: Option<Rubric>,
+    /// Previously judged comparisons.
+    cache: Option<JudgeCache>,
 }
 
 impl LlmJudge {
     /// Create a new judge with the given LLM client.
     pub fn new(client: LlmClient) -> Self {
-        Self { client }
+        Self {
+            client,
+            ensemble: Vec::new(),
+            swap_positions: false,
+            rubric: None,
+            cache: None,
+        }
+    }
+
+    /// Reuse comparison verdicts from a cache, and record new ones in it.
+    pub fn with_cache(mut self, cache: JudgeCache) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// The verdict cache, if one is set.
+    pub fn cache(&self) -> Option<&JudgeCache> {
+        self.cache.as_ref()
+    }
+
+    /// Judge with a rubric's weighted criteria instead of the built-in ones.
+    ///
+    /// Relevance judging then returns per-criterion scores; comparisons use
+    /// the criteria to rate each system.
+    pub fn with_rubric(mut self, rubric: Rubric) -> Self {
+        self.rubric = Some(rubric);
+        self
+    }
+
+    /// Criteria text for comparison prompts.
+    fn comparison_criteria(&self, default: &str) -> String {
+        match &self.rubric {
+            Some(rubric) => format!(
+                "{}\n   Weight each criterion by its weight when rating.",
+                rubric
+                    .prompt_section()
+                    .lines()
+                    .map(|line| format!("   {}", line))
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            ),
+            None => default.to_string(),
+        }
+    }
+
+    /// Add another judge model to the ensemble. Comparisons are then judged
+    /// by every model and aggregated; relevance judging uses the first.
+    pub fn with_judge(mut self, client: LlmClient) -> Self {
+        self.ensemble.push(client);
+        self
+    }
+
+    /// Token usage across all judge models.
+    pub fn usage(&self) -> UsageStats {
+        self.ensemble
+            .iter()
+            .fold(self.client.usage(), |usage, client| usage + client.usage())
+    }
+
+    /// Run each comparison twice, with the systems' A/B order swapped, and
+    /// reconcile the two judgments. Doubles judge calls.
+    pub fn with_position_swap(mut self, enabled: bool) -> Self {
+        self.swap_positions = enabled;
+        self
     }
 
     /// Create from LLM config.
@@ -56,6 +418,39 @@ impl LlmJudge {
             .map(|gt| format!("\n\nGround Truth Answer: {}", gt))
             .unwrap_or_default();
 
+        if let Some(rubric) = &self.rubric {
+            let prompt = format!(
+                r#"You are an expert judge evaluating the quality of retrieved content for answering a question.
+
+Question: {}
+
+Retrieved Content:
+{}
+{}
+
+Score the retrieved content from 1 (poor) to 5 (excellent) on each criterion:
+{}
+
+Also decide whether someone could answer the question using ONLY this retrieved content.
+
+Respond in JSON format:
+{{
+    "scores": {{"<criterion name>": <1-5>, ...}},
+    "answerable": <true/false>,
+    "explanation": "<brief explanation>"
+}}
+
+Respond with only the JSON, no other text."#,
+                query,
+                retrieved_content,
+                ground_truth_section,
+                rubric.prompt_section()
+            );
+
+            let response = self.client.complete(None, &prompt).await?;
+            return Self::parse_rubric_response(&response, rubric);
+        }
+
         let prompt = format!(
             r#"You are an expert judge evaluating the quality of retrieved content for answering a question.
 
@@ -85,6 +480,142 @@ Respond with only the JSON, no other text."#,
         Ok(result)
     }
 
+    /// Grade an answer's correctness against the gold answer, independently
+    /// of any other system. Uses the first judge model.
+    pub async fn grade_answer(
+        &self,
+        question: &str,
+        answer: &str,
+        gold: &str,
+    ) -> Result<AnswerGrade> {
+        let prompt = format!(
+            r#"You are an expert judge grading an answer against a reference answer.
+
+Question: {}
+
+Reference Answer: {}
+
+Answer to Grade:
+{}
+
+Grade the answer's correctness:
+1. Does it convey the same facts as the reference answer? Wording may differ; extra detail is fine unless it contradicts the reference.
+2. Rate its correctness (1-5): 5 = fully correct, 3 = partially correct, 1 = wrong or no answer.
+
+Respond in JSON format:
+{{
+    "score": <1-5>,
+    "correct": <true/false>,
+    "explanation": "<brief explanation>"
+}}
+
+Respond with only the JSON, no other text."#,
+            question, gold, answer
+        );
+
+        let response = self.client.complete(None, &prompt).await?;
+        Self::parse_grade_response(&response)
+    }
+
+    /// Check whether an answer is supported by the content it was generated
+    /// from, regardless of whether it is correct. Uses the first judge model.
+    pub async fn check_groundedness(
+        &self,
+        question: &str,
+        answer: &str,
+        content: &str,
+    ) -> Result<Groundedness> {
+        let prompt = format!(
+            r#"You are an expert judge checking whether an answer is supported by the retrieved content it was generated from.
+
+Question: {}
+
+Retrieved Content:
+{}
+
+Answer to Check:
+{}
+
+Check the answer against the retrieved content only, not your own knowledge:
+1. List any claims in the answer that the content does not state or directly imply. An answer can be correct and still unsupported.
+2. Rate how well the content supports the answer (1-5): 5 = every claim supported, 3 = partly supported, 1 = unsupported or contradicted.
+
+Respond in JSON format:
+{{
+    "score": <1-5>,
+    "grounded": <true/false>,
+    "unsupported_claims": ["<claim>", ...],
+    "explanation": "<brief explanation>"
+}}
+
+Respond with only the JSON, no other text."#,
+            question, content, answer
+        );
+
+        let response = self.client.complete(None, &prompt).await?;
+        Self::parse_groundedness_response(&response)
+    }
+
+    /// Run a pairwise comparison with every judge, aggregating an ensemble.
+    async fn compare(
+        &self,
+        prompt_for: impl Fn(&str, &str, &str, &str) -> String,
+        system1: (&str, &str),
+        system2: (&str, &str),
+    ) -> Result<ComparisonResult> {
+        let first = self
+            .compare_with(&self.client, &prompt_for, system1, system2)
+            .await?;
+        if self.ensemble.is_empty() {
+            return Ok(first);
+        }
+
+        let mut verdicts = vec![(self.client.model().to_string(), first)];
+        for client in &self.ensemble {
+            let verdict = self
+                .compare_with(client, &prompt_for, system1, system2)
+                .await?;
+            verdicts.push((client.model().to_string(), verdict));
+        }
+        Ok(ComparisonResult::aggregate(verdicts))
+    }
+
+    /// Run a pairwise comparison with one judge, in both orders if position
+    /// swapping is on.
+    async fn compare_with(
+        &self,
+        client: &LlmClient,
+        prompt_for: &impl Fn(&str, &str, &str, &str) -> String,
+        system1: (&str, &str),
+        system2: (&str, &str),
+    ) -> Result<ComparisonResult> {
+        let prompt = prompt_for(system1.0, system1.1, system2.0, system2.1);
+        let result = self.judge_comparison(client, &prompt).await?;
+        if !self.swap_positions {
+            return Ok(result);
+        }
+
+        let prompt = prompt_for(system2.0, system2.1, system1.0, system1.1);
+        let swapped = self.judge_comparison(client, &prompt).await?.swapped();
+        Ok(result.reconcile(swapped))
+    }
+
+    /// Get one judge's verdict on a comparison prompt, from the cache if
+    /// possible.
+    async fn judge_comparison(&self, client: &LlmClient, prompt: &str) -> Result<ComparisonResult> {
+        let key = JudgeCache::key(client.model(), prompt);
+        if let Some(result) = self.cache.as_ref().and_then(|cache| cache.get(&key)) {
+            return Ok(result);
+        }
+
+        let response = client.complete(None, prompt).await?;
+        let result = Self::parse_comparison_response(&response)?;
+        if let Some(cache) = &self.cache {
+            cache.insert(key, result.clone())?;
+        }
+        Ok(result)
+    }
+
     /// Compare two retrieval systems head-to-head.
     pub async fn compare_systems(
         &self,
@@ -99,8 +630,13 @@ Respond with only the JSON, no other text."#,
             .map(|gt| format!("\n\nGround Truth Answer: {}", gt))
             .unwrap_or_default();
 
-        let prompt = format!(
-            r#"You are an expert judge comparing two retrieval systems.
+        let retrieval_criteria = match &self.rubric {
+            Some(_) => format!(" based on:\n{}", self.comparison_criteria("")),
+            None => ".".to_string(),
+        };
+        let prompt_for = |name_a: &str, content_a: &str, name_b: &str, content_b: &str| {
+            format!(
+                r#"You are an expert judge comparing two retrieval systems.
 
 Question: {}
 
@@ -115,7 +651,7 @@ System B ({}) Retrieved:
 
 Compare the two systems:
 1. Which system retrieved more relevant content for answering the question?
-2. Rate each system's retrieval quality (1-5).
+2. Rate each system's retrieval quality (1-5){}
 
 Respond in JSON format:
 {{
@@ -126,17 +662,22 @@ Respond in JSON format:
 }}
 
 Respond with only the JSON, no other text."#,
-            query,
-            system1_name,
-            system1_content,
-            system2_name,
-            system2_content,
-            ground_truth_section
-        );
+                query,
+                name_a,
+                content_a,
+                name_b,
+                content_b,
+                ground_truth_section,
+                retrieval_criteria
+            )
+        };
 
-        let response = self.client.complete(None, &prompt).await?;
-        let result = Self::parse_comparison_response(&response)?;
-        Ok(result)
+        self.compare(
+            prompt_for,
+            (system1_name, system1_content),
+            (system2_name, system2_content),
+        )
+        .await
     }
 
     /// Compare two RAG system answers head-to-head.
@@ -154,8 +695,12 @@ Respond with only the JSON, no other text."#,
             .map(|gt| format!("\n\nGround Truth Answer: {}", gt))
             .unwrap_or_default();
 
-        let prompt = format!(
-            r#"You are an expert judge comparing answers from two RAG (Retrieval-Augmented Generation) systems.
+        let answer_criteria = self.comparison_criteria(
+            "   - Accuracy: Is the information correct?\n   - Completeness: Does it fully answer the question?\n   - Relevance: Does it directly address what was asked?",
+        );
+        let prompt_for = |name_a: &str, answer_a: &str, name_b: &str, answer_b: &str| {
+            format!(
+                r#"You are an expert judge comparing answers from two RAG (Retrieval-Augmented Generation) systems.
 
 Question: {}
 
@@ -171,9 +716,7 @@ System B ({}) Answer:
 Compare the two answers:
 1. Which answer is more accurate, complete, and helpful for the question?
 2. Rate each answer's quality (1-5) based on:
-   - Accuracy: Is the information correct?
-   - Completeness: Does it fully answer the question?
-   - Relevance: Does it directly address what was asked?
+{}
 
 Respond in JSON format:
 {{
@@ -184,12 +727,16 @@ Respond in JSON format:
 }}
 
 Respond with only the JSON, no other text."#,
-            query, system1_name, system1_answer, system2_name, system2_answer, ground_truth_section
-        );
+                query, name_a, answer_a, name_b, answer_b, ground_truth_section, answer_criteria
+            )
+        };
 
-        let response = self.client.complete(None, &prompt).await?;
-        let result = Self::parse_comparison_response(&response)?;
-        Ok(result)
+        self.compare(
+            prompt_for,
+            (system1_name, system1_answer),
+            (system2_name, system2_answer),
+        )
+        .await
     }
 
     /// Parse judge response JSON.
@@ -214,6 +761,84 @@ Respond with only the JSON, no other text."#,
             relevance: raw.relevance.clamp(1, 5),
             answerable: raw.answerable,
             explanation: raw.explanation,
+            criteria: Vec::new(),
+            weighted_score: None,
+        })
+    }
+
+    /// Parse a pointwise grade response.
+    fn parse_grade_response(response: &str) -> Result<AnswerGrade> {
+        let json_str = Self::extract_json(response);
+        let grade: AnswerGrade = serde_json::from_str(&json_str).map_err(|e| {
+            crate::error::PageIndexError::LlmParse(format!(
+                "Failed to parse grade response: {}. Response: {}",
+                e, response
+            ))
+        })?;
+        Ok(AnswerGrade {
+            score: grade.score.clamp(1, 5),
+            ..grade
+        })
+    }
+
+    /// Parse a groundedness check response.
+    fn parse_groundedness_response(response: &str) -> Result<Groundedness> {
+        let json_str = Self::extract_json(response);
+        let check: Groundedness = serde_json::from_str(&json_str).map_err(|e| {
+            crate::error::PageIndexError::LlmParse(format!(
+                "Failed to parse groundedness response: {}. Response: {}",
+                e, response
+            ))
+        })?;
+        Ok(Groundedness {
+            score: check.score.clamp(1, 5),
+            ..check
+        })
+    }
+
+    /// Parse a rubric judge response; every criterion must be scored.
+    fn parse_rubric_response(response: &str, rubric: &Rubric) -> Result<JudgeResult> {
+        let json_str = Self::extract_json(response);
+
+        #[derive(Deserialize)]
+        struct RawRubricResult {
+            scores: HashMap<String, u8>,
+            answerable: bool,
+            explanation: String,
+        }
+
+        let raw: RawRubricResult = serde_json::from_str(&json_str).map_err(|e| {
+            crate::error::PageIndexError::LlmParse(format!(
+                "Failed to parse rubric judge response: {}. Response: {}",
+                e, response
+            ))
+        })?;
+
+        let criteria = rubric
+            .criteria
+            .iter()
+            .map(|c| {
+                let score = raw.scores.get(&c.name).ok_or_else(|| {
+                    crate::error::PageIndexError::LlmParse(format!(
+                        "Rubric judge response has no score for '{}'. Response: {}",
+                        c.name, response
+                    ))
+                })?;
+                Ok(CriterionScore {
+                    name: c.name.clone(),
+                    score: (*score).clamp(1, 5),
+                    weight: c.weight,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let weighted = Rubric::weighted_score(&criteria);
+
+        Ok(JudgeResult {
+            relevance: weighted.round() as u8,
+            answerable: raw.answerable,
+            explanation: raw.explanation,
+            criteria,
+            weighted_score: Some(weighted),
         })
     }
 
@@ -244,9 +869,12 @@ Respond with only the JSON, no other text."#,
 
         Ok(ComparisonResult {
             winner,
-            score_system1: raw.score_system_a.clamp(1, 5),
-            score_system2: raw.score_system_b.clamp(1, 5),
+            score_system1: raw.score_system_a.clamp(1, 5) as f32,
+            score_system2: raw.score_system_b.clamp(1, 5) as f32,
             explanation: raw.explanation,
+            order_consistent: None,
+            judges: Vec::new(),
+            agreement: None,
         })
     }
 
@@ -254,30 +882,29 @@ Respond with only the JSON, no other text."#,
     fn extract_json(response: &str) -> String {
         let response = response.trim();
 
-        if response.starts_with("```json") {
-            if let Some(end) = response.rfind("```") {
-                let start = "```json".len();
-                if end > start {
-                    return response[start..end].trim().to_string();
-                }
+        if response.starts_with("```json")
+            && let Some(end) = response.rfind("```")
+        {
+            let start = "```json".len();
+            if end > start {
+                return response[start..end].trim().to_string();
             }
         }
 
-        if response.starts_with("```") {
-            if let Some(end) = response.rfind("```") {
-                let start = response.find('\n').map(|n| n + 1).unwrap_or(3);
-                if end > start {
-                    return response[start..end].trim().to_string();
-                }
+        if response.starts_with("```")
+            && let Some(end) = response.rfind("```")
+        {
+            let start = response.find('\n').map(|n| n + 1).unwrap_or(3);
+            if end > start {
+                return response[start..end].trim().to_string();
             }
         }
 
-        if let Some(start) = response.find('{') {
-            if let Some(end) = response.rfind('}') {
-                if end > start {
-                    return response[start..=end].to_string();
-                }
-            }
+        if let Some(start) = response.find('{')
+            && let Some(end) = response.rfind('}')
+            && end > start
+        {
+            return response[start..=end].to_string();
         }
 
         response.to_string()
@@ -297,13 +924,173 @@ mod tests {
         assert!(result.answerable);
     }
 
+    #[test]
+    fn test_parse_groundedness_response() {
+        let response = r#"{"score": 2, "grounded": false, "unsupported_claims": ["Founded in 1998"], "explanation": "date not in content"}"#;
+        let check = LlmJudge::parse_groundedness_response(response).unwrap();
+        assert_eq!(check.score, 2);
+        assert!(!check.grounded);
+        assert_eq!(check.unsupported_claims, vec!["Founded in 1998"]);
+        assert!(LlmJudge::parse_groundedness_response("no json").is_err());
+
+        let supported = LlmJudge::parse_groundedness_response(
+            r#"{"score": 9, "grounded": true, "explanation": "ok"}"#,
+        )
+        .unwrap();
+        assert_eq!(supported.score, 5);
+        assert!(supported.unsupported_claims.is_empty());
+
+        // Correct but unsupported, correct and supported, ungraded
+        let summary = GroundednessSummary::compute(&[
+            (&check, Some(true)),
+            (&supported, Some(true)),
+            (&check, None),
+        ])
+        .unwrap();
+        assert_eq!(summary.checked, 3);
+        assert!((summary.avg_score - 3.0).abs() < 1e-9);
+        assert!((summary.grounded_rate - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(summary.correct_ungrounded, 1);
+        assert!(GroundednessSummary::compute(&[]).is_none());
+    }
+
+    #[test]
+    fn test_parse_grade_response() {
+        let response = r#"Sure: {"score": 7, "correct": true, "explanation": "Matches"}"#;
+        let grade = LlmJudge::parse_grade_response(response).unwrap();
+        assert_eq!(grade.score, 5);
+        assert!(grade.correct);
+        assert!(LlmJudge::parse_grade_response("no json").is_err());
+
+        let wrong = AnswerGrade {
+            score: 2,
+            correct: false,
+            explanation: String::new(),
+        };
+        let summary = GradeSummary::compute(&[&grade, &wrong]).unwrap();
+        assert_eq!(summary.graded, 2);
+        assert!((summary.avg_score - 3.5).abs() < 1e-9);
+        assert!((summary.correct_rate - 0.5).abs() < 1e-9);
+        assert!(GradeSummary::compute(&[]).is_none());
+    }
+
+    #[test]
+    fn test_parse_rubric_response() {
+        let rubric: Rubric = serde_yaml::from_str(
+            "criteria:\n  - {name: correctness, description: c, weight: 3}\n  - {name: groundedness, description: g}\n",
+        )
+        .unwrap();
+
+        let response = r#"{"scores": {"correctness": 4, "groundedness": 2}, "answerable": true, "explanation": "ok"}"#;
+        let result = LlmJudge::parse_rubric_response(response, &rubric).unwrap();
+        assert_eq!(result.criteria.len(), 2);
+        assert_eq!(result.criteria[0].score, 4);
+        assert_eq!(result.weighted_score, Some(3.5));
+        assert_eq!(result.relevance, 4);
+
+        let missing = r#"{"scores": {"correctness": 4}, "answerable": true, "explanation": "ok"}"#;
+        assert!(LlmJudge::parse_rubric_response(missing, &rubric).is_err());
+    }
+
     #[test]
     fn test_parse_comparison_response() {
         let response = r#"{"winner": "A", "score_system_a": 5, "score_system_b": 3, "explanation": "A is better"}"#;
         let result = LlmJudge::parse_comparison_response(response).unwrap();
 
         assert_eq!(result.winner, 1);
-        assert_eq!(result.score_system1, 5);
-        assert_eq!(result.score_system2, 3);
+        assert_eq!(result.score_system1, 5.0);
+        assert_eq!(result.score_system2, 3.0);
+    }
+
+    fn comparison(winner: u8, score1: f32, score2: f32) -> ComparisonResult {
+        ComparisonResult {
+            winner,
+            score_system1: score1,
+            score_system2: score2,
+            explanation: String::new(),
+            order_consistent: None,
+            judges: Vec::new(),
+            agreement: None,
+        }
+    }
+
+    #[test]
+    fn test_reconcile_swapped_orders() {
+        // The judge prefers position A both times: a conflict, so a tie
+        let first = comparison(1, 4.0, 3.0);
+        let swapped = comparison(1, 4.0, 2.0).swapped();
+        let result = first.reconcile(swapped);
+        assert_eq!(result.winner, 0);
+        assert_eq!(result.score_system1, 3.0);
+        assert_eq!(result.score_system2, 3.5);
+        assert_eq!(result.order_consistent, Some(false));
+
+        // System 1 wins in both orders
+        let result = comparison(1, 5.0, 3.0).reconcile(comparison(2, 3.0, 4.0).swapped());
+        assert_eq!(result.winner, 1);
+        assert_eq!(result.order_consistent, Some(true));
+    }
+
+    #[tokio::test]
+    async fn test_judge_cache() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("judge.jsonl");
+
+        let cache = JudgeCache::open(&path).unwrap();
+        assert!(cache.is_empty());
+        // Unreachable endpoint: a cache miss would fail the comparison
+        let client = LlmClient::new(LlmConfig {
+            api_base: "http://127.0.0.1:9".to_string(),
+            model: "judge".to_string(),
+            ..Default::default()
+        });
+        let prompt = |a: &str, x: &str, b: &str, y: &str| format!("{} {} {} {}", a, x, b, y);
+        let key = JudgeCache::key("judge", &prompt("A", "yes", "B", "no"));
+        cache.insert(key, comparison(1, 4.0, 2.0)).unwrap();
+        let judge = LlmJudge::new(client).with_cache(cache);
+
+        let result = judge
+            .compare(prompt, ("A", "yes"), ("B", "no"))
+            .await
+            .unwrap();
+        assert_eq!(result.winner, 1);
+        assert_eq!(judge.cache().unwrap().hits(), 1);
+        assert!(
+            judge
+                .compare(prompt, ("A", "no"), ("B", "yes"))
+                .await
+                .is_err()
+        );
+
+        // Reopening loads the persisted verdict, skipping a truncated line
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap();
+        write!(file, "{{\"key\": \"0").unwrap();
+        let reopened = JudgeCache::open(&path).unwrap();
+        assert_eq!(reopened.len(), 1);
+    }
+
+    #[test]
+    fn test_aggregate_ensemble() {
+        let result = ComparisonResult::aggregate(vec![
+            ("m1".to_string(), comparison(1, 5.0, 3.0)),
+            ("m2".to_string(), comparison(1, 4.0, 3.0)),
+            ("m3".to_string(), comparison(2, 3.0, 4.0)),
+        ]);
+        assert_eq!(result.winner, 1);
+        assert_eq!(result.score_system1, 4.0);
+        assert_eq!(result.score_system2, 10.0 / 3.0);
+        assert!((result.agreement.unwrap() - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(result.judges.len(), 3);
+        assert_eq!(result.judges[2].model, "m3");
+
+        // A split vote with no majority is a tie
+        let result = ComparisonResult::aggregate(vec![
+            ("m1".to_string(), comparison(1, 5.0, 3.0)),
+            ("m2".to_string(), comparison(2, 3.0, 5.0)),
+        ]);
+        assert_eq!(result.winner, 0);
     }
 }
@@ -0,0 +1,290 @@
+//! Local sentence embedding and cross-encoder models using candle
+//! (`local-embeddings` feature).
+
+use super::embeddings::{EmbeddingDevice, ModelSpec, Pooling};
+use anyhow::{Context, Result};
+use candle_core::{Device, Module, Tensor};
+use candle_nn::{Linear, VarBuilder};
+use candle_transformers::models::bert::{BertModel, Config as BertConfig, DTYPE};
+use hf_hub::{Repo, RepoType, api::sync::Api};
+use std::path::PathBuf;
+use tokenizers::{Tokenizer, TruncationParams};
+
+/// Longest input a cross-encoder scores; longer pairs are truncated.
+const CROSS_ENCODER_MAX_TOKENS: usize = 512;
+
+impl EmbeddingDevice {
+    /// Create the candle device.
+    pub fn to_device(self) -> Result<Device> {
+        let device = match self {
+            Self::Auto if candle_core::utils::cuda_is_available() => Device::new_cuda(0)?,
+            Self::Auto if candle_core::utils::metal_is_available() => Device::new_metal(0)?,
+            Self::Auto | Self::Cpu => Device::Cpu,
+            Self::Cuda(ordinal) => Device::new_cuda(ordinal)
+                .with_context(|| format!("Failed to open CUDA device {}", ordinal))?,
+            Self::Metal => Device::new_metal(0).context("Failed to open Metal device")?,
+        };
+        Ok(device)
+    }
+}
+
+/// Config, tokenizer and weights of a model on Hugging Face Hub.
+struct ModelFiles {
+    config: PathBuf,
+    tokenizer: PathBuf,
+    weights: PathBuf,
+}
+
+impl ModelFiles {
+    /// Download (or find in the Hub cache) a model's files.
+    fn download(model_id: &str) -> Result<Self> {
+        let api = Api::new().context("Failed to create HF Hub API")?;
+        let repo = api.repo(Repo::new(model_id.to_string(), RepoType::Model));
+
+        Ok(Self {
+            config: repo
+                .get("config.json")
+                .context("Failed to get config.json")?,
+            tokenizer: repo
+                .get("tokenizer.json")
+                .context("Failed to get tokenizer.json")?,
+            weights: repo
+                .get("model.safetensors")
+                .or_else(|_| repo.get("pytorch_model.bin"))
+                .context("Failed to get model weights")?,
+        })
+    }
+
+    /// Parse the config and load the tokenizer.
+    fn load_config(&self) -> Result<(BertConfig, Tokenizer)> {
+        let config: BertConfig = serde_json::from_str(&std::fs::read_to_string(&self.config)?)
+            .context("Failed to parse config")?;
+        let tokenizer = Tokenizer::from_file(&self.tokenizer)
+            .map_err(|e| anyhow::anyhow!("Failed to load tokenizer: {}", e))?;
+        Ok((config, tokenizer))
+    }
+
+    /// Memory-map the weights on a device.
+    fn var_builder(&self, device: &Device) -> Result<VarBuilder<'static>> {
+        let vb = unsafe {
+            VarBuilder::from_mmaped_safetensors(std::slice::from_ref(&self.weights), DTYPE, device)
+                .context("Failed to load model weights")?
+        };
+        Ok(vb)
+    }
+}
+
+/// Pad token ids, attention masks and token type ids of a batch to a
+/// common length, as `(batch, max_len)` tensors.
+fn batch_tensors(
+    encodings: &[tokenizers::Encoding],
+    type_ids: bool,
+    device: &Device,
+) -> Result<(Tensor, Tensor, Tensor)> {
+    let max_len = encodings
+        .iter()
+        .map(|e| e.get_ids().len())
+        .max()
+        .unwrap_or(0);
+
+    let mut input_ids_vec = Vec::new();
+    let mut attention_mask_vec = Vec::new();
+    let mut token_type_ids_vec = Vec::new();
+
+    for encoding in encodings {
+        let ids = encoding.get_ids();
+
+        // Pad to max_len
+        let mut padded_ids = ids.to_vec();
+        let mut padded_mask = encoding.get_attention_mask().to_vec();
+        let mut padded_types = if type_ids {
+            encoding.get_type_ids().to_vec()
+        } else {
+            vec![0u32; ids.len()]
+        };
+
+        padded_ids.resize(max_len, 0);
+        padded_mask.resize(max_len, 0);
+        padded_types.resize(max_len, 0);
+
+        input_ids_vec.extend(padded_ids);
+        attention_mask_vec.extend(padded_mask);
+        token_type_ids_vec.extend(padded_types);
+    }
+
+    let shape = (encodings.len(), max_len);
+    Ok((
+        Tensor::from_vec(input_ids_vec, shape, device)?,
+        Tensor::from_vec(attention_mask_vec, shape, device)?,
+        Tensor::from_vec(token_type_ids_vec, shape, device)?,
+    ))
+}
+
+/// A BERT-family model downloaded from Hugging Face Hub.
+pub(crate) struct LocalModel {
+    model: BertModel,
+    tokenizer: Tokenizer,
+    device: Device,
+    normalize: bool,
+    pooling: Pooling,
+    dimension: usize,
+}
+
+impl LocalModel {
+    /// Download and load a model on the given device.
+    pub(crate) fn load(spec: &ModelSpec, device: EmbeddingDevice) -> Result<Self> {
+        let device = device.to_device()?;
+        tracing::debug!("Embedding device: {:?}", device);
+
+        let files = ModelFiles::download(&spec.model_id)?;
+        let (config, tokenizer) = files.load_config()?;
+        let vb = files.var_builder(&device)?;
+        let model = BertModel::load(vb, &config).context("Failed to load BERT model")?;
+
+        Ok(Self {
+            model,
+            tokenizer,
+            device,
+            normalize: true,
+            pooling: spec.pooling,
+            dimension: config.hidden_size,
+        })
+    }
+
+    /// Generate embeddings for a batch of texts.
+    pub(crate) fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Tokenize
+        let encodings = self
+            .tokenizer
+            .encode_batch(texts.to_vec(), true)
+            .map_err(|e| anyhow::anyhow!("Tokenization failed: {}", e))?;
+
+        // Prepare tensors
+        let (input_ids, attention_mask, token_type_ids) =
+            batch_tensors(&encodings, false, &self.device)?;
+
+        // Run model
+        let output = self
+            .model
+            .forward(&input_ids, &token_type_ids, Some(&attention_mask))?;
+
+        let mean_embeddings = match self.pooling {
+            Pooling::Cls => output.narrow(1, 0, 1)?.squeeze(1)?,
+            Pooling::Mean => {
+                // Mean pooling over sequence dimension (with attention mask)
+                let attention_mask_expanded = attention_mask
+                    .unsqueeze(2)?
+                    .to_dtype(output.dtype())?
+                    .broadcast_as(output.shape())?;
+
+                let sum_embeddings = (output * &attention_mask_expanded)?.sum(1)?;
+                let sum_mask = attention_mask_expanded.sum(1)?.clamp(1e-9, f64::MAX)?;
+                (sum_embeddings / sum_mask)?
+            }
+        };
+
+        // Normalize if requested
+        let final_embeddings = if self.normalize {
+            let norms = mean_embeddings.sqr()?.sum_keepdim(1)?.sqrt()?;
+            let shape = mean_embeddings.shape().clone();
+            (mean_embeddings / norms.broadcast_as(&shape)?)?
+        } else {
+            mean_embeddings
+        };
+
+        // Convert to Vec<Vec<f32>>
+        let embeddings_vec: Vec<f32> = final_embeddings
+            .to_vec2::<f32>()?
+            .into_iter()
+            .flatten()
+            .collect();
+
+        let embedding_dim = final_embeddings.dim(1)?;
+        let result: Vec<Vec<f32>> = embeddings_vec
+            .chunks(embedding_dim)
+            .map(|c| c.to_vec())
+            .collect();
+
+        Ok(result)
+    }
+
+    /// Get embedding dimension.
+    pub(crate) fn dimension(&self) -> usize {
+        self.dimension
+    }
+}
+
+/// A BERT sequence-classification model scoring (query, passage) pairs,
+/// such as the MS MARCO cross-encoders.
+pub(crate) struct LocalCrossEncoder {
+    model: BertModel,
+    pooler: Linear,
+    classifier: Linear,
+    tokenizer: Tokenizer,
+    device: Device,
+}
+
+impl LocalCrossEncoder {
+    /// Download and load a cross-encoder on the given device.
+    pub(crate) fn load(model_id: &str, device: EmbeddingDevice) -> Result<Self> {
+        let device = device.to_device()?;
+        tracing::debug!("Reranker device: {:?}", device);
+
+        let files = ModelFiles::download(model_id)?;
+        let (config, mut tokenizer) = files.load_config()?;
+        tokenizer
+            .with_truncation(Some(TruncationParams {
+                max_length: CROSS_ENCODER_MAX_TOKENS.min(config.max_position_embeddings),
+                ..Default::default()
+            }))
+            .map_err(|e| anyhow::anyhow!("Failed to configure tokenizer: {}", e))?;
+        let vb = files.var_builder(&device)?;
+
+        // BertForSequenceClassification: encoder, pooler, one-logit head
+        let model = BertModel::load(vb.pp("bert"), &config).context("Failed to load BERT model")?;
+        let pooler = candle_nn::linear(
+            config.hidden_size,
+            config.hidden_size,
+            vb.pp("bert.pooler.dense"),
+        )
+        .context("Failed to load pooler")?;
+        let classifier = candle_nn::linear(config.hidden_size, 1, vb.pp("classifier"))
+            .context("Failed to load classifier")?;
+
+        Ok(Self {
+            model,
+            pooler,
+            classifier,
+            tokenizer,
+            device,
+        })
+    }
+
+    /// Relevance logits of each passage for the query (higher is better).
+    pub(crate) fn score_batch(&self, query: &str, passages: &[&str]) -> Result<Vec<f32>> {
+        if passages.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let pairs: Vec<(&str, &str)> = passages.iter().map(|p| (query, *p)).collect();
+        let encodings = self
+            .tokenizer
+            .encode_batch(pairs, true)
+            .map_err(|e| anyhow::anyhow!("Tokenization failed: {}", e))?;
+        let (input_ids, attention_mask, token_type_ids) =
+            batch_tensors(&encodings, true, &self.device)?;
+
+        let output = self
+            .model
+            .forward(&input_ids, &token_type_ids, Some(&attention_mask))?;
+        let cls = output.narrow(1, 0, 1)?.squeeze(1)?;
+        let pooled = self.pooler.forward(&cls)?.tanh()?;
+        let logits = self.classifier.forward(&pooled)?.squeeze(1)?;
+
+        Ok(logits.to_dtype(candle_core::DType::F32)?.to_vec1::<f32>()?)
+    }
+}
@@ -0,0 +1,242 @@
+//! Reference-based metrics: retrieval against gold evidence, and answers
+//! against gold answers.
+//!
+//! A retrieved passage is relevant when it contains a gold evidence span
+//! (compared case-insensitively with whitespace collapsed). Answers are scored
+//! with SQuAD-style exact match and token F1. These metrics need no LLM calls,
+//! so they are cheap to compute on every run.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Retrieval quality for one item, or averaged over a benchmark.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct RetrievalMetrics {
+    /// Fraction of evidence spans found in the top-k passages.
+    pub recall_at_k: f64,
+    /// Reciprocal rank of the first relevant passage (0 if none).
+    pub mrr: f64,
+    /// Normalized discounted cumulative gain over the top-k passages.
+    pub ndcg_at_k: f64,
+}
+
+impl RetrievalMetrics {
+    /// Average metrics over items.
+    pub fn mean(metrics: &[RetrievalMetrics]) -> Option<RetrievalMetrics> {
+        if metrics.is_empty() {
+            return None;
+        }
+        let n = metrics.len() as f64;
+        Some(RetrievalMetrics {
+            recall_at_k: metrics.iter().map(|m| m.recall_at_k).sum::<f64>() / n,
+            mrr: metrics.iter().map(|m| m.mrr).sum::<f64>() / n,
+            ndcg_at_k: metrics.iter().map(|m| m.ndcg_at_k).sum::<f64>() / n,
+        })
+    }
+}
+
+fn normalize(text: &str) -> String {
+    text.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Score ranked passages against gold evidence spans.
+///
+/// Returns `None` when there is no evidence to score against. For nDCG, a
+/// passage gains 1 only if it covers an evidence span no higher-ranked
+/// passage covered, so overlapping chunks aren't counted twice.
+pub fn retrieval_metrics(
+    passages: &[String],
+    evidence: &[String],
+    k: usize,
+) -> Option<RetrievalMetrics> {
+    let evidence: Vec<String> = evidence
+        .iter()
+        .map(|e| normalize(e))
+        .filter(|e| !e.is_empty())
+        .collect();
+    if evidence.is_empty() {
+        return None;
+    }
+
+    let mut covered = vec![false; evidence.len()];
+    let mut first_relevant = None;
+    let mut dcg = 0.0;
+
+    for (rank, passage) in passages.iter().take(k).enumerate() {
+        let passage = normalize(passage);
+        let mut relevant = false;
+        let mut novel = false;
+        for (span, seen) in evidence.iter().zip(covered.iter_mut()) {
+            if passage.contains(span.as_str()) {
+                relevant = true;
+                novel |= !*seen;
+                *seen = true;
+            }
+        }
+        if relevant && first_relevant.is_none() {
+            first_relevant = Some(rank);
+        }
+        if novel {
+            dcg += 1.0 / (rank as f64 + 2.0).log2();
+        }
+    }
+
+    let ideal: f64 = (0..evidence.len().min(k))
+        .map(|rank| 1.0 / (rank as f64 + 2.0).log2())
+        .sum();
+
+    Some(RetrievalMetrics {
+        recall_at_k: covered.iter().filter(|c| **c).count() as f64 / evidence.len() as f64,
+        mrr: first_relevant.map_or(0.0, |rank| 1.0 / (rank as f64 + 1.0)),
+        ndcg_at_k: if ideal > 0.0 { dcg / ideal } else { 0.0 },
+    })
+}
+
+/// Answer quality for one item, or averaged over a benchmark.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct AnswerMetrics {
+    /// 1.0 if the normalized answer equals a normalized gold answer.
+    pub exact_match: f64,
+    /// Best token-level F1 against any gold answer.
+    pub f1: f64,
+}
+
+impl AnswerMetrics {
+    /// Average metrics over items.
+    pub fn mean(metrics: &[AnswerMetrics]) -> Option<AnswerMetrics> {
+        if metrics.is_empty() {
+            return None;
+        }
+        let n = metrics.len() as f64;
+        Some(AnswerMetrics {
+            exact_match: metrics.iter().map(|m| m.exact_match).sum::<f64>() / n,
+            f1: metrics.iter().map(|m| m.f1).sum::<f64>() / n,
+        })
+    }
+}
+
+/// SQuAD answer normalization: lowercase, drop punctuation and the articles
+/// "a", "an", "the", and collapse whitespace.
+pub fn normalize_answer(text: &str) -> String {
+    let text: String = text
+        .to_lowercase()
+        .chars()
+        .filter(|c| !c.is_ascii_punctuation())
+        .collect();
+    text.split_whitespace()
+        .filter(|w| !matches!(*w, "a" | "an" | "the"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn token_f1(prediction: &str, gold: &str) -> f64 {
+    let prediction: Vec<_> = prediction.split_whitespace().collect();
+    let gold: Vec<_> = gold.split_whitespace().collect();
+    if prediction.is_empty() || gold.is_empty() {
+        // Both empty counts as a match, as in the SQuAD script
+        return if prediction == gold { 1.0 } else { 0.0 };
+    }
+
+    let mut gold_counts: HashMap<&str, usize> = HashMap::new();
+    for token in &gold {
+        *gold_counts.entry(token).or_insert(0) += 1;
+    }
+    let mut common = 0;
+    for token in &prediction {
+        if let Some(count) = gold_counts.get_mut(token)
+            && *count > 0
+        {
+            *count -= 1;
+            common += 1;
+        }
+    }
+    if common == 0 {
+        return 0.0;
+    }
+
+    let precision = common as f64 / prediction.len() as f64;
+    let recall = common as f64 / gold.len() as f64;
+    2.0 * precision * recall / (precision + recall)
+}
+
+/// Score an answer against gold answers, taking the best match for each metric.
+///
+/// Returns `None` when there are no gold answers.
+pub fn answer_metrics(prediction: &str, golds: &[&str]) -> Option<AnswerMetrics> {
+    if golds.is_empty() {
+        return None;
+    }
+    let prediction = normalize_answer(prediction);
+    let golds: Vec<String> = golds.iter().map(|g| normalize_answer(g)).collect();
+
+    Some(AnswerMetrics {
+        exact_match: if golds.contains(&prediction) {
+            1.0
+        } else {
+            0.0
+        },
+        f1: golds
+            .iter()
+            .map(|g| token_f1(&prediction, g))
+            .fold(0.0, f64::max),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(items: &[&str]) -> Vec<String> {
+        items.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_retrieval_metrics() {
+        let passages = strings(&[
+            "Nothing useful here.",
+            "The band  formed in\n1970 in London.",
+            "Beta formed in 1980.",
+        ]);
+        let evidence = strings(&["the band formed in 1970", "Beta formed in 1980", "missing"]);
+
+        let m = retrieval_metrics(&passages, &evidence, 3).unwrap();
+        assert!((m.recall_at_k - 2.0 / 3.0).abs() < 1e-9);
+        assert!((m.mrr - 0.5).abs() < 1e-9);
+        let dcg = 1.0 / 3f64.log2() + 1.0 / 4f64.log2();
+        let ideal = 1.0 + 1.0 / 3f64.log2() + 1.0 / 4f64.log2();
+        assert!((m.ndcg_at_k - dcg / ideal).abs() < 1e-9);
+
+        // Only the first k passages count
+        let m = retrieval_metrics(&passages, &evidence, 1).unwrap();
+        assert_eq!(m, RetrievalMetrics::default());
+
+        assert!(retrieval_metrics(&passages, &[], 3).is_none());
+    }
+
+    #[test]
+    fn test_answer_metrics() {
+        assert_eq!(normalize_answer("The  Eiffel Tower!"), "eiffel tower");
+
+        let m = answer_metrics("the Eiffel tower.", &["Eiffel Tower"]).unwrap();
+        assert_eq!(m.exact_match, 1.0);
+        assert_eq!(m.f1, 1.0);
+
+        // Best F1 across golds: "in paris" vs "paris france" shares one token
+        let m = answer_metrics("in Paris", &["London", "Paris, France"]).unwrap();
+        assert_eq!(m.exact_match, 0.0);
+        assert!((m.f1 - 0.5).abs() < 1e-9);
+
+        assert!(answer_metrics("anything", &[]).is_none());
+    }
+
+    #[test]
+    fn test_overlapping_passages_do_not_exceed_ideal() {
+        let passages = strings(&["gold span", "also the gold span"]);
+        let m = retrieval_metrics(&passages, &strings(&["gold span"]), 2).unwrap();
+        assert_eq!(m.ndcg_at_k, 1.0);
+        assert_eq!(m.mrr, 1.0);
+    }
+}
@@ -1,20 +1,64 @@
 //! Evaluation module for comparing PageIndex vs Vector Search.
 //!
 //! This module provides:
-//! - Vector search baseline using local embeddings (candle + sentence-transformers)
-//! - LLM-as-judge evaluation framework
-//! - Benchmark dataset loading (QuALITY, QASPER)
+//! - Vector search baseline using local (candle) or API embeddings
+//! - BM25 keyword and hybrid (BM25 + vector, reciprocal rank fusion) baselines
+//!   with exact or HNSW approximate nearest-neighbor search
+//! - Cross-encoder reranking of vector candidates and PageIndex sections
+//! - LLM-as-judge evaluation framework with customizable rubrics
+//! - Retrieval metrics (recall@k, MRR, nDCG) against gold evidence
+//! - Exact-match and token-F1 answer metrics against gold answers
+//! - HTML/Markdown benchmark reports and CSV/Parquet item-level export
+//! - Bootstrap confidence intervals and significance tests
+//! - Benchmark dataset loading (QuALITY, NarrativeQA, HotpotQA)
+//! - Synthetic Q&A datasets generated from an indexed corpus
+//! - Regression checks comparing two versions of an index
 
 pub mod benchmark;
+pub mod bm25;
 pub mod dataset;
 pub mod embeddings;
+pub mod export;
+pub mod hnsw;
+pub mod hybrid;
 pub mod judge;
+#[cfg(feature = "local-embeddings")]
+mod local_embeddings;
+pub mod metrics;
+pub mod regression;
+pub mod report;
+pub mod rerank;
+pub mod rubric;
+pub mod stats;
+pub mod synthetic;
+pub mod system;
 pub mod vector_search;
 
-pub use benchmark::{Benchmark, BenchmarkConfig, BenchmarkResults};
+pub use benchmark::{
+    BaselineResult, BaselineSummary, Benchmark, BenchmarkConfig, BenchmarkResults, ItemResult,
+    ItemUsage, PairwiseMatch, SystemCost, TagBreakdown, load_results_log, tree_path,
+};
+pub use bm25::{Bm25Config, Bm25Index, Bm25System};
 pub use dataset::{
-    Dataset, DatasetItem, create_sample_dataset, load_quality_dataset, load_simple_dataset,
+    Dataset, DatasetItem, QualityFilter, SupportingFact, create_sample_dataset,
+    download_narrativeqa, load_hotpotqa_dataset, load_narrativeqa_dataset, load_quality_dataset,
+    load_quality_dataset_filtered, load_simple_dataset, quality_split_path,
+};
+pub use embeddings::{
+    DEFAULT_API_EMBEDDING_MODEL, DEFAULT_EMBEDDING_MODEL, EmbeddingBackend, EmbeddingDevice,
+    EmbeddingModel, ModelSpec, Pooling,
+};
+pub use hnsw::{HnswConfig, HnswIndex};
+pub use judge::{
+    AnswerGrade, ComparisonResult, GradeSummary, Groundedness, GroundednessSummary, JudgeCache,
+    JudgeResult, JudgeVerdict, LlmJudge,
+};
+pub use metrics::{
+    AnswerMetrics, RetrievalMetrics, answer_metrics, normalize_answer, retrieval_metrics,
 };
-pub use embeddings::EmbeddingModel;
-pub use judge::{ComparisonResult, JudgeResult, LlmJudge};
-pub use vector_search::{ChunkConfig, VectorIndex, VectorSearcher};
+pub use regression::{QueryDiff, RegressionQuery, RegressionReport, RegressionRun};
+pub use rerank::{DEFAULT_RERANKER_MODEL, Reranker};
+pub use rubric::{CriterionScore, Rubric, RubricCriterion};
+pub use synthetic::QuestionGenerator;
+pub use system::{Retrieval, RetrievalSystem};
+pub use vector_search::{ChunkConfig, ChunkUnit, VectorIndex, VectorSearcher, VectorSystem};
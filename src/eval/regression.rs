@@ -0,0 +1,470 @@
+//! Regression checks between two versions of an index.
+//!
+//! A [`RegressionRun`] runs the same queries against a baseline and a
+//! candidate index of one document (e.g. built with different prompts or
+//! models), diffs the sections each retrieves, and has the judge compare
+//! the answers built from them. The [`RegressionReport`] lists per-query
+//! changes and the candidate's quality delta, so a prompt or model change
+//! can be validated before rollout.
+//!
+//! Queries whose retrieved sections and answers are unchanged are not
+//! judged.
+
+use super::dataset::Dataset;
+use super::judge::{ComparisonResult, LlmJudge};
+use super::stats::Significance;
+use crate::document::Document;
+use crate::error::Result;
+use crate::llm::{LlmClient, UsageStats};
+use crate::page_index::{AnswerStyle, answer_from};
+use crate::query::terms_in;
+use crate::search::{SearchOptions, SearchResult, TreeSearcher};
+use crate::tree::DocumentTree;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+/// A query to run against both indexes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RegressionQuery {
+    pub id: String,
+    pub query: String,
+    /// Reference answer shown to the judge, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub answer: Option<String>,
+}
+
+impl RegressionQuery {
+    /// The questions of a dataset, with their reference answers.
+    pub fn from_dataset(dataset: &Dataset) -> Vec<Self> {
+        dataset
+            .items
+            .iter()
+            .map(|item| Self {
+                id: item.id.clone(),
+                query: item.question.clone(),
+                answer: item.answer.clone(),
+            })
+            .collect()
+    }
+
+    /// One query per non-empty line (`#` starts a comment line), numbered
+    /// from 1.
+    pub fn from_lines(text: &str) -> Vec<Self> {
+        text.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .enumerate()
+            .map(|(i, line)| Self {
+                id: (i + 1).to_string(),
+                query: line.to_string(),
+                answer: None,
+            })
+            .collect()
+    }
+}
+
+/// One query's results from both indexes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryDiff {
+    pub id: String,
+    pub query: String,
+    pub baseline: Vec<SearchResult>,
+    pub candidate: Vec<SearchResult>,
+    /// Sections only the candidate retrieved, as `title (pages a-b)`.
+    pub added: Vec<String>,
+    /// Sections only the baseline retrieved.
+    pub removed: Vec<String>,
+    pub baseline_answer: String,
+    pub candidate_answer: String,
+    /// The judge's comparison (system 1 = candidate), unless both indexes
+    /// retrieved and answered the same.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comparison: Option<ComparisonResult>,
+    /// Why the query could not be run or judged.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl QueryDiff {
+    /// Whether the candidate retrieved different sections or answered
+    /// differently.
+    pub fn changed(&self) -> bool {
+        !self.added.is_empty()
+            || !self.removed.is_empty()
+            || self.baseline_answer != self.candidate_answer
+    }
+
+    /// Whether the judge preferred the baseline.
+    pub fn regressed(&self) -> bool {
+        self.comparison.as_ref().is_some_and(|c| c.winner == 2)
+    }
+
+    /// Whether the judge preferred the candidate.
+    pub fn improved(&self) -> bool {
+        self.comparison.as_ref().is_some_and(|c| c.winner == 1)
+    }
+}
+
+/// Per-query diffs and the candidate's quality delta.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegressionReport {
+    pub baseline: String,
+    pub candidate: String,
+    pub queries: Vec<QueryDiff>,
+    /// Queries with different sections or answers.
+    pub changed: usize,
+    pub improved: usize,
+    pub regressed: usize,
+    /// Judged queries the judge called a tie.
+    pub ties: usize,
+    pub errors: usize,
+    /// Mean judge scores over judged queries (1-5).
+    pub avg_baseline_score: f64,
+    pub avg_candidate_score: f64,
+    /// Candidate vs. baseline over judged queries: win rate and score
+    /// difference (candidate - baseline).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub significance: Option<Significance>,
+}
+
+impl RegressionReport {
+    fn new(baseline: &str, candidate: &str, queries: Vec<QueryDiff>) -> Self {
+        let judged: Vec<&ComparisonResult> = queries
+            .iter()
+            .filter_map(|q| q.comparison.as_ref())
+            .collect();
+        let mean = |score: fn(&ComparisonResult) -> f32| {
+            if judged.is_empty() {
+                0.0
+            } else {
+                judged.iter().map(|c| score(c) as f64).sum::<f64>() / judged.len() as f64
+            }
+        };
+        let winners: Vec<u8> = judged.iter().map(|c| c.winner).collect();
+        let scores: Vec<(f64, f64)> = judged
+            .iter()
+            .map(|c| (c.score_system1 as f64, c.score_system2 as f64))
+            .collect();
+        Self {
+            baseline: baseline.to_string(),
+            candidate: candidate.to_string(),
+            changed: queries.iter().filter(|q| q.changed()).count(),
+            improved: queries.iter().filter(|q| q.improved()).count(),
+            regressed: queries.iter().filter(|q| q.regressed()).count(),
+            ties: judged.iter().filter(|c| !matches!(c.winner, 1 | 2)).count(),
+            errors: queries.iter().filter(|q| q.error.is_some()).count(),
+            avg_candidate_score: mean(|c| c.score_system1),
+            avg_baseline_score: mean(|c| c.score_system2),
+            significance: Significance::compute(&winners, &scores),
+            queries,
+        }
+    }
+
+    /// Queries where the judge preferred the baseline.
+    pub fn regressions(&self) -> Vec<&QueryDiff> {
+        self.queries.iter().filter(|q| q.regressed()).collect()
+    }
+
+    /// Print the totals, then each changed query.
+    pub fn print_summary(&self) {
+        println!("\n========== Regression Results ==========");
+        println!("Baseline:  {}", self.baseline);
+        println!("Candidate: {}", self.candidate);
+        println!("Queries:   {}", self.queries.len());
+        println!("----------------------------------------");
+        println!("Changed:   {}", self.changed);
+        println!("Improved:  {}", self.improved);
+        println!("Regressed: {}", self.regressed);
+        println!("Ties:      {}", self.ties);
+        if self.errors > 0 {
+            println!("Errors:    {}", self.errors);
+        }
+        println!("----------------------------------------");
+        println!("Avg baseline score:  {:.2}/5", self.avg_baseline_score);
+        println!("Avg candidate score: {:.2}/5", self.avg_candidate_score);
+        if let Some(sig) = &self.significance {
+            let verdict = |significant: bool| {
+                if significant {
+                    "significant"
+                } else {
+                    "not significant"
+                }
+            };
+            println!(
+                "Score difference: {:+.2} [95% CI {:+.2} to {:+.2}], p={:.3} ({})",
+                sig.score_difference.estimate,
+                sig.score_difference.lower,
+                sig.score_difference.upper,
+                sig.score_p_value,
+                verdict(sig.scores_significant())
+            );
+        }
+
+        for diff in self
+            .queries
+            .iter()
+            .filter(|q| q.changed() || q.error.is_some())
+        {
+            let verdict = match (&diff.comparison, &diff.error) {
+                (_, Some(error)) => format!("error: {}", error),
+                (Some(c), _) if c.winner == 1 => format!(
+                    "improved ({:.1} -> {:.1})",
+                    c.score_system2, c.score_system1
+                ),
+                (Some(c), _) if c.winner == 2 => format!(
+                    "REGRESSED ({:.1} -> {:.1})",
+                    c.score_system2, c.score_system1
+                ),
+                (Some(_), _) => "tie".to_string(),
+                (None, _) => "not judged".to_string(),
+            };
+            println!("\n[{}] {}: {}", diff.id, diff.query, verdict);
+            for section in &diff.added {
+                println!("  + {}", section);
+            }
+            for section in &diff.removed {
+                println!("  - {}", section);
+            }
+            if let Some(c) = &diff.comparison {
+                println!("  {}", c.explanation);
+            }
+        }
+        println!("========================================");
+    }
+}
+
+/// Runs a query set against two versions of an index.
+pub struct RegressionRun {
+    client: LlmClient,
+    judge: LlmJudge,
+    top_k: usize,
+}
+
+impl RegressionRun {
+    /// Search and answer with `client`, compare answers with `judge`.
+    pub fn new(client: LlmClient, judge: LlmJudge) -> Self {
+        Self {
+            client,
+            judge,
+            top_k: SearchOptions::default().top_k,
+        }
+    }
+
+    /// Set the number of sections retrieved per query.
+    pub fn with_top_k(mut self, top_k: usize) -> Self {
+        self.top_k = top_k.max(1);
+        self
+    }
+
+    /// LLM usage of the judge so far.
+    pub fn judge_usage(&self) -> UsageStats {
+        self.judge.usage()
+    }
+
+    /// Search one index and answer from its results.
+    async fn answer(
+        &self,
+        tree: &DocumentTree,
+        document: Option<&Document>,
+        query: &str,
+    ) -> Result<(Vec<SearchResult>, String)> {
+        let searcher = TreeSearcher::with_options(
+            self.client.clone(),
+            SearchOptions {
+                top_k: self.top_k,
+                ..SearchOptions::default()
+            },
+        );
+        let mut results = match document {
+            Some(document) => searcher.search_with_content(tree, document, query).await?,
+            None => searcher.search(tree, query).await?,
+        };
+        results.truncate(self.top_k);
+        let terms = terms_in(query, &[&tree.terms]);
+        let answer = answer_from(
+            &self.client,
+            query,
+            results,
+            &AnswerStyle::default(),
+            &terms,
+            document,
+        )
+        .await?;
+        Ok((answer.sources, answer.answer))
+    }
+
+    /// Run every query against `baseline` and `candidate`, with section
+    /// text from `document` if given. A query that fails is reported with
+    /// its error rather than failing the run.
+    pub async fn run(
+        &self,
+        (baseline_name, baseline): (&str, &DocumentTree),
+        (candidate_name, candidate): (&str, &DocumentTree),
+        document: Option<&Document>,
+        queries: &[RegressionQuery],
+    ) -> RegressionReport {
+        let mut diffs = Vec::new();
+        for (i, query) in queries.iter().enumerate() {
+            info!(query = %query.id, progress = %format!("{}/{}", i + 1, queries.len()), "running regression query");
+            let mut diff = QueryDiff {
+                id: query.id.clone(),
+                query: query.query.clone(),
+                baseline: Vec::new(),
+                candidate: Vec::new(),
+                added: Vec::new(),
+                removed: Vec::new(),
+                baseline_answer: String::new(),
+                candidate_answer: String::new(),
+                comparison: None,
+                error: None,
+            };
+            let answers = match self.answer(baseline, document, &query.query).await {
+                Ok(base) => self
+                    .answer(candidate, document, &query.query)
+                    .await
+                    .map(|cand| (base, cand)),
+                Err(e) => Err(e),
+            };
+            let ((baseline_results, baseline_answer), (candidate_results, candidate_answer)) =
+                match answers {
+                    Ok(answers) => answers,
+                    Err(e) => {
+                        warn!(query = %query.id, "regression query failed: {}", e);
+                        diff.error = Some(e.to_string());
+                        diffs.push(diff);
+                        continue;
+                    }
+                };
+            diff.added = section_labels(&candidate_results, &baseline_results);
+            diff.removed = section_labels(&baseline_results, &candidate_results);
+            diff.baseline = baseline_results;
+            diff.candidate = candidate_results;
+            diff.baseline_answer = baseline_answer;
+            diff.candidate_answer = candidate_answer;
+
+            if diff.changed() {
+                match self
+                    .judge
+                    .compare_answers(
+                        &query.query,
+                        candidate_name,
+                        &diff.candidate_answer,
+                        baseline_name,
+                        &diff.baseline_answer,
+                        query.answer.as_deref(),
+                    )
+                    .await
+                {
+                    Ok(comparison) => diff.comparison = Some(comparison),
+                    Err(e) => {
+                        warn!(query = %query.id, "judging failed: {}", e);
+                        diff.error = Some(e.to_string());
+                    }
+                }
+            }
+            diffs.push(diff);
+        }
+        RegressionReport::new(baseline_name, candidate_name, diffs)
+    }
+}
+
+/// Sections of `results` not in `other` (by title and page range), as
+/// `title (pages a-b)`.
+fn section_labels(results: &[SearchResult], other: &[SearchResult]) -> Vec<String> {
+    results
+        .iter()
+        .filter(|r| {
+            !other.iter().any(|o| {
+                o.title == r.title && o.start_index == r.start_index && o.end_index == r.end_index
+            })
+        })
+        .map(|r| format!("{} (pages {}-{})", r.title, r.start_index, r.end_index))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{chat_reply, replay_interactions};
+    use crate::golden::PromptCapture;
+    use crate::tree::TreeNode;
+    use serde_json::json;
+
+    #[test]
+    fn test_regression_queries() {
+        let queries =
+            RegressionQuery::from_lines("# smoke set\nWhat is X?\n\n  How is Y done?  \n");
+        assert_eq!(queries.len(), 2);
+        assert_eq!(
+            (queries[1].id.as_str(), queries[1].query.as_str()),
+            ("2", "How is Y done?")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_regression_run() {
+        let baseline = DocumentTree::new(
+            "manual",
+            vec![
+                TreeNode::new("Cleaning", 1, 1),
+                TreeNode::new("Warranty", 2, 2),
+            ],
+            2,
+        );
+        let candidate = DocumentTree::new(
+            "manual",
+            vec![
+                TreeNode::new("Cleaning the Filter", 1, 1),
+                TreeNode::new("Warranty", 2, 2),
+            ],
+            2,
+        );
+        let search = |title: &str, page: usize| {
+            json!({"thinking": "", "relevant_sections": [
+                {"title": title, "start_index": page, "end_index": page, "relevance": "high", "reason": "r"}
+            ]})
+            .to_string()
+        };
+        let verdict = json!({"winner": "A", "score_system_a": 5, "score_system_b": 3, "explanation": "More specific."}).to_string();
+        let interactions = vec![
+            // First query: same section, same answer from both
+            chat_reply(&search("Warranty", 2)),
+            chat_reply("Two years."),
+            chat_reply(&search("Warranty", 2)),
+            chat_reply("Two years."),
+            // Second query: the candidate finds the renamed section
+            chat_reply(&search("Warranty", 2)),
+            chat_reply("Not stated."),
+            chat_reply(&search("Cleaning the Filter", 1)),
+            chat_reply("Weekly."),
+            chat_reply(&verdict),
+        ];
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("regression.fixture.json");
+        let capture = PromptCapture::new(replay_interactions(&path, interactions));
+        let client = capture.client().unwrap();
+
+        let run = RegressionRun::new(client.clone(), LlmJudge::new(client)).with_top_k(1);
+        let queries = RegressionQuery::from_lines(
+            "How long is the warranty?\nHow often is the filter cleaned?",
+        );
+        let report = run
+            .run(("v1", &baseline), ("v2", &candidate), None, &queries)
+            .await;
+
+        assert_eq!(capture.prompts().len(), 9);
+        assert_eq!(
+            (report.changed, report.improved, report.regressed),
+            (1, 1, 0)
+        );
+        assert!(!report.queries[0].changed());
+        assert!(report.queries[0].comparison.is_none());
+        let diff = &report.queries[1];
+        assert_eq!(diff.added, vec!["Cleaning the Filter (pages 1-1)"]);
+        assert_eq!(diff.removed, vec!["Warranty (pages 2-2)"]);
+        assert!(diff.improved());
+        assert_eq!(report.avg_candidate_score, 5.0);
+        assert_eq!(report.avg_baseline_score, 3.0);
+        assert!(report.regressions().is_empty());
+    }
+}
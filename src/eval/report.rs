@@ -0,0 +1,905 @@
+//! Standalone HTML and Markdown reports for benchmark results.
+//!
+//! Reports include summary metrics, additional baselines, a win-rate chart,
+//! latency histograms, a per-item table, and example retrievals. HTML reports are self-contained
+//! (inline CSS, no scripts) so they can be attached or archived as is.
+
+use super::benchmark::{BenchmarkResults, ItemResult};
+use super::judge::{GradeSummary, GroundednessSummary};
+use super::stats::{LatencyStats, Significance};
+use anyhow::Result;
+use std::fmt::Write;
+use std::path::Path;
+
+/// Number of buckets in latency histograms.
+const HISTOGRAM_BINS: usize = 10;
+/// Number of items shown in the example retrievals section.
+const EXAMPLE_COUNT: usize = 3;
+/// Maximum characters of retrieved content shown per example.
+const EXAMPLE_CHARS: usize = 600;
+
+/// A latency histogram bucket: `[start_ms, end_ms)` and per-system counts.
+#[derive(Debug, Clone, PartialEq)]
+struct Bucket {
+    start_ms: u64,
+    end_ms: u64,
+    pageindex: usize,
+    vector: usize,
+}
+
+/// Bucket both systems' latencies over a shared range.
+fn latency_histogram(results: &BenchmarkResults, bins: usize) -> Vec<Bucket> {
+    let pageindex: Vec<u64> = results
+        .item_results
+        .iter()
+        .filter_map(|r| r.pageindex_time_ms)
+        .collect();
+    let vector: Vec<u64> = results
+        .item_results
+        .iter()
+        .filter_map(|r| r.vector_time_ms)
+        .collect();
+
+    let all = pageindex.iter().chain(&vector);
+    let (Some(&min), Some(&max)) = (all.clone().min(), all.max()) else {
+        return Vec::new();
+    };
+    let width = ((max - min) / bins as u64 + 1).max(1);
+
+    let mut buckets: Vec<Bucket> = (0..bins as u64)
+        .map(|i| Bucket {
+            start_ms: min + i * width,
+            end_ms: min + (i + 1) * width,
+            pageindex: 0,
+            vector: 0,
+        })
+        .collect();
+    for &t in &pageindex {
+        buckets[((t - min) / width) as usize].pageindex += 1;
+    }
+    for &t in &vector {
+        buckets[((t - min) / width) as usize].vector += 1;
+    }
+    buckets
+}
+
+fn winner_label(result: &ItemResult) -> &'static str {
+    match result.comparison.as_ref().map(|c| c.winner) {
+        Some(1) => "PageIndex",
+        Some(2) => "Vector",
+        Some(_) => "Tie",
+        None => "-",
+    }
+}
+
+fn percent(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        count as f64 / total as f64 * 100.0
+    }
+}
+
+fn opt_ms(ms: Option<u64>) -> String {
+    ms.map_or("-".to_string(), |ms| ms.to_string())
+}
+
+fn opt_correct(correct: Option<bool>) -> &'static str {
+    match correct {
+        Some(true) => "yes",
+        Some(false) => "no",
+        None => "-",
+    }
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((i, _)) => format!("{}...", &text[..i]),
+        None => text.to_string(),
+    }
+}
+
+/// Summary rows shared by both formats: (metric, PageIndex, Vector).
+fn summary_rows(results: &BenchmarkResults) -> Vec<(String, String, String)> {
+    let mut rows = vec![
+        (
+            "Wins".to_string(),
+            format!(
+                "{} ({:.1}%)",
+                results.pageindex_wins,
+                percent(results.pageindex_wins, results.total_items)
+            ),
+            format!(
+                "{} ({:.1}%)",
+                results.vector_wins,
+                percent(results.vector_wins, results.total_items)
+            ),
+        ),
+        (
+            "Avg judge score".to_string(),
+            format!("{:.2}/5", results.avg_pageindex_score),
+            format!("{:.2}/5", results.avg_vector_score),
+        ),
+        (
+            "Avg retrieval time".to_string(),
+            format!("{:.0}ms", results.avg_pageindex_time_ms),
+            format!("{:.0}ms", results.avg_vector_time_ms),
+        ),
+    ];
+
+    let percentiles = |l: Option<LatencyStats>| {
+        l.map_or("-".to_string(), |l| {
+            format!("{:.0} / {:.0} / {:.0}ms", l.p50_ms, l.p95_ms, l.p99_ms)
+        })
+    };
+    if results.pageindex_latency.is_some() || results.vector_latency.is_some() {
+        rows.push((
+            "Retrieval p50 / p95 / p99".to_string(),
+            percentiles(results.pageindex_latency),
+            percentiles(results.vector_latency),
+        ));
+    }
+    if results.pageindex_search_latency.is_some() {
+        rows.push((
+            "Tree build (amortized / query)".to_string(),
+            format!("{:.0}ms", results.amortized_pageindex_build_ms),
+            "-".to_string(),
+        ));
+        rows.push((
+            "Search p50 / p95 / p99 (excl. build)".to_string(),
+            percentiles(results.pageindex_search_latency),
+            "-".to_string(),
+        ));
+    }
+
+    let fmt = |v: Option<f64>| v.map_or("-".to_string(), |v| format!("{:.3}", v));
+    if results.pageindex_accuracy.is_some() || results.vector_accuracy.is_some() {
+        let pct = |v: Option<f64>| v.map_or("-".to_string(), |v| format!("{:.1}%", v * 100.0));
+        rows.push((
+            "Accuracy".to_string(),
+            pct(results.pageindex_accuracy),
+            pct(results.vector_accuracy),
+        ));
+    }
+    if results.pageindex_grades.is_some() || results.vector_grades.is_some() {
+        let grade = |g: Option<GradeSummary>| {
+            g.map_or("-".to_string(), |g| {
+                format!(
+                    "{:.2}/5 ({:.1}% correct)",
+                    g.avg_score,
+                    g.correct_rate * 100.0
+                )
+            })
+        };
+        rows.push((
+            "Graded correctness".to_string(),
+            grade(results.pageindex_grades),
+            grade(results.vector_grades),
+        ));
+    }
+    if results.pageindex_groundedness.is_some() || results.vector_groundedness.is_some() {
+        let grounded = |g: Option<GroundednessSummary>| {
+            g.map_or("-".to_string(), |g| {
+                format!(
+                    "{:.1}% ({} correct but unsupported)",
+                    g.grounded_rate * 100.0,
+                    g.correct_ungrounded
+                )
+            })
+        };
+        rows.push((
+            "Grounded in retrieval".to_string(),
+            grounded(results.pageindex_groundedness),
+            grounded(results.vector_groundedness),
+        ));
+    }
+    let (pi, vec) = (
+        results.pageindex_answer_metrics,
+        results.vector_answer_metrics,
+    );
+    if pi.is_some() || vec.is_some() {
+        rows.push((
+            "Exact match".to_string(),
+            fmt(pi.map(|m| m.exact_match)),
+            fmt(vec.map(|m| m.exact_match)),
+        ));
+        rows.push((
+            "Token F1".to_string(),
+            fmt(pi.map(|m| m.f1)),
+            fmt(vec.map(|m| m.f1)),
+        ));
+    }
+    let (pi, vec) = (results.pageindex_retrieval, results.vector_retrieval);
+    if pi.is_some() || vec.is_some() {
+        rows.push((
+            "Recall@k".to_string(),
+            fmt(pi.map(|m| m.recall_at_k)),
+            fmt(vec.map(|m| m.recall_at_k)),
+        ));
+        rows.push((
+            "MRR".to_string(),
+            fmt(pi.map(|m| m.mrr)),
+            fmt(vec.map(|m| m.mrr)),
+        ));
+        rows.push((
+            "nDCG@k".to_string(),
+            fmt(pi.map(|m| m.ndcg_at_k)),
+            fmt(vec.map(|m| m.ndcg_at_k)),
+        ));
+    }
+    rows
+}
+
+/// Column headers for the additional baselines table.
+const BASELINE_HEADERS: [&str; 8] = [
+    "Baseline",
+    "PageIndex wins",
+    "Baseline wins",
+    "Ties",
+    "Avg score (PI / baseline)",
+    "Avg time",
+    "Accuracy / F1",
+    "Recall@k",
+];
+
+/// Rows for the additional baselines table, one per baseline.
+fn baseline_rows(results: &BenchmarkResults) -> Vec<[String; 8]> {
+    results
+        .baselines
+        .iter()
+        .map(|b| {
+            let quality = match (b.accuracy, b.answer_metrics) {
+                (Some(acc), _) => format!("{:.1}%", acc * 100.0),
+                (None, Some(m)) => format!("{:.3}", m.f1),
+                (None, None) => "-".to_string(),
+            };
+            [
+                b.system.clone(),
+                b.pageindex_wins.to_string(),
+                b.baseline_wins.to_string(),
+                b.ties.to_string(),
+                format!("{:.2} / {:.2}", b.avg_pageindex_score, b.avg_baseline_score),
+                format!("{:.0}ms", b.avg_time_ms),
+                quality,
+                b.retrieval
+                    .map_or("-".to_string(), |m| format!("{:.3}", m.recall_at_k)),
+            ]
+        })
+        .collect()
+}
+
+/// Column headers for the leaderboard table.
+const LEADERBOARD_HEADERS: [&str; 6] = ["Rank", "System", "Rating", "Wins", "Losses", "Ties"];
+
+/// Rows for the leaderboard table, best system first.
+fn leaderboard_rows(results: &BenchmarkResults) -> Vec<[String; 6]> {
+    results
+        .leaderboard
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            [
+                (i + 1).to_string(),
+                entry.system.clone(),
+                format!("{:.0}", entry.rating),
+                entry.wins.to_string(),
+                entry.losses.to_string(),
+                entry.ties.to_string(),
+            ]
+        })
+        .collect()
+}
+
+/// Column headers for the per-tag table.
+const TAG_HEADERS: [&str; 7] = [
+    "Tag",
+    "Items",
+    "PageIndex wins",
+    "Vector wins",
+    "Ties",
+    "Avg score (PI / Vec)",
+    "Accuracy / F1 (PI / Vec)",
+];
+
+/// Rows for the per-tag table, one per tag value.
+fn tag_rows(results: &BenchmarkResults) -> Vec<[String; 7]> {
+    results
+        .tag_breakdowns
+        .iter()
+        .map(|b| {
+            let quality = match (b.pageindex_accuracy, b.vector_accuracy) {
+                (None, None) => {
+                    let f1 = |v: Option<f64>| v.map_or("-".to_string(), |v| format!("{:.3}", v));
+                    format!("{} / {}", f1(b.pageindex_f1), f1(b.vector_f1))
+                }
+                (pi, vec) => {
+                    let pct = |v: Option<f64>| {
+                        v.map_or("-".to_string(), |v| format!("{:.1}%", v * 100.0))
+                    };
+                    format!("{} / {}", pct(pi), pct(vec))
+                }
+            };
+            [
+                format!("{}={}", b.tag, b.value),
+                b.items.to_string(),
+                b.pageindex_wins.to_string(),
+                b.vector_wins.to_string(),
+                b.ties.to_string(),
+                format!("{:.2} / {:.2}", b.avg_pageindex_score, b.avg_vector_score),
+                quality,
+            ]
+        })
+        .collect()
+}
+
+/// Column headers for the cost table.
+const COST_HEADERS: [&str; 6] = [
+    "System",
+    "Cost / query",
+    "Indexing (total)",
+    "Search + answer / query",
+    "LLM tokens (index / query)",
+    "Embedding tokens",
+];
+
+/// Rows for the cost table, one per system with recorded usage.
+fn cost_rows(results: &BenchmarkResults) -> Vec<[String; 6]> {
+    let usd = |cost: Option<f64>| cost.map_or("-".to_string(), |c| format!("${:.4}", c));
+    results
+        .costs
+        .iter()
+        .map(|c| {
+            [
+                c.system.clone(),
+                usd(c.cost_per_query_usd),
+                usd(c.index_cost_usd),
+                usd(c.query_cost_usd),
+                format!(
+                    "{} / {}",
+                    c.index_usage.total_tokens, c.query_usage.total_tokens
+                ),
+                c.embedding_tokens.to_string(),
+            ]
+        })
+        .collect()
+}
+
+/// One-sentence significance summary shared by both formats.
+fn significance_line(sig: &Significance) -> String {
+    let verdict = |significant: bool| {
+        if significant {
+            "significant"
+        } else {
+            "not significant"
+        }
+    };
+    format!(
+        "PageIndex win rate (excluding ties) {:.1}% [95% CI {:.1}-{:.1}%], sign test p={:.3} ({}); \
+         score difference {:+.2} [95% CI {:+.2} to {:+.2}], permutation test p={:.3} ({}); n={}.",
+        sig.win_rate.estimate * 100.0,
+        sig.win_rate.lower * 100.0,
+        sig.win_rate.upper * 100.0,
+        sig.win_p_value,
+        verdict(sig.wins_significant()),
+        sig.score_difference.estimate,
+        sig.score_difference.lower,
+        sig.score_difference.upper,
+        sig.score_p_value,
+        verdict(sig.scores_significant()),
+        sig.n
+    )
+}
+
+/// Render results as a Markdown report.
+pub fn render_markdown(results: &BenchmarkResults) -> String {
+    let mut out = String::new();
+    let _ = writeln!(out, "# Benchmark Report: {}\n", results.dataset_name);
+    let _ = writeln!(
+        out,
+        "{} items, {:.1}s total, {} LLM calls ({} tokens)\n",
+        results.total_items,
+        results.total_time_secs,
+        results.usage.calls,
+        results.usage.total_tokens
+    );
+
+    out.push_str("## Summary\n\n| Metric | PageIndex | Vector |\n|---|---|---|\n");
+    for (metric, pi, vec) in summary_rows(results) {
+        let _ = writeln!(out, "| {} | {} | {} |", metric, pi, vec);
+    }
+    if let Some(sig) = &results.significance {
+        let _ = writeln!(out, "\n{}", significance_line(sig));
+    }
+
+    let baselines = baseline_rows(results);
+    if !baselines.is_empty() {
+        let _ = writeln!(
+            out,
+            "\n## Other Baselines\n\n| {} |\n|{}",
+            BASELINE_HEADERS.join(" | "),
+            "---|".repeat(BASELINE_HEADERS.len())
+        );
+        for row in &baselines {
+            let _ = writeln!(out, "| {} |", row.join(" | "));
+        }
+    }
+
+    let leaderboard = leaderboard_rows(results);
+    if !leaderboard.is_empty() {
+        let _ = writeln!(
+            out,
+            "\n## Leaderboard\n\nBradley–Terry ratings over all judged pairs (1000 = average).\n\n| {} |\n|{}",
+            LEADERBOARD_HEADERS.join(" | "),
+            "---|".repeat(LEADERBOARD_HEADERS.len())
+        );
+        for row in &leaderboard {
+            let _ = writeln!(out, "| {} |", row.join(" | "));
+        }
+    }
+
+    let tags = tag_rows(results);
+    if !tags.is_empty() {
+        let _ = writeln!(
+            out,
+            "\n## By Tag\n\n| {} |\n|{}",
+            TAG_HEADERS.join(" | "),
+            "---|".repeat(TAG_HEADERS.len())
+        );
+        for row in &tags {
+            let _ = writeln!(out, "| {} |", row.join(" | "));
+        }
+    }
+
+    let costs = cost_rows(results);
+    if !costs.is_empty() {
+        let _ = writeln!(
+            out,
+            "\n## Cost\n\n| {} |\n|{}",
+            COST_HEADERS.join(" | "),
+            "---|".repeat(COST_HEADERS.len())
+        );
+        for row in &costs {
+            let _ = writeln!(out, "| {} |", row.join(" | "));
+        }
+    }
+
+    out.push_str("\n## Win Rate\n\n```\n");
+    for (label, count) in [
+        ("PageIndex", results.pageindex_wins),
+        ("Vector   ", results.vector_wins),
+        ("Tie      ", results.ties),
+    ] {
+        let pct = percent(count, results.total_items);
+        let _ = writeln!(
+            out,
+            "{} {:<50} {:.1}%",
+            label,
+            "#".repeat((pct / 2.0).round() as usize),
+            pct
+        );
+    }
+    out.push_str("```\n");
+
+    let histogram = latency_histogram(results, HISTOGRAM_BINS);
+    if !histogram.is_empty() {
+        out.push_str(
+            "\n## Retrieval Latency\n\n| Range (ms) | PageIndex | Vector |\n|---|---|---|\n",
+        );
+        for b in &histogram {
+            let _ = writeln!(
+                out,
+                "| {}-{} | {} | {} |",
+                b.start_ms, b.end_ms, b.pageindex, b.vector
+            );
+        }
+    }
+
+    out.push_str("\n## Items\n\n| Item | Winner | PI score | Vec score | PI ms | Vec ms | PI correct | Vec correct | Error |\n|---|---|---|---|---|---|---|---|---|\n");
+    for r in &results.item_results {
+        let (s1, s2) = r
+            .comparison
+            .as_ref()
+            .map_or(("-".to_string(), "-".to_string()), |c| {
+                (c.score_system1.to_string(), c.score_system2.to_string())
+            });
+        let _ = writeln!(
+            out,
+            "| {} | {} | {} | {} | {} | {} | {} | {} | {} |",
+            r.item_id,
+            winner_label(r),
+            s1,
+            s2,
+            opt_ms(r.pageindex_time_ms),
+            opt_ms(r.vector_time_ms),
+            opt_correct(r.pageindex_correct),
+            opt_correct(r.vector_correct),
+            r.error.as_deref().unwrap_or("").replace(['\n', '|'], " ")
+        );
+    }
+
+    out.push_str("\n## Example Retrievals\n");
+    for r in results.item_results.iter().take(EXAMPLE_COUNT) {
+        let _ = writeln!(out, "\n### {}\n", r.item_id);
+        for (label, content, answer) in [
+            ("PageIndex", &r.pageindex_content, &r.pageindex_answer),
+            ("Vector", &r.vector_content, &r.vector_answer),
+        ] {
+            if let Some(content) = content {
+                let _ = writeln!(
+                    out,
+                    "**{}** answer: {}\n\n```\n{}\n```\n",
+                    label,
+                    answer.as_deref().unwrap_or("-"),
+                    truncate(content, EXAMPLE_CHARS)
+                );
+            }
+        }
+    }
+
+    out
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+const HTML_STYLE: &str = "body{font-family:system-ui,sans-serif;max-width:1100px;margin:2em auto;padding:0 1em;color:#222}\
+table{border-collapse:collapse;margin:1em 0}th,td{border:1px solid #ccc;padding:4px 8px;text-align:left}\
+th{background:#f4f4f4}.bar{height:18px;display:inline-block;vertical-align:middle}\
+.pi{background:#3b82f6}.vec{background:#f59e0b}.tie{background:#9ca3af}\
+pre{background:#f7f7f7;padding:8px;white-space:pre-wrap}";
+
+/// Render results as a standalone HTML report.
+pub fn render_html(results: &BenchmarkResults) -> String {
+    let mut out = String::new();
+    let title = escape_html(&results.dataset_name);
+    let _ = write!(
+        out,
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Benchmark Report: {}</title><style>{}</style></head><body>\n",
+        title, HTML_STYLE
+    );
+    let _ = writeln!(out, "<h1>Benchmark Report: {}</h1>", title);
+    let _ = writeln!(
+        out,
+        "<p>{} items, {:.1}s total, {} LLM calls ({} tokens)</p>",
+        results.total_items,
+        results.total_time_secs,
+        results.usage.calls,
+        results.usage.total_tokens
+    );
+
+    out.push_str(
+        "<h2>Summary</h2>\n<table><tr><th>Metric</th><th>PageIndex</th><th>Vector</th></tr>\n",
+    );
+    for (metric, pi, vec) in summary_rows(results) {
+        let _ = writeln!(
+            out,
+            "<tr><td>{}</td><td>{}</td><td>{}</td></tr>",
+            metric, pi, vec
+        );
+    }
+    out.push_str("</table>\n");
+    if let Some(sig) = &results.significance {
+        let _ = writeln!(out, "<p>{}</p>", significance_line(sig));
+    }
+
+    let baselines = baseline_rows(results);
+    if !baselines.is_empty() {
+        let _ = writeln!(
+            out,
+            "<h2>Other Baselines</h2>\n<table><tr><th>{}</th></tr>",
+            BASELINE_HEADERS.join("</th><th>")
+        );
+        for row in &baselines {
+            let cells: Vec<String> = row.iter().map(|c| escape_html(c)).collect();
+            let _ = writeln!(out, "<tr><td>{}</td></tr>", cells.join("</td><td>"));
+        }
+        out.push_str("</table>\n");
+    }
+
+    let leaderboard = leaderboard_rows(results);
+    if !leaderboard.is_empty() {
+        let _ = writeln!(
+            out,
+            "<h2>Leaderboard</h2>\n<table><tr><th>{}</th></tr>",
+            LEADERBOARD_HEADERS.join("</th><th>")
+        );
+        for row in &leaderboard {
+            let cells: Vec<String> = row.iter().map(|c| escape_html(c)).collect();
+            let _ = writeln!(out, "<tr><td>{}</td></tr>", cells.join("</td><td>"));
+        }
+        out.push_str("</table>\n");
+    }
+
+    let tags = tag_rows(results);
+    if !tags.is_empty() {
+        let _ = writeln!(
+            out,
+            "<h2>By Tag</h2>\n<table><tr><th>{}</th></tr>",
+            TAG_HEADERS.join("</th><th>")
+        );
+        for row in &tags {
+            let cells: Vec<String> = row.iter().map(|c| escape_html(c)).collect();
+            let _ = writeln!(out, "<tr><td>{}</td></tr>", cells.join("</td><td>"));
+        }
+        out.push_str("</table>\n");
+    }
+
+    let costs = cost_rows(results);
+    if !costs.is_empty() {
+        let _ = writeln!(
+            out,
+            "<h2>Cost</h2>\n<table><tr><th>{}</th></tr>",
+            COST_HEADERS.join("</th><th>")
+        );
+        for row in &costs {
+            let cells: Vec<String> = row.iter().map(|c| escape_html(c)).collect();
+            let _ = writeln!(out, "<tr><td>{}</td></tr>", cells.join("</td><td>"));
+        }
+        out.push_str("</table>\n");
+    }
+
+    out.push_str("<h2>Win Rate</h2>\n<table>\n");
+    for (label, class, count) in [
+        ("PageIndex", "pi", results.pageindex_wins),
+        ("Vector", "vec", results.vector_wins),
+        ("Tie", "tie", results.ties),
+    ] {
+        let pct = percent(count, results.total_items);
+        let _ = writeln!(
+            out,
+            "<tr><td>{}</td><td style=\"width:400px\"><span class=\"bar {}\" style=\"width:{:.1}%\"></span></td><td>{} ({:.1}%)</td></tr>",
+            label, class, pct, count, pct
+        );
+    }
+    out.push_str("</table>\n");
+
+    let histogram = latency_histogram(results, HISTOGRAM_BINS);
+    if !histogram.is_empty() {
+        let max = histogram
+            .iter()
+            .map(|b| b.pageindex.max(b.vector))
+            .max()
+            .unwrap_or(1)
+            .max(1);
+        out.push_str("<h2>Retrieval Latency</h2>\n<table><tr><th>Range (ms)</th><th>PageIndex</th><th>Vector</th></tr>\n");
+        for b in &histogram {
+            let _ = writeln!(
+                out,
+                "<tr><td>{}-{}</td><td><span class=\"bar pi\" style=\"width:{}px\"></span> {}</td><td><span class=\"bar vec\" style=\"width:{}px\"></span> {}</td></tr>",
+                b.start_ms,
+                b.end_ms,
+                b.pageindex * 200 / max,
+                b.pageindex,
+                b.vector * 200 / max,
+                b.vector
+            );
+        }
+        out.push_str("</table>\n");
+    }
+
+    out.push_str("<h2>Items</h2>\n<table><tr><th>Item</th><th>Winner</th><th>PI score</th><th>Vec score</th><th>PI ms</th><th>Vec ms</th><th>PI correct</th><th>Vec correct</th><th>Error</th></tr>\n");
+    for r in &results.item_results {
+        let (s1, s2) = r
+            .comparison
+            .as_ref()
+            .map_or(("-".to_string(), "-".to_string()), |c| {
+                (c.score_system1.to_string(), c.score_system2.to_string())
+            });
+        let _ = writeln!(
+            out,
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape_html(&r.item_id),
+            winner_label(r),
+            s1,
+            s2,
+            opt_ms(r.pageindex_time_ms),
+            opt_ms(r.vector_time_ms),
+            opt_correct(r.pageindex_correct),
+            opt_correct(r.vector_correct),
+            escape_html(r.error.as_deref().unwrap_or(""))
+        );
+    }
+    out.push_str("</table>\n");
+
+    out.push_str("<h2>Example Retrievals</h2>\n");
+    for r in results.item_results.iter().take(EXAMPLE_COUNT) {
+        let _ = writeln!(out, "<h3>{}</h3>", escape_html(&r.item_id));
+        for (label, content, answer) in [
+            ("PageIndex", &r.pageindex_content, &r.pageindex_answer),
+            ("Vector", &r.vector_content, &r.vector_answer),
+        ] {
+            if let Some(content) = content {
+                let _ = writeln!(
+                    out,
+                    "<p><b>{}</b> answer: {}</p><pre>{}</pre>",
+                    label,
+                    escape_html(answer.as_deref().unwrap_or("-")),
+                    escape_html(&truncate(content, EXAMPLE_CHARS))
+                );
+            }
+        }
+    }
+
+    out.push_str("</body></html>\n");
+    out
+}
+
+/// Write a report as HTML if the path ends in `.html`/`.htm`, otherwise as Markdown.
+pub fn write_report(results: &BenchmarkResults, path: &Path) -> Result<()> {
+    let is_html = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("html") || e.eq_ignore_ascii_case("htm"));
+
+    let content = if is_html {
+        render_html(results)
+    } else {
+        render_markdown(results)
+    };
+    std::fs::write(path, content)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::ModelPricing;
+    use crate::eval::benchmark::{BaselineResult, ItemUsage, PairwiseMatch};
+    use crate::eval::judge::ComparisonResult;
+    use crate::llm::UsageStats;
+
+    fn sample_results() -> BenchmarkResults {
+        let mut results = BenchmarkResults::new("test <set>");
+        for (id, pi_ms, vec_ms, winner) in [("a", 100, 10, 1), ("b", 300, 20, 2)] {
+            let mut item: ItemResult =
+                serde_json::from_value(serde_json::json!({ "item_id": id })).unwrap();
+            item.pageindex_time_ms = Some(pi_ms);
+            item.vector_time_ms = Some(vec_ms);
+            item.pageindex_content = Some("<section> text".to_string());
+            item.comparison = Some(ComparisonResult {
+                winner,
+                score_system1: 4.0,
+                score_system2: 3.0,
+                explanation: String::new(),
+                order_consistent: None,
+                judges: Vec::new(),
+                agreement: None,
+            });
+            results.item_results.push(item);
+        }
+        results.calculate_summary();
+        results
+    }
+
+    #[test]
+    fn test_latency_histogram() {
+        let histogram = latency_histogram(&sample_results(), 10);
+        assert_eq!(histogram.len(), 10);
+        assert_eq!(histogram[0].start_ms, 10);
+        assert_eq!(histogram.iter().map(|b| b.pageindex).sum::<usize>(), 2);
+        assert_eq!(histogram[0].vector, 2);
+        assert_eq!(histogram[9].pageindex, 1);
+    }
+
+    #[test]
+    fn test_render_reports() {
+        let results = sample_results();
+
+        let md = render_markdown(&results);
+        assert!(md.contains("| Wins | 1 (50.0%) | 1 (50.0%) |"));
+        assert!(md.contains("| a | PageIndex | 4 | 3 | 100 | 10 |"));
+        assert!(md.contains("| Retrieval p50 / p95 / p99 | 200 / 290 / 298ms |"));
+        assert!(!md.contains("Other Baselines"));
+
+        let html = render_html(&results);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("test &lt;set&gt;"));
+        assert!(html.contains("&lt;section&gt; text"));
+        assert!(!html.contains("<section>"));
+    }
+
+    #[test]
+    fn test_baselines_table() {
+        let mut results = sample_results();
+        for item in &mut results.item_results {
+            let mut baseline: BaselineResult =
+                serde_json::from_value(serde_json::json!({ "system": "BM25" })).unwrap();
+            baseline.time_ms = Some(3);
+            baseline.comparison = item.comparison.clone();
+            item.baselines.push(baseline);
+        }
+        results.calculate_summary();
+
+        let md = render_markdown(&results);
+        assert!(md.contains("## Other Baselines"));
+        assert!(md.contains("| BM25 | 1 | 1 | 0 | 4.00 / 3.00 | 3ms | - | - |"));
+        assert!(render_html(&results).contains("<td>BM25</td><td>1</td>"));
+    }
+
+    #[test]
+    fn test_leaderboard_table() {
+        let mut results = sample_results();
+        for item in &mut results.item_results {
+            let mut baseline: BaselineResult =
+                serde_json::from_value(serde_json::json!({ "system": "BM25" })).unwrap();
+            baseline.comparison = item.comparison.clone();
+            item.baselines.push(baseline);
+        }
+        results.calculate_summary();
+        // Three systems judged against PageIndex are enough for a leaderboard
+        assert_eq!(results.leaderboard.len(), 3);
+
+        for item in &mut results.item_results {
+            let comparison = item.comparison.clone().unwrap();
+            item.matches.push(PairwiseMatch {
+                system1: "Vector".to_string(),
+                system2: "BM25".to_string(),
+                comparison: ComparisonResult {
+                    winner: 1,
+                    ..comparison
+                },
+            });
+        }
+        results.calculate_summary();
+        let systems: Vec<&str> = results
+            .leaderboard
+            .iter()
+            .map(|e| e.system.as_str())
+            .collect();
+        assert_eq!(systems, vec!["Vector", "PageIndex", "BM25"]);
+        assert_eq!(results.leaderboard[0].wins, 3);
+
+        let md = render_markdown(&results);
+        assert!(md.contains("## Leaderboard"));
+        assert!(md.contains("| 3 | BM25 | "));
+        assert!(render_html(&results).contains("<h2>Leaderboard</h2>"));
+    }
+
+    #[test]
+    fn test_tag_table() {
+        let mut results = sample_results();
+        assert!(!render_markdown(&results).contains("## By Tag"));
+
+        for item in &mut results.item_results {
+            item.tags
+                .insert("difficulty".to_string(), "hard".to_string());
+        }
+        results.calculate_summary();
+
+        let md = render_markdown(&results);
+        assert!(md.contains("| difficulty=hard | 2 | "));
+        assert!(render_html(&results).contains("<td>difficulty=hard</td><td>2</td>"));
+    }
+
+    #[test]
+    fn test_cost_table() {
+        let mut results = sample_results();
+        assert!(!render_markdown(&results).contains("## Cost"));
+
+        results.pricing = Some(ModelPricing {
+            prompt_per_million: 1.0,
+            completion_per_million: 1.0,
+        });
+        for item in &mut results.item_results {
+            item.pageindex_usage = Some(ItemUsage {
+                query: UsageStats {
+                    calls: 2,
+                    prompt_tokens: 1_000,
+                    completion_tokens: 0,
+                    total_tokens: 1_000,
+                },
+                ..ItemUsage::default()
+            });
+        }
+        results.calculate_summary();
+
+        let md = render_markdown(&results);
+        assert!(md.contains("## Cost"));
+        assert!(md.contains("| PageIndex | $0.0010 | $0.0000 | $0.0010 | 0 / 2000 | 0 |"));
+        assert!(render_html(&results).contains("<h2>Cost</h2>"));
+    }
+}
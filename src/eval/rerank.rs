@@ -0,0 +1,160 @@
+//! Cross-encoder reranking for the vector baseline and PageIndex sections.
+//!
+//! A cross-encoder reads the query and a passage together and scores their
+//! relevance, which is slower than comparing embeddings but more accurate.
+//! Production RAG commonly retrieves a wide candidate set by embedding
+//! similarity and reranks it with a cross-encoder, keeping the top few. The
+//! local models (MS MARCO MiniLM by default) run with candle and require the
+//! `local-embeddings` feature; [`Reranker::from_fn`] plugs in any other
+//! scorer, such as a hosted rerank API.
+
+#[cfg(feature = "local-embeddings")]
+use super::embeddings::EmbeddingDevice;
+#[cfg(feature = "local-embeddings")]
+use super::local_embeddings::LocalCrossEncoder;
+use anyhow::Result;
+
+/// Default cross-encoder model.
+pub const DEFAULT_RERANKER_MODEL: &str = "cross-encoder/ms-marco-MiniLM-L-6-v2";
+
+/// Default number of vector search candidates passed to the reranker.
+pub const DEFAULT_RERANK_CANDIDATES: usize = 20;
+
+/// Resolve a cross-encoder name or short alias (`minilm`, `minilm-l12`,
+/// `tinybert`) to its Hugging Face id.
+pub fn resolve_reranker_model(name: &str) -> &str {
+    match name.to_lowercase().as_str() {
+        "minilm" => DEFAULT_RERANKER_MODEL,
+        "minilm-l12" => "cross-encoder/ms-marco-MiniLM-L-12-v2",
+        "tinybert" => "cross-encoder/ms-marco-TinyBERT-L-2-v2",
+        _ => name,
+    }
+}
+
+type ScoreFn = dyn Fn(&str, &[&str]) -> Result<Vec<f32>> + Send + Sync;
+
+enum Backend {
+    #[cfg(feature = "local-embeddings")]
+    Local(Box<LocalCrossEncoder>),
+    Custom(Box<ScoreFn>),
+}
+
+/// Scores (query, passage) pairs and reorders passages by relevance.
+pub struct Reranker {
+    backend: Backend,
+    model_id: String,
+    batch_size: usize,
+}
+
+impl Reranker {
+    /// Load a cross-encoder by Hugging Face id or alias (see
+    /// [`resolve_reranker_model`]) on the given device.
+    #[cfg(feature = "local-embeddings")]
+    pub fn load_on(name: &str, device: EmbeddingDevice) -> Result<Self> {
+        let model_id = resolve_reranker_model(name).to_string();
+        Ok(Self {
+            backend: Backend::Local(Box::new(LocalCrossEncoder::load(&model_id, device)?)),
+            model_id,
+            batch_size: super::embeddings::DEFAULT_BATCH_SIZE,
+        })
+    }
+
+    /// Score with a function returning one score per passage (higher is
+    /// more relevant).
+    pub fn from_fn(
+        name: &str,
+        score: impl Fn(&str, &[&str]) -> Result<Vec<f32>> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            backend: Backend::Custom(Box::new(score)),
+            model_id: name.to_string(),
+            batch_size: super::embeddings::DEFAULT_BATCH_SIZE,
+        }
+    }
+
+    /// Set the number of pairs scored per forward pass or call.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Model id (or the name given to [`from_fn`](Self::from_fn)).
+    pub fn model_id(&self) -> &str {
+        &self.model_id
+    }
+
+    /// Relevance score of each passage for the query.
+    pub async fn score(&self, query: &str, passages: &[&str]) -> Result<Vec<f32>> {
+        let mut scores = Vec::with_capacity(passages.len());
+        for batch in passages.chunks(self.batch_size) {
+            let batch_scores = match &self.backend {
+                #[cfg(feature = "local-embeddings")]
+                Backend::Local(model) => model.score_batch(query, batch)?,
+                Backend::Custom(score) => score(query, batch)?,
+            };
+            anyhow::ensure!(
+                batch_scores.len() == batch.len(),
+                "Reranker returned {} scores for {} passages",
+                batch_scores.len(),
+                batch.len()
+            );
+            scores.extend(batch_scores);
+        }
+        Ok(scores)
+    }
+
+    /// Indices of the `top_k` most relevant passages with their scores,
+    /// best first. Ties keep the original order.
+    pub async fn rerank(
+        &self,
+        query: &str,
+        passages: &[&str],
+        top_k: usize,
+    ) -> Result<Vec<(usize, f32)>> {
+        let mut ranked: Vec<(usize, f32)> = self
+            .score(query, passages)
+            .await?
+            .into_iter()
+            .enumerate()
+            .collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+        ranked.truncate(top_k);
+        Ok(ranked)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Scores passages by how many query words they contain.
+    fn overlap(query: &str, passages: &[&str]) -> Result<Vec<f32>> {
+        Ok(passages
+            .iter()
+            .map(|p| query.split_whitespace().filter(|w| p.contains(w)).count() as f32)
+            .collect())
+    }
+
+    #[tokio::test]
+    async fn test_rerank() {
+        let reranker = Reranker::from_fn("overlap", overlap).with_batch_size(2);
+        let passages = [
+            "the pump",
+            "clean the filter weekly",
+            "filter",
+            "clean the filter",
+        ];
+        let ranked = reranker
+            .rerank("clean filter weekly", &passages, 3)
+            .await
+            .unwrap();
+        assert_eq!(ranked, vec![(1, 3.0), (3, 2.0), (2, 1.0)]);
+        assert!(reranker.rerank("x", &[], 3).await.unwrap().is_empty());
+
+        let short = Reranker::from_fn("short", |_: &str, _: &[&str]| Ok(vec![1.0]));
+        assert!(short.score("q", &["a", "b"]).await.is_err());
+
+        assert_eq!(resolve_reranker_model("MiniLM"), DEFAULT_RERANKER_MODEL);
+        assert_eq!(resolve_reranker_model("my/model"), "my/model");
+    }
+}
@@ -0,0 +1,177 @@
+//! Judging rubrics: weighted criteria injected into judge prompts.
+//!
+//! A rubric file (YAML or JSON) lists the criteria the judge scores:
+//!
+//! ```yaml
+//! criteria:
+//!   - name: correctness
+//!     description: Is the information factually correct?
+//!     weight: 0.5
+//!   - name: completeness
+//!     description: Does it fully answer the question?
+//!     weight: 0.3
+//!   - name: groundedness
+//!     description: Is every claim supported by the retrieved content?
+//!     weight: 0.2
+//! ```
+
+use crate::error::{PageIndexError, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// A single judging criterion.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RubricCriterion {
+    /// Short name, used as the key in the judge's response.
+    pub name: String,
+    /// What the judge should assess.
+    pub description: String,
+    /// Relative weight in the overall score.
+    #[serde(default = "default_weight")]
+    pub weight: f64,
+}
+
+fn default_weight() -> f64 {
+    1.0
+}
+
+/// A set of weighted judging criteria.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rubric {
+    pub criteria: Vec<RubricCriterion>,
+}
+
+/// A judge's score on one rubric criterion.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CriterionScore {
+    pub name: String,
+    /// Score (1-5).
+    pub score: u8,
+    pub weight: f64,
+}
+
+impl Rubric {
+    /// Load a rubric from a YAML or JSON file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| PageIndexError::io(path, e))?;
+        let rubric: Rubric = serde_yaml::from_str(&content)
+            .map_err(|e| PageIndexError::InvalidConfig(format!("rubric {:?}: {}", path, e)))?;
+        rubric.validate()?;
+        Ok(rubric)
+    }
+
+    /// Check that the rubric has uniquely named criteria with positive weights.
+    pub fn validate(&self) -> Result<()> {
+        if self.criteria.is_empty() {
+            return Err(PageIndexError::InvalidConfig(
+                "rubric has no criteria".to_string(),
+            ));
+        }
+        for (i, criterion) in self.criteria.iter().enumerate() {
+            if criterion.name.trim().is_empty() {
+                return Err(PageIndexError::InvalidConfig(format!(
+                    "rubric criterion {} has no name",
+                    i + 1
+                )));
+            }
+            if criterion.weight <= 0.0 || !criterion.weight.is_finite() {
+                return Err(PageIndexError::InvalidConfig(format!(
+                    "rubric criterion '{}' must have a positive weight",
+                    criterion.name
+                )));
+            }
+            if self.criteria[..i].iter().any(|c| c.name == criterion.name) {
+                return Err(PageIndexError::InvalidConfig(format!(
+                    "duplicate rubric criterion '{}'",
+                    criterion.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Criteria as a prompt list, e.g. `- correctness (weight 0.5): Is it correct?`.
+    pub fn prompt_section(&self) -> String {
+        self.criteria
+            .iter()
+            .map(|c| format!("- {} (weight {}): {}", c.name, c.weight, c.description))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Weighted mean of criterion scores.
+    pub fn weighted_score(scores: &[CriterionScore]) -> f64 {
+        let total_weight: f64 = scores.iter().map(|s| s.weight).sum();
+        if total_weight == 0.0 {
+            return 0.0;
+        }
+        scores
+            .iter()
+            .map(|s| s.score as f64 * s.weight)
+            .sum::<f64>()
+            / total_weight
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load_rubric() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("rubric.yaml");
+        std::fs::write(
+            &path,
+            "criteria:\n  - name: correctness\n    description: Is it correct?\n    weight: 3\n  - name: groundedness\n    description: Is it supported?\n",
+        )
+        .unwrap();
+
+        let rubric = Rubric::load(&path).unwrap();
+        assert_eq!(rubric.criteria.len(), 2);
+        assert_eq!(rubric.criteria[1].weight, 1.0);
+        assert_eq!(
+            rubric.prompt_section(),
+            "- correctness (weight 3): Is it correct?\n- groundedness (weight 1): Is it supported?"
+        );
+
+        let scores = vec![
+            CriterionScore {
+                name: "correctness".to_string(),
+                score: 5,
+                weight: 3.0,
+            },
+            CriterionScore {
+                name: "groundedness".to_string(),
+                score: 1,
+                weight: 1.0,
+            },
+        ];
+        assert_eq!(Rubric::weighted_score(&scores), 4.0);
+    }
+
+    #[test]
+    fn test_invalid_rubrics() {
+        let criterion = |name: &str, weight: f64| RubricCriterion {
+            name: name.to_string(),
+            description: String::new(),
+            weight,
+        };
+
+        assert!(Rubric { criteria: vec![] }.validate().is_err());
+        assert!(
+            Rubric {
+                criteria: vec![criterion("a", 0.0)]
+            }
+            .validate()
+            .is_err()
+        );
+        assert!(
+            Rubric {
+                criteria: vec![criterion("a", 1.0), criterion("a", 2.0)]
+            }
+            .validate()
+            .is_err()
+        );
+    }
+}
@@ -0,0 +1,462 @@
+//! Statistical significance for benchmark comparisons.
+//!
+//! Bootstrap confidence intervals for win rate and score difference, a sign
+//! test on wins vs. losses, a paired permutation test on judge scores, and
+//! inter-judge agreement for judge ensembles. Resampling uses a fixed seed so
+//! results are reproducible. Also latency percentiles, since averages hide
+//! the long tail of multi-call LLM retrieval, and Bradley–Terry ratings for
+//! ranking more than two systems from pairwise verdicts.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Number of bootstrap/permutation resamples.
+const RESAMPLES: usize = 10_000;
+/// Seed for resampling, so repeated summaries of the same results agree.
+const SEED: u64 = 0x5EED;
+/// Significance level used when describing results.
+pub const ALPHA: f64 = 0.05;
+
+/// A point estimate with a 95% confidence interval.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ConfidenceInterval {
+    pub estimate: f64,
+    pub lower: f64,
+    pub upper: f64,
+}
+
+/// Significance of the PageIndex vs. Vector comparison.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Significance {
+    /// Number of judged items.
+    pub n: usize,
+    /// PageIndex win rate among decided (non-tie) items.
+    pub win_rate: ConfidenceInterval,
+    /// Two-sided sign test p-value for wins vs. losses (ties excluded).
+    pub win_p_value: f64,
+    /// Mean judge score difference (PageIndex - Vector).
+    pub score_difference: ConfidenceInterval,
+    /// Two-sided paired permutation test p-value for the score difference.
+    pub score_p_value: f64,
+}
+
+impl Significance {
+    /// Compute significance from per-item judge outcomes.
+    ///
+    /// `winners` uses the judge's encoding (1 = PageIndex, 2 = Vector, other
+    /// = tie); `scores` are paired (PageIndex, Vector) scores. Returns `None`
+    /// without judged items.
+    pub fn compute(winners: &[u8], scores: &[(f64, f64)]) -> Option<Self> {
+        if scores.is_empty() {
+            return None;
+        }
+        let mut rng = StdRng::seed_from_u64(SEED);
+
+        let decided: Vec<f64> = winners
+            .iter()
+            .filter(|w| matches!(w, 1 | 2))
+            .map(|w| if *w == 1 { 1.0 } else { 0.0 })
+            .collect();
+        let wins = decided.iter().filter(|d| **d == 1.0).count();
+        let losses = decided.len() - wins;
+
+        let differences: Vec<f64> = scores.iter().map(|(a, b)| a - b).collect();
+
+        Some(Self {
+            n: scores.len(),
+            win_rate: bootstrap_mean_ci(&decided, &mut rng).unwrap_or(ConfidenceInterval {
+                estimate: 0.5,
+                lower: 0.0,
+                upper: 1.0,
+            }),
+            win_p_value: sign_test(wins, losses),
+            score_difference: bootstrap_mean_ci(&differences, &mut rng)?,
+            score_p_value: paired_permutation_test(&differences, &mut rng),
+        })
+    }
+
+    /// Whether the win/loss split is significant at [`ALPHA`].
+    pub fn wins_significant(&self) -> bool {
+        self.win_p_value < ALPHA
+    }
+
+    /// Whether the score difference is significant at [`ALPHA`].
+    pub fn scores_significant(&self) -> bool {
+        self.score_p_value < ALPHA
+    }
+}
+
+/// Agreement between the judges of an ensemble across a benchmark.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct JudgeAgreement {
+    /// Number of judges per item.
+    pub judges: usize,
+    /// Mean fraction of judges agreeing with each item's majority verdict.
+    pub mean_agreement: f64,
+    /// Fleiss' kappa over winner verdicts (`None` when undefined, e.g. every
+    /// judge always gave the same verdict).
+    pub fleiss_kappa: Option<f64>,
+}
+
+impl JudgeAgreement {
+    /// Compute agreement from per-item winner verdicts (0 = tie, 1, 2).
+    ///
+    /// Only items rated by the most common number of judges (at least two,
+    /// the larger number on a tie) are counted, since Fleiss' kappa needs a
+    /// fixed number of raters.
+    pub fn compute(verdicts: &[Vec<u8>]) -> Option<Self> {
+        let mut frequency: BTreeMap<usize, usize> = BTreeMap::new();
+        for v in verdicts.iter().filter(|v| v.len() >= 2) {
+            *frequency.entry(v.len()).or_default() += 1;
+        }
+        let (judges, _) = frequency
+            .into_iter()
+            .max_by_key(|&(judges, items)| (items, judges))?;
+        let items: Vec<[usize; 3]> = verdicts
+            .iter()
+            .filter(|v| v.len() == judges)
+            .map(|v| {
+                let mut counts = [0; 3];
+                for w in v {
+                    counts[(*w as usize).min(2)] += 1;
+                }
+                counts
+            })
+            .collect();
+        let n = judges as f64;
+        let total = items.len() as f64;
+
+        let mean_agreement = items
+            .iter()
+            .map(|c| *c.iter().max().unwrap() as f64 / n)
+            .sum::<f64>()
+            / total;
+
+        let observed = items
+            .iter()
+            .map(|c| (c.iter().map(|x| (x * x) as f64).sum::<f64>() - n) / (n * (n - 1.0)))
+            .sum::<f64>()
+            / total;
+        let expected: f64 = (0..3)
+            .map(|j| {
+                let p = items.iter().map(|c| c[j] as f64).sum::<f64>() / (total * n);
+                p * p
+            })
+            .sum();
+        let fleiss_kappa = (expected < 1.0).then(|| (observed - expected) / (1.0 - expected));
+
+        Some(Self {
+            judges,
+            mean_agreement,
+            fleiss_kappa,
+        })
+    }
+}
+
+/// Latency distribution over items, in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LatencyStats {
+    pub mean_ms: f64,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+impl LatencyStats {
+    /// Summarize per-item times (`None` without any).
+    pub fn compute(times_ms: &[f64]) -> Option<Self> {
+        if times_ms.is_empty() {
+            return None;
+        }
+        let mut sorted = times_ms.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        Some(Self {
+            mean_ms: mean(&sorted),
+            p50_ms: percentile(&sorted, 50.0),
+            p95_ms: percentile(&sorted, 95.0),
+            p99_ms: percentile(&sorted, 99.0),
+            max_ms: sorted[sorted.len() - 1],
+        })
+    }
+}
+
+/// Maximum iterations when fitting Bradley–Terry strengths.
+const BT_ITERATIONS: usize = 1_000;
+
+/// One system's standing in a leaderboard fitted to pairwise verdicts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LeaderboardEntry {
+    pub system: String,
+    /// Bradley–Terry strength on an Elo-like scale: 1000 is the virtual
+    /// average opponent and a 400 point gap means 10:1 odds of winning.
+    pub rating: f64,
+    pub wins: usize,
+    pub losses: usize,
+    pub ties: usize,
+}
+
+impl LeaderboardEntry {
+    /// Rank `systems` by Bradley–Terry strength fitted to verdicts
+    /// `(a, b, winner)` between them (indices into `systems`; winner 1 = a,
+    /// 2 = b, 0 = tie). Ties count as half a win for each side.
+    ///
+    /// Strengths are fitted with the MM algorithm (Hunter, 2004). Every
+    /// system also gets one virtual tie against an average opponent, so
+    /// undefeated or winless systems get finite ratings.
+    pub fn rank(systems: &[&str], matches: &[(usize, usize, u8)]) -> Vec<Self> {
+        let n = systems.len();
+        let mut entries: Vec<Self> = systems
+            .iter()
+            .map(|system| Self {
+                system: system.to_string(),
+                rating: 0.0,
+                wins: 0,
+                losses: 0,
+                ties: 0,
+            })
+            .collect();
+        let mut scores = vec![0.5; n];
+        let mut games = vec![vec![0.0; n]; n];
+        for &(a, b, winner) in matches {
+            games[a][b] += 1.0;
+            games[b][a] += 1.0;
+            match winner {
+                1 => {
+                    scores[a] += 1.0;
+                    entries[a].wins += 1;
+                    entries[b].losses += 1;
+                }
+                2 => {
+                    scores[b] += 1.0;
+                    entries[b].wins += 1;
+                    entries[a].losses += 1;
+                }
+                _ => {
+                    scores[a] += 0.5;
+                    scores[b] += 0.5;
+                    entries[a].ties += 1;
+                    entries[b].ties += 1;
+                }
+            }
+        }
+
+        let mut strengths = vec![1.0; n];
+        for _ in 0..BT_ITERATIONS {
+            let next: Vec<f64> = (0..n)
+                .map(|i| {
+                    let games_over_strength: f64 = 1.0 / (strengths[i] + 1.0)
+                        + (0..n)
+                            .map(|j| games[i][j] / (strengths[i] + strengths[j]))
+                            .sum::<f64>();
+                    scores[i] / games_over_strength
+                })
+                .collect();
+            let change = next
+                .iter()
+                .zip(&strengths)
+                .map(|(a, b)| (a - b).abs())
+                .fold(0.0, f64::max);
+            strengths = next;
+            if change < 1e-10 {
+                break;
+            }
+        }
+
+        for (entry, strength) in entries.iter_mut().zip(&strengths) {
+            entry.rating = 1000.0 + 400.0 * strength.log10();
+        }
+        entries.sort_by(|a, b| b.rating.total_cmp(&a.rating));
+        entries
+    }
+}
+
+/// Percentile of sorted values, interpolating linearly between ranks.
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    let rank = p / 100.0 * (sorted.len() - 1) as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+/// 95% percentile bootstrap interval for the mean.
+fn bootstrap_mean_ci(values: &[f64], rng: &mut StdRng) -> Option<ConfidenceInterval> {
+    if values.is_empty() {
+        return None;
+    }
+    let n = values.len();
+    let mut means: Vec<f64> = (0..RESAMPLES)
+        .map(|_| (0..n).map(|_| values[rng.random_range(0..n)]).sum::<f64>() / n as f64)
+        .collect();
+    means.sort_by(|a, b| a.total_cmp(b));
+
+    let percentile = |p: f64| means[((RESAMPLES as f64 - 1.0) * p).round() as usize];
+    Some(ConfidenceInterval {
+        estimate: mean(values),
+        lower: percentile(0.025),
+        upper: percentile(0.975),
+    })
+}
+
+/// Exact two-sided sign test: P(a split at least this uneven | p = 0.5).
+fn sign_test(wins: usize, losses: usize) -> f64 {
+    let n = wins + losses;
+    if n == 0 {
+        return 1.0;
+    }
+    let k = wins.min(losses);
+
+    // Sum binomial(n, i) / 2^n for i <= k, in log space to avoid overflow
+    let ln_half_n = n as f64 * 0.5f64.ln();
+    let mut ln_choose = 0.0;
+    let mut tail = 0.0;
+    for i in 0..=k {
+        if i > 0 {
+            ln_choose += ((n - i + 1) as f64).ln() - (i as f64).ln();
+        }
+        tail += (ln_choose + ln_half_n).exp();
+    }
+    (2.0 * tail).min(1.0)
+}
+
+/// Two-sided paired permutation test on differences via random sign flips.
+fn paired_permutation_test(differences: &[f64], rng: &mut StdRng) -> f64 {
+    let observed = mean(differences).abs();
+    if observed == 0.0 {
+        return 1.0;
+    }
+    let extreme = (0..RESAMPLES)
+        .filter(|_| {
+            let permuted: f64 = differences
+                .iter()
+                .map(|d| if rng.random_bool(0.5) { *d } else { -*d })
+                .sum::<f64>()
+                / differences.len() as f64;
+            permuted.abs() >= observed - 1e-12
+        })
+        .count();
+    // Add-one smoothing keeps the p-value away from an impossible zero
+    (extreme + 1) as f64 / (RESAMPLES + 1) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_test() {
+        assert_eq!(sign_test(0, 0), 1.0);
+        assert_eq!(sign_test(5, 5), 1.0);
+        // 9 of 10: 2 * (1 + 10) / 1024
+        assert!((sign_test(9, 1) - 22.0 / 1024.0).abs() < 1e-12);
+        assert!(sign_test(1000, 900) < 0.05);
+    }
+
+    #[test]
+    fn test_small_sample_is_not_significant() {
+        // 54% wins on 30 items is well within noise
+        let winners: Vec<u8> = (0..30).map(|i| if i < 16 { 1 } else { 2 }).collect();
+        let scores: Vec<(f64, f64)> = winners
+            .iter()
+            .map(|w| if *w == 1 { (4.0, 3.0) } else { (3.0, 4.0) })
+            .collect();
+
+        let sig = Significance::compute(&winners, &scores).unwrap();
+        assert_eq!(sig.n, 30);
+        assert!((sig.win_rate.estimate - 16.0 / 30.0).abs() < 1e-9);
+        assert!(sig.win_rate.lower < 0.5 && sig.win_rate.upper > 0.5);
+        assert!(!sig.wins_significant());
+        assert!(!sig.scores_significant());
+    }
+
+    #[test]
+    fn test_latency_percentiles() {
+        let times: Vec<f64> = (1..=100).rev().map(|t| t as f64 * 10.0).collect();
+        let latency = LatencyStats::compute(&times).unwrap();
+        assert!((latency.mean_ms - 505.0).abs() < 1e-9);
+        assert!((latency.p50_ms - 505.0).abs() < 1e-9);
+        assert!((latency.p95_ms - 950.5).abs() < 1e-9);
+        assert!((latency.p99_ms - 990.1).abs() < 1e-9);
+        assert_eq!(latency.max_ms, 1000.0);
+
+        let single = LatencyStats::compute(&[42.0]).unwrap();
+        assert_eq!((single.p50_ms, single.p99_ms), (42.0, 42.0));
+        assert!(LatencyStats::compute(&[]).is_none());
+    }
+
+    #[test]
+    fn test_leaderboard_ranking() {
+        // A beats B and C, B beats C, each three times
+        let mut matches = Vec::new();
+        for _ in 0..3 {
+            matches.extend([(0, 1, 1), (2, 0, 2), (1, 2, 1)]);
+        }
+        let board = LeaderboardEntry::rank(&["A", "B", "C"], &matches);
+
+        let order: Vec<&str> = board.iter().map(|e| e.system.as_str()).collect();
+        assert_eq!(order, vec!["A", "B", "C"]);
+        assert_eq!((board[0].wins, board[0].losses, board[0].ties), (6, 0, 0));
+        assert_eq!((board[1].wins, board[1].losses, board[1].ties), (3, 3, 0));
+        assert!(board[0].rating.is_finite() && board[2].rating.is_finite());
+        // A and C mirror each other around B, which sits at the average
+        assert!((board[1].rating - 1000.0).abs() < 1e-6);
+        assert!((board[0].rating + board[2].rating - 2000.0).abs() < 1e-6);
+
+        // Evenly matched systems are rated the same; idle ones are average
+        let even = LeaderboardEntry::rank(&["X", "Y", "Z"], &[(0, 1, 1), (0, 1, 2), (0, 1, 0)]);
+        assert_eq!(even[0].ties, 1);
+        assert!(even.iter().all(|e| (e.rating - 1000.0).abs() < 1e-6));
+        assert!(LeaderboardEntry::rank(&[], &[]).is_empty());
+    }
+
+    #[test]
+    fn test_judge_agreement() {
+        let perfect = JudgeAgreement::compute(&[vec![1, 1, 1], vec![2, 2, 2]]).unwrap();
+        assert_eq!(perfect.mean_agreement, 1.0);
+        assert!((perfect.fleiss_kappa.unwrap() - 1.0).abs() < 1e-9);
+
+        let mixed = JudgeAgreement::compute(&[vec![1, 1, 2], vec![2, 2, 2]]).unwrap();
+        assert_eq!(mixed.judges, 3);
+        assert!((mixed.mean_agreement - 5.0 / 6.0).abs() < 1e-9);
+        // P = (1/3 + 1) / 2, Pe = (2/6)^2 + (4/6)^2
+        let (p, pe) = (2.0 / 3.0, 5.0 / 9.0);
+        assert!((mixed.fleiss_kappa.unwrap() - (p - pe) / (1.0 - pe)).abs() < 1e-9);
+
+        assert!(JudgeAgreement::compute(&[vec![1], vec![2]]).is_none());
+
+        // Uneven panels (a judge failed on some items): the usual panel
+        // size wins, and only items it rated count
+        let uneven =
+            JudgeAgreement::compute(&[vec![1, 1, 1, 2], vec![1, 1, 2], vec![2, 2, 2], vec![1]])
+                .unwrap();
+        assert_eq!(uneven.judges, 3);
+        assert!((uneven.mean_agreement - 5.0 / 6.0).abs() < 1e-9);
+        assert!((uneven.fleiss_kappa.unwrap() - mixed.fleiss_kappa.unwrap()).abs() < 1e-9);
+        let tied = JudgeAgreement::compute(&[vec![1, 1], vec![1, 1, 2]]).unwrap();
+        assert_eq!(tied.judges, 3);
+        assert!(
+            JudgeAgreement::compute(&[vec![1, 1]])
+                .unwrap()
+                .fleiss_kappa
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_consistent_difference_is_significant() {
+        let winners = vec![1u8; 40];
+        let scores = vec![(5.0, 3.0); 40];
+
+        let sig = Significance::compute(&winners, &scores).unwrap();
+        assert!(sig.wins_significant());
+        assert!(sig.scores_significant());
+        assert_eq!(sig.score_difference.lower, 2.0);
+        // Deterministic across runs
+        assert_eq!(Significance::compute(&winners, &scores), Some(sig));
+    }
+}
@@ -0,0 +1,297 @@
+//! Synthetic evaluation datasets from an indexed corpus.
+//!
+//! [`QuestionGenerator`] asks the LLM for questions each section of an
+//! indexed document answers, grounded in the section's text: every question
+//! comes with its answer and a verbatim evidence quote, and questions whose
+//! quote is not in the section are dropped. The result is a [`Dataset`], so
+//! teams can benchmark retrieval on their own documents (`eval custom`)
+//! without writing questions by hand.
+//!
+//! ```no_run
+//! use rust_page_indexer::eval::synthetic::QuestionGenerator;
+//! use rust_page_indexer::{Config, Document, LlmClient, load_tree};
+//! use std::path::Path;
+//!
+//! # async fn run() -> rust_page_indexer::Result<()> {
+//! let client = LlmClient::new(Config::load()?.llm);
+//! let tree = load_tree(Path::new("data/tree_index.json"))?;
+//! let document = Document::from_text_file(Path::new("report.txt"))?;
+//! let dataset = QuestionGenerator::new(client)
+//!     .with_max_sections(50, 0)
+//!     .generate(&tree, &document)
+//!     .await?;
+//! println!("{} questions", dataset.len());
+//! # Ok(())
+//! # }
+//! ```
+
+use super::dataset::{Dataset, DatasetItem};
+use crate::document::Document;
+use crate::error::{PageIndexError, Result};
+use crate::llm::{LlmClient, Prompts};
+use crate::search::{TreeSearcher, section_content};
+use crate::tree::{DocumentTree, TreeNode};
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::index;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tracing::{debug, info, warn};
+
+/// Default number of questions asked per section.
+pub const DEFAULT_QUESTIONS_PER_SECTION: usize = 2;
+
+/// Sections with less text than this (in characters) are skipped: there is
+/// too little in them to ask about.
+pub const MIN_SECTION_CHARS: usize = 200;
+
+/// Most characters of section text questions are generated from.
+const MAX_SECTION_CHARS: usize = 12_000;
+
+/// Default number of LLM calls in flight at once.
+const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Value of [`DatasetItem::source`] for generated items.
+pub const SYNTHETIC_SOURCE: &str = "synthetic";
+
+/// Generates grounded Q&A pairs per section of an indexed document.
+pub struct QuestionGenerator {
+    client: LlmClient,
+    questions_per_section: usize,
+    max_sections: Option<(usize, u64)>,
+    concurrency: usize,
+}
+
+#[derive(Deserialize)]
+struct GeneratedQuestions {
+    #[serde(default)]
+    questions: Vec<GeneratedQuestion>,
+}
+
+#[derive(Deserialize)]
+struct GeneratedQuestion {
+    question: String,
+    #[serde(default)]
+    answer: Option<String>,
+    #[serde(default)]
+    evidence: String,
+}
+
+impl QuestionGenerator {
+    /// Create a generator calling the given client.
+    pub fn new(client: LlmClient) -> Self {
+        Self {
+            client,
+            questions_per_section: DEFAULT_QUESTIONS_PER_SECTION,
+            max_sections: None,
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+
+    /// Set the number of questions asked per section.
+    pub fn with_questions_per_section(mut self, count: usize) -> Self {
+        self.questions_per_section = count.max(1);
+        self
+    }
+
+    /// Ask about a random subset of at most `max` sections per document.
+    /// The same seed always picks the same sections.
+    pub fn with_max_sections(mut self, max: usize, seed: u64) -> Self {
+        self.max_sections = Some((max, seed));
+        self
+    }
+
+    /// Set the maximum number of LLM calls in flight.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// The sections questions are asked about: leaves with enough text, in
+    /// document order.
+    fn sections<'a>(
+        &self,
+        tree: &'a DocumentTree,
+        document: &Document,
+    ) -> Vec<(&'a TreeNode, String)> {
+        let mut sections: Vec<_> = tree
+            .nodes
+            .iter()
+            .flat_map(|n| n.leaves())
+            .map(|n| (n, section_content(document, n.start_index, n.end_index)))
+            .filter(|(_, text)| text.chars().count() >= MIN_SECTION_CHARS)
+            .collect();
+        if let Some((max, seed)) = self.max_sections
+            && sections.len() > max
+        {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut picked = index::sample(&mut rng, sections.len(), max).into_vec();
+            picked.sort_unstable();
+            sections = picked.into_iter().map(|i| sections[i].clone()).collect();
+        }
+        sections
+    }
+
+    /// Generate questions about the sections of an indexed document. Items
+    /// carry the whole document's text, the question, its answer and
+    /// evidence, and `section` and `document` tags.
+    ///
+    /// A section whose reply can't be parsed is skipped with a warning;
+    /// LLM call failures fail the run.
+    pub async fn generate(&self, tree: &DocumentTree, document: &Document) -> Result<Dataset> {
+        let sections = self.sections(tree, document);
+        info!(
+            document = %tree.name,
+            sections = sections.len(),
+            "generating questions"
+        );
+        let semaphore = Arc::new(Semaphore::new(self.concurrency));
+        let mut tasks = JoinSet::new();
+        for (i, (node, text)) in sections.iter().enumerate() {
+            let content: String = text.chars().take(MAX_SECTION_CHARS).collect();
+            let prompt = Prompts::generate_questions()
+                .replace("{count}", &self.questions_per_section.to_string())
+                .replace("{document}", &tree.name)
+                .replace("{title}", &node.title)
+                .replace("{content}", &content);
+            let client = self.client.clone();
+            let semaphore = semaphore.clone();
+            tasks.spawn(async move {
+                let _permit = semaphore.acquire_owned().await.expect("semaphore closed");
+                let response = client
+                    .complete(Some(Prompts::system_document_analyzer()), &prompt)
+                    .await;
+                (i, response)
+            });
+        }
+
+        let mut replies = vec![None; sections.len()];
+        while let Some(joined) = tasks.join_next().await {
+            let (i, response) = joined.map_err(|e| PageIndexError::TreeError(e.to_string()))?;
+            replies[i] = Some(response?);
+        }
+
+        let text = document.raw_content();
+        let mut dataset = Dataset::new(&format!("{}-{}", SYNTHETIC_SOURCE, tree.name));
+        let mut seen = HashSet::new();
+        for ((node, section_text), reply) in sections.iter().zip(replies) {
+            let reply = reply.unwrap_or_default();
+            let parsed: GeneratedQuestions =
+                match serde_json::from_str(&TreeSearcher::extract_json(&reply)) {
+                    Ok(parsed) => parsed,
+                    Err(e) => {
+                        warn!(section = %node.title, "skipping unparseable questions: {}", e);
+                        continue;
+                    }
+                };
+            let section_id = node
+                .node_id
+                .clone()
+                .or_else(|| node.structure.clone())
+                .unwrap_or_else(|| format!("p{}-{}", node.start_index, node.end_index));
+            let mut count = 0;
+            for generated in parsed
+                .questions
+                .into_iter()
+                .take(self.questions_per_section)
+            {
+                let question = generated.question.trim().to_string();
+                let evidence = generated.evidence.trim().to_string();
+                if question.is_empty() || !seen.insert(question.to_lowercase()) {
+                    continue;
+                }
+                if evidence.is_empty() || !section_text.contains(&evidence) {
+                    debug!(section = %node.title, %question, "dropping question without verbatim evidence");
+                    continue;
+                }
+                count += 1;
+                dataset.add_item(DatasetItem {
+                    id: format!("{}_{}_{}", tree.name, section_id, count),
+                    document: text.clone(),
+                    question,
+                    answer: generated.answer.filter(|a| !a.trim().is_empty()),
+                    alt_answers: Vec::new(),
+                    supporting_facts: Vec::new(),
+                    evidence: vec![evidence],
+                    options: None,
+                    correct_option: None,
+                    source: SYNTHETIC_SOURCE.to_string(),
+                    tags: BTreeMap::from([
+                        ("document".to_string(), tree.name.clone()),
+                        ("section".to_string(), node.title.clone()),
+                    ]),
+                });
+            }
+        }
+        Ok(dataset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::Page;
+    use crate::fixtures::replay_replies;
+    use crate::golden::PromptCapture;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_generate_questions() {
+        let filler = "The unit ships with a reusable mesh filter. ".repeat(5);
+        let document = Document::new(
+            "manual",
+            vec![
+                Page::new(
+                    1,
+                    format!("{}Soak the filter weekly in warm water.", filler),
+                ),
+                Page::new(2, format!("{}Replace the seal every 12 months.", filler)),
+                Page::new(3, "Too short.".to_string()),
+            ],
+        );
+        let mut cleaning = TreeNode::new("Cleaning", 1, 1);
+        cleaning.node_id = Some("0001".to_string());
+        let mut parts = TreeNode::new("Parts", 2, 3);
+        parts.add_child(TreeNode::new("Seal", 2, 2).with_structure("2.1"));
+        parts.add_child(TreeNode::new("Notes", 3, 3).with_structure("2.2"));
+        let tree = DocumentTree::new("manual", vec![cleaning, parts], 3);
+
+        let replies = [
+            json!({"questions": [
+                {"question": "How often should the filter be soaked?", "answer": "Weekly", "evidence": "Soak the filter weekly in warm water."},
+                {"question": "What is the filter made of?", "answer": "Mesh", "evidence": "made of steel"},
+                {"question": "Unasked third question?", "answer": "x", "evidence": "Soak the filter"}
+            ]}),
+            json!({"questions": [
+                {"question": "How often should the seal be replaced?", "answer": "Every 12 months", "evidence": "Replace the seal every 12 months."}
+            ]}),
+        ];
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("questions.fixture.json");
+        let replies: Vec<_> = replies.iter().map(|reply| reply.to_string()).collect();
+        let capture = PromptCapture::new(replay_replies(&path, &replies));
+
+        let dataset = QuestionGenerator::new(capture.client().unwrap())
+            .with_concurrency(1)
+            .generate(&tree, &document)
+            .await
+            .unwrap();
+
+        // One call per leaf with enough text; ungrounded questions dropped
+        assert_eq!(capture.prompts().len(), 2);
+        let ids: Vec<_> = dataset.items.iter().map(|i| i.id.as_str()).collect();
+        assert_eq!(ids, vec!["manual_0001_1", "manual_2.1_1"]);
+        let item = &dataset.items[0];
+        assert_eq!(item.answer.as_deref(), Some("Weekly"));
+        assert_eq!(item.evidence, vec!["Soak the filter weekly in warm water."]);
+        assert!(item.document.contains(&item.evidence[0]));
+        assert_eq!(item.tags["section"], "Cleaning");
+        assert_eq!(item.source, SYNTHETIC_SOURCE);
+
+        let generator = QuestionGenerator::new(capture.client().unwrap()).with_max_sections(1, 7);
+        assert_eq!(generator.sections(&tree, &document).len(), 1);
+    }
+}
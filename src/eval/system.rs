@@ -0,0 +1,58 @@
+//! Pluggable retrieval systems for the benchmark.
+//!
+//! Implement [`RetrievalSystem`] to benchmark your own retriever (e.g. a
+//! production search service) against PageIndex: register it with
+//! [`Benchmark::with_system`](super::Benchmark::with_system) and each item's
+//! question is answered from its retrieved content and judged against
+//! PageIndex, like the built-in baselines.
+//!
+//! ```no_run
+//! use async_trait::async_trait;
+//! use rust_page_indexer::eval::{Retrieval, RetrievalSystem};
+//!
+//! struct FirstParagraph;
+//!
+//! #[async_trait]
+//! impl RetrievalSystem for FirstParagraph {
+//!     fn name(&self) -> &str {
+//!         "FirstParagraph"
+//!     }
+//!
+//!     async fn retrieve(&self, _question: &str, document: &str) -> anyhow::Result<Retrieval> {
+//!         let paragraph = document.split("\n\n").next().unwrap_or_default();
+//!         Ok(Retrieval::from_passages(vec![paragraph.to_string()]))
+//!     }
+//! }
+//! ```
+
+use anyhow::Result;
+use async_trait::async_trait;
+
+/// Content retrieved for one question.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Retrieval {
+    /// Context the answer is generated from.
+    pub content: String,
+    /// Retrieved passages in rank order, scored against gold evidence.
+    pub passages: Vec<String>,
+}
+
+impl Retrieval {
+    /// Retrieval whose context is the passages joined by separators.
+    pub fn from_passages(passages: Vec<String>) -> Self {
+        Self {
+            content: passages.join("\n\n---\n\n"),
+            passages,
+        }
+    }
+}
+
+/// A retriever compared against PageIndex in the benchmark.
+#[async_trait]
+pub trait RetrievalSystem: Send + Sync {
+    /// Name shown in results and reports (e.g. "BM25").
+    fn name(&self) -> &str;
+
+    /// Retrieve context for `question` from `document`.
+    async fn retrieve(&self, question: &str, document: &str) -> Result<Retrieval>;
+}
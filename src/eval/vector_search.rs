@@ -1,16 +1,38 @@
 //! Vector search implementation for baseline comparison.
 
 use super::embeddings::{EmbeddingModel, cosine_similarity};
-use anyhow::Result;
+use super::hnsw::{HnswConfig, HnswIndex};
+use super::system::{Retrieval, RetrievalSystem};
+use crate::tree::fnv1a_hex;
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// Unit that chunk sizes and overlaps are measured in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ChunkUnit {
+    /// Characters.
+    #[default]
+    Chars,
+    /// Estimated tokens (words / 0.75, as for page token counts). Chunks
+    /// break between words.
+    Tokens,
+}
 
 /// Configuration for text chunking.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ChunkConfig {
-    /// Maximum characters per chunk.
+    /// Maximum size of a chunk, in `unit`s.
     pub chunk_size: usize,
-    /// Overlap between consecutive chunks.
+    /// Overlap between consecutive chunks, in `unit`s.
     pub chunk_overlap: usize,
+    /// Unit of `chunk_size` and `chunk_overlap`.
+    #[serde(default)]
+    pub unit: ChunkUnit,
 }
 
 impl Default for ChunkConfig {
@@ -18,10 +40,16 @@ impl Default for ChunkConfig {
         Self {
             chunk_size: 512,
             chunk_overlap: 50,
+            unit: ChunkUnit::Chars,
         }
     }
 }
 
+/// Words in `tokens` estimated tokens (the inverse of `words / 0.75`).
+fn tokens_to_words(tokens: usize) -> usize {
+    tokens * 3 / 4
+}
+
 /// A chunk of text with metadata.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Chunk {
@@ -36,16 +64,25 @@ pub struct Chunk {
 }
 
 /// A vector index entry.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IndexEntry {
     pub chunk: Chunk,
     pub embedding: Vec<f32>,
 }
 
+impl AsRef<[f32]> for IndexEntry {
+    fn as_ref(&self) -> &[f32] {
+        &self.embedding
+    }
+}
+
 /// Vector search index.
+#[derive(Serialize, Deserialize)]
 pub struct VectorIndex {
     entries: Vec<IndexEntry>,
     config: ChunkConfig,
+    /// Optional ANN graph; exact search is used without one.
+    hnsw: Option<HnswIndex>,
 }
 
 impl VectorIndex {
@@ -54,6 +91,7 @@ impl VectorIndex {
         Self {
             entries: Vec::new(),
             config,
+            hnsw: None,
         }
     }
 
@@ -63,16 +101,15 @@ impl VectorIndex {
     }
 
     /// Build index from document text.
-    pub fn build(text: &str, model: &EmbeddingModel, config: ChunkConfig) -> Result<Self> {
+    pub async fn build(text: &str, model: &EmbeddingModel, config: ChunkConfig) -> Result<Self> {
         let chunks = Self::chunk_text(text, &config);
 
         // Generate embeddings in batches
-        let batch_size = 32;
         let mut entries = Vec::new();
 
-        for batch in chunks.chunks(batch_size) {
+        for batch in chunks.chunks(model.batch_size()) {
             let texts: Vec<&str> = batch.iter().map(|c| c.text.as_str()).collect();
-            let embeddings = model.embed_batch(&texts)?;
+            let embeddings = model.embed_passages(&texts).await?;
 
             for (chunk, embedding) in batch.iter().zip(embeddings) {
                 entries.push(IndexEntry {
@@ -82,11 +119,96 @@ impl VectorIndex {
             }
         }
 
-        Ok(Self { entries, config })
+        Ok(Self {
+            entries,
+            config,
+            hnsw: None,
+        })
+    }
+
+    /// Build an HNSW graph over the entries so searches are approximate
+    /// rather than brute force.
+    pub fn with_hnsw(mut self, config: HnswConfig) -> Self {
+        self.hnsw = Some(HnswIndex::build(&self.entries, config));
+        self
+    }
+
+    /// Build index from document text, reusing a previously saved index from
+    /// `cache_dir` when one exists for the same document, chunk config and
+    /// embedding model.
+    pub async fn build_cached(
+        text: &str,
+        model: &EmbeddingModel,
+        config: ChunkConfig,
+        cache_dir: &Path,
+    ) -> Result<Self> {
+        let path = Self::cache_path(cache_dir, text, &config, model.model_id());
+        if path.exists() {
+            match Self::load(&path) {
+                Ok(index) if index.config == config => {
+                    tracing::debug!("Loaded cached vector index from {:?}", path);
+                    return Ok(index);
+                }
+                Ok(_) => tracing::warn!("Ignoring cached vector index {:?}: config mismatch", path),
+                Err(e) => tracing::warn!("Ignoring unreadable vector index {:?}: {}", path, e),
+            }
+        }
+
+        let index = Self::build(text, model, config).await?;
+        std::fs::create_dir_all(cache_dir)
+            .with_context(|| format!("Failed to create cache directory {:?}", cache_dir))?;
+        index.save(&path)?;
+        Ok(index)
+    }
+
+    /// Cache file for a document indexed with the given config and model.
+    pub fn cache_path(
+        cache_dir: &Path,
+        text: &str,
+        config: &ChunkConfig,
+        model_id: &str,
+    ) -> PathBuf {
+        // 0xff never occurs in UTF-8, so it separates the two unambiguously
+        let key = [text.as_bytes(), &[0xff], model_id.as_bytes()].concat();
+        cache_dir.join(format!(
+            "{}-{}-{}{}.bin",
+            fnv1a_hex(&key),
+            config.chunk_size,
+            config.chunk_overlap,
+            match config.unit {
+                ChunkUnit::Chars => "",
+                ChunkUnit::Tokens => "-tok",
+            }
+        ))
+    }
+
+    /// Save the index (chunks and embeddings) to a bincode file.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let data = bincode::serde::encode_to_vec(self, bincode::config::standard())
+            .context("Failed to serialize vector index")?;
+        std::fs::write(path, data).with_context(|| format!("Failed to write {:?}", path))?;
+        Ok(())
+    }
+
+    /// Load an index saved with [`VectorIndex::save`].
+    pub fn load(path: &Path) -> Result<Self> {
+        let data = std::fs::read(path).with_context(|| format!("Failed to read {:?}", path))?;
+        let (index, _) = bincode::serde::decode_from_slice(&data, bincode::config::standard())
+            .context("Failed to deserialize vector index")?;
+        Ok(index)
     }
 
     /// Chunk text into overlapping segments.
-    fn chunk_text(text: &str, config: &ChunkConfig) -> Vec<Chunk> {
+    pub(crate) fn chunk_text(text: &str, config: &ChunkConfig) -> Vec<Chunk> {
+        match config.unit {
+            ChunkUnit::Chars => Self::chunk_chars(text, config),
+            ChunkUnit::Tokens => Self::chunk_tokens(text, config),
+        }
+    }
+
+    /// Chunk by character count, breaking at a sentence end where one falls
+    /// in the last 100 characters.
+    fn chunk_chars(text: &str, config: &ChunkConfig) -> Vec<Chunk> {
         let mut chunks = Vec::new();
         let chars: Vec<char> = text.chars().collect();
         let text_len = chars.len();
@@ -108,7 +230,7 @@ impl VectorIndex {
                 if search_start < end {
                     let search_text: String = chars[search_start..end].iter().collect();
 
-                    if let Some(pos) = search_text.rfind(|c| c == '.' || c == '!' || c == '?') {
+                    if let Some(pos) = search_text.rfind(['.', '!', '?']) {
                         let candidate = search_start + pos + 1;
                         // Only use this if it's past start
                         if candidate > start { candidate } else { end }
@@ -160,6 +282,66 @@ impl VectorIndex {
         chunks
     }
 
+    /// Chunk by estimated token count. Chunks hold whole words and break
+    /// after a sentence end where one falls in the last quarter of the chunk;
+    /// consecutive chunks share `chunk_overlap` tokens' worth of words.
+    fn chunk_tokens(text: &str, config: &ChunkConfig) -> Vec<Chunk> {
+        // (start, end) char positions of each whitespace-delimited word
+        let mut words: Vec<(usize, usize)> = Vec::new();
+        let mut word_start = None;
+        let mut len = 0;
+        for (pos, c) in text.chars().enumerate() {
+            match (c.is_whitespace(), word_start) {
+                (true, Some(start)) => {
+                    words.push((start, pos));
+                    word_start = None;
+                }
+                (false, None) => word_start = Some(pos),
+                _ => {}
+            }
+            len = pos + 1;
+        }
+        if let Some(start) = word_start {
+            words.push((start, len));
+        }
+
+        let chars: Vec<char> = text.chars().collect();
+        let size = tokens_to_words(config.chunk_size).max(1);
+        let overlap = tokens_to_words(config.chunk_overlap);
+        let ends_sentence = |&(_, end): &(usize, usize)| matches!(chars[end - 1], '.' | '!' | '?');
+
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        while start < words.len() {
+            let mut end = (start + size).min(words.len());
+            if end < words.len() {
+                let search_from = (end - size / 4).max(start + 1);
+                if let Some(pos) = (search_from..end)
+                    .rev()
+                    .find(|&i| ends_sentence(&words[i - 1]))
+                {
+                    end = pos;
+                }
+            }
+
+            let (start_pos, end_pos) = (words[start].0, words[end - 1].1);
+            chunks.push(Chunk {
+                text: chars[start_pos..end_pos].iter().collect(),
+                start_pos,
+                end_pos,
+                index: chunks.len(),
+            });
+
+            if end >= words.len() {
+                break;
+            }
+            // Step back by the overlap, always moving forward at least one word
+            start = end.saturating_sub(overlap).max(start + 1);
+        }
+
+        chunks
+    }
+
     /// Number of chunks in the index.
     pub fn len(&self) -> usize {
         self.entries.len()
@@ -174,6 +356,16 @@ impl VectorIndex {
     pub fn entries(&self) -> &[IndexEntry] {
         &self.entries
     }
+
+    /// Get the chunk configuration used to build this index.
+    pub fn config(&self) -> &ChunkConfig {
+        &self.config
+    }
+
+    /// Get the HNSW graph, if one was built.
+    pub fn hnsw(&self) -> Option<&HnswIndex> {
+        self.hnsw.as_ref()
+    }
 }
 
 /// Vector search result.
@@ -198,8 +390,19 @@ impl<'a> VectorSearcher<'a> {
     }
 
     /// Search for similar chunks.
-    pub fn search(&self, query: &str, top_k: usize) -> Result<Vec<VectorSearchResult>> {
-        let query_embedding = self.model.embed(query)?;
+    pub async fn search(&self, query: &str, top_k: usize) -> Result<Vec<VectorSearchResult>> {
+        let query_embedding = self.model.embed_query(query).await?;
+
+        if let Some(hnsw) = &self.index.hnsw {
+            return Ok(hnsw
+                .search(&self.index.entries, &query_embedding, top_k)
+                .into_iter()
+                .map(|(id, score)| VectorSearchResult {
+                    chunk: self.index.entries[id].chunk.clone(),
+                    score,
+                })
+                .collect());
+        }
 
         let mut results: Vec<VectorSearchResult> = self
             .index
@@ -228,16 +431,96 @@ impl<'a> VectorSearcher<'a> {
     }
 
     /// Search and return concatenated context.
-    pub fn search_context(&self, query: &str, top_k: usize) -> Result<String> {
-        let results = self.search(query, top_k)?;
+    pub async fn search_context(&self, query: &str, top_k: usize) -> Result<String> {
+        let results = self.search(query, top_k).await?;
+        Ok(Self::format_context(&results))
+    }
 
-        let context = results
+    /// Search and return the context with its passages in rank order.
+    pub async fn retrieve(&self, query: &str, top_k: usize) -> Result<Retrieval> {
+        let results = self.search(query, top_k).await?;
+        Ok(Retrieval {
+            content: Self::format_context(&results),
+            passages: results.into_iter().map(|r| r.chunk.text).collect(),
+        })
+    }
+
+    /// Concatenate search results into a context string.
+    pub fn format_context(results: &[VectorSearchResult]) -> String {
+        results
             .iter()
             .map(|r| format!("[Score: {:.3}]\n{}", r.score, r.chunk.text))
             .collect::<Vec<_>>()
-            .join("\n\n---\n\n");
+            .join("\n\n---\n\n")
+    }
+}
 
-        Ok(context)
+/// Vector search over each document's chunks, as a benchmark baseline.
+///
+/// The benchmark's built-in Vector RAG leg searches the same way; register
+/// this with [`Benchmark::with_system`](super::Benchmark::with_system) to
+/// compare another embedding model or chunking as well.
+pub struct VectorSystem {
+    name: String,
+    model: Arc<EmbeddingModel>,
+    chunk_config: ChunkConfig,
+    top_k: usize,
+    cache_dir: Option<PathBuf>,
+    hnsw: Option<HnswConfig>,
+}
+
+impl VectorSystem {
+    /// Retrieve the `top_k` most similar chunks, named "VectorRAG".
+    pub fn new(model: Arc<EmbeddingModel>, chunk_config: ChunkConfig, top_k: usize) -> Self {
+        Self {
+            name: "VectorRAG".to_string(),
+            model,
+            chunk_config,
+            top_k,
+            cache_dir: None,
+            hnsw: None,
+        }
+    }
+
+    /// Name shown in results, to tell several vector systems apart.
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Reuse indexes saved in `dir` (see [`VectorIndex::build_cached`]).
+    pub fn with_cache_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cache_dir = Some(dir.into());
+        self
+    }
+
+    /// Search an HNSW graph instead of comparing against every chunk.
+    pub fn with_hnsw(mut self, config: HnswConfig) -> Self {
+        self.hnsw = Some(config);
+        self
+    }
+}
+
+#[async_trait]
+impl RetrievalSystem for VectorSystem {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn retrieve(&self, question: &str, document: &str) -> Result<Retrieval> {
+        let chunk_config = self.chunk_config.clone();
+        let mut index = match &self.cache_dir {
+            Some(dir) => {
+                VectorIndex::build_cached(document, &self.model, chunk_config, dir).await?
+            }
+            None => VectorIndex::build(document, &self.model, chunk_config).await?,
+        };
+        if let Some(hnsw) = &self.hnsw {
+            index = index.with_hnsw(hnsw.clone());
+        }
+        VectorSearcher::new(&index, &self.model)
+            .retrieve(question, self.top_k)
+            .await
     }
 }
 
@@ -251,6 +534,7 @@ mod tests {
         let config = ChunkConfig {
             chunk_size: 20,
             chunk_overlap: 5,
+            ..Default::default()
         };
 
         let chunks = VectorIndex::chunk_text(text, &config);
@@ -261,10 +545,137 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_chunk_tokens() {
+        // 12 words; 8 tokens is 6 words, 4 tokens of overlap is 3 words
+        let text = "One two three four five. Six seven\neight nine ten eleven twelve";
+        let config = ChunkConfig {
+            chunk_size: 8,
+            chunk_overlap: 4,
+            unit: ChunkUnit::Tokens,
+        };
+
+        let chunks = VectorIndex::chunk_text(text, &config);
+        let texts: Vec<&str> = chunks.iter().map(|c| c.text.as_str()).collect();
+        // The first chunk stops early at the sentence end
+        assert_eq!(
+            texts,
+            vec![
+                "One two three four five.",
+                "three four five. Six seven\neight",
+                "Six seven\neight nine ten eleven",
+                "nine ten eleven twelve",
+            ]
+        );
+        assert_eq!((chunks[1].start_pos, chunks[1].end_pos), (8, 40));
+        assert_eq!(chunks[3].index, 3);
+
+        assert!(VectorIndex::chunk_text("  \n ", &config).is_empty());
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let config = ChunkConfig {
+            chunk_size: 20,
+            chunk_overlap: 5,
+            ..Default::default()
+        };
+        let text = "This is a test. Another sentence here.";
+        let index = VectorIndex {
+            entries: VectorIndex::chunk_text(text, &config)
+                .into_iter()
+                .map(|chunk| IndexEntry {
+                    embedding: vec![chunk.index as f32, 0.5],
+                    chunk,
+                })
+                .collect(),
+            config: config.clone(),
+            hnsw: None,
+        };
+
+        let path = VectorIndex::cache_path(dir.path(), text, &config, "model");
+        index.save(&path).unwrap();
+        let loaded = VectorIndex::load(&path).unwrap();
+
+        assert_eq!(loaded.config(), &config);
+        assert_eq!(loaded.len(), index.len());
+        assert_eq!(loaded.entries()[1].embedding, vec![1.0, 0.5]);
+        assert_eq!(
+            loaded.entries()[1].chunk.text,
+            index.entries()[1].chunk.text
+        );
+
+        // Different document, chunking or model gets a different file
+        assert_ne!(
+            path,
+            VectorIndex::cache_path(dir.path(), "other", &config, "model")
+        );
+        assert_ne!(
+            path,
+            VectorIndex::cache_path(dir.path(), text, &ChunkConfig::default(), "model")
+        );
+        assert_ne!(
+            path,
+            VectorIndex::cache_path(dir.path(), text, &config, "other")
+        );
+        let tokens = ChunkConfig {
+            unit: ChunkUnit::Tokens,
+            ..config.clone()
+        };
+        assert_ne!(
+            path,
+            VectorIndex::cache_path(dir.path(), text, &tokens, "model")
+        );
+    }
+
     #[test]
     fn test_chunk_config_default() {
         let config = ChunkConfig::default();
         assert_eq!(config.chunk_size, 512);
         assert_eq!(config.chunk_overlap, 50);
+        assert_eq!(config.unit, ChunkUnit::Chars);
+    }
+
+    #[tokio::test]
+    async fn test_vector_system() {
+        use crate::fixtures::replay_interactions;
+        use crate::golden::PromptCapture;
+        use serde_json::json;
+
+        let embeddings = |vectors: &[[f32; 2]]| {
+            let data: Vec<_> = vectors
+                .iter()
+                .enumerate()
+                .map(|(index, embedding)| json!({"embedding": embedding, "index": index}))
+                .collect();
+            json!({"endpoint": "embeddings", "request": {}, "status": 200, "response": {"data": data}})
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("vector.fixture.json");
+        // Two chunks, then the query, which points at the second
+        let interactions = vec![
+            embeddings(&[[1.0, 0.0], [0.0, 1.0]]),
+            embeddings(&[[0.1, 0.9]]),
+        ];
+        let capture = PromptCapture::new(replay_interactions(&path, interactions));
+        let model = EmbeddingModel::api(capture.client().unwrap());
+        let chunk_config = ChunkConfig {
+            chunk_size: 40,
+            chunk_overlap: 0,
+            ..Default::default()
+        };
+        let system = VectorSystem::new(Arc::new(model), chunk_config, 1).with_name("MiniLM");
+
+        let document = "The cat sat on the mat all day. Quarterly revenue grew by ten percent.";
+        let retrieval = system
+            .retrieve("How much did revenue grow?", document)
+            .await
+            .unwrap();
+        assert_eq!(system.name(), "MiniLM");
+        assert_eq!(retrieval.passages.len(), 1);
+        assert!(retrieval.passages[0].contains("revenue"));
+        assert!(retrieval.content.starts_with("[Score: "));
+        assert_eq!(capture.prompts().len(), 2);
     }
 }
@@ -0,0 +1,675 @@
+//! Exporters for converting document trees into other formats.
+//!
+//! Supports documentation-friendly formats (Markdown, HTML), graph
+//! visualization (Graphviz DOT), tabular output (CSV), the JSON
+//! structure produced by the original Python PageIndex, and flattened
+//! LangChain-style documents. [`to_context_markdown`] bundles the sections
+//! retrieved for a query instead, for pasting into other LLM tools.
+//!
+//! [`to_qdrant_jsonl`] and [`to_pgvector_csv`] export node embeddings (see
+//! [`embed_nodes`](crate::summarize::embed_nodes)) with each node's path,
+//! summary and pages, to serve the tree from an existing vector database.
+
+use crate::error::{PageIndexError, Result};
+use crate::retriever::tree_documents;
+use crate::search::SearchResult;
+use crate::tree::{DocumentTree, TreeNode};
+use serde::Serialize;
+use std::str::FromStr;
+
+/// Output format for tree exports.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Markdown document with one heading per section.
+    Markdown,
+    /// Standalone HTML page with a nested outline.
+    Html,
+    /// Graphviz DOT graph.
+    Dot,
+    /// Flat CSV table (one row per node).
+    Csv,
+    /// JSON in the Python PageIndex `structure` layout.
+    PageIndexJson,
+    /// JSON array of LangChain `Document`s, one per node.
+    LangChain,
+    /// Qdrant points as JSON lines, one per embedded node.
+    Qdrant,
+    /// CSV for `COPY` into a pgvector table, one row per embedded node.
+    Pgvector,
+}
+
+impl ExportFormat {
+    /// Conventional file extension for this format.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Html => "html",
+            ExportFormat::Dot => "dot",
+            ExportFormat::Csv | ExportFormat::Pgvector => "csv",
+            ExportFormat::PageIndexJson | ExportFormat::LangChain => "json",
+            ExportFormat::Qdrant => "jsonl",
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "md" | "markdown" => Ok(ExportFormat::Markdown),
+            "html" => Ok(ExportFormat::Html),
+            "dot" | "graphviz" => Ok(ExportFormat::Dot),
+            "csv" => Ok(ExportFormat::Csv),
+            "pageindex-json" | "pageindex" => Ok(ExportFormat::PageIndexJson),
+            "langchain" | "llamaindex" => Ok(ExportFormat::LangChain),
+            "qdrant" => Ok(ExportFormat::Qdrant),
+            "pgvector" => Ok(ExportFormat::Pgvector),
+            other => Err(format!(
+                "unknown export format '{}' (expected md, html, dot, csv, pageindex-json, langchain, qdrant or pgvector)",
+                other
+            )),
+        }
+    }
+}
+
+/// Export a tree to the given format.
+pub fn export_tree(tree: &DocumentTree, format: ExportFormat) -> Result<String> {
+    match format {
+        ExportFormat::Markdown => Ok(to_markdown(tree)),
+        ExportFormat::Html => Ok(to_html(tree)),
+        ExportFormat::Dot => Ok(to_dot(tree)),
+        ExportFormat::Csv => Ok(to_csv(tree)),
+        ExportFormat::PageIndexJson => to_pageindex_json(tree),
+        ExportFormat::LangChain => to_langchain_json(tree),
+        ExportFormat::Qdrant => to_qdrant_jsonl(tree),
+        ExportFormat::Pgvector => to_pgvector_csv(tree),
+    }
+}
+
+/// Render the tree as Markdown, one heading per section.
+pub fn to_markdown(tree: &DocumentTree) -> String {
+    fn render(node: &TreeNode, depth: usize, out: &mut String) {
+        // Document title is level 1, so sections start at level 2
+        let level = (depth + 2).min(6);
+        let structure = node
+            .structure
+            .as_ref()
+            .map(|s| format!("{} ", s))
+            .unwrap_or_default();
+
+        out.push_str(&format!(
+            "{} {}{}\n\n",
+            "#".repeat(level),
+            structure,
+            node.title
+        ));
+        out.push_str(&format!(
+            "_Pages {}-{}_\n\n",
+            node.start_index, node.end_index
+        ));
+        if let Some(summary) = &node.summary {
+            out.push_str(summary);
+            out.push_str("\n\n");
+        }
+
+        for child in &node.nodes {
+            render(child, depth + 1, out);
+        }
+    }
+
+    let mut out = format!("# {}\n\n", tree.name);
+    if let Some(desc) = &tree.description {
+        out.push_str(desc);
+        out.push_str("\n\n");
+    }
+    out.push_str(&format!(
+        "{} pages, {} sections\n\n",
+        tree.total_pages,
+        tree.node_count()
+    ));
+
+    for node in &tree.nodes {
+        render(node, 0, &mut out);
+    }
+
+    out
+}
+
+/// Render the sections retrieved for a query as one Markdown document,
+/// ready to paste into a prompt: per section its title, pages, relevance,
+/// a link to its first page in the source document (when the tree records
+/// one), the ranker's snippet and the section text. Results without
+/// content fall back to the reason they matched.
+pub fn to_context_markdown(tree: &DocumentTree, query: &str, results: &[SearchResult]) -> String {
+    let source = tree.source.as_ref().map(|s| s.path.as_str());
+    let mut out = format!(
+        "# Context: {}
+
+",
+        query
+    );
+    out.push_str(&format!(
+        "{} section(s) retrieved from {}, most relevant first.\n\n",
+        results.len(),
+        match source {
+            Some(path) => format!("[{}](<{}>)", tree.name, path),
+            None => tree.name.clone(),
+        }
+    ));
+
+    for (i, result) in results.iter().enumerate() {
+        out.push_str(&format!("## {}. {}\n\n", i + 1, result.title));
+        let mut details = format!(
+            "Pages {}-{} · relevance: {}",
+            result.start_index,
+            result.end_index,
+            format!("{:?}", result.relevance).to_lowercase()
+        );
+        if let Some(path) = source {
+            details.push_str(&format!(
+                " · [source](<{}#page={}>)",
+                path, result.start_index
+            ));
+        }
+        out.push_str(&format!("_{}_\n\n", details));
+        if let Some(snippet) = &result.snippet {
+            out.push_str(&format!("> {}\n\n", snippet.replace('\n', "\n> ")));
+        }
+        match &result.content {
+            Some(content) => out.push_str(content.trim()),
+            None => out.push_str(&format!("_Section text not included._ {}", result.reason)),
+        }
+        out.push_str("\n\n");
+    }
+
+    out
+}
+
+/// Render the tree as a standalone HTML page with a nested outline.
+pub fn to_html(tree: &DocumentTree) -> String {
+    fn render(node: &TreeNode, indent: usize, out: &mut String) {
+        let prefix = "  ".repeat(indent);
+        let structure = node
+            .structure
+            .as_ref()
+            .map(|s| format!("{} ", html_escape(s)))
+            .unwrap_or_default();
+
+        out.push_str(&format!(
+            "{}<li><strong>{}{}</strong> <span class=\"pages\">pages {}-{}</span>",
+            prefix,
+            structure,
+            html_escape(&node.title),
+            node.start_index,
+            node.end_index
+        ));
+        if let Some(summary) = &node.summary {
+            out.push_str(&format!("<p>{}</p>", html_escape(summary)));
+        }
+
+        if node.nodes.is_empty() {
+            out.push_str("</li>\n");
+        } else {
+            out.push_str(&format!("\n{}  <ul>\n", prefix));
+            for child in &node.nodes {
+                render(child, indent + 2, out);
+            }
+            out.push_str(&format!("{}  </ul>\n{}</li>\n", prefix, prefix));
+        }
+    }
+
+    let title = html_escape(&tree.name);
+    let mut out = String::new();
+    out.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+    out.push_str(&format!("<title>{}</title>\n", title));
+    out.push_str(
+        "<style>body{font-family:sans-serif;max-width:60em;margin:2em auto}\
+         .pages{color:#666;font-size:0.9em}p{margin:0.2em 0 0.6em}</style>\n",
+    );
+    out.push_str("</head>\n<body>\n");
+    out.push_str(&format!("<h1>{}</h1>\n", title));
+    if let Some(desc) = &tree.description {
+        out.push_str(&format!("<p>{}</p>\n", html_escape(desc)));
+    }
+    out.push_str(&format!(
+        "<p>{} pages, {} sections</p>\n<ul>\n",
+        tree.total_pages,
+        tree.node_count()
+    ));
+    for node in &tree.nodes {
+        render(node, 1, &mut out);
+    }
+    out.push_str("</ul>\n</body>\n</html>\n");
+
+    out
+}
+
+/// Render the tree as a Graphviz DOT digraph.
+pub fn to_dot(tree: &DocumentTree) -> String {
+    fn render(node: &TreeNode, parent: &str, counter: &mut usize, out: &mut String) {
+        let id = format!("n{}", *counter);
+        *counter += 1;
+
+        out.push_str(&format!(
+            "  {} [label=\"{}\\npages {}-{}\"];\n",
+            id,
+            dot_escape(&node.title),
+            node.start_index,
+            node.end_index
+        ));
+        out.push_str(&format!("  {} -> {};\n", parent, id));
+
+        for child in &node.nodes {
+            render(child, &id, counter, out);
+        }
+    }
+
+    let mut out = String::from("digraph tree {\n  rankdir=LR;\n  node [shape=box];\n");
+    out.push_str(&format!(
+        "  root [label=\"{}\", shape=folder];\n",
+        dot_escape(&tree.name)
+    ));
+
+    let mut counter = 0;
+    for node in &tree.nodes {
+        render(node, "root", &mut counter, &mut out);
+    }
+    out.push_str("}\n");
+
+    out
+}
+
+/// Render the tree as CSV, one row per node in depth-first order.
+pub fn to_csv(tree: &DocumentTree) -> String {
+    fn render(node: &TreeNode, depth: usize, parent_id: Option<&str>, out: &mut String) {
+        let fields = [
+            node.node_id.clone().unwrap_or_default(),
+            parent_id.unwrap_or_default().to_string(),
+            node.structure.clone().unwrap_or_default(),
+            node.title.clone(),
+            depth.to_string(),
+            node.start_index.to_string(),
+            node.end_index.to_string(),
+            node.summary.clone().unwrap_or_default(),
+        ];
+        let row = fields
+            .iter()
+            .map(|f| csv_escape(f))
+            .collect::<Vec<_>>()
+            .join(",");
+        out.push_str(&row);
+        out.push('\n');
+
+        for child in &node.nodes {
+            render(child, depth + 1, node.node_id.as_deref(), out);
+        }
+    }
+
+    let mut out =
+        String::from("node_id,parent_id,structure,title,depth,start_index,end_index,summary\n");
+    for node in &tree.nodes {
+        render(node, 0, None, &mut out);
+    }
+
+    out
+}
+
+/// Python PageIndex output document.
+#[derive(Serialize)]
+struct PageIndexDocument<'a> {
+    doc_name: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    doc_description: Option<&'a str>,
+    structure: Vec<PageIndexNode<'a>>,
+}
+
+/// Python PageIndex node layout.
+#[derive(Serialize)]
+struct PageIndexNode<'a> {
+    title: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    node_id: Option<&'a str>,
+    start_index: usize,
+    end_index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    prefix_summary: Option<&'a str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    nodes: Vec<PageIndexNode<'a>>,
+}
+
+impl<'a> PageIndexNode<'a> {
+    fn from_node(node: &'a TreeNode) -> Self {
+        Self {
+            title: &node.title,
+            node_id: node.node_id.as_deref(),
+            start_index: node.start_index,
+            end_index: node.end_index,
+            summary: node.summary.as_deref(),
+            prefix_summary: node.prefix_summary.as_deref(),
+            nodes: node.nodes.iter().map(PageIndexNode::from_node).collect(),
+        }
+    }
+}
+
+/// Render the tree as JSON compatible with the Python PageIndex output.
+pub fn to_pageindex_json(tree: &DocumentTree) -> Result<String> {
+    let doc = PageIndexDocument {
+        doc_name: &tree.name,
+        doc_description: tree.description.as_deref(),
+        structure: tree.nodes.iter().map(PageIndexNode::from_node).collect(),
+    };
+
+    serde_json::to_string_pretty(&doc).map_err(|e| PageIndexError::Serialization(e.to_string()))
+}
+
+/// Render every node as a LangChain `Document` (page content is the
+/// summary or title; see [`tree_documents`] to include section text).
+pub fn to_langchain_json(tree: &DocumentTree) -> Result<String> {
+    serde_json::to_string_pretty(&tree_documents(tree, None))
+        .map_err(|e| PageIndexError::Serialization(e.to_string()))
+}
+
+/// A node with its embedding, as exported to vector databases.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct VectorRecord {
+    /// Position of the node in the tree, depth first from 0. Vector
+    /// databases that need integer IDs (Qdrant) use it as the point ID.
+    pub id: usize,
+    /// Document name.
+    pub source: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node_id: Option<String>,
+    pub title: String,
+    /// Titles from the top-level section down to this one.
+    pub path: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub structure: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    pub start_index: usize,
+    pub end_index: usize,
+    #[serde(skip)]
+    pub embedding: Vec<f32>,
+}
+
+/// The tree's embedded nodes, depth first. Fails if no node has an
+/// embedding.
+pub fn vector_records(tree: &DocumentTree) -> Result<Vec<VectorRecord>> {
+    fn walk(
+        nodes: &[TreeNode],
+        source: &str,
+        path: &mut Vec<String>,
+        position: &mut usize,
+        out: &mut Vec<VectorRecord>,
+    ) {
+        for node in nodes {
+            path.push(node.title.clone());
+            if let Some(embedding) = &node.embedding {
+                out.push(VectorRecord {
+                    id: *position,
+                    source: source.to_string(),
+                    node_id: node.node_id.clone(),
+                    title: node.title.clone(),
+                    path: path.clone(),
+                    structure: node.structure.clone(),
+                    summary: node.summary.clone(),
+                    start_index: node.start_index,
+                    end_index: node.end_index,
+                    embedding: embedding.clone(),
+                });
+            }
+            *position += 1;
+            walk(&node.nodes, source, path, position, out);
+            path.pop();
+        }
+    }
+
+    let mut out = Vec::new();
+    walk(&tree.nodes, &tree.name, &mut Vec::new(), &mut 0, &mut out);
+    if out.is_empty() {
+        return Err(PageIndexError::TreeError(format!(
+            "No node of '{}' has an embedding; embed the tree first",
+            tree.name
+        )));
+    }
+    Ok(out)
+}
+
+/// Export embedded nodes as Qdrant points, one JSON object per line:
+/// `{"id", "vector", "payload"}`, with the rest of the [`VectorRecord`] as
+/// payload.
+pub fn to_qdrant_jsonl(tree: &DocumentTree) -> Result<String> {
+    let mut out = String::new();
+    for record in vector_records(tree)? {
+        let point = serde_json::json!({
+            "id": record.id,
+            "vector": record.embedding,
+            "payload": record,
+        });
+        out.push_str(&point.to_string());
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Export embedded nodes as CSV with a header row, the path joined with
+/// ` > ` and the embedding in pgvector's `[x,y,...]` text form, for
+/// `\copy sections FROM 'tree.csv' CSV HEADER` into a table with these
+/// columns and a `vector` column `embedding`.
+pub fn to_pgvector_csv(tree: &DocumentTree) -> Result<String> {
+    let mut out = String::from(
+        "id,source,node_id,structure,title,path,summary,start_index,end_index,embedding\n",
+    );
+    for record in vector_records(tree)? {
+        let embedding = record
+            .embedding
+            .iter()
+            .map(f32::to_string)
+            .collect::<Vec<_>>()
+            .join(",");
+        let fields = [
+            record.id.to_string(),
+            record.source,
+            record.node_id.unwrap_or_default(),
+            record.structure.unwrap_or_default(),
+            record.title,
+            record.path.join(" > "),
+            record.summary.unwrap_or_default(),
+            record.start_index.to_string(),
+            record.end_index.to_string(),
+            format!("[{}]", embedding),
+        ];
+        let row = fields
+            .iter()
+            .map(|f| csv_escape(f))
+            .collect::<Vec<_>>()
+            .join(",");
+        out.push_str(&row);
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Escape text for inclusion in HTML.
+fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Escape text for a double-quoted DOT label.
+fn dot_escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', " ")
+}
+
+/// Quote a CSV field if it contains separators, quotes, or newlines.
+pub(crate) fn csv_escape(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_tree() -> DocumentTree {
+        let mut ch1 = TreeNode::new("Introduction", 1, 10).with_structure("1");
+        ch1.node_id = Some("0000".to_string());
+        ch1.summary = Some("Covers the basics, \"quoted\".".to_string());
+        let mut sec = TreeNode::new("Background <notes>", 1, 5).with_structure("1.1");
+        sec.node_id = Some("0001".to_string());
+        ch1.add_child(sec);
+
+        let mut ch2 = TreeNode::new("Methods", 11, 20).with_structure("2");
+        ch2.node_id = Some("0002".to_string());
+
+        DocumentTree::new("Test Document", vec![ch1, ch2], 20)
+    }
+
+    #[test]
+    fn test_format_from_str() {
+        assert_eq!("md".parse(), Ok(ExportFormat::Markdown));
+        assert_eq!("HTML".parse(), Ok(ExportFormat::Html));
+        assert_eq!("dot".parse(), Ok(ExportFormat::Dot));
+        assert_eq!("csv".parse(), Ok(ExportFormat::Csv));
+        assert_eq!("pageindex-json".parse(), Ok(ExportFormat::PageIndexJson));
+        assert_eq!("LlamaIndex".parse(), Ok(ExportFormat::LangChain));
+        assert_eq!("qdrant".parse(), Ok(ExportFormat::Qdrant));
+        assert_eq!("pgvector".parse(), Ok(ExportFormat::Pgvector));
+        assert!("pdf".parse::<ExportFormat>().is_err());
+    }
+
+    #[test]
+    fn test_markdown_headings() {
+        let md = to_markdown(&create_test_tree());
+        assert!(md.starts_with("# Test Document"));
+        assert!(md.contains("## 1 Introduction"));
+        assert!(md.contains("### 1.1 Background <notes>"));
+        assert!(md.contains("_Pages 11-20_"));
+    }
+
+    #[test]
+    fn test_html_is_escaped() {
+        let html = to_html(&create_test_tree());
+        assert!(html.contains("Background &lt;notes&gt;"));
+        assert!(html.contains("&quot;quoted&quot;"));
+        assert!(html.trim_end().ends_with("</html>"));
+    }
+
+    #[test]
+    fn test_dot_edges() {
+        let dot = to_dot(&create_test_tree());
+        assert!(dot.starts_with("digraph tree {"));
+        assert!(dot.contains("root -> n0;"));
+        assert!(dot.contains("n0 -> n1;"));
+        assert!(dot.contains("root -> n2;"));
+    }
+
+    #[test]
+    fn test_csv_rows() {
+        let csv = to_csv(&create_test_tree());
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert!(lines[0].starts_with("node_id,parent_id"));
+        assert_eq!(
+            lines[1],
+            "0000,,1,Introduction,0,1,10,\"Covers the basics, \"\"quoted\"\".\""
+        );
+        assert!(lines[2].starts_with("0001,0000,1.1,"));
+    }
+
+    #[test]
+    fn test_vector_exports() {
+        let mut tree = create_test_tree();
+        assert!(to_qdrant_jsonl(&tree).is_err());
+        tree.nodes[0].nodes[0].embedding = Some(vec![0.5, -1.0]);
+        tree.nodes[1].embedding = Some(vec![0.25, 0.0]);
+
+        let qdrant = to_qdrant_jsonl(&tree).unwrap();
+        let points: Vec<serde_json::Value> = qdrant
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0]["id"], 1);
+        assert_eq!(points[0]["vector"], serde_json::json!([0.5, -1.0]));
+        let payload = &points[0]["payload"];
+        assert_eq!(payload["node_id"], "0001");
+        assert_eq!(
+            payload["path"],
+            serde_json::json!(["Introduction", "Background <notes>"])
+        );
+        assert_eq!(payload["start_index"], 1);
+        assert!(payload.get("embedding").is_none());
+        assert_eq!(points[1]["id"], 2);
+
+        let csv = to_pgvector_csv(&tree).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].ends_with(",embedding"));
+        assert_eq!(
+            lines[1],
+            "1,Test Document,0001,1.1,Background <notes>,Introduction > Background <notes>,,1,5,\"[0.5,-1]\""
+        );
+    }
+
+    #[test]
+    fn test_context_markdown() {
+        use crate::search::Relevance;
+        use crate::tree::SourceInfo;
+
+        let mut tree = create_test_tree();
+        let result = |title: &str, start, end, content: Option<&str>| SearchResult {
+            title: title.to_string(),
+            start_index: start,
+            end_index: end,
+            relevance: Relevance::High,
+            reason: "Describes the setup.".to_string(),
+            content: content.map(str::to_string),
+            snippet: None,
+            snippet_span: None,
+        };
+        let mut methods = result("Methods", 11, 20, Some("We sampled 40 sites.\n"));
+        methods.snippet = Some("40 sites".to_string());
+        let results = vec![methods, result("Background <notes>", 1, 5, None)];
+
+        let md = to_context_markdown(&tree, "How was it sampled?", &results);
+        assert!(md.starts_with(
+            "# Context: How was it sampled?\n\n2 section(s) retrieved from Test Document,"
+        ));
+        assert!(md.contains("## 1. Methods\n\n_Pages 11-20 · relevance: high_\n\n> 40 sites\n\nWe sampled 40 sites.\n\n"));
+        assert!(md.contains("_Section text not included._ Describes the setup."));
+
+        tree.source = Some(SourceInfo {
+            path: "docs/report 1.pdf".to_string(),
+            hash: String::new(),
+            size: 0,
+            modified: None,
+        });
+        let md = to_context_markdown(&tree, "q", &results);
+        assert!(md.contains("retrieved from [Test Document](<docs/report 1.pdf>)"));
+        assert!(md.contains("relevance: high · [source](<docs/report 1.pdf#page=11>)_"));
+    }
+
+    #[test]
+    fn test_pageindex_json_layout() {
+        let json = to_pageindex_json(&create_test_tree()).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["doc_name"], "Test Document");
+        assert_eq!(value["structure"][0]["node_id"], "0000");
+        assert_eq!(
+            value["structure"][0]["nodes"][0]["title"],
+            "Background <notes>"
+        );
+        assert!(value["structure"][1].get("nodes").is_none());
+    }
+}
@@ -0,0 +1,334 @@
+//! Structured extraction over retrieved sections.
+//!
+//! After retrieval, the next step is often filling in a record: the parties
+//! and dates of a contract, the amounts of an invoice. An [`Extractor`]
+//! asks the LLM for data matching a caller's JSON Schema from the retrieved
+//! sections, checks the reply against the schema, and cites the section
+//! each field came from.
+//!
+//! ```no_run
+//! use rust_page_indexer::PageIndex;
+//! use serde_json::json;
+//!
+//! # async fn run() -> rust_page_indexer::Result<()> {
+//! let mut index = PageIndex::builder().build()?;
+//! index.load("data/tree_index.json")?;
+//! index.load_document("contract.txt")?;
+//! let schema = json!({
+//!     "type": "object",
+//!     "properties": {
+//!         "parties": { "type": "array", "items": { "type": "string" } },
+//!         "effective_date": { "type": "string" }
+//!     },
+//!     "required": ["parties", "effective_date"]
+//! });
+//! let extraction = index.extract("Who are the parties and when does it start?", &schema).await?;
+//! println!("{}", extraction.data);
+//! for citation in &extraction.citations {
+//!     println!("{} from {} (pages {}-{})", citation.field, citation.title, citation.start_index, citation.end_index);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Only the common subset of JSON Schema is checked: `type`, `properties`,
+//! `required`, `items` and `enum`. A value the sections don't state is
+//! `null`, which every schema accepts.
+
+use crate::error::{PageIndexError, Result};
+use crate::llm::{LlmClient, Prompts};
+use crate::search::{SearchResult, TreeSearcher};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tracing::debug;
+
+/// Data extracted from retrieved sections.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Extraction {
+    /// Conforms to the schema extracted with.
+    pub data: Value,
+    /// Where the fields of `data` came from.
+    pub citations: Vec<Citation>,
+    /// Retrieved sections, best first.
+    pub sources: Vec<SearchResult>,
+}
+
+/// The section an extracted field came from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Citation {
+    /// JSON Pointer to the field in [`Extraction::data`], e.g. `/parties/0`.
+    pub field: String,
+    /// Section title.
+    pub title: String,
+    pub start_index: usize,
+    pub end_index: usize,
+    /// The text the value was taken from, as quoted by the LLM.
+    pub quote: String,
+}
+
+/// Extracts schema-conformant data from retrieved sections.
+pub struct Extractor {
+    client: LlmClient,
+}
+
+impl Extractor {
+    /// Create an extractor calling the given client.
+    pub fn new(client: LlmClient) -> Self {
+        Self { client }
+    }
+
+    /// Extract data matching `schema` for a query from already retrieved
+    /// sections (with their content, for anything but titles to go on).
+    ///
+    /// Fails with [`PageIndexError::LlmParse`] if the reply does not
+    /// conform to the schema.
+    pub async fn extract(
+        &self,
+        query: &str,
+        schema: &Value,
+        sources: Vec<SearchResult>,
+    ) -> Result<Extraction> {
+        let sections = sources
+            .iter()
+            .enumerate()
+            .map(|(i, r)| {
+                format!(
+                    "[{}] {} (pages {}-{})\n{}",
+                    i + 1,
+                    r.title,
+                    r.start_index,
+                    r.end_index,
+                    r.content.as_deref().unwrap_or(&r.reason)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n---\n\n");
+        let schema_str = serde_json::to_string_pretty(schema)?;
+        let prompt = Prompts::extract_fields()
+            .replace("{query}", query)
+            .replace("{schema}", &schema_str)
+            .replace("{sections}", &sections);
+
+        let response = self
+            .client
+            .complete(Some(Prompts::system_document_analyzer()), &prompt)
+            .await?;
+        let (data, citations) =
+            parse_extraction(schema, &sources, &TreeSearcher::extract_json(&response))?;
+        Ok(Extraction {
+            data,
+            citations,
+            sources,
+        })
+    }
+}
+
+/// Parse an extraction reply: the data, checked against the schema, and
+/// the citations naming a known section and a field present in the data.
+fn parse_extraction(
+    schema: &Value,
+    sources: &[SearchResult],
+    json: &str,
+) -> Result<(Value, Vec<Citation>)> {
+    #[derive(Deserialize)]
+    struct ExtractResponse {
+        data: Value,
+        #[serde(default)]
+        citations: Vec<RawCitation>,
+    }
+
+    #[derive(Deserialize)]
+    struct RawCitation {
+        field: String,
+        section: usize,
+        #[serde(default)]
+        quote: String,
+    }
+
+    let parsed: ExtractResponse = serde_json::from_str(json).map_err(|e| {
+        PageIndexError::LlmParse(format!("Failed to parse extraction response: {}", e))
+    })?;
+
+    let violations = validate(&parsed.data, schema);
+    if !violations.is_empty() {
+        return Err(PageIndexError::LlmParse(format!(
+            "Extracted data does not match the schema: {}",
+            violations.join("; ")
+        )));
+    }
+
+    let mut citations = Vec::new();
+    for raw in parsed.citations {
+        let field = match raw.field.as_str() {
+            "" | "/" => String::new(),
+            f if f.starts_with('/') => f.to_string(),
+            f => format!("/{}", f),
+        };
+        let source = raw.section.checked_sub(1).and_then(|i| sources.get(i));
+        let (Some(source), Some(_)) = (source, parsed.data.pointer(&field)) else {
+            debug!(field = %raw.field, section = raw.section, "dropping unresolved citation");
+            continue;
+        };
+        citations.push(Citation {
+            field,
+            title: source.title.clone(),
+            start_index: source.start_index,
+            end_index: source.end_index,
+            quote: raw.quote,
+        });
+    }
+    Ok((parsed.data, citations))
+}
+
+/// Where `value` breaks `schema`, as `"<pointer>: <problem>"` messages.
+/// Empty if it conforms. `null` conforms to any schema.
+pub fn validate(value: &Value, schema: &Value) -> Vec<String> {
+    let mut violations = Vec::new();
+    validate_at(value, schema, "", &mut violations);
+    violations
+}
+
+fn validate_at(value: &Value, schema: &Value, path: &str, violations: &mut Vec<String>) {
+    if value.is_null() {
+        return;
+    }
+    let at = if path.is_empty() { "/" } else { path };
+
+    if let Some(expected) = schema.get("type") {
+        let types: Vec<&str> = match expected {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+            _ => Vec::new(),
+        };
+        if !types.is_empty() && !types.iter().any(|t| has_type(value, t)) {
+            violations.push(format!("{}: expected {}", at, types.join(" or ")));
+            return;
+        }
+    }
+
+    if let Some(allowed) = schema.get("enum").and_then(Value::as_array)
+        && !allowed.contains(value)
+    {
+        violations.push(format!(
+            "{}: {} is not one of the allowed values",
+            at, value
+        ));
+    }
+
+    if let Value::Object(fields) = value {
+        for name in schema
+            .get("required")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(Value::as_str)
+        {
+            if !fields.contains_key(name) {
+                violations.push(format!("{}: missing required field '{}'", at, name));
+            }
+        }
+        if let Some(properties) = schema.get("properties").and_then(Value::as_object) {
+            for (name, field) in fields {
+                if let Some(field_schema) = properties.get(name) {
+                    let field_path = format!("{}/{}", path, name);
+                    validate_at(field, field_schema, &field_path, violations);
+                }
+            }
+        }
+    }
+
+    if let (Value::Array(items), Some(item_schema)) = (value, schema.get("items")) {
+        for (i, item) in items.iter().enumerate() {
+            validate_at(item, item_schema, &format!("{}/{}", path, i), violations);
+        }
+    }
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    match name {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::search::Relevance;
+    use serde_json::json;
+
+    fn contract_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "parties": { "type": "array", "items": { "type": "string" } },
+                "amount": { "type": "number" },
+                "currency": { "enum": ["USD", "EUR"] }
+            },
+            "required": ["parties", "amount"]
+        })
+    }
+
+    #[test]
+    fn test_validate() {
+        let schema = contract_schema();
+        assert!(validate(&json!({ "parties": ["A", "B"], "amount": 10 }), &schema).is_empty());
+        // Values not stated in the document are null
+        assert!(validate(&json!({ "parties": null, "amount": null }), &schema).is_empty());
+
+        let violations = validate(&json!({ "parties": ["A", 2], "currency": "GBP" }), &schema);
+        assert_eq!(
+            violations,
+            vec![
+                "/: missing required field 'amount'",
+                "/currency: \"GBP\" is not one of the allowed values",
+                "/parties/1: expected string",
+            ]
+        );
+        assert_eq!(validate(&json!([]), &schema), vec!["/: expected object"]);
+        assert!(validate(&json!(3), &json!({ "type": ["integer", "string"] })).is_empty());
+        assert!(!validate(&json!(3.5), &json!({ "type": "integer" })).is_empty());
+    }
+
+    #[test]
+    fn test_parse_extraction() {
+        let source = |title: &str, start| SearchResult {
+            title: title.to_string(),
+            start_index: start,
+            end_index: start + 1,
+            relevance: Relevance::High,
+            reason: String::new(),
+            content: None,
+            snippet: None,
+            snippet_span: None,
+        };
+        let sources = vec![source("Parties", 1), source("Payment", 4)];
+        let json = r#"{
+            "data": {"parties": ["Acme", "Globex"], "amount": 1200},
+            "citations": [
+                {"field": "/parties/1", "section": 1, "quote": "and Globex Corp."},
+                {"field": "amount", "section": 2, "quote": "USD 1,200"},
+                {"field": "/currency", "section": 2},
+                {"field": "/amount", "section": 3}
+            ]
+        }"#;
+        let (data, citations) = parse_extraction(&contract_schema(), &sources, json).unwrap();
+        assert_eq!(data["parties"][1], "Globex");
+        assert_eq!(citations.len(), 2);
+        assert_eq!(citations[0].title, "Parties");
+        assert_eq!(citations[1].field, "/amount");
+        assert_eq!((citations[1].start_index, citations[1].end_index), (4, 5));
+
+        let bad = r#"{"data": {"parties": "Acme", "amount": 1}}"#;
+        assert!(matches!(
+            parse_extraction(&contract_schema(), &sources, bad),
+            Err(PageIndexError::LlmParse(_))
+        ));
+    }
+}
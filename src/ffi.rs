@@ -0,0 +1,352 @@
+//! C ABI for embedding the searcher in non-Rust backends (`ffi` feature).
+//!
+//! Open a saved index once, search it as often as needed, and get results
+//! back as JSON strings. Every function is synchronous: an index owns a
+//! single-threaded tokio runtime that drives its LLM calls. The header is
+//! `include/page_indexer.h`.
+//!
+//! Functions returning a pointer return null on failure; the message is
+//! then available from [`pi_last_error`] on the same thread. A panic
+//! never unwinds into the caller: it is reported as a failure. Strings
+//! returned by this module must be freed with [`pi_string_free`], and
+//! indexes with [`pi_index_free`].
+
+use crate::config::{Config, LlmConfig};
+use crate::document::Document;
+use crate::error::{PageIndexError, Result};
+use crate::llm::LlmClient;
+use crate::persistence::load_tree;
+use crate::search::{SearchOptions, TreeSearcher};
+use crate::tree::DocumentTree;
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char};
+use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::path::Path;
+use std::ptr;
+use tokio::runtime::Runtime;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// A loaded index: the tree, optionally its source document, and a client.
+pub struct PiIndex {
+    tree: DocumentTree,
+    document: Option<Document>,
+    client: LlmClient,
+    runtime: Runtime,
+}
+
+impl PiIndex {
+    fn open(
+        tree_path: &str,
+        document_path: Option<&str>,
+        llm_config: Option<&str>,
+    ) -> Result<Self> {
+        let config = match llm_config {
+            Some(json) => serde_json::from_str::<LlmConfig>(json)
+                .map_err(|e| PageIndexError::InvalidConfig(e.to_string()))?,
+            None => {
+                let config = Config::load()?;
+                config.validate()?;
+                config.llm
+            }
+        };
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| PageIndexError::Config(format!("Failed to start runtime: {}", e)))?;
+        Ok(Self {
+            tree: load_tree(Path::new(tree_path))?,
+            document: document_path
+                .map(|path| crate::loader::load(Path::new(path)))
+                .transpose()?,
+            client: LlmClient::new(config),
+            runtime,
+        })
+    }
+
+    fn search(&self, query: &str, top_k: usize) -> Result<String> {
+        let mut options = SearchOptions::default();
+        if top_k > 0 {
+            options.top_k = top_k;
+        }
+        let searcher = TreeSearcher::with_options(self.client.clone(), options);
+        let results = self.runtime.block_on(async {
+            match &self.document {
+                Some(document) => {
+                    searcher
+                        .search_with_content(&self.tree, document, query)
+                        .await
+                }
+                None => searcher.search(&self.tree, query).await,
+            }
+        })?;
+        Ok(serde_json::to_string(&results)?)
+    }
+}
+
+fn set_last_error(message: impl Into<String>) {
+    let message = CString::new(message.into().replace('\0', " ")).unwrap_or_default();
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(message));
+}
+
+/// Run an entry point's body, returning `on_panic` (with the panic
+/// recorded as the last error) if it panics, since unwinding into C is
+/// not allowed.
+fn guard<T>(on_panic: T, body: impl FnOnce() -> T) -> T {
+    catch_unwind(AssertUnwindSafe(body)).unwrap_or_else(|payload| {
+        let message = payload
+            .downcast_ref::<&str>()
+            .copied()
+            .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+            .unwrap_or("unknown cause");
+        set_last_error(format!("Internal error: panicked: {}", message));
+        on_panic
+    })
+}
+
+/// Read a required (`Some`) or optional (`None` if null) UTF-8 argument.
+///
+/// # Safety
+///
+/// `ptr` must be null or a valid NUL-terminated string.
+unsafe fn str_arg<'a>(ptr: *const c_char, name: &str) -> std::result::Result<Option<&'a str>, ()> {
+    if ptr.is_null() {
+        return Ok(None);
+    }
+    match unsafe { CStr::from_ptr(ptr) }.to_str() {
+        Ok(s) => Ok(Some(s)),
+        Err(_) => {
+            set_last_error(format!("{} is not valid UTF-8", name));
+            Err(())
+        }
+    }
+}
+
+/// Hand a string to the caller, or null (with the error recorded).
+fn into_c_string(result: Result<String>) -> *mut c_char {
+    match result.map(|s| CString::new(s.replace('\0', " "))) {
+        Ok(Ok(s)) => s.into_raw(),
+        Ok(Err(e)) => {
+            set_last_error(e.to_string());
+            ptr::null_mut()
+        }
+        Err(e) => {
+            set_last_error(e.to_string());
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Open a saved index (`.json` or `.bin`).
+///
+/// `document_path` (nullable) is the text file the index was built from;
+/// with it, results include each section's content. `llm_config` (nullable)
+/// is a JSON object with `api_base`, `api_key` and `model` (and optionally
+/// `max_tokens` and `temperature`); when null, configuration is read from
+/// the environment and config file as for the CLI.
+///
+/// Returns null on failure.
+///
+/// # Safety
+///
+/// Each argument must be null (where allowed) or a valid NUL-terminated
+/// string.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pi_index_open(
+    tree_path: *const c_char,
+    document_path: *const c_char,
+    llm_config: *const c_char,
+) -> *mut PiIndex {
+    guard(ptr::null_mut(), || {
+        let args = unsafe {
+            (
+                str_arg(tree_path, "tree_path"),
+                str_arg(document_path, "document_path"),
+                str_arg(llm_config, "llm_config"),
+            )
+        };
+        let (Ok(tree_path), Ok(document_path), Ok(llm_config)) = args else {
+            return ptr::null_mut();
+        };
+        let Some(tree_path) = tree_path else {
+            set_last_error("tree_path is null");
+            return ptr::null_mut();
+        };
+
+        match PiIndex::open(tree_path, document_path, llm_config) {
+            Ok(index) => Box::into_raw(Box::new(index)),
+            Err(e) => {
+                set_last_error(e.to_string());
+                ptr::null_mut()
+            }
+        }
+    })
+}
+
+/// Search an index, returning the results as a JSON array (see
+/// `SearchResult`). `top_k` of 0 uses the default. Blocks until the LLM
+/// calls finish.
+///
+/// Returns null on failure; free the result with [`pi_string_free`].
+///
+/// # Safety
+///
+/// `index` must come from [`pi_index_open`] and not have been freed, and
+/// `query` must be a valid NUL-terminated string. An index must not be
+/// searched from two threads at once.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pi_index_search(
+    index: *const PiIndex,
+    query: *const c_char,
+    top_k: usize,
+) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        let Some(index) = (unsafe { index.as_ref() }) else {
+            set_last_error("index is null");
+            return ptr::null_mut();
+        };
+        let query = match unsafe { str_arg(query, "query") } {
+            Ok(Some(query)) => query,
+            Ok(None) => {
+                set_last_error("query is null");
+                return ptr::null_mut();
+            }
+            Err(()) => return ptr::null_mut(),
+        };
+        into_c_string(index.search(query, top_k))
+    })
+}
+
+/// The index's tree as JSON.
+///
+/// Returns null on failure; free the result with [`pi_string_free`].
+///
+/// # Safety
+///
+/// `index` must come from [`pi_index_open`] and not have been freed.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pi_index_tree_json(index: *const PiIndex) -> *mut c_char {
+    guard(ptr::null_mut(), || {
+        let Some(index) = (unsafe { index.as_ref() }) else {
+            set_last_error("index is null");
+            return ptr::null_mut();
+        };
+        into_c_string(index.tree.to_json().map_err(Into::into))
+    })
+}
+
+/// Free an index. Null is ignored.
+///
+/// # Safety
+///
+/// `index` must be null or come from [`pi_index_open`], and must not be
+/// used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pi_index_free(index: *mut PiIndex) {
+    guard((), || {
+        if !index.is_null() {
+            drop(unsafe { Box::from_raw(index) });
+        }
+    })
+}
+
+/// Free a string returned by this library. Null is ignored.
+///
+/// # Safety
+///
+/// `s` must be null or a string returned by this library, and must not be
+/// used afterwards.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn pi_string_free(s: *mut c_char) {
+    guard((), || {
+        if !s.is_null() {
+            drop(unsafe { CString::from_raw(s) });
+        }
+    })
+}
+
+/// The last error on this thread, or null if there was none. The pointer
+/// is valid until the next call into this library on the same thread; do
+/// not free it.
+#[unsafe(no_mangle)]
+pub extern "C" fn pi_last_error() -> *const c_char {
+    guard(ptr::null(), || {
+        LAST_ERROR.with(|e| e.borrow().as_ref().map_or(ptr::null(), |s| s.as_ptr()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::save_tree;
+    use crate::tree::TreeNode;
+
+    fn last_error() -> String {
+        let ptr = pi_last_error();
+        assert!(!ptr.is_null());
+        unsafe { CStr::from_ptr(ptr) }
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[test]
+    fn test_ffi_open_and_search() {
+        let dir = tempfile::tempdir().unwrap();
+        let tree_path = dir.path().join("tree.json");
+        let tree = DocumentTree::new("doc", vec![TreeNode::new("Introduction", 1, 2)], 2);
+        save_tree(&tree, &tree_path).unwrap();
+
+        let path = CString::new(tree_path.to_str().unwrap()).unwrap();
+        // Nothing listens on port 9, so searching fails after loading
+        let config =
+            CString::new(r#"{"api_base": "http://127.0.0.1:9", "api_key": "x", "model": "m"}"#)
+                .unwrap();
+        let index = unsafe { pi_index_open(path.as_ptr(), ptr::null(), config.as_ptr()) };
+        assert!(!index.is_null());
+
+        let json = unsafe { pi_index_tree_json(index) };
+        assert!(!json.is_null());
+        let loaded = DocumentTree::from_json(unsafe { CStr::from_ptr(json) }.to_str().unwrap());
+        assert_eq!(loaded.unwrap().nodes[0].title, "Introduction");
+        unsafe { pi_string_free(json) };
+
+        let query = CString::new("introduction").unwrap();
+        let results = unsafe { pi_index_search(index, query.as_ptr(), 3) };
+        assert!(results.is_null());
+        assert!(last_error().contains("HTTP request failed"));
+
+        assert!(unsafe { pi_index_search(index, ptr::null(), 3) }.is_null());
+        assert_eq!(last_error(), "query is null");
+        unsafe { pi_index_free(index) };
+    }
+
+    #[test]
+    fn test_ffi_open_errors() {
+        let missing = CString::new("/nonexistent/tree.json").unwrap();
+        let config = CString::new(r#"{"api_base": "x", "api_key": "x", "model": "m"}"#).unwrap();
+        let index = unsafe { pi_index_open(missing.as_ptr(), ptr::null(), config.as_ptr()) };
+        assert!(index.is_null());
+        assert!(last_error().contains("/nonexistent/tree.json"));
+
+        let bad_config = CString::new("{").unwrap();
+        let index = unsafe { pi_index_open(missing.as_ptr(), ptr::null(), bad_config.as_ptr()) };
+        assert!(index.is_null());
+        assert!(last_error().starts_with("Invalid configuration"));
+
+        assert!(unsafe { pi_index_open(ptr::null(), ptr::null(), ptr::null()) }.is_null());
+        assert_eq!(last_error(), "tree_path is null");
+    }
+
+    #[test]
+    fn test_ffi_panic_is_an_error() {
+        let result = guard(ptr::null_mut::<c_char>(), || panic!("index poisoned"));
+        assert!(result.is_null());
+        assert_eq!(last_error(), "Internal error: panicked: index poisoned");
+
+        let result = guard(ptr::null_mut::<c_char>(), || panic!("{} of {}", 3, 2));
+        assert!(result.is_null());
+        assert!(last_error().ends_with("3 of 2"));
+    }
+}
@@ -0,0 +1,377 @@
+//! Recorded LLM interactions for deterministic tests.
+//!
+//! A [`Fixture`] attached to an [`LlmClient`] either records every request
+//! and response into a JSON file, or replays them from one without touching
+//! the network. Replay matches requests by endpoint and body, so a test
+//! replays correctly as long as the code under test sends the same prompts
+//! it sent while recording.
+//!
+//! ```no_run
+//! use rust_page_indexer::PageIndex;
+//! use rust_page_indexer::fixtures::Fixture;
+//!
+//! # async fn run() -> rust_page_indexer::Result<()> {
+//! // Replays by default; records against the configured API when
+//! // PAGE_INDEXER_RECORD is set.
+//! let fixture = Fixture::from_env("data/fixtures/sample.fixture.json")?;
+//! let mut index = PageIndex::builder().with_client(fixture.client()?).build()?;
+//! index.index_file("data/fixtures/sample.txt").await?;
+//! fixture.save()?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! `data/fixtures` holds a small sample document and its recorded indexing,
+//! search and answer calls, which the end-to-end test below replays.
+
+use crate::config::{Config, LlmConfig};
+use crate::error::{PageIndexError, Result};
+use crate::llm::LlmClient;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Set to record fixtures against the configured API instead of replaying.
+pub const RECORD_ENV: &str = "PAGE_INDEXER_RECORD";
+
+/// Whether a fixture records or replays.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixtureMode {
+    Record,
+    Replay,
+}
+
+/// A fixture file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct FixtureFile {
+    /// Generation settings of the recording client; replay uses the same
+    /// ones so request bodies match.
+    model: String,
+    max_tokens: u32,
+    temperature: f32,
+    interactions: Vec<Interaction>,
+}
+
+/// One request and the response it got.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Interaction {
+    endpoint: String,
+    request: Value,
+    status: u16,
+    /// The response body, as JSON when it parses (a string otherwise).
+    response: Value,
+}
+
+impl Interaction {
+    fn status_and_body(&self) -> (u16, String) {
+        let body = match &self.response {
+            Value::String(body) => body.clone(),
+            response => response.to_string(),
+        };
+        (self.status, body)
+    }
+}
+
+struct State {
+    file: FixtureFile,
+    /// Interactions already replayed, so identical requests get their
+    /// responses in recorded order.
+    used: Vec<bool>,
+}
+
+/// Recorded LLM interactions, shared by every client it is attached to.
+#[derive(Clone)]
+pub struct Fixture {
+    path: PathBuf,
+    mode: FixtureMode,
+    state: Arc<Mutex<State>>,
+}
+
+impl Fixture {
+    /// Start recording into `path` (written by [`save`](Self::save)).
+    pub fn record(path: impl Into<PathBuf>) -> Self {
+        Self::with_file(path.into(), FixtureMode::Record, FixtureFile::default())
+    }
+
+    /// Replay the interactions recorded in `path`.
+    pub fn replay(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let json = std::fs::read_to_string(&path).map_err(|e| PageIndexError::io(&path, e))?;
+        let file: FixtureFile = serde_json::from_str(&json)?;
+        Ok(Self::with_file(path, FixtureMode::Replay, file))
+    }
+
+    /// Record when [`RECORD_ENV`] is set, replay otherwise.
+    pub fn from_env(path: impl Into<PathBuf>) -> Result<Self> {
+        if std::env::var_os(RECORD_ENV).is_some() {
+            Ok(Self::record(path))
+        } else {
+            Self::replay(path)
+        }
+    }
+
+    fn with_file(path: PathBuf, mode: FixtureMode, file: FixtureFile) -> Self {
+        let used = vec![false; file.interactions.len()];
+        Self {
+            path,
+            mode,
+            state: Arc::new(Mutex::new(State { file, used })),
+        }
+    }
+
+    /// The fixture file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether this fixture records or replays.
+    pub fn mode(&self) -> FixtureMode {
+        self.mode
+    }
+
+    /// A client using this fixture. When replaying, it is configured from
+    /// the fixture and never reaches the network; when recording, it uses
+    /// the configuration from the environment and config file.
+    pub fn client(&self) -> Result<LlmClient> {
+        let config = match self.mode {
+            FixtureMode::Replay => {
+                let state = self.lock();
+                LlmConfig {
+                    api_base: "http://fixture.invalid".to_string(),
+                    api_key: String::new(),
+                    model: state.file.model.clone(),
+                    max_tokens: state.file.max_tokens,
+                    temperature: state.file.temperature,
+                    pricing: None,
+                }
+            }
+            FixtureMode::Record => {
+                let config = Config::load()?;
+                config.validate()?;
+                config.llm
+            }
+        };
+        Ok(LlmClient::new(config).with_fixture(self.clone()))
+    }
+
+    /// Write the recorded interactions to the fixture file. Does nothing
+    /// when replaying.
+    pub fn save(&self) -> Result<()> {
+        if self.mode == FixtureMode::Replay {
+            return Ok(());
+        }
+        if let Some(dir) = self.path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(|e| PageIndexError::io(dir, e))?;
+        }
+        let json = serde_json::to_string_pretty(&self.lock().file)?;
+        std::fs::write(&self.path, json + "\n").map_err(|e| PageIndexError::io(&self.path, e))
+    }
+
+    /// Record the generation settings of a client being attached.
+    pub(crate) fn attach(&self, config: &LlmConfig) {
+        if self.mode == FixtureMode::Record {
+            let mut state = self.lock();
+            state.file.model = config.model.clone();
+            state.file.max_tokens = config.max_tokens;
+            state.file.temperature = config.temperature;
+        }
+    }
+
+    /// The recorded status and body for a request.
+    pub(crate) fn replayed(&self, endpoint: &str, request: &Value) -> Result<(u16, String)> {
+        let mut state = self.lock();
+        let State { file, used } = &mut *state;
+        let matching: Vec<usize> = (0..file.interactions.len())
+            .filter(|&i| {
+                let interaction = &file.interactions[i];
+                interaction.endpoint == endpoint && &interaction.request == request
+            })
+            .collect();
+        // Once every match has been replayed, keep returning the last one
+        let Some(&i) = matching.iter().find(|&&i| !used[i]).or(matching.last()) else {
+            return Err(PageIndexError::LlmApi(format!(
+                "No recorded {} response in {} for this request; re-record with {}=1",
+                endpoint,
+                self.path.display(),
+                RECORD_ENV
+            )));
+        };
+        used[i] = true;
+        Ok(file.interactions[i].status_and_body())
+    }
+
+    /// The recorded response for a request, or if none matches, the next
+    /// unreplayed response from the same endpoint in recorded order. Lets a
+    /// run with changed prompts continue (see [`crate::golden`]).
+    pub(crate) fn replayed_loosely(
+        &self,
+        endpoint: &str,
+        request: &Value,
+    ) -> Result<(u16, String)> {
+        if let Ok(replayed) = self.replayed(endpoint, request) {
+            return Ok(replayed);
+        }
+        let mut state = self.lock();
+        let State { file, used } = &mut *state;
+        let next = (0..file.interactions.len())
+            .find(|&i| !used[i] && file.interactions[i].endpoint == endpoint)
+            .ok_or_else(|| {
+                PageIndexError::LlmApi(format!(
+                    "No {} responses left in {}",
+                    endpoint,
+                    self.path.display()
+                ))
+            })?;
+        used[next] = true;
+        Ok(file.interactions[next].status_and_body())
+    }
+
+    /// Add a request and its response to the recording.
+    pub(crate) fn recorded(&self, endpoint: &str, request: Value, status: u16, body: &str) {
+        let response =
+            serde_json::from_str(body).unwrap_or_else(|_| Value::String(body.to_string()));
+        self.lock().file.interactions.push(Interaction {
+            endpoint: endpoint.to_string(),
+            request,
+            status,
+            response,
+        });
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().expect("fixture lock poisoned")
+    }
+}
+
+/// A client whose every LLM call fails, for tests that must not reach one.
+#[cfg(test)]
+pub(crate) fn offline_client() -> LlmClient {
+    // Nothing listens on port 9
+    LlmClient::new(LlmConfig {
+        api_base: "http://127.0.0.1:9".to_string(),
+        api_key: "x".to_string(),
+        ..LlmConfig::default()
+    })
+}
+
+/// A chat interaction answering with `content`, for [`replay_interactions`].
+#[cfg(test)]
+pub(crate) fn chat_reply(content: &str) -> Value {
+    serde_json::json!({
+        "endpoint": "chat",
+        "request": {},
+        "status": 200,
+        "response": {"choices": [{"message": {"role": "assistant", "content": content}}]},
+    })
+}
+
+/// Write a fixture holding `interactions` to `path` and replay it. The
+/// recorded requests are empty, so it is meant to be wrapped in a
+/// [`PromptCapture`](crate::golden::PromptCapture), which replays in order.
+#[cfg(test)]
+pub(crate) fn replay_interactions(path: &Path, interactions: Vec<Value>) -> Fixture {
+    let file = serde_json::json!({
+        "model": "m",
+        "max_tokens": 1024,
+        "temperature": 0.0,
+        "interactions": interactions,
+    });
+    std::fs::write(path, file.to_string()).unwrap();
+    Fixture::replay(path).unwrap()
+}
+
+/// Write a fixture answering chat requests with `replies` in order to
+/// `path` and replay it.
+#[cfg(test)]
+pub(crate) fn replay_replies<S: AsRef<str>>(path: &Path, replies: &[S]) -> Fixture {
+    let interactions = replies.iter().map(|r| chat_reply(r.as_ref())).collect();
+    replay_interactions(path, interactions)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::page_index::PageIndex;
+    use serde_json::json;
+
+    #[test]
+    fn test_record_and_replay() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested/fixture.json");
+        let recording = Fixture::record(&path);
+        recording.attach(&LlmConfig {
+            model: "test-model".to_string(),
+            ..LlmConfig::default()
+        });
+        let request = json!({"messages": [{"role": "user", "content": "hi"}]});
+        recording.recorded("chat", request.clone(), 200, r#"{"first": 1}"#);
+        recording.recorded("chat", request.clone(), 200, r#"{"second": 2}"#);
+        recording.recorded("chat", json!({"other": true}), 500, "upstream down");
+        recording.save().unwrap();
+
+        let replay = Fixture::replay(&path).unwrap();
+        assert_eq!(replay.client().unwrap().model(), "test-model");
+        assert_eq!(
+            replay.replayed("chat", &request).unwrap().1,
+            r#"{"first":1}"#
+        );
+        assert_eq!(
+            replay.replayed("chat", &request).unwrap().1,
+            r#"{"second":2}"#
+        );
+        assert_eq!(
+            replay.replayed("chat", &request).unwrap().1,
+            r#"{"second":2}"#
+        );
+        assert_eq!(
+            replay.replayed("chat", &json!({"other": true})).unwrap(),
+            (500, "upstream down".to_string())
+        );
+        assert!(matches!(
+            replay.replayed("embeddings", &request),
+            Err(PageIndexError::LlmApi(_))
+        ));
+
+        // Unmatched requests get the remaining responses in order
+        let loose = Fixture::replay(&path).unwrap();
+        let changed = json!({"messages": [{"role": "user", "content": "hello"}]});
+        assert_eq!(
+            loose.replayed_loosely("chat", &request).unwrap().1,
+            r#"{"first":1}"#
+        );
+        assert_eq!(
+            loose.replayed_loosely("chat", &changed).unwrap().1,
+            r#"{"second":2}"#
+        );
+        assert_eq!(loose.replayed_loosely("chat", &changed).unwrap().0, 500);
+        assert!(loose.replayed_loosely("chat", &changed).is_err());
+    }
+
+    /// Index, search and answer the sample document end to end. Replays
+    /// `data/fixtures/sample.fixture.json`; run with `PAGE_INDEXER_RECORD=1`
+    /// and an API configured to record it again.
+    #[tokio::test]
+    async fn test_sample_document_end_to_end() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("data/fixtures");
+        let fixture = Fixture::from_env(dir.join("sample.fixture.json")).unwrap();
+        let mut index = PageIndex::builder()
+            .with_client(fixture.client().unwrap())
+            .build()
+            .unwrap();
+
+        let tree = index.index_file(dir.join("sample.txt")).await.unwrap();
+        assert_eq!(tree.node_count(), 6);
+        let section = tree.find_by_title("Cleaning the Filter").unwrap();
+        assert!(section.summary.as_deref().unwrap().contains("weekly soak"));
+
+        let answer = index
+            .ask("How often should the filter be cleaned?")
+            .await
+            .unwrap();
+        assert_eq!(answer.sources[0].title, "Cleaning the Filter");
+        assert!(answer.sources[0].content.is_some());
+        assert!(!answer.answer.is_empty());
+        fixture.save().unwrap();
+    }
+}
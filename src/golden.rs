@@ -0,0 +1,258 @@
+//! Golden snapshots of the prompts a run constructs.
+//!
+//! A [`PromptCapture`] attached to a client records every request it builds
+//! and answers it from a recorded [`Fixture`] instead of sending it. Saved
+//! next to the fixture, the snapshot turns a prompt change into a reviewable
+//! diff in tests before it changes the behaviour of a real (and expensive)
+//! run:
+//!
+//! ```no_run
+//! use rust_page_indexer::PageIndex;
+//! use rust_page_indexer::fixtures::Fixture;
+//! use rust_page_indexer::golden::{PromptCapture, UPDATE_ENV};
+//!
+//! # async fn run() -> rust_page_indexer::Result<()> {
+//! let capture = PromptCapture::new(Fixture::replay("data/fixtures/sample.fixture.json")?);
+//! let mut index = PageIndex::builder().with_client(capture.client()?).build()?;
+//! index.index_file("data/fixtures/sample.txt").await?;
+//!
+//! if std::env::var_os(UPDATE_ENV).is_some() {
+//!     capture.save("data/fixtures/sample.prompts.json")?;
+//! } else {
+//!     let diff = capture.diff("data/fixtures/sample.prompts.json")?;
+//!     assert!(diff.is_empty(), "{}", diff);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Requests that no longer match a recorded one are answered with the
+//! fixture's next unused response, so a run with changed prompts still
+//! completes and every later prompt shows up in the diff.
+
+use crate::error::{PageIndexError, Result};
+use crate::fixtures::Fixture;
+use crate::llm::LlmClient;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// Set to write snapshots instead of comparing against them.
+pub const UPDATE_ENV: &str = "PAGE_INDEXER_UPDATE_PROMPTS";
+
+/// One request as the client built it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CapturedPrompt {
+    pub endpoint: String,
+    /// [`prompt_hash`] of the request.
+    pub hash: String,
+    pub request: Value,
+}
+
+/// A saved snapshot.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SnapshotFile {
+    prompts: Vec<CapturedPrompt>,
+}
+
+/// Stable hash of a request body (64-bit FNV-1a of its JSON, in hex).
+pub fn prompt_hash(request: &Value) -> String {
+    let hash = request
+        .to_string()
+        .bytes()
+        .fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        });
+    format!("{:016x}", hash)
+}
+
+/// Records the requests of every client it is attached to.
+#[derive(Clone)]
+pub struct PromptCapture {
+    responses: Fixture,
+    prompts: Arc<Mutex<Vec<CapturedPrompt>>>,
+}
+
+impl PromptCapture {
+    /// Capture requests, answering them from `responses`.
+    pub fn new(responses: Fixture) -> Self {
+        Self {
+            responses,
+            prompts: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// A client that captures its requests and never sends them, with the
+    /// generation settings of the fixture.
+    pub fn client(&self) -> Result<LlmClient> {
+        Ok(self.responses.client()?.with_prompt_capture(self.clone()))
+    }
+
+    /// Record a request and return the response to play for it.
+    pub(crate) fn captured(&self, endpoint: &str, request: Value) -> Result<(u16, String)> {
+        let response = self.responses.replayed_loosely(endpoint, &request);
+        self.prompts
+            .lock()
+            .expect("capture lock poisoned")
+            .push(CapturedPrompt {
+                endpoint: endpoint.to_string(),
+                hash: prompt_hash(&request),
+                request,
+            });
+        response
+    }
+
+    /// Captured requests, sorted by endpoint and body so concurrent calls
+    /// snapshot in the same order every run.
+    pub fn prompts(&self) -> Vec<CapturedPrompt> {
+        let mut prompts = self.prompts.lock().expect("capture lock poisoned").clone();
+        prompts.sort_by_cached_key(|p| (p.endpoint.clone(), p.request.to_string()));
+        prompts
+    }
+
+    /// Write the captured requests to a snapshot file.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        let snapshot = SnapshotFile {
+            prompts: self.prompts(),
+        };
+        let json = serde_json::to_string_pretty(&snapshot)?;
+        std::fs::write(path, json + "\n").map_err(|e| PageIndexError::io(path, e))
+    }
+
+    /// Compare the captured requests with a saved snapshot.
+    pub fn diff(&self, path: impl AsRef<Path>) -> Result<PromptDiff> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path).map_err(|e| PageIndexError::io(path, e))?;
+        let expected: SnapshotFile = serde_json::from_str(&json)?;
+        let actual = self.prompts();
+
+        let missing_from = |prompts: &[CapturedPrompt], others: &[CapturedPrompt]| {
+            let mut others: Vec<&str> = others.iter().map(|p| p.hash.as_str()).collect();
+            prompts
+                .iter()
+                .filter(|p| match others.iter().position(|h| *h == p.hash) {
+                    Some(i) => {
+                        others.swap_remove(i);
+                        false
+                    }
+                    None => true,
+                })
+                .cloned()
+                .collect()
+        };
+        Ok(PromptDiff {
+            added: missing_from(&actual, &expected.prompts),
+            removed: missing_from(&expected.prompts, &actual),
+        })
+    }
+}
+
+/// Prompts a run built that the snapshot doesn't have, and the other way
+/// round.
+#[derive(Debug, Clone, Default)]
+pub struct PromptDiff {
+    pub added: Vec<CapturedPrompt>,
+    pub removed: Vec<CapturedPrompt>,
+}
+
+impl PromptDiff {
+    /// Whether the run built exactly the snapshot's prompts.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+impl fmt::Display for PromptDiff {
+    /// One line per changed prompt: `+`/`-`, endpoint, hash and the start
+    /// of its last message.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} prompt(s) added, {} removed (review, then re-run with {}=1):",
+            self.added.len(),
+            self.removed.len(),
+            UPDATE_ENV
+        )?;
+        let lines = self
+            .removed
+            .iter()
+            .map(|p| ('-', p))
+            .chain(self.added.iter().map(|p| ('+', p)));
+        for (sign, prompt) in lines {
+            let last = prompt.request["messages"]
+                .as_array()
+                .and_then(|m| m.last())
+                .and_then(|m| m["content"].as_str())
+                .unwrap_or_default();
+            let preview: String = last.chars().take(60).collect();
+            writeln!(
+                f,
+                "{} {} {} {:?}",
+                sign, prompt.endpoint, prompt.hash, preview
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::page_index::PageIndex;
+    use serde_json::json;
+
+    #[test]
+    fn test_prompt_hash() {
+        let request = json!({"messages": [{"role": "user", "content": "hi"}]});
+        assert_eq!(prompt_hash(&request), prompt_hash(&request.clone()));
+        assert_eq!(prompt_hash(&request).len(), 16);
+        assert_ne!(prompt_hash(&request), prompt_hash(&json!({"messages": []})));
+    }
+
+    /// The prompts sent while indexing and querying the sample document.
+    /// Run with `PAGE_INDEXER_UPDATE_PROMPTS=1` to accept prompt changes.
+    #[tokio::test]
+    async fn test_sample_document_prompts() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("data/fixtures");
+        let fixture = Fixture::replay(dir.join("sample.fixture.json")).unwrap();
+        let capture = PromptCapture::new(fixture);
+        let mut index = PageIndex::builder()
+            .with_client(capture.client().unwrap())
+            .build()
+            .unwrap();
+        index.index_file(dir.join("sample.txt")).await.unwrap();
+        index
+            .ask("How often should the filter be cleaned?")
+            .await
+            .unwrap();
+
+        let snapshot = dir.join("sample.prompts.json");
+        if std::env::var_os(UPDATE_ENV).is_some() {
+            capture.save(&snapshot).unwrap();
+        }
+        let diff = capture.diff(&snapshot).unwrap();
+        assert!(diff.is_empty(), "{}", diff);
+
+        // A changed prompt shows up as one removed and one added
+        let changed = PromptCapture::new(Fixture::replay(dir.join("sample.fixture.json")).unwrap());
+        let prompts = capture.prompts();
+        for prompt in &prompts[1..] {
+            changed
+                .captured(&prompt.endpoint, prompt.request.clone())
+                .unwrap();
+        }
+        changed
+            .captured(
+                "chat",
+                json!({"messages": [{"role": "user", "content": "New prompt"}]}),
+            )
+            .unwrap();
+        let diff = changed.diff(&snapshot).unwrap();
+        assert_eq!((diff.added.len(), diff.removed.len()), (1, 1));
+        assert_eq!(diff.removed[0], prompts[0]);
+        assert!(diff.to_string().contains("+ chat"), "{}", diff);
+    }
+}
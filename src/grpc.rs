@@ -0,0 +1,369 @@
+//! gRPC service (`grpc` feature).
+//!
+//! Implements `page_indexer.v1.PageIndexer` from `proto/page_indexer.proto`
+//! over a set of named indexes: `Index` builds one from document text,
+//! `Search` and `Ask` query one, and `StreamAsk` streams an answer's
+//! progress. Indexes can be preloaded from saved trees, and with an index
+//! directory, indexes built over RPC are saved there too.
+
+use crate::cache::AnswerCache;
+use crate::document::Document;
+use crate::error::{PageIndexError, Result};
+use crate::llm::LlmClient;
+use crate::page_index::PageIndex;
+use crate::persistence::index_files;
+use crate::search::{Relevance, SearchOptions, SearchResult};
+use crate::telemetry;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::sync::{RwLock, mpsc};
+use tokio_stream::Stream;
+use tokio_stream::wrappers::ReceiverStream;
+use tonic::{Request, Response, Status};
+use tracing::{info, instrument, warn};
+
+/// Generated protobuf types, the service trait and a client
+/// (`proto::page_indexer_client::PageIndexerClient`) for Rust callers.
+#[allow(clippy::all)]
+pub mod proto {
+    tonic::include_proto!("page_indexer.v1");
+}
+
+use proto::page_indexer_server::{PageIndexer, PageIndexerServer};
+use proto::{
+    AskEvent, AskRequest, AskResponse, IndexRequest, IndexResponse, SearchRequest, SearchResponse,
+    SearchStarted, Section, ask_event,
+};
+
+/// The indexes being served.
+pub struct GrpcService {
+    client: LlmClient,
+    indexes: RwLock<HashMap<String, Arc<PageIndex>>>,
+    index_dir: Option<PathBuf>,
+    answers: AnswerCache,
+}
+
+impl GrpcService {
+    /// Serve no indexes yet, building new ones with the given client.
+    pub fn new(client: LlmClient) -> Self {
+        Self {
+            client,
+            indexes: RwLock::new(HashMap::new()),
+            index_dir: None,
+            answers: AnswerCache::default(),
+        }
+    }
+
+    /// Set the answer cache shared by the served indexes (by default 256
+    /// answers for an hour).
+    pub fn with_answer_cache(mut self, cache: AnswerCache) -> Self {
+        self.answers = cache;
+        self
+    }
+
+    /// Save indexes built over RPC to `dir` as `<name>.json`, with their
+    /// text as `<name>.txt`.
+    pub fn with_index_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.index_dir = Some(dir.into());
+        self
+    }
+
+    /// Serve a saved tree under its file stem. A `.txt` file with the same
+    /// stem next to it is loaded as the source document.
+    pub fn load(&mut self, path: &Path) -> Result<String> {
+        let name = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("untitled")
+            .to_string();
+        let mut index = self.new_index()?;
+        index.load(path)?;
+        let text = path.with_extension("txt");
+        if text.exists() {
+            index.load_document(&text)?;
+        }
+        self.indexes.get_mut().insert(name.clone(), Arc::new(index));
+        Ok(name)
+    }
+
+    /// Serve every `.json`/`.bin`/`.bincode` tree in the index directory.
+    pub fn load_index_dir(&mut self) -> Result<Vec<String>> {
+        let Some(dir) = self.index_dir.clone() else {
+            return Ok(Vec::new());
+        };
+        if !dir.exists() {
+            return Ok(Vec::new());
+        }
+        index_files(&dir)?
+            .iter()
+            .map(|path| self.load(path))
+            .collect()
+    }
+
+    fn new_index(&self) -> Result<PageIndex> {
+        PageIndex::builder()
+            .with_client(self.client.clone())
+            .with_answer_cache(self.answers.clone())
+            .build()
+    }
+
+    async fn get(&self, name: &str) -> std::result::Result<Arc<PageIndex>, Status> {
+        self.indexes
+            .read()
+            .await
+            .get(name)
+            .cloned()
+            .ok_or_else(|| Status::not_found(format!("No index named '{}'", name)))
+    }
+
+    fn save(&self, name: &str, index: &PageIndex, content: &str) -> Result<()> {
+        let Some(dir) = &self.index_dir else {
+            return Ok(());
+        };
+        std::fs::create_dir_all(dir).map_err(|e| PageIndexError::io(dir, e))?;
+        index.save(dir.join(format!("{}.json", name)))?;
+        let text = dir.join(format!("{}.txt", name));
+        std::fs::write(&text, content).map_err(|e| PageIndexError::io(&text, e))
+    }
+}
+
+/// Index names become file names, so keep them to a safe alphabet.
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+}
+
+fn search_options(top_k: u32) -> SearchOptions {
+    let mut options = SearchOptions::default();
+    if top_k > 0 {
+        options.top_k = top_k as usize;
+    }
+    options
+}
+
+/// LLM and network failures are upstream failures; the rest are ours.
+fn status(operation: &'static str, err: PageIndexError) -> Status {
+    telemetry::record_error(operation, &err);
+    warn!(operation, error = %err, "request failed");
+    match err {
+        PageIndexError::RateLimited { .. } => Status::resource_exhausted(err.to_string()),
+        PageIndexError::Timeout(_) => Status::deadline_exceeded(err.to_string()),
+        PageIndexError::LlmApi(_)
+        | PageIndexError::AuthFailed(_)
+        | PageIndexError::ServerError { .. }
+        | PageIndexError::Http(_) => Status::unavailable(err.to_string()),
+        _ => Status::internal(err.to_string()),
+    }
+}
+
+fn section(result: SearchResult) -> Section {
+    let relevance = match result.relevance {
+        Relevance::High => proto::Relevance::High,
+        Relevance::Medium => proto::Relevance::Medium,
+        Relevance::Low => proto::Relevance::Low,
+    };
+    Section {
+        title: result.title,
+        start_index: result.start_index as u32,
+        end_index: result.end_index as u32,
+        relevance: relevance.into(),
+        reason: result.reason,
+        content: result.content,
+    }
+}
+
+fn sections(results: Vec<SearchResult>) -> Vec<Section> {
+    results.into_iter().map(section).collect()
+}
+
+type AskStream = Pin<Box<dyn Stream<Item = std::result::Result<AskEvent, Status>> + Send>>;
+
+#[tonic::async_trait]
+impl PageIndexer for GrpcService {
+    #[instrument(name = "grpc.index", skip_all)]
+    async fn index(
+        &self,
+        request: Request<IndexRequest>,
+    ) -> std::result::Result<Response<IndexResponse>, Status> {
+        let IndexRequest { name, content } = request.into_inner();
+        if !valid_name(&name) {
+            return Err(Status::invalid_argument(format!(
+                "Invalid index name '{}' (use letters, digits, '-', '_' and '.')",
+                name
+            )));
+        }
+        if content.trim().is_empty() {
+            return Err(Status::invalid_argument("Document content is empty"));
+        }
+        info!(name = %name, chars = content.len(), "indexing");
+
+        // Build outside the lock so searches on other indexes continue
+        let mut index = self.new_index().map_err(|e| status("grpc.index", e))?;
+        let document = Document::from_text(&name, content.clone());
+        let tree = index
+            .index_document(document)
+            .await
+            .map_err(|e| status("grpc.index", e))?;
+        let response = IndexResponse {
+            name: name.clone(),
+            node_count: tree.node_count() as u32,
+            total_pages: tree.total_pages as u32,
+            tree_json: tree
+                .to_json()
+                .map_err(|e| Status::internal(e.to_string()))?,
+        };
+        self.save(&name, &index, &content)
+            .map_err(|e| status("grpc.index", e))?;
+        self.indexes.write().await.insert(name, Arc::new(index));
+        Ok(Response::new(response))
+    }
+
+    #[instrument(name = "grpc.search", skip_all)]
+    async fn search(
+        &self,
+        request: Request<SearchRequest>,
+    ) -> std::result::Result<Response<SearchResponse>, Status> {
+        let request = request.into_inner();
+        let index = self.get(&request.index).await?;
+        let results = index
+            .search_with(&request.query, &search_options(request.top_k))
+            .await
+            .map_err(|e| status("grpc.search", e))?;
+        Ok(Response::new(SearchResponse {
+            results: sections(results),
+        }))
+    }
+
+    #[instrument(name = "grpc.ask", skip_all)]
+    async fn ask(
+        &self,
+        request: Request<AskRequest>,
+    ) -> std::result::Result<Response<AskResponse>, Status> {
+        let request = request.into_inner();
+        let index = self.get(&request.index).await?;
+        let sources = index
+            .search_with(&request.question, &search_options(request.top_k))
+            .await
+            .map_err(|e| status("grpc.ask", e))?;
+        let answer = index
+            .answer(&request.question, sources)
+            .await
+            .map_err(|e| status("grpc.ask", e))?;
+        Ok(Response::new(AskResponse {
+            answer: answer.answer,
+            sources: sections(answer.sources),
+        }))
+    }
+
+    type StreamAskStream = AskStream;
+
+    async fn stream_ask(
+        &self,
+        request: Request<AskRequest>,
+    ) -> std::result::Result<Response<Self::StreamAskStream>, Status> {
+        let request = request.into_inner();
+        let index = self.get(&request.index).await?;
+        let (tx, rx) = mpsc::channel(4);
+
+        tokio::spawn(async move {
+            let event = |event| Ok(AskEvent { event: Some(event) });
+            let nodes = index.tree().map_or(0, |t| t.node_count()) as u32;
+            let started = ask_event::Event::SearchStarted(SearchStarted { nodes });
+            if tx.send(event(started)).await.is_err() {
+                return;
+            }
+
+            let options = search_options(request.top_k);
+            let sources = match index.search_with(&request.question, &options).await {
+                Ok(sources) => sources,
+                Err(e) => {
+                    let _ = tx.send(Err(status("grpc.stream_ask", e))).await;
+                    return;
+                }
+            };
+            let found = ask_event::Event::Sources(SearchResponse {
+                results: sections(sources.clone()),
+            });
+            if tx.send(event(found)).await.is_err() {
+                return;
+            }
+
+            let message = match index.answer(&request.question, sources).await {
+                Ok(answer) => event(ask_event::Event::Answer(answer.answer)),
+                Err(e) => Err(status("grpc.stream_ask", e)),
+            };
+            let _ = tx.send(message).await;
+        });
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(rx))))
+    }
+}
+
+/// Serve until Ctrl-C.
+pub async fn serve(addr: SocketAddr, service: GrpcService) -> Result<()> {
+    info!(%addr, "serving gRPC");
+    tonic::transport::Server::builder()
+        .add_service(PageIndexerServer::new(service))
+        .serve_with_shutdown(addr, async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await
+        .map_err(|e| PageIndexError::Server(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::offline_client;
+    use crate::persistence::save_tree;
+    use crate::tree::{DocumentTree, TreeNode};
+
+    #[test]
+    fn test_valid_name() {
+        assert!(valid_name("annual-report_2024.v2"));
+        assert!(!valid_name(""));
+        assert!(!valid_name(".hidden"));
+        assert!(!valid_name("../etc"));
+        assert!(!valid_name("a/b"));
+    }
+
+    #[tokio::test]
+    async fn test_requests() {
+        let dir = tempfile::tempdir().unwrap();
+        let tree = DocumentTree::new("doc", vec![TreeNode::new("Introduction", 1, 1)], 1);
+        save_tree(&tree, &dir.path().join("report.json")).unwrap();
+        std::fs::write(dir.path().join("report.txt"), "Intro text.").unwrap();
+
+        let mut service = GrpcService::new(offline_client()).with_index_dir(dir.path());
+        assert_eq!(service.load_index_dir().unwrap(), vec!["report"]);
+        let index = service.get("report").await.unwrap();
+        assert!(index.document().is_some());
+
+        let search = |index: &str| {
+            Request::new(SearchRequest {
+                index: index.to_string(),
+                query: "intro".to_string(),
+                top_k: 0,
+            })
+        };
+        let err = service.search(search("missing")).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::NotFound);
+        let err = service.search(search("report")).await.unwrap_err();
+        assert_eq!(err.code(), tonic::Code::Unavailable);
+
+        let err = service
+            .index(Request::new(IndexRequest {
+                name: "../x".to_string(),
+                content: "text".to_string(),
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code(), tonic::Code::InvalidArgument);
+    }
+}
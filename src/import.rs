@@ -0,0 +1,848 @@
+//! Trees from existing structure, without LLM calls.
+//!
+//! Many documents already carry a trustworthy table of contents: PDF
+//! bookmarks, an EPUB navigation document, DocBook sections, or an outline
+//! written by hand. [`import_structure`] turns one into a [`DocumentTree`]
+//! directly, skipping structure extraction entirely. Supported sources
+//! ([`StructureFormat`]):
+//!
+//! - **YAML outlines**: nested `sections` with a `title` and a `page` (or
+//!   `start` and `end`) each;
+//! - **PDF bookmarks**, as dumped by `pdftk doc.pdf dump_data` (or
+//!   `qpdf`/`pdftk` compatible tools): `BookmarkTitle`, `BookmarkLevel` and
+//!   `BookmarkPageNumber` records;
+//! - **EPUB navigation**: the `toc` nav of an EPUB 3 `nav.xhtml`, or an
+//!   EPUB 2 `toc.ncx`;
+//! - **DocBook**: the titles of `part`, `chapter`, `section`, `sect1`...
+//!   elements.
+//!
+//! EPUB and DocBook sources link sections rather than pages, so their
+//! sections are placed by finding each title in the document's pages, in
+//! order. Sections end where the next section at the same or a higher
+//! level starts.
+//!
+//! ```no_run
+//! use rust_page_indexer::import::import_structure;
+//! use rust_page_indexer::{Document, save_tree};
+//! use std::path::Path;
+//!
+//! # fn run() -> rust_page_indexer::Result<()> {
+//! let document = Document::from_text_file(Path::new("book.txt"))?;
+//! let tree = import_structure(Path::new("toc.ncx"), None, Some(&document))?;
+//! save_tree(&tree, Path::new("data/book.json"))?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::document::Document;
+use crate::error::{PageIndexError, Result};
+use crate::indexer::TreeIndexer;
+use crate::tree::{DocumentTree, SourceInfo, TreeNode};
+use serde::Deserialize;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Kind of structure file imported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StructureFormat {
+    /// Hand-written YAML (or JSON) outline.
+    Yaml,
+    /// PDF bookmarks from `pdftk dump_data`.
+    PdfBookmarks,
+    /// EPUB 3 navigation document (`nav.xhtml`).
+    EpubNav,
+    /// EPUB 2 navigation control file (`toc.ncx`).
+    Ncx,
+    /// DocBook XML.
+    DocBook,
+}
+
+impl StructureFormat {
+    /// Guess the format of a structure file from its extension and content.
+    pub fn detect(path: &Path, content: &str) -> Option<Self> {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_lowercase());
+        match extension.as_deref() {
+            Some("yaml" | "yml" | "json") => return Some(StructureFormat::Yaml),
+            Some("ncx") => return Some(StructureFormat::Ncx),
+            _ => {}
+        }
+        if content.contains("BookmarkTitle:") {
+            Some(StructureFormat::PdfBookmarks)
+        } else if content.contains("<navMap") {
+            Some(StructureFormat::Ncx)
+        } else if content.contains("<nav") {
+            Some(StructureFormat::EpubNav)
+        } else if ["<book", "<article", "<chapter", "docbook"]
+            .iter()
+            .any(|marker| content.contains(marker))
+        {
+            Some(StructureFormat::DocBook)
+        } else {
+            None
+        }
+    }
+}
+
+impl FromStr for StructureFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "yaml" | "yml" | "outline" => Ok(StructureFormat::Yaml),
+            "pdf" | "bookmarks" | "pdftk" => Ok(StructureFormat::PdfBookmarks),
+            "epub" | "nav" => Ok(StructureFormat::EpubNav),
+            "ncx" => Ok(StructureFormat::Ncx),
+            "docbook" => Ok(StructureFormat::DocBook),
+            other => Err(format!(
+                "unknown structure format '{}' (expected yaml, pdf, epub, ncx or docbook)",
+                other
+            )),
+        }
+    }
+}
+
+/// One section of an imported outline, before the tree is built.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutlineEntry {
+    pub title: String,
+    /// Nesting level, 1 for top-level sections.
+    pub level: usize,
+    /// First page, when the source gives one.
+    pub page: Option<usize>,
+    /// Last page, when the source gives one.
+    pub end: Option<usize>,
+}
+
+/// An outline parsed from a structure file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Outline {
+    /// Document name, when the source gives one.
+    pub name: Option<String>,
+    /// Page count, when the source gives one.
+    pub total_pages: Option<usize>,
+    /// Sections in document order.
+    pub entries: Vec<OutlineEntry>,
+}
+
+#[derive(Deserialize)]
+struct YamlOutline {
+    #[serde(default)]
+    name: Option<String>,
+    #[serde(default)]
+    pages: Option<usize>,
+    #[serde(default)]
+    sections: Vec<YamlSection>,
+}
+
+#[derive(Deserialize)]
+struct YamlSection {
+    title: String,
+    #[serde(default, alias = "start")]
+    page: Option<usize>,
+    #[serde(default)]
+    end: Option<usize>,
+    #[serde(default)]
+    sections: Vec<YamlSection>,
+}
+
+impl Outline {
+    /// Parse a structure file's content.
+    pub fn parse(content: &str, format: StructureFormat) -> Result<Self> {
+        let outline = match format {
+            StructureFormat::Yaml => Self::from_yaml(content)?,
+            StructureFormat::PdfBookmarks => Self::from_pdf_bookmarks(content),
+            StructureFormat::EpubNav => Self::from_epub_nav(content),
+            StructureFormat::Ncx => Self::from_ncx(content),
+            StructureFormat::DocBook => Self::from_docbook(content),
+        };
+        if outline.entries.is_empty() {
+            return Err(PageIndexError::TreeError(
+                "structure file has no sections".to_string(),
+            ));
+        }
+        Ok(outline)
+    }
+
+    fn from_yaml(content: &str) -> Result<Self> {
+        fn flatten(sections: Vec<YamlSection>, level: usize, entries: &mut Vec<OutlineEntry>) {
+            for section in sections {
+                entries.push(OutlineEntry {
+                    title: section.title,
+                    level,
+                    page: section.page,
+                    end: section.end,
+                });
+                flatten(section.sections, level + 1, entries);
+            }
+        }
+
+        let outline: YamlOutline = serde_yaml::from_str(content)
+            .map_err(|e| PageIndexError::Serialization(format!("invalid outline: {}", e)))?;
+        let mut entries = Vec::new();
+        flatten(outline.sections, 1, &mut entries);
+        Ok(Self {
+            name: outline.name,
+            total_pages: outline.pages,
+            entries,
+        })
+    }
+
+    fn from_pdf_bookmarks(content: &str) -> Self {
+        let mut outline = Self::default();
+        let mut title: Option<String> = None;
+        let mut level = 1;
+        for line in content.lines() {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim() {
+                "NumberOfPages" => outline.total_pages = value.parse().ok(),
+                "BookmarkTitle" => title = Some(decode_entities(value)),
+                "BookmarkLevel" => level = value.parse().unwrap_or(1),
+                "BookmarkPageNumber" => {
+                    if let Some(title) = title.take() {
+                        outline.entries.push(OutlineEntry {
+                            title,
+                            level: level.max(1),
+                            // Bookmarks to no page (0) fall back to placement
+                            page: value.parse().ok().filter(|&p| p > 0),
+                            end: None,
+                        });
+                    }
+                    level = 1;
+                }
+                _ => {}
+            }
+        }
+        outline
+    }
+
+    fn from_epub_nav(content: &str) -> Self {
+        let events = xml_events(content);
+        // The nav marked as the table of contents, or the first one
+        let in_toc = |attrs: &str| attribute(attrs, "epub:type").is_some_and(|t| t == "toc");
+        let start = events
+            .iter()
+            .position(
+                |e| matches!(e, XmlEvent::Start(name, attrs) if name == "nav" && in_toc(attrs)),
+            )
+            .or_else(|| {
+                events
+                    .iter()
+                    .position(|e| matches!(e, XmlEvent::Start(name, _) if name == "nav"))
+            });
+
+        let mut outline = Self::default();
+        let Some(start) = start else {
+            return outline;
+        };
+        let mut depth = 0;
+        // Whether each open list item already has its label
+        let mut items: Vec<bool> = Vec::new();
+        // The label being read: its element, elements of the same name
+        // open inside it, and its text
+        let mut label: Option<(String, usize, String)> = None;
+        for event in &events[start + 1..] {
+            match event {
+                XmlEvent::Start(name, _) if name == "ol" => depth += 1,
+                XmlEvent::End(name) if name == "ol" => depth -= 1,
+                XmlEvent::Start(name, _) if name == "li" => items.push(false),
+                XmlEvent::End(name) if name == "li" => {
+                    items.pop();
+                }
+                XmlEvent::Start(name, _) => match &mut label {
+                    Some((element, nested, _)) if element == name => *nested += 1,
+                    Some(_) => {}
+                    None if (name == "a" || name == "span") && items.last() == Some(&false) => {
+                        label = Some((name.clone(), 0, String::new()));
+                    }
+                    None => {}
+                },
+                XmlEvent::Text(text) => {
+                    if let Some((_, _, label)) = &mut label {
+                        label.push_str(text);
+                    }
+                }
+                XmlEvent::End(name) if label.as_ref().is_some_and(|(e, _, _)| e == name) => {
+                    if let Some((_, nested, _)) = &mut label
+                        && *nested > 0
+                    {
+                        *nested -= 1;
+                        continue;
+                    }
+                    if let Some(title) = label.take().map(|(_, _, l)| normalize(&l))
+                        && !title.is_empty()
+                        && let Some(labeled) = items.last_mut()
+                    {
+                        *labeled = true;
+                        outline.entries.push(OutlineEntry {
+                            title,
+                            level: depth.max(1),
+                            page: None,
+                            end: None,
+                        });
+                    }
+                }
+                XmlEvent::End(name) if name == "nav" => break,
+                _ => {}
+            }
+        }
+        outline
+    }
+
+    fn from_ncx(content: &str) -> Self {
+        let mut outline = Self::default();
+        let mut depth = 0;
+        let mut in_label = false;
+        let mut label: Option<String> = None;
+        for event in xml_events(content) {
+            match event {
+                XmlEvent::Start(name, _) if name == "docTitle" => in_label = true,
+                XmlEvent::End(name) if name == "docTitle" => in_label = false,
+                XmlEvent::Start(name, _) if name == "navPoint" => depth += 1,
+                XmlEvent::End(name) if name == "navPoint" => depth -= 1,
+                XmlEvent::Start(name, _) if name == "navLabel" && depth > 0 => in_label = true,
+                XmlEvent::End(name) if name == "navLabel" => in_label = false,
+                XmlEvent::Start(name, _) if name == "text" && in_label => {
+                    label = Some(String::new());
+                }
+                XmlEvent::Text(text) => {
+                    if let Some(label) = &mut label {
+                        label.push_str(&text);
+                    }
+                }
+                XmlEvent::End(name) if name == "text" => {
+                    let Some(title) = label.take().map(|l| normalize(&l)) else {
+                        continue;
+                    };
+                    if title.is_empty() {
+                        continue;
+                    }
+                    if depth == 0 {
+                        outline.name.get_or_insert(title);
+                    } else {
+                        outline.entries.push(OutlineEntry {
+                            title,
+                            level: depth,
+                            page: None,
+                            end: None,
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+        outline
+    }
+
+    fn from_docbook(content: &str) -> Self {
+        const SECTIONS: &[&str] = &[
+            "part",
+            "chapter",
+            "appendix",
+            "preface",
+            "section",
+            "sect1",
+            "sect2",
+            "sect3",
+            "sect4",
+            "sect5",
+            "simplesect",
+        ];
+
+        let mut outline = Self::default();
+        // Open elements, and for sections whether their title was seen
+        let mut open: Vec<(String, bool)> = Vec::new();
+        let mut label: Option<String> = None;
+        for event in xml_events(content) {
+            match event {
+                XmlEvent::Start(name, _) if name == "title" => {
+                    // A section's own title is its child, or in its <info>
+                    let owner = match open.as_slice() {
+                        [.., (section, false), (info, _)] if info == "info" => Some(section),
+                        [.., (section, false)] => Some(section),
+                        _ => None,
+                    };
+                    let is_book = owner.is_some_and(|o| o == "book" || o == "article");
+                    if owner.is_some_and(|o| SECTIONS.contains(&o.as_str()) || is_book) {
+                        label = Some(String::new());
+                    }
+                    open.push((name, true));
+                }
+                XmlEvent::Start(name, _) => open.push((name, false)),
+                XmlEvent::Text(text) => {
+                    if let Some(label) = &mut label {
+                        label.push_str(&text);
+                    }
+                }
+                XmlEvent::End(name) => {
+                    if name == "title"
+                        && let Some(title) = label.take().map(|l| normalize(&l))
+                    {
+                        let owner = open.len().checked_sub(2).and_then(|i| {
+                            if open[i].0 == "info" {
+                                i.checked_sub(1)
+                            } else {
+                                Some(i)
+                            }
+                        });
+                        if let Some(owner) = owner {
+                            open[owner].1 = true;
+                            let element = &open[owner].0;
+                            if element == "book" || element == "article" {
+                                outline.name.get_or_insert(title);
+                            } else if !title.is_empty() {
+                                let level = open[..=owner]
+                                    .iter()
+                                    .filter(|(e, _)| SECTIONS.contains(&e.as_str()))
+                                    .count();
+                                outline.entries.push(OutlineEntry {
+                                    title,
+                                    level,
+                                    page: None,
+                                    end: None,
+                                });
+                            }
+                        }
+                    }
+                    if let Some(i) = open.iter().rposition(|(e, _)| *e == name) {
+                        open.truncate(i);
+                    }
+                }
+            }
+        }
+        outline
+    }
+
+    /// Give entries without a page one, by finding their titles in the
+    /// document's pages: each title is looked for from the page the
+    /// previous section starts on, and a title that can't be found starts
+    /// on that page too.
+    pub fn place(&mut self, document: &Document) {
+        let pages: Vec<(usize, String)> = document
+            .pages
+            .iter()
+            .map(|p| (p.number, normalize(&p.content).to_lowercase()))
+            .collect();
+        let mut from = 0;
+        for entry in &mut self.entries {
+            if let Some(page) = entry.page {
+                from = pages.iter().position(|(n, _)| *n >= page).unwrap_or(from);
+                continue;
+            }
+            let title = normalize(&entry.title).to_lowercase();
+            if let Some(offset) = pages[from..].iter().position(|(_, c)| c.contains(&title)) {
+                from += offset;
+            }
+            entry.page = pages.get(from).map(|(n, _)| *n);
+        }
+    }
+
+    /// Build the tree. Every entry needs a page (see [`Outline::place`]).
+    /// Levels deeper than one below their parent are pulled up, and
+    /// sections end where the next section at their level or above starts,
+    /// unless the outline says otherwise.
+    pub fn into_tree(self, name: &str, total_pages: Option<usize>) -> Result<DocumentTree> {
+        let mut levels = Vec::with_capacity(self.entries.len());
+        for entry in &self.entries {
+            let parent = levels.last().copied().unwrap_or(0);
+            levels.push(entry.level.clamp(1, parent + 1));
+        }
+        let mut starts = Vec::with_capacity(self.entries.len());
+        for entry in &self.entries {
+            let page = entry.page.ok_or_else(|| {
+                PageIndexError::TreeError(format!(
+                    "section '{}' has no page; pass the document to place it",
+                    entry.title
+                ))
+            })?;
+            // Sections never start before the one ahead of them
+            starts.push(page.max(starts.last().copied().unwrap_or(1)));
+        }
+        let last_start = starts.last().copied().unwrap_or(1);
+        let total_pages = total_pages
+            .or(self.total_pages)
+            .unwrap_or(last_start)
+            .max(last_start);
+
+        // Stack of open sections with their levels
+        let mut open: Vec<(usize, TreeNode)> = Vec::new();
+        let mut roots = Vec::new();
+        let mut counters: Vec<usize> = Vec::new();
+        for (i, entry) in self.entries.into_iter().enumerate() {
+            let level = levels[i];
+            let next = (i + 1..levels.len()).find(|&j| levels[j] <= level);
+            let end = entry
+                .end
+                .unwrap_or_else(|| next.map_or(total_pages, |j| starts[j].saturating_sub(1)))
+                .max(starts[i]);
+
+            counters.truncate(level);
+            counters.resize(level, 0);
+            counters[level - 1] += 1;
+            let structure = counters
+                .iter()
+                .map(|c| c.to_string())
+                .collect::<Vec<_>>()
+                .join(".");
+
+            while open.last().is_some_and(|(l, _)| *l >= level) {
+                close(&mut open, &mut roots);
+            }
+            open.push((
+                level,
+                TreeNode::new(entry.title, starts[i], end).with_structure(structure),
+            ));
+        }
+        while !open.is_empty() {
+            close(&mut open, &mut roots);
+        }
+
+        TreeIndexer::assign_node_ids(&mut roots);
+        Ok(DocumentTree::new(
+            self.name.as_deref().unwrap_or(name),
+            roots,
+            total_pages,
+        ))
+    }
+}
+
+/// Attach the innermost open section to its parent, or to the roots.
+fn close(open: &mut Vec<(usize, TreeNode)>, roots: &mut Vec<TreeNode>) {
+    if let Some((_, node)) = open.pop() {
+        match open.last_mut() {
+            Some((_, parent)) => parent.add_child(node),
+            None => roots.push(node),
+        }
+    }
+}
+
+/// Build a tree from a structure file, detecting its format unless given.
+///
+/// With the document, sections without pages are placed in it, the page
+/// count is the document's, and the tree records the document as its
+/// source. Without it, every section needs a page.
+pub fn import_structure(
+    path: &Path,
+    format: Option<StructureFormat>,
+    document: Option<&Document>,
+) -> Result<DocumentTree> {
+    let content = std::fs::read_to_string(path).map_err(|e| PageIndexError::io(path, e))?;
+    let format = format
+        .or_else(|| StructureFormat::detect(path, &content))
+        .ok_or_else(|| {
+            PageIndexError::UnsupportedFormat(format!(
+                "can't tell the structure format of '{}'; pass it explicitly",
+                path.display()
+            ))
+        })?;
+    let mut outline = Outline::parse(&content, format)?;
+
+    let name = match document {
+        Some(document) => document.name.clone(),
+        None => path.file_stem().map_or_else(
+            || "document".to_string(),
+            |s| s.to_string_lossy().into_owned(),
+        ),
+    };
+    if let Some(document) = document {
+        outline.place(document);
+        // The document's name wins over the structure file's
+        outline.name = None;
+    }
+    let mut tree = outline.into_tree(&name, document.map(|d| d.page_count()))?;
+    if let Some(path) = document.and_then(|d| d.path.as_ref()) {
+        tree.source = Some(SourceInfo::read(path)?);
+    }
+    Ok(tree)
+}
+
+/// Whitespace runs collapsed to single spaces, trimmed.
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// A piece of an XML document, as far as outlines need it.
+#[derive(Debug, Clone, PartialEq)]
+enum XmlEvent {
+    /// Opening tag with its local name and raw attributes. Self-closing
+    /// tags are followed by their [`XmlEvent::End`].
+    Start(String, String),
+    End(String),
+    /// Text with entities decoded.
+    Text(String),
+}
+
+/// Split XML (or XHTML) into tags and text. Comments, processing
+/// instructions and doctypes are skipped, namespace prefixes dropped from
+/// element names, and CDATA kept as text. Not a validating parser: it
+/// only needs to recover outlines from well-formed files.
+fn xml_events(content: &str) -> Vec<XmlEvent> {
+    let mut events = Vec::new();
+    let mut rest = content;
+    while !rest.is_empty() {
+        let Some(open) = rest.find('<') else {
+            events.push(XmlEvent::Text(decode_entities(rest)));
+            break;
+        };
+        if open > 0 {
+            events.push(XmlEvent::Text(decode_entities(&rest[..open])));
+        }
+        rest = &rest[open..];
+        let skip = |rest: &str, terminator: &str| {
+            rest.find(terminator)
+                .map_or(rest.len(), |i| i + terminator.len())
+        };
+        if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+            let end = cdata.find("]]>").unwrap_or(cdata.len());
+            events.push(XmlEvent::Text(cdata[..end].to_string()));
+            rest = &rest[skip(rest, "]]>")..];
+        } else if rest.starts_with("<!--") {
+            rest = &rest[skip(rest, "-->")..];
+        } else if rest.starts_with("<?") {
+            rest = &rest[skip(rest, "?>")..];
+        } else if rest.starts_with("<!") {
+            rest = &rest[skip(rest, ">")..];
+        } else {
+            let end = skip(rest, ">");
+            let tag = rest[1..end].trim_end_matches('>');
+            rest = &rest[end..];
+            if let Some(name) = tag.strip_prefix('/') {
+                events.push(XmlEvent::End(local_name(name.trim()).to_string()));
+                continue;
+            }
+            let self_closing = tag.ends_with('/');
+            let tag = tag.trim_end_matches('/');
+            let (name, attrs) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+            let name = local_name(name).to_string();
+            events.push(XmlEvent::Start(name.clone(), attrs.to_string()));
+            if self_closing {
+                events.push(XmlEvent::End(name));
+            }
+        }
+    }
+    events
+}
+
+/// Element name without its namespace prefix.
+fn local_name(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+/// Value of an attribute in a tag's raw attributes.
+fn attribute(attrs: &str, name: &str) -> Option<String> {
+    let mut rest = attrs;
+    while let Some(eq) = rest.find('=') {
+        let key = rest[..eq].trim();
+        let value = rest[eq + 1..].trim_start();
+        let quote = value.chars().next()?;
+        if quote != '"' && quote != '\'' {
+            return None;
+        }
+        let end = value[1..].find(quote)? + 1;
+        if key == name {
+            return Some(decode_entities(&value[1..end]));
+        }
+        rest = &value[end + 1..];
+    }
+    None
+}
+
+/// Decode XML character and predefined entity references.
+fn decode_entities(text: &str) -> String {
+    let mut decoded = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        decoded.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let Some(semi) = rest.find(';').filter(|&i| i <= 10) else {
+            decoded.push('&');
+            rest = &rest[1..];
+            continue;
+        };
+        let entity = &rest[1..semi];
+        let character = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            "nbsp" => Some(' '),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|d| d.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match character {
+            Some(c) => {
+                decoded.push(c);
+                rest = &rest[semi + 1..];
+            }
+            None => {
+                decoded.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::Page;
+
+    fn titles(nodes: &[TreeNode]) -> Vec<(String, usize, usize)> {
+        let mut out = Vec::new();
+        for node in nodes {
+            out.push((
+                format!("{} {}", node.structure.as_deref().unwrap_or(""), node.title),
+                node.start_index,
+                node.end_index,
+            ));
+            out.extend(titles(&node.nodes));
+        }
+        out
+    }
+
+    fn expected(items: &[(&str, usize, usize)]) -> Vec<(String, usize, usize)> {
+        items
+            .iter()
+            .map(|(t, s, e)| (t.to_string(), *s, *e))
+            .collect()
+    }
+
+    #[test]
+    fn test_yaml_and_bookmarks() {
+        let yaml = r#"
+name: manual
+pages: 12
+sections:
+  - title: Introduction
+    page: 1
+  - title: Setup
+    page: 3
+    sections:
+      - title: Unpacking
+        page: 3
+      - title: Wiring
+        start: 5
+        end: 6
+  - title: Appendix
+    page: 10
+"#;
+        let tree = Outline::parse(yaml, StructureFormat::Yaml)
+            .unwrap()
+            .into_tree("fallback", None)
+            .unwrap();
+        assert_eq!(tree.name, "manual");
+        assert_eq!(tree.total_pages, 12);
+        assert_eq!(
+            titles(&tree.nodes),
+            expected(&[
+                ("1 Introduction", 1, 2),
+                ("2 Setup", 3, 9),
+                ("2.1 Unpacking", 3, 4),
+                ("2.2 Wiring", 5, 6),
+                ("3 Appendix", 10, 12),
+            ])
+        );
+        assert_eq!(tree.nodes[1].nodes[1].node_id.as_deref(), Some("0003"));
+
+        let dump = "InfoKey: Title\nNumberOfPages: 8\nBookmarkBegin\nBookmarkTitle: Safety &amp; Care\nBookmarkLevel: 1\nBookmarkPageNumber: 2\nBookmarkBegin\nBookmarkTitle: Cleaning\nBookmarkLevel: 3\nBookmarkPageNumber: 4\nBookmarkBegin\nBookmarkTitle: Warranty\nBookmarkLevel: 1\nBookmarkPageNumber: 7\n";
+        assert_eq!(
+            StructureFormat::detect(Path::new("dump.txt"), dump),
+            Some(StructureFormat::PdfBookmarks)
+        );
+        let tree = Outline::parse(dump, StructureFormat::PdfBookmarks)
+            .unwrap()
+            .into_tree("pump", None)
+            .unwrap();
+        assert_eq!(tree.name, "pump");
+        // Level 3 under level 1 is pulled up to 2
+        assert_eq!(
+            titles(&tree.nodes),
+            expected(&[
+                ("1 Safety & Care", 2, 6),
+                ("1.1 Cleaning", 4, 6),
+                ("2 Warranty", 7, 8),
+            ])
+        );
+
+        let unplaced = Outline::parse("sections:\n  - title: Intro\n", StructureFormat::Yaml)
+            .unwrap()
+            .into_tree("x", None);
+        assert!(unplaced.is_err());
+    }
+
+    #[test]
+    fn test_xml_sources() {
+        let document = Document::new(
+            "guide",
+            vec![
+                Page::new(1, "Contents".to_string()),
+                Page::new(2, "Getting   Started\nInstall it.".to_string()),
+                Page::new(3, "More install notes.".to_string()),
+                Page::new(4, "Configuration\nSet options.".to_string()),
+                Page::new(5, "Advanced options &c.".to_string()),
+            ],
+        );
+
+        let nav = r#"<?xml version="1.0"?>
+<html xmlns:epub="http://www.idpf.org/2007/ops"><body>
+<nav epub:type="landmarks"><ol><li><a href="c.xhtml">Cover</a></li></ol></nav>
+<nav epub:type="toc"><h1>Contents</h1><ol>
+  <li><a href="1.xhtml">Getting Started</a></li>
+  <li><span>Configuration</span><ol>
+    <li><a href="2.xhtml#adv">Advanced <em>options</em></a></li>
+  </ol></li>
+</ol></nav></body></html>"#;
+        let ncx = r#"<ncx><docTitle><text>Guide</text></docTitle><navMap>
+<navPoint id="a"><navLabel><text>Getting Started</text></navLabel><content src="1.xhtml"/></navPoint>
+<navPoint id="b"><navLabel><text>Configuration</text></navLabel>
+  <navPoint id="c"><navLabel><text>Advanced options</text></navLabel></navPoint>
+</navPoint></navMap></ncx>"#;
+        let docbook = r#"<!DOCTYPE book><book><info><title>Guide</title></info>
+<chapter><title>Getting Started</title><para>Install it.</para></chapter>
+<chapter><title>Configuration</title>
+  <figure><title>Not a section</title></figure>
+  <section><info><title>Advanced options</title></info></section>
+</chapter></book>"#;
+
+        let want = expected(&[
+            ("1 Getting Started", 2, 3),
+            ("2 Configuration", 4, 5),
+            ("2.1 Advanced options", 5, 5),
+        ]);
+        for (content, format) in [
+            (nav, StructureFormat::EpubNav),
+            (ncx, StructureFormat::Ncx),
+            (docbook, StructureFormat::DocBook),
+        ] {
+            assert_eq!(
+                StructureFormat::detect(Path::new("toc"), content),
+                Some(format)
+            );
+            let mut outline = Outline::parse(content, format).unwrap();
+            outline.place(&document);
+            let tree = outline.into_tree("guide", Some(5)).unwrap();
+            assert_eq!(titles(&tree.nodes), want, "{:?}", format);
+        }
+
+        assert_eq!(
+            xml_events("<a x='1'/>&#65;&amp;&bogus"),
+            vec![
+                XmlEvent::Start("a".to_string(), "x='1'".to_string()),
+                XmlEvent::End("a".to_string()),
+                XmlEvent::Text("A&&bogus".to_string()),
+            ]
+        );
+    }
+}
@@ -6,11 +6,93 @@
 //! 3. Map sections to physical page indices
 //! 4. Verify and correct page mappings
 //! 5. Generate summaries for each node (optional but recommended)
+//!
+//! A long document with no discernible structure (a transcript, a chat log)
+//! can come out of step 2 as one or two sections, leaving search nothing to
+//! navigate. It is then split into runs of pages of about the same size,
+//! titled by the LLM (see [`IndexerOptions::chunk_fallback`]).
 
+use crate::checkpoint::{IndexCheckpoint, document_fingerprint, load_checkpoint, save_checkpoint};
+use crate::config::Config;
 use crate::document::Document;
 use crate::error::{PageIndexError, Result};
 use crate::llm::{LlmClient, Prompts};
-use crate::tree::{DocumentTree, RawTocItem, TreeNode, build_tree_from_toc};
+use crate::progress::{ProgressCallback, ProgressEvent, emit};
+#[cfg(not(target_arch = "wasm32"))]
+use crate::stream::{EventStream, IndexEvent, forward_progress};
+use crate::tree::{
+    DocumentTree, RawTocItem, SourceInfo, TreeNode, build_tree_from_toc, canonicalize_titles,
+    fnv1a_hex, parse_effective_date,
+};
+use std::path::PathBuf;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::Arc;
+#[cfg(not(target_arch = "wasm32"))]
+use tokio::{sync::Semaphore, task::JoinSet};
+use tracing::{Instrument, debug, info, info_span, instrument, warn};
+
+/// Titles of sections that rarely answer a query, excluded from search
+/// prompts by default (see [`IndexerOptions::excluded_sections`]).
+pub const DEFAULT_EXCLUDED_SECTIONS: &[&str] = &[
+    "References",
+    "Bibliography",
+    "Works Cited",
+    "Index",
+    "Copyright",
+    "Copyright Notice",
+    "Acknowledgements",
+    "Acknowledgments",
+    "Table of Contents",
+    "Contents",
+];
+
+/// Failure rate of sampled checks above which
+/// [`VerificationMode::Sample`] checks every node.
+pub const DEFAULT_MAX_FAILURE_RATE: f32 = 0.2;
+
+/// Which nodes the indexer checks (by looking for their title on their
+/// start page) when [scoring confidence](IndexerOptions::score_confidence).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VerificationMode {
+    /// Check every node.
+    All,
+    /// Check the top-level nodes and a sample of `leaf_rate` of the
+    /// leaves, then every other node too if more than `max_failure_rate`
+    /// of those checks fail. The sample is pseudo-random but stable, so
+    /// indexing the same document again with the same `seed` checks the
+    /// same leaves, whatever the build.
+    Sample {
+        leaf_rate: f32,
+        max_failure_rate: f32,
+        seed: u64,
+    },
+}
+
+/// Most characters of a passage sent to the LLM to title it.
+const MAX_PASSAGE_CHARS: usize = 6000;
+
+/// Fixed-size sections to fall back to when a document has no discernible
+/// structure.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChunkFallback {
+    /// Fall back when structure extraction finds at most this many
+    /// sections.
+    pub max_sections: usize,
+    /// Approximate size of each section in tokens. Sections are runs of
+    /// whole pages, so the fallback only applies to documents split into
+    /// more than `max_sections` of them (a text file loaded as a single
+    /// page never is).
+    pub section_tokens: usize,
+}
+
+impl Default for ChunkFallback {
+    fn default() -> Self {
+        Self {
+            max_sections: 2,
+            section_tokens: 2000,
+        }
+    }
+}
 
 /// Options for tree index generation.
 #[derive(Debug, Clone)]
@@ -23,6 +105,54 @@ pub struct IndexerOptions {
     pub max_fix_attempts: usize,
     /// Whether to generate summaries for each node.
     pub generate_summaries: bool,
+    /// Whether to compose prefix summaries from the generated summaries
+    /// (see [`DocumentTree::compose_prefix_summaries`]).
+    pub prefix_summaries: bool,
+    /// Titles of non-content sections to mark as
+    /// [excluded](crate::tree::TreeNode::excluded) from search prompts (see
+    /// [`DocumentTree::mark_excluded`]). They stay in the tree.
+    pub excluded_sections: Vec<String>,
+    /// Whether to score each node's [confidence](TreeNode::confidence):
+    /// the TOC prompts ask the LLM how sure it is of each item, and each
+    /// title is looked for on its start page (one more LLM call per node).
+    pub score_confidence: bool,
+    /// Which nodes to check when scoring confidence. Unchecked nodes are
+    /// scored from the LLM's certainty alone.
+    pub verification: VerificationMode,
+    /// Maximum title checks in flight at once.
+    pub verification_concurrency: usize,
+    /// Whether to clean up extracted titles (see
+    /// [`canonical_title`](crate::tree::canonical_title)), keeping the
+    /// originals in [`TreeNode::original_title`].
+    pub canonicalize_titles: bool,
+    /// Whether to find each section's
+    /// [effective date](TreeNode::effective_date) on its start page (one
+    /// more LLM call per section), for recency-aware search.
+    pub extract_dates: bool,
+    /// Fixed-size sections, titled by the LLM (one more call per section),
+    /// to use instead of the extracted structure when it has too few
+    /// sections to navigate. `None` keeps the extracted structure.
+    pub chunk_fallback: Option<ChunkFallback>,
+}
+
+impl IndexerOptions {
+    /// Default options with the settings from a configuration.
+    pub fn from_config(config: &Config) -> Self {
+        Self {
+            excluded_sections: config.indexer.excluded_sections.clone(),
+            score_confidence: config.indexer.score_confidence,
+            extract_dates: config.indexer.extract_dates,
+            verification: match config.indexer.verify_sample {
+                Some(leaf_rate) => VerificationMode::Sample {
+                    leaf_rate,
+                    max_failure_rate: DEFAULT_MAX_FAILURE_RATE,
+                    seed: config.indexer.verify_seed,
+                },
+                None => VerificationMode::All,
+            },
+            ..Default::default()
+        }
+    }
 }
 
 impl Default for IndexerOptions {
@@ -32,6 +162,17 @@ impl Default for IndexerOptions {
             verify_indices: true,
             max_fix_attempts: 3,
             generate_summaries: true, // Enable by default - critical for search quality!
+            prefix_summaries: true,
+            excluded_sections: DEFAULT_EXCLUDED_SECTIONS
+                .iter()
+                .map(|s| s.to_string())
+                .collect(),
+            score_confidence: false,
+            verification: VerificationMode::All,
+            verification_concurrency: 4,
+            canonicalize_titles: true,
+            extract_dates: false,
+            chunk_fallback: Some(ChunkFallback::default()),
         }
     }
 }
@@ -41,6 +182,30 @@ pub struct TreeIndexer {
     client: LlmClient,
     #[allow(dead_code)]
     options: IndexerOptions,
+    progress: Option<ProgressCallback>,
+    checkpoint: Option<CheckpointSettings>,
+}
+
+/// Where to write checkpoints and whether to resume from an existing one.
+#[derive(Clone)]
+struct CheckpointSettings {
+    path: PathBuf,
+    resume: bool,
+}
+
+/// In-progress checkpoint state, saved after each completed step.
+struct CheckpointState<'a> {
+    settings: Option<&'a CheckpointSettings>,
+    data: IndexCheckpoint,
+}
+
+impl CheckpointState<'_> {
+    fn save(&self) -> Result<()> {
+        match self.settings {
+            Some(settings) => save_checkpoint(&self.data, &settings.path),
+            None => Ok(()),
+        }
+    }
 }
 
 impl TreeIndexer {
@@ -49,98 +214,513 @@ impl TreeIndexer {
         Self {
             client,
             options: IndexerOptions::default(),
+            progress: None,
+            checkpoint: None,
         }
     }
 
     /// Create with custom options.
     pub fn with_options(client: LlmClient, options: IndexerOptions) -> Self {
-        Self { client, options }
+        Self {
+            client,
+            options,
+            progress: None,
+            checkpoint: None,
+        }
+    }
+
+    /// Report progress events (chunks processed, summaries generated) to a callback.
+    pub fn with_progress(mut self, callback: ProgressCallback) -> Self {
+        self.progress = Some(callback);
+        self
+    }
+
+    /// Save progress to a checkpoint file after every completed chunk and summary.
+    ///
+    /// With `resume`, an existing checkpoint for the same document is loaded
+    /// first and its completed steps are skipped. The file is deleted once the
+    /// tree is built.
+    pub fn with_checkpoint(mut self, path: impl Into<PathBuf>, resume: bool) -> Self {
+        self.checkpoint = Some(CheckpointSettings {
+            path: path.into(),
+            resume,
+        });
+        self
+    }
+
+    /// Load the checkpoint to resume from, or start a fresh one.
+    fn start_checkpoint(&self, document: &Document) -> Result<CheckpointState<'_>> {
+        let fingerprint = document_fingerprint(document, self.options.max_tokens_per_chunk);
+        let settings = self.checkpoint.as_ref();
+
+        let data = match settings {
+            Some(s) if s.resume && s.path.exists() => {
+                let data = load_checkpoint(&s.path)?;
+                if data.fingerprint != fingerprint {
+                    return Err(PageIndexError::Checkpoint(format!(
+                        "'{}' was written for a different document or chunk size",
+                        s.path.display()
+                    )));
+                }
+                info!(
+                    chunks = data.chunks_completed,
+                    summaries = data.summaries.len(),
+                    "resuming from checkpoint"
+                );
+                data
+            }
+            Some(s) if s.resume => {
+                warn!(path = %s.path.display(), "no checkpoint found, starting from scratch");
+                IndexCheckpoint {
+                    fingerprint,
+                    ..Default::default()
+                }
+            }
+            _ => IndexCheckpoint {
+                fingerprint,
+                ..Default::default()
+            },
+        };
+
+        Ok(CheckpointState { settings, data })
+    }
+
+    /// [`index`](Self::index) as a stream of progress events ending with
+    /// the tree.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn index_stream<'a>(&self, document: &'a Document) -> EventStream<'a, IndexEvent> {
+        let indexer = TreeIndexer {
+            client: self.client.clone(),
+            options: self.options.clone(),
+            progress: self.progress.clone(),
+            checkpoint: self.checkpoint.clone(),
+        };
+        EventStream::new(move |tx| async move {
+            let progress = forward_progress(indexer.progress.clone(), tx, IndexEvent::Progress);
+            let tree = indexer.with_progress(progress).index(document).await?;
+            Ok(IndexEvent::Completed(tree))
+        })
     }
 
     /// Build a tree index for a document.
+    #[instrument(
+        name = "index",
+        skip_all,
+        fields(document = %document.name, pages = document.page_count())
+    )]
     pub async fn index(&self, document: &Document) -> Result<DocumentTree> {
         // For MVP: Generate tree structure directly (no TOC detection)
         // This is the "process_no_toc" path from the Python implementation
 
-        let content = document.content_with_tags();
-        let toc_items = self.generate_toc_init(&content).await?;
+        let mut checkpoint = self.start_checkpoint(document)?;
+
+        let chunks = self.page_chunks(document);
+        let total_chunks = chunks.len();
+        info!(
+            document = %document.name,
+            pages = document.page_count(),
+            chunks = total_chunks,
+            "extracting document structure"
+        );
+        let resumed_chunks = checkpoint.data.chunks_completed.min(total_chunks);
+        emit(
+            &self.progress,
+            ProgressEvent::ChunkProcessed {
+                completed: resumed_chunks,
+                total: total_chunks,
+            },
+        );
+
+        let mut toc_items = std::mem::take(&mut checkpoint.data.toc_items);
+        for (i, content) in chunks.iter().enumerate().skip(resumed_chunks) {
+            let span = info_span!("index.chunk", chunk = i + 1, total = total_chunks);
+            if i == 0 {
+                toc_items = self.generate_toc_init(content).instrument(span).await?;
+            } else {
+                let more = self
+                    .generate_toc_continue(&toc_items, content)
+                    .instrument(span)
+                    .await?;
+                toc_items.extend(more);
+            }
+            checkpoint.data.chunks_completed = i + 1;
+            checkpoint.data.toc_items = toc_items.clone();
+            checkpoint.save()?;
+            debug!(
+                chunk = i + 1,
+                total = total_chunks,
+                items = toc_items.len(),
+                "processed chunk"
+            );
+            emit(
+                &self.progress,
+                ProgressEvent::ChunkProcessed {
+                    completed: i + 1,
+                    total: total_chunks,
+                },
+            );
+        }
 
         // Build tree structure from flat TOC items
         let mut nodes = build_tree_from_toc(&toc_items, document.page_count());
 
+        if let Some(fallback) = self.options.chunk_fallback {
+            let found: usize = nodes.iter().map(|n| n.node_count()).sum();
+            let ranges = page_groups(document, fallback.section_tokens);
+            if found <= fallback.max_sections && ranges.len() > fallback.max_sections {
+                info!(
+                    found,
+                    sections = ranges.len(),
+                    "no discernible structure, falling back to fixed-size sections"
+                );
+                nodes = self
+                    .chunk_sections(&ranges, document)
+                    .instrument(info_span!("index.fallback", sections = ranges.len()))
+                    .await?;
+            }
+        }
+
+        if self.options.score_confidence {
+            let total: usize = nodes.iter().map(|n| n.node_count()).sum();
+            info!(nodes = total, "scoring node confidence");
+            self.score_confidence(&mut nodes, document)
+                .instrument(info_span!("index.confidence", nodes = total))
+                .await?;
+        }
+
+        if self.options.extract_dates {
+            let total: usize = nodes.iter().map(|n| n.node_count()).sum();
+            info!(nodes = total, "finding effective dates");
+            self.extract_dates(&mut nodes, document)
+                .instrument(info_span!("index.dates", nodes = total))
+                .await?;
+        }
+
+        if self.options.canonicalize_titles {
+            let changed = canonicalize_titles(&mut nodes);
+            if changed > 0 {
+                debug!(changed, "canonicalized titles");
+            }
+        }
+
         // Generate summaries for each node if enabled
         if self.options.generate_summaries {
-            self.generate_summaries_for_nodes(&mut nodes, document)
-                .await?;
+            let total = nodes.iter().map(|n| n.node_count()).sum();
+            info!(nodes = total, "generating node summaries");
+            let mut completed = 0;
+            emit(
+                &self.progress,
+                ProgressEvent::SummaryGenerated {
+                    completed,
+                    total,
+                    title: String::new(),
+                },
+            );
+            self.generate_summaries_for_nodes(
+                &mut nodes,
+                document,
+                &mut completed,
+                total,
+                &mut checkpoint,
+            )
+            .instrument(info_span!("index.summaries", nodes = total))
+            .await?;
         }
 
         // Assign node IDs for easier reference
         Self::assign_node_ids(&mut nodes);
 
-        let tree = DocumentTree::new(&document.name, nodes, document.page_count());
+        if let Some(settings) = checkpoint.settings
+            && settings.path.exists()
+        {
+            std::fs::remove_file(&settings.path)
+                .map_err(|e| PageIndexError::io(&settings.path, e))?;
+        }
+
+        let mut tree = DocumentTree::new(&document.name, nodes, document.page_count());
+        if let Some(path) = &document.path {
+            match SourceInfo::read(path) {
+                Ok(source) => tree.source = Some(source),
+                Err(e) => warn!(error = %e, "could not record the source document"),
+            }
+        }
+        if self.options.generate_summaries && self.options.prefix_summaries {
+            tree.compose_prefix_summaries();
+        }
+        let excluded = tree.mark_excluded(&self.options.excluded_sections);
+        if excluded > 0 {
+            debug!(excluded, "marked non-content sections");
+        }
+        info!(
+            sections = tree.node_count(),
+            max_depth = tree.max_depth(),
+            "tree index built"
+        );
 
         Ok(tree)
     }
 
+    /// Group document pages into chunks that fit within `max_tokens_per_chunk`.
+    ///
+    /// Each chunk is the tagged content of consecutive pages. A single page
+    /// larger than the limit becomes its own chunk.
+    fn page_chunks(&self, document: &Document) -> Vec<String> {
+        let mut chunks = Vec::new();
+        let mut current = String::new();
+        let mut current_tokens = 0;
+
+        for page in &document.pages {
+            if !current.is_empty()
+                && current_tokens + page.token_count > self.options.max_tokens_per_chunk
+            {
+                chunks.push(std::mem::take(&mut current));
+                current_tokens = 0;
+            }
+            current.push_str(&page.with_index_tags());
+            current_tokens += page.token_count;
+        }
+
+        if !current.is_empty() || chunks.is_empty() {
+            chunks.push(current);
+        }
+
+        chunks
+    }
+
     /// Generate summaries for all nodes in the tree.
+    ///
+    /// `completed` doubles as the node's depth-first position, which indexes
+    /// summaries already recorded in the checkpoint.
     async fn generate_summaries_for_nodes(
         &self,
         nodes: &mut [TreeNode],
         document: &Document,
+        completed: &mut usize,
+        total: usize,
+        checkpoint: &mut CheckpointState<'_>,
     ) -> Result<()> {
         for node in nodes.iter_mut() {
-            // Get the text content for this node
-            let text = document.content_range(node.start_index, node.end_index);
+            if let Some(summary) = checkpoint.data.summaries.get(*completed) {
+                node.summary = Some(summary.clone());
+            } else {
+                // Get the text content for this node
+                let text = document.content_range(node.start_index, node.end_index);
+
+                // Generate summary using LLM
+                let summary = self.generate_node_summary(&node.title, &text).await?;
+                checkpoint.data.summaries.push(summary.clone());
+                checkpoint.save()?;
+                node.summary = Some(summary);
+                debug!(title = %node.title, "generated summary");
+            }
 
-            // Generate summary using LLM
-            let summary = self.generate_node_summary(&node.title, &text).await?;
-            node.summary = Some(summary);
+            *completed += 1;
+            emit(
+                &self.progress,
+                ProgressEvent::SummaryGenerated {
+                    completed: *completed,
+                    total,
+                    title: node.title.clone(),
+                },
+            );
 
             // Recursively generate summaries for children
             if !node.nodes.is_empty() {
                 // Use Box::pin to handle recursive async
-                Box::pin(self.generate_summaries_for_nodes(&mut node.nodes, document)).await?;
+                Box::pin(self.generate_summaries_for_nodes(
+                    &mut node.nodes,
+                    document,
+                    completed,
+                    total,
+                    checkpoint,
+                ))
+                .await?;
             }
         }
         Ok(())
     }
 
-    /// Generate a summary for a single node.
-    async fn generate_node_summary(&self, title: &str, content: &str) -> Result<String> {
-        // Truncate content if too long to avoid token limits
-        let max_content_len = 8000; // ~2000 tokens
-        let truncated_content = if content.len() > max_content_len {
-            format!("{}...[truncated]", &content[..max_content_len])
-        } else {
-            content.to_string()
-        };
+    /// Score the [confidence](TreeNode::confidence) of every node from the
+    /// LLM's certainty in its TOC item and whether its title is found on
+    /// its start page.
+    async fn score_confidence(&self, nodes: &mut [TreeNode], document: &Document) -> Result<()> {
+        let candidates = verification_candidates(nodes);
+        let selected = select_for_verification(&candidates, self.options.verification);
+        let mut found = vec![None; candidates.len()];
+        self.check_titles(&candidates, &selected, document, &mut found)
+            .await?;
 
-        let prompt = format!(
-            "{}",
-            Prompts::generate_node_summary()
-                .replace("{title}", title)
-                .replace("{content}", &truncated_content)
+        if let VerificationMode::Sample {
+            max_failure_rate, ..
+        } = self.options.verification
+        {
+            let rate = failure_rate(&found);
+            if rate > max_failure_rate {
+                info!(
+                    failure_rate = rate,
+                    "sampled checks failed too often, checking every node"
+                );
+                let rest: Vec<usize> = (0..found.len()).filter(|&i| found[i].is_none()).collect();
+                self.check_titles(&candidates, &rest, document, &mut found)
+                    .await?;
+            }
+        }
+        debug!(
+            checked = found.iter().filter(|f| f.is_some()).count(),
+            nodes = found.len(),
+            "verified titles"
         );
 
-        let response = self
-            .client
-            .complete(Some(Prompts::system_document_analyzer()), &prompt)
-            .await?;
+        fn assign(nodes: &mut [TreeNode], found: &[Option<bool>], position: &mut usize) {
+            for node in nodes {
+                node.confidence = confidence_score(node.confidence, found[*position]);
+                *position += 1;
+                assign(&mut node.nodes, found, position);
+            }
+        }
+        assign(nodes, &found, &mut 0);
+        Ok(())
+    }
 
-        // Clean up the response
-        let summary = response.trim().to_string();
+    /// Set the [effective date](TreeNode::effective_date) of every undated
+    /// node the LLM finds one for on its start page.
+    async fn extract_dates(&self, nodes: &mut [TreeNode], document: &Document) -> Result<()> {
+        fn collect(
+            nodes: &[TreeNode],
+            document: &Document,
+            position: &mut usize,
+            out: &mut Vec<(usize, String, String)>,
+        ) {
+            for node in nodes {
+                if node.effective_date.is_none()
+                    && let Some(page) = document.get_page(node.start_index)
+                {
+                    out.push((*position, node.title.clone(), page.content.clone()));
+                }
+                *position += 1;
+                collect(&node.nodes, document, position, out);
+            }
+        }
+        let mut lookups = Vec::new();
+        let mut total = 0;
+        collect(nodes, document, &mut total, &mut lookups);
+        let mut dates = vec![None; total];
+        for (i, date) in
+            find_dates(&self.client, lookups, self.options.verification_concurrency).await?
+        {
+            dates[i] = date;
+        }
+        debug!(
+            dated = dates.iter().filter(|d| d.is_some()).count(),
+            nodes = dates.len(),
+            "found effective dates"
+        );
 
-        // Limit summary length
-        let max_summary_len = 500;
-        if summary.len() > max_summary_len {
-            Ok(format!("{}...", &summary[..max_summary_len]))
-        } else {
-            Ok(summary)
+        fn assign(nodes: &mut [TreeNode], dates: &mut [Option<String>], position: &mut usize) {
+            for node in nodes {
+                if let Some(date) = dates[*position].take() {
+                    node.effective_date = Some(date);
+                }
+                *position += 1;
+                assign(&mut node.nodes, dates, position);
+            }
+        }
+        assign(nodes, &mut dates, &mut 0);
+        Ok(())
+    }
+
+    /// Top-level sections for the given page ranges, titled by the LLM
+    /// from their text. A passage the LLM gives no usable title is named
+    /// after its pages.
+    async fn chunk_sections(
+        &self,
+        ranges: &[(usize, usize)],
+        document: &Document,
+    ) -> Result<Vec<TreeNode>> {
+        let passages = ranges
+            .iter()
+            .enumerate()
+            .map(|(i, &(start, end))| (i, document.content_range(start, end)))
+            .collect();
+        let mut titles = vec![None; ranges.len()];
+        for (i, title) in title_passages(
+            &self.client,
+            passages,
+            self.options.verification_concurrency,
+        )
+        .await?
+        {
+            titles[i] = title;
+        }
+
+        Ok(ranges
+            .iter()
+            .zip(titles)
+            .enumerate()
+            .map(|(i, (&(start, end), title))| {
+                let title = title.unwrap_or_else(|| {
+                    if start == end {
+                        format!("Page {}", start)
+                    } else {
+                        format!("Pages {}-{}", start, end)
+                    }
+                });
+                TreeNode::new(title, start, end).with_structure((i + 1).to_string())
+            })
+            .collect())
+    }
+
+    /// Look for the titles of the `selected` candidates on their start
+    /// pages, with up to `verification_concurrency` checks in flight, and
+    /// record the outcomes in `found`.
+    async fn check_titles(
+        &self,
+        candidates: &[Candidate],
+        selected: &[usize],
+        document: &Document,
+        found: &mut [Option<bool>],
+    ) -> Result<()> {
+        let checked = found.iter().filter(|f| f.is_some()).count();
+        let total = checked + selected.len();
+        let mut checks = Vec::new();
+        for &i in selected {
+            match document.get_page(candidates[i].start_index) {
+                Some(page) => checks.push((i, candidates[i].title.clone(), page.content.clone())),
+                None => found[i] = Some(false),
+            }
+        }
+        // Nodes starting past the last page fail without a check
+        let checked = checked + selected.len() - checks.len();
+        let report = |completed: usize| {
+            emit(
+                &self.progress,
+                ProgressEvent::NodeVerified {
+                    completed: checked + completed,
+                    total,
+                },
+            )
+        };
+        report(0);
+        for (i, on_page) in check_titles(
+            &self.client,
+            checks,
+            self.options.verification_concurrency,
+            report,
+        )
+        .await?
+        {
+            found[i] = Some(on_page);
         }
+        Ok(())
+    }
+
+    /// Generate a summary for a single node.
+    async fn generate_node_summary(&self, title: &str, content: &str) -> Result<String> {
+        summarize_section(&self.client, title, content).await
     }
 
     /// Assign node IDs to all nodes in the tree (depth-first).
-    fn assign_node_ids(nodes: &mut [TreeNode]) {
+    pub(crate) fn assign_node_ids(nodes: &mut [TreeNode]) {
         let mut counter = 0;
         Self::assign_node_ids_recursive(nodes, &mut counter);
     }
@@ -155,7 +735,12 @@ impl TreeIndexer {
 
     /// Generate initial TOC/structure from document content.
     async fn generate_toc_init(&self, content: &str) -> Result<Vec<RawTocItem>> {
-        let prompt = format!("{}\nGiven text\n:{}", Prompts::generate_toc_init(), content);
+        let prompt = format!(
+            "{}{}\nGiven text\n:{}",
+            Prompts::generate_toc_init(),
+            self.confidence_instruction(),
+            content
+        );
 
         let response = self
             .client
@@ -199,55 +784,53 @@ impl TreeIndexer {
         let response = response.trim();
 
         // Check for ```json code block
-        if response.starts_with("```json") {
-            if let Some(end) = response.rfind("```") {
-                let start = "```json".len();
-                if end > start {
-                    return response[start..end].trim().to_string();
-                }
+        if response.starts_with("```json")
+            && let Some(end) = response.rfind("```")
+        {
+            let start = "```json".len();
+            if end > start {
+                return response[start..end].trim().to_string();
             }
         }
 
         // Check for ``` code block
-        if response.starts_with("```") {
-            if let Some(end) = response.rfind("```") {
-                let start = response.find('\n').map(|n| n + 1).unwrap_or(3);
-                if end > start {
-                    return response[start..end].trim().to_string();
-                }
+        if response.starts_with("```")
+            && let Some(end) = response.rfind("```")
+        {
+            let start = response.find('\n').map(|n| n + 1).unwrap_or(3);
+            if end > start {
+                return response[start..end].trim().to_string();
             }
         }
 
         // Find JSON array or object
-        if let Some(start) = response.find('[') {
-            if let Some(end) = response.rfind(']') {
-                if end > start {
-                    return response[start..=end].to_string();
-                }
-            }
+        if let Some(start) = response.find('[')
+            && let Some(end) = response.rfind(']')
+            && end > start
+        {
+            return response[start..=end].to_string();
         }
 
-        if let Some(start) = response.find('{') {
-            if let Some(end) = response.rfind('}') {
-                if end > start {
-                    return response[start..=end].to_string();
-                }
-            }
+        if let Some(start) = response.find('{')
+            && let Some(end) = response.rfind('}')
+            && end > start
+        {
+            return response[start..=end].to_string();
         }
 
         response.to_string()
     }
 
     /// Continue generating TOC for additional document parts.
-    #[allow(dead_code)]
     async fn generate_toc_continue(
         &self,
         previous_toc: &[RawTocItem],
         content: &str,
     ) -> Result<Vec<RawTocItem>> {
         let prompt = format!(
-            "{}\nGiven text\n:{}\nPrevious tree structure\n:{}",
+            "{}{}\nGiven text\n:{}\nPrevious tree structure\n:{}",
             Prompts::generate_toc_continue(),
+            self.confidence_instruction(),
             content,
             serde_json::to_string_pretty(previous_toc)
                 .map_err(|e| PageIndexError::Serialization(e.to_string()))?
@@ -261,42 +844,367 @@ impl TreeIndexer {
         Self::parse_toc_response(&response)
     }
 
-    /// Verify that section titles appear on their claimed pages.
-    #[allow(dead_code)]
-    async fn verify_title_on_page(&self, title: &str, page_content: &str) -> Result<bool> {
-        let prompt = Prompts::check_title_appearance()
-            .replace("{title}", title)
-            .replace("{page_text}", page_content);
+    /// Extra TOC prompt instruction asking for each item's certainty, when
+    /// scoring confidence.
+    fn confidence_instruction(&self) -> String {
+        if self.options.score_confidence {
+            format!("\n\n{}", Prompts::toc_confidence())
+        } else {
+            String::new()
+        }
+    }
 
-        let response = self
-            .client
-            .complete(Some(Prompts::system_document_analyzer()), &prompt)
-            .await?;
+    /// Ask the LLM whether a section title appears or starts in the given page text.
+    pub async fn verify_title_on_page(&self, title: &str, page_content: &str) -> Result<bool> {
+        title_on_page(&self.client, title, page_content).await
+    }
+}
 
-        // Parse response
-        let json_str = Self::extract_json(&response);
+/// Ask the LLM whether a section title appears or starts in the given page text.
+async fn title_on_page(client: &LlmClient, title: &str, page_content: &str) -> Result<bool> {
+    let prompt = Prompts::check_title_appearance()
+        .replace("{title}", title)
+        .replace("{page_text}", page_content);
 
-        #[derive(serde::Deserialize)]
-        struct VerifyResponse {
-            answer: String,
+    let response = client
+        .complete(Some(Prompts::system_document_analyzer()), &prompt)
+        .await?;
+
+    // Parse response
+    let json_str = TreeIndexer::extract_json(&response);
+
+    #[derive(serde::Deserialize)]
+    struct VerifyResponse {
+        answer: String,
+    }
+
+    if let Ok(parsed) = serde_json::from_str::<VerifyResponse>(&json_str) {
+        Ok(parsed.answer.to_lowercase() == "yes")
+    } else {
+        // Default to false if parsing fails
+        Ok(false)
+    }
+}
+
+/// Ask the LLM for the date a section took effect, from its start page.
+async fn effective_date_on_page(
+    client: &LlmClient,
+    title: &str,
+    page_content: &str,
+) -> Result<Option<String>> {
+    let prompt = Prompts::find_effective_date()
+        .replace("{title}", title)
+        .replace("{page_text}", page_content);
+
+    let response = client
+        .complete(Some(Prompts::system_document_analyzer()), &prompt)
+        .await?;
+
+    #[derive(serde::Deserialize)]
+    struct DateResponse {
+        effective_date: Option<String>,
+    }
+
+    // An unparseable reply or date leaves the section undated
+    let parsed = serde_json::from_str::<DateResponse>(&TreeIndexer::extract_json(&response));
+    Ok(parsed
+        .ok()
+        .and_then(|r| r.effective_date)
+        .and_then(|d| parse_effective_date(&d)))
+}
+
+/// Run `(position, title, page text)` date lookups, at most `concurrency`
+/// at a time, returning `(position, date)` in completion order.
+#[cfg(not(target_arch = "wasm32"))]
+async fn find_dates(
+    client: &LlmClient,
+    lookups: Vec<(usize, String, String)>,
+    concurrency: usize,
+) -> Result<Vec<(usize, Option<String>)>> {
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+    let mut tasks = JoinSet::new();
+    for (i, title, page) in lookups {
+        let client = client.clone();
+        let semaphore = semaphore.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await.expect("semaphore closed");
+            (i, effective_date_on_page(&client, &title, &page).await)
+        });
+    }
+
+    let mut dates = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        let (i, date) = joined.map_err(|e| PageIndexError::TreeError(e.to_string()))?;
+        dates.push((i, date?));
+    }
+    Ok(dates)
+}
+
+/// Run `(position, title, page text)` date lookups one at a time (there is
+/// no task runtime in the browser).
+#[cfg(target_arch = "wasm32")]
+async fn find_dates(
+    client: &LlmClient,
+    lookups: Vec<(usize, String, String)>,
+    _concurrency: usize,
+) -> Result<Vec<(usize, Option<String>)>> {
+    let mut dates = Vec::new();
+    for (i, title, page) in lookups {
+        dates.push((i, effective_date_on_page(client, &title, &page).await?));
+    }
+    Ok(dates)
+}
+
+/// Ask the LLM for a title for a passage without headings.
+async fn passage_title(client: &LlmClient, content: &str) -> Result<Option<String>> {
+    let content: String = content.chars().take(MAX_PASSAGE_CHARS).collect();
+    let prompt = Prompts::title_passage().replace("{content}", &content);
+
+    let response = client
+        .complete(Some(Prompts::system_document_analyzer()), &prompt)
+        .await?;
+
+    #[derive(serde::Deserialize)]
+    struct TitleResponse {
+        title: Option<String>,
+    }
+
+    // An unparseable reply leaves the passage untitled
+    let parsed = serde_json::from_str::<TitleResponse>(&TreeIndexer::extract_json(&response));
+    Ok(parsed
+        .ok()
+        .and_then(|r| r.title)
+        .map(|t| t.trim().to_string())
+        .filter(|t| !t.is_empty()))
+}
+
+/// Title `(position, passage)` pairs, at most `concurrency` at a time,
+/// returning `(position, title)` in completion order.
+#[cfg(not(target_arch = "wasm32"))]
+async fn title_passages(
+    client: &LlmClient,
+    passages: Vec<(usize, String)>,
+    concurrency: usize,
+) -> Result<Vec<(usize, Option<String>)>> {
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+    let mut tasks = JoinSet::new();
+    for (i, content) in passages {
+        let client = client.clone();
+        let semaphore = semaphore.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await.expect("semaphore closed");
+            (i, passage_title(&client, &content).await)
+        });
+    }
+
+    let mut titles = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        let (i, title) = joined.map_err(|e| PageIndexError::TreeError(e.to_string()))?;
+        titles.push((i, title?));
+    }
+    Ok(titles)
+}
+
+/// Title `(position, passage)` pairs one at a time (there is no task
+/// runtime in the browser).
+#[cfg(target_arch = "wasm32")]
+async fn title_passages(
+    client: &LlmClient,
+    passages: Vec<(usize, String)>,
+    _concurrency: usize,
+) -> Result<Vec<(usize, Option<String>)>> {
+    let mut titles = Vec::new();
+    for (i, content) in passages {
+        titles.push((i, passage_title(client, &content).await?));
+    }
+    Ok(titles)
+}
+
+/// Split a document's pages into runs of about `section_tokens` tokens,
+/// returning each run's first and last page number. A page larger than
+/// the limit is a run of its own.
+fn page_groups(document: &Document, section_tokens: usize) -> Vec<(usize, usize)> {
+    let mut groups: Vec<(usize, usize)> = Vec::new();
+    let mut tokens = 0;
+    for page in &document.pages {
+        match groups.last_mut() {
+            Some(group) if tokens + page.token_count <= section_tokens => {
+                group.1 = page.number;
+                tokens += page.token_count;
+            }
+            _ => {
+                groups.push((page.number, page.number));
+                tokens = page.token_count;
+            }
         }
+    }
+    groups
+}
 
-        if let Ok(parsed) = serde_json::from_str::<VerifyResponse>(&json_str) {
-            Ok(parsed.answer.to_lowercase() == "yes")
-        } else {
-            // Default to false if parsing fails
-            Ok(false)
+/// Run `(position, title, page text)` title checks, at most `concurrency`
+/// at a time, returning `(position, found)` in completion order.
+#[cfg(not(target_arch = "wasm32"))]
+async fn check_titles(
+    client: &LlmClient,
+    checks: Vec<(usize, String, String)>,
+    concurrency: usize,
+    report: impl Fn(usize),
+) -> Result<Vec<(usize, bool)>> {
+    let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+    let mut tasks = JoinSet::new();
+    for (i, title, page) in checks {
+        let client = client.clone();
+        let semaphore = semaphore.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await.expect("semaphore closed");
+            (i, title_on_page(&client, &title, &page).await)
+        });
+    }
+
+    let mut found = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        let (i, on_page) = joined.map_err(|e| PageIndexError::TreeError(e.to_string()))?;
+        found.push((i, on_page?));
+        report(found.len());
+    }
+    Ok(found)
+}
+
+/// Run `(position, title, page text)` title checks one at a time (there is
+/// no task runtime in the browser).
+#[cfg(target_arch = "wasm32")]
+async fn check_titles(
+    client: &LlmClient,
+    checks: Vec<(usize, String, String)>,
+    _concurrency: usize,
+    report: impl Fn(usize),
+) -> Result<Vec<(usize, bool)>> {
+    let mut found = Vec::new();
+    for (i, title, page) in checks {
+        found.push((i, title_on_page(client, &title, &page).await?));
+        report(found.len());
+    }
+    Ok(found)
+}
+
+/// A node that may be checked, in depth-first order.
+struct Candidate {
+    title: String,
+    start_index: usize,
+    top_level: bool,
+    leaf: bool,
+}
+
+fn verification_candidates(nodes: &[TreeNode]) -> Vec<Candidate> {
+    fn visit(nodes: &[TreeNode], top_level: bool, out: &mut Vec<Candidate>) {
+        for node in nodes {
+            out.push(Candidate {
+                title: node.title.clone(),
+                start_index: node.start_index,
+                top_level,
+                leaf: node.nodes.is_empty(),
+            });
+            visit(&node.nodes, false, out);
         }
     }
+    let mut out = Vec::new();
+    visit(nodes, true, &mut out);
+    out
+}
+
+/// Positions of the candidates to check first under `mode`.
+fn select_for_verification(candidates: &[Candidate], mode: VerificationMode) -> Vec<usize> {
+    let VerificationMode::Sample {
+        leaf_rate, seed, ..
+    } = mode
+    else {
+        return (0..candidates.len()).collect();
+    };
+    let mut selected: Vec<usize> = (0..candidates.len())
+        .filter(|&i| candidates[i].top_level)
+        .collect();
+
+    // Order the other leaves by a seeded hash of their title and page, and
+    // take the first `leaf_rate` of them
+    let mut leaves: Vec<(String, usize)> = (0..candidates.len())
+        .filter(|&i| candidates[i].leaf && !candidates[i].top_level)
+        .map(|i| {
+            let key = format!(
+                "{}\n{}\n{}",
+                seed, candidates[i].start_index, candidates[i].title
+            );
+            (fnv1a_hex(key.as_bytes()), i)
+        })
+        .collect();
+    leaves.sort_unstable();
+    let sample = (leaves.len() as f32 * leaf_rate.clamp(0.0, 1.0)).ceil() as usize;
+    selected.extend(leaves.iter().take(sample).map(|&(_, i)| i));
+    selected.sort_unstable();
+    selected
+}
+
+/// Share of the checks made so far whose title was not found.
+fn failure_rate(found: &[Option<bool>]) -> f32 {
+    let checked = found.iter().filter(|f| f.is_some()).count();
+    let failed = found.iter().filter(|f| **f == Some(false)).count();
+    if checked == 0 {
+        0.0
+    } else {
+        failed as f32 / checked as f32
+    }
+}
+
+/// A node's confidence: the mean of the LLM's certainty in its TOC item
+/// and whether its title was found on its start page, of those known.
+fn confidence_score(certainty: Option<f32>, title_found: Option<bool>) -> Option<f32> {
+    let found = title_found.map(|found| if found { 1.0 } else { 0.0 });
+    match (certainty, found) {
+        (Some(certainty), Some(found)) => Some((certainty + found) / 2.0),
+        (certainty, found) => certainty.or(found),
+    }
 }
 
 /// Convenience function to index a document from a file path.
 pub async fn index_document(path: &std::path::Path, client: LlmClient) -> Result<DocumentTree> {
-    let document = Document::from_text_file(path)?;
+    let document = crate::loader::load(path)?;
     let indexer = TreeIndexer::new(client);
     indexer.index(&document).await
 }
 
+/// Summarize a section from its text.
+pub(crate) async fn summarize_section(
+    client: &LlmClient,
+    title: &str,
+    content: &str,
+) -> Result<String> {
+    // Truncate content if too long to avoid token limits
+    let max_content_len = 8000; // ~2000 tokens
+    let truncated_content = if content.len() > max_content_len {
+        format!("{}...[truncated]", &content[..max_content_len])
+    } else {
+        content.to_string()
+    };
+
+    let prompt = Prompts::generate_node_summary()
+        .replace("{title}", title)
+        .replace("{content}", &truncated_content);
+
+    let response = client
+        .complete(Some(Prompts::system_document_analyzer()), &prompt)
+        .await?;
+
+    // Clean up the response
+    let summary = response.trim().to_string();
+
+    // Limit summary length
+    let max_summary_len = 500;
+    if summary.len() > max_summary_len {
+        Ok(format!("{}...", &summary[..max_summary_len]))
+    } else {
+        Ok(summary)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -351,11 +1259,263 @@ That's the result."#;
         assert_eq!(items[0].title, "Chapter 1");
     }
 
+    #[test]
+    fn test_page_chunks_respects_token_limit() {
+        let config = crate::config::LlmConfig::default();
+        let options = IndexerOptions {
+            max_tokens_per_chunk: 10,
+            ..Default::default()
+        };
+        let indexer = TreeIndexer::with_options(LlmClient::new(config), options);
+
+        // Each page is ~5 tokens, so two pages fit per chunk
+        let pages = (1..=5)
+            .map(|i| crate::document::Page::new(i, "one two three four".to_string()))
+            .collect();
+        let document = Document::new("test", pages);
+
+        let chunks = indexer.page_chunks(&document);
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks[0].contains("<physical_index_1>"));
+        assert!(chunks[0].contains("<physical_index_2>"));
+        assert!(chunks[2].contains("<physical_index_5>"));
+    }
+
     #[test]
     fn test_indexer_options_default() {
         let options = IndexerOptions::default();
         assert_eq!(options.max_tokens_per_chunk, 20000);
         assert!(options.verify_indices);
         assert_eq!(options.max_fix_attempts, 3);
+        assert!(!options.score_confidence);
+        assert!(options.canonicalize_titles);
+        assert_eq!(options.chunk_fallback, Some(ChunkFallback::default()));
+    }
+
+    #[test]
+    fn test_page_groups() {
+        use crate::document::Page;
+
+        // Three words are four tokens
+        let pages = [1, 2, 3, 5, 6]
+            .into_iter()
+            .map(|n| Page::new(n, "one two three".to_string()))
+            .collect();
+        let document = Document::new("log", pages);
+        assert_eq!(page_groups(&document, 8), vec![(1, 2), (3, 5), (6, 6)]);
+        assert_eq!(page_groups(&document, 1).len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_chunk_fallback() {
+        use crate::document::Page;
+        use crate::fixtures::replay_replies;
+        use crate::golden::PromptCapture;
+
+        let pages = (1..=4)
+            .map(|n| Page::new(n, format!("[00:0{}] Speaker: line {}", n, n)))
+            .collect();
+        let document = Document::new("call", pages);
+        let replies = [
+            r#"[{"structure": "1", "title": "Call transcript", "physical_index": 1}]"#,
+            r#"{"title": "Introductions"}"#,
+            r#"{"title": " "}"#,
+        ];
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("fallback.fixture.json");
+        let capture = PromptCapture::new(replay_replies(&path, &replies));
+
+        let page_tokens = document.pages[0].token_count;
+        let options = IndexerOptions {
+            generate_summaries: false,
+            verification_concurrency: 1,
+            chunk_fallback: Some(ChunkFallback {
+                max_sections: 1,
+                section_tokens: page_tokens * 2,
+            }),
+            ..IndexerOptions::default()
+        };
+        let indexer = TreeIndexer::with_options(capture.client().unwrap(), options);
+        let tree = indexer.index(&document).await.unwrap();
+
+        let sections: Vec<_> = tree
+            .nodes
+            .iter()
+            .map(|n| (n.title.as_str(), n.start_index, n.end_index))
+            .collect();
+        assert_eq!(sections, vec![("Introductions", 1, 2), ("Pages 3-4", 3, 4)]);
+        assert_eq!(tree.nodes[1].structure.as_deref(), Some("2"));
+        assert_eq!(capture.prompts().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_multi_chunk_toc_reports_verification() {
+        use crate::document::Page;
+        use crate::fixtures::replay_replies;
+        use crate::golden::PromptCapture;
+        use std::sync::Mutex;
+
+        let document = Document::new(
+            "report",
+            vec![
+                Page::new(1, "Intro. Why we wrote this.".to_string()),
+                Page::new(2, "Methods. How we did it.".to_string()),
+            ],
+        );
+        let replies = [
+            r#"[{"structure": "1", "title": "Intro", "physical_index": 1}]"#,
+            r#"[{"structure": "2", "title": "Methods", "physical_index": 2}]"#,
+            r#"{"answer": "yes"}"#,
+            r#"{"answer": "yes"}"#,
+        ];
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("chunks.fixture.json");
+        let capture = PromptCapture::new(replay_replies(&path, &replies));
+
+        // One page per chunk
+        let options = IndexerOptions {
+            max_tokens_per_chunk: document.pages[0].token_count,
+            generate_summaries: false,
+            score_confidence: true,
+            verification_concurrency: 1,
+            chunk_fallback: None,
+            ..IndexerOptions::default()
+        };
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let sink = events.clone();
+        let indexer = TreeIndexer::with_options(capture.client().unwrap(), options).with_progress(
+            Arc::new(move |event: &ProgressEvent| sink.lock().unwrap().push(event.clone())),
+        );
+        let tree = indexer.index(&document).await.unwrap();
+
+        let sections: Vec<_> = tree
+            .nodes
+            .iter()
+            .map(|n| (n.title.as_str(), n.start_index, n.end_index))
+            .collect();
+        assert_eq!(sections, vec![("Intro", 1, 1), ("Methods", 2, 2)]);
+        assert_eq!(capture.prompts().len(), 4);
+
+        let events = events.lock().unwrap();
+        let chunks: Vec<_> = events
+            .iter()
+            .filter_map(|e| match e {
+                ProgressEvent::ChunkProcessed { completed, total } => Some((*completed, *total)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(chunks, vec![(0, 2), (1, 2), (2, 2)]);
+        let verified: Vec<_> = events
+            .iter()
+            .filter_map(|e| match e {
+                ProgressEvent::NodeVerified { completed, total } => Some((*completed, *total)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(verified, vec![(0, 2), (1, 2), (2, 2)]);
+    }
+
+    #[test]
+    fn test_confidence_score() {
+        assert_eq!(confidence_score(Some(0.8), Some(true)), Some(0.9));
+        assert_eq!(confidence_score(Some(0.8), Some(false)), Some(0.4));
+        assert_eq!(confidence_score(None, Some(true)), Some(1.0));
+        assert_eq!(confidence_score(Some(0.8), None), Some(0.8));
+        assert_eq!(confidence_score(None, None), None);
+    }
+
+    #[test]
+    fn test_select_for_verification() {
+        // Two chapters of ten sections each
+        let nodes: Vec<TreeNode> = (1..=2)
+            .map(|c| {
+                let mut chapter = TreeNode::new(format!("Chapter {}", c), c, c);
+                for s in 1..=10 {
+                    chapter.add_child(TreeNode::new(format!("Section {}.{}", c, s), c, c));
+                }
+                chapter
+            })
+            .collect();
+        let candidates = verification_candidates(&nodes);
+        assert_eq!(candidates.len(), 22);
+        assert_eq!(
+            select_for_verification(&candidates, VerificationMode::All).len(),
+            22
+        );
+
+        let mode = VerificationMode::Sample {
+            leaf_rate: 0.25,
+            max_failure_rate: DEFAULT_MAX_FAILURE_RATE,
+            seed: 0,
+        };
+        let selected = select_for_verification(&candidates, mode);
+        // Both chapters and 5 of the 20 sections, the same ones every time
+        assert_eq!(selected.len(), 7);
+        assert!(selected.contains(&0) && selected.contains(&11));
+        assert_eq!(selected, select_for_verification(&candidates, mode));
+        // The hash is stable across builds, so the sample is too
+        assert_eq!(selected, vec![0, 4, 5, 6, 7, 10, 11]);
+        let reseeded = VerificationMode::Sample {
+            leaf_rate: 0.25,
+            max_failure_rate: DEFAULT_MAX_FAILURE_RATE,
+            seed: 7,
+        };
+        assert_eq!(
+            select_for_verification(&candidates, reseeded),
+            vec![0, 1, 2, 3, 8, 9, 11]
+        );
+
+        let mut found = vec![None; 4];
+        assert_eq!(failure_rate(&found), 0.0);
+        found[0] = Some(true);
+        found[1] = Some(false);
+        assert_eq!(failure_rate(&found), 0.5);
+    }
+
+    #[tokio::test]
+    async fn test_resume_from_complete_checkpoint_skips_llm_calls() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("tree.json.checkpoint");
+        let document = Document::from_text("doc", "Intro text. Background text.".to_string());
+
+        let options = IndexerOptions::default();
+        let checkpoint = IndexCheckpoint {
+            fingerprint: document_fingerprint(&document, options.max_tokens_per_chunk),
+            chunks_completed: 1,
+            toc_items: vec![
+                RawTocItem {
+                    structure: Some("1".to_string()),
+                    title: "Intro".to_string(),
+                    physical_index: Some(serde_json::json!(1)),
+                    confidence: None,
+                },
+                RawTocItem {
+                    structure: Some("1.1".to_string()),
+                    title: "Background".to_string(),
+                    physical_index: Some(serde_json::json!(1)),
+                    confidence: None,
+                },
+            ],
+            summaries: vec!["About intro".to_string(), "About background".to_string()],
+        };
+        save_checkpoint(&checkpoint, &path).unwrap();
+
+        // The client points nowhere, so any LLM call would fail the test
+        let client = LlmClient::new(crate::config::LlmConfig {
+            api_base: "http://127.0.0.1:9".to_string(),
+            ..Default::default()
+        });
+        let indexer =
+            TreeIndexer::with_options(client.clone(), options).with_checkpoint(&path, true);
+        let tree = indexer.index(&document).await.unwrap();
+
+        assert_eq!(tree.node_count(), 2);
+        assert_eq!(tree.nodes[0].summary.as_deref(), Some("About intro"));
+        assert_eq!(
+            tree.nodes[0].nodes[0].summary.as_deref(),
+            Some("About background")
+        );
+        assert_eq!(client.usage().calls, 0);
+        assert!(!path.exists());
     }
 }
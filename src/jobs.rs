@@ -0,0 +1,503 @@
+//! Background indexing jobs.
+//!
+//! A [`JobQueue`] takes documents, indexes them in the background (a few at
+//! a time) and keeps each job's state, progress and resulting tree, so a
+//! large document never holds up the caller:
+//!
+//! ```no_run
+//! use rust_page_indexer::{Document, LlmClient};
+//! use rust_page_indexer::config::Config;
+//! use rust_page_indexer::jobs::{JobQueue, JobState};
+//! use std::path::Path;
+//!
+//! # async fn run() -> rust_page_indexer::Result<()> {
+//! let queue = JobQueue::new(LlmClient::new(Config::load()?.llm));
+//! let job = queue.submit(Document::from_text_file(Path::new("report.txt"))?, None)?;
+//!
+//! // Later
+//! if let Some(job) = queue.get(&job.id)?
+//!     && job.state == JobState::Completed
+//! {
+//!     let tree = queue.tree(&job.id)?;
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! With [`JobQueue::with_dir`], jobs are also stored on disk
+//! (`<id>.json` for the job, `<id>.document.json` while it is pending and
+//! `<id>.tree.json` once it completes), so another process can run them and
+//! report on them. This is how the `jobs` command keeps indexing out of the
+//! terminal.
+
+use crate::document::Document;
+use crate::error::{PageIndexError, Result};
+use crate::indexer::{IndexerOptions, TreeIndexer};
+use crate::llm::LlmClient;
+use crate::persistence::{load_tree, save_tree};
+use crate::progress::ProgressEvent;
+use crate::tree::DocumentTree;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::sync::Semaphore;
+use tracing::{info, warn};
+
+/// Default number of jobs indexed at the same time.
+pub const DEFAULT_CONCURRENCY: usize = 2;
+
+/// Where a job is in its life.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+}
+
+impl JobState {
+    /// Whether the job has stopped, successfully or not.
+    pub fn is_finished(self) -> bool {
+        matches!(self, JobState::Completed | JobState::Failed)
+    }
+}
+
+/// Indexing progress of a job.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JobProgress {
+    pub chunks_completed: usize,
+    pub chunks_total: usize,
+    pub summaries_completed: usize,
+    pub summaries_total: usize,
+}
+
+/// An indexing job.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Job {
+    pub id: String,
+    /// Name of the document being indexed.
+    pub document: String,
+    pub state: JobState,
+    pub progress: JobProgress,
+    /// Why the job failed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Sections in the resulting tree.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sections: Option<usize>,
+    /// Where the tree is also saved when the job completes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output: Option<PathBuf>,
+    /// Unix timestamps (seconds).
+    pub submitted_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub started_at: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finished_at: Option<u64>,
+}
+
+struct Entry {
+    job: Job,
+    /// Dropped once the job has run.
+    document: Option<Document>,
+    tree: Option<DocumentTree>,
+}
+
+/// Submitted jobs and the client that indexes them.
+///
+/// Clones share the same jobs.
+#[derive(Clone)]
+pub struct JobQueue {
+    client: LlmClient,
+    options: IndexerOptions,
+    dir: Option<PathBuf>,
+    jobs: Arc<Mutex<HashMap<String, Entry>>>,
+    permits: Arc<Semaphore>,
+}
+
+impl JobQueue {
+    /// An in-memory queue indexing with the given client.
+    pub fn new(client: LlmClient) -> Self {
+        Self {
+            client,
+            options: IndexerOptions::default(),
+            dir: None,
+            jobs: Arc::new(Mutex::new(HashMap::new())),
+            permits: Arc::new(Semaphore::new(DEFAULT_CONCURRENCY)),
+        }
+    }
+
+    /// Set the options used when indexing.
+    pub fn with_options(mut self, options: IndexerOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Also store jobs in `dir`, and find jobs stored there by other
+    /// processes.
+    pub fn with_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dir = Some(dir.into());
+        self
+    }
+
+    /// Set how many jobs this queue indexes at the same time.
+    pub fn with_concurrency(mut self, jobs: usize) -> Self {
+        self.permits = Arc::new(Semaphore::new(jobs.max(1)));
+        self
+    }
+
+    /// The per-user directory the `jobs` command stores jobs in.
+    pub fn default_dir() -> Option<PathBuf> {
+        directories::ProjectDirs::from("", "", "rust-page-indexer")
+            .map(|dirs| dirs.data_local_dir().join("jobs"))
+    }
+
+    /// Queue a document and start indexing it in the background (on the
+    /// current tokio runtime). When `output` is set, the tree is saved there
+    /// too.
+    pub fn submit(&self, document: Document, output: Option<PathBuf>) -> Result<Job> {
+        let job = self.enqueue(document, output)?;
+        let queue = self.clone();
+        let id = job.id.clone();
+        tokio::spawn(async move {
+            if let Err(e) = queue.run(&id).await {
+                warn!(job = %id, error = %e, "job could not run");
+            }
+        });
+        Ok(job)
+    }
+
+    /// Queue a document without starting it; see [`run`](Self::run).
+    pub fn enqueue(&self, document: Document, output: Option<PathBuf>) -> Result<Job> {
+        let job = Job {
+            id: new_id(),
+            document: document.name.clone(),
+            state: JobState::Queued,
+            progress: JobProgress::default(),
+            error: None,
+            sections: None,
+            output,
+            submitted_at: now(),
+            started_at: None,
+            finished_at: None,
+        };
+        if let Some(dir) = &self.dir {
+            std::fs::create_dir_all(dir).map_err(|e| PageIndexError::io(dir, e))?;
+            let path = dir.join(format!("{}.document.json", job.id));
+            let json = serde_json::to_string(&document)?;
+            std::fs::write(&path, json).map_err(|e| PageIndexError::io(&path, e))?;
+            self.store(&job)?;
+        }
+        info!(job = %job.id, document = %job.document, "job queued");
+        self.lock().insert(
+            job.id.clone(),
+            Entry {
+                job: job.clone(),
+                document: Some(document),
+                tree: None,
+            },
+        );
+        Ok(job)
+    }
+
+    /// Index a queued job, waiting for a free slot first. Indexing failures
+    /// are recorded in the returned job; an error means the job could not
+    /// be run at all (unknown, already run, or unreadable).
+    pub async fn run(&self, id: &str) -> Result<Job> {
+        let _permit = self
+            .permits
+            .acquire()
+            .await
+            .map_err(|e| PageIndexError::Config(e.to_string()))?;
+
+        let document = self.start(id)?;
+        let jobs = self.jobs.clone();
+        let dir = self.dir.clone();
+        let job_id = id.to_string();
+        let progress = Arc::new(move |event: &ProgressEvent| {
+            let mut jobs = jobs.lock().expect("jobs lock poisoned");
+            let Some(entry) = jobs.get_mut(&job_id) else {
+                return;
+            };
+            let progress = &mut entry.job.progress;
+            match event {
+                ProgressEvent::ChunkProcessed { completed, total } => {
+                    progress.chunks_completed = *completed;
+                    progress.chunks_total = *total;
+                }
+                ProgressEvent::SummaryGenerated {
+                    completed, total, ..
+                } => {
+                    progress.summaries_completed = *completed;
+                    progress.summaries_total = *total;
+                }
+                _ => return,
+            }
+            if let Some(dir) = &dir
+                && let Err(e) = write_job(dir, &entry.job)
+            {
+                warn!(job = %job_id, error = %e, "failed to store job progress");
+            }
+        });
+
+        let indexer = TreeIndexer::with_options(self.client.clone(), self.options.clone())
+            .with_progress(progress);
+        let result = indexer.index(&document).await;
+        self.finish(id, result)
+    }
+
+    /// Mark a queued job as running and take its document.
+    fn start(&self, id: &str) -> Result<Document> {
+        let mut jobs = self.lock();
+        if !jobs.contains_key(id) {
+            let entry = self.load_entry(id)?;
+            jobs.insert(id.to_string(), entry);
+        }
+        let entry = jobs.get_mut(id).expect("job just inserted");
+        if entry.job.state != JobState::Queued {
+            return Err(PageIndexError::Config(format!(
+                "Job {} is {:?}, not queued",
+                id, entry.job.state
+            )));
+        }
+        let document = entry.document.take().ok_or_else(|| {
+            PageIndexError::Config(format!("Job {} has no document to index", id))
+        })?;
+        entry.job.state = JobState::Running;
+        entry.job.started_at = Some(now());
+        let job = entry.job.clone();
+        drop(jobs);
+        self.store(&job)?;
+        info!(job = %id, document = %job.document, "job started");
+        Ok(document)
+    }
+
+    /// Record the outcome of a job and save its tree.
+    fn finish(&self, id: &str, result: Result<DocumentTree>) -> Result<Job> {
+        let mut result = result;
+        let output = self.lock().get(id).and_then(|e| e.job.output.clone());
+        if let Ok(tree) = &result {
+            let saved = self
+                .dir
+                .iter()
+                .map(|dir| dir.join(format!("{}.tree.json", id)))
+                .chain(output)
+                .try_for_each(|path| save_tree(tree, &path));
+            if let Err(e) = saved {
+                result = Err(e);
+            }
+        }
+
+        let mut jobs = self.lock();
+        let entry = jobs.get_mut(id).expect("running job is tracked");
+        entry.job.finished_at = Some(now());
+        match result {
+            Ok(tree) => {
+                info!(job = %id, sections = tree.node_count(), "job completed");
+                entry.job.state = JobState::Completed;
+                entry.job.sections = Some(tree.node_count());
+                entry.tree = Some(tree);
+            }
+            Err(e) => {
+                warn!(job = %id, error = %e, "job failed");
+                entry.job.state = JobState::Failed;
+                entry.job.error = Some(e.to_string());
+            }
+        }
+        let job = entry.job.clone();
+        drop(jobs);
+        self.store(&job)?;
+        if let Some(dir) = &self.dir {
+            let path = dir.join(format!("{}.document.json", id));
+            let _ = std::fs::remove_file(path);
+        }
+        Ok(job)
+    }
+
+    /// A job by ID.
+    pub fn get(&self, id: &str) -> Result<Option<Job>> {
+        if let Some(entry) = self.lock().get(id) {
+            return Ok(Some(entry.job.clone()));
+        }
+        match self.job_path(id) {
+            Some(path) if path.exists() => read_job(&path).map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// All jobs, oldest first.
+    pub fn list(&self) -> Result<Vec<Job>> {
+        let mut jobs: HashMap<String, Job> = HashMap::new();
+        if let Some(dir) = self.dir.as_ref().filter(|d| d.exists()) {
+            let entries = std::fs::read_dir(dir).map_err(|e| PageIndexError::io(dir, e))?;
+            for path in entries.filter_map(|e| e.ok().map(|e| e.path())) {
+                let is_job = path
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .and_then(|n| n.strip_suffix(".json"))
+                    .is_some_and(valid_id);
+                if is_job {
+                    let job = read_job(&path)?;
+                    jobs.insert(job.id.clone(), job);
+                }
+            }
+        }
+        for entry in self.lock().values() {
+            jobs.insert(entry.job.id.clone(), entry.job.clone());
+        }
+        let mut jobs: Vec<Job> = jobs.into_values().collect();
+        jobs.sort_by(|a, b| (a.submitted_at, &a.id).cmp(&(b.submitted_at, &b.id)));
+        Ok(jobs)
+    }
+
+    /// The tree a job produced, once it has completed.
+    pub fn tree(&self, id: &str) -> Result<Option<DocumentTree>> {
+        if let Some(tree) = self.lock().get(id).and_then(|e| e.tree.clone()) {
+            return Ok(Some(tree));
+        }
+        match (&self.dir, valid_id(id)) {
+            (Some(dir), true) => {
+                let path = dir.join(format!("{}.tree.json", id));
+                if path.exists() {
+                    load_tree(&path).map(Some)
+                } else {
+                    Ok(None)
+                }
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Read a job stored by another process.
+    fn load_entry(&self, id: &str) -> Result<Entry> {
+        let unknown = || PageIndexError::Config(format!("No job with ID '{}'", id));
+        let path = self
+            .job_path(id)
+            .filter(|p| p.exists())
+            .ok_or_else(unknown)?;
+        let job = read_job(&path)?;
+        let document_path = path.with_file_name(format!("{}.document.json", id));
+        let document = if document_path.exists() {
+            let json = std::fs::read_to_string(&document_path)
+                .map_err(|e| PageIndexError::io(&document_path, e))?;
+            Some(serde_json::from_str(&json)?)
+        } else {
+            None
+        };
+        Ok(Entry {
+            job,
+            document,
+            tree: None,
+        })
+    }
+
+    fn job_path(&self, id: &str) -> Option<PathBuf> {
+        let dir = self.dir.as_ref()?;
+        valid_id(id).then(|| dir.join(format!("{}.json", id)))
+    }
+
+    fn store(&self, job: &Job) -> Result<()> {
+        match &self.dir {
+            Some(dir) => write_job(dir, job),
+            None => Ok(()),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<String, Entry>> {
+        self.jobs.lock().expect("jobs lock poisoned")
+    }
+}
+
+/// Write a job file, replacing it in one step so readers in other
+/// processes never see a partial file.
+fn write_job(dir: &Path, job: &Job) -> Result<()> {
+    let path = dir.join(format!("{}.json", job.id));
+    let tmp = dir.join(format!("{}.json.tmp", job.id));
+    let json = serde_json::to_string_pretty(job)?;
+    std::fs::write(&tmp, json).map_err(|e| PageIndexError::io(&tmp, e))?;
+    std::fs::rename(&tmp, &path).map_err(|e| PageIndexError::io(&path, e))
+}
+
+fn read_job(path: &Path) -> Result<Job> {
+    let json = std::fs::read_to_string(path).map_err(|e| PageIndexError::io(path, e))?;
+    Ok(serde_json::from_str(&json)?)
+}
+
+/// IDs become file names, so only accept ones [`new_id`] could make.
+fn valid_id(id: &str) -> bool {
+    !id.is_empty() && id.chars().all(|c| c.is_ascii_hexdigit() || c == '-')
+}
+
+/// A unique, roughly time-ordered job ID.
+fn new_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis());
+    let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+    format!("{:x}-{:x}-{:x}", millis, std::process::id(), count)
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{Fixture, offline_client};
+
+    #[tokio::test]
+    async fn test_failed_job_is_recorded() {
+        let dir = tempfile::tempdir().unwrap();
+        // Indexing fails at the first LLM call
+        let client = offline_client();
+        let queue = JobQueue::new(client.clone()).with_dir(dir.path());
+        let document = Document::from_text("report", "Introduction\nText.".to_string());
+        let job = queue.enqueue(document, None).unwrap();
+        assert_eq!(job.state, JobState::Queued);
+
+        // Another process sees the queued job and can run it
+        let other = JobQueue::new(client).with_dir(dir.path());
+        assert_eq!(other.get(&job.id).unwrap().unwrap().state, JobState::Queued);
+        let finished = other.run(&job.id).await.unwrap();
+        assert_eq!(finished.state, JobState::Failed);
+        assert!(finished.error.unwrap().contains("HTTP request failed"));
+        assert!(other.run(&job.id).await.is_err());
+
+        assert_eq!(queue.list().unwrap().len(), 1);
+        assert!(queue.tree(&job.id).unwrap().is_none());
+        assert!(queue.get("../etc").unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_completed_job() {
+        let fixtures = Path::new(env!("CARGO_MANIFEST_DIR")).join("data/fixtures");
+        let fixture = Fixture::replay(fixtures.join("sample.fixture.json")).unwrap();
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("sample.json");
+
+        let queue = JobQueue::new(fixture.client().unwrap());
+        let document = Document::from_text_file(&fixtures.join("sample.txt")).unwrap();
+        let job = queue.submit(document, Some(output.clone())).unwrap();
+        let job = loop {
+            let job = queue.get(&job.id).unwrap().unwrap();
+            if job.state.is_finished() {
+                break job;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        };
+
+        assert_eq!(job.state, JobState::Completed, "{:?}", job.error);
+        assert_eq!(job.sections, Some(6));
+        assert_eq!(job.progress.summaries_completed, 6);
+        assert_eq!(queue.tree(&job.id).unwrap().unwrap().node_count(), 6);
+        assert_eq!(load_tree(&output).unwrap().node_count(), 6);
+    }
+}
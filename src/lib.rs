@@ -15,47 +15,33 @@
 //! # Quick Start
 //!
 //! ```no_run
-//! use rust_page_indexer::{
-//!     config::Config,
-//!     document::Document,
-//!     indexer::TreeIndexer,
-//!     search::TreeSearcher,
-//!     llm::LlmClient,
-//!     persistence::{save_tree, load_tree},
-//! };
-//! use std::path::Path;
+//! use rust_page_indexer::PageIndex;
 //!
 //! #[tokio::main]
 //! async fn main() -> anyhow::Result<()> {
-//!     // Load configuration
-//!     let config = Config::load()?;
-//!     config.validate()?;
+//!     // Configuration comes from the environment or config file
+//!     let mut index = PageIndex::builder().build()?;
 //!
-//!     // Create LLM client
-//!     let client = LlmClient::new(config.llm.clone());
-//!
-//!     // Load a document
-//!     let document = Document::from_text_file(Path::new("document.txt"))?;
-//!
-//!     // Build tree index
-//!     let indexer = TreeIndexer::new(client.clone());
-//!     let tree = indexer.index(&document).await?;
-//!
-//!     // Save the tree for later use
-//!     save_tree(&tree, Path::new("tree_index.json"))?;
+//!     // Build a tree index and save it for later use
+//!     index.index_file("document.txt").await?;
+//!     index.save("tree_index.json")?;
 //!
 //!     // Search the tree
-//!     let searcher = TreeSearcher::new(client);
-//!     let results = searcher.search(&tree, "your query here").await?;
-//!
-//!     for result in results {
+//!     for result in index.search("your query here").await? {
 //!         println!("{}: {:?}", result.title, result.relevance);
 //!     }
 //!
+//!     // Or answer a question from the retrieved sections
+//!     let answer = index.ask("your question here").await?;
+//!     println!("{}", answer.answer);
+//!
 //!     Ok(())
 //! }
 //! ```
 //!
+//! [`PageIndex`] wraps the lower-level types below; use them directly for
+//! checkpoints, custom options per call, or several trees at once.
+//!
 //! # Architecture
 //!
 //! - **Document**: Page-based document representation
@@ -63,25 +49,100 @@
 //! - **TreeIndexer**: Builds hierarchical tree structure from documents
 //! - **TreeSearcher**: Searches trees using LLM reasoning
 //! - **DocumentTree**: The hierarchical tree structure
+//! - **PageIndex**: Facade tying the above together
+//!
+//! # WebAssembly
+//!
+//! The tree, document, search and LLM client modules also build for
+//! `wasm32-unknown-unknown`, where the client sends requests with the
+//! browser's `fetch`. Modules needing a filesystem watcher, a tokio runtime
+//! or wall-clock timing (`batch`, `logging`, `usage`, `watch`) are native
+//! only. With the `wasm` feature, the `wasm` module exposes JavaScript bindings:
+//!
+//! ```text
+//! cargo build --lib --target wasm32-unknown-unknown --features wasm
+//! wasm-bindgen --target web target/wasm32-unknown-unknown/debug/rust_page_indexer.wasm --out-dir pkg
+//! ```
 
+#[cfg(not(target_arch = "wasm32"))]
+pub mod audit;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod batch;
+#[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+pub mod blocking;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod cache;
+pub mod checkpoint;
 pub mod config;
 pub mod document;
+pub mod edit;
 pub mod error;
+pub mod export;
+pub mod extract;
+pub mod fixtures;
+pub mod golden;
+#[cfg(all(feature = "grpc", not(target_arch = "wasm32")))]
+pub mod grpc;
+pub mod import;
 pub mod indexer;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod jobs;
 pub mod llm;
+pub mod loader;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod logging;
+#[cfg(all(feature = "serve", not(target_arch = "wasm32")))]
+pub mod metrics;
+#[cfg(all(feature = "node", not(target_arch = "wasm32")))]
+pub mod node;
+pub mod outcome;
+pub mod page_index;
 pub mod persistence;
+pub mod progress;
+pub mod query;
+pub mod retriever;
+pub mod router;
 pub mod search;
+#[cfg(all(feature = "serve", not(target_arch = "wasm32")))]
+pub mod serve;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod stats;
+pub mod store;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod stream;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod summarize;
+pub mod telemetry;
 pub mod tree;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod tree_cache;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod usage;
+pub mod validate;
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+pub mod wasm;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod watch;
 
 #[cfg(feature = "eval")]
 pub mod eval;
 
+#[cfg(all(feature = "ffi", not(target_arch = "wasm32")))]
+pub mod ffi;
+
 // Re-export commonly used types
 pub use config::Config;
 pub use document::Document;
 pub use error::{PageIndexError, Result};
+pub use export::{ExportFormat, export_tree};
 pub use indexer::TreeIndexer;
 pub use llm::LlmClient;
+pub use page_index::{Answer, AnswerStyle, PageIndex};
 pub use persistence::{load_tree, save_tree};
+pub use progress::{ProgressCallback, ProgressEvent};
 pub use search::{SearchResult, TreeSearcher};
 pub use tree::{DocumentTree, TreeNode};
+#[cfg(not(target_arch = "wasm32"))]
+pub use usage::{UsageReport, UsageTracker};
+#[cfg(not(target_arch = "wasm32"))]
+pub use watch::DirectoryWatcher;
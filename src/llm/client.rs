@@ -5,8 +5,15 @@
 
 use crate::config::LlmConfig;
 use crate::error::{PageIndexError, Result};
-use reqwest::Client;
+use crate::fixtures::{Fixture, FixtureMode};
+use crate::golden::PromptCapture;
+use crate::telemetry;
+use reqwest::{Client, StatusCode};
 use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tracing::field::Empty;
+use tracing::{debug, instrument, warn};
 
 /// Message role in a conversation.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -85,6 +92,34 @@ struct Usage {
     total_tokens: u32,
 }
 
+/// Request body for embeddings.
+#[derive(Debug, Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [&'a str],
+}
+
+/// Response from embeddings.
+#[derive(Debug, Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+    #[serde(default)]
+    usage: Option<EmbeddingUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingData {
+    embedding: Vec<f32>,
+    #[serde(default)]
+    index: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingUsage {
+    prompt_tokens: u32,
+    total_tokens: u32,
+}
+
 /// OpenAI API error response.
 #[derive(Debug, Deserialize)]
 struct ApiError {
@@ -95,8 +130,17 @@ struct ApiError {
 struct ApiErrorDetail {
     message: String,
     #[serde(rename = "type")]
-    #[allow(dead_code)]
     error_type: Option<String>,
+    /// A string for OpenAI, a number for some other providers.
+    #[serde(default)]
+    code: Option<serde_json::Value>,
+}
+
+/// Status, body and `Retry-After` of an API response.
+struct RawResponse {
+    status: StatusCode,
+    body: String,
+    retry_after: Option<Duration>,
 }
 
 /// Response from an LLM call including metadata.
@@ -117,11 +161,71 @@ pub struct TokenUsage {
     pub total_tokens: u32,
 }
 
+/// Token usage accumulated across all calls made by a client.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageStats {
+    /// Number of successful LLM calls.
+    pub calls: u64,
+    /// Total prompt tokens.
+    pub prompt_tokens: u64,
+    /// Total completion tokens.
+    pub completion_tokens: u64,
+    /// Total tokens (prompt + completion).
+    pub total_tokens: u64,
+}
+
+impl UsageStats {
+    /// Add a single response's token usage.
+    fn record(&mut self, usage: Option<&TokenUsage>) {
+        self.calls += 1;
+        if let Some(usage) = usage {
+            self.prompt_tokens += usage.prompt_tokens as u64;
+            self.completion_tokens += usage.completion_tokens as u64;
+            self.total_tokens += usage.total_tokens as u64;
+        }
+    }
+
+    /// Usage accumulated since an earlier snapshot of the same counters.
+    pub fn since(&self, earlier: &UsageStats) -> UsageStats {
+        UsageStats {
+            calls: self.calls.saturating_sub(earlier.calls),
+            prompt_tokens: self.prompt_tokens.saturating_sub(earlier.prompt_tokens),
+            completion_tokens: self
+                .completion_tokens
+                .saturating_sub(earlier.completion_tokens),
+            total_tokens: self.total_tokens.saturating_sub(earlier.total_tokens),
+        }
+    }
+}
+
+impl std::ops::Add for UsageStats {
+    type Output = UsageStats;
+
+    /// Combine usage from separate clients.
+    fn add(self, other: UsageStats) -> UsageStats {
+        UsageStats {
+            calls: self.calls + other.calls,
+            prompt_tokens: self.prompt_tokens + other.prompt_tokens,
+            completion_tokens: self.completion_tokens + other.completion_tokens,
+            total_tokens: self.total_tokens + other.total_tokens,
+        }
+    }
+}
+
 /// OpenAI-compatible LLM client.
+///
+/// Clones share the same usage counters, so [`LlmClient::usage`] reports
+/// totals for every component the client was handed to. A
+/// [metered](LlmClient::metered) clone also counts its own calls.
 #[derive(Clone)]
 pub struct LlmClient {
     client: Client,
     config: LlmConfig,
+    usage: Arc<Mutex<UsageStats>>,
+    /// Counters of the clients this one was [metered](Self::metered) from.
+    parents: Vec<Arc<Mutex<UsageStats>>>,
+    fixture: Option<Fixture>,
+    capture: Option<PromptCapture>,
 }
 
 impl LlmClient {
@@ -130,6 +234,60 @@ impl LlmClient {
         Self {
             client: Client::new(),
             config,
+            usage: Arc::new(Mutex::new(UsageStats::default())),
+            parents: Vec::new(),
+            fixture: None,
+            capture: None,
+        }
+    }
+
+    /// Record requests into, or replay them from, a fixture (see
+    /// [`crate::fixtures`]).
+    pub fn with_fixture(mut self, fixture: Fixture) -> Self {
+        fixture.attach(&self.config);
+        self.fixture = Some(fixture);
+        self
+    }
+
+    /// Capture requests instead of sending them (see [`crate::golden`]).
+    pub fn with_prompt_capture(mut self, capture: PromptCapture) -> Self {
+        self.capture = Some(capture);
+        self
+    }
+
+    /// A client for another model on the same endpoint. It shares this
+    /// client's usage counters, so [`usage`](Self::usage) covers both.
+    pub fn with_model(&self, model: impl Into<String>) -> Self {
+        let mut client = self.clone();
+        client.config.model = model.into();
+        client
+    }
+
+    /// A clone with usage counters of its own, e.g. to measure one request
+    /// while others share the client. Its calls still count towards this
+    /// client's usage.
+    pub fn metered(&self) -> Self {
+        let mut client = self.clone();
+        client.parents.push(self.usage.clone());
+        client.usage = Arc::new(Mutex::new(UsageStats::default()));
+        client
+    }
+
+    /// Get the model name this client sends requests to.
+    pub fn model(&self) -> &str {
+        &self.config.model
+    }
+
+    /// Get accumulated token usage for this client and its clones.
+    pub fn usage(&self) -> UsageStats {
+        *self.usage.lock().expect("usage lock poisoned")
+    }
+
+    /// Add a response's token usage to this client's counters and those of
+    /// the clients it was metered from.
+    fn record_usage(&self, usage: Option<&TokenUsage>) {
+        for counters in std::iter::once(&self.usage).chain(&self.parents) {
+            counters.lock().expect("usage lock poisoned").record(usage);
         }
     }
 
@@ -139,42 +297,179 @@ impl LlmClient {
         format!("{}/v1/chat/completions", base)
     }
 
-    /// Send a chat completion request.
-    pub async fn chat(&self, messages: Vec<Message>) -> Result<LlmResponse> {
-        let request = ChatCompletionRequest {
-            model: self.config.model.clone(),
-            messages,
-            max_tokens: Some(self.config.max_tokens),
-            temperature: Some(self.config.temperature),
+    /// Get the embeddings endpoint URL.
+    fn embeddings_endpoint(&self) -> String {
+        let base = self.config.api_base.trim_end_matches('/');
+        format!("{}/v1/embeddings", base)
+    }
+
+    /// POST a JSON request and return the response, replayed from or
+    /// recorded into the fixture if there is one.
+    async fn post(
+        &self,
+        url: String,
+        endpoint: &str,
+        request: &impl Serialize,
+    ) -> Result<RawResponse> {
+        if let Some(capture) = &self.capture {
+            let (status, body) = capture.captured(endpoint, serde_json::to_value(request)?)?;
+            return Ok(RawResponse {
+                status: recorded_status(status)?,
+                body,
+                retry_after: None,
+            });
+        }
+
+        let fixture = self.fixture.as_ref();
+        let recorded = match fixture {
+            Some(_) => Some(serde_json::to_value(request)?),
+            None => None,
         };
+        if let (Some(fixture), Some(request)) = (fixture, &recorded)
+            && fixture.mode() == FixtureMode::Replay
+        {
+            let (status, body) = fixture.replayed(endpoint, request)?;
+            return Ok(RawResponse {
+                status: recorded_status(status)?,
+                body,
+                retry_after: None,
+            });
+        }
 
         let response = self
             .client
-            .post(self.endpoint())
+            .post(url)
             .header("Authorization", format!("Bearer {}", self.config.api_key))
             .header("Content-Type", "application/json")
-            .json(&request)
+            .json(request)
             .send()
             .await?;
 
         let status = response.status();
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.trim().parse::<f64>().ok())
+            .filter(|secs| secs.is_finite() && *secs >= 0.0)
+            .map(Duration::from_secs_f64);
         let body = response.text().await?;
 
-        if !status.is_success() {
-            // Try to parse as API error
-            if let Ok(api_error) = serde_json::from_str::<ApiError>(&body) {
-                return Err(PageIndexError::LlmApi(format!(
-                    "API error ({}): {}",
-                    status, api_error.error.message
-                )));
-            }
-            return Err(PageIndexError::LlmApi(format!(
-                "Request failed ({}): {}",
-                status, body
-            )));
+        if let (Some(fixture), Some(request)) = (fixture, recorded) {
+            fixture.recorded(endpoint, request, status.as_u16(), &body);
         }
+        Ok(RawResponse {
+            status,
+            body,
+            retry_after,
+        })
+    }
 
-        let completion: ChatCompletionResponse = serde_json::from_str(&body)?;
+    /// Build the error for a non-success API response, typed by the status
+    /// and the provider's error type or code.
+    fn api_error(response: &RawResponse) -> PageIndexError {
+        let RawResponse {
+            status,
+            body,
+            retry_after,
+        } = response;
+        warn!(status = %status, "LLM request failed");
+
+        let detail = serde_json::from_str::<ApiError>(body).ok().map(|e| e.error);
+        let message = match &detail {
+            Some(detail) => format!("API error ({}): {}", status, detail.message),
+            None => format!("Request failed ({}): {}", status, body),
+        };
+        // OpenAI puts it in `code`, Anthropic-style proxies in `type`
+        let code = detail
+            .as_ref()
+            .and_then(|d| {
+                d.code
+                    .as_ref()
+                    .and_then(|c| c.as_str())
+                    .or(d.error_type.as_deref())
+            })
+            .unwrap_or_default();
+        let lower = message.to_lowercase();
+
+        if code == "context_length_exceeded"
+            || status == &StatusCode::PAYLOAD_TOO_LARGE
+            || lower.contains("context length")
+            || lower.contains("context window")
+            || lower.contains("prompt is too long")
+        {
+            return PageIndexError::ContextLengthExceeded(message);
+        }
+        // Running out of quota is also a 429, but waiting will not help
+        if code == "insufficient_quota" {
+            return PageIndexError::LlmApi(message);
+        }
+        match status.as_u16() {
+            429 => PageIndexError::RateLimited {
+                retry_after: retry_after.or_else(|| retry_after_in(&lower)),
+                message,
+            },
+            401 | 403 => PageIndexError::AuthFailed(message),
+            408 | 504 => PageIndexError::Timeout(message),
+            500..=599 => PageIndexError::ServerError {
+                status: status.as_u16(),
+                message,
+            },
+            _ => match code {
+                "rate_limit_error" | "rate_limit_exceeded" => PageIndexError::RateLimited {
+                    retry_after: retry_after.or_else(|| retry_after_in(&lower)),
+                    message,
+                },
+                "authentication_error" | "permission_error" | "invalid_api_key" => {
+                    PageIndexError::AuthFailed(message)
+                }
+                _ => PageIndexError::LlmApi(message),
+            },
+        }
+    }
+
+    /// Send a chat completion request.
+    #[instrument(
+        name = "llm.chat",
+        skip_all,
+        fields(
+            model = %self.config.model,
+            prompt_tokens = Empty,
+            completion_tokens = Empty,
+            otel.status_code = Empty,
+        )
+    )]
+    pub async fn chat(&self, messages: Vec<Message>) -> Result<LlmResponse> {
+        let result = self.send_chat(messages).await;
+        match &result {
+            Ok(response) => telemetry::record_llm_call("chat", response.usage.as_ref()),
+            Err(e) => telemetry::record_error("llm.chat", e),
+        }
+        result
+    }
+
+    async fn send_chat(&self, messages: Vec<Message>) -> Result<LlmResponse> {
+        debug!(
+            model = %self.config.model,
+            messages = messages.len(),
+            prompt_chars = messages.iter().map(|m| m.content.len()).sum::<usize>(),
+            "sending chat completion request"
+        );
+
+        let request = ChatCompletionRequest {
+            model: self.config.model.clone(),
+            messages,
+            max_tokens: Some(self.config.max_tokens),
+            temperature: Some(self.config.temperature),
+        };
+
+        let response = self.post(self.endpoint(), "chat", &request).await?;
+
+        if !response.status.is_success() {
+            return Err(Self::api_error(&response));
+        }
+
+        let completion: ChatCompletionResponse = serde_json::from_str(&response.body)?;
 
         let choice = completion
             .choices
@@ -182,14 +477,25 @@ impl LlmClient {
             .next()
             .ok_or_else(|| PageIndexError::LlmApi("No choices in response".to_string()))?;
 
+        let usage = completion.usage.map(|u| TokenUsage {
+            prompt_tokens: u.prompt_tokens,
+            completion_tokens: u.completion_tokens,
+            total_tokens: u.total_tokens,
+        });
+
+        debug!(
+            prompt_tokens = usage.as_ref().map(|u| u.prompt_tokens),
+            completion_tokens = usage.as_ref().map(|u| u.completion_tokens),
+            finish_reason = choice.finish_reason.as_deref(),
+            "received chat completion"
+        );
+
+        self.record_usage(usage.as_ref());
+
         Ok(LlmResponse {
             content: choice.message.content,
             finish_reason: choice.finish_reason,
-            usage: completion.usage.map(|u| TokenUsage {
-                prompt_tokens: u.prompt_tokens,
-                completion_tokens: u.completion_tokens,
-                total_tokens: u.total_tokens,
-            }),
+            usage,
         })
     }
 
@@ -206,6 +512,75 @@ impl LlmClient {
         Ok(response.content)
     }
 
+    /// Embed texts with the configured model via the `/v1/embeddings`
+    /// endpoint, returning one vector per input in input order.
+    #[instrument(
+        name = "llm.embed",
+        skip_all,
+        fields(
+            model = %self.config.model,
+            inputs = inputs.len(),
+            prompt_tokens = Empty,
+            otel.status_code = Empty,
+        )
+    )]
+    pub async fn embed(&self, inputs: &[&str]) -> Result<Vec<Vec<f32>>> {
+        if inputs.is_empty() {
+            return Ok(Vec::new());
+        }
+        match self.send_embed(inputs).await {
+            Ok((embeddings, usage)) => {
+                telemetry::record_llm_call("embed", usage.as_ref());
+                Ok(embeddings)
+            }
+            Err(e) => {
+                telemetry::record_error("llm.embed", &e);
+                Err(e)
+            }
+        }
+    }
+
+    async fn send_embed(&self, inputs: &[&str]) -> Result<(Vec<Vec<f32>>, Option<TokenUsage>)> {
+        debug!(
+            model = %self.config.model,
+            inputs = inputs.len(),
+            "sending embeddings request"
+        );
+
+        let request = EmbeddingRequest {
+            model: &self.config.model,
+            input: inputs,
+        };
+
+        let raw = self
+            .post(self.embeddings_endpoint(), "embeddings", &request)
+            .await?;
+
+        if !raw.status.is_success() {
+            return Err(Self::api_error(&raw));
+        }
+
+        let mut response: EmbeddingResponse = serde_json::from_str(&raw.body)?;
+        if response.data.len() != inputs.len() {
+            return Err(PageIndexError::LlmApi(format!(
+                "Expected {} embeddings, got {}",
+                inputs.len(),
+                response.data.len()
+            )));
+        }
+        response.data.sort_by_key(|d| d.index);
+
+        let usage = response.usage.map(|u| TokenUsage {
+            prompt_tokens: u.prompt_tokens,
+            completion_tokens: 0,
+            total_tokens: u.total_tokens,
+        });
+        self.record_usage(usage.as_ref());
+
+        let embeddings = response.data.into_iter().map(|d| d.embedding).collect();
+        Ok((embeddings, usage))
+    }
+
     /// Test connectivity to the API.
     pub async fn test_connection(&self) -> Result<()> {
         let messages = vec![Message::user("Say 'hello' and nothing else.")];
@@ -223,6 +598,28 @@ impl LlmClient {
     }
 }
 
+fn recorded_status(status: u16) -> Result<StatusCode> {
+    StatusCode::from_u16(status)
+        .map_err(|e| PageIndexError::LlmApi(format!("Bad recorded status: {}", e)))
+}
+
+/// Wait time from messages like "Please try again in 1.5s" or "... in
+/// 120ms", which some providers send instead of a `Retry-After` header.
+fn retry_after_in(message: &str) -> Option<Duration> {
+    let rest = &message[message.find("try again in ")? + "try again in ".len()..];
+    let end = rest
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(rest.len());
+    let value: f64 = rest[..end].parse().ok()?;
+    let seconds = match rest[end..].trim_start() {
+        unit if unit.starts_with("ms") => value / 1000.0,
+        unit if unit.starts_with('s') => value,
+        unit if unit.starts_with('m') => value * 60.0,
+        _ => return None,
+    };
+    Some(Duration::from_secs_f64(seconds))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -238,6 +635,43 @@ mod tests {
         assert!(matches!(assistant.role, Role::Assistant));
     }
 
+    #[test]
+    fn test_usage_is_shared_between_clones() {
+        let client = LlmClient::new(LlmConfig::default());
+        let clone = client.clone();
+
+        clone.usage.lock().unwrap().record(Some(&TokenUsage {
+            prompt_tokens: 10,
+            completion_tokens: 5,
+            total_tokens: 15,
+        }));
+        client.usage.lock().unwrap().record(None);
+
+        let usage = client.usage();
+        assert_eq!(usage.calls, 2);
+        assert_eq!(usage.prompt_tokens, 10);
+        assert_eq!(usage.total_tokens, 15);
+    }
+
+    #[test]
+    fn test_metered_usage() {
+        let client = LlmClient::new(LlmConfig::default());
+        let metered = client.metered();
+        let nested = metered.with_model("other").metered();
+
+        nested.record_usage(Some(&TokenUsage {
+            prompt_tokens: 10,
+            completion_tokens: 5,
+            total_tokens: 15,
+        }));
+        client.record_usage(None);
+
+        assert_eq!(nested.usage().total_tokens, 15);
+        assert_eq!(metered.usage().calls, 1);
+        assert_eq!(client.usage().calls, 2);
+        assert_eq!(client.usage().total_tokens, 15);
+    }
+
     #[test]
     fn test_endpoint_construction() {
         let config = LlmConfig {
@@ -264,5 +698,74 @@ mod tests {
             client2.endpoint(),
             "https://api.example.com/v1/chat/completions"
         );
+        assert_eq!(
+            client2.embeddings_endpoint(),
+            "https://api.example.com/v1/embeddings"
+        );
+    }
+
+    #[test]
+    fn test_api_error_taxonomy() {
+        let error = |status: u16, body: &str, retry_after: Option<u64>| {
+            LlmClient::api_error(&RawResponse {
+                status: StatusCode::from_u16(status).unwrap(),
+                body: body.to_string(),
+                retry_after: retry_after.map(Duration::from_secs),
+            })
+        };
+        let openai = |message: &str, code: &str| {
+            format!(
+                r#"{{"error": {{"message": "{}", "type": "invalid_request_error", "code": "{}"}}}}"#,
+                message, code
+            )
+        };
+
+        let err = error(429, &openai("Slow down", "rate_limit_exceeded"), Some(7));
+        assert!(matches!(
+            err,
+            PageIndexError::RateLimited { retry_after: Some(d), .. } if d == Duration::from_secs(7)
+        ));
+        // Without a header, the wait comes from the message
+        let err = error(429, &openai("Please try again in 1.5s.", ""), None);
+        assert_eq!(err.retry_after(), Some(Duration::from_millis(1500)));
+        let err = error(429, &openai("Quota used up", "insufficient_quota"), None);
+        assert!(matches!(err, PageIndexError::LlmApi(_)));
+
+        let err = error(
+            400,
+            &openai(
+                "This model's maximum context length is 8192 tokens",
+                "context_length_exceeded",
+            ),
+            None,
+        );
+        assert!(matches!(err, PageIndexError::ContextLengthExceeded(_)));
+        let anthropic = r#"{"type": "error", "error": {"type": "invalid_request_error", "message": "prompt is too long"}}"#;
+        assert!(matches!(
+            error(400, anthropic, None),
+            PageIndexError::ContextLengthExceeded(_)
+        ));
+
+        assert!(matches!(
+            error(401, &openai("Incorrect API key", "invalid_api_key"), None),
+            PageIndexError::AuthFailed(_)
+        ));
+        assert!(matches!(error(504, "", None), PageIndexError::Timeout(_)));
+        let err = error(502, "<html>Bad Gateway</html>", None);
+        assert!(matches!(
+            err,
+            PageIndexError::ServerError { status: 502, .. }
+        ));
+        assert!(err.to_string().contains("Bad Gateway"));
+        assert!(matches!(
+            error(400, &openai("Unknown model", "model_not_found"), None),
+            PageIndexError::LlmApi(_)
+        ));
+
+        assert_eq!(
+            retry_after_in("try again in 120ms"),
+            Some(Duration::from_millis(120))
+        );
+        assert_eq!(retry_after_in("try again later"), None);
     }
 }
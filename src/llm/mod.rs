@@ -6,5 +6,6 @@
 mod client;
 mod prompts;
 
-pub use client::LlmClient;
+pub(crate) use client::TokenUsage;
+pub use client::{LlmClient, UsageStats};
 pub use prompts::Prompts;
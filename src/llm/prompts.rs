@@ -113,6 +113,39 @@ Reply format:
 Directly return the final JSON structure. Do not output anything else."#
     }
 
+    /// Prompt to find the date a section took effect on its start page.
+    pub fn find_effective_date() -> &'static str {
+        r#"Your job is to find the date the given section of a document took effect: its effective date, the date it is in force from, or failing those the date it was adopted or last revised.
+
+The given section title is {title}.
+The given page_text, where the section starts, is {page_text}.
+
+Reply format:
+{
+    "thinking": <where the page_text gives a date for this section, if anywhere>
+    "effective_date": "YYYY-MM-DD" or null (null if the page_text gives no date for this section; use the first day of the month or year when only those are given)
+}
+Directly return the final JSON structure. Do not output anything else."#
+    }
+
+    /// Prompt to title a passage of a document without headings.
+    pub fn title_passage() -> &'static str {
+        r#"You are given a passage of a document that has no headings of its own, such as part of a transcript or chat log. Your job is to give it a short, descriptive title (at most 8 words) naming what it is mainly about, as it would appear in a table of contents.
+
+The given passage is {content}.
+
+Reply format:
+{
+    "title": <the title>
+}
+Directly return the final JSON structure. Do not output anything else."#
+    }
+
+    /// Instruction added to the TOC prompts to have the LLM rate each item.
+    pub fn toc_confidence() -> &'static str {
+        r#"For each item, also give "confidence": how sure you are, from 0 to 1, that the title and physical_index are correct. Use a low value for titles you had to guess or pages you could not locate exactly."#
+    }
+
     /// Prompt to fix incorrect TOC item page index.
     pub fn single_toc_item_index_fixer() -> &'static str {
         r#"You are given a section title and several pages of a document, your job is to find the physical index of the start page of the section in the partial document.
@@ -161,6 +194,155 @@ Order sections by relevance (most relevant first).
 Directly return the final JSON structure. Do not output anything else."#
     }
 
+    /// Prompt to find relevant sections in a tree without summaries,
+    /// from titles and page ranges alone.
+    pub fn tree_search_titles() -> &'static str {
+        r#"You are an expert at navigating hierarchical document structures to find relevant information.
+
+You are given:
+1. A query/question from the user
+2. A hierarchical tree structure of a document with section titles and page indices, but no section summaries
+
+Your task is to identify which sections are most likely to contain information relevant to the query. Only the titles are known, so judge each section by its title, its place in the hierarchy and its page range. When a title is vague, include a plausible section at lower relevance rather than leave it out.
+
+Tree structure:
+{tree_structure}
+
+User query: {query}
+
+Reply in JSON format:
+{
+    "thinking": <explain your reasoning about which sections are relevant and why>,
+    "relevant_sections": [
+        {
+            "title": <section title>,
+            "start_index": <page number where section starts>,
+            "end_index": <page number where section ends>,
+            "relevance": <"high", "medium", or "low">,
+            "reason": <why this section is relevant to the query>
+        },
+        ...
+    ]
+}
+
+Order sections by relevance (most relevant first).
+Directly return the final JSON structure. Do not output anything else."#
+    }
+
+    /// Prompt to check that search results' content addresses the query.
+    pub fn verify_search_results() -> &'static str {
+        r#"You are checking the results of a search over a document. Each section below was selected from its title and summary alone. Read its content and judge whether it actually addresses the query.
+
+User query: {query}
+
+Sections:
+{sections}
+
+Reply in JSON format:
+{
+    "verdicts": [
+        {
+            "section": <section number>,
+            "addresses_query": <"yes", "partly", or "no">,
+            "reason": <what in the content does or does not address the query>
+        },
+        ...
+    ]
+}
+
+Give one verdict per section.
+Directly return the final JSON structure. Do not output anything else."#
+    }
+
+    /// Prompt to rank a search shortlist by content and quote the part of
+    /// each section that answers the query.
+    pub fn rank_shortlist() -> &'static str {
+        r#"You are ranking the candidate sections of a search over a document. The sections below were shortlisted from their titles and summaries. Read their content, decide which best answer the query, and quote from each the passage that answers it.
+
+User query: {query}
+
+Sections:
+{sections}
+
+Reply in JSON format:
+{
+    "ranking": [
+        {
+            "section": <section number>,
+            "relevance": <"high", "medium", "low", or "none">,
+            "snippet": <the sentence or two of the content that answer the query, quoted exactly>
+        },
+        ...
+    ]
+}
+
+List sections from best to worst answer. Leave out sections, or mark them "none", that do not address the query.
+Directly return the final JSON structure. Do not output anything else."#
+    }
+
+    /// Prompt to rephrase a query for multi-query search.
+    pub fn paraphrase_query() -> &'static str {
+        r#"You are helping search a document. Rewrite the user's query in {count} different ways that ask for the same information, using other words, synonyms, or the terms a document on the subject would use. Make vague queries more specific where the intent is clear.
+
+User query: {query}
+
+Reply in JSON format:
+{
+    "paraphrases": [<rewritten query>, ...]
+}
+
+Directly return the final JSON structure. Do not output anything else."#
+    }
+
+    /// Prompt to pinpoint the paragraphs of a section that answer a query.
+    pub fn zoom_section() -> &'static str {
+        r#"You are looking for the exact passages of a document section that answer a query. The section is split into numbered paragraphs, each labelled with its page.
+
+User query: {query}
+
+Section: {section}
+
+Paragraphs:
+{paragraphs}
+
+Reply in JSON format:
+{
+    "thinking": <where in the section the answer is, and why>,
+    "passages": [
+        {
+            "page": <page number>,
+            "paragraph": <paragraph number within the page>,
+            "relevance": <"high", "medium", or "low">,
+            "reason": <what the paragraph says that answers the query>
+        },
+        ...
+    ]
+}
+
+List only paragraphs that directly contain the answer, most relevant first. Return an empty list if none do.
+Directly return the final JSON structure. Do not output anything else."#
+    }
+
+    /// Prompt to classify a query into the categories of a corpus's
+    /// documents.
+    pub fn route_query() -> &'static str {
+        r#"You are routing a query over a collection of documents of different types. Decide which types of document could contain the answer.
+
+User query: {query}
+
+Document types:
+{categories}
+
+Reply in JSON format:
+{
+    "thinking": <which kinds of document would answer the query, and why>,
+    "categories": [<name of a matching document type>, ...]
+}
+
+List every type that could contain the answer, most likely first. Use the names exactly as given. Return an empty list if no type fits.
+Directly return the final JSON structure. Do not output anything else."#
+    }
+
     /// Prompt to detect if page index numbers are given in TOC.
     pub fn detect_page_index() -> &'static str {
         r#"You will be given a table of contents.
@@ -213,6 +395,43 @@ Section Content:
 Provide ONLY the summary text, nothing else. Be specific about what information this section contains that would help someone searching for relevant content."#
     }
 
+    /// Prompt to generate a detailed summary for a frequently retrieved
+    /// section.
+    pub fn generate_detailed_node_summary() -> &'static str {
+        r#"You are given a section from a document that readers frequently search for. Generate a detailed summary (4-6 sentences) of it: its main topics, the specific facts, figures, names, definitions and procedures it contains, and the questions it answers.
+
+Section Title: {title}
+
+Section Content:
+{content}
+
+Provide ONLY the summary text, nothing else. Be specific and concrete so that someone searching for any of the information in this section can tell it is here."#
+    }
+
+    /// Prompt to summarize a section from its subsections' summaries.
+    pub fn roll_up_section_summary() -> &'static str {
+        r#"You are given the summaries of the subsections of a document section. Generate a concise summary (2-3 sentences) of the whole section, covering the main topics and key information across its subsections.
+
+Section Title: {title}
+
+Subsection Summaries:
+{summaries}
+
+Provide ONLY the summary text, nothing else. Be specific about what information this section contains that would help someone searching for relevant content."#
+    }
+
+    /// Prompt to write a document abstract from its top-level section summaries.
+    pub fn roll_up_document_summary() -> &'static str {
+        r#"You are given the summaries of the top-level sections of a document. Write an abstract of the whole document in one paragraph (3-5 sentences): what it is about, its main points, and how it is organized.
+
+Document: {title}
+
+Section Summaries:
+{summaries}
+
+Provide ONLY the abstract text, nothing else."#
+    }
+
     /// System prompt for general document analysis.
     pub fn system_document_analyzer() -> &'static str {
         "You are an expert document analyzer. You help extract structure, navigate content, and answer questions about documents. Always respond with valid JSON when requested."
@@ -230,9 +449,88 @@ Context (retrieved from document):
 Instructions:
 - Answer the question using ONLY the information provided in the context above.
 - If the context does not contain enough information to answer the question, say "I cannot answer this question based on the provided context."
-- Be concise and direct in your answer.
+{style}
 - Do not make up information that is not in the context.
 
+Answer:"#
+    }
+
+    /// Prompt to extract schema-conformant data from retrieved sections.
+    pub fn extract_fields() -> &'static str {
+        r#"You are extracting structured data from sections of a document.
+
+Request: {query}
+
+JSON Schema of the data to extract:
+{schema}
+
+Sections (numbered):
+{sections}
+
+Reply in JSON format:
+{
+    "data": <the extracted data, conforming to the schema>,
+    "citations": [
+        {
+            "field": <JSON Pointer to an extracted value, e.g. "/parties/0">,
+            "section": <number of the section the value was taken from>,
+            "quote": <the exact text of the section stating the value>
+        },
+        ...
+    ]
+}
+
+Take every value from the sections; use null for values they do not state. Cite each extracted value.
+Directly return the final JSON structure. Do not output anything else."#
+    }
+
+    /// Prompt to write evaluation questions answered by one section.
+    pub fn generate_questions() -> &'static str {
+        r#"You are writing questions to evaluate a search system over a document. Write {count} questions that the following section answers.
+
+Document: {document}
+Section Title: {title}
+
+Section Content:
+{content}
+
+Each question must:
+- be answerable from this section alone, with a specific fact, figure, name, definition or procedure it states
+- make sense to someone who has not seen the section (do not refer to "this section" or "the text")
+- not be answerable from the section title alone
+
+Reply in JSON format:
+{
+    "questions": [
+        {
+            "question": <the question>,
+            "answer": <a short answer, as stated in the section>,
+            "evidence": <the exact text of the section the answer comes from, copied verbatim>
+        },
+        ...
+    ]
+}
+
+Directly return the final JSON structure. Do not output anything else."#
+    }
+
+    /// Prompt for multiple-choice answering from retrieved context.
+    pub fn multiple_choice_answer() -> &'static str {
+        r#"You are answering a multiple-choice question based on the provided context.
+
+Question: {question}
+
+Options:
+{options}
+
+Context (retrieved from document):
+{context}
+
+Instructions:
+- Choose the option best supported by the context above.
+- If the context is insufficient, choose the most plausible option anyway.
+- Reply with the letter of the chosen option only.
+
 Answer:"#
     }
 }
@@ -249,7 +547,18 @@ mod tests {
         assert!(!Prompts::generate_toc_continue().is_empty());
         assert!(!Prompts::check_title_appearance().is_empty());
         assert!(!Prompts::tree_search().is_empty());
+        assert!(!Prompts::tree_search_titles().is_empty());
+        assert!(!Prompts::verify_search_results().is_empty());
+        assert!(!Prompts::rank_shortlist().is_empty());
+        assert!(!Prompts::route_query().is_empty());
+        assert!(!Prompts::zoom_section().is_empty());
+        assert!(!Prompts::paraphrase_query().is_empty());
         assert!(!Prompts::generate_node_summary().is_empty());
+        assert!(!Prompts::generate_detailed_node_summary().is_empty());
+        assert!(!Prompts::generate_questions().is_empty());
+        assert!(!Prompts::roll_up_section_summary().is_empty());
+        assert!(!Prompts::roll_up_document_summary().is_empty());
         assert!(!Prompts::rag_answer().is_empty());
+        assert!(!Prompts::multiple_choice_answer().is_empty());
     }
 }
@@ -0,0 +1,317 @@
+//! Document loaders by file extension and MIME type.
+//!
+//! Everything that reads a document from a path (the CLI,
+//! [`PageIndex::index_file`](crate::PageIndex::index_file), directory
+//! watching, serve tenants) goes through the process-wide
+//! [`LoaderRegistry`], so a new format only needs a loader registered at
+//! startup:
+//!
+//! ```no_run
+//! use rust_page_indexer::document::{Document, Page};
+//! use rust_page_indexer::{PageIndexError, loader};
+//! use std::path::Path;
+//!
+//! // One page per CSV row
+//! loader::register_extension("csv", |path: &Path| {
+//!     let text = std::fs::read_to_string(path).map_err(|e| PageIndexError::io(path, e))?;
+//!     let pages = text
+//!         .lines()
+//!         .enumerate()
+//!         .map(|(i, row)| Page::new(i + 1, row.to_string()))
+//!         .collect();
+//!     Ok(Document::new("table", pages))
+//! });
+//! let document = loader::load(Path::new("data/table.csv"))?;
+//! # Ok::<(), PageIndexError>(())
+//! ```
+//!
+//! `.txt`, `.md` and `.markdown` files (and `text/plain`, `text/markdown`)
+//! load as plain text out of the box. Files with an extension nobody
+//! registered are read as plain text too, and rejected with
+//! [`PageIndexError::UnsupportedFormat`] if they are not UTF-8 text.
+
+use crate::document::Document;
+use crate::error::{PageIndexError, Result};
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{Arc, OnceLock, RwLock};
+use tracing::debug;
+
+/// Extensions loaded as plain text by default.
+pub const TEXT_EXTENSIONS: &[&str] = &["txt", "md", "markdown"];
+
+/// MIME types loaded as plain text by default.
+pub const TEXT_MIME_TYPES: &[&str] = &["text/plain", "text/markdown"];
+
+/// Reads a file into a [`Document`].
+///
+/// Implemented for closures taking a `&Path`.
+pub trait DocumentLoader: Send + Sync {
+    fn load(&self, path: &Path) -> Result<Document>;
+}
+
+impl<F> DocumentLoader for F
+where
+    F: Fn(&Path) -> Result<Document> + Send + Sync,
+{
+    fn load(&self, path: &Path) -> Result<Document> {
+        self(path)
+    }
+}
+
+/// Loads a file as a single page of text ([`Document::from_text_file`]).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TextLoader;
+
+impl DocumentLoader for TextLoader {
+    fn load(&self, path: &Path) -> Result<Document> {
+        Document::from_text_file(path)
+    }
+}
+
+/// Loaders keyed by lowercase file extension and MIME type.
+#[derive(Clone)]
+pub struct LoaderRegistry {
+    extensions: HashMap<String, Arc<dyn DocumentLoader>>,
+    mime_types: HashMap<String, Arc<dyn DocumentLoader>>,
+}
+
+impl Default for LoaderRegistry {
+    /// A registry with the plain-text loaders.
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        for extension in TEXT_EXTENSIONS {
+            registry.register_extension(extension, TextLoader);
+        }
+        for mime_type in TEXT_MIME_TYPES {
+            registry.register_mime_type(mime_type, TextLoader);
+        }
+        registry
+    }
+}
+
+impl LoaderRegistry {
+    /// A registry with the plain-text loaders.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A registry without any loaders.
+    pub fn empty() -> Self {
+        Self {
+            extensions: HashMap::new(),
+            mime_types: HashMap::new(),
+        }
+    }
+
+    /// Load files with this extension (with or without the leading dot)
+    /// using `loader`, replacing any previous loader for it.
+    pub fn register_extension(&mut self, extension: &str, loader: impl DocumentLoader + 'static) {
+        let extension = extension.trim_start_matches('.').to_lowercase();
+        self.extensions.insert(extension, Arc::new(loader));
+    }
+
+    /// Load files of this MIME type using `loader`, replacing any previous
+    /// loader for it.
+    pub fn register_mime_type(&mut self, mime_type: &str, loader: impl DocumentLoader + 'static) {
+        self.mime_types.insert(essence(mime_type), Arc::new(loader));
+    }
+
+    /// Builder form of [`register_extension`](Self::register_extension).
+    pub fn with_extension(
+        mut self,
+        extension: &str,
+        loader: impl DocumentLoader + 'static,
+    ) -> Self {
+        self.register_extension(extension, loader);
+        self
+    }
+
+    /// Builder form of [`register_mime_type`](Self::register_mime_type).
+    pub fn with_mime_type(
+        mut self,
+        mime_type: &str,
+        loader: impl DocumentLoader + 'static,
+    ) -> Self {
+        self.register_mime_type(mime_type, loader);
+        self
+    }
+
+    /// The loader registered for a path's extension, in any case.
+    /// Non-UTF-8 extensions are matched lossily.
+    pub fn for_path(&self, path: &Path) -> Option<Arc<dyn DocumentLoader>> {
+        let extension = path.extension()?.to_string_lossy().to_lowercase();
+        self.extensions.get(&extension).cloned()
+    }
+
+    /// The loader registered for a MIME type (parameters such as
+    /// `; charset=utf-8` are ignored).
+    pub fn for_mime_type(&self, mime_type: &str) -> Option<Arc<dyn DocumentLoader>> {
+        self.mime_types.get(&essence(mime_type)).cloned()
+    }
+
+    /// Whether a loader is registered for the path's extension.
+    pub fn supports(&self, path: &Path) -> bool {
+        self.for_path(path).is_some()
+    }
+
+    /// Registered extensions, sorted.
+    pub fn extensions(&self) -> Vec<String> {
+        let mut extensions: Vec<String> = self.extensions.keys().cloned().collect();
+        extensions.sort();
+        extensions
+    }
+
+    /// Load a file with the loader for its extension, or as plain text when
+    /// none is registered.
+    pub fn load(&self, path: &Path) -> Result<Document> {
+        if let Some(loader) = self.for_path(path) {
+            return loader.load(path);
+        }
+        debug!(path = %path.display(), "no loader registered, reading as text");
+        TextLoader.load(path).map_err(|e| match e {
+            PageIndexError::Io { source, .. }
+                if source.kind() == std::io::ErrorKind::InvalidData =>
+            {
+                let extension = path
+                    .extension()
+                    .map(|e| format!("'.{}' files", e.to_string_lossy()))
+                    .unwrap_or_else(|| "files without an extension".to_string());
+                PageIndexError::UnsupportedFormat(format!(
+                    "'{}' is not text and no loader is registered for {}",
+                    path.display(),
+                    extension
+                ))
+            }
+            e => e,
+        })
+    }
+
+    /// Load a file with the loader for a MIME type.
+    pub fn load_as(&self, mime_type: &str, path: &Path) -> Result<Document> {
+        let loader = self.for_mime_type(mime_type).ok_or_else(|| {
+            PageIndexError::UnsupportedFormat(format!(
+                "No loader is registered for '{}'",
+                mime_type
+            ))
+        })?;
+        loader.load(path)
+    }
+}
+
+/// `type/subtype` of a MIME type, lowercased.
+fn essence(mime_type: &str) -> String {
+    mime_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase()
+}
+
+fn global() -> &'static RwLock<LoaderRegistry> {
+    static REGISTRY: OnceLock<RwLock<LoaderRegistry>> = OnceLock::new();
+    REGISTRY.get_or_init(|| RwLock::new(LoaderRegistry::default()))
+}
+
+/// A copy of the process-wide registry.
+pub fn registry() -> LoaderRegistry {
+    global().read().expect("loader registry poisoned").clone()
+}
+
+/// Register a loader for an extension in the process-wide registry.
+pub fn register_extension(extension: &str, loader: impl DocumentLoader + 'static) {
+    global()
+        .write()
+        .expect("loader registry poisoned")
+        .register_extension(extension, loader);
+}
+
+/// Register a loader for a MIME type in the process-wide registry.
+pub fn register_mime_type(mime_type: &str, loader: impl DocumentLoader + 'static) {
+    global()
+        .write()
+        .expect("loader registry poisoned")
+        .register_mime_type(mime_type, loader);
+}
+
+/// Load a file through the process-wide registry (see
+/// [`LoaderRegistry::load`]).
+pub fn load(path: &Path) -> Result<Document> {
+    // Clone the loader out so a slow load does not hold the lock
+    let loader = global()
+        .read()
+        .expect("loader registry poisoned")
+        .for_path(path);
+    match loader {
+        Some(loader) => loader.load(path),
+        None => LoaderRegistry::empty().load(path),
+    }
+}
+
+/// Whether the process-wide registry has a loader for the path's extension.
+pub fn supports(path: &Path) -> bool {
+    global()
+        .read()
+        .expect("loader registry poisoned")
+        .supports(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::Page;
+
+    fn rows(path: &Path) -> Result<Document> {
+        let text = std::fs::read_to_string(path).map_err(|e| PageIndexError::io(path, e))?;
+        let pages = text
+            .lines()
+            .enumerate()
+            .map(|(i, row)| Page::new(i + 1, row.to_string()))
+            .collect();
+        Ok(Document::new("rows", pages))
+    }
+
+    #[test]
+    fn test_registry() {
+        let dir = tempfile::tempdir().unwrap();
+        let csv = dir.path().join("table.CSV");
+        std::fs::write(&csv, "a,1\nb,2\nc,3").unwrap();
+        let binary = dir.path().join("scan.pdf");
+        std::fs::write(&binary, [0xff, 0xfe, 0x00, 0x9f]).unwrap();
+
+        let registry = LoaderRegistry::new()
+            .with_extension(".csv", rows)
+            .with_mime_type("text/csv", rows);
+        assert_eq!(registry.extensions(), ["csv", "markdown", "md", "txt"]);
+        assert_eq!(registry.load(&csv).unwrap().page_count(), 3);
+        assert_eq!(
+            registry
+                .load_as("Text/CSV; charset=utf-8", &csv)
+                .unwrap()
+                .page_count(),
+            3
+        );
+        assert!(matches!(
+            registry.load_as("application/pdf", &binary),
+            Err(PageIndexError::UnsupportedFormat(_))
+        ));
+
+        // Unregistered extensions fall back to text, unless they are binary
+        assert_eq!(LoaderRegistry::empty().load(&csv).unwrap().page_count(), 1);
+        let err = registry.load(&binary).unwrap_err();
+        assert!(err.to_string().contains("'.pdf' files"), "{}", err);
+    }
+
+    #[test]
+    fn test_global_registry() {
+        let path = Path::new("notes.pidx-test");
+        assert!(!supports(path));
+        register_extension("pidx-test", |_: &Path| {
+            Ok(Document::from_text("custom", "Custom".to_string()))
+        });
+        assert!(supports(path));
+        assert!(crate::watch::is_document(path));
+        assert_eq!(load(path).unwrap().name, "custom");
+    }
+}
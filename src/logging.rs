@@ -0,0 +1,128 @@
+//! Logging setup shared by the CLI binaries.
+//!
+//! Logs are written to stderr via `tracing-subscriber`, so stdout stays
+//! reserved for command output (including `--json` output).
+//! `RUST_LOG` overrides the verbosity flags when set.
+
+use std::str::FromStr;
+use tracing::Subscriber;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+
+/// Log output format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Human-readable single-line logs.
+    #[default]
+    Text,
+    /// One JSON object per log line.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(LogFormat::Text),
+            "json" => Ok(LogFormat::Json),
+            other => Err(format!(
+                "unknown log format '{}' (expected text or json)",
+                other
+            )),
+        }
+    }
+}
+
+/// Map a `-v` count to a log level for this crate's targets.
+pub fn level_for_verbosity(verbosity: u8) -> &'static str {
+    match verbosity {
+        0 => "warn",
+        1 => "info",
+        2 => "debug",
+        _ => "trace",
+    }
+}
+
+/// Build the filter directive for a verbosity level.
+///
+/// Dependencies stay at `warn` so `-vv` shows our debug logs without
+/// drowning them in HTTP client internals.
+fn filter_directive(verbosity: u8, binary_target: &str) -> String {
+    let level = level_for_verbosity(verbosity);
+    format!(
+        "warn,rust_page_indexer={},{}={}",
+        level, binary_target, level
+    )
+}
+
+/// Initialize the global tracing subscriber.
+///
+/// `binary_target` is the binary's module path (e.g. `page_indexer`) so its
+/// own log statements follow the verbosity flag too.
+pub fn init(verbosity: u8, format: LogFormat, binary_target: &str) {
+    // Ignore the error if a subscriber is already installed (e.g. in tests)
+    let _ = tracing_subscriber::registry()
+        .with(stderr_layer(verbosity, format, binary_target))
+        .try_init();
+}
+
+/// Initialize the global tracing subscriber, also exporting spans to
+/// OpenTelemetry. Export is independent of the verbosity flags.
+#[cfg(feature = "otel")]
+pub fn init_with_telemetry(
+    verbosity: u8,
+    format: LogFormat,
+    binary_target: &str,
+    telemetry: &crate::telemetry::Telemetry,
+) {
+    let _ = tracing_subscriber::registry()
+        .with(stderr_layer(verbosity, format, binary_target))
+        .with(telemetry.layer(binary_target))
+        .try_init();
+}
+
+/// The stderr log layer, filtered by `RUST_LOG` or the verbosity.
+fn stderr_layer<S>(
+    verbosity: u8,
+    format: LogFormat,
+    binary_target: &str,
+) -> Box<dyn Layer<S> + Send + Sync>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(filter_directive(verbosity, binary_target)));
+
+    let layer = tracing_subscriber::fmt::layer().with_writer(std::io::stderr);
+    match format {
+        LogFormat::Text => layer.compact().with_filter(filter).boxed(),
+        LogFormat::Json => layer.json().with_filter(filter).boxed(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_format_from_str() {
+        assert_eq!("text".parse(), Ok(LogFormat::Text));
+        assert_eq!("JSON".parse(), Ok(LogFormat::Json));
+        assert!("xml".parse::<LogFormat>().is_err());
+    }
+
+    #[test]
+    fn test_verbosity_levels() {
+        assert_eq!(level_for_verbosity(0), "warn");
+        assert_eq!(level_for_verbosity(1), "info");
+        assert_eq!(level_for_verbosity(2), "debug");
+        assert_eq!(level_for_verbosity(5), "trace");
+        assert_eq!(
+            filter_directive(2, "page_indexer"),
+            "warn,rust_page_indexer=debug,page_indexer=debug"
+        );
+    }
+}
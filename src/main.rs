@@ -3,17 +3,45 @@
 //! An LLM-powered hierarchical tree indexing system for document search.
 
 use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
+use clap::{ArgAction, Parser, Subcommand};
+use indicatif::{ProgressBar, ProgressStyle};
 use rust_page_indexer::{
-    config::Config,
-    document::Document,
-    indexer::TreeIndexer,
+    audit::{AuditLog, AuditRecord},
+    batch::{BatchRunner, failure_summary, load_queries, write_report},
+    checkpoint::checkpoint_path_for,
+    config::{Config, LlmConfig},
+    edit::{TreeEdit, apply_edit},
+    export::{ExportFormat, export_tree, to_context_markdown},
+    extract::Extractor,
+    import::{StructureFormat, import_structure},
+    indexer::{IndexerOptions, TreeIndexer},
+    jobs::{Job, JobQueue, JobState},
     llm::LlmClient,
-    persistence::{load_tree, save_tree, tree_exists, tree_size},
-    search::TreeSearcher,
+    loader,
+    logging::{self, LogFormat},
+    outcome::{ExitPolicy, FailureSummary},
+    persistence::{
+        self, SaveFormat, index_files, load_tree, save_navigation_tree, save_tree, tree_exists,
+        tree_size,
+    },
+    progress::{ProgressCallback, ProgressEvent},
+    query::{QueryNormalizer, load_glossary},
+    retriever::{result_documents, tree_documents},
+    router::{QueryRouter, Taxonomy},
+    search::{SearchOptions, SearchOutcome, TreeSearcher},
+    summarize::{HotSectionRefresher, TreeSummarizer, embed_nodes},
+    tree::{
+        DisplayOptions, DocumentTree, Freshness, LOW_CONFIDENCE, TreeFilter, parse_effective_date,
+    },
+    usage::{UsageReport, UsageTracker},
+    validate::{Severity, validate_index, validate_tree},
+    watch::DirectoryWatcher,
 };
-use std::path::PathBuf;
-use std::time::Instant;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
 
 /// Rust Page Indexer - An LLM-powered hierarchical tree indexing system
 #[derive(Parser)]
@@ -22,15 +50,101 @@ use std::time::Instant;
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Emit machine-readable JSON on stdout instead of formatted text
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Increase log verbosity (-v info, -vv debug, -vvv trace)
+    #[arg(short, long, action = ArgAction::Count, global = true)]
+    verbose: u8,
+
+    /// Log output format on stderr (text, json)
+    #[arg(long, global = true, default_value = "text")]
+    log_format: LogFormat,
+
+    /// Save a JSON usage report (LLM calls, tokens, cost, timings) to this file
+    #[arg(long, global = true, value_name = "FILE")]
+    usage_report: Option<PathBuf>,
+
+    /// Append each search and answer to this JSONL audit log (also
+    /// storage.audit_log in the config file)
+    #[arg(long, global = true, value_name = "FILE")]
+    audit_log: Option<PathBuf>,
+
+    /// When indexing a directory or running batch queries, exit non-zero if
+    /// any item fails (2 if some succeeded), only if all fail, or never
+    #[arg(
+        long,
+        global = true,
+        default_value = "any",
+        value_name = "any|all|never"
+    )]
+    fail_on: ExitPolicy,
+
+    /// Resolve relative index paths against this directory (also
+    /// PAGE_INDEXER_DATA_ROOT or storage.data_root in the config file)
+    #[arg(long, global = true, value_name = "DIR")]
+    data_root: Option<PathBuf>,
+
+    /// Export traces and metrics to this OTLP/HTTP collector (also enabled
+    /// by OTEL_EXPORTER_OTLP_ENDPOINT)
+    #[cfg(feature = "otel")]
+    #[arg(long, global = true, value_name = "URL")]
+    otlp_endpoint: Option<String>,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     /// Build a tree index for a document
     Index {
-        /// Path to the document file (text file)
+        /// Path to the document file (text file), or a directory to index
+        /// every document in it
         document: PathBuf,
 
+        /// Output path for the tree index file, or the index directory when
+        /// indexing a directory [default: data/tree_index.json, data/indexes]
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Resume an interrupted run from its checkpoint (<output>.checkpoint)
+        #[arg(long)]
+        resume: bool,
+
+        /// Score how sure the indexer is of each section (one more LLM call
+        /// per section); see them with `show --confidence`
+        #[arg(long)]
+        confidence: bool,
+
+        /// With --confidence, check the top-level sections and this share of
+        /// the others (e.g. 0.2) instead of all, checking all only if too
+        /// many fail
+        #[arg(long, value_name = "RATE", requires = "confidence")]
+        verify_sample: Option<f32>,
+
+        /// Find the date each section took effect (one more LLM call per
+        /// section), for `search --prefer-recent` and `--since`
+        #[arg(long)]
+        extract_dates: bool,
+    },
+
+    /// Build a tree index from existing structure (a YAML outline, PDF
+    /// bookmarks from `pdftk dump_data`, an EPUB nav.xhtml or toc.ncx, or
+    /// DocBook) without LLM calls
+    Import {
+        /// Path to the structure file
+        structure: PathBuf,
+
+        /// The document the structure belongs to; needed to place sections
+        /// given without pages (EPUB, DocBook)
+        #[arg(short, long)]
+        document: Option<PathBuf>,
+
+        /// Structure format (yaml, pdf, epub, ncx, docbook) [default:
+        /// detected from the file]
+        #[arg(short, long)]
+        format: Option<StructureFormat>,
+
         /// Output path for the tree index file
         #[arg(short, long, default_value = "data/tree_index.json")]
         output: PathBuf,
@@ -39,7 +153,20 @@ enum Commands {
     /// Search a tree index using LLM reasoning
     Search {
         /// The search query
-        query: String,
+        #[arg(required_unless_present = "queries", conflicts_with = "queries")]
+        query: Option<String>,
+
+        /// Run every query in a file (one per line, or JSONL with "query"/"id")
+        #[arg(long, value_name = "FILE")]
+        queries: Option<PathBuf>,
+
+        /// Write batch results to a report file (.csv or .json)
+        #[arg(long, value_name = "FILE", requires = "queries")]
+        report: Option<PathBuf>,
+
+        /// Number of batch queries to run concurrently
+        #[arg(long, default_value_t = 4, requires = "queries")]
+        concurrency: usize,
 
         /// Path to the tree index file
         #[arg(short, long, default_value = "data/tree_index.json")]
@@ -56,232 +183,2874 @@ enum Commands {
         /// Path to the original document (required if --with-content is set)
         #[arg(short, long)]
         document: Option<PathBuf>,
-    },
 
-    /// Display the tree structure of an index
-    Show {
-        /// Path to the tree index file
-        #[arg(default_value = "data/tree_index.json")]
-        index: PathBuf,
+        /// Check each result's content against the query and drop sections
+        /// that don't address it (requires --document)
+        #[arg(long, requires = "document")]
+        verify: bool,
+
+        /// Send the tree as a compact outline of at most about N tokens,
+        /// dropping detail from the deepest levels to fit
+        #[arg(long, value_name = "N")]
+        outline_tokens: Option<usize>,
+
+        /// Also search N paraphrases of the query (against section titles)
+        /// and fuse the results, for better recall on vague queries
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        paraphrases: usize,
+
+        /// Also search sections excluded as non-content (references, index, ...)
+        #[arg(long)]
+        include_excluded: bool,
+
+        /// Search only sections open to everyone or tagged with one of
+        /// these access labels (comma-separated)
+        #[arg(long, value_name = "LABEL", value_delimiter = ',')]
+        acl: Option<Vec<String>>,
+
+        /// Stop searching after this many milliseconds (per query) and
+        /// return the results found so far, marked as truncated
+        #[arg(long, value_name = "MS")]
+        deadline_ms: Option<u64>,
+
+        /// Correct misspelled query words against the index's titles and
+        /// summaries before searching
+        #[arg(long)]
+        spell_correct: bool,
+
+        /// Expand the abbreviations in this glossary (a YAML or JSON map
+        /// of abbreviation to expansion) in queries before searching
+        #[arg(long, value_name = "FILE")]
+        glossary: Option<PathBuf>,
+
+        /// Define the terms in this glossary (a YAML or JSON map of term to
+        /// definition) in search prompts, in addition to the index's own
+        #[arg(long, value_name = "FILE")]
+        terms: Option<PathBuf>,
+
+        /// Among equally relevant sections, rank those that took effect
+        /// most recently first
+        #[arg(long)]
+        prefer_recent: bool,
 
-        /// Output as JSON instead of formatted tree
+        /// Search only sections in effect since this date (YYYY, YYYY-MM
+        /// or YYYY-MM-DD); undated sections are kept
+        #[arg(long, value_name = "DATE", value_parser = parse_date)]
+        since: Option<String>,
+
+        /// Re-index the document first if it changed since the index was
+        /// built (otherwise a warning is printed)
         #[arg(long)]
-        json: bool,
+        auto_reindex: bool,
+
+        /// Print results as a JSON array of LangChain documents
+        #[arg(long, conflicts_with = "queries")]
+        langchain: bool,
+
+        /// Write the retrieved sections, with their text, to a Markdown
+        /// file ready to paste into other LLM tools (requires --document)
+        #[arg(
+            long,
+            value_name = "FILE",
+            requires = "document",
+            conflicts_with = "queries"
+        )]
+        export_context: Option<PathBuf>,
     },
 
-    /// Show information about an index
-    Info {
+    /// Pinpoint the paragraphs of one section that answer a query
+    Zoom {
+        /// The search query
+        query: String,
+
+        /// The section's node_id or structure path (e.g. 0003 or 1.2)
+        #[arg(short, long)]
+        node: String,
+
         /// Path to the tree index file
-        #[arg(default_value = "data/tree_index.json")]
+        #[arg(short, long, default_value = "data/tree_index.json")]
         index: PathBuf,
+
+        /// Path to the original document
+        #[arg(short, long)]
+        document: PathBuf,
+
+        /// Number of passages to return
+        #[arg(short = 'k', long, default_value_t = 3)]
+        top_k: usize,
     },
 
-    /// Test LLM connection
-    Test,
-}
+    /// Extract data matching a JSON Schema from the sections retrieved for
+    /// a query, citing the section of each field
+    Extract {
+        /// What to extract, as a query for retrieval
+        query: String,
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    let cli = Cli::parse();
+        /// JSON Schema file describing the data to extract
+        #[arg(short, long, value_name = "FILE")]
+        schema: PathBuf,
 
-    match cli.command {
-        Commands::Index { document, output } => cmd_index(document, output).await,
-        Commands::Search {
-            query,
-            index,
-            top_k,
-            with_content,
-            document,
-        } => cmd_search(query, index, top_k, with_content, document).await,
-        Commands::Show { index, json } => cmd_show(index, json),
-        Commands::Info { index } => cmd_info(index),
-        Commands::Test => cmd_test().await,
-    }
-}
+        /// Path to the tree index file
+        #[arg(short, long, default_value = "data/tree_index.json")]
+        index: PathBuf,
 
-async fn cmd_index(document_path: PathBuf, output: PathBuf) -> Result<()> {
-    println!("Loading configuration...");
-    let config = Config::load().context("Failed to load configuration")?;
-    config.validate().context("Invalid configuration")?;
+        /// Path to the original document
+        #[arg(short, long)]
+        document: PathBuf,
 
-    println!("Indexing document: {}", document_path.display());
-    println!("Using model: {}", config.llm.model);
+        /// Number of sections to extract from
+        #[arg(short = 'k', long, default_value_t = 5)]
+        top_k: usize,
+    },
 
-    let start = Instant::now();
+    /// Search only the indexes of a directory whose document type matches
+    /// the query (set types with `edit meta type <value>`)
+    Route {
+        /// The search query
+        query: String,
 
-    // Load document
-    let document = Document::from_text_file(&document_path).context("Failed to load document")?;
+        /// Taxonomy file (YAML) with the document categories to route to
+        #[arg(short, long, value_name = "FILE")]
+        taxonomy: PathBuf,
 
-    println!(
-        "  Document: {} ({} pages, ~{} tokens)",
-        document.name,
-        document.page_count(),
-        document.total_tokens()
-    );
+        /// Directory of tree index files
+        #[arg(long, default_value = "data/indexes")]
+        index_dir: PathBuf,
 
-    // Create client and indexer
-    let client = LlmClient::new(config.llm);
-    let indexer = TreeIndexer::new(client);
+        /// Number of results to return
+        #[arg(short = 'k', long, default_value_t = 5)]
+        top_k: usize,
 
-    // Build tree index
-    println!("\nBuilding tree index via LLM...");
-    let tree = indexer
-        .index(&document)
-        .await
-        .context("Failed to build tree index")?;
+        /// Only classify the query and list the selected documents
+        #[arg(long)]
+        classify_only: bool,
 
-    let build_duration = start.elapsed();
+        /// Define the terms in this glossary (a YAML or JSON map of term to
+        /// definition) in search prompts, for every document
+        #[arg(long, value_name = "FILE")]
+        terms: Option<PathBuf>,
+    },
 
-    // Show stats
-    println!("\nTree Index Built:");
-    println!("  Sections:    {}", tree.node_count());
-    println!("  Max depth:   {}", tree.max_depth());
-    println!("  Build time:  {:.2?}", build_duration);
+    /// Display the tree structure of an index
+    Show {
+        /// Path to the tree index file
+        #[arg(default_value = "data/tree_index.json")]
+        index: PathBuf,
 
-    // Save tree
-    save_tree(&tree, &output).context("Failed to save tree index")?;
+        /// Only show sections up to this depth (1 = top level)
+        #[arg(long)]
+        depth: Option<usize>,
 
-    let size = tree_size(&output)?;
-    println!("\nIndex saved to: {}", output.display());
-    println!("  File size: {:.1} KB", size as f64 / 1024.0);
+        /// Only show sections overlapping a page range (e.g. 10-20 or 15)
+        #[arg(long, value_parser = parse_page_range)]
+        pages: Option<(usize, usize)>,
 
-    Ok(())
-}
+        /// Only show sections whose title contains this text (case-insensitive)
+        #[arg(long)]
+        filter: Option<String>,
 
-async fn cmd_search(
-    query: String,
-    index_path: PathBuf,
-    top_k: usize,
-    with_content: bool,
-    document_path: Option<PathBuf>,
-) -> Result<()> {
-    if !tree_exists(&index_path) {
-        anyhow::bail!(
-            "Index not found at '{}'. Run 'index' command first.",
-            index_path.display()
-        );
-    }
+        /// Include node summaries
+        #[arg(long)]
+        summaries: bool,
 
-    if with_content && document_path.is_none() {
-        anyhow::bail!("--document is required when using --with-content");
-    }
+        /// Include confidence scores (from `index --confidence`), flagging
+        /// sections worth double-checking
+        #[arg(long)]
+        confidence: bool,
+    },
 
-    println!("Loading configuration...");
-    let config = Config::load().context("Failed to load configuration")?;
-    config.validate().context("Invalid configuration")?;
+    /// Export a tree index to another format
+    Export {
+        /// Path to the tree index file
+        #[arg(default_value = "data/tree_index.json")]
+        index: PathBuf,
 
-    let tree = load_tree(&index_path).context("Failed to load tree index")?;
+        /// Output format (md, html, dot, csv, pageindex-json, langchain,
+        /// qdrant, pgvector)
+        #[arg(short, long, default_value = "md")]
+        format: ExportFormat,
 
-    let client = LlmClient::new(config.llm.clone());
-    let searcher = TreeSearcher::new(client);
+        /// Output file (prints to stdout if omitted)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
 
-    println!("Searching for: \"{}\"", query);
-    println!("Using model: {}", config.llm.model);
-    println!();
+        /// Original document, to include section text as page content (langchain)
+        #[arg(short, long)]
+        document: Option<PathBuf>,
 
-    let start = Instant::now();
+        /// Embed nodes without an embedding first (with models.embed /
+        /// LLM_EMBED_MODEL) and save them to the index (qdrant, pgvector)
+        #[arg(long)]
+        embed: bool,
+    },
 
-    let results = if with_content {
-        let document = Document::from_text_file(document_path.as_ref().unwrap())
-            .context("Failed to load document")?;
-        searcher
-            .search_with_content(&tree, &document, &query)
-            .await
-            .context("Search failed")?
-    } else {
-        searcher
-            .search(&tree, &query)
-            .await
-            .context("Search failed")?
-    };
+    /// Roll section summaries up into a document summary (stored as the
+    /// index's description)
+    Summarize {
+        /// Path to the tree index file
+        #[arg(default_value = "data/tree_index.json")]
+        index: PathBuf,
 
-    let search_duration = start.elapsed();
+        /// The indexed document (leaves are summarized from its text)
+        #[arg(short, long)]
+        document: PathBuf,
 
-    if results.is_empty() {
-        println!("No relevant sections found.");
-    } else {
-        println!("Results:");
-        println!("{}", "─".repeat(60));
+        /// Write the summarized tree here instead of overwriting the index
+        #[arg(short, long)]
+        output: Option<PathBuf>,
 
-        for (i, result) in results.iter().take(top_k).enumerate() {
-            println!(
-                "{:>2}. {} [pages {}-{}] ({:?})",
-                i + 1,
-                result.title,
-                result.start_index,
-                result.end_index,
-                result.relevance
-            );
-            println!("    Reason: {}", result.reason);
+        /// Maximum LLM calls in flight
+        #[arg(long, default_value_t = 4)]
+        concurrency: usize,
 
-            if let Some(content) = &result.content {
-                println!("    Content preview:");
-                let preview: String = content.chars().take(200).collect();
-                for line in preview.lines().take(3) {
-                    println!("      {}", line);
-                }
-                if content.len() > 200 {
-                    println!("      ...");
-                }
-            }
-            println!();
-        }
+        /// Summarize leaves again even if they already have a summary
+        #[arg(long)]
+        resummarize_leaves: bool,
+    },
 
-        println!("{}", "─".repeat(60));
-        println!("Found {} results in {:.2?}", results.len(), search_duration);
-    }
+    /// Re-summarize the most retrieved sections in more detail (from the
+    /// stats recorded by `serve --stats`)
+    Refresh {
+        /// Path to the tree index file
+        #[arg(default_value = "data/tree_index.json")]
+        index: PathBuf,
 
-    Ok(())
-}
+        /// The indexed document
+        #[arg(short, long)]
+        document: PathBuf,
 
-fn cmd_show(index_path: PathBuf, json: bool) -> Result<()> {
-    if !tree_exists(&index_path) {
-        anyhow::bail!(
-            "Index not found at '{}'. Run 'index' command first.",
-            index_path.display()
-        );
-    }
+        /// Write the refreshed tree here instead of overwriting the index
+        #[arg(short, long)]
+        output: Option<PathBuf>,
 
-    let tree = load_tree(&index_path).context("Failed to load tree index")?;
+        /// Retrievals since its last refresh for a section to be refreshed
+        #[arg(long, default_value_t = 5)]
+        min_retrievals: u64,
 
-    if json {
-        let json_str = tree.to_json().context("Failed to serialize tree")?;
-        println!("{}", json_str);
-    } else {
-        println!("{}", tree.format());
-    }
+        /// Refresh at most this many sections, the most retrieved first
+        #[arg(long)]
+        limit: Option<usize>,
 
-    Ok(())
-}
+        /// Also embed refreshed sections (with models.embed / LLM_EMBED_MODEL)
+        #[arg(long)]
+        embed: bool,
 
-fn cmd_info(index_path: PathBuf) -> Result<()> {
-    if !tree_exists(&index_path) {
-        anyhow::bail!(
-            "Index not found at '{}'. Run 'index' command first.",
-            index_path.display()
-        );
-    }
+        /// Maximum LLM calls in flight
+        #[arg(long, default_value_t = 4)]
+        concurrency: usize,
+    },
 
-    let tree = load_tree(&index_path).context("Failed to load tree index")?;
-    let size = tree_size(&index_path)?;
+    /// Write a navigation-only copy of an index (titles, structure, node IDs
+    /// and page ranges; no summaries)
+    Compact {
+        /// Path to the tree index file
+        #[arg(default_value = "data/tree_index.json")]
+        index: PathBuf,
 
-    println!("Tree Index Information");
-    println!("{}", "─".repeat(40));
-    println!("  Document:     {}", tree.name);
-    println!("  Total pages:  {}", tree.total_pages);
-    println!("  Sections:     {}", tree.node_count());
-    println!("  Max depth:    {}", tree.max_depth());
-    println!("  File size:    {:.1} KB", size as f64 / 1024.0);
-    println!("  Index path:   {}", index_path.display());
+        /// Output file (default: <index>.nav.json, or .nav.bin for binary indexes)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
 
-    if let Some(desc) = &tree.description {
-        println!("  Description:  {}", desc);
-    }
+    /// Show information about an index
+    Info {
+        /// Path to the tree index file
+        #[arg(default_value = "data/tree_index.json")]
+        index: PathBuf,
 
-    Ok(())
+        /// Show how often each section was retrieved (recorded by `serve --stats`)
+        #[arg(long)]
+        stats: bool,
+    },
+
+    /// Edit nodes of an index (addressed by node_id or structure path), or
+    /// its metadata
+    Edit {
+        #[command(subcommand)]
+        action: EditAction,
+
+        /// Path to the tree index file
+        #[arg(short, long, global = true, default_value = "data/tree_index.json")]
+        index: PathBuf,
+
+        /// Write the edited tree here instead of overwriting the index
+        #[arg(short, long, global = true)]
+        output: Option<PathBuf>,
+
+        /// Save even if the edit introduces validation errors
+        #[arg(long, global = true)]
+        force: bool,
+    },
+
+    /// Validate an index file (exits non-zero on problems)
+    Validate {
+        /// Path to the tree index file
+        #[arg(default_value = "data/tree_index.json")]
+        index: PathBuf,
+
+        /// Source document to spot-check section titles against (uses the LLM)
+        #[arg(short, long)]
+        document: Option<PathBuf>,
+
+        /// Number of section titles to spot-check when --document is given
+        #[arg(long, default_value_t = 5)]
+        spot_checks: usize,
+
+        /// Treat warnings as failures
+        #[arg(long)]
+        strict: bool,
+    },
+
+    /// Serve an index over HTTP as a LangChain/LangServe-compatible retriever
+    #[cfg(feature = "serve")]
+    Serve {
+        /// Path to the tree index file
+        #[arg(default_value = "data/tree_index.json")]
+        index: PathBuf,
+
+        /// Original document, to return section text as page content
+        #[arg(short, long)]
+        document: Option<PathBuf>,
+
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8000")]
+        addr: std::net::SocketAddr,
+
+        /// Default number of documents per query
+        #[arg(short = 'k', long, default_value_t = 5)]
+        top_k: usize,
+
+        /// Number of query results and answers to cache (0 disables caching)
+        #[arg(long, default_value_t = 256)]
+        cache_size: usize,
+
+        /// Seconds a cached answer is reused
+        #[arg(long, default_value_t = 3600, value_name = "SECS")]
+        answer_ttl: u64,
+
+        /// Serve the tenants and indexes in this YAML file instead of one index
+        #[arg(long, conflicts_with_all = ["index", "document"])]
+        tenants: Option<PathBuf>,
+
+        /// Count how often each section is retrieved, in INDEX.stats (for
+        /// tenants, set `stats: true` on an index instead)
+        #[arg(long, conflicts_with = "tenants")]
+        stats: bool,
+
+        /// Default length of /ask answers (requests may set "length")
+        #[arg(long, value_enum, default_value = "medium")]
+        answer_length: rust_page_indexer::page_index::AnswerLength,
+
+        /// Default layout of /ask answers (requests may set "format")
+        #[arg(long, value_enum, default_value = "prose")]
+        answer_format: rust_page_indexer::page_index::AnswerFormat,
+
+        /// Default language of /ask answers, e.g. German (requests may set
+        /// "language")
+        #[arg(long, value_name = "LANGUAGE")]
+        answer_language: Option<String>,
+
+        /// Refuse to answer (outcome "not_found") unless a retrieved section
+        /// is at least this relevant (requests may set "required_relevance")
+        #[arg(long, value_enum, value_name = "RELEVANCE")]
+        require_relevance: Option<rust_page_indexer::search::Relevance>,
+    },
+
+    /// Serve indexes over gRPC (Index, Search, Ask, StreamAsk)
+    #[cfg(feature = "grpc")]
+    Grpc {
+        /// Tree index files to serve, named by file stem
+        indexes: Vec<PathBuf>,
+
+        /// Serve the trees in this directory and save indexes built over RPC to it
+        #[arg(long)]
+        index_dir: Option<PathBuf>,
+
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:50051")]
+        addr: std::net::SocketAddr,
+    },
+
+    /// Watch a directory and re-index documents as they change
+    Watch {
+        /// Directory of documents to watch (.txt, .md)
+        dir: PathBuf,
+
+        /// Directory to write one index file per document into
+        #[arg(short, long, default_value = "data/indexes")]
+        index_dir: PathBuf,
+    },
+
+    /// Index documents in the background and check on them
+    Jobs {
+        #[command(subcommand)]
+        action: JobsAction,
+
+        /// Directory jobs are stored in (defaults to the user's data directory)
+        #[arg(long, global = true, value_name = "DIR")]
+        jobs_dir: Option<PathBuf>,
+    },
+
+    /// Test LLM connection
+    Test,
+}
+
+#[derive(Subcommand)]
+enum JobsAction {
+    /// Queue a document for indexing and print the job ID
+    Submit {
+        /// Path to the document file
+        document: PathBuf,
+
+        /// Also save the tree here when the job completes
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Show a job's state and progress, or list all jobs
+    Status {
+        /// Job ID
+        id: Option<String>,
+    },
+
+    /// Print a completed job's tree, or save it with --output
+    Result {
+        /// Job ID
+        id: String,
+
+        /// Save the tree to this file instead of printing it
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Run a queued job in the foreground (started by `submit`)
+    #[command(hide = true)]
+    Run {
+        /// Job ID
+        id: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum EditAction {
+    /// Rename a node
+    Rename {
+        /// Node ID or structure path (e.g. 0003 or 1.2)
+        node: String,
+        /// New title
+        title: String,
+    },
+
+    /// Change a node's page range
+    Pages {
+        /// Node ID or structure path
+        node: String,
+        /// First page (1-indexed)
+        start: usize,
+        /// Last page (inclusive)
+        end: usize,
+    },
+
+    /// Delete a node and its subtree
+    Delete {
+        /// Node ID or structure path
+        node: String,
+    },
+
+    /// Move a node and its subtree under another parent
+    Move {
+        /// Node ID or structure path of the node to move
+        node: String,
+        /// New parent (omit to move to the top level)
+        #[arg(long)]
+        parent: Option<String>,
+        /// Position among the new siblings (default: last)
+        #[arg(long)]
+        position: Option<usize>,
+    },
+
+    /// Set a document metadata value (e.g. type=contract), used by `route`
+    Meta {
+        /// Metadata key
+        key: String,
+        /// New value (omit to remove the key)
+        value: Option<String>,
+    },
+
+    /// Define a term of the document's jargon for search and answer prompts
+    /// (e.g. DSCR "Debt service coverage ratio")
+    Term {
+        /// The term, as queries write it
+        term: String,
+        /// Its definition (omit to remove the term)
+        definition: Option<String>,
+    },
+
+    /// Leave a non-content section out of search prompts
+    Exclude {
+        /// Node ID or structure path
+        node: String,
+    },
+
+    /// Search a section excluded as non-content again
+    Include {
+        /// Node ID or structure path
+        node: String,
+    },
+
+    /// Restrict a node and its subtree to callers holding one of these
+    /// access labels (no labels: visible to all)
+    Acl {
+        /// Node ID or structure path
+        node: String,
+        /// Access labels
+        labels: Vec<String>,
+    },
+
+    /// Set the date a section took effect (e.g. 2023-07-01), inherited by
+    /// its subsections
+    Date {
+        /// Node ID or structure path
+        node: String,
+        /// The date, as YYYY, YYYY-MM or YYYY-MM-DD (omit to clear it)
+        date: Option<String>,
+    },
+}
+
+impl From<EditAction> for TreeEdit {
+    fn from(action: EditAction) -> Self {
+        match action {
+            EditAction::Rename { node, title } => TreeEdit::Rename { node, title },
+            EditAction::Pages { node, start, end } => TreeEdit::SetPages {
+                node,
+                start_index: start,
+                end_index: end,
+            },
+            EditAction::Delete { node } => TreeEdit::Delete { node },
+            EditAction::Move {
+                node,
+                parent,
+                position,
+            } => TreeEdit::Move {
+                node,
+                parent,
+                position,
+            },
+            EditAction::Meta { key, value } => TreeEdit::SetMetadata { key, value },
+            EditAction::Term { term, definition } => TreeEdit::SetTerm { term, definition },
+            EditAction::Exclude { node } => TreeEdit::SetExcluded {
+                node,
+                excluded: true,
+            },
+            EditAction::Include { node } => TreeEdit::SetExcluded {
+                node,
+                excluded: false,
+            },
+            EditAction::Acl { node, labels } => TreeEdit::SetAcl { node, labels },
+            EditAction::Date { node, date } => TreeEdit::SetEffectiveDate { node, date },
+        }
+    }
+}
+
+/// Resolve the command's index paths (and the directories and outputs
+/// indexes are written to) against the data root, if any.
+fn resolve_index_paths(command: &mut Commands, root: Option<&Path>) {
+    let resolve = |path: &mut PathBuf| *path = persistence::resolve_index_path(path, root);
+    match command {
+        Commands::Index { output, .. } => output.iter_mut().for_each(resolve),
+        Commands::Import { output, .. } => resolve(output),
+        Commands::Search { index, .. }
+        | Commands::Zoom { index, .. }
+        | Commands::Extract { index, .. }
+        | Commands::Show { index, .. }
+        | Commands::Export { index, .. }
+        | Commands::Info { index, .. }
+        | Commands::Validate { index, .. } => resolve(index),
+        Commands::Summarize { index, output, .. }
+        | Commands::Refresh { index, output, .. }
+        | Commands::Compact { index, output }
+        | Commands::Edit { index, output, .. } => {
+            resolve(index);
+            output.iter_mut().for_each(resolve);
+        }
+        Commands::Route { index_dir, .. } | Commands::Watch { index_dir, .. } => resolve(index_dir),
+        #[cfg(feature = "serve")]
+        Commands::Serve { index, .. } => resolve(index),
+        #[cfg(feature = "grpc")]
+        Commands::Grpc {
+            indexes, index_dir, ..
+        } => {
+            indexes.iter_mut().for_each(resolve);
+            index_dir.iter_mut().for_each(resolve);
+        }
+        Commands::Jobs {
+            action: JobsAction::Submit { output, .. } | JobsAction::Result { output, .. },
+            ..
+        } => output.iter_mut().for_each(resolve),
+        Commands::Jobs { .. } | Commands::Test => {}
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let mut cli = Cli::parse();
+    #[cfg(feature = "otel")]
+    let telemetry = init_telemetry(&cli)?;
+    #[cfg(not(feature = "otel"))]
+    logging::init(cli.verbose, cli.log_format, "page_indexer");
+    let json = cli.json;
+    let usage_report = cli.usage_report.as_deref();
+    let fail_on = cli.fail_on;
+    let storage = Config::load().map(|c| c.storage).unwrap_or_default();
+    let data_root = cli.data_root.take().or(storage.data_root);
+    let data_root = data_root.as_deref();
+    let audit_log = cli.audit_log.or(storage.audit_log);
+    let audit_log = audit_log.as_deref();
+    if let Some(root) = data_root {
+        info!(root = %root.display(), "resolving index paths against the data root");
+    }
+    resolve_index_paths(&mut cli.command, data_root);
+
+    let result = match cli.command {
+        Commands::Index {
+            document,
+            output,
+            resume,
+            confidence,
+            verify_sample,
+            extract_dates,
+        } if document.is_dir() => {
+            if resume {
+                anyhow::bail!("--resume is not supported when indexing a directory");
+            }
+            let index_dir = output.unwrap_or_else(|| {
+                persistence::resolve_index_path(Path::new("data/indexes"), data_root)
+            });
+            cmd_index_corpus(
+                document,
+                index_dir,
+                confidence,
+                verify_sample,
+                extract_dates,
+                fail_on,
+                json,
+                usage_report,
+            )
+            .await
+        }
+        Commands::Index {
+            document,
+            output,
+            resume,
+            confidence,
+            verify_sample,
+            extract_dates,
+        } => {
+            let output = output.unwrap_or_else(|| {
+                persistence::resolve_index_path(Path::new("data/tree_index.json"), data_root)
+            });
+            cmd_index(
+                document,
+                output,
+                resume,
+                confidence,
+                verify_sample,
+                extract_dates,
+                json,
+                usage_report,
+            )
+            .await
+        }
+        Commands::Search {
+            queries: Some(queries),
+            report,
+            concurrency,
+            index,
+            top_k,
+            with_content,
+            document,
+            verify,
+            outline_tokens,
+            paraphrases,
+            auto_reindex,
+            include_excluded,
+            acl,
+            deadline_ms,
+            spell_correct,
+            glossary,
+            terms,
+            prefer_recent,
+            since,
+            ..
+        } => {
+            cmd_batch_search(
+                BatchArgs {
+                    queries,
+                    report,
+                    concurrency,
+                    top_k,
+                    with_content,
+                    document,
+                    verify,
+                    outline_tokens,
+                    paraphrases,
+                    auto_reindex,
+                    include_excluded,
+                    acl,
+                    deadline_ms,
+                    spell_correct,
+                    glossary,
+                    terms,
+                    prefer_recent,
+                    since,
+                },
+                index,
+                fail_on,
+                json,
+                usage_report,
+                audit_log,
+            )
+            .await
+        }
+        Commands::Search {
+            query,
+            index,
+            top_k,
+            with_content,
+            document,
+            verify,
+            outline_tokens,
+            paraphrases,
+            auto_reindex,
+            include_excluded,
+            acl,
+            deadline_ms,
+            spell_correct,
+            glossary,
+            terms,
+            prefer_recent,
+            since,
+            langchain,
+            export_context,
+            ..
+        } => {
+            let options = SearchOptions {
+                verify_results: verify,
+                outline_tokens,
+                paraphrases,
+                include_excluded,
+                acl_labels: acl,
+                deadline: deadline_ms.map(Duration::from_millis),
+                normalizer: query_normalizer(&index, spell_correct, glossary.as_deref())?,
+                terms: load_terms(terms.as_deref())?,
+                prefer_recent,
+                effective_since: since,
+                ..Default::default()
+            };
+            cmd_search(
+                query.expect("clap requires a query without --queries"),
+                index,
+                top_k,
+                with_content,
+                document,
+                options,
+                auto_reindex,
+                langchain,
+                export_context.as_deref(),
+                json,
+                usage_report,
+                audit_log,
+            )
+            .await
+        }
+        Commands::Zoom {
+            query,
+            node,
+            index,
+            document,
+            top_k,
+        } => cmd_zoom(query, node, index, document, top_k, json, usage_report).await,
+        Commands::Extract {
+            query,
+            schema,
+            index,
+            document,
+            top_k,
+        } => cmd_extract(query, schema, index, document, top_k, json, usage_report).await,
+        Commands::Route {
+            query,
+            taxonomy,
+            index_dir,
+            top_k,
+            classify_only,
+            terms,
+        } => {
+            cmd_route(
+                query,
+                taxonomy,
+                index_dir,
+                top_k,
+                classify_only,
+                load_terms(terms.as_deref())?,
+                json,
+                usage_report,
+            )
+            .await
+        }
+        Commands::Show {
+            index,
+            depth,
+            pages,
+            filter,
+            summaries,
+            confidence,
+        } => {
+            let filter = TreeFilter {
+                max_depth: depth,
+                pages,
+                title: filter,
+            };
+            let display = DisplayOptions {
+                summaries,
+                confidence,
+            };
+            cmd_show(index, filter, display, json)
+        }
+        Commands::Export {
+            index,
+            format,
+            output,
+            document,
+            embed,
+        } => cmd_export(index, format, output, document, embed).await,
+        Commands::Summarize {
+            index,
+            document,
+            output,
+            concurrency,
+            resummarize_leaves,
+        } => {
+            cmd_summarize(
+                index,
+                document,
+                output,
+                concurrency,
+                resummarize_leaves,
+                json,
+                usage_report,
+            )
+            .await
+        }
+        Commands::Refresh {
+            index,
+            document,
+            output,
+            min_retrievals,
+            limit,
+            embed,
+            concurrency,
+        } => {
+            cmd_refresh(
+                index,
+                document,
+                output,
+                min_retrievals,
+                limit,
+                embed,
+                concurrency,
+                json,
+                usage_report,
+            )
+            .await
+        }
+        Commands::Import {
+            structure,
+            document,
+            format,
+            output,
+        } => cmd_import(structure, document, format, output, json),
+        Commands::Compact { index, output } => cmd_compact(index, output, json),
+        Commands::Info { index, stats } => cmd_info(index, stats, json),
+        Commands::Edit {
+            action,
+            index,
+            output,
+            force,
+        } => cmd_edit(action.into(), index, output, force, json),
+        Commands::Validate {
+            index,
+            document,
+            spot_checks,
+            strict,
+        } => cmd_validate(index, document, spot_checks, strict, json).await,
+        #[cfg(feature = "serve")]
+        Commands::Serve {
+            index,
+            document,
+            addr,
+            top_k,
+            cache_size,
+            answer_ttl,
+            tenants,
+            stats,
+            answer_length,
+            answer_format,
+            answer_language,
+            require_relevance,
+        } => {
+            let answer_ttl = Duration::from_secs(answer_ttl);
+            let style = rust_page_indexer::AnswerStyle {
+                length: answer_length,
+                format: answer_format,
+                language: answer_language,
+                required_relevance: require_relevance,
+            };
+            cmd_serve(
+                index, document, tenants, addr, top_k, cache_size, answer_ttl, stats, style,
+                audit_log,
+            )
+            .await
+        }
+        #[cfg(feature = "grpc")]
+        Commands::Grpc {
+            indexes,
+            index_dir,
+            addr,
+        } => cmd_grpc(indexes, index_dir, addr).await,
+        Commands::Watch { dir, index_dir } => cmd_watch(dir, index_dir).await,
+        Commands::Jobs { action, jobs_dir } => cmd_jobs(action, jobs_dir, json).await,
+        Commands::Test => cmd_test(json).await,
+    };
+
+    #[cfg(feature = "otel")]
+    if let Some(telemetry) = telemetry
+        && let Err(e) = telemetry.shutdown()
+    {
+        tracing::warn!(error = %e, "failed to flush telemetry");
+    }
+    if let Err(e) = &result
+        && let Some(PartialFailure(code)) = e.downcast_ref()
+    {
+        std::process::exit(*code);
+    }
+    result
+}
+
+/// Items of a corpus or batch run failed, and `--fail-on` makes that an
+/// error; exits with the code instead of printing an error.
+#[derive(Debug)]
+struct PartialFailure(i32);
+
+impl std::fmt::Display for PartialFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "some items failed (exit code {})", self.0)
+    }
+}
+
+impl std::error::Error for PartialFailure {}
+
+/// Fail with the `--fail-on` exit code for a run's failures, if any.
+fn check_failures(policy: ExitPolicy, summary: &FailureSummary) -> Result<()> {
+    match policy.exit_code(summary) {
+        0 => Ok(()),
+        code => Err(PartialFailure(code).into()),
+    }
+}
+
+/// Set up logging, exporting to OpenTelemetry when an OTLP endpoint is
+/// configured.
+#[cfg(feature = "otel")]
+fn init_telemetry(cli: &Cli) -> Result<Option<rust_page_indexer::telemetry::Telemetry>> {
+    let enabled =
+        cli.otlp_endpoint.is_some() || std::env::var_os("OTEL_EXPORTER_OTLP_ENDPOINT").is_some();
+    if !enabled {
+        logging::init(cli.verbose, cli.log_format, "page_indexer");
+        return Ok(None);
+    }
+    let telemetry =
+        rust_page_indexer::telemetry::Telemetry::init(cli.otlp_endpoint.as_deref(), "page-indexer")
+            .context("Failed to start OpenTelemetry export")?;
+    logging::init_with_telemetry(cli.verbose, cli.log_format, "page_indexer", &telemetry);
+    Ok(Some(telemetry))
+}
+
+/// Print a value as pretty JSON on stdout.
+fn print_json(value: &serde_json::Value) -> Result<()> {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(value).context("Failed to serialize JSON output")?
+    );
+    Ok(())
+}
+
+/// Save the usage report if `--usage-report` was given.
+/// Open the audit log searches and answers are appended to, if any.
+fn open_audit_log(path: Option<&Path>) -> Result<Option<AuditLog>> {
+    path.map(|path| {
+        AuditLog::open(path)
+            .with_context(|| format!("Failed to open audit log '{}'", path.display()))
+    })
+    .transpose()
+}
+
+fn save_usage_report(report: &UsageReport, path: Option<&Path>) -> Result<()> {
+    if let Some(path) = path {
+        report.save(path).context("Failed to save usage report")?;
+        info!(path = %path.display(), "saved usage report");
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn cmd_index(
+    document_path: PathBuf,
+    output: PathBuf,
+    resume: bool,
+    confidence: bool,
+    verify_sample: Option<f32>,
+    extract_dates: bool,
+    json: bool,
+    usage_report: Option<&Path>,
+) -> Result<()> {
+    info!("loading configuration");
+    let mut config = Config::load().context("Failed to load configuration")?;
+    config.validate().context("Invalid configuration")?;
+    config.indexer.score_confidence |= confidence;
+    config.indexer.extract_dates |= extract_dates;
+    if verify_sample.is_some() {
+        config.indexer.verify_sample = verify_sample;
+    }
+
+    info!(document = %document_path.display(), model = %config.llm.model, "indexing document");
+
+    let start = Instant::now();
+    let mut tracker = UsageTracker::new("index", &config.llm.model);
+
+    // Load document
+    let document = loader::load(&document_path).context("Failed to load document")?;
+    tracker.phase("load_document", Default::default());
+
+    info!(
+        name = %document.name,
+        pages = document.page_count(),
+        estimated_tokens = document.total_tokens(),
+        "loaded document"
+    );
+
+    // Create client and indexer
+    let client = LlmClient::new(config.llm.clone());
+    let (bar, progress) = progress_bar(json);
+    let indexer = TreeIndexer::with_options(client.clone(), IndexerOptions::from_config(&config))
+        .with_progress(progress)
+        .with_checkpoint(checkpoint_path_for(&output), resume);
+
+    // Build tree index
+    let tree = indexer.index(&document).await;
+    bar.finish_and_clear();
+    let tree = tree.context("Failed to build tree index")?;
+
+    let build_duration = start.elapsed();
+    tracker.phase("build_tree", client.usage());
+
+    // Save tree
+    save_tree(&tree, &output).context("Failed to save tree index")?;
+    let size = tree_size(&output)?;
+    tracker.phase("save_index", client.usage());
+
+    let report = tracker.finish(config.llm.pricing.as_ref());
+    save_usage_report(&report, usage_report)?;
+    let low_confidence = config
+        .indexer
+        .score_confidence
+        .then(|| tree.low_confidence(LOW_CONFIDENCE).len());
+
+    if json {
+        return print_json(&serde_json::json!({
+            "document": document.name,
+            "document_path": document_path,
+            "pages": document.page_count(),
+            "estimated_tokens": document.total_tokens(),
+            "sections": tree.node_count(),
+            "max_depth": tree.max_depth(),
+            "low_confidence": low_confidence,
+            "output": output,
+            "file_size_bytes": size,
+            "build_time_ms": build_duration.as_millis() as u64,
+            "model": client.model(),
+            "usage": client.usage(),
+            "usage_report": report,
+        }));
+    }
+
+    // Show stats
+    println!("Tree Index Built:");
+    println!("  Sections:    {}", tree.node_count());
+    println!("  Max depth:   {}", tree.max_depth());
+    if let Some(low) = low_confidence {
+        println!("  Low confidence: {} (see show --confidence)", low);
+    }
+    println!("  Build time:  {:.2?}", build_duration);
+
+    println!("\nIndex saved to: {}", output.display());
+    println!("  File size: {:.1} KB", size as f64 / 1024.0);
+    report.print_summary();
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn cmd_index_corpus(
+    dir: PathBuf,
+    index_dir: PathBuf,
+    confidence: bool,
+    verify_sample: Option<f32>,
+    extract_dates: bool,
+    fail_on: ExitPolicy,
+    json: bool,
+    usage_report: Option<&Path>,
+) -> Result<()> {
+    let mut config = Config::load().context("Failed to load configuration")?;
+    config.validate().context("Invalid configuration")?;
+    config.indexer.score_confidence |= confidence;
+    config.indexer.extract_dates |= extract_dates;
+    if verify_sample.is_some() {
+        config.indexer.verify_sample = verify_sample;
+    }
+
+    info!(corpus = %dir.display(), index_dir = %index_dir.display(), "indexing directory");
+
+    let mut tracker = UsageTracker::new("index", &config.llm.model);
+    let client = LlmClient::new(config.llm.clone());
+    let indexer = TreeIndexer::with_options(client.clone(), IndexerOptions::from_config(&config));
+    let watcher = DirectoryWatcher::new(&dir, &index_dir, indexer);
+    let report = watcher.sync().await.context("Failed to index directory")?;
+    tracker.phase("build_trees", client.usage());
+
+    let usage = tracker.finish(config.llm.pricing.as_ref());
+    save_usage_report(&usage, usage_report)?;
+    let summary = report.indexed.summary();
+
+    if json {
+        print_json(&serde_json::json!({
+            "corpus": dir,
+            "index_dir": index_dir,
+            "indexed": report.indexed.succeeded,
+            "unchanged": report.unchanged,
+            "failed": report.indexed.failed,
+            "summary": summary,
+            "model": client.model(),
+            "usage_report": usage,
+        }))?;
+        return check_failures(fail_on, &summary);
+    }
+
+    for path in &report.indexed.succeeded {
+        println!("indexed: {}", path.display());
+    }
+    for failure in &report.indexed.failed {
+        println!(
+            "failed:  {} [{}] {}",
+            failure.item, failure.kind, failure.message
+        );
+    }
+    println!(
+        "\nIndexed {} document(s), {} unchanged; {}",
+        report.indexed.succeeded.len(),
+        report.unchanged.len(),
+        summary
+    );
+    println!("Indexes in: {}", index_dir.display());
+    usage.print_summary();
+
+    check_failures(fail_on, &summary)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn cmd_search(
+    query: String,
+    index_path: PathBuf,
+    top_k: usize,
+    with_content: bool,
+    document_path: Option<PathBuf>,
+    options: SearchOptions,
+    auto_reindex: bool,
+    langchain: bool,
+    export_context: Option<&Path>,
+    json: bool,
+    usage_report: Option<&Path>,
+    audit_log: Option<&Path>,
+) -> Result<()> {
+    if !tree_exists(&index_path) {
+        anyhow::bail!(
+            "Index not found at '{}'. Run 'index' command first.",
+            index_path.display()
+        );
+    }
+
+    if with_content && document_path.is_none() {
+        anyhow::bail!("--document is required when using --with-content");
+    }
+
+    info!("loading configuration");
+    let config = Config::load().context("Failed to load configuration")?;
+    config.validate().context("Invalid configuration")?;
+
+    let mut tracker = UsageTracker::new("search", &config.llm.model);
+    let mut tree = load_tree(&index_path).context("Failed to load tree index")?;
+    tracker.phase("load_index", Default::default());
+
+    let client = LlmClient::new(config.llm.clone());
+    if let Some(fresh) = refresh_stale(
+        &tree,
+        &index_path,
+        document_path.as_deref(),
+        auto_reindex,
+        &config,
+        &client,
+    )
+    .await?
+    {
+        tree = fresh;
+        tracker.phase("reindex", client.usage());
+    }
+    let audit = open_audit_log(audit_log)?;
+    let before = client.usage();
+    let (bar, progress) = progress_bar(json);
+    // The ranking model reads the shortlist's content, so it needs the document
+    let rank = config.models.rank.is_some() && document_path.is_some();
+    let verify = options.verify_results;
+    let searcher =
+        TreeSearcher::with_models(&client, &config.models, options).with_progress(progress);
+
+    info!(query = %query, model = %config.llm.model, index = %index_path.display(), "searching");
+
+    let start = Instant::now();
+
+    let outcome = if with_content || verify || rank || export_context.is_some() {
+        let document =
+            loader::load(document_path.as_ref().unwrap()).context("Failed to load document")?;
+        searcher
+            .search_with_content_within(&tree, &document, &query)
+            .await
+    } else {
+        searcher.search_within(&tree, &query).await
+    };
+    bar.finish_and_clear();
+    let SearchOutcome {
+        mut results,
+        truncated,
+    } = outcome.context("Search failed")?;
+    if let Some(path) = export_context {
+        let shown: Vec<_> = results.iter().take(top_k).cloned().collect();
+        std::fs::write(path, to_context_markdown(&tree, &query, &shown))
+            .with_context(|| format!("Failed to write context to '{}'", path.display()))?;
+        info!(path = %path.display(), "exported context");
+    }
+    if !with_content {
+        // Fetched only for verification, ranking or the context export
+        results.iter_mut().for_each(|r| r.content = None);
+    }
+
+    let search_duration = start.elapsed();
+    tracker.phase("search", client.usage());
+
+    let report = tracker.finish(config.llm.pricing.as_ref());
+    save_usage_report(&report, usage_report)?;
+    if let Some(audit) = &audit {
+        let shown: Vec<_> = results.iter().take(top_k).cloned().collect();
+        let mut record = AuditRecord::search(&query, &result_documents(&tree, &shown));
+        record.index = Some(index_path.display().to_string());
+        record.usage = Some(client.usage().since(&before));
+        audit.record(&record).context("Failed to write audit log")?;
+    }
+
+    if langchain {
+        let results: Vec<_> = results.into_iter().take(top_k).collect();
+        return print_json(&serde_json::to_value(result_documents(&tree, &results))?);
+    }
+
+    if json {
+        let results: Vec<_> = results.into_iter().take(top_k).collect();
+        return print_json(&serde_json::json!({
+            "query": query,
+            "index": index_path,
+            "results": results,
+            "truncated": truncated,
+            "search_time_ms": search_duration.as_millis() as u64,
+            "model": client.model(),
+            "usage": client.usage(),
+            "usage_report": report,
+            "context": export_context,
+        }));
+    }
+
+    if results.is_empty() {
+        println!("No relevant sections found.");
+    } else {
+        println!("Results:");
+        println!("{}", "─".repeat(60));
+
+        for (i, result) in results.iter().take(top_k).enumerate() {
+            println!(
+                "{:>2}. {} [pages {}-{}] ({:?})",
+                i + 1,
+                result.title,
+                result.start_index,
+                result.end_index,
+                result.relevance
+            );
+            println!("    Reason: {}", result.reason);
+            if let Some(snippet) = &result.snippet {
+                println!("    Snippet: {}", snippet);
+            }
+
+            if let Some(content) = &result.content {
+                println!("    Content preview:");
+                let preview: String = content.chars().take(200).collect();
+                for line in preview.lines().take(3) {
+                    println!("      {}", line);
+                }
+                if content.len() > 200 {
+                    println!("      ...");
+                }
+            }
+            println!();
+        }
+
+        println!("{}", "─".repeat(60));
+        println!("Found {} results in {:.2?}", results.len(), search_duration);
+    }
+    if truncated {
+        println!("Search deadline passed: these are the results found in time.");
+    }
+    if let Some(path) = export_context {
+        println!("Context written to: {}", path.display());
+    }
+    report.print_summary();
+
+    Ok(())
+}
+
+/// Query normalization for searches of an index: its settings file
+/// (`tree.json.query`), if any, with `--spell-correct` and `--glossary`
+/// added on top. `None` when there is nothing to normalize.
+fn query_normalizer(
+    index_path: &Path,
+    spell_correct: bool,
+    glossary: Option<&Path>,
+) -> Result<Option<QueryNormalizer>> {
+    let mut normalizer = QueryNormalizer::for_index(index_path)
+        .context("Failed to load query settings")?
+        .unwrap_or_default();
+    if let Some(path) = glossary {
+        let entries = load_glossary(path).context("Failed to load glossary")?;
+        normalizer = normalizer.with_glossary(entries);
+    }
+    normalizer.spell_correct |= spell_correct;
+    Ok((normalizer != QueryNormalizer::default()).then_some(normalizer))
+}
+
+/// The glossary of `--terms`, if given.
+fn load_terms(path: Option<&Path>) -> Result<BTreeMap<String, String>> {
+    let terms = path.map(load_glossary).transpose();
+    Ok(terms.context("Failed to load terms")?.unwrap_or_default())
+}
+
+/// Check whether an index's source document (`document`, or the path
+/// recorded in the tree) changed since the index was built. A changed
+/// source is re-indexed and the new tree saved over the index when
+/// `auto_reindex` is set, resuming from a checkpoint if a previous attempt
+/// was interrupted; otherwise it only gets a warning.
+async fn refresh_stale(
+    tree: &DocumentTree,
+    index_path: &Path,
+    document: Option<&Path>,
+    auto_reindex: bool,
+    config: &Config,
+    client: &LlmClient,
+) -> Result<Option<DocumentTree>> {
+    let Some(source) = document
+        .map(Path::to_path_buf)
+        .or_else(|| tree.source.as_ref().map(|s| PathBuf::from(&s.path)))
+    else {
+        return Ok(None);
+    };
+    match tree.freshness_of(&source) {
+        Freshness::Changed => {}
+        Freshness::Missing => {
+            info!(source = %source.display(), "source document not found; skipping freshness check");
+            return Ok(None);
+        }
+        Freshness::Fresh | Freshness::Unknown => return Ok(None),
+    }
+    if !auto_reindex {
+        warn!(
+            index = %index_path.display(),
+            source = %source.display(),
+            "the document changed since this index was built; results may be out of date \
+             (re-index it, or pass --auto-reindex)"
+        );
+        return Ok(None);
+    }
+
+    info!(source = %source.display(), "re-indexing changed document");
+    let document = loader::load(&source).context("Failed to load document")?;
+    let indexer = TreeIndexer::with_options(client.clone(), IndexerOptions::from_config(config))
+        .with_checkpoint(checkpoint_path_for(index_path), true);
+    let mut fresh = indexer
+        .index(&document)
+        .await
+        .context("Failed to re-index the document")?;
+    fresh.metadata = tree.metadata.clone();
+    save_tree(&fresh, index_path).context("Failed to save tree index")?;
+    Ok(Some(fresh))
+}
+
+async fn cmd_zoom(
+    query: String,
+    node: String,
+    index_path: PathBuf,
+    document_path: PathBuf,
+    top_k: usize,
+    json: bool,
+    usage_report: Option<&Path>,
+) -> Result<()> {
+    if !tree_exists(&index_path) {
+        anyhow::bail!(
+            "Index not found at '{}'. Run 'index' command first.",
+            index_path.display()
+        );
+    }
+
+    let config = Config::load().context("Failed to load configuration")?;
+    config.validate().context("Invalid configuration")?;
+
+    let mut tracker = UsageTracker::new("zoom", &config.llm.model);
+    let tree = load_tree(&index_path).context("Failed to load tree index")?;
+    let document = loader::load(&document_path).context("Failed to load document")?;
+    tracker.phase("load_index", Default::default());
+
+    let client = LlmClient::new(config.llm.clone());
+    let searcher = TreeSearcher::with_options(
+        client.clone(),
+        SearchOptions {
+            top_k,
+            ..Default::default()
+        },
+    );
+    info!(query = %query, node = %node, "zooming");
+
+    let start = Instant::now();
+    let passages = searcher
+        .zoom(&tree, &document, &node, &query)
+        .await
+        .context("Zoom failed")?;
+    let duration = start.elapsed();
+    tracker.phase("zoom", client.usage());
+
+    let report = tracker.finish(config.llm.pricing.as_ref());
+    save_usage_report(&report, usage_report)?;
+
+    if json {
+        return print_json(&serde_json::json!({
+            "query": query,
+            "node": node,
+            "passages": passages,
+            "search_time_ms": duration.as_millis() as u64,
+            "model": client.model(),
+            "usage": client.usage(),
+            "usage_report": report,
+        }));
+    }
+
+    if passages.is_empty() {
+        println!("No passage in this section answers the query.");
+    } else {
+        println!("Passages:");
+        println!("{}", "─".repeat(60));
+        for (i, passage) in passages.iter().enumerate() {
+            println!(
+                "{:>2}. page {}, paragraph {} ({:?})",
+                i + 1,
+                passage.page,
+                passage.paragraph,
+                passage.relevance
+            );
+            println!("    Reason: {}", passage.reason);
+            for line in passage.text.lines() {
+                println!("      {}", line);
+            }
+            println!();
+        }
+        println!("{}", "─".repeat(60));
+        println!("Found {} passages in {:.2?}", passages.len(), duration);
+    }
+    report.print_summary();
+
+    Ok(())
+}
+
+async fn cmd_extract(
+    query: String,
+    schema_path: PathBuf,
+    index_path: PathBuf,
+    document_path: PathBuf,
+    top_k: usize,
+    json: bool,
+    usage_report: Option<&Path>,
+) -> Result<()> {
+    if !tree_exists(&index_path) {
+        anyhow::bail!(
+            "Index not found at '{}'. Run 'index' command first.",
+            index_path.display()
+        );
+    }
+    let schema: serde_json::Value = serde_json::from_str(
+        &std::fs::read_to_string(&schema_path).context("Failed to read schema")?,
+    )
+    .context("Schema is not valid JSON")?;
+
+    let config = Config::load().context("Failed to load configuration")?;
+    config.validate().context("Invalid configuration")?;
+
+    let mut tracker = UsageTracker::new("extract", &config.llm.model);
+    let tree = load_tree(&index_path).context("Failed to load tree index")?;
+    let document = loader::load(&document_path).context("Failed to load document")?;
+    tracker.phase("load_index", Default::default());
+
+    let client = LlmClient::new(config.llm.clone());
+    let searcher = TreeSearcher::with_options(
+        client.clone(),
+        SearchOptions {
+            top_k,
+            ..Default::default()
+        },
+    );
+    info!(query = %query, "extracting");
+
+    let start = Instant::now();
+    let sources = searcher
+        .search_with_content(&tree, &document, &query)
+        .await
+        .context("Search failed")?;
+    tracker.phase("search", client.usage());
+    let extraction = Extractor::new(client.clone())
+        .extract(&query, &schema, sources)
+        .await
+        .context("Extraction failed")?;
+    let duration = start.elapsed();
+    tracker.phase("extract", client.usage());
+
+    let report = tracker.finish(config.llm.pricing.as_ref());
+    save_usage_report(&report, usage_report)?;
+
+    if json {
+        return print_json(&serde_json::json!({
+            "query": query,
+            "data": extraction.data,
+            "citations": extraction.citations,
+            "sources": extraction.sources,
+            "extract_time_ms": duration.as_millis() as u64,
+            "model": client.model(),
+            "usage": client.usage(),
+            "usage_report": report,
+        }));
+    }
+
+    println!("{}", serde_json::to_string_pretty(&extraction.data)?);
+    println!("{}", "─".repeat(60));
+    if extraction.citations.is_empty() {
+        println!("No citations.");
+    }
+    for citation in &extraction.citations {
+        println!(
+            "{} from {} [pages {}-{}]",
+            citation.field, citation.title, citation.start_index, citation.end_index
+        );
+        if !citation.quote.is_empty() {
+            println!("    \"{}\"", citation.quote);
+        }
+    }
+    println!("{}", "─".repeat(60));
+    println!(
+        "Extracted from {} sections in {:.2?}",
+        extraction.sources.len(),
+        duration
+    );
+    report.print_summary();
+
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn cmd_route(
+    query: String,
+    taxonomy_path: PathBuf,
+    index_dir: PathBuf,
+    top_k: usize,
+    classify_only: bool,
+    terms: BTreeMap<String, String>,
+    json: bool,
+    usage_report: Option<&Path>,
+) -> Result<()> {
+    let taxonomy = Taxonomy::load(&taxonomy_path).context("Failed to load taxonomy")?;
+    if !index_dir.is_dir() {
+        anyhow::bail!("Index directory not found at '{}'", index_dir.display());
+    }
+
+    let config = Config::load().context("Failed to load configuration")?;
+    config.validate().context("Invalid configuration")?;
+
+    let mut tracker = UsageTracker::new("route", &config.llm.model);
+    let trees = index_files(&index_dir)?
+        .iter()
+        .map(|path| load_tree(path).with_context(|| format!("Failed to load '{}'", path.display())))
+        .collect::<Result<Vec<_>>>()?;
+    if trees.is_empty() {
+        anyhow::bail!("No indexes found in '{}'", index_dir.display());
+    }
+    tracker.phase("load_index", Default::default());
+
+    let client = LlmClient::new(config.llm.clone());
+    let router = QueryRouter::new(client.clone(), taxonomy);
+    info!(query = %query, documents = trees.len(), "routing");
+
+    let start = Instant::now();
+    let (route, results) = if classify_only {
+        let route = router
+            .route(&query, &trees)
+            .await
+            .context("Routing failed")?;
+        (route, Vec::new())
+    } else {
+        let searcher = TreeSearcher::with_options(
+            client.clone(),
+            SearchOptions {
+                top_k,
+                terms,
+                ..Default::default()
+            },
+        );
+        let routed = router
+            .search(&searcher, &trees, &query)
+            .await
+            .context("Search failed")?;
+        (routed.route, routed.results)
+    };
+    let duration = start.elapsed();
+    tracker.phase("route", client.usage());
+
+    let report = tracker.finish(config.llm.pricing.as_ref());
+    save_usage_report(&report, usage_report)?;
+
+    let documents: Vec<&str> = route
+        .selected
+        .iter()
+        .map(|&i| trees[i].name.as_str())
+        .collect();
+
+    if json {
+        return print_json(&serde_json::json!({
+            "query": query,
+            "categories": route.categories,
+            "documents": documents,
+            "fallback": route.fallback,
+            "results": results,
+            "search_time_ms": duration.as_millis() as u64,
+            "model": client.model(),
+            "usage": client.usage(),
+            "usage_report": report,
+        }));
+    }
+
+    let categories = if route.categories.is_empty() {
+        "none".to_string()
+    } else {
+        route.categories.join(", ")
+    };
+    println!("Categories: {}", categories);
+    println!(
+        "Documents:  {} of {}{}",
+        documents.len(),
+        trees.len(),
+        if route.fallback {
+            " (no match, searching all)"
+        } else {
+            ""
+        }
+    );
+    for name in &documents {
+        println!("  - {}", name);
+    }
+
+    if !classify_only {
+        println!();
+        if results.is_empty() {
+            println!("No relevant sections found.");
+        } else {
+            println!("Results:");
+            println!("{}", "─".repeat(60));
+            for (i, routed) in results.iter().enumerate() {
+                let result = &routed.result;
+                println!(
+                    "{:>2}. {} — {} [pages {}-{}] ({:?})",
+                    i + 1,
+                    routed.document,
+                    result.title,
+                    result.start_index,
+                    result.end_index,
+                    result.relevance
+                );
+                println!("    Reason: {}", result.reason);
+                println!();
+            }
+            println!("{}", "─".repeat(60));
+        }
+        println!("Searched in {:.2?}", duration);
+    }
+    report.print_summary();
+
+    Ok(())
+}
+
+/// Options for `search --queries`.
+struct BatchArgs {
+    queries: PathBuf,
+    report: Option<PathBuf>,
+    concurrency: usize,
+    top_k: usize,
+    with_content: bool,
+    document: Option<PathBuf>,
+    verify: bool,
+    outline_tokens: Option<usize>,
+    paraphrases: usize,
+    auto_reindex: bool,
+    include_excluded: bool,
+    acl: Option<Vec<String>>,
+    deadline_ms: Option<u64>,
+    spell_correct: bool,
+    glossary: Option<PathBuf>,
+    terms: Option<PathBuf>,
+    prefer_recent: bool,
+    since: Option<String>,
+}
+
+async fn cmd_batch_search(
+    args: BatchArgs,
+    index_path: PathBuf,
+    fail_on: ExitPolicy,
+    json: bool,
+    usage_report: Option<&Path>,
+    audit_log: Option<&Path>,
+) -> Result<()> {
+    if !tree_exists(&index_path) {
+        anyhow::bail!(
+            "Index not found at '{}'. Run 'index' command first.",
+            index_path.display()
+        );
+    }
+    if args.with_content && args.document.is_none() {
+        anyhow::bail!("--document is required when using --with-content");
+    }
+
+    let config = Config::load().context("Failed to load configuration")?;
+    config.validate().context("Invalid configuration")?;
+
+    let mut tracker = UsageTracker::new("search", &config.llm.model);
+    let queries = load_queries(&args.queries).context("Failed to load queries")?;
+    let mut tree = load_tree(&index_path).context("Failed to load tree index")?;
+    let rank = config.models.rank.is_some();
+    let document = match (&args.document, args.with_content || args.verify || rank) {
+        (Some(path), true) => Some(Arc::new(
+            loader::load(path).context("Failed to load document")?,
+        )),
+        _ => None,
+    };
+    tracker.phase("load_index", Default::default());
+
+    let client = LlmClient::new(config.llm.clone());
+    if let Some(fresh) = refresh_stale(
+        &tree,
+        &index_path,
+        args.document.as_deref(),
+        args.auto_reindex,
+        &config,
+        &client,
+    )
+    .await?
+    {
+        tree = fresh;
+        tracker.phase("reindex", client.usage());
+    }
+    let tree = Arc::new(tree);
+
+    info!(
+        queries = queries.len(),
+        concurrency = args.concurrency,
+        "running batch search"
+    );
+
+    let options = SearchOptions {
+        top_k: args.top_k,
+        verify_results: args.verify,
+        outline_tokens: args.outline_tokens,
+        paraphrases: args.paraphrases,
+        include_excluded: args.include_excluded,
+        acl_labels: args.acl,
+        deadline: args.deadline_ms.map(Duration::from_millis),
+        normalizer: query_normalizer(&index_path, args.spell_correct, args.glossary.as_deref())?,
+        terms: load_terms(args.terms.as_deref())?,
+        prefer_recent: args.prefer_recent,
+        effective_since: args.since,
+        ..Default::default()
+    };
+    let (bar, progress) = progress_bar(json);
+    bar.set_message("Running queries");
+    let runner = BatchRunner::new(TreeSearcher::with_models(&client, &config.models, options))
+        .with_concurrency(args.concurrency)
+        .with_progress(progress);
+
+    let audit = open_audit_log(audit_log)?;
+    let mut results = runner.run(tree.clone(), document, queries).await;
+    bar.finish_and_clear();
+    if !args.with_content {
+        // Fetched only for verification or ranking
+        for result in results.iter_mut().flat_map(|b| &mut b.results) {
+            result.content = None;
+        }
+    }
+    tracker.phase("search", client.usage());
+
+    let report = tracker.finish(config.llm.pricing.as_ref());
+    save_usage_report(&report, usage_report)?;
+    if let Some(audit) = &audit {
+        // Queries share the client concurrently, so their usage is unknown
+        for result in results.iter().filter(|r| r.error.is_none()) {
+            let mut record =
+                AuditRecord::search(&result.query, &result_documents(&tree, &result.results));
+            record.index = Some(index_path.display().to_string());
+            audit.record(&record).context("Failed to write audit log")?;
+        }
+    }
+
+    if let Some(path) = &args.report {
+        write_report(&results, path).context("Failed to write batch report")?;
+        info!(path = %path.display(), "saved batch report");
+    }
+
+    let summary = failure_summary(&results);
+
+    if json {
+        print_json(&serde_json::json!({
+            "index": index_path,
+            "queries": results.len(),
+            "failed": summary.failed,
+            "summary": summary,
+            "results": results,
+            "report": args.report,
+            "usage_report": report,
+        }))?;
+        return check_failures(fail_on, &summary);
+    }
+
+    for result in &results {
+        match (&result.error, result.results.first()) {
+            (Some(error), _) => println!("[{}] {} -> error: {}", result.id, result.query, error),
+            (None, Some(top)) => println!(
+                "[{}] {} -> {} result(s), top: {} [pages {}-{}]",
+                result.id,
+                result.query,
+                result.results.len(),
+                top.title,
+                top.start_index,
+                top.end_index
+            ),
+            (None, None) => println!("[{}] {} -> no results", result.id, result.query),
+        }
+    }
+    println!("\nRan {} queries: {}", results.len(), summary);
+    if let Some(path) = &args.report {
+        println!("Report saved to: {}", path.display());
+    }
+    report.print_summary();
+
+    check_failures(fail_on, &summary)
+}
+
+/// Create a progress bar driven by indexer/search progress events.
+///
+/// Draws to stderr, so piped stdout output is unaffected. Hidden in JSON mode.
+fn progress_bar(hidden: bool) -> (ProgressBar, ProgressCallback) {
+    let bar = if hidden {
+        ProgressBar::hidden()
+    } else {
+        ProgressBar::new(0)
+    };
+    bar.set_style(
+        ProgressStyle::with_template(
+            "{spinner:.green} {msg:<22} [{bar:30.cyan/blue}] {pos}/{len} ({elapsed}, eta {eta})",
+        )
+        .expect("valid progress template")
+        .progress_chars("=> "),
+    );
+    bar.enable_steady_tick(Duration::from_millis(120));
+
+    let handle = bar.clone();
+    let callback: ProgressCallback = Arc::new(move |event: &ProgressEvent| match event {
+        ProgressEvent::ChunkProcessed { completed, total } => {
+            handle.set_message("Extracting structure");
+            handle.set_length(*total as u64);
+            handle.set_position(*completed as u64);
+        }
+        ProgressEvent::NodeVerified { completed, total } => {
+            if *completed == 0 {
+                handle.reset_eta();
+            }
+            handle.set_message("Verifying titles");
+            handle.set_length(*total as u64);
+            handle.set_position(*completed as u64);
+        }
+        ProgressEvent::SummaryGenerated {
+            completed, total, ..
+        } => {
+            if *completed == 0 {
+                handle.reset_eta();
+            }
+            handle.set_message("Generating summaries");
+            handle.set_length(*total as u64);
+            handle.set_position(*completed as u64);
+        }
+        ProgressEvent::SearchStarted { nodes } => {
+            handle.set_style(
+                ProgressStyle::with_template("{spinner:.green} {msg} ({elapsed})")
+                    .expect("valid progress template"),
+            );
+            handle.set_message(format!("Reasoning over {} sections", nodes));
+        }
+        ProgressEvent::SearchCompleted { .. } => handle.finish_and_clear(),
+        ProgressEvent::ItemCompleted {
+            completed, total, ..
+        } => {
+            handle.set_length(*total as u64);
+            handle.set_position(*completed as u64);
+        }
+        ProgressEvent::ItemStarted { .. } | ProgressEvent::WinsTally { .. } => {}
+    });
+
+    (bar, callback)
+}
+
+/// Parse a page range like `10-20`, or a single page like `15`.
+fn parse_page_range(s: &str) -> std::result::Result<(usize, usize), String> {
+    let parse = |p: &str| {
+        p.trim()
+            .parse::<usize>()
+            .map_err(|_| format!("invalid page number '{}'", p.trim()))
+    };
+    let (start, end) = match s.split_once('-') {
+        Some((start, end)) => (parse(start)?, parse(end)?),
+        None => {
+            let page = parse(s)?;
+            (page, page)
+        }
+    };
+    if start == 0 || end < start {
+        return Err(format!("invalid page range '{}'", s));
+    }
+    Ok((start, end))
+}
+
+fn parse_date(s: &str) -> std::result::Result<String, String> {
+    parse_effective_date(s).ok_or_else(|| format!("invalid date '{}' (expected YYYY-MM-DD)", s))
+}
+
+fn cmd_show(
+    index_path: PathBuf,
+    filter: TreeFilter,
+    display: DisplayOptions,
+    json: bool,
+) -> Result<()> {
+    if !tree_exists(&index_path) {
+        anyhow::bail!(
+            "Index not found at '{}'. Run 'index' command first.",
+            index_path.display()
+        );
+    }
+
+    let tree = load_tree(&index_path).context("Failed to load tree index")?;
+    let shown = if filter == TreeFilter::default() {
+        tree.clone()
+    } else {
+        tree.filtered(&filter)
+    };
+
+    if json {
+        let json_str = shown.to_json().context("Failed to serialize tree")?;
+        println!("{}", json_str);
+    } else {
+        print!("{}", shown.format_options(&display));
+        if shown.node_count() != tree.node_count() {
+            println!(
+                "\nShowing {} of {} sections",
+                shown.node_count(),
+                tree.node_count()
+            );
+        }
+        if display.confidence {
+            let low = shown.low_confidence(LOW_CONFIDENCE).len();
+            if low > 0 {
+                println!(
+                    "\n{} sections below {:.2} confidence are worth double-checking",
+                    low, LOW_CONFIDENCE
+                );
+            } else if !shown.has_confidence() {
+                println!("\nNo confidence scores; re-index with --confidence");
+            }
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+async fn cmd_export(
+    index_path: PathBuf,
+    format: ExportFormat,
+    output: Option<PathBuf>,
+    document_path: Option<PathBuf>,
+    embed: bool,
+) -> Result<()> {
+    if !tree_exists(&index_path) {
+        anyhow::bail!(
+            "Index not found at '{}'. Run 'index' command first.",
+            index_path.display()
+        );
+    }
+
+    let mut tree = load_tree(&index_path).context("Failed to load tree index")?;
+    if embed {
+        if !matches!(format, ExportFormat::Qdrant | ExportFormat::Pgvector) {
+            anyhow::bail!("--embed is only used with --format qdrant or pgvector");
+        }
+        let config = Config::load().context("Failed to load configuration")?;
+        config.validate().context("Invalid configuration")?;
+        let Some(model) = &config.models.embed else {
+            anyhow::bail!(
+                "--embed needs an embedding model: set models.embed in the config file or LLM_EMBED_MODEL"
+            );
+        };
+        let client = LlmClient::new(config.llm.clone()).with_model(model);
+        let embedded = embed_nodes(&client, &mut tree, false)
+            .await
+            .context("Failed to embed nodes")?;
+        if embedded > 0 {
+            save_tree(&tree, &index_path).context("Failed to save tree index")?;
+            eprintln!(
+                "Embedded {} node(s) with {} (saved to {})",
+                embedded,
+                model,
+                index_path.display()
+            );
+        }
+    }
+    let exported = match (format, document_path) {
+        (ExportFormat::LangChain, Some(path)) => {
+            let document = loader::load(&path).context("Failed to load document")?;
+            serde_json::to_string_pretty(&tree_documents(&tree, Some(&document)))?
+        }
+        (_, Some(_)) => anyhow::bail!("--document is only used with --format langchain"),
+        (format, None) => export_tree(&tree, format).context("Failed to export tree")?,
+    };
+
+    match output {
+        Some(path) => {
+            std::fs::write(&path, exported)
+                .with_context(|| format!("Failed to write '{}'", path.display()))?;
+            println!("Exported {} to {}", tree.name, path.display());
+        }
+        None => print!("{}", exported),
+    }
+
+    Ok(())
+}
+
+async fn cmd_summarize(
+    index_path: PathBuf,
+    document_path: PathBuf,
+    output: Option<PathBuf>,
+    concurrency: usize,
+    resummarize_leaves: bool,
+    json: bool,
+    usage_report: Option<&Path>,
+) -> Result<()> {
+    if !tree_exists(&index_path) {
+        anyhow::bail!(
+            "Index not found at '{}'. Run 'index' command first.",
+            index_path.display()
+        );
+    }
+
+    let config = Config::load().context("Failed to load configuration")?;
+    config.validate().context("Invalid configuration")?;
+    let mut tracker = UsageTracker::new("summarize", &config.llm.model);
+
+    let mut tree = load_tree(&index_path).context("Failed to load tree index")?;
+    let document = loader::load(&document_path).context("Failed to load document")?;
+    tracker.phase("load", Default::default());
+
+    let client = LlmClient::new(config.llm.clone());
+    let (bar, progress) = progress_bar(json);
+    let summary = TreeSummarizer::new(client.clone())
+        .with_concurrency(concurrency)
+        .with_resummarize_leaves(resummarize_leaves)
+        .with_progress(progress)
+        .summarize(&mut tree, &document)
+        .await;
+    bar.finish_and_clear();
+    let summary = summary.context("Failed to summarize document")?;
+    tracker.phase("summarize", client.usage());
+
+    let output = output.unwrap_or(index_path);
+    save_tree(&tree, &output).context("Failed to save tree index")?;
+
+    let report = tracker.finish(config.llm.pricing.as_ref());
+    save_usage_report(&report, usage_report)?;
+
+    if json {
+        return print_json(&serde_json::json!({
+            "document": tree.name,
+            "summary": summary,
+            "sections": tree.node_count(),
+            "output": output,
+            "usage": client.usage(),
+        }));
+    }
+
+    println!("{}\n", summary);
+    println!("Summaries saved to: {}", output.display());
+    report.print_summary();
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn cmd_refresh(
+    index_path: PathBuf,
+    document_path: PathBuf,
+    output: Option<PathBuf>,
+    min_retrievals: u64,
+    limit: Option<usize>,
+    embed: bool,
+    concurrency: usize,
+    json: bool,
+    usage_report: Option<&Path>,
+) -> Result<()> {
+    use rust_page_indexer::stats::{RetrievalStats, stats_path_for};
+
+    if !tree_exists(&index_path) {
+        anyhow::bail!(
+            "Index not found at '{}'. Run 'index' command first.",
+            index_path.display()
+        );
+    }
+
+    let config = Config::load().context("Failed to load configuration")?;
+    config.validate().context("Invalid configuration")?;
+    let embed_model = match (embed, &config.models.embed) {
+        (false, _) => None,
+        (true, Some(model)) => Some(model.clone()),
+        (true, None) => anyhow::bail!(
+            "--embed needs an embedding model: set models.embed in the config file or LLM_EMBED_MODEL"
+        ),
+    };
+    let mut tracker = UsageTracker::new("refresh", &config.llm.model);
+
+    let stats_path = stats_path_for(&index_path);
+    let mut stats = RetrievalStats::load(&stats_path).context("Failed to load retrieval stats")?;
+    if stats.queries == 0 {
+        anyhow::bail!(
+            "No retrieval stats at '{}'. Serve the index with 'serve --stats' first.",
+            stats_path.display()
+        );
+    }
+    let mut tree = load_tree(&index_path).context("Failed to load tree index")?;
+    let document = loader::load(&document_path).context("Failed to load document")?;
+    tracker.phase("load", Default::default());
+
+    let client = LlmClient::new(config.llm.clone());
+    let mut refresher = HotSectionRefresher::new(client.clone())
+        .with_min_retrievals(min_retrievals)
+        .with_concurrency(concurrency);
+    if let Some(limit) = limit {
+        refresher = refresher.with_limit(limit);
+    }
+    if let Some(model) = &embed_model {
+        refresher = refresher.with_embeddings(client.with_model(model));
+    }
+    let refreshed = refresher.refresh(&mut tree, &document, &mut stats).await;
+
+    // Sections refreshed before a failure keep their summaries
+    let output = output.unwrap_or(index_path);
+    save_tree(&tree, &output).context("Failed to save tree index")?;
+    stats
+        .save(&stats_path)
+        .context("Failed to save retrieval stats")?;
+    let refreshed = refreshed.context("Failed to refresh sections")?;
+    tracker.phase("refresh", client.usage());
+
+    let report = tracker.finish(config.llm.pricing.as_ref());
+    save_usage_report(&report, usage_report)?;
+
+    if json {
+        return print_json(&serde_json::json!({
+            "document": tree.name,
+            "refreshed": refreshed,
+            "embedded": embed_model.is_some() && !refreshed.is_empty(),
+            "output": output,
+            "usage": client.usage(),
+        }));
+    }
+
+    if refreshed.is_empty() {
+        println!(
+            "No section retrieved {}+ times since its last refresh.",
+            min_retrievals
+        );
+        return Ok(());
+    }
+    println!("Refreshed {} section(s)", refreshed.len());
+    println!("{}", "─".repeat(60));
+    for section in &refreshed {
+        println!(
+            "  {:>6}  {} (pages {}-{})",
+            section.retrievals, section.title, section.start_index, section.end_index
+        );
+    }
+    println!();
+    println!("Summaries saved to: {}", output.display());
+    report.print_summary();
+    Ok(())
+}
+
+fn cmd_import(
+    structure: PathBuf,
+    document_path: Option<PathBuf>,
+    format: Option<StructureFormat>,
+    output: PathBuf,
+    json: bool,
+) -> Result<()> {
+    let document = document_path
+        .as_deref()
+        .map(loader::load)
+        .transpose()
+        .context("Failed to load document")?;
+    let tree = import_structure(&structure, format, document.as_ref())
+        .context("Failed to import structure")?;
+    save_tree(&tree, &output).context("Failed to save tree index")?;
+
+    if json {
+        return print_json(&serde_json::json!({
+            "document": tree.name,
+            "structure": structure,
+            "pages": tree.total_pages,
+            "sections": tree.node_count(),
+            "max_depth": tree.max_depth(),
+            "output": output,
+            "file_size_bytes": tree_size(&output)?,
+        }));
+    }
+
+    println!(
+        "Imported {} sections ({} pages, depth {}) from {}",
+        tree.node_count(),
+        tree.total_pages,
+        tree.max_depth(),
+        structure.display()
+    );
+    println!("Index saved to: {}", output.display());
+    Ok(())
+}
+
+fn cmd_compact(index_path: PathBuf, output: Option<PathBuf>, json: bool) -> Result<()> {
+    if !tree_exists(&index_path) {
+        anyhow::bail!(
+            "Index not found at '{}'. Run 'index' command first.",
+            index_path.display()
+        );
+    }
+
+    let tree = load_tree(&index_path).context("Failed to load tree index")?;
+    let output = output.unwrap_or_else(|| {
+        let extension = match SaveFormat::from_path(&index_path) {
+            SaveFormat::Json => "nav.json",
+            SaveFormat::Bincode => "nav.bin",
+        };
+        index_path.with_extension(extension)
+    });
+    save_navigation_tree(&tree, &output).context("Failed to save navigation index")?;
+    let (before, after) = (tree_size(&index_path)?, tree_size(&output)?);
+
+    if json {
+        return print_json(&serde_json::json!({
+            "output": output,
+            "sections": tree.node_count(),
+            "file_size_bytes": after,
+            "original_size_bytes": before,
+        }));
+    }
+
+    println!(
+        "Wrote navigation index for {} to {} ({:.1} KB, was {:.1} KB)",
+        tree.name,
+        output.display(),
+        after as f64 / 1024.0,
+        before as f64 / 1024.0
+    );
+    Ok(())
+}
+
+fn cmd_info(index_path: PathBuf, stats: bool, json: bool) -> Result<()> {
+    if !tree_exists(&index_path) {
+        anyhow::bail!(
+            "Index not found at '{}'. Run 'index' command first.",
+            index_path.display()
+        );
+    }
+
+    let tree = load_tree(&index_path).context("Failed to load tree index")?;
+    let size = tree_size(&index_path)?;
+    if stats {
+        return print_retrieval_stats(&index_path, &tree, json);
+    }
+
+    if json {
+        return print_json(&serde_json::json!({
+            "document": tree.name,
+            "total_pages": tree.total_pages,
+            "sections": tree.node_count(),
+            "max_depth": tree.max_depth(),
+            "file_size_bytes": size,
+            "index_path": index_path,
+            "description": tree.description,
+            "navigation_only": tree.navigation_only,
+            "metadata": tree.metadata,
+            "source": tree.source,
+            "freshness": tree.freshness(),
+        }));
+    }
+
+    println!("Tree Index Information");
+    println!("{}", "─".repeat(40));
+    println!("  Document:     {}", tree.name);
+    println!("  Total pages:  {}", tree.total_pages);
+    println!("  Sections:     {}", tree.node_count());
+    println!("  Max depth:    {}", tree.max_depth());
+    println!("  File size:    {:.1} KB", size as f64 / 1024.0);
+    println!("  Index path:   {}", index_path.display());
+    if tree.navigation_only {
+        println!("  Navigation:   only (summaries stripped)");
+    }
+
+    for (key, value) in &tree.metadata {
+        println!("  Metadata:     {} = {}", key, value);
+    }
+    if let Some(source) = &tree.source {
+        let freshness = match tree.freshness() {
+            Freshness::Fresh => "unchanged",
+            Freshness::Changed => "changed since indexing",
+            Freshness::Missing => "not found",
+            Freshness::Unknown => "unknown",
+        };
+        println!("  Source:       {} ({})", source.path, freshness);
+    }
+
+    if let Some(desc) = &tree.description {
+        println!("  Description:  {}", desc);
+    }
+
+    Ok(())
+}
+
+/// The `info --stats` report: sections by how often they were retrieved,
+/// then those never retrieved.
+fn print_retrieval_stats(index_path: &Path, tree: &DocumentTree, json: bool) -> Result<()> {
+    use rust_page_indexer::stats::{RetrievalStats, stats_path_for};
+
+    let path = stats_path_for(index_path);
+    let stats = RetrievalStats::load(&path).context("Failed to load retrieval stats")?;
+    let never = stats.never_retrieved(tree);
+
+    if json {
+        return print_json(&serde_json::json!({
+            "document": tree.name,
+            "stats_path": path,
+            "queries": stats.queries,
+            "sections": stats.most_retrieved(),
+            "never_retrieved": never
+                .iter()
+                .map(|d| serde_json::json!({
+                    "node_id": d.id,
+                    "title": d.metadata.title,
+                    "start_index": d.metadata.start_index,
+                    "end_index": d.metadata.end_index,
+                }))
+                .collect::<Vec<_>>(),
+        }));
+    }
+
+    println!("Retrieval Statistics: {}", tree.name);
+    println!("{}", "─".repeat(60));
+    if stats.queries == 0 {
+        println!("  No queries recorded in {}", path.display());
+        println!("  Run 'serve --stats' to record them.");
+        return Ok(());
+    }
+    println!("  Queries:      {}", stats.queries);
+    println!("  Stats file:   {}", path.display());
+    println!();
+    println!(
+        "  {:>6}  {:>4} {:>4} {:>4}  Section",
+        "Hits", "High", "Med", "Low"
+    );
+    for section in stats.most_retrieved() {
+        println!(
+            "  {:>6}  {:>4} {:>4} {:>4}  {} (pages {}-{})",
+            section.retrievals,
+            section.high,
+            section.medium,
+            section.low,
+            section.title,
+            section.start_index,
+            section.end_index
+        );
+    }
+    if !never.is_empty() {
+        println!();
+        println!(
+            "  Never retrieved ({} of {} sections):",
+            never.len(),
+            tree.node_count()
+        );
+        for document in &never {
+            println!(
+                "    {} (pages {}-{})",
+                document.metadata.title, document.metadata.start_index, document.metadata.end_index
+            );
+        }
+    }
+    Ok(())
+}
+
+fn cmd_edit(
+    edit: TreeEdit,
+    index_path: PathBuf,
+    output: Option<PathBuf>,
+    force: bool,
+    json: bool,
+) -> Result<()> {
+    if !tree_exists(&index_path) {
+        anyhow::bail!("Index not found at '{}'", index_path.display());
+    }
+
+    let mut tree = load_tree(&index_path).context("Failed to load tree index")?;
+    let error_count = |tree: &_| {
+        validate_tree(tree)
+            .iter()
+            .filter(|i| i.severity == Severity::Error)
+            .count()
+    };
+    let errors_before = error_count(&tree);
+
+    apply_edit(&mut tree, &edit).context("Edit failed")?;
+
+    // Refuse to save edits that break the tree unless forced
+    let errors_after = error_count(&tree);
+    if errors_after > errors_before && !force {
+        for issue in validate_tree(&tree) {
+            if issue.severity == Severity::Error {
+                eprintln!("error: {}: {}", issue.title, issue.message);
+            }
+        }
+        anyhow::bail!(
+            "Edit introduces {} validation error(s); not saved (use --force to save anyway)",
+            errors_after - errors_before
+        );
+    }
+
+    let output = output.unwrap_or(index_path);
+    save_tree(&tree, &output).context("Failed to save tree index")?;
+
+    if json {
+        return print_json(&serde_json::json!({
+            "output": output,
+            "sections": tree.node_count(),
+            "validation_errors": errors_after,
+        }));
+    }
+
+    println!("Saved edited tree to {}", output.display());
+    Ok(())
+}
+
+async fn cmd_validate(
+    index_path: PathBuf,
+    document_path: Option<PathBuf>,
+    spot_checks: usize,
+    strict: bool,
+    json: bool,
+) -> Result<()> {
+    if !tree_exists(&index_path) {
+        anyhow::bail!("Index not found at '{}'", index_path.display());
+    }
+
+    let tree = load_tree(&index_path).context("Failed to load tree index")?;
+
+    let report = match &document_path {
+        Some(path) => {
+            let config = Config::load().context("Failed to load configuration")?;
+            config.validate().context("Invalid configuration")?;
+            let document = loader::load(path).context("Failed to load document")?;
+            let indexer = TreeIndexer::new(LlmClient::new(config.llm));
+            validate_index(&tree, Some((&document, &indexer)), spot_checks).await
+        }
+        None => validate_index(&tree, None, 0).await,
+    }
+    .context("Validation failed")?;
+
+    let failed = report.has_errors() || (strict && report.warning_count() > 0);
+
+    if json {
+        print_json(&serde_json::json!({
+            "index": index_path,
+            "ok": !failed,
+            "errors": report.error_count(),
+            "warnings": report.warning_count(),
+            "spot_checks": report.spot_checks,
+            "issues": report.issues,
+        }))?;
+    } else {
+        for issue in &report.issues {
+            let level = match issue.severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+            };
+            match &issue.node_id {
+                Some(id) => println!("{}: [{}] {}: {}", level, id, issue.title, issue.message),
+                None if !issue.title.is_empty() => {
+                    println!("{}: {}: {}", level, issue.title, issue.message)
+                }
+                None => println!("{}: {}", level, issue.message),
+            }
+        }
+        println!(
+            "{}: {} errors, {} warnings, {} titles spot-checked",
+            index_path.display(),
+            report.error_count(),
+            report.warning_count(),
+            report.spot_checks
+        );
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "serve")]
+#[allow(clippy::too_many_arguments)]
+async fn cmd_serve(
+    index_path: PathBuf,
+    document_path: Option<PathBuf>,
+    tenants_path: Option<PathBuf>,
+    addr: std::net::SocketAddr,
+    top_k: usize,
+    cache_size: usize,
+    answer_ttl: Duration,
+    stats: bool,
+    style: rust_page_indexer::AnswerStyle,
+    audit_log: Option<&Path>,
+) -> Result<()> {
+    use rust_page_indexer::cache::AnswerCache;
+    use rust_page_indexer::query::normalizer_path_for;
+    use rust_page_indexer::serve::{ServeState, serve};
+    use rust_page_indexer::stats::{StatsFile, stats_path_for};
+
+    let config = Config::load().context("Failed to load configuration")?;
+    config.validate().context("Invalid configuration")?;
+
+    let state = match &tenants_path {
+        Some(path) => {
+            let state = ServeState::from_tenants_file(path, &config.llm)
+                .context("Failed to load tenants")?;
+            println!("Serving {} on http://{}", path.display(), addr);
+            for (tenant, indexes) in state.tenants() {
+                println!("  tenant {} ({} indexes)", tenant, indexes);
+            }
+            if let Some(cache) = state.tree_cache_stats() {
+                println!(
+                    "  tree cache: {:.1} of {:.1} MB ({} trees in memory)",
+                    cache.bytes as f64 / (1024.0 * 1024.0),
+                    cache.budget as f64 / (1024.0 * 1024.0),
+                    cache.trees
+                );
+            }
+            state
+        }
+        None => {
+            if !tree_exists(&index_path) {
+                anyhow::bail!(
+                    "Index not found at '{}'. Run 'index' command first.",
+                    index_path.display()
+                );
+            }
+            let tree = load_tree(&index_path).context("Failed to load tree index")?;
+            let mut state = ServeState::new(tree, LlmClient::new(config.llm.clone()));
+            if let Some(path) = document_path {
+                let document = loader::load(&path).context("Failed to load document")?;
+                state = state.with_document(document);
+            }
+            println!("Serving {} on http://{}", index_path.display(), addr);
+            if stats {
+                let path = stats_path_for(&index_path);
+                let file = StatsFile::open(&path).context("Failed to load retrieval stats")?;
+                println!("  retrieval stats: {}", path.display());
+                state = state.with_stats(file);
+            }
+            if let Some(normalizer) =
+                QueryNormalizer::for_index(&index_path).context("Failed to load query settings")?
+            {
+                println!(
+                    "  query settings: {}",
+                    normalizer_path_for(&index_path).display()
+                );
+                state = state.with_query_normalizer(normalizer);
+            }
+            state
+        }
+    };
+    let answers = AnswerCache::new(cache_size).with_ttl(answer_ttl);
+    let state = state
+        .with_top_k(top_k)
+        .with_cache_size(cache_size)
+        .with_answer_cache(answers)
+        .with_answer_style(style);
+    let state = match open_audit_log(audit_log)? {
+        Some(log) => {
+            println!("  audit log: {}", log.path().display());
+            state.with_audit_log(log)
+        }
+        None => state,
+    };
+
+    println!("  POST /retrieve  (LangChain RemoteLangChainRetriever)");
+    println!("  POST /invoke    (LangServe RemoteRunnable)");
+    println!("  POST /indexes/{{index}}/retrieve, /indexes/{{index}}/invoke");
+    println!("  POST /ask, /indexes/{{index}}/ask");
+    println!("  DELETE /answers (drop cached answers)");
+    println!("  GET  /indexes");
+    println!("  GET  /health");
+    println!("  GET  /metrics   (Prometheus)");
+    serve(addr, state).await.context("Server failed")
+}
+
+#[cfg(feature = "grpc")]
+async fn cmd_grpc(
+    indexes: Vec<PathBuf>,
+    index_dir: Option<PathBuf>,
+    addr: std::net::SocketAddr,
+) -> Result<()> {
+    use rust_page_indexer::grpc::{GrpcService, serve};
+
+    let config = Config::load().context("Failed to load configuration")?;
+    config.validate().context("Invalid configuration")?;
+
+    let mut service = GrpcService::new(LlmClient::new(config.llm));
+    if let Some(dir) = index_dir {
+        service = service.with_index_dir(dir);
+    }
+    let mut names = service
+        .load_index_dir()
+        .context("Failed to load index directory")?;
+    for path in &indexes {
+        let name = service
+            .load(path)
+            .with_context(|| format!("Failed to load index '{}'", path.display()))?;
+        names.push(name);
+    }
+
+    println!("Serving gRPC on {}", addr);
+    if names.is_empty() {
+        println!("  No indexes loaded yet; build one with the Index RPC");
+    } else {
+        println!("  Indexes: {}", names.join(", "));
+    }
+    serve(addr, service).await.context("Server failed")
+}
+
+async fn cmd_watch(dir: PathBuf, index_dir: PathBuf) -> Result<()> {
+    let config = Config::load().context("Failed to load configuration")?;
+    config.validate().context("Invalid configuration")?;
+
+    let options = IndexerOptions::from_config(&config);
+    let indexer = TreeIndexer::with_options(LlmClient::new(config.llm), options);
+    let watcher = DirectoryWatcher::new(&dir, &index_dir, indexer);
+
+    println!(
+        "Watching {} (indexes in {}), press Ctrl+C to stop",
+        dir.display(),
+        index_dir.display()
+    );
+    watcher.watch().await.context("Watch failed")?;
+
+    Ok(())
+}
+
+async fn cmd_jobs(action: JobsAction, jobs_dir: Option<PathBuf>, json: bool) -> Result<()> {
+    let Some(dir) = jobs_dir.or_else(JobQueue::default_dir) else {
+        anyhow::bail!("No data directory found; pass --jobs-dir");
+    };
+    // Only submitting and running jobs call the LLM
+    let store = || JobQueue::new(LlmClient::new(LlmConfig::default())).with_dir(&dir);
+
+    match action {
+        JobsAction::Submit { document, output } => {
+            let config = Config::load().context("Failed to load configuration")?;
+            config.validate().context("Invalid configuration")?;
+            let document = loader::load(&document).context("Failed to load document")?;
+            let output = output
+                .map(std::path::absolute)
+                .transpose()
+                .context("Invalid output path")?;
+
+            let job = store()
+                .enqueue(document, output)
+                .context("Failed to queue job")?;
+
+            // Index in a detached process so the terminal is free
+            let mut command = std::process::Command::new(
+                std::env::current_exe().context("Failed to find the page-indexer binary")?,
+            );
+            command
+                .arg("jobs")
+                .arg("--jobs-dir")
+                .arg(&dir)
+                .arg("run")
+                .arg(&job.id)
+                .stdin(std::process::Stdio::null())
+                .stdout(std::process::Stdio::null())
+                .stderr(std::process::Stdio::null());
+            #[cfg(unix)]
+            std::os::unix::process::CommandExt::process_group(&mut command, 0);
+            command.spawn().context("Failed to start indexing job")?;
+
+            if json {
+                return print_json(&serde_json::to_value(&job)?);
+            }
+            println!("{}", job.id);
+            Ok(())
+        }
+        JobsAction::Status { id: Some(id) } => {
+            let Some(job) = store().get(&id)? else {
+                anyhow::bail!("No job with ID '{}'", id);
+            };
+            if json {
+                return print_json(&serde_json::to_value(&job)?);
+            }
+            print_job(&job);
+            Ok(())
+        }
+        JobsAction::Status { id: None } => {
+            let jobs = store().list().context("Failed to list jobs")?;
+            if json {
+                return print_json(&serde_json::json!({ "jobs": jobs }));
+            }
+            if jobs.is_empty() {
+                println!("No jobs in {}", dir.display());
+            }
+            jobs.iter().for_each(print_job);
+            Ok(())
+        }
+        JobsAction::Result { id, output } => {
+            let store = store();
+            let Some(job) = store.get(&id)? else {
+                anyhow::bail!("No job with ID '{}'", id);
+            };
+            let tree = match store.tree(&id)? {
+                Some(tree) => tree,
+                None if job.state == JobState::Failed => {
+                    anyhow::bail!("Job {} failed: {}", id, job.error.unwrap_or_default())
+                }
+                None => anyhow::bail!("Job {} has not completed", id),
+            };
+            let Some(output) = output else {
+                return print_json(&serde_json::to_value(&tree)?);
+            };
+            save_tree(&tree, &output).context("Failed to save tree index")?;
+            if json {
+                return print_json(&serde_json::json!({
+                    "output": output,
+                    "sections": tree.node_count(),
+                }));
+            }
+            println!("Saved tree to {}", output.display());
+            Ok(())
+        }
+        JobsAction::Run { id } => {
+            let config = Config::load().context("Failed to load configuration")?;
+            let options = IndexerOptions::from_config(&config);
+            let queue = JobQueue::new(LlmClient::new(config.llm))
+                .with_options(options)
+                .with_dir(&dir);
+            let job = queue.run(&id).await.context("Failed to run job")?;
+            if let Some(error) = job.error {
+                anyhow::bail!("Job {} failed: {}", id, error);
+            }
+            Ok(())
+        }
+    }
+}
+
+/// One line per job: ID, state, document and progress or outcome.
+fn print_job(job: &Job) {
+    let p = &job.progress;
+    let detail = match job.state {
+        JobState::Queued => String::new(),
+        JobState::Running => format!(
+            "chunks {}/{}, summaries {}/{}",
+            p.chunks_completed, p.chunks_total, p.summaries_completed, p.summaries_total
+        ),
+        JobState::Completed => format!("{} sections", job.sections.unwrap_or_default()),
+        JobState::Failed => job.error.clone().unwrap_or_default(),
+    };
+    let state = format!("{:?}", job.state).to_lowercase();
+    println!("{}  {:<9}  {}  {}", job.id, state, job.document, detail);
 }
 
-async fn cmd_test() -> Result<()> {
+async fn cmd_test(json: bool) -> Result<()> {
+    if json {
+        let config = Config::load().context("Failed to load configuration")?;
+        let (error, latency_ms) = match config.validate() {
+            Ok(()) => {
+                let client = LlmClient::new(config.llm.clone());
+                let start = Instant::now();
+                let result = client.test_connection().await;
+                (
+                    result.err().map(|e| e.to_string()),
+                    Some(start.elapsed().as_millis() as u64),
+                )
+            }
+            Err(e) => (Some(e.to_string()), None),
+        };
+        return print_json(&serde_json::json!({
+            "api_base": config.llm.api_base,
+            "model": config.llm.model,
+            "ok": error.is_none(),
+            "error": error,
+            "latency_ms": latency_ms,
+        }));
+    }
+
     println!("Testing LLM connection...\n");
 
     let config = Config::load().context("Failed to load configuration")?;
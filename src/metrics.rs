@@ -0,0 +1,279 @@
+//! Prometheus metrics for serve mode (`serve` feature).
+//!
+//! [`Metrics`] collects what the HTTP server does and renders it in the
+//! Prometheus text format for `GET /metrics`:
+//!
+//! - `page_indexer_http_requests_total` (`route`, `status`)
+//! - `page_indexer_http_request_duration_seconds` histogram (`route`)
+//! - `page_indexer_searches_total` (`tenant`, `index`): searches that
+//!   reached the LLM
+//! - `page_indexer_search_cache_hits_total` and
+//!   `page_indexer_search_cache_misses_total` (`tenant`, `index`); the hit
+//!   rate is `rate(hits) / (rate(hits) + rate(misses))`
+//! - `page_indexer_llm_calls_total` (`tenant`) and
+//!   `page_indexer_llm_tokens_total` (`tenant`, `type` =
+//!   `prompt`/`completion`), from each tenant's [`UsageStats`]
+
+use crate::llm::UsageStats;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Upper bounds (seconds) of the latency histogram buckets. Requests that
+/// search wait on the LLM, so the buckets run up to a minute.
+const LATENCY_BUCKETS: [f64; 11] = [0.005, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0];
+
+#[derive(Default)]
+struct Histogram {
+    /// Observations per bucket (not cumulative).
+    buckets: [u64; LATENCY_BUCKETS.len()],
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        if let Some(i) = LATENCY_BUCKETS.iter().position(|&le| seconds <= le) {
+            self.buckets[i] += 1;
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+}
+
+#[derive(Default)]
+struct Counts {
+    requests: BTreeMap<(String, u16), u64>,
+    latency: BTreeMap<String, Histogram>,
+    searches: BTreeMap<(String, String), u64>,
+    cache_hits: BTreeMap<(String, String), u64>,
+    cache_misses: BTreeMap<(String, String), u64>,
+}
+
+/// Request, search and cache counters for a server.
+#[derive(Default)]
+pub struct Metrics {
+    counts: Mutex<Counts>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count a finished request to `route` and its latency.
+    pub fn record_request(&self, route: &str, status: u16, elapsed: Duration) {
+        let mut counts = self.lock();
+        *counts
+            .requests
+            .entry((route.to_string(), status))
+            .or_default() += 1;
+        counts
+            .latency
+            .entry(route.to_string())
+            .or_default()
+            .observe(elapsed.as_secs_f64());
+    }
+
+    /// Count a search of a tenant's index that was not answered from the
+    /// cache.
+    pub fn record_search(&self, tenant: &str, index: &str) {
+        *self
+            .lock()
+            .searches
+            .entry((tenant.to_string(), index.to_string()))
+            .or_default() += 1;
+    }
+
+    /// Count a result-cache lookup for a tenant's index.
+    pub fn record_cache(&self, tenant: &str, index: &str, hit: bool) {
+        let mut counts = self.lock();
+        let map = if hit {
+            &mut counts.cache_hits
+        } else {
+            &mut counts.cache_misses
+        };
+        *map.entry((tenant.to_string(), index.to_string()))
+            .or_default() += 1;
+    }
+
+    /// The metrics in Prometheus text format, with each tenant's LLM usage
+    /// appended.
+    pub fn render(&self, usage: &[(&str, UsageStats)]) -> String {
+        let counts = self.lock();
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "page_indexer_http_requests_total",
+            "counter",
+            "HTTP requests by route and status",
+        );
+        for ((route, status), n) in &counts.requests {
+            let _ = writeln!(
+                out,
+                "page_indexer_http_requests_total{{route=\"{}\",status=\"{}\"}} {}",
+                escape(route),
+                status,
+                n
+            );
+        }
+
+        let name = "page_indexer_http_request_duration_seconds";
+        header(&mut out, name, "histogram", "HTTP request latency by route");
+        for (route, histogram) in &counts.latency {
+            let route = escape(route);
+            let mut cumulative = 0;
+            for (le, n) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+                cumulative += n;
+                let _ = writeln!(
+                    out,
+                    "{}_bucket{{route=\"{}\",le=\"{}\"}} {}",
+                    name, route, le, cumulative
+                );
+            }
+            let _ = writeln!(
+                out,
+                "{}_bucket{{route=\"{}\",le=\"+Inf\"}} {}",
+                name, route, histogram.count
+            );
+            let _ = writeln!(out, "{}_sum{{route=\"{}\"}} {}", name, route, histogram.sum);
+            let _ = writeln!(
+                out,
+                "{}_count{{route=\"{}\"}} {}",
+                name, route, histogram.count
+            );
+        }
+
+        for (name, help, map) in [
+            (
+                "page_indexer_searches_total",
+                "Tree searches run against the LLM by tenant and index",
+                &counts.searches,
+            ),
+            (
+                "page_indexer_search_cache_hits_total",
+                "Queries answered from the result cache by tenant and index",
+                &counts.cache_hits,
+            ),
+            (
+                "page_indexer_search_cache_misses_total",
+                "Queries not found in the result cache by tenant and index",
+                &counts.cache_misses,
+            ),
+        ] {
+            header(&mut out, name, "counter", help);
+            for ((tenant, index), n) in map {
+                let _ = writeln!(
+                    out,
+                    "{}{{tenant=\"{}\",index=\"{}\"}} {}",
+                    name,
+                    escape(tenant),
+                    escape(index),
+                    n
+                );
+            }
+        }
+
+        header(
+            &mut out,
+            "page_indexer_llm_calls_total",
+            "counter",
+            "Successful LLM API calls",
+        );
+        for (tenant, usage) in usage {
+            let _ = writeln!(
+                out,
+                "page_indexer_llm_calls_total{{tenant=\"{}\"}} {}",
+                escape(tenant),
+                usage.calls
+            );
+        }
+        header(
+            &mut out,
+            "page_indexer_llm_tokens_total",
+            "counter",
+            "Tokens used by LLM API calls",
+        );
+        for (tenant, usage) in usage {
+            for (kind, n) in [
+                ("prompt", usage.prompt_tokens),
+                ("completion", usage.completion_tokens),
+            ] {
+                let _ = writeln!(
+                    out,
+                    "page_indexer_llm_tokens_total{{tenant=\"{}\",type=\"{}\"}} {}",
+                    escape(tenant),
+                    kind,
+                    n
+                );
+            }
+        }
+        out
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Counts> {
+        self.counts.lock().expect("metrics lock poisoned")
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// Escape a label value.
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let metrics = Metrics::new();
+        metrics.record_request("/retrieve", 200, Duration::from_millis(300));
+        metrics.record_request("/retrieve", 200, Duration::from_secs(2));
+        metrics.record_request("/retrieve", 502, Duration::from_secs(120));
+        metrics.record_search("research", "report");
+        metrics.record_cache("research", "report", false);
+        metrics.record_cache("research", "report", true);
+        metrics.record_search("legal", "say \"hi\"");
+
+        let usage = UsageStats {
+            calls: 3,
+            prompt_tokens: 100,
+            completion_tokens: 20,
+            total_tokens: 120,
+        };
+        let text = metrics.render(&[("research", usage), ("legal", UsageStats::default())]);
+        for line in [
+            "page_indexer_http_requests_total{route=\"/retrieve\",status=\"200\"} 2",
+            "page_indexer_http_requests_total{route=\"/retrieve\",status=\"502\"} 1",
+            "page_indexer_http_request_duration_seconds_bucket{route=\"/retrieve\",le=\"0.5\"} 1",
+            "page_indexer_http_request_duration_seconds_bucket{route=\"/retrieve\",le=\"60\"} 2",
+            "page_indexer_http_request_duration_seconds_bucket{route=\"/retrieve\",le=\"+Inf\"} 3",
+            "page_indexer_http_request_duration_seconds_count{route=\"/retrieve\"} 3",
+            "page_indexer_searches_total{tenant=\"research\",index=\"report\"} 1",
+            "page_indexer_searches_total{tenant=\"legal\",index=\"say \\\"hi\\\"\"} 1",
+            "page_indexer_search_cache_hits_total{tenant=\"research\",index=\"report\"} 1",
+            "page_indexer_search_cache_misses_total{tenant=\"research\",index=\"report\"} 1",
+            "page_indexer_llm_calls_total{tenant=\"research\"} 3",
+            "page_indexer_llm_calls_total{tenant=\"legal\"} 0",
+            "page_indexer_llm_tokens_total{tenant=\"research\",type=\"completion\"} 20",
+        ] {
+            assert!(
+                text.lines().any(|l| l == line),
+                "missing {}\n{}",
+                line,
+                text
+            );
+        }
+    }
+}
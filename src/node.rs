@@ -0,0 +1,120 @@
+//! Node.js bindings (`node` feature).
+//!
+//! Exposes [`PageIndex`] to JavaScript as a `PageIndex` class via napi-rs.
+//! Every method returns a promise and runs on napi's tokio runtime, so
+//! indexing and searching never block the event loop. Results are plain
+//! objects in the same JSON shapes as the CLI's `--json` output; the
+//! TypeScript declarations are in `node/index.d.ts`.
+
+use crate::config::{Config, LlmConfig};
+use crate::error::PageIndexError;
+use crate::page_index::PageIndex;
+use crate::search::SearchOptions;
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+use serde_json::Value;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+
+fn js_error(err: PageIndexError) -> Error {
+    Error::from_reason(err.to_string())
+}
+
+fn to_js(value: impl serde::Serialize) -> Result<Value> {
+    serde_json::to_value(value).map_err(|e| Error::from_reason(e.to_string()))
+}
+
+fn search_options(top_k: Option<u32>) -> SearchOptions {
+    let mut options = SearchOptions::default();
+    if let Some(top_k) = top_k.filter(|&k| k > 0) {
+        options.top_k = top_k as usize;
+    }
+    options
+}
+
+/// `PageIndex` in JavaScript.
+#[napi(js_name = "PageIndex")]
+pub struct JsPageIndex {
+    inner: Arc<RwLock<PageIndex>>,
+}
+
+#[napi]
+impl JsPageIndex {
+    /// `llmConfig` is `{ api_base, api_key, model, max_tokens?, temperature? }`;
+    /// when omitted, configuration is read from the environment and config
+    /// file as for the CLI.
+    #[napi(constructor)]
+    pub fn new(llm_config: Option<Value>) -> Result<Self> {
+        let config = match llm_config {
+            Some(value) => Config {
+                llm: serde_json::from_value::<LlmConfig>(value)
+                    .map_err(|e| js_error(PageIndexError::InvalidConfig(e.to_string())))?,
+                ..Default::default()
+            },
+            None => Config::load().map_err(js_error)?,
+        };
+        let index = PageIndex::builder()
+            .with_config(config)
+            .build()
+            .map_err(js_error)?;
+        Ok(Self {
+            inner: Arc::new(RwLock::new(index)),
+        })
+    }
+
+    /// Index a text file, resolving to the tree.
+    #[napi]
+    pub async fn index_file(&self, path: String) -> Result<Value> {
+        let mut index = self.inner.write().await;
+        to_js(index.index_file(&path).await.map_err(js_error)?)
+    }
+
+    /// Load a saved tree (`.json` or `.bin`), resolving to the tree.
+    #[napi]
+    pub async fn load(&self, path: String) -> Result<Value> {
+        let mut index = self.inner.write().await;
+        to_js(index.load(&path).map_err(js_error)?)
+    }
+
+    /// Attach the text file the loaded tree was built from, so results
+    /// include section text.
+    #[napi]
+    pub async fn load_document(&self, path: String) -> Result<()> {
+        let mut index = self.inner.write().await;
+        index.load_document(&path).map_err(js_error)
+    }
+
+    /// Save the loaded tree; the format follows the extension.
+    #[napi]
+    pub async fn save(&self, path: String) -> Result<()> {
+        self.inner.read().await.save(&path).map_err(js_error)
+    }
+
+    /// Search the loaded tree, resolving to an array of results.
+    #[napi]
+    pub async fn search(&self, query: String, top_k: Option<u32>) -> Result<Value> {
+        let index = self.inner.read().await;
+        let results = index
+            .search_with(&query, &search_options(top_k))
+            .await
+            .map_err(js_error)?;
+        to_js(results)
+    }
+
+    /// Answer a question, resolving to `{ answer, sources }`.
+    #[napi]
+    pub async fn ask(&self, question: String, top_k: Option<u32>) -> Result<Value> {
+        let index = self.inner.read().await;
+        let sources = index
+            .search_with(&question, &search_options(top_k))
+            .await
+            .map_err(js_error)?;
+        to_js(index.answer(&question, sources).await.map_err(js_error)?)
+    }
+
+    /// LLM calls and tokens used so far.
+    #[napi]
+    pub async fn usage(&self) -> Result<Value> {
+        to_js(self.inner.read().await.client().usage())
+    }
+}
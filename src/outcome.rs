@@ -0,0 +1,227 @@
+//! Per-item results for operations over many documents or queries.
+//!
+//! Corpus indexing and batch search keep going when one item fails. Each
+//! [`Failure`] keeps the error's [kind](PageIndexError::kind), and a
+//! [`FailureSummary`] totals them by kind, so a caller (or the CLI's exit
+//! code, see [`ExitPolicy`]) can decide whether a partly failed run counts
+//! as a failure.
+
+use crate::error::{PageIndexError, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// Exit code when some, but not all, items failed under [`ExitPolicy::Any`].
+pub const EXIT_PARTIAL_FAILURE: i32 = 2;
+
+/// An item that failed, with the kind of error it failed with.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Failure {
+    /// Document path or query id.
+    pub item: String,
+    /// [`PageIndexError::kind`] of the error.
+    pub kind: String,
+    pub message: String,
+    /// Whether retrying the item later may succeed.
+    pub transient: bool,
+}
+
+impl Failure {
+    /// Record `error` as the failure of `item`.
+    pub fn new(item: impl Into<String>, error: &PageIndexError) -> Self {
+        Self {
+            item: item.into(),
+            kind: error.kind().to_string(),
+            message: error.to_string(),
+            transient: error.is_transient(),
+        }
+    }
+}
+
+/// Successes and failures of an operation over many items.
+#[derive(Debug, Clone, Serialize)]
+pub struct PartialResult<T> {
+    pub succeeded: Vec<T>,
+    pub failed: Vec<Failure>,
+}
+
+impl<T> Default for PartialResult<T> {
+    fn default() -> Self {
+        Self {
+            succeeded: Vec::new(),
+            failed: Vec::new(),
+        }
+    }
+}
+
+impl<T> PartialResult<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the result of one item.
+    pub fn push(&mut self, item: impl Into<String>, result: Result<T>) {
+        match result {
+            Ok(value) => self.succeeded.push(value),
+            Err(e) => self.failed.push(Failure::new(item, &e)),
+        }
+    }
+
+    /// Whether every item succeeded.
+    pub fn is_complete(&self) -> bool {
+        self.failed.is_empty()
+    }
+
+    /// Number of items recorded.
+    pub fn total(&self) -> usize {
+        self.succeeded.len() + self.failed.len()
+    }
+
+    /// Failure counts by kind.
+    pub fn summary(&self) -> FailureSummary {
+        FailureSummary::from_kinds(self.total(), self.failed.iter().map(|f| f.kind.as_str()))
+    }
+}
+
+/// How many of a run's items failed, by error kind.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct FailureSummary {
+    pub total: usize,
+    pub failed: usize,
+    /// Failures per [`PageIndexError::kind`].
+    pub by_kind: BTreeMap<String, usize>,
+}
+
+impl FailureSummary {
+    /// Summarize `total` items given the error kind of each failed one.
+    pub fn from_kinds<'a>(total: usize, kinds: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut summary = Self {
+            total,
+            ..Default::default()
+        };
+        for kind in kinds {
+            summary.failed += 1;
+            *summary.by_kind.entry(kind.to_string()).or_default() += 1;
+        }
+        summary
+    }
+
+    /// Number of items that succeeded.
+    pub fn succeeded(&self) -> usize {
+        self.total.saturating_sub(self.failed)
+    }
+
+    /// Whether there were items and none of them succeeded.
+    pub fn all_failed(&self) -> bool {
+        self.total > 0 && self.failed >= self.total
+    }
+}
+
+impl fmt::Display for FailureSummary {
+    /// `2 of 5 failed (rate_limited: 1, timeout: 1)`, or `all 5 succeeded`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.failed == 0 {
+            return write!(f, "all {} succeeded", self.total);
+        }
+        let kinds = self
+            .by_kind
+            .iter()
+            .map(|(kind, count)| format!("{}: {}", kind, count))
+            .collect::<Vec<_>>()
+            .join(", ");
+        write!(f, "{} of {} failed ({})", self.failed, self.total, kinds)
+    }
+}
+
+/// When a run with failed items exits non-zero.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExitPolicy {
+    /// Exit 1 if every item failed, [`EXIT_PARTIAL_FAILURE`] if some did.
+    #[default]
+    Any,
+    /// Exit 1 only if every item failed.
+    All,
+    /// Always exit 0.
+    Never,
+}
+
+impl ExitPolicy {
+    /// Process exit code for a run with this summary.
+    pub fn exit_code(self, summary: &FailureSummary) -> i32 {
+        if summary.failed == 0 {
+            return 0;
+        }
+        match self {
+            ExitPolicy::Never => 0,
+            _ if summary.all_failed() => 1,
+            ExitPolicy::Any => EXIT_PARTIAL_FAILURE,
+            ExitPolicy::All => 0,
+        }
+    }
+}
+
+impl FromStr for ExitPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "any" => Ok(ExitPolicy::Any),
+            "all" => Ok(ExitPolicy::All),
+            "never" => Ok(ExitPolicy::Never),
+            other => Err(format!(
+                "unknown exit policy '{}' (expected any, all or never)",
+                other
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partial_result() {
+        let mut result = PartialResult::new();
+        result.push("a.txt", Ok(1));
+        result.push(
+            "b.txt",
+            Err(PageIndexError::Timeout("no response".to_string())),
+        );
+        result.push("c.txt", Err(PageIndexError::Http("refused".to_string())));
+        result.push("d.txt", Err(PageIndexError::Http("refused".to_string())));
+
+        assert!(!result.is_complete());
+        assert_eq!(result.succeeded, vec![1]);
+        assert_eq!(result.failed[0].item, "b.txt");
+        assert!(result.failed[0].transient);
+
+        let summary = result.summary();
+        assert_eq!(
+            (summary.total, summary.failed, summary.succeeded()),
+            (4, 3, 1)
+        );
+        assert_eq!(summary.to_string(), "3 of 4 failed (http: 2, timeout: 1)");
+        assert_eq!(
+            FailureSummary::from_kinds(2, []).to_string(),
+            "all 2 succeeded"
+        );
+    }
+
+    #[test]
+    fn test_exit_policy() {
+        let none = FailureSummary::from_kinds(3, []);
+        let some = FailureSummary::from_kinds(3, ["http"]);
+        let all = FailureSummary::from_kinds(1, ["http"]);
+
+        let codes =
+            |policy: ExitPolicy| [&none, &some, &all].map(|summary| policy.exit_code(summary));
+        assert_eq!(codes(ExitPolicy::Any), [0, EXIT_PARTIAL_FAILURE, 1]);
+        assert_eq!(codes(ExitPolicy::All), [0, 0, 1]);
+        assert_eq!(codes(ExitPolicy::Never), [0, 0, 0]);
+
+        assert_eq!("ALL".parse::<ExitPolicy>(), Ok(ExitPolicy::All));
+        assert!("some".parse::<ExitPolicy>().is_err());
+    }
+}
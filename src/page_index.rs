@@ -0,0 +1,882 @@
+//! High-level API: index, search and answer questions over one document.
+//!
+//! [`PageIndex`] wires [`Document`], [`TreeIndexer`], [`TreeSearcher`] and
+//! persistence together, so the common path needs a single type:
+//!
+//! ```no_run
+//! use rust_page_indexer::PageIndex;
+//!
+//! # async fn run() -> rust_page_indexer::Result<()> {
+//! let mut index = PageIndex::builder().build()?;
+//! index.index_file("document.txt").await?;
+//! index.save("tree_index.json")?;
+//!
+//! let answer = index.ask("What were the main findings?").await?;
+//! println!("{}", answer.answer);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The lower-level types remain available for anything the facade does not
+//! cover (checkpoints, batch runs, custom prompts).
+
+#[cfg(not(target_arch = "wasm32"))]
+use crate::cache::{AnswerCache, AnswerKey};
+use crate::config::{Config, OperationModels};
+use crate::document::{Document, SECTION_SEPARATOR, section_header};
+use crate::error::{PageIndexError, Result};
+use crate::export::to_context_markdown;
+use crate::extract::{Extraction, Extractor};
+use crate::indexer::{IndexerOptions, TreeIndexer};
+use crate::llm::{LlmClient, Prompts};
+use crate::persistence::{load_tree, save_tree};
+use crate::progress::ProgressCallback;
+use crate::query::{terms_in, with_definitions};
+use crate::search::{Relevance, SearchOptions, SearchResult, TreeSearcher};
+use crate::store::DocumentStore;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::stream::{AskEvent, EventStream, IndexEvent, SearchEvent, forward_progress};
+use crate::tree::{DocumentTree, Freshness};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Answer text of a [`AnswerOutcome::NotFound`] answer.
+pub const NOT_FOUND_ANSWER: &str = "Not found in the document.";
+
+/// Whether a question was answered from the document.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AnswerOutcome {
+    #[default]
+    Answered,
+    /// No retrieved section was relevant enough (see
+    /// [`AnswerStyle::required_relevance`]), so the model was not asked.
+    NotFound,
+}
+
+/// An answer with the sections it was generated from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Answer {
+    pub answer: String,
+    /// Retrieved sections, best first.
+    pub sources: Vec<SearchResult>,
+    #[serde(default)]
+    pub outcome: AnswerOutcome,
+}
+
+impl Answer {
+    /// The answer to a question no section supports.
+    pub fn not_found() -> Self {
+        Self {
+            answer: NOT_FOUND_ANSWER.to_string(),
+            sources: Vec::new(),
+            outcome: AnswerOutcome::NotFound,
+        }
+    }
+
+    /// Whether the answer was refused for lack of support.
+    pub fn is_not_found(&self) -> bool {
+        self.outcome == AnswerOutcome::NotFound
+    }
+}
+
+/// How long answers should be.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum AnswerLength {
+    /// A sentence or two.
+    Short,
+    /// Concise and direct.
+    #[default]
+    Medium,
+    /// Every relevant point the sources make.
+    Long,
+}
+
+/// How answers are laid out.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum AnswerFormat {
+    #[default]
+    Prose,
+    /// A bulleted list, one point per line.
+    Bullets,
+}
+
+/// Length, layout and language of generated answers, given to the answer
+/// prompt, and whether to refuse questions the document doesn't support.
+/// The default is a concise prose answer in the language the model picks,
+/// from whatever was retrieved.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct AnswerStyle {
+    #[serde(default)]
+    pub length: AnswerLength,
+    #[serde(default)]
+    pub format: AnswerFormat,
+    /// Language to answer in (e.g. "German"), whatever the language of
+    /// the question and document.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Answer only from sections at least this relevant, and with
+    /// [`AnswerOutcome::NotFound`] when there are none, instead of letting
+    /// the model answer from what it already knows.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub required_relevance: Option<Relevance>,
+}
+
+impl AnswerStyle {
+    /// The answer prompt's instructions for this style, one per line.
+    pub fn instructions(&self) -> String {
+        let mut lines = vec![
+            match self.length {
+                AnswerLength::Short => "- Answer in one or two sentences.",
+                AnswerLength::Medium => "- Be concise and direct in your answer.",
+                AnswerLength::Long => {
+                    "- Answer in detail, covering every relevant point the context makes."
+                }
+            }
+            .to_string(),
+        ];
+        if self.format == AnswerFormat::Bullets {
+            lines.push(
+                "- Format the answer as a bulleted list, one point per line starting with \"- \"."
+                    .to_string(),
+            );
+        }
+        if let Some(language) = self.language.as_deref().filter(|l| !l.trim().is_empty()) {
+            lines.push(format!(
+                "- Write the answer in {}, whatever the language of the question and context.",
+                language.trim()
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Builder for [`PageIndex`].
+#[derive(Default)]
+pub struct PageIndexBuilder {
+    config: Option<Config>,
+    client: Option<LlmClient>,
+    indexer_options: IndexerOptions,
+    search_options: SearchOptions,
+    answer_style: AnswerStyle,
+    progress: Option<ProgressCallback>,
+    store: Option<Arc<dyn DocumentStore>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    answer_cache: Option<AnswerCache>,
+}
+
+impl PageIndexBuilder {
+    /// Use this configuration instead of [`Config::load`].
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Use an existing client (e.g. one shared with other components).
+    /// Takes precedence over [`with_config`](Self::with_config), apart
+    /// from the config's [per-operation models](Config::models).
+    pub fn with_client(mut self, client: LlmClient) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Set the options used when indexing.
+    pub fn with_indexer_options(mut self, options: IndexerOptions) -> Self {
+        self.indexer_options = options;
+        self
+    }
+
+    /// Set the options used when searching and answering.
+    pub fn with_search_options(mut self, options: SearchOptions) -> Self {
+        self.search_options = options;
+        self
+    }
+
+    /// Set the style of answers from [`PageIndex::ask`].
+    pub fn with_answer_style(mut self, style: AnswerStyle) -> Self {
+        self.answer_style = style;
+        self
+    }
+
+    /// Report indexing and search progress to a callback.
+    pub fn with_progress(mut self, callback: ProgressCallback) -> Self {
+        self.progress = Some(callback);
+        self
+    }
+
+    /// Fetch section text from a store when no source document is loaded
+    /// (e.g. after [`PageIndex::load`]).
+    pub fn with_store(mut self, store: impl DocumentStore + 'static) -> Self {
+        self.store = Some(Arc::new(store));
+        self
+    }
+
+    /// Reuse answers to questions already asked of the same tree version
+    /// (see [`crate::cache`]). Clones of the cache can be shared between
+    /// indexes.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn with_answer_cache(mut self, cache: AnswerCache) -> Self {
+        self.answer_cache = Some(cache);
+        self
+    }
+
+    /// Create the index. Without a client or config, configuration is
+    /// loaded from the environment and config file and validated.
+    pub fn build(self) -> Result<PageIndex> {
+        let (client, models) = match (self.client, self.config) {
+            (Some(client), config) => (client, config.map(|c| c.models).unwrap_or_default()),
+            (None, config) => {
+                let config = match config {
+                    Some(config) => config,
+                    None => Config::load()?,
+                };
+                config.validate()?;
+                (LlmClient::new(config.llm), config.models)
+            }
+        };
+        Ok(PageIndex {
+            client,
+            models,
+            indexer_options: self.indexer_options,
+            search_options: self.search_options,
+            answer_style: self.answer_style,
+            progress: self.progress,
+            tree: None,
+            document: None,
+            store: self.store,
+            #[cfg(not(target_arch = "wasm32"))]
+            answer_cache: self.answer_cache,
+        })
+    }
+}
+
+/// A document's tree index, ready to search and answer questions.
+///
+/// Holds at most one tree at a time, built with [`index_file`] or read with
+/// [`load`]. When the source document is known (always after `index_file`,
+/// or after [`load_document`]) or a [`DocumentStore`] is configured,
+/// results carry section text and answers are generated from it.
+///
+/// [`index_file`]: PageIndex::index_file
+/// [`load`]: PageIndex::load
+/// [`load_document`]: PageIndex::load_document
+pub struct PageIndex {
+    client: LlmClient,
+    models: OperationModels,
+    indexer_options: IndexerOptions,
+    search_options: SearchOptions,
+    answer_style: AnswerStyle,
+    progress: Option<ProgressCallback>,
+    tree: Option<DocumentTree>,
+    document: Option<Document>,
+    store: Option<Arc<dyn DocumentStore>>,
+    #[cfg(not(target_arch = "wasm32"))]
+    answer_cache: Option<AnswerCache>,
+}
+
+impl PageIndex {
+    /// Start building an index.
+    pub fn builder() -> PageIndexBuilder {
+        PageIndexBuilder::default()
+    }
+
+    /// The loaded tree, if any.
+    pub fn tree(&self) -> Option<&DocumentTree> {
+        self.tree.as_ref()
+    }
+
+    /// The loaded source document, if any.
+    pub fn document(&self) -> Option<&Document> {
+        self.document.as_ref()
+    }
+
+    /// The answer cache, if configured.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn answer_cache(&self) -> Option<&AnswerCache> {
+        self.answer_cache.as_ref()
+    }
+
+    /// The LLM client (e.g. for [`LlmClient::usage`]).
+    pub fn client(&self) -> &LlmClient {
+        &self.client
+    }
+
+    /// Read a document (through [`crate::loader`]) and build its tree,
+    /// replacing any loaded tree.
+    pub async fn index_file(&mut self, path: impl AsRef<Path>) -> Result<&DocumentTree> {
+        let document = crate::loader::load(path.as_ref())?;
+        self.index_document(document).await
+    }
+
+    /// Build the tree for a document, replacing any loaded tree.
+    pub async fn index_document(&mut self, document: Document) -> Result<&DocumentTree> {
+        let progress = self.progress.clone();
+        self.index_document_with(document, progress).await
+    }
+
+    async fn index_document_with(
+        &mut self,
+        document: Document,
+        progress: Option<ProgressCallback>,
+    ) -> Result<&DocumentTree> {
+        let mut indexer =
+            TreeIndexer::with_options(self.client.clone(), self.indexer_options.clone());
+        if let Some(progress) = progress {
+            indexer = indexer.with_progress(progress);
+        }
+        let tree = indexer.index(&document).await?;
+        self.document = Some(document);
+        Ok(self.tree.insert(tree))
+    }
+
+    /// Load a saved tree (`.json` or `.bin`), replacing any loaded tree and
+    /// forgetting the previous document.
+    pub fn load(&mut self, path: impl AsRef<Path>) -> Result<&DocumentTree> {
+        let tree = load_tree(path.as_ref())?;
+        self.document = None;
+        Ok(self.tree.insert(tree))
+    }
+
+    /// Attach the document the loaded tree was built from.
+    pub fn load_document(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        self.document = Some(crate::loader::load(path.as_ref())?);
+        Ok(())
+    }
+
+    /// Whether the loaded tree's source document changed since the tree was
+    /// built (see [`DocumentTree::freshness`]); `None` without a tree.
+    pub fn freshness(&self) -> Option<Freshness> {
+        self.tree.as_ref().map(DocumentTree::freshness)
+    }
+
+    /// Re-index the loaded tree's source document if it changed since the
+    /// tree was built, keeping the tree's metadata (other manual edits are
+    /// lost). Returns whether it re-indexed; [`save`](Self::save) the new
+    /// tree to keep it.
+    pub async fn refresh(&mut self) -> Result<bool> {
+        let tree = self.loaded()?;
+        let Some(source) = tree.source.as_ref().filter(|_| tree.freshness().is_stale()) else {
+            return Ok(false);
+        };
+        let metadata = tree.metadata.clone();
+        let document = crate::loader::load(Path::new(&source.path))?;
+        let progress = self.progress.clone();
+        self.index_document_with(document, progress).await?;
+        if let Some(tree) = self.tree.as_mut() {
+            tree.metadata = metadata;
+        }
+        Ok(true)
+    }
+
+    /// Save the loaded tree; the format follows the extension.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        save_tree(self.loaded()?, path.as_ref())
+    }
+
+    /// Search the loaded tree, including section text when the document or
+    /// a store is available.
+    pub async fn search(&self, query: &str) -> Result<Vec<SearchResult>> {
+        self.search_with(query, &self.search_options).await
+    }
+
+    /// Search with options other than the builder's.
+    pub async fn search_with(
+        &self,
+        query: &str,
+        options: &SearchOptions,
+    ) -> Result<Vec<SearchResult>> {
+        self.search_inner(query, options, self.progress.clone())
+            .await
+    }
+
+    async fn search_inner(
+        &self,
+        query: &str,
+        options: &SearchOptions,
+        progress: Option<ProgressCallback>,
+    ) -> Result<Vec<SearchResult>> {
+        let tree = self.loaded()?;
+        let mut searcher = TreeSearcher::with_models(&self.client, &self.models, options.clone());
+        if let Some(progress) = progress {
+            searcher = searcher.with_progress(progress);
+        }
+        match (&self.document, &self.store) {
+            (Some(document), _) => searcher.search_with_content(tree, document, query).await,
+            (None, Some(store)) => {
+                searcher
+                    .search_with_content(tree, store.as_ref(), query)
+                    .await
+            }
+            (None, None) => searcher.search(tree, query).await,
+        }
+    }
+
+    /// Answer a question from the sections retrieved for it.
+    ///
+    /// Without the source document, the answer can only draw on section
+    /// titles and the reasons they matched. With an answer cache, a
+    /// question already answered from the same tree is not asked again.
+    pub async fn ask(&self, question: &str) -> Result<Answer> {
+        self.ask_with(question, &self.answer_style).await
+    }
+
+    /// Ask with an answer style other than the builder's.
+    pub async fn ask_with(&self, question: &str, style: &AnswerStyle) -> Result<Answer> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some((cache, key)) = self.cache_key(question, style)? {
+            if let Some(answer) = cache.get(&key) {
+                return Ok(answer);
+            }
+            let sources = self.search(question).await?;
+            let answer = self.answer_with(question, sources, style).await?;
+            cache.insert(key, answer.clone());
+            return Ok(answer);
+        }
+        let sources = self.search(question).await?;
+        self.answer_with(question, sources, style).await
+    }
+
+    /// The answer cache and the key of a question about the loaded tree.
+    /// Answers restricted by access labels or effective date are kept
+    /// under the tree's version with those filters appended, since indexes
+    /// sharing the cache may see different sections of one tree.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn cache_key(
+        &self,
+        question: &str,
+        style: &AnswerStyle,
+    ) -> Result<Option<(&AnswerCache, AnswerKey)>> {
+        let Some(cache) = &self.answer_cache else {
+            return Ok(None);
+        };
+        let mut version = self.loaded()?.content_hash();
+        if let Some(labels) = &self.search_options.acl_labels {
+            version = format!("{}#{}", version, labels.join(","));
+        }
+        if let Some(since) = &self.search_options.effective_since {
+            version = format!("{}@{}", version, since);
+        }
+        let key = AnswerKey::new(question, version, self.client.model()).with_style(style.clone());
+        Ok(Some((cache, key)))
+    }
+
+    /// Answer a question from already retrieved sections.
+    pub async fn answer(&self, question: &str, sources: Vec<SearchResult>) -> Result<Answer> {
+        self.answer_with(question, sources, &self.answer_style)
+            .await
+    }
+
+    /// Answer from retrieved sections in a given style.
+    pub async fn answer_with(
+        &self,
+        question: &str,
+        sources: Vec<SearchResult>,
+        style: &AnswerStyle,
+    ) -> Result<Answer> {
+        let tree_terms = self.tree.as_ref().map(|t| &t.terms);
+        let glossaries: Vec<_> = tree_terms
+            .into_iter()
+            .chain([&self.search_options.terms])
+            .collect();
+        let terms = terms_in(question, &glossaries);
+        let document = self.quotable_document();
+        answer_from(&self.client, question, sources, style, &terms, document).await
+    }
+
+    /// The source document to quote answers from: none when access labels
+    /// hide some of its pages, since sources then carry only the pages the
+    /// caller may see.
+    fn quotable_document(&self) -> Option<&Document> {
+        let hides_pages = match (&self.tree, &self.search_options.acl_labels) {
+            (Some(tree), Some(labels)) => !tree.hidden_pages(labels).is_empty(),
+            _ => false,
+        };
+        self.document.as_ref().filter(|_| !hides_pages)
+    }
+
+    /// Extract data matching a JSON Schema from the sections retrieved for
+    /// a query, citing the section of each field (see [`crate::extract`]).
+    pub async fn extract(&self, query: &str, schema: &serde_json::Value) -> Result<Extraction> {
+        let sources = self.search(query).await?;
+        Extractor::new(self.client.clone())
+            .extract(query, schema, sources)
+            .await
+    }
+
+    /// Render the sections retrieved for a query as one Markdown bundle
+    /// for pasting into other LLM tools (see [`to_context_markdown`]).
+    pub async fn export_context(&self, query: &str) -> Result<String> {
+        let results = self.search(query).await?;
+        Ok(to_context_markdown(self.loaded()?, query, &results))
+    }
+
+    fn loaded(&self) -> Result<&DocumentTree> {
+        self.tree.as_ref().ok_or_else(|| {
+            PageIndexError::TreeError("No index loaded; call index_file or load first".to_string())
+        })
+    }
+}
+
+/// Answer a question from retrieved sections with the RAG answer prompt,
+/// defining the given terms (see [`terms_in`]) after the question.
+/// With a required relevance, answers only from the sections that meet it,
+/// without calling the LLM when none do.
+///
+/// With the source document, the context is its pages for each section
+/// (see [`Document::extract_ranked`]), each page once; without it, each
+/// section's fetched content, or the reason it matched.
+pub(crate) async fn answer_from(
+    client: &LlmClient,
+    question: &str,
+    mut sources: Vec<SearchResult>,
+    style: &AnswerStyle,
+    terms: &[(String, String)],
+    document: Option<&Document>,
+) -> Result<Answer> {
+    if let Some(required) = style.required_relevance {
+        sources.retain(|r| r.relevance.score() >= required.score());
+        if sources.is_empty() {
+            return Ok(Answer::not_found());
+        }
+    }
+    let context = match document {
+        Some(document) => document.extract_ranked(&sources, usize::MAX),
+        None => sources
+            .iter()
+            .map(|r| {
+                let content = r.content.as_deref().unwrap_or(&r.reason);
+                format!("{}{}", section_header(r), content)
+            })
+            .collect::<Vec<_>>()
+            .join(SECTION_SEPARATOR),
+    };
+    let prompt = Prompts::rag_answer()
+        .replace("{style}", &style.instructions())
+        .replace("{question}", &with_definitions(question, terms))
+        .replace("{context}", &context);
+    let answer = client.complete(None, &prompt).await?;
+    Ok(Answer {
+        answer: answer.trim().to_string(),
+        sources,
+        outcome: AnswerOutcome::Answered,
+    })
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl PageIndex {
+    /// [`index_document`](PageIndex::index_document) as a stream of events.
+    pub fn index_stream(&mut self, document: Document) -> EventStream<'_, IndexEvent> {
+        EventStream::new(move |tx| async move {
+            let progress = forward_progress(self.progress.clone(), tx, IndexEvent::Progress);
+            let tree = self.index_document_with(document, Some(progress)).await?;
+            Ok(IndexEvent::Completed(tree.clone()))
+        })
+    }
+
+    /// [`search`](PageIndex::search) as a stream of events.
+    pub fn search_stream<'a>(&'a self, query: &'a str) -> EventStream<'a, SearchEvent> {
+        EventStream::new(move |tx| async move {
+            let progress = forward_progress(self.progress.clone(), tx, SearchEvent::Progress);
+            let options = &self.search_options;
+            let results = self.search_inner(query, options, Some(progress)).await?;
+            Ok(SearchEvent::Completed(results))
+        })
+    }
+
+    /// [`ask`](PageIndex::ask) as a stream of events. A cached answer
+    /// completes the stream right after its sources.
+    pub fn ask_stream<'a>(&'a self, question: &'a str) -> EventStream<'a, AskEvent> {
+        EventStream::new(move |tx| async move {
+            let cached = self.cache_key(question, &self.answer_style)?;
+            if let Some(answer) = cached.as_ref().and_then(|(cache, key)| cache.get(key)) {
+                let _ = tx.send(AskEvent::Sources(answer.sources.clone()));
+                return Ok(AskEvent::Completed(answer));
+            }
+            let progress = forward_progress(self.progress.clone(), tx.clone(), AskEvent::Progress);
+            let options = &self.search_options;
+            let sources = self.search_inner(question, options, Some(progress)).await?;
+            let _ = tx.send(AskEvent::Sources(sources.clone()));
+            let answer = self.answer(question, sources).await?;
+            if let Some((cache, key)) = cached {
+                cache.insert(key, answer.clone());
+            }
+            Ok(AskEvent::Completed(answer))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::offline_client;
+    use crate::progress::ProgressEvent;
+    use crate::tree::TreeNode;
+
+    fn offline_index() -> PageIndex {
+        PageIndex::builder()
+            .with_client(offline_client())
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_builder_validates_config() {
+        let result = PageIndex::builder().with_config(Config::default()).build();
+        assert!(matches!(result, Err(PageIndexError::Config(_))));
+
+        let config = Config::with_llm("http://localhost", "key", "model");
+        assert!(PageIndex::builder().with_config(config).build().is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_load_save_and_search() {
+        let mut index = offline_index();
+        assert!(matches!(
+            index.search("intro").await,
+            Err(PageIndexError::TreeError(_))
+        ));
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tree.json");
+        let tree = DocumentTree::new("doc", vec![TreeNode::new("Introduction", 1, 1)], 1);
+        save_tree(&tree, &path).unwrap();
+
+        assert_eq!(index.load(&path).unwrap().name, "doc");
+        index.save(dir.path().join("copy.bin")).unwrap();
+        assert_eq!(load_tree(&dir.path().join("copy.bin")).unwrap().name, "doc");
+
+        // With a tree loaded, search gets as far as the LLM call
+        assert!(matches!(
+            index.ask("intro").await,
+            Err(PageIndexError::Http(_))
+        ));
+
+        use tokio_stream::StreamExt;
+        let events: Vec<_> = index.ask_stream("intro").collect().await;
+        assert!(matches!(
+            events[0],
+            Ok(AskEvent::Progress(ProgressEvent::SearchStarted {
+                nodes: 1
+            }))
+        ));
+        assert!(matches!(events[1], Err(PageIndexError::Http(_))));
+        assert_eq!(events.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_answer_cache() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("data/fixtures");
+        let fixture = crate::fixtures::Fixture::replay(dir.join("sample.fixture.json")).unwrap();
+        let cache = AnswerCache::default();
+        let mut index = PageIndex::builder()
+            .with_client(fixture.client().unwrap())
+            .with_answer_cache(cache.clone())
+            .build()
+            .unwrap();
+        index.index_file(dir.join("sample.txt")).await.unwrap();
+
+        let answer = index
+            .ask("How often should the filter be cleaned?")
+            .await
+            .unwrap();
+        let calls = index.client().usage().calls;
+        let again = index
+            .ask("how often should the filter be  cleaned")
+            .await
+            .unwrap();
+        assert_eq!(again.answer, answer.answer);
+        assert_eq!(index.client().usage().calls, calls);
+
+        use tokio_stream::StreamExt;
+        let events: Vec<_> = index
+            .ask_stream("How often should the filter be cleaned?")
+            .collect()
+            .await;
+        assert!(matches!(events.last(), Some(Ok(AskEvent::Completed(_)))));
+        assert_eq!(index.client().usage().calls, calls);
+
+        // Indexes sharing the cache with other labels or dates don't share
+        // answers
+        let style = AnswerStyle::default();
+        let question = "How often should the filter be cleaned?";
+        let (_, open) = index.cache_key(question, &style).unwrap().unwrap();
+        assert!(cache.get(&open).is_some());
+        for options in [
+            SearchOptions {
+                acl_labels: Some(Vec::new()),
+                ..SearchOptions::default()
+            },
+            SearchOptions {
+                effective_since: Some("2024-01-01".to_string()),
+                ..SearchOptions::default()
+            },
+        ] {
+            let mut restricted = PageIndex::builder()
+                .with_client(index.client().clone())
+                .with_answer_cache(cache.clone())
+                .with_search_options(options)
+                .build()
+                .unwrap();
+            restricted.tree = index.tree.clone();
+            let (_, key) = restricted.cache_key(question, &style).unwrap().unwrap();
+            assert_ne!(key, open);
+            assert!(cache.get(&key).is_none());
+        }
+
+        let version = index.tree().unwrap().content_hash();
+        assert_eq!(cache.invalidate_version(&version), 1);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_answer_style() {
+        // The default keeps the original answer prompt
+        let prompt =
+            Prompts::rag_answer().replace("{style}", &AnswerStyle::default().instructions());
+        assert!(prompt.contains("- Be concise and direct in your answer.\n- Do not make up"));
+
+        let style = AnswerStyle {
+            length: AnswerLength::Short,
+            format: AnswerFormat::Bullets,
+            language: Some(" German ".to_string()),
+            ..AnswerStyle::default()
+        };
+        let lines: Vec<_> = style.instructions().lines().map(str::to_string).collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains("one or two sentences"));
+        assert!(lines[1].contains("bulleted list"));
+        assert!(lines[2].contains("in German,"));
+    }
+
+    #[tokio::test]
+    async fn test_required_relevance() {
+        let index = offline_index();
+        let source = |relevance| SearchResult {
+            title: "Warranty".to_string(),
+            start_index: 2,
+            end_index: 2,
+            relevance,
+            reason: String::new(),
+            content: None,
+            snippet: None,
+            snippet_span: None,
+        };
+        let style = AnswerStyle {
+            required_relevance: Some(Relevance::Medium),
+            ..AnswerStyle::default()
+        };
+
+        // Nothing relevant enough: refused without calling the LLM
+        let answer = index
+            .answer_with("q", vec![source(Relevance::Low)], &style)
+            .await
+            .unwrap();
+        assert!(answer.is_not_found());
+        assert_eq!(answer.answer, NOT_FOUND_ANSWER);
+        assert!(answer.sources.is_empty());
+        let json = serde_json::to_value(&answer).unwrap();
+        assert_eq!(json["outcome"], "not_found");
+
+        // A supported question goes to the (offline) LLM
+        let sources = vec![source(Relevance::High), source(Relevance::Low)];
+        assert!(index.answer_with("q", sources, &style).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_answer_context() {
+        use crate::document::Page;
+        use crate::fixtures::replay_replies;
+        use crate::golden::PromptCapture;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("answer.fixture.json");
+        let capture = PromptCapture::new(replay_replies(&path, &["Weekly.", "Weekly."]));
+        let client = capture.client().unwrap();
+        let document = Document::new(
+            "manual",
+            vec![
+                Page::new(1, "Cleaning.".to_string()),
+                Page::new(2, "Soak the filter weekly.".to_string()),
+            ],
+        );
+        let source = |title: &str, start_index, end_index| SearchResult {
+            title: title.to_string(),
+            start_index,
+            end_index,
+            relevance: Relevance::High,
+            reason: "r".to_string(),
+            content: Some(format!("{} text", title)),
+            snippet: None,
+            snippet_span: None,
+        };
+        let sources = vec![source("Care", 1, 2), source("Filter", 2, 2)];
+        let style = AnswerStyle::default();
+
+        // From the document: page tags, and each page once
+        answer_from(&client, "q", sources.clone(), &style, &[], Some(&document))
+            .await
+            .unwrap();
+        // Without it: each section's fetched content
+        answer_from(&client, "q", sources, &style, &[], None)
+            .await
+            .unwrap();
+
+        let prompts: Vec<String> = capture
+            .prompts()
+            .iter()
+            .map(|p| {
+                p.request["messages"][0]["content"]
+                    .as_str()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect();
+        assert!(prompts[0].contains(
+            "[Section: Care] (pages 1-2)\n<physical_index_1>\nCleaning.\n<physical_index_1>"
+        ));
+        assert!(!prompts[0].contains("[Section: Filter]"));
+        assert!(prompts[1].contains(
+            "[Section: Care] (pages 1-2)\nCare text\n\n---\n\n[Section: Filter] (pages 2-2)\nFilter text"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_refresh() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("data/fixtures");
+        let fixture = crate::fixtures::Fixture::replay(dir.join("sample.fixture.json")).unwrap();
+        let mut index = PageIndex::builder()
+            .with_client(fixture.client().unwrap())
+            .build()
+            .unwrap();
+        let source = dir.join("sample.txt");
+        index.index_file(&source).await.unwrap();
+        assert_eq!(index.freshness(), Some(Freshness::Fresh));
+        assert!(!index.refresh().await.unwrap());
+
+        // A tree whose recorded source no longer matches the file
+        let tmp = tempfile::TempDir::new().unwrap();
+        let saved = tmp.path().join("tree.json");
+        let mut tree = index
+            .tree()
+            .unwrap()
+            .clone()
+            .with_metadata("type", "manual");
+        let recorded = tree.source.as_mut().unwrap();
+        recorded.hash = "0".repeat(16);
+        recorded.modified = None;
+        save_tree(&tree, &saved).unwrap();
+        index.load(&saved).unwrap();
+        assert_eq!(index.freshness(), Some(Freshness::Changed));
+
+        let calls = index.client().usage().calls;
+        assert!(index.refresh().await.unwrap());
+        assert!(index.client().usage().calls > calls);
+        let tree = index.tree().unwrap();
+        assert_eq!(tree.freshness(), Freshness::Fresh);
+        assert_eq!(tree.metadata["type"], "manual");
+    }
+}
@@ -1,11 +1,20 @@
 //! Persistence layer for saving/loading document trees.
 //!
 //! Supports both JSON (human-readable) and bincode (efficient binary) formats.
+//! Navigation-only trees ([`save_navigation_tree`]) are written as compact
+//! JSON, since they are meant to be shipped rather than read.
+//!
+//! Paths are used as given: callers with a data root (`storage.data_root`
+//! in the config file) resolve relative index paths against it first with
+//! [`resolve_index_path`]. On Windows, paths too long for the legacy
+//! `MAX_PATH` limit are passed to the filesystem in their extended-length
+//! (`\\?\`) form, so indexes can live under deeply nested corpora. Paths
+//! returned to callers never carry that prefix.
 
 use crate::error::{PageIndexError, Result};
 use crate::tree::DocumentTree;
 use std::fs;
-use std::path::Path;
+use std::path::{Component, Path, PathBuf};
 
 /// Default filename for the tree index.
 pub const DEFAULT_INDEX_FILENAME: &str = "tree_index.json";
@@ -20,9 +29,9 @@ pub enum SaveFormat {
 }
 
 impl SaveFormat {
-    /// Determine format from file extension.
+    /// Determine format from file extension (in any case).
     pub fn from_path(path: &Path) -> Self {
-        match path.extension().and_then(|e| e.to_str()) {
+        match extension(path).as_deref() {
             Some("json") => SaveFormat::Json,
             Some("bin") | Some("bincode") => SaveFormat::Bincode,
             _ => SaveFormat::Json, // Default to JSON
@@ -30,6 +39,78 @@ impl SaveFormat {
     }
 }
 
+/// A path's extension, lowercased. Non-UTF-8 names are read lossily.
+fn extension(path: &Path) -> Option<String> {
+    path.extension().map(|e| e.to_string_lossy().to_lowercase())
+}
+
+/// Resolve a relative index path against the data root `root`, dropping
+/// `.` and `..` components. Absolute paths, and any path without a root,
+/// are returned as given.
+pub fn resolve_index_path(path: &Path, root: Option<&Path>) -> PathBuf {
+    match root {
+        Some(root) if path.is_relative() => normalize_path(&root.join(path)),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Drop `.` components and resolve `..` against the preceding component,
+/// without touching the filesystem. Leading `..` of a relative path are
+/// kept.
+pub fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                Some(Component::RootDir | Component::Prefix(_)) => {}
+                _ => normalized.push(".."),
+            },
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// Longest path (in UTF-16 units) Windows accepts everywhere without the
+/// extended-length prefix: `MAX_PATH` less room for an 8.3 file name, the
+/// limit for directories.
+const WINDOWS_MAX_DIR_PATH: usize = 248;
+
+/// The path to hand to the filesystem: on Windows, in extended-length
+/// form when it is too long for `MAX_PATH`. Elsewhere long paths need no
+/// special treatment. Not for returning to callers.
+pub(crate) fn long_path(path: &Path) -> PathBuf {
+    if !cfg!(windows) {
+        return path.to_path_buf();
+    }
+    let absolute =
+        std::path::absolute(path).map_or_else(|_| path.to_path_buf(), |p| normalize_path(&p));
+    extended_length(&absolute.to_string_lossy())
+        .map(PathBuf::from)
+        .unwrap_or(absolute)
+}
+
+/// Extended-length form of an absolute Windows path over
+/// [`WINDOWS_MAX_DIR_PATH`] (`C:\x` -> `\\?\C:\x`, `\\server\share` ->
+/// `\\?\UNC\server\share`), or `None` if it needs none.
+fn extended_length(path: &str) -> Option<String> {
+    if path.encode_utf16().count() < WINDOWS_MAX_DIR_PATH || path.starts_with(r"\\?\") {
+        return None;
+    }
+    // Extended-length paths are taken literally: only backslashes separate
+    let path = path.replace('/', r"\");
+    if let Some(unc) = path.strip_prefix(r"\\") {
+        return Some(format!(r"\\?\UNC\{}", unc));
+    }
+    let drive = path.as_bytes();
+    (drive.len() > 2 && drive[0].is_ascii_alphabetic() && drive[1] == b':' && drive[2] == b'\\')
+        .then(|| format!(r"\\?\{}", path))
+}
+
 /// Save a DocumentTree to a file.
 pub fn save_tree(tree: &DocumentTree, path: &Path) -> Result<()> {
     let format = SaveFormat::from_path(path);
@@ -38,14 +119,20 @@ pub fn save_tree(tree: &DocumentTree, path: &Path) -> Result<()> {
 
 /// Save a DocumentTree with specific format.
 pub fn save_tree_with_format(tree: &DocumentTree, path: &Path, format: SaveFormat) -> Result<()> {
+    let target = long_path(path);
+
     // Ensure parent directory exists
-    if let Some(parent) = path.parent() {
-        if !parent.exists() {
-            fs::create_dir_all(parent).map_err(|e| PageIndexError::io(parent, e))?;
-        }
+    if let Some(parent) = target.parent()
+        && !parent.exists()
+    {
+        fs::create_dir_all(parent)
+            .map_err(|e| PageIndexError::io(path.parent().unwrap_or(path), e))?;
     }
 
     let data = match format {
+        SaveFormat::Json if tree.navigation_only => {
+            serde_json::to_vec(tree).map_err(|e| PageIndexError::Serialization(e.to_string()))?
+        }
         SaveFormat::Json => serde_json::to_string_pretty(tree)
             .map_err(|e| PageIndexError::Serialization(e.to_string()))?
             .into_bytes(),
@@ -56,14 +143,20 @@ pub fn save_tree_with_format(tree: &DocumentTree, path: &Path, format: SaveForma
         }
     };
 
-    fs::write(path, &data).map_err(|e| PageIndexError::io(path, e))?;
+    fs::write(target, &data).map_err(|e| PageIndexError::io(path, e))?;
 
     Ok(())
 }
 
+/// Save a navigation-only copy of a DocumentTree (see
+/// [`DocumentTree::to_navigation_only`]).
+pub fn save_navigation_tree(tree: &DocumentTree, path: &Path) -> Result<()> {
+    save_tree(&tree.to_navigation_only(), path)
+}
+
 /// Load a DocumentTree from a file.
 pub fn load_tree(path: &Path) -> Result<DocumentTree> {
-    if !path.exists() {
+    if !long_path(path).exists() {
         return Err(PageIndexError::IndexNotFound(path.to_path_buf()));
     }
 
@@ -73,7 +166,7 @@ pub fn load_tree(path: &Path) -> Result<DocumentTree> {
 
 /// Load a DocumentTree with specific format.
 pub fn load_tree_with_format(path: &Path, format: SaveFormat) -> Result<DocumentTree> {
-    let data = fs::read(path).map_err(|e| PageIndexError::io(path, e))?;
+    let data = fs::read(long_path(path)).map_err(|e| PageIndexError::io(path, e))?;
 
     let tree = match format {
         SaveFormat::Json => {
@@ -95,15 +188,27 @@ pub fn load_tree_with_format(path: &Path, format: SaveFormat) -> Result<Document
 
 /// Check if an index file exists at the given path.
 pub fn tree_exists(path: &Path) -> bool {
-    path.exists() && path.is_file()
+    long_path(path).is_file()
 }
 
 /// Get the size of an index file in bytes.
 pub fn tree_size(path: &Path) -> Result<u64> {
-    let metadata = fs::metadata(path).map_err(|e| PageIndexError::io(path, e))?;
+    let metadata = fs::metadata(long_path(path)).map_err(|e| PageIndexError::io(path, e))?;
     Ok(metadata.len())
 }
 
+/// Index files (`.json`, `.bin` or `.bincode`, in any case) in a directory,
+/// sorted by path. The paths are `dir` joined with each file name.
+pub fn index_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = fs::read_dir(long_path(dir)).map_err(|e| PageIndexError::io(dir, e))?;
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| dir.join(e.file_name())))
+        .filter(|p| matches!(extension(p).as_deref(), Some("json" | "bin" | "bincode")))
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -170,6 +275,76 @@ mod tests {
             SaveFormat::Bincode
         );
         assert_eq!(SaveFormat::from_path(Path::new("test")), SaveFormat::Json);
+        assert_eq!(
+            SaveFormat::from_path(Path::new("TEST.BIN")),
+            SaveFormat::Bincode
+        );
+    }
+
+    #[test]
+    fn test_path_resolution() {
+        let root = Path::new("/data/indexes");
+        assert_eq!(
+            resolve_index_path(Path::new("./corpus/../manuals/pump.json"), Some(root)),
+            Path::new("/data/indexes/manuals/pump.json")
+        );
+        assert_eq!(
+            resolve_index_path(Path::new("/srv/pump.json"), Some(root)),
+            Path::new("/srv/pump.json")
+        );
+        assert_eq!(
+            resolve_index_path(Path::new("a/../b.json"), None),
+            Path::new("a/../b.json")
+        );
+        assert_eq!(
+            normalize_path(Path::new("../a/./b/../c")),
+            Path::new("../a/c")
+        );
+        assert_eq!(normalize_path(Path::new("/../a")), Path::new("/a"));
+
+        let deep = format!(r"C:\corpora\{}index.json", "nested\\".repeat(40));
+        assert_eq!(
+            extended_length(&deep.replace(r"C:\corpora", "C:/corpora")),
+            Some(format!(r"\\?\{}", deep))
+        );
+        assert_eq!(
+            extended_length(&format!(r"\\server\share\{}", "x".repeat(300))),
+            Some(format!(r"\\?\UNC\server\share\{}", "x".repeat(300)))
+        );
+        assert_eq!(extended_length(r"C:\short\index.json"), None);
+    }
+
+    #[test]
+    fn test_unicode_and_data_root() {
+        let dir = TempDir::new().unwrap();
+        let nested = dir.path().join("Ünïcode corpus").join("手册");
+        let path = nested.join("índice.JSON");
+        save_tree(&create_test_tree(), &path).unwrap();
+        assert!(tree_exists(&path));
+        let binary = nested.join("índice.bincode");
+        save_tree(&create_test_tree(), &binary).unwrap();
+        fs::write(nested.join("notes.txt"), "not an index").unwrap();
+        assert_eq!(
+            index_files(&nested).unwrap(),
+            vec![path.clone(), binary.clone()]
+        );
+        assert_eq!(load_tree(&binary).unwrap().name, "Test Document");
+
+        // Errors name the path as given
+        let missing = nested.join("missing.json");
+        assert!(matches!(
+            tree_size(&missing),
+            Err(PageIndexError::Io { path, .. }) if path == missing
+        ));
+        assert_eq!(load_tree(&path).unwrap().name, "Test Document");
+
+        // Relative paths are found once resolved against the data root
+        let relative = Path::new("Ünïcode corpus/手册/índice.JSON");
+        assert!(!tree_exists(relative));
+        let resolved = resolve_index_path(relative, Some(dir.path()));
+        assert_eq!(resolved, path);
+        assert!(tree_exists(&resolved));
+        assert_eq!(load_tree(&resolved).unwrap().name, "Test Document");
     }
 
     #[test]
@@ -190,6 +365,34 @@ mod tests {
         assert!(size > 0);
     }
 
+    #[test]
+    fn test_save_navigation_tree() {
+        let dir = TempDir::new().unwrap();
+        let mut tree = create_test_tree();
+        tree.description = Some("A long abstract of the document.".to_string());
+        for node in &mut tree.nodes {
+            node.summary = Some("A long summary of the chapter.".to_string());
+        }
+
+        for name in ["nav.json", "nav.bin"] {
+            let full = dir.path().join(format!("full.{}", name));
+            let nav = dir.path().join(name);
+            save_tree(&tree, &full).unwrap();
+            save_navigation_tree(&tree, &nav).unwrap();
+            assert!(tree_size(&nav).unwrap() < tree_size(&full).unwrap());
+
+            let loaded = load_tree(&nav).unwrap();
+            assert!(loaded.navigation_only);
+            assert!(loaded.description.is_none() && loaded.nodes[0].summary.is_none());
+            assert_eq!(loaded.node_count(), tree.node_count());
+        }
+        assert!(
+            !load_tree(&dir.path().join("full.nav.json"))
+                .unwrap()
+                .navigation_only
+        );
+    }
+
     #[test]
     fn test_json_is_readable() {
         let dir = TempDir::new().unwrap();
@@ -0,0 +1,76 @@
+//! Progress reporting for long-running operations.
+//!
+//! The indexer, searcher, and benchmark emit [`ProgressEvent`]s through an
+//! optional callback so front-ends (like the CLI progress bars) can show
+//! meaningful progress instead of silence.
+
+use std::sync::Arc;
+
+/// A progress update from an indexing, search, or evaluation run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProgressEvent {
+    /// Structure extraction progress over document chunks.
+    ChunkProcessed { completed: usize, total: usize },
+    /// Title verification progress over the nodes being checked.
+    NodeVerified { completed: usize, total: usize },
+    /// Summary generation progress over tree nodes.
+    SummaryGenerated {
+        completed: usize,
+        total: usize,
+        title: String,
+    },
+    /// Tree search started reasoning over the given number of nodes.
+    SearchStarted { nodes: usize },
+    /// Tree search finished with the given number of results.
+    SearchCompleted { results: usize },
+    /// An evaluation item started (`completed` items are already done).
+    ItemStarted {
+        completed: usize,
+        total: usize,
+        item_id: String,
+    },
+    /// An evaluation item finished.
+    ItemCompleted {
+        completed: usize,
+        total: usize,
+        item_id: String,
+    },
+    /// Running head-to-head judge verdicts after an evaluation item.
+    WinsTally {
+        pageindex_wins: usize,
+        vector_wins: usize,
+        ties: usize,
+    },
+}
+
+/// Callback invoked for each progress event.
+pub type ProgressCallback = Arc<dyn Fn(&ProgressEvent) + Send + Sync>;
+
+/// Invoke the callback (if any) with an event.
+pub(crate) fn emit(callback: &Option<ProgressCallback>, event: ProgressEvent) {
+    if let Some(callback) = callback {
+        callback(&event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_emit_calls_callback() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let callback: Option<ProgressCallback> = Some(Arc::new(move |event: &ProgressEvent| {
+            sink.lock().unwrap().push(event.clone());
+        }));
+
+        emit(&callback, ProgressEvent::SearchStarted { nodes: 3 });
+        emit(&None, ProgressEvent::SearchCompleted { results: 1 });
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 1);
+        assert_eq!(seen[0], ProgressEvent::SearchStarted { nodes: 3 });
+    }
+}
@@ -0,0 +1,466 @@
+//! Query normalization before search.
+//!
+//! Search hands the query to the LLM as typed, and a misspelled term
+//! ("maintanence", "filtr") or an abbreviation the document spells out
+//! ("MTBF") can steer it to the wrong sections. A [`QueryNormalizer`] tidies
+//! the query first, without an LLM call:
+//!
+//! - whitespace is collapsed, and a query typed in capitals is lowercased;
+//! - words in the glossary are expanded, keeping the abbreviation
+//!   ("MTBF" -> "mean time between failures (MTBF)");
+//! - with spell correction on, words that are neither in the tree's titles
+//!   and summaries nor common English are replaced by the closest word that
+//!   is, within one or two edits.
+//!
+//! Normalization is set per index: `tree.json` picks up the settings in
+//! `tree.json.query` (YAML), or search with
+//! [`SearchOptions::normalizer`](crate::search::SearchOptions::normalizer).
+//!
+//! Jargon the LLM may misread ("DSCR", "EBITDA add-backs") can instead be
+//! defined: the definitions of the [`terms`](DocumentTree::terms) a query
+//! uses are added after it in search and answer prompts (see
+//! [`terms_in`] and [`with_definitions`]).
+//!
+//! ```no_run
+//! use rust_page_indexer::load_tree;
+//! use rust_page_indexer::query::QueryNormalizer;
+//! use std::path::Path;
+//!
+//! # fn run() -> rust_page_indexer::Result<()> {
+//! let tree = load_tree(Path::new("data/tree_index.json"))?;
+//! let normalizer = QueryNormalizer::new()
+//!     .with_spell_correction(true)
+//!     .with_glossary([("MTBF", "mean time between failures")]);
+//! // "mean time between failures (MTBF) of the pump"
+//! println!("{}", normalizer.normalize("MTBF of the pupm", &tree));
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{PageIndexError, Result};
+use crate::tree::{DocumentTree, TreeNode};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// Words shorter than this are never spell-corrected: too many short
+/// words are a single edit apart.
+const MIN_CORRECTED_CHARS: usize = 4;
+
+/// Words of at least this many characters may be two edits from their
+/// correction, shorter ones one.
+const TWO_EDIT_CHARS: usize = 8;
+
+/// Common English words that are never corrected, though the tree may not
+/// use them.
+const COMMON_WORDS: &[&str] = &[
+    "about", "above", "after", "again", "against", "also", "always", "another", "anything",
+    "around", "because", "been", "before", "being", "below", "best", "better", "between", "both",
+    "came", "cannot", "come", "could", "does", "doing", "done", "down", "during", "each", "either",
+    "else", "enough", "even", "ever", "every", "explain", "find", "first", "from", "further",
+    "get", "give", "going", "good", "have", "having", "help", "here", "how", "into", "just",
+    "know", "last", "less", "like", "list", "long", "look", "make", "many", "mean", "means",
+    "more", "most", "much", "must", "need", "needs", "never", "next", "none", "often", "once",
+    "only", "other", "over", "part", "same", "should", "show", "since", "some", "still", "such",
+    "take", "tell", "than", "that", "their", "them", "then", "there", "these", "they", "thing",
+    "things", "this", "those", "through", "time", "under", "until", "upon", "used", "using",
+    "very", "want", "well", "were", "what", "when", "where", "whether", "which", "while", "whom",
+    "whose", "will", "with", "within", "without", "work", "would", "your",
+];
+
+/// Default normalization settings path for an index path (`tree.json` ->
+/// `tree.json.query`).
+pub fn normalizer_path_for(index: &Path) -> PathBuf {
+    let mut name = index.as_os_str().to_owned();
+    name.push(".query");
+    PathBuf::from(name)
+}
+
+/// Tidies queries before search: casing, glossary expansion and spell
+/// correction against the tree's own words.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QueryNormalizer {
+    /// Correct misspelled words against the words of the tree.
+    #[serde(default)]
+    pub spell_correct: bool,
+    /// Abbreviations and their expansions. Matched as whole words, ignoring
+    /// case.
+    #[serde(default)]
+    pub glossary: BTreeMap<String, String>,
+}
+
+impl QueryNormalizer {
+    /// A normalizer that only tidies whitespace and casing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Turn spell correction on or off.
+    pub fn with_spell_correction(mut self, enabled: bool) -> Self {
+        self.spell_correct = enabled;
+        self
+    }
+
+    /// Add glossary entries, replacing those for the same abbreviation.
+    pub fn with_glossary(
+        mut self,
+        entries: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
+    ) -> Self {
+        self.glossary
+            .extend(entries.into_iter().map(|(k, v)| (k.into(), v.into())));
+        self
+    }
+
+    /// Load settings from a YAML (or JSON) file with `spell_correct` and
+    /// `glossary` keys.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| PageIndexError::io(path, e))?;
+        serde_yaml::from_str(&content).map_err(|e| {
+            PageIndexError::Config(format!(
+                "Invalid query settings in '{}': {}",
+                path.display(),
+                e
+            ))
+        })
+    }
+
+    /// The settings for an index, from its [sidecar
+    /// file](normalizer_path_for), or `None` if it has none.
+    pub fn for_index(index: &Path) -> Result<Option<Self>> {
+        let path = normalizer_path_for(index);
+        if !path.exists() {
+            return Ok(None);
+        }
+        Self::load(&path).map(Some)
+    }
+
+    /// Normalize a query for searching `tree`.
+    pub fn normalize(&self, query: &str, tree: &DocumentTree) -> String {
+        let mut query = query.split_whitespace().collect::<Vec<_>>().join(" ");
+        let letters = query.chars().filter(|c| c.is_alphabetic());
+        if letters.clone().count() > 1 && letters.clone().all(char::is_uppercase) {
+            query = query.to_lowercase();
+        }
+
+        // Keys match as written ("e.g."), or without surrounding punctuation
+        let glossary: HashMap<String, &str> = self
+            .glossary
+            .iter()
+            .flat_map(|(k, v)| {
+                [
+                    (trim_word(k).2.to_lowercase(), v.as_str()),
+                    (k.to_lowercase(), v.as_str()),
+                ]
+            })
+            .collect();
+        let vocabulary = if self.spell_correct {
+            Vocabulary::of(tree, self.glossary.values())
+        } else {
+            Vocabulary::default()
+        };
+
+        let normalized = query
+            .split(' ')
+            .map(|chunk| {
+                if let Some(expansion) = glossary.get(&chunk.to_lowercase()) {
+                    return format!("{} ({})", expansion, chunk);
+                }
+                let (before, after, word) = trim_word(chunk);
+                let replacement = match glossary.get(&word.to_lowercase()) {
+                    Some(expansion) => format!("{} ({})", expansion, word),
+                    None if self.spell_correct => vocabulary.correct(word),
+                    None => return chunk.to_string(),
+                };
+                format!("{}{}{}", before, replacement, after)
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+        if normalized != query {
+            debug!(query, normalized, "normalized query");
+        }
+        normalized
+    }
+}
+
+/// Load a glossary from a YAML (or JSON) map: abbreviations to their
+/// expansions for a [`QueryNormalizer`], or terms to their definitions for
+/// [`SearchOptions::terms`](crate::search::SearchOptions::terms).
+pub fn load_glossary(path: &Path) -> Result<BTreeMap<String, String>> {
+    let content = std::fs::read_to_string(path).map_err(|e| PageIndexError::io(path, e))?;
+    serde_yaml::from_str(&content).map_err(|e| {
+        PageIndexError::Config(format!("Invalid glossary '{}': {}", path.display(), e))
+    })
+}
+
+/// The entries of `glossaries` (term -> definition) whose term appears in
+/// `text` as whole words, ignoring case, in the order they appear. A term
+/// defined in several glossaries takes its first definition.
+pub fn terms_in(text: &str, glossaries: &[&BTreeMap<String, String>]) -> Vec<(String, String)> {
+    let text = text.to_lowercase();
+    let mut found: Vec<(usize, String, String)> = Vec::new();
+    for glossary in glossaries {
+        for (term, definition) in glossary.iter() {
+            let needle = term.trim().to_lowercase();
+            if needle.is_empty() || found.iter().any(|(_, t, _)| t.to_lowercase() == needle) {
+                continue;
+            }
+            let position = text.match_indices(&needle).map(|(i, _)| i).find(|&i| {
+                let before = text[..i].chars().next_back();
+                let after = text[i + needle.len()..].chars().next();
+                !before.is_some_and(char::is_alphanumeric)
+                    && !after.is_some_and(char::is_alphanumeric)
+            });
+            if let Some(position) = position {
+                found.push((position, term.trim().to_string(), definition.clone()));
+            }
+        }
+    }
+    found.sort_by_key(|(position, _, _)| *position);
+    found.into_iter().map(|(_, t, d)| (t, d)).collect()
+}
+
+/// A query followed by the definitions of the terms it uses, as it is put
+/// into prompts. Unchanged without terms.
+pub fn with_definitions<'q>(query: &'q str, terms: &[(String, String)]) -> Cow<'q, str> {
+    if terms.is_empty() {
+        return Cow::Borrowed(query);
+    }
+    let definitions: Vec<_> = terms
+        .iter()
+        .map(|(term, definition)| format!("- {}: {}", term, definition.trim()))
+        .collect();
+    Cow::Owned(format!(
+        "{}\n\nDefinitions of terms used in the query:\n{}",
+        query,
+        definitions.join("\n")
+    ))
+}
+
+/// Split leading and trailing punctuation off a chunk of a query.
+fn trim_word(chunk: &str) -> (&str, &str, &str) {
+    let word = chunk.trim_matches(|c: char| !c.is_alphanumeric());
+    let start = chunk.find(word).unwrap_or(0);
+    (&chunk[..start], &chunk[start + word.len()..], word)
+}
+
+/// Words known to be spelled right, with how often the tree uses them.
+#[derive(Default)]
+struct Vocabulary {
+    words: HashMap<String, usize>,
+}
+
+impl Vocabulary {
+    /// The words of a tree's titles and summaries, and of `extra` text.
+    fn of<'a>(tree: &DocumentTree, extra: impl Iterator<Item = &'a String>) -> Self {
+        fn add_node(vocabulary: &mut Vocabulary, node: &TreeNode) {
+            vocabulary.add(&node.title);
+            if let Some(summary) = &node.summary {
+                vocabulary.add(summary);
+            }
+            for child in &node.nodes {
+                add_node(vocabulary, child);
+            }
+        }
+
+        let mut vocabulary = Self::default();
+        for node in &tree.nodes {
+            add_node(&mut vocabulary, node);
+        }
+        for text in extra {
+            vocabulary.add(text);
+        }
+        vocabulary
+    }
+
+    fn add(&mut self, text: &str) {
+        for word in text.split(|c: char| !c.is_alphabetic()) {
+            if word.chars().count() > 1 {
+                *self.words.entry(word.to_lowercase()).or_default() += 1;
+            }
+        }
+    }
+
+    /// The closest known word to `word` within the allowed edits, most
+    /// used first, or `word` itself. Short words, known words, acronyms and
+    /// words with digits are left alone.
+    fn correct(&self, word: &str) -> String {
+        let length = word.chars().count();
+        let mut chars = word.chars();
+        let first = chars.next();
+        if length < MIN_CORRECTED_CHARS
+            || !word.chars().all(char::is_alphabetic)
+            || chars.any(char::is_uppercase)
+        {
+            return word.to_string();
+        }
+        let lower = word.to_lowercase();
+        if self.words.contains_key(&lower) || COMMON_WORDS.contains(&lower.as_str()) {
+            return word.to_string();
+        }
+
+        let max_edits = if length >= TWO_EDIT_CHARS { 2 } else { 1 };
+        let best = self
+            .words
+            .iter()
+            .filter(|(known, _)| known.chars().count().abs_diff(length) <= max_edits)
+            .map(|(known, count)| (edit_distance(&lower, known), known, *count))
+            .filter(|(distance, _, _)| *distance <= max_edits)
+            .min_by(|a, b| a.0.cmp(&b.0).then(b.2.cmp(&a.2)).then(a.1.cmp(b.1)));
+        match best {
+            Some((_, known, _)) if first.is_some_and(char::is_uppercase) => {
+                let mut chars = known.chars();
+                chars
+                    .next()
+                    .map(|c| c.to_uppercase().chain(chars).collect())
+                    .unwrap_or_default()
+            }
+            Some((_, known, _)) => known.clone(),
+            None => word.to_string(),
+        }
+    }
+}
+
+/// Edits (insertions, deletions, substitutions and swaps of adjacent
+/// characters) turning `a` into `b`.
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = distance;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn test_tree() -> DocumentTree {
+        let mut maintenance = TreeNode::new("Maintenance", 1, 4);
+        maintenance.summary = Some("Cleaning the filter and replacing the pump seal.".to_string());
+        maintenance.add_child(TreeNode::new("Filter cleaning", 2, 3));
+        DocumentTree::new("manual", vec![maintenance], 4)
+    }
+
+    #[test]
+    fn test_normalize_query() {
+        let tree = test_tree();
+        let normalizer = QueryNormalizer::new()
+            .with_spell_correction(true)
+            .with_glossary([("MTBF", "mean time between failures")]);
+
+        assert_eq!(
+            normalizer.normalize("  How do I clean the  filtr?", &tree),
+            "How do I clean the filter?"
+        );
+        assert_eq!(
+            normalizer.normalize("Maintanence schedule for the pupm", &tree),
+            "Maintenance schedule for the pump"
+        );
+        // Glossary terms are expanded whatever their case; acronyms,
+        // numbers and common words are left alone
+        assert_eq!(
+            normalizer.normalize("What is the mtbf, per RAID 5?", &tree),
+            "What is the mean time between failures (mtbf), per RAID 5?"
+        );
+        assert_eq!(
+            normalizer.normalize("WHERE IS THE FILTR", &tree),
+            "where is the filter"
+        );
+
+        // Without spell correction, only whitespace, casing and glossary
+        let plain = QueryNormalizer::new();
+        assert_eq!(
+            plain.normalize("clean the  filtr", &tree),
+            "clean the filtr"
+        );
+
+        assert_eq!(edit_distance("pupm", "pump"), 1);
+        assert_eq!(edit_distance("maintanence", "maintenance"), 2);
+    }
+
+    #[test]
+    fn test_terms_in() {
+        let tree_terms = BTreeMap::from([
+            (
+                "DSCR".to_string(),
+                "Debt service coverage ratio.".to_string(),
+            ),
+            (
+                "EBITDA add-backs".to_string(),
+                "Adjustments to EBITDA.".to_string(),
+            ),
+            ("cap".to_string(), "Interest rate cap.".to_string()),
+        ]);
+        let corpus_terms = BTreeMap::from([("dscr".to_string(), "Corpus definition.".to_string())]);
+
+        let terms = terms_in(
+            "Which EBITDA add-backs affect the dscr covenant? (capex excluded)",
+            &[&tree_terms, &corpus_terms],
+        );
+        assert_eq!(
+            terms,
+            vec![
+                (
+                    "EBITDA add-backs".to_string(),
+                    "Adjustments to EBITDA.".to_string()
+                ),
+                (
+                    "DSCR".to_string(),
+                    "Debt service coverage ratio.".to_string()
+                ),
+            ]
+        );
+        assert_eq!(
+            with_definitions("What is the DSCR?", &terms[1..]),
+            "What is the DSCR?\n\nDefinitions of terms used in the query:\n\
+             - DSCR: Debt service coverage ratio."
+        );
+        assert!(matches!(with_definitions("q", &[]), Cow::Borrowed("q")));
+    }
+
+    #[test]
+    fn test_for_index() {
+        let dir = TempDir::new().unwrap();
+        let index = dir.path().join("tree.json");
+        assert!(normalizer_path_for(&index).ends_with("tree.json.query"));
+        assert_eq!(QueryNormalizer::for_index(&index).unwrap(), None);
+
+        std::fs::write(
+            normalizer_path_for(&index),
+            "spell_correct: true\nglossary:\n  PSU: power supply unit\n",
+        )
+        .unwrap();
+        let normalizer = QueryNormalizer::for_index(&index).unwrap().unwrap();
+        assert!(normalizer.spell_correct);
+        assert_eq!(normalizer.glossary["PSU"], "power supply unit");
+
+        let glossary = dir.path().join("glossary.json");
+        std::fs::write(&glossary, r#"{"e.g.": "for example"}"#).unwrap();
+        let loaded = load_glossary(&glossary).unwrap();
+        let normalizer = QueryNormalizer::new().with_glossary(loaded);
+        assert_eq!(
+            normalizer.normalize("e.g. a fuse", &test_tree()),
+            "for example (e.g.) a fuse"
+        );
+    }
+}
@@ -0,0 +1,195 @@
+//! Retrieved sections and tree nodes as LangChain-style documents.
+//!
+//! Each section becomes `{"id", "page_content", "metadata"}`, the JSON shape
+//! of a LangChain `Document` (`Document(**doc)` in Python). LlamaIndex reads
+//! the same shape with `Document.from_langchain_format`.
+
+use crate::document::Document;
+use crate::search::{Relevance, SearchResult, TextSpan, section_content};
+use crate::tree::{DocumentTree, TreeNode};
+use serde::{Deserialize, Serialize};
+
+/// A section in LangChain `Document` form.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetrieverDocument {
+    /// Node ID when the tree has one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// Section text if the source document is available, otherwise its
+    /// summary (or, for search results, the reason it matched), otherwise
+    /// its title.
+    pub page_content: String,
+    pub metadata: RetrieverMetadata,
+}
+
+/// Metadata attached to each [`RetrieverDocument`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RetrieverMetadata {
+    /// Document name (LangChain's conventional `source` key).
+    pub source: String,
+    /// Section title.
+    pub title: String,
+    /// Titles from the top-level section down to this one.
+    pub path: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub structure: Option<String>,
+    /// First page (1-indexed).
+    pub start_index: usize,
+    /// Last page (inclusive).
+    pub end_index: usize,
+    /// Search relevance (search results only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub relevance: Option<Relevance>,
+    /// Relevance as a score in (0, 1] (search results only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f32>,
+    /// Why the section matched (search results only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Where the ranker's snippet is in `page_content`, as character
+    /// offsets (ranked search results only).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snippet_span: Option<TextSpan>,
+}
+
+/// Every node of a tree, depth first, as documents. With the source
+/// document, each node's page content is its section text.
+pub fn tree_documents(tree: &DocumentTree, document: Option<&Document>) -> Vec<RetrieverDocument> {
+    fn walk(
+        node: &TreeNode,
+        path: &mut Vec<String>,
+        source: &str,
+        document: Option<&Document>,
+        out: &mut Vec<RetrieverDocument>,
+    ) {
+        path.push(node.title.clone());
+        let page_content = match document {
+            Some(document) => section_content(document, node.start_index, node.end_index),
+            None => node.summary.clone().unwrap_or_else(|| node.title.clone()),
+        };
+        out.push(RetrieverDocument {
+            id: node.node_id.clone(),
+            page_content,
+            metadata: RetrieverMetadata {
+                source: source.to_string(),
+                title: node.title.clone(),
+                path: path.clone(),
+                structure: node.structure.clone(),
+                start_index: node.start_index,
+                end_index: node.end_index,
+                relevance: None,
+                score: None,
+                reason: None,
+                snippet_span: None,
+            },
+        });
+        for child in &node.nodes {
+            walk(child, path, source, document, out);
+        }
+        path.pop();
+    }
+
+    let mut out = Vec::new();
+    for node in &tree.nodes {
+        walk(node, &mut Vec::new(), &tree.name, document, &mut out);
+    }
+    out
+}
+
+/// Search results as documents, best first. Results are matched back to
+/// tree nodes by title and page range to fill in IDs and paths.
+pub fn result_documents(tree: &DocumentTree, results: &[SearchResult]) -> Vec<RetrieverDocument> {
+    let nodes = tree_documents(tree, None);
+    results
+        .iter()
+        .map(|result| {
+            let node = nodes.iter().find(|n| {
+                n.metadata.title == result.title
+                    && n.metadata.start_index == result.start_index
+                    && n.metadata.end_index == result.end_index
+            });
+            RetrieverDocument {
+                id: node.and_then(|n| n.id.clone()),
+                page_content: result
+                    .content
+                    .clone()
+                    .unwrap_or_else(|| result.reason.clone()),
+                metadata: RetrieverMetadata {
+                    source: tree.name.clone(),
+                    title: result.title.clone(),
+                    path: node
+                        .map_or_else(|| vec![result.title.clone()], |n| n.metadata.path.clone()),
+                    structure: node.and_then(|n| n.metadata.structure.clone()),
+                    start_index: result.start_index,
+                    end_index: result.end_index,
+                    relevance: Some(result.relevance),
+                    score: Some(result.relevance.score() as f32 / Relevance::High.score() as f32),
+                    reason: Some(result.reason.clone()),
+                    snippet_span: result.content.as_ref().and(result.snippet_span),
+                },
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::Page;
+
+    fn test_tree() -> DocumentTree {
+        let mut intro = TreeNode::new("Introduction", 1, 2).with_structure("1");
+        intro.node_id = Some("0000".to_string());
+        intro.summary = Some("Overview.".to_string());
+        let mut background = TreeNode::new("Background", 2, 2).with_structure("1.1");
+        background.node_id = Some("0001".to_string());
+        intro.add_child(background);
+        DocumentTree::new("report", vec![intro], 2)
+    }
+
+    #[test]
+    fn test_tree_documents() {
+        let tree = test_tree();
+        let docs = tree_documents(&tree, None);
+        assert_eq!(docs.len(), 2);
+        assert_eq!(docs[0].page_content, "Overview.");
+        assert_eq!(docs[1].page_content, "Background");
+        assert_eq!(docs[1].metadata.path, vec!["Introduction", "Background"]);
+        assert_eq!(docs[1].id.as_deref(), Some("0001"));
+
+        let document = Document::new(
+            "report",
+            vec![
+                Page::new(1, "First page.".to_string()),
+                Page::new(2, "Second page.".to_string()),
+            ],
+        );
+        let docs = tree_documents(&tree, Some(&document));
+        assert!(docs[0].page_content.contains("First page."));
+        assert_eq!(docs[1].page_content, "Second page.");
+
+        let json = serde_json::to_value(&docs[1]).unwrap();
+        assert_eq!(json["metadata"]["source"], "report");
+        assert!(json["metadata"].get("relevance").is_none());
+    }
+
+    #[test]
+    fn test_result_documents() {
+        let results = vec![SearchResult {
+            title: "Background".to_string(),
+            start_index: 2,
+            end_index: 2,
+            relevance: Relevance::Medium,
+            reason: "Explains the setting".to_string(),
+            content: None,
+            snippet: None,
+            snippet_span: None,
+        }];
+        let docs = result_documents(&test_tree(), &results);
+        assert_eq!(docs[0].id.as_deref(), Some("0001"));
+        assert_eq!(docs[0].page_content, "Explains the setting");
+        assert_eq!(docs[0].metadata.path, vec!["Introduction", "Background"]);
+        assert_eq!(docs[0].metadata.relevance, Some(Relevance::Medium));
+        assert!((docs[0].metadata.score.unwrap() - 2.0 / 3.0).abs() < 1e-6);
+    }
+}
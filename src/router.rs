@@ -0,0 +1,483 @@
+//! Query routing for corpora of mixed document types.
+//!
+//! In a corpus of contracts, manuals and emails, most documents cannot
+//! answer a given query, and searching every tree costs an LLM call each.
+//! A [`QueryRouter`] first classifies the query into the categories of a
+//! [`Taxonomy`], then searches only the trees whose
+//! [metadata](DocumentTree::metadata) puts them in one of those categories.
+//!
+//! The taxonomy is configurable, usually from a YAML file:
+//!
+//! ```yaml
+//! key: type            # metadata key to route on (default: type)
+//! categories:
+//!   - name: contract
+//!     description: Agreements, terms and obligations between parties
+//!   - name: manual
+//!     description: Product documentation and instructions
+//!     values: [manual, guide]   # metadata values in the category (default: the name)
+//! ```
+//!
+//! ```no_run
+//! use rust_page_indexer::router::{QueryRouter, Taxonomy};
+//! use rust_page_indexer::{Config, LlmClient, TreeSearcher, load_tree};
+//! use std::path::Path;
+//!
+//! # async fn run() -> rust_page_indexer::Result<()> {
+//! let client = LlmClient::new(Config::load()?.llm);
+//! let taxonomy = Taxonomy::load(Path::new("taxonomy.yaml"))?;
+//! let trees = vec![load_tree(Path::new("data/indexes/lease.json"))?];
+//! let routed = QueryRouter::new(client.clone(), taxonomy)
+//!     .search(&TreeSearcher::new(client), &trees, "When can the tenant terminate?")
+//!     .await?;
+//! for result in &routed.results {
+//!     println!("{}: {}", result.document, result.result.title);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{PageIndexError, Result};
+use crate::llm::{LlmClient, Prompts};
+use crate::search::{SearchResult, TreeSearcher};
+use crate::tree::DocumentTree;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::{debug, info};
+
+/// Metadata key documents are routed on unless the taxonomy sets another.
+pub const DEFAULT_ROUTING_KEY: &str = "type";
+
+fn default_key() -> String {
+    DEFAULT_ROUTING_KEY.to_string()
+}
+
+/// A kind of document a query can be routed to.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Category {
+    pub name: String,
+    /// What documents in the category contain, shown to the classifier.
+    #[serde(default)]
+    pub description: String,
+    /// Metadata values of documents in the category (default: the name).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<String>,
+}
+
+impl Category {
+    pub fn new(name: impl Into<String>, description: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            values: Vec::new(),
+        }
+    }
+
+    /// Set the metadata values of documents in the category.
+    pub fn with_values(mut self, values: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.values = values.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Whether a document's metadata value puts it in this category
+    /// (case-insensitive).
+    pub fn matches(&self, value: &str) -> bool {
+        if self.values.is_empty() {
+            return self.name.eq_ignore_ascii_case(value.trim());
+        }
+        self.values
+            .iter()
+            .any(|v| v.eq_ignore_ascii_case(value.trim()))
+    }
+}
+
+/// The categories queries are classified into, and the metadata key that
+/// assigns documents to them.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Taxonomy {
+    #[serde(default = "default_key")]
+    pub key: String,
+    pub categories: Vec<Category>,
+}
+
+impl Taxonomy {
+    /// A taxonomy routing on [`DEFAULT_ROUTING_KEY`].
+    pub fn new(categories: Vec<Category>) -> Self {
+        Self {
+            key: default_key(),
+            categories,
+        }
+    }
+
+    /// Route on a different metadata key.
+    pub fn with_key(mut self, key: impl Into<String>) -> Self {
+        self.key = key.into();
+        self
+    }
+
+    /// Load a taxonomy from a YAML (or JSON) file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| PageIndexError::io(path, e))?;
+        let taxonomy: Taxonomy = serde_yaml::from_str(&content)
+            .map_err(|e| PageIndexError::Config(format!("Failed to parse taxonomy: {}", e)))?;
+        taxonomy.validate()?;
+        Ok(taxonomy)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.categories.is_empty() {
+            return Err(PageIndexError::Config(
+                "taxonomy has no categories".to_string(),
+            ));
+        }
+        for (i, category) in self.categories.iter().enumerate() {
+            if category.name.trim().is_empty() {
+                return Err(PageIndexError::Config(format!(
+                    "taxonomy category {} has no name",
+                    i + 1
+                )));
+            }
+            if self.categories[..i]
+                .iter()
+                .any(|c| c.name.eq_ignore_ascii_case(&category.name))
+            {
+                return Err(PageIndexError::Config(format!(
+                    "duplicate taxonomy category '{}'",
+                    category.name
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// The category with this name (case-insensitive).
+    pub fn category(&self, name: &str) -> Option<&Category> {
+        self.categories
+            .iter()
+            .find(|c| c.name.eq_ignore_ascii_case(name.trim()))
+    }
+
+    /// The category a tree's metadata puts it in, if any.
+    pub fn category_of(&self, tree: &DocumentTree) -> Option<&Category> {
+        let value = tree.metadata.get(&self.key)?;
+        self.categories.iter().find(|c| c.matches(value))
+    }
+}
+
+/// Which trees a query was routed to.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct Route {
+    /// Categories the query was classified into, most likely first.
+    pub categories: Vec<String>,
+    /// Indices of the selected trees.
+    pub selected: Vec<usize>,
+    /// Whether no tree matched and every tree was selected instead.
+    pub fallback: bool,
+}
+
+/// A search result and the document it came from.
+#[derive(Debug, Clone, Serialize)]
+pub struct RoutedResult {
+    /// Name of the tree the result belongs to.
+    pub document: String,
+    #[serde(flatten)]
+    pub result: SearchResult,
+}
+
+/// The route a query took and the results of searching it.
+#[derive(Debug, Clone, Serialize)]
+pub struct RoutedSearch {
+    pub route: Route,
+    pub results: Vec<RoutedResult>,
+}
+
+/// Classifies queries and narrows a corpus to the documents that match.
+pub struct QueryRouter {
+    client: LlmClient,
+    taxonomy: Taxonomy,
+}
+
+impl QueryRouter {
+    /// Create a router classifying with the given client.
+    pub fn new(client: LlmClient, taxonomy: Taxonomy) -> Self {
+        Self { client, taxonomy }
+    }
+
+    pub fn taxonomy(&self) -> &Taxonomy {
+        &self.taxonomy
+    }
+
+    /// Names of the categories that could answer `query`, most likely
+    /// first. Names the taxonomy doesn't have are dropped.
+    pub async fn classify(&self, query: &str) -> Result<Vec<String>> {
+        let categories = self
+            .taxonomy
+            .categories
+            .iter()
+            .map(|c| {
+                if c.description.is_empty() {
+                    format!("- {}", c.name)
+                } else {
+                    format!("- {}: {}", c.name, c.description)
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let prompt = Prompts::route_query()
+            .replace("{query}", query)
+            .replace("{categories}", &categories);
+
+        let response = self
+            .client
+            .complete(Some(Prompts::system_document_analyzer()), &prompt)
+            .await?;
+        self.parse_classification(&response)
+    }
+
+    fn parse_classification(&self, response: &str) -> Result<Vec<String>> {
+        #[derive(Deserialize)]
+        struct Classification {
+            #[serde(default)]
+            #[allow(dead_code)]
+            thinking: Option<String>,
+            #[serde(default)]
+            categories: Vec<String>,
+        }
+
+        let json_str = TreeSearcher::extract_json(response);
+        let parsed: Classification = serde_json::from_str(&json_str).map_err(|e| {
+            PageIndexError::LlmParse(format!("Failed to parse query classification: {}", e))
+        })?;
+
+        let mut names: Vec<String> = Vec::new();
+        for name in parsed.categories {
+            match self.taxonomy.category(&name) {
+                Some(category) if !names.contains(&category.name) => {
+                    names.push(category.name.clone())
+                }
+                Some(_) => {}
+                None => debug!(category = %name, "classifier returned unknown category"),
+            }
+        }
+        Ok(names)
+    }
+
+    /// The trees in any of `categories`. Trees without the routing key are
+    /// always selected, since nothing says they can't answer; when no tree
+    /// is selected at all, every tree is.
+    pub fn select(&self, categories: &[String], trees: &[DocumentTree]) -> Route {
+        let selected: Vec<usize> = trees
+            .iter()
+            .enumerate()
+            .filter(|(_, tree)| match tree.metadata.get(&self.taxonomy.key) {
+                None => true,
+                Some(value) => categories.iter().any(|name| {
+                    self.taxonomy
+                        .category(name)
+                        .is_some_and(|c| c.matches(value))
+                }),
+            })
+            .map(|(i, _)| i)
+            .collect();
+
+        let fallback = selected.is_empty();
+        Route {
+            categories: categories.to_vec(),
+            selected: if fallback {
+                (0..trees.len()).collect()
+            } else {
+                selected
+            },
+            fallback,
+        }
+    }
+
+    /// Classify `query` and select the trees that could answer it.
+    pub async fn route(&self, query: &str, trees: &[DocumentTree]) -> Result<Route> {
+        let categories = self.classify(query).await?;
+        let route = self.select(&categories, trees);
+        info!(
+            categories = ?route.categories,
+            selected = route.selected.len(),
+            documents = trees.len(),
+            fallback = route.fallback,
+            "routed query"
+        );
+        Ok(route)
+    }
+
+    /// Route `query`, then search each selected tree with `searcher`. The
+    /// results of all trees are merged by relevance (ties keep corpus
+    /// order) and cut to the searcher's `top_k`.
+    pub async fn search(
+        &self,
+        searcher: &TreeSearcher,
+        trees: &[DocumentTree],
+        query: &str,
+    ) -> Result<RoutedSearch> {
+        let route = self.route(query, trees).await?;
+
+        let mut results = Vec::new();
+        for &i in &route.selected {
+            let tree = &trees[i];
+            results.extend(
+                searcher
+                    .search(tree, query)
+                    .await?
+                    .into_iter()
+                    .map(|result| RoutedResult {
+                        document: tree.name.clone(),
+                        result,
+                    }),
+            );
+        }
+        results.sort_by_key(|r| std::cmp::Reverse(r.result.relevance.score()));
+        results.truncate(searcher.options().top_k);
+
+        Ok(RoutedSearch { route, results })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::replay_replies;
+    use crate::golden::PromptCapture;
+    use crate::search::Relevance;
+    use crate::tree::TreeNode;
+
+    fn taxonomy() -> Taxonomy {
+        Taxonomy::new(vec![
+            Category::new("contract", "Agreements between parties"),
+            Category::new("manual", "Product instructions").with_values(["manual", "guide"]),
+            Category::new("email", ""),
+        ])
+    }
+
+    fn corpus() -> Vec<DocumentTree> {
+        let tree =
+            |name: &str, title: &str| DocumentTree::new(name, vec![TreeNode::new(title, 1, 1)], 1);
+        vec![
+            tree("lease", "Termination").with_metadata("type", "contract"),
+            tree("pump", "Cleaning the Filter").with_metadata("type", "Guide"),
+            tree("thread", "Re: invoice").with_metadata("type", "email"),
+            tree("notes", "Misc"),
+        ]
+    }
+
+    fn client_for(responses: &[&str]) -> (PromptCapture, LlmClient, tempfile::TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("router.fixture.json");
+        let capture = PromptCapture::new(replay_replies(&path, responses));
+        let client = capture.client().unwrap();
+        (capture, client, dir)
+    }
+
+    #[test]
+    fn test_taxonomy() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("taxonomy.yaml");
+        std::fs::write(
+            &path,
+            "key: kind\ncategories:\n  - name: contract\n    description: Agreements\n  - name: manual\n    values: [manual, guide]\n",
+        )
+        .unwrap();
+        let loaded = Taxonomy::load(&path).unwrap();
+        assert_eq!(loaded.key, "kind");
+        assert!(loaded.category("Manual").unwrap().matches("GUIDE"));
+        assert!(!loaded.category("manual").unwrap().matches("contract"));
+        assert!(loaded.category("email").is_none());
+
+        let corpus = corpus();
+        let routed = taxonomy();
+        assert_eq!(routed.key, DEFAULT_ROUTING_KEY);
+        assert_eq!(routed.category_of(&corpus[1]).unwrap().name, "manual");
+        assert!(routed.category_of(&corpus[3]).is_none());
+
+        for bad in [
+            "categories: []\n",
+            "categories:\n  - name: a\n  - name: A\n",
+            "categories: nope\n",
+        ] {
+            std::fs::write(&path, bad).unwrap();
+            assert!(
+                matches!(Taxonomy::load(&path), Err(PageIndexError::Config(_))),
+                "{}",
+                bad
+            );
+        }
+    }
+
+    #[test]
+    fn test_select() {
+        let (_capture, client, _dir) = client_for(&[]);
+        let router = QueryRouter::new(client, taxonomy());
+        let corpus = corpus();
+
+        // Untagged documents are always searched
+        let route = router.select(&["manual".to_string()], &corpus);
+        assert_eq!(route.selected, vec![1, 3]);
+        assert!(!route.fallback);
+
+        let route = router.select(&["contract".to_string(), "email".to_string()], &corpus);
+        assert_eq!(route.selected, vec![0, 2, 3]);
+
+        // Nothing matches: search everything
+        let tagged = &corpus[..3];
+        let route = router.select(&[], tagged);
+        assert_eq!(route.selected, vec![0, 1, 2]);
+        assert!(route.fallback);
+
+        let names = router
+            .parse_classification(
+                r#"{"thinking": "t", "categories": ["Manual", "memo", "manual", "email"]}"#,
+            )
+            .unwrap();
+        assert_eq!(names, vec!["manual", "email"]);
+        assert!(router.parse_classification("not json").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_routed_search() {
+        let (capture, client, _dir) = client_for(&[
+            r#"{"thinking": "Cleaning instructions are in manuals.", "categories": ["manual"]}"#,
+            r#"{"relevant_sections": [{"title": "Cleaning the Filter", "start_index": 1, "end_index": 1, "relevance": "high", "reason": "Covers cleaning."}]}"#,
+            r#"{"relevant_sections": [{"title": "Misc", "start_index": 1, "end_index": 1, "relevance": "low", "reason": "Might mention it."}]}"#,
+        ]);
+        let router = QueryRouter::new(client.clone(), taxonomy());
+        let routed = router
+            .search(
+                &TreeSearcher::new(client),
+                &corpus(),
+                "How do I clean the filter?",
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(routed.route.categories, vec!["manual"]);
+        assert_eq!(routed.route.selected, vec![1, 3]);
+        let found: Vec<(&str, Relevance)> = routed
+            .results
+            .iter()
+            .map(|r| (r.document.as_str(), r.result.relevance))
+            .collect();
+        assert_eq!(
+            found,
+            vec![("pump", Relevance::High), ("notes", Relevance::Low)]
+        );
+
+        // One classification and one search per selected tree; the
+        // classifier sees the taxonomy
+        let prompts = capture.prompts();
+        assert_eq!(prompts.len(), 3);
+        let classification = prompts
+            .iter()
+            .find_map(|p| {
+                let content = p.request["messages"][1]["content"].as_str()?;
+                content.contains("Document types:").then_some(content)
+            })
+            .unwrap();
+        assert!(classification.contains("- manual: Product instructions"));
+        assert!(classification.contains("- email\n"));
+    }
+}
@@ -4,11 +4,25 @@
 //! given a query and a document tree, use LLM reasoning to find
 //! the most relevant sections.
 
+use crate::config::OperationModels;
 use crate::document::Document;
 use crate::error::{PageIndexError, Result};
 use crate::llm::{LlmClient, Prompts};
-use crate::tree::DocumentTree;
+use crate::progress::{ProgressCallback, ProgressEvent, emit};
+use crate::query::{QueryNormalizer, terms_in, with_definitions};
+use crate::store::DocumentStore;
+#[cfg(not(target_arch = "wasm32"))]
+use crate::stream::{EventStream, SearchEvent, forward_progress};
+use crate::tree::{DocumentTree, OutlineOptions};
+use clap::ValueEnum;
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tracing::field::Empty;
+use tracing::{Instrument, debug, info_span, instrument, warn};
 
 /// A search result from tree-based search.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -26,10 +40,92 @@ pub struct SearchResult {
     /// Extracted content from the section (if available).
     #[serde(skip_serializing_if = "Option::is_none")]
     pub content: Option<String>,
+    /// The part of the content that answers the query, quoted by the
+    /// [ranker](TreeSearcher::with_ranker).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+    /// Where the snippet is in `content`, for highlighting it, when it was
+    /// found there.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snippet_span: Option<TextSpan>,
 }
 
-/// Relevance level for search results.
+/// A range of text, as character (not byte) offsets: `start` inclusive,
+/// `end` exclusive.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TextSpan {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Find a quoted snippet in a section's text. Matches exactly, or else
+/// ignoring differences in whitespace (quotes often reflow line breaks);
+/// surrounding quote marks and ellipses are ignored.
+pub fn locate_snippet(content: &str, snippet: &str) -> Option<TextSpan> {
+    let snippet = snippet
+        .trim()
+        .trim_matches(|c| matches!(c, '"' | '\u{201c}' | '\u{201d}' | '\u{2026}'))
+        .trim_start_matches("...")
+        .trim_end_matches("...")
+        .trim();
+    if snippet.is_empty() {
+        return None;
+    }
+    if let Some(byte) = content.find(snippet) {
+        let start = content[..byte].chars().count();
+        return Some(TextSpan {
+            start,
+            end: start + snippet.chars().count(),
+        });
+    }
+
+    // Collapse whitespace runs to one space, remembering where each kept
+    // character came from
+    let mut collapsed = Vec::new();
+    let mut origins = Vec::new();
+    for (i, c) in content.chars().enumerate() {
+        if c.is_whitespace() {
+            if collapsed.last().is_none_or(|&last: &char| last == ' ') {
+                continue;
+            }
+            collapsed.push(' ');
+        } else {
+            collapsed.push(c);
+        }
+        origins.push(i);
+    }
+    let wanted: Vec<char> = snippet
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .chars()
+        .collect();
+    let at = collapsed
+        .windows(wanted.len())
+        .position(|window| window == wanted.as_slice())?;
+    Some(TextSpan {
+        start: origins[at],
+        end: origins[at + wanted.len() - 1] + 1,
+    })
+}
+
+/// A paragraph pinpointed within a section by [`TreeSearcher::zoom`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Passage {
+    /// Page the paragraph is on (1-indexed).
+    pub page: usize,
+    /// Paragraph number within the page (1-indexed).
+    pub paragraph: usize,
+    /// Text of the paragraph, taken from the document.
+    pub text: String,
+    /// Relevance level.
+    pub relevance: Relevance,
+    /// Reason for relevance.
+    pub reason: String,
+}
+
+/// Relevance level for search results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ValueEnum)]
 #[serde(rename_all = "lowercase")]
 pub enum Relevance {
     High,
@@ -39,6 +135,7 @@ pub enum Relevance {
 
 impl Relevance {
     /// Convert from string (case-insensitive).
+    #[allow(clippy::should_implement_trait)]
     pub fn from_str(s: &str) -> Self {
         match s.to_lowercase().as_str() {
             "high" => Relevance::High,
@@ -66,6 +163,56 @@ pub struct SearchOptions {
     pub min_relevance: Relevance,
     /// Whether to include section content in results.
     pub include_content: bool,
+    /// Describe nodes by their prefix summaries (ancestor titles plus
+    /// summary) in the search prompt, where the tree has them.
+    pub prefix_summaries: bool,
+    /// Have the LLM read each result's content and drop results that do
+    /// not address the query, demoting those that only partly do. Takes
+    /// effect when results have content
+    /// ([`TreeSearcher::search_with_content`]).
+    pub verify_results: bool,
+    /// Describe the tree as a compact outline of about this many tokens at
+    /// most (see [`DocumentTree::to_outline`]) instead of the full search
+    /// format. Detail is dropped from the deepest levels up to fit.
+    pub outline_tokens: Option<usize>,
+    /// Also search this many LLM-written paraphrases of the query, against
+    /// the tree's titles only, and fuse all result lists by reciprocal
+    /// rank. Improves recall for vague queries at the cost of one extra
+    /// call plus one per paraphrase. 0 disables.
+    pub paraphrases: usize,
+    /// Show the LLM sections [excluded](crate::tree::TreeNode::excluded) as
+    /// non-content (references, index, ...), which are left out by
+    /// default.
+    pub include_excluded: bool,
+    /// The caller's access labels. When set, sections they may not see
+    /// (see [`DocumentTree::visible_to`]) are removed before prompts are
+    /// built, and results that don't match a visible section are dropped.
+    /// `None` sees every section.
+    pub acl_labels: Option<Vec<String>>,
+    /// Time the whole search may take, across all of its LLM calls
+    /// (navigation, paraphrases, ranking, verification). When it passes,
+    /// the search stops and returns the best results gathered so far,
+    /// marked [truncated](SearchOutcome::truncated): the navigation
+    /// shortlist if ranking or verification was cut, the results of the
+    /// paraphrases searched so far, or none if navigation itself was.
+    /// Fetching section content is not bounded. Ignored in the browser.
+    pub deadline: Option<Duration>,
+    /// Tidy the query before searching: casing, glossary abbreviations
+    /// and, if enabled, misspelled words (see [`crate::query`]).
+    pub normalizer: Option<QueryNormalizer>,
+    /// Glossary shared by the trees searched, such as a corpus's, term to
+    /// definition. Definitions of the terms the query uses are added to
+    /// its prompts, along with those of the tree's own
+    /// [terms](DocumentTree::terms), which take precedence.
+    pub terms: BTreeMap<String, String>,
+    /// Among results of the same relevance, rank sections that took effect
+    /// later first (see [`DocumentTree::effective_date_of`]); undated
+    /// sections come last. For corpora where superseded versions of a
+    /// section are still in the tree.
+    pub prefer_recent: bool,
+    /// Leave out sections that took effect before this date (`YYYY-MM-DD`,
+    /// see [`DocumentTree::effective_since`]). Undated sections stay.
+    pub effective_since: Option<String>,
 }
 
 impl Default for SearchOptions {
@@ -74,37 +221,486 @@ impl Default for SearchOptions {
             top_k: 10,
             min_relevance: Relevance::Low,
             include_content: false,
+            prefix_summaries: false,
+            verify_results: false,
+            outline_tokens: None,
+            paraphrases: 0,
+            include_excluded: false,
+            acl_labels: None,
+            deadline: None,
+            normalizer: None,
+            terms: BTreeMap::new(),
+            prefer_recent: false,
+            effective_since: None,
+        }
+    }
+}
+
+/// Results of a search, and whether [`SearchOptions::deadline`] cut it
+/// short.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SearchOutcome {
+    pub results: Vec<SearchResult>,
+    /// The deadline passed before every step of the search ran, so the
+    /// results are the best found by then.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+}
+
+/// When a search has to be done by (see [`SearchOptions::deadline`]).
+#[derive(Debug, Clone, Copy)]
+struct Deadline {
+    #[cfg(not(target_arch = "wasm32"))]
+    at: Option<tokio::time::Instant>,
+}
+
+impl Deadline {
+    /// A deadline `limit` from now, or none.
+    #[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+    fn after(limit: Option<Duration>) -> Self {
+        Self {
+            #[cfg(not(target_arch = "wasm32"))]
+            at: limit.map(|limit| tokio::time::Instant::now() + limit),
+        }
+    }
+
+    /// Run one step of a search, or return `None` if the deadline passes
+    /// before it finishes. Steps are not started once it has passed.
+    async fn run<T>(&self, step: impl Future<Output = Result<T>>) -> Result<Option<T>> {
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(at) = self.at {
+            if tokio::time::Instant::now() >= at {
+                return Ok(None);
+            }
+            return match tokio::time::timeout_at(at, step).await {
+                Ok(result) => result.map(Some),
+                Err(_) => Ok(None),
+            };
+        }
+        step.await.map(Some)
+    }
+}
+
+/// What a tree offers searches beyond titles and page ranges. Detected on
+/// every search, so trees indexed before an enrichment existed (or
+/// without it, like [navigation-only](DocumentTree::to_navigation_only)
+/// trees) are searched with prompts that don't expect it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchCapabilities {
+    /// Some sections have summaries. Without any, the tree is searched by
+    /// titles with a prompt written for that
+    /// ([`Prompts::tree_search_titles`]).
+    pub summaries: bool,
+    /// Some sections have prefix summaries, which
+    /// [`SearchOptions::prefix_summaries`] describes sections by. Without
+    /// any, plain summaries are used.
+    pub prefix_summaries: bool,
+}
+
+impl SearchCapabilities {
+    /// Detect what a tree offers.
+    pub fn of(tree: &DocumentTree) -> Self {
+        Self {
+            summaries: tree.has_summaries(),
+            prefix_summaries: tree.has_prefix_summaries(),
         }
     }
+
+    /// The prompt for finding relevant sections in such a tree.
+    fn search_prompt(&self) -> &'static str {
+        if self.summaries {
+            Prompts::tree_search()
+        } else {
+            Prompts::tree_search_titles()
+        }
+    }
+}
+
+/// Words of each result's content shown to the verifier and the ranker.
+const VERIFY_CONTENT_WORDS: usize = 600;
+
+/// With a [ranker](TreeSearcher::with_ranker), navigation shortlists this
+/// many times [`SearchOptions::top_k`] sections for it to rank.
+pub const SHORTLIST_FACTOR: usize = 3;
+
+/// Rank offset in reciprocal rank fusion: a result at rank `r` (from 1)
+/// scores `1 / (RRF_K + r)` in each list it appears in.
+const RRF_K: f64 = 60.0;
+
+/// Text of a page range (1-indexed, inclusive) without the physical index
+/// tags.
+pub fn section_content(document: &Document, start: usize, end: usize) -> String {
+    document
+        .content_range(start, end)
+        .lines()
+        .filter(|line| !line.starts_with("<physical_index_"))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// The runs of pages in `start..=end` not in `hidden`, as inclusive ranges.
+fn visible_ranges(start: usize, end: usize, hidden: &BTreeSet<usize>) -> Vec<(usize, usize)> {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for page in (start..=end).filter(|p| !hidden.contains(p)) {
+        match ranges.last_mut() {
+            Some((_, last)) if *last + 1 == page => *last = page,
+            _ => ranges.push((page, page)),
+        }
+    }
+    ranges
+}
+
+/// Paragraphs of a page range (1-indexed, inclusive) as `(page, paragraph,
+/// text)`, numbering paragraphs from 1 on each page. Paragraphs are
+/// separated by blank lines.
+pub fn section_paragraphs(
+    document: &Document,
+    start: usize,
+    end: usize,
+) -> Vec<(usize, usize, String)> {
+    let mut paragraphs = Vec::new();
+    for page in document
+        .pages
+        .iter()
+        .filter(|p| p.number >= start && p.number <= end)
+    {
+        let mut number = 0;
+        let mut current: Vec<&str> = Vec::new();
+        for line in page.content.lines().chain(std::iter::once("")) {
+            if !line.trim().is_empty() {
+                current.push(line.trim_end());
+            } else if !current.is_empty() {
+                number += 1;
+                paragraphs.push((page.number, number, current.join("\n")));
+                current.clear();
+            }
+        }
+    }
+    paragraphs
+}
+
+/// A tree's descriptions for search prompts (see
+/// [`DocumentTree::format_for_search_with`]), rendered on first use and
+/// kept, so a server does not render a large tree again for every query.
+/// Belongs to one tree: only search that tree with it.
+#[derive(Debug, Default)]
+pub struct SearchFormats {
+    plain: OnceLock<String>,
+    prefixed: OnceLock<String>,
+}
+
+impl SearchFormats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The tree's description, with prefix summaries or not, rendered the
+    /// first time it is asked for.
+    pub fn get(&self, tree: &DocumentTree, prefix_summaries: bool) -> &str {
+        let cell = if prefix_summaries {
+            &self.prefixed
+        } else {
+            &self.plain
+        };
+        cell.get_or_init(|| tree.format_for_search_with(prefix_summaries))
+    }
+
+    /// Bytes of the descriptions rendered so far.
+    pub fn bytes(&self) -> usize {
+        [&self.plain, &self.prefixed]
+            .iter()
+            .filter_map(|cell| cell.get())
+            .map(String::len)
+            .sum()
+    }
 }
 
 /// Tree-based searcher using LLM reasoning.
 pub struct TreeSearcher {
     client: LlmClient,
+    ranker: Option<LlmClient>,
     options: SearchOptions,
+    progress: Option<ProgressCallback>,
+    formats: Option<Arc<SearchFormats>>,
 }
 
 impl TreeSearcher {
     /// Create a new tree searcher.
     pub fn new(client: LlmClient) -> Self {
+        Self::with_options(client, SearchOptions::default())
+    }
+
+    /// Create with custom options.
+    pub fn with_options(client: LlmClient, options: SearchOptions) -> Self {
         Self {
             client,
-            options: SearchOptions::default(),
+            ranker: None,
+            options,
+            progress: None,
+            formats: None,
         }
     }
 
-    /// Create with custom options.
-    pub fn with_options(client: LlmClient, options: SearchOptions) -> Self {
-        Self { client, options }
+    /// Create with the per-operation models of a config: `client`
+    /// switched to the navigation model, and the ranking model as
+    /// [ranker](Self::with_ranker) when one is set.
+    pub fn with_models(
+        client: &LlmClient,
+        models: &OperationModels,
+        options: SearchOptions,
+    ) -> Self {
+        let navigator = match &models.navigate {
+            Some(model) => client.with_model(model),
+            None => client.clone(),
+        };
+        let searcher = Self::with_options(navigator, options);
+        match &models.rank {
+            Some(model) => searcher.with_ranker(client.with_model(model)),
+            None => searcher,
+        }
+    }
+
+    /// Search in two stages: the searcher's client navigates the tree to a
+    /// shortlist of [`SHORTLIST_FACTOR`] times `top_k` sections, and
+    /// `ranker`, usually a stronger model, reads their content, ranks them
+    /// and quotes a [snippet](SearchResult::snippet) from each. Only the
+    /// shortlist reaches the ranker, so most tokens go to the cheaper
+    /// model. Takes effect when results have content
+    /// ([`search_with_content`](Self::search_with_content)), in place of
+    /// [`SearchOptions::verify_results`].
+    pub fn with_ranker(mut self, ranker: LlmClient) -> Self {
+        self.ranker = Some(ranker);
+        self
+    }
+
+    /// Describe the tree in search prompts from `formats` rather than
+    /// rendering it for each search. Only search the tree `formats`
+    /// belongs to; searches that show part of it (excluded sections, access
+    /// labels, dates, outlines) still render their own.
+    pub fn with_search_formats(mut self, formats: Arc<SearchFormats>) -> Self {
+        self.formats = Some(formats);
+        self
+    }
+
+    /// The options searches run with.
+    pub fn options(&self) -> &SearchOptions {
+        &self.options
+    }
+
+    /// Report search progress events to a callback.
+    pub fn with_progress(mut self, callback: ProgressCallback) -> Self {
+        self.progress = Some(callback);
+        self
     }
 
     /// Search the document tree for relevant sections.
     pub async fn search(&self, tree: &DocumentTree, query: &str) -> Result<Vec<SearchResult>> {
+        Ok(self.search_within(tree, query).await?.results)
+    }
+
+    /// [`search`](Self::search), reporting whether
+    /// [`SearchOptions::deadline`] cut it short.
+    pub async fn search_within(&self, tree: &DocumentTree, query: &str) -> Result<SearchOutcome> {
+        let deadline = Deadline::after(self.options.deadline);
+        let query = self.normalize(tree, query);
+        let outcome = self
+            .navigate(tree, &query, self.options.top_k, deadline)
+            .await?;
+        self.warn_truncated(&outcome);
+        Ok(outcome)
+    }
+
+    /// The query as searched, after [`SearchOptions::normalizer`]. Words
+    /// are corrected against the sections the caller may see only.
+    fn normalize<'q>(&self, tree: &DocumentTree, query: &'q str) -> Cow<'q, str> {
+        match &self.options.normalizer {
+            Some(normalizer) => Cow::Owned(normalizer.normalize(query, &self.searchable(tree))),
+            None => Cow::Borrowed(query),
+        }
+    }
+
+    /// Definitions of the terms the query uses, from the tree's glossary
+    /// and then [`SearchOptions::terms`].
+    fn definitions(&self, tree: &DocumentTree, query: &str) -> Vec<(String, String)> {
+        terms_in(query, &[&tree.terms, &self.options.terms])
+    }
+
+    fn warn_truncated(&self, outcome: &SearchOutcome) {
+        if outcome.truncated {
+            warn!(
+                deadline = ?self.options.deadline,
+                results = outcome.results.len(),
+                "search deadline passed, returning the results found so far"
+            );
+        }
+    }
+
+    /// Find up to `limit` sections from the tree description.
+    #[instrument(
+        name = "search",
+        skip_all,
+        fields(query, nodes = tree.node_count(), results = Empty, truncated = Empty)
+    )]
+    async fn navigate(
+        &self,
+        tree: &DocumentTree,
+        query: &str,
+        limit: usize,
+        deadline: Deadline,
+    ) -> Result<SearchOutcome> {
+        let full = tree;
+        let searchable = self.searchable(full);
+        let tree = searchable.as_ref();
+        let capabilities = SearchCapabilities::of(tree);
+        let prefix_summaries = self.options.prefix_summaries && capabilities.prefix_summaries;
+
         // Use the search-friendly format that includes summaries
-        let tree_structure = tree.format_for_search();
+        let tree_structure = match (self.options.outline_tokens, &self.formats) {
+            (Some(max_tokens), _) => Cow::Owned(tree.to_outline(&OutlineOptions {
+                summaries: true,
+                prefix_summaries,
+                max_tokens: Some(max_tokens),
+            })),
+            (None, Some(formats)) if matches!(searchable, Cow::Borrowed(_)) => {
+                Cow::Borrowed(formats.get(tree, prefix_summaries))
+            }
+            (None, _) => Cow::Owned(tree.format_for_search_with(prefix_summaries)),
+        };
+        debug!(
+            query,
+            nodes = tree.node_count(),
+            prompt_chars = tree_structure.len(),
+            ?capabilities,
+            "searching tree"
+        );
+        let prompt = capabilities.search_prompt();
+        let terms = self.definitions(tree, query);
+        emit(
+            &self.progress,
+            ProgressEvent::SearchStarted {
+                nodes: tree.node_count(),
+            },
+        );
 
-        let prompt = Prompts::tree_search()
-            .replace("{tree_structure}", &tree_structure)
+        // Nothing the caller may see: no prompt to send
+        let mut truncated = false;
+        let mut results = if tree.nodes.is_empty() {
+            Vec::new()
+        } else {
+            let defined = with_definitions(query, &terms);
+            let step = self.search_structure(prompt, &tree_structure, &defined);
+            deadline.run(step).await?.unwrap_or_else(|| {
+                truncated = true;
+                Vec::new()
+            })
+        };
+
+        if self.options.paraphrases > 0 && !tree.nodes.is_empty() && !truncated {
+            let titles = tree.to_outline(&OutlineOptions {
+                summaries: false,
+                prefix_summaries: false,
+                max_tokens: self.options.outline_tokens,
+            });
+            let mut lists = vec![results];
+            let paraphrases = deadline.run(self.paraphrase(query, &terms)).await?;
+            truncated = paraphrases.is_none();
+            for paraphrase in paraphrases.unwrap_or_default() {
+                let defined = with_definitions(&paraphrase, &terms);
+                let step = self.search_structure(prompt, &titles, &defined);
+                match deadline.run(step).await? {
+                    Some(list) => lists.push(list),
+                    None => {
+                        truncated = true;
+                        break;
+                    }
+                }
+            }
+            results = fuse_reciprocal_rank(lists);
+        }
+
+        if self.options.acl_labels.is_some() || self.options.effective_since.is_some() {
+            // Never return a section the caller can't see, even if named,
+            // nor an older section kept only for its current subsections
+            let since = self.options.effective_since.as_deref();
+            results.retain(|r| {
+                full.find_section(&r.title, r.start_index, r.end_index)
+                    .is_some_and(|node| {
+                        tree.has_section(node)
+                            && since.is_none_or(|date| full.is_current_since(node, date))
+                    })
+            });
+        }
+        self.order_by_recency(tree, &mut results);
+
+        // Limit to top_k, or the ranker's shortlist
+        results.truncate(limit);
+        tracing::Span::current().record("results", results.len());
+        tracing::Span::current().record("truncated", truncated);
+
+        emit(
+            &self.progress,
+            ProgressEvent::SearchCompleted {
+                results: results.len(),
+            },
+        );
+
+        Ok(SearchOutcome { results, truncated })
+    }
+
+    /// The part of a tree searches may show: without excluded sections
+    /// unless asked for, and only what the caller's labels allow.
+    fn searchable<'a>(&self, tree: &'a DocumentTree) -> Cow<'a, DocumentTree> {
+        let mut tree = Cow::Borrowed(tree);
+        if !self.options.include_excluded && tree.has_excluded() {
+            tree = Cow::Owned(tree.without_excluded());
+        }
+        if let Some(labels) = &self.options.acl_labels
+            && tree.has_acl()
+        {
+            tree = Cow::Owned(tree.visible_to(labels));
+        }
+        if let Some(date) = &self.options.effective_since
+            && tree.has_effective_dates()
+        {
+            tree = Cow::Owned(tree.effective_since(date));
+        }
+        tree
+    }
+
+    /// Pages the caller's [labels](SearchOptions::acl_labels) don't allow.
+    fn hidden_pages(&self, tree: &DocumentTree) -> BTreeSet<usize> {
+        match &self.options.acl_labels {
+            Some(labels) if tree.has_acl() => tree.hidden_pages(labels),
+            _ => BTreeSet::new(),
+        }
+    }
+
+    /// With [`SearchOptions::prefer_recent`], order results by relevance
+    /// and then by effective date, latest first.
+    fn order_by_recency(&self, tree: &DocumentTree, results: &mut [SearchResult]) {
+        if self.options.prefer_recent && tree.has_effective_dates() {
+            results.sort_by_cached_key(|r| {
+                let date = tree.effective_date_of(r.start_index, r.end_index);
+                (
+                    Reverse(r.relevance.score()),
+                    Reverse(date.map(str::to_string)),
+                )
+            });
+        }
+    }
+
+    /// Run one search prompt over a tree description, keeping results of at
+    /// least the minimum relevance, most relevant first.
+    async fn search_structure(
+        &self,
+        prompt: &str,
+        tree_structure: &str,
+        query: &str,
+    ) -> Result<Vec<SearchResult>> {
+        let prompt = prompt
+            .replace("{tree_structure}", tree_structure)
             .replace("{query}", query);
 
         let response = self
@@ -113,43 +709,247 @@ impl TreeSearcher {
             .await?;
 
         let mut results = self.parse_search_response(&response)?;
+        debug!(candidates = results.len(), "parsed search response");
 
         // Filter by minimum relevance
         results.retain(|r| r.relevance.score() >= self.options.min_relevance.score());
 
         // Sort by relevance (high to low)
-        results.sort_by(|a, b| b.relevance.score().cmp(&a.relevance.score()));
+        results.sort_by_key(|r| std::cmp::Reverse(r.relevance.score()));
+        Ok(results)
+    }
 
-        // Limit to top_k
-        results.truncate(self.options.top_k);
+    /// Have the LLM rephrase the query (see [`SearchOptions::paraphrases`]).
+    #[instrument(name = "search.paraphrase", skip_all, fields(paraphrases = Empty))]
+    async fn paraphrase(&self, query: &str, terms: &[(String, String)]) -> Result<Vec<String>> {
+        let count = self.options.paraphrases;
+        let prompt = Prompts::paraphrase_query()
+            .replace("{count}", &count.to_string())
+            .replace("{query}", &with_definitions(query, terms));
+        let response = self
+            .client
+            .complete(Some(Prompts::system_document_analyzer()), &prompt)
+            .await?;
+        let paraphrases = parse_paraphrases(query, &Self::extract_json(&response), count)?;
+        debug!(?paraphrases, "paraphrased query");
+        tracing::Span::current().record("paraphrases", paraphrases.len());
+        Ok(paraphrases)
+    }
 
-        Ok(results)
+    /// [`search`](Self::search) as a stream of progress events ending with
+    /// the results.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn search_stream<'a>(
+        &self,
+        tree: &'a DocumentTree,
+        query: &'a str,
+    ) -> EventStream<'a, SearchEvent> {
+        let searcher = TreeSearcher {
+            client: self.client.clone(),
+            ranker: self.ranker.clone(),
+            options: self.options.clone(),
+            progress: self.progress.clone(),
+            formats: self.formats.clone(),
+        };
+        EventStream::new(move |tx| async move {
+            let progress = forward_progress(searcher.progress.clone(), tx, SearchEvent::Progress);
+            let results = searcher.with_progress(progress).search(tree, query).await?;
+            Ok(SearchEvent::Completed(results))
+        })
     }
 
-    /// Search and include content from the document.
+    /// Search and include each result's text, fetched from `store` by the
+    /// tree's document name. Pass the [`Document`] itself when it is loaded.
     pub async fn search_with_content(
         &self,
         tree: &DocumentTree,
-        document: &Document,
+        store: &(impl DocumentStore + ?Sized),
         query: &str,
     ) -> Result<Vec<SearchResult>> {
-        let mut results = self.search(tree, query).await?;
+        Ok(self
+            .search_with_content_within(tree, store, query)
+            .await?
+            .results)
+    }
 
-        // Add content for each result
+    /// [`search_with_content`](Self::search_with_content), reporting
+    /// whether [`SearchOptions::deadline`] cut it short.
+    pub async fn search_with_content_within(
+        &self,
+        tree: &DocumentTree,
+        store: &(impl DocumentStore + ?Sized),
+        query: &str,
+    ) -> Result<SearchOutcome> {
+        let deadline = Deadline::after(self.options.deadline);
+        let normalized = self.normalize(tree, query);
+        let query = normalized.as_ref();
+        let limit = match self.ranker {
+            Some(_) => self.options.top_k * SHORTLIST_FACTOR,
+            None => self.options.top_k,
+        };
+        let SearchOutcome {
+            mut results,
+            mut truncated,
+        } = self.navigate(tree, query, limit, deadline).await?;
+
+        // Add content for each result, without the pages of subsections
+        // the caller may not see
+        let hidden = self.hidden_pages(tree);
+        let span = info_span!("search.content", results = results.len());
         for result in &mut results {
-            let content = document.content_range(result.start_index, result.end_index);
-            // Strip the index tags for cleaner output
-            let clean_content = content
-                .lines()
-                .filter(|line| !line.starts_with("<physical_index_"))
-                .collect::<Vec<_>>()
-                .join("\n")
-                .trim()
-                .to_string();
+            let mut parts = Vec::new();
+            for (start, end) in visible_ranges(result.start_index, result.end_index, &hidden) {
+                let part = store
+                    .get_page_range(&tree.name, start, end)
+                    .instrument(span.clone())
+                    .await?;
+                parts.push(part);
+            }
+            result.content = Some(parts.join("\n\n"));
+        }
 
-            result.content = Some(clean_content);
+        // Past the deadline, the navigation results stand unchecked
+        let terms = self.definitions(tree, query);
+        let defined = with_definitions(query, &terms);
+        let query = defined.as_ref();
+        let checked = match &self.ranker {
+            Some(ranker) if !results.is_empty() => Some(
+                deadline
+                    .run(self.rank(ranker, query, results.clone()))
+                    .await?,
+            ),
+            None if self.options.verify_results && !results.is_empty() => {
+                Some(deadline.run(self.verify(query, results.clone())).await?)
+            }
+            _ => None,
+        };
+        match checked {
+            Some(Some(mut checked)) => {
+                self.order_by_recency(tree, &mut checked);
+                results = checked;
+            }
+            Some(None) => {
+                truncated = true;
+                results.truncate(self.options.top_k);
+            }
+            None => {}
+        }
+
+        let outcome = SearchOutcome { results, truncated };
+        self.warn_truncated(&outcome);
+        Ok(outcome)
+    }
+
+    /// Pinpoint the paragraphs of one section that answer a query.
+    ///
+    /// `node_id` is the section's `node_id` or structure path (see
+    /// [`DocumentTree::find_node`]). Every paragraph of the section's pages
+    /// is sent in one prompt, so zoom into sections a search has already
+    /// narrowed down rather than whole chapters. Passages are filtered by
+    /// [`SearchOptions::min_relevance`] and capped at
+    /// [`SearchOptions::top_k`], most relevant first; their text comes from
+    /// `document`, not the LLM, so it can be quoted as is. Sections hidden
+    /// by [`SearchOptions::acl_labels`] are not found.
+    #[instrument(
+        name = "search.zoom",
+        skip_all,
+        fields(node = node_id, paragraphs = Empty, passages = Empty)
+    )]
+    pub async fn zoom(
+        &self,
+        tree: &DocumentTree,
+        document: &Document,
+        node_id: &str,
+        query: &str,
+    ) -> Result<Vec<Passage>> {
+        let visible = match &self.options.acl_labels {
+            Some(labels) => Cow::Owned(tree.visible_to(labels)),
+            None => Cow::Borrowed(tree),
+        };
+        let node = visible.find_node(node_id).ok_or_else(|| {
+            PageIndexError::TreeError(format!("no node with node_id or structure '{}'", node_id))
+        })?;
+        let hidden = self.hidden_pages(tree);
+        let mut paragraphs = section_paragraphs(document, node.start_index, node.end_index);
+        paragraphs.retain(|(page, _, _)| !hidden.contains(page));
+        tracing::Span::current().record("paragraphs", paragraphs.len());
+        if paragraphs.is_empty() {
+            return Ok(Vec::new());
         }
 
+        let listing = paragraphs
+            .iter()
+            .map(|(page, paragraph, text)| {
+                format!("[page {}, paragraph {}]\n{}", page, paragraph, text)
+            })
+            .collect::<Vec<_>>()
+            .join("\n\n");
+        let section = format!(
+            "{} (pages {}-{})",
+            node.title, node.start_index, node.end_index
+        );
+        let prompt = Prompts::zoom_section()
+            .replace("{query}", query)
+            .replace("{section}", &section)
+            .replace("{paragraphs}", &listing);
+
+        let response = self
+            .client
+            .complete(Some(Prompts::system_document_analyzer()), &prompt)
+            .await?;
+        let mut passages = parse_passages(&paragraphs, &Self::extract_json(&response))?;
+
+        passages.retain(|p| p.relevance.score() >= self.options.min_relevance.score());
+        passages.sort_by_key(|p| std::cmp::Reverse(p.relevance.score()));
+        passages.truncate(self.options.top_k);
+        tracing::Span::current().record("passages", passages.len());
+        Ok(passages)
+    }
+
+    /// Check each result's content against the query (see
+    /// [`SearchOptions::verify_results`]).
+    #[instrument(name = "search.verify", skip_all, fields(results = results.len()))]
+    async fn verify(&self, query: &str, results: Vec<SearchResult>) -> Result<Vec<SearchResult>> {
+        let prompt = Prompts::verify_search_results()
+            .replace("{query}", query)
+            .replace("{sections}", &numbered_excerpts(&results));
+
+        let response = self
+            .client
+            .complete(Some(Prompts::system_document_analyzer()), &prompt)
+            .await?;
+        let mut results = apply_verdicts(results, &Self::extract_json(&response))?;
+
+        results.retain(|r| r.relevance.score() >= self.options.min_relevance.score());
+        results.sort_by_key(|r| std::cmp::Reverse(r.relevance.score()));
+        Ok(results)
+    }
+
+    /// Have the ranker order the shortlist by content and quote snippets
+    /// (see [`with_ranker`](Self::with_ranker)).
+    #[instrument(
+        name = "search.rank",
+        skip_all,
+        fields(model = ranker.model(), shortlist = results.len(), results = Empty)
+    )]
+    async fn rank(
+        &self,
+        ranker: &LlmClient,
+        query: &str,
+        results: Vec<SearchResult>,
+    ) -> Result<Vec<SearchResult>> {
+        let prompt = Prompts::rank_shortlist()
+            .replace("{query}", query)
+            .replace("{sections}", &numbered_excerpts(&results));
+
+        let response = ranker
+            .complete(Some(Prompts::system_document_analyzer()), &prompt)
+            .await?;
+        let mut results = apply_ranking(results, &Self::extract_json(&response))?;
+
+        results.retain(|r| r.relevance.score() >= self.options.min_relevance.score());
+        results.truncate(self.options.top_k);
+        tracing::Span::current().record("results", results.len());
         Ok(results)
     }
 
@@ -192,6 +992,8 @@ impl TreeSearcher {
                 relevance: Relevance::from_str(&r.relevance),
                 reason: r.reason,
                 content: None,
+                snippet: None,
+                snippet_span: None,
             })
             .collect();
 
@@ -199,37 +1001,279 @@ impl TreeSearcher {
     }
 
     /// Extract JSON from response (same logic as indexer).
-    fn extract_json(response: &str) -> String {
+    pub(crate) fn extract_json(response: &str) -> String {
         let response = response.trim();
 
-        if response.starts_with("```json") {
-            if let Some(end) = response.rfind("```") {
-                let start = "```json".len();
-                if end > start {
-                    return response[start..end].trim().to_string();
-                }
+        if response.starts_with("```json")
+            && let Some(end) = response.rfind("```")
+        {
+            let start = "```json".len();
+            if end > start {
+                return response[start..end].trim().to_string();
+            }
+        }
+
+        if response.starts_with("```")
+            && let Some(end) = response.rfind("```")
+        {
+            let start = response.find('\n').map(|n| n + 1).unwrap_or(3);
+            if end > start {
+                return response[start..end].trim().to_string();
+            }
+        }
+
+        if let Some(start) = response.find('{')
+            && let Some(end) = response.rfind('}')
+            && end > start
+        {
+            return response[start..=end].to_string();
+        }
+
+        response.to_string()
+    }
+}
+
+/// Number results with their title, pages and the start of their content,
+/// for the verifier and the ranker to refer to by number.
+fn numbered_excerpts(results: &[SearchResult]) -> String {
+    results
+        .iter()
+        .enumerate()
+        .map(|(i, r)| {
+            let content = r.content.as_deref().unwrap_or_default();
+            let mut words = content.split_whitespace();
+            let mut excerpt = words
+                .by_ref()
+                .take(VERIFY_CONTENT_WORDS)
+                .collect::<Vec<_>>()
+                .join(" ");
+            if words.next().is_some() {
+                excerpt.push_str(" ...");
             }
+            format!(
+                "[{}] {} (pages {}-{})\n{}",
+                i + 1,
+                r.title,
+                r.start_index,
+                r.end_index,
+                excerpt
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Order a shortlist as a ranking response does, with its relevance and
+/// snippets. Sections it leaves out, repeats or marks "none" are dropped.
+fn apply_ranking(shortlist: Vec<SearchResult>, json: &str) -> Result<Vec<SearchResult>> {
+    #[derive(Deserialize)]
+    struct RankResponse {
+        ranking: Vec<Ranked>,
+    }
+
+    #[derive(Deserialize)]
+    struct Ranked {
+        section: usize,
+        relevance: String,
+        #[serde(default)]
+        snippet: Option<String>,
+    }
+
+    let parsed: RankResponse = serde_json::from_str(json).map_err(|e| {
+        PageIndexError::LlmParse(format!("Failed to parse ranking response: {}", e))
+    })?;
+
+    let shortlisted = shortlist.len();
+    let mut shortlist: Vec<Option<SearchResult>> = shortlist.into_iter().map(Some).collect();
+    let mut ranked = Vec::new();
+    for entry in parsed.ranking {
+        if entry.relevance.eq_ignore_ascii_case("none") {
+            continue;
         }
+        let Some(mut result) = entry
+            .section
+            .checked_sub(1)
+            .and_then(|i| shortlist.get_mut(i))
+            .and_then(Option::take)
+        else {
+            continue;
+        };
+        result.relevance = Relevance::from_str(&entry.relevance);
+        result.snippet = entry.snippet.filter(|s| !s.trim().is_empty());
+        result.snippet_span = result
+            .snippet
+            .as_deref()
+            .zip(result.content.as_deref())
+            .and_then(|(snippet, content)| locate_snippet(content, snippet));
+        ranked.push(result);
+    }
+    debug!(shortlisted, ranked = ranked.len(), "ranked shortlist");
+    Ok(ranked)
+}
+
+/// Drop the results a verification response says do not address the query
+/// and demote the ones it says partly do. Results without a verdict are
+/// kept as they are.
+fn apply_verdicts(results: Vec<SearchResult>, json: &str) -> Result<Vec<SearchResult>> {
+    #[derive(Deserialize)]
+    struct VerifyResponse {
+        verdicts: Vec<Verdict>,
+    }
+
+    #[derive(Deserialize)]
+    struct Verdict {
+        section: usize,
+        addresses_query: String,
+    }
+
+    let parsed: VerifyResponse = serde_json::from_str(json).map_err(|e| {
+        PageIndexError::LlmParse(format!("Failed to parse verification response: {}", e))
+    })?;
+
+    let mut verdicts = vec![None; results.len()];
+    for verdict in parsed.verdicts {
+        if let Some(slot) = verdict
+            .section
+            .checked_sub(1)
+            .and_then(|i| verdicts.get_mut(i))
+        {
+            *slot = Some(verdict.addresses_query.to_lowercase());
+        }
+    }
 
-        if response.starts_with("```") {
-            if let Some(end) = response.rfind("```") {
-                let start = response.find('\n').map(|n| n + 1).unwrap_or(3);
-                if end > start {
-                    return response[start..end].trim().to_string();
+    let mut removed = 0;
+    let mut demoted = 0;
+    let verified = results
+        .into_iter()
+        .zip(verdicts)
+        .filter_map(|(mut result, verdict)| {
+            match verdict.as_deref() {
+                Some("no") => {
+                    removed += 1;
+                    return None;
                 }
+                Some("partly" | "partial") => {
+                    demoted += 1;
+                    result.relevance = match result.relevance {
+                        Relevance::High => Relevance::Medium,
+                        _ => Relevance::Low,
+                    };
+                }
+                _ => {}
             }
+            Some(result)
+        })
+        .collect();
+    debug!(removed, demoted, "verified search results");
+    Ok(verified)
+}
+
+/// Read up to `count` paraphrases from a response, skipping blanks, repeats
+/// and restatements of the query itself.
+fn parse_paraphrases(query: &str, json: &str, count: usize) -> Result<Vec<String>> {
+    #[derive(Deserialize)]
+    struct ParaphraseResponse {
+        paraphrases: Vec<String>,
+    }
+
+    let parsed: ParaphraseResponse = serde_json::from_str(json).map_err(|e| {
+        PageIndexError::LlmParse(format!("Failed to parse paraphrase response: {}", e))
+    })?;
+
+    let mut seen = vec![query.trim().to_lowercase()];
+    let mut paraphrases = Vec::new();
+    for paraphrase in parsed.paraphrases {
+        let paraphrase = paraphrase.trim();
+        let key = paraphrase.to_lowercase();
+        if paraphrase.is_empty() || seen.contains(&key) {
+            continue;
+        }
+        seen.push(key);
+        paraphrases.push(paraphrase.to_string());
+        if paraphrases.len() == count {
+            break;
         }
+    }
+    Ok(paraphrases)
+}
 
-        if let Some(start) = response.find('{') {
-            if let Some(end) = response.rfind('}') {
-                if end > start {
-                    return response[start..=end].to_string();
+/// Merge ranked result lists by reciprocal rank fusion. A section found by
+/// several lists keeps its first result, with the highest relevance any
+/// list gave it. Sections are ordered by fused score, ties by first
+/// appearance.
+fn fuse_reciprocal_rank(lists: Vec<Vec<SearchResult>>) -> Vec<SearchResult> {
+    let mut fused: Vec<(f64, SearchResult)> = Vec::new();
+    for list in lists {
+        for (rank, result) in list.into_iter().enumerate() {
+            let score = 1.0 / (RRF_K + rank as f64 + 1.0);
+            let existing = fused.iter_mut().find(|(_, r)| {
+                r.start_index == result.start_index
+                    && r.end_index == result.end_index
+                    && r.title == result.title
+            });
+            match existing {
+                Some((total, existing)) => {
+                    *total += score;
+                    if result.relevance.score() > existing.relevance.score() {
+                        existing.relevance = result.relevance;
+                    }
                 }
+                None => fused.push((score, result)),
             }
         }
+    }
+    fused.sort_by(|a, b| b.0.total_cmp(&a.0));
+    fused.into_iter().map(|(_, result)| result).collect()
+}
 
-        response.to_string()
+/// Resolve a zoom response against the paragraphs that were sent. Passages
+/// naming a paragraph that wasn't sent, or one already listed, are dropped.
+fn parse_passages(paragraphs: &[(usize, usize, String)], json: &str) -> Result<Vec<Passage>> {
+    #[derive(Deserialize)]
+    struct ZoomResponse {
+        passages: Vec<RawPassage>,
     }
+
+    #[derive(Deserialize)]
+    struct RawPassage {
+        page: usize,
+        paragraph: usize,
+        relevance: String,
+        #[serde(default)]
+        reason: String,
+    }
+
+    let parsed: ZoomResponse = serde_json::from_str(json)
+        .map_err(|e| PageIndexError::LlmParse(format!("Failed to parse zoom response: {}", e)))?;
+
+    let mut passages: Vec<Passage> = Vec::new();
+    for raw in parsed.passages {
+        let Some((_, _, text)) = paragraphs
+            .iter()
+            .find(|(page, paragraph, _)| *page == raw.page && *paragraph == raw.paragraph)
+        else {
+            debug!(
+                page = raw.page,
+                paragraph = raw.paragraph,
+                "zoom response named an unknown paragraph"
+            );
+            continue;
+        };
+        if passages
+            .iter()
+            .any(|p| p.page == raw.page && p.paragraph == raw.paragraph)
+        {
+            continue;
+        }
+        passages.push(Passage {
+            page: raw.page,
+            paragraph: raw.paragraph,
+            text: text.clone(),
+            relevance: Relevance::from_str(&raw.relevance),
+            reason: raw.reason,
+        });
+    }
+    Ok(passages)
 }
 
 /// Convenience function to search a document tree.
@@ -245,6 +1289,10 @@ pub async fn search_tree(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::fixtures::replay_replies;
+    use crate::golden::PromptCapture;
+    use crate::tree::TreeNode;
+    use serde_json::json;
 
     #[test]
     fn test_relevance_ordering() {
@@ -266,6 +1314,640 @@ mod tests {
         assert_eq!(options.top_k, 10);
         assert_eq!(options.min_relevance, Relevance::Low);
         assert!(!options.include_content);
+        assert!(!options.verify_results);
+        assert!(options.outline_tokens.is_none());
+        assert_eq!(options.paraphrases, 0);
+        assert!(!options.include_excluded);
+        assert!(options.acl_labels.is_none());
+    }
+
+    #[test]
+    fn test_parse_paraphrases() {
+        let json = r#"{"paraphrases": [
+            "When must the filter be replaced?",
+            "  ",
+            "How often should I change the filter",
+            "when must the filter be replaced?",
+            "Filter maintenance schedule"
+        ]}"#;
+        let paraphrases =
+            parse_paraphrases("How often should I change the filter", json, 2).unwrap();
+        assert_eq!(
+            paraphrases,
+            vec![
+                "When must the filter be replaced?",
+                "Filter maintenance schedule"
+            ]
+        );
+        assert!(parse_paraphrases("q", "not json", 2).is_err());
+    }
+
+    #[test]
+    fn test_fuse_reciprocal_rank() {
+        let result = |title: &str, start, relevance| SearchResult {
+            title: title.to_string(),
+            start_index: start,
+            end_index: start,
+            relevance,
+            reason: title.to_string(),
+            content: None,
+            snippet: None,
+            snippet_span: None,
+        };
+        let fused = fuse_reciprocal_rank(vec![
+            vec![
+                result("Warranty", 9, Relevance::High),
+                result("Filter", 4, Relevance::Medium),
+            ],
+            vec![
+                result("Filter", 4, Relevance::High),
+                result("Descaling", 5, Relevance::Medium),
+            ],
+            vec![
+                result("Descaling", 5, Relevance::Low),
+                result("Filter", 4, Relevance::Low),
+            ],
+        ]);
+        let ranked: Vec<_> = fused
+            .iter()
+            .map(|r| (r.title.as_str(), r.relevance))
+            .collect();
+        assert_eq!(
+            ranked,
+            vec![
+                ("Filter", Relevance::High),
+                ("Descaling", Relevance::Medium),
+                ("Warranty", Relevance::High),
+            ]
+        );
+        assert!(fuse_reciprocal_rank(Vec::new()).is_empty());
+    }
+
+    #[test]
+    fn test_apply_verdicts() {
+        let result = |title: &str, relevance| SearchResult {
+            title: title.to_string(),
+            start_index: 1,
+            end_index: 1,
+            relevance,
+            reason: String::new(),
+            content: Some(String::new()),
+            snippet: None,
+            snippet_span: None,
+        };
+        let results = vec![
+            result("Wiring", Relevance::High),
+            result("Warranty", Relevance::High),
+            result("Safety", Relevance::High),
+            result("Index", Relevance::Medium),
+        ];
+        let json = r#"{"verdicts": [
+            {"section": 1, "addresses_query": "yes", "reason": "Covers it"},
+            {"section": 2, "addresses_query": "No", "reason": "Only the title matches"},
+            {"section": 3, "addresses_query": "partly", "reason": "Mentions it"},
+            {"section": 9, "addresses_query": "no", "reason": "Out of range"}
+        ]}"#;
+
+        let verified = apply_verdicts(results, json).unwrap();
+        let kept: Vec<_> = verified
+            .iter()
+            .map(|r| (r.title.as_str(), r.relevance))
+            .collect();
+        assert_eq!(
+            kept,
+            vec![
+                ("Wiring", Relevance::High),
+                ("Safety", Relevance::Medium),
+                ("Index", Relevance::Medium),
+            ]
+        );
+        assert!(apply_verdicts(Vec::new(), "not json").is_err());
+    }
+
+    #[test]
+    fn test_apply_ranking() {
+        let result = |title: &str| SearchResult {
+            title: title.to_string(),
+            start_index: 1,
+            end_index: 1,
+            relevance: Relevance::High,
+            reason: String::new(),
+            content: Some(format!("{}: Disconnect power first.", title)),
+            snippet: None,
+            snippet_span: None,
+        };
+        let shortlist = vec![
+            result("Wiring"),
+            result("Warranty"),
+            result("Safety"),
+            result("Index"),
+        ];
+        let json = r#"{"ranking": [
+            {"section": 3, "relevance": "high", "snippet": "Disconnect power first."},
+            {"section": 1, "relevance": "Medium", "snippet": " "},
+            {"section": 3, "relevance": "low"},
+            {"section": 4, "relevance": "none"},
+            {"section": 9, "relevance": "high"}
+        ]}"#;
+
+        let ranked = apply_ranking(shortlist, json).unwrap();
+        let order: Vec<_> = ranked
+            .iter()
+            .map(|r| (r.title.as_str(), r.relevance, r.snippet.as_deref()))
+            .collect();
+        assert_eq!(
+            order,
+            vec![
+                ("Safety", Relevance::High, Some("Disconnect power first.")),
+                ("Wiring", Relevance::Medium, None),
+            ]
+        );
+        assert_eq!(ranked[0].snippet_span, Some(TextSpan { start: 8, end: 31 }));
+        assert_eq!(ranked[1].snippet_span, None);
+        assert!(apply_ranking(Vec::new(), "not json").is_err());
+    }
+
+    #[test]
+    fn test_locate_snippet() {
+        let content = "Café hours.\nThe filter is\n  cleaned weekly.";
+        let span = locate_snippet(content, "The filter").unwrap();
+        assert_eq!((span.start, span.end), (12, 22));
+
+        // Reflowed whitespace, quotes and ellipses
+        let span = locate_snippet(content, "\"...filter is cleaned weekly.\"").unwrap();
+        let text: String = content
+            .chars()
+            .skip(span.start)
+            .take(span.end - span.start)
+            .collect();
+        assert_eq!(text, "filter is\n  cleaned weekly.");
+
+        assert_eq!(locate_snippet(content, "cleaned monthly"), None);
+        assert_eq!(locate_snippet(content, " ... "), None);
+    }
+
+    /// Capture requests, replying with `responses` in order.
+    fn capture_replying(responses: &[&str]) -> (PromptCapture, tempfile::TempDir) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("search.fixture.json");
+        (PromptCapture::new(replay_replies(&path, responses)), dir)
+    }
+
+    /// The user prompts of captured requests.
+    fn captured_prompts(capture: &PromptCapture) -> Vec<String> {
+        capture
+            .prompts()
+            .iter()
+            .map(|p| {
+                p.request["messages"][1]["content"]
+                    .as_str()
+                    .unwrap()
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_search_capabilities() {
+        let response = r#"{"relevant_sections": [{"title": "Wiring", "start_index": 1, "end_index": 1, "relevance": "high", "reason": "r"}]}"#;
+        let mut tree = DocumentTree::new(
+            "manual",
+            vec![TreeNode::new("Wiring", 1, 1), TreeNode::new("Safety", 2, 2)],
+            2,
+        );
+        assert_eq!(
+            SearchCapabilities::of(&tree),
+            SearchCapabilities {
+                summaries: false,
+                prefix_summaries: false
+            }
+        );
+
+        // Titles only: the title prompt
+        let (capture, _dir) = capture_replying(&[response]);
+        let searcher = TreeSearcher::new(capture.client().unwrap());
+        let results = searcher.search(&tree, "wires").await.unwrap();
+        assert_eq!(results[0].title, "Wiring");
+        let prompt = &captured_prompts(&capture)[0];
+        assert!(prompt.contains("but no section summaries"));
+
+        // Summaries: the usual prompt, with them; asking for prefix
+        // summaries the tree lacks falls back to the summaries
+        tree.nodes[1].summary = Some("Breakers and grounding.".to_string());
+        assert!(SearchCapabilities::of(&tree).summaries);
+        let (capture, _dir) = capture_replying(&[response]);
+        let options = SearchOptions {
+            prefix_summaries: true,
+            ..Default::default()
+        };
+        let searcher = TreeSearcher::with_options(capture.client().unwrap(), options);
+        searcher.search(&tree, "wires").await.unwrap();
+        let prompt = &captured_prompts(&capture)[0];
+        assert!(!prompt.contains("but no section summaries"));
+        assert!(prompt.contains("Safety (pages 2-2) - Breakers and grounding."));
+    }
+
+    #[tokio::test]
+    async fn test_two_stage_search() {
+        use crate::document::Page;
+
+        let (capture, _dir) = capture_replying(&[
+            r#"{"relevant_sections": [
+                {"title": "Wiring", "start_index": 1, "end_index": 1, "relevance": "high", "reason": "r"},
+                {"title": "Safety", "start_index": 2, "end_index": 2, "relevance": "medium", "reason": "r"}
+            ]}"#,
+            r#"{"ranking": [{"section": 2, "relevance": "high", "snippet": "Turn off the breaker."}]}"#,
+        ]);
+
+        let tree = DocumentTree::new(
+            "manual",
+            vec![TreeNode::new("Wiring", 1, 1), TreeNode::new("Safety", 2, 2)],
+            2,
+        );
+        let document = Document::new(
+            "manual",
+            vec![
+                Page::new(1, "Connect the red wire.".to_string()),
+                Page::new(2, "Turn off the breaker.".to_string()),
+            ],
+        );
+        let models = OperationModels {
+            navigate: Some("cheap".to_string()),
+            rank: Some("strong".to_string()),
+            ..OperationModels::default()
+        };
+        let options = SearchOptions {
+            top_k: 1,
+            ..Default::default()
+        };
+        let searcher = TreeSearcher::with_models(&capture.client().unwrap(), &models, options);
+        let results = searcher
+            .search_with_content(&tree, &document, "How do I stay safe?")
+            .await
+            .unwrap();
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].title, "Safety");
+        assert_eq!(results[0].snippet.as_deref(), Some("Turn off the breaker."));
+
+        // The tree goes to the navigation model, only the shortlist's
+        // content to the ranking model
+        let requests: Vec<_> = capture
+            .prompts()
+            .into_iter()
+            .map(|p| {
+                let model = p.request["model"].as_str().unwrap().to_string();
+                let prompt = p.request["messages"][1]["content"]
+                    .as_str()
+                    .unwrap()
+                    .to_string();
+                (model, prompt)
+            })
+            .collect();
+        assert_eq!(requests.len(), 2);
+        let (navigate, rank): (Vec<_>, Vec<_>) =
+            requests.iter().partition(|(model, _)| model == "cheap");
+        assert!(navigate[0].1.contains("Wiring"));
+        assert_eq!(rank[0].0, "strong");
+        assert!(
+            rank[0]
+                .1
+                .contains("[2] Safety (pages 2-2)\nTurn off the breaker.")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_deadline() {
+        use crate::config::LlmConfig;
+        use crate::document::Page;
+        use std::time::Instant;
+
+        // An LLM endpoint that accepts connections and never replies
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut open = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                open.push(socket);
+            }
+        });
+        let stalled = LlmClient::new(LlmConfig {
+            api_base: format!("http://{}", addr),
+            model: "slow".to_string(),
+            ..LlmConfig::default()
+        });
+
+        let tree = DocumentTree::new(
+            "manual",
+            vec![TreeNode::new("Wiring", 1, 1), TreeNode::new("Safety", 2, 2)],
+            2,
+        );
+        let document = Document::new(
+            "manual",
+            vec![
+                Page::new(1, "Connect the red wire.".to_string()),
+                Page::new(2, "Turn off the breaker.".to_string()),
+            ],
+        );
+        let options = SearchOptions {
+            top_k: 1,
+            deadline: Some(Duration::from_millis(200)),
+            ..Default::default()
+        };
+
+        // Navigation cut: nothing found in time
+        let started = Instant::now();
+        let outcome = TreeSearcher::with_options(stalled.clone(), options.clone())
+            .search_within(&tree, "How do I stay safe?")
+            .await
+            .unwrap();
+        assert!(outcome.truncated && outcome.results.is_empty());
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        // Ranking cut: the navigation shortlist stands
+        let (capture, _dir) = capture_replying(&[r#"{"relevant_sections": [
+            {"title": "Safety", "start_index": 2, "end_index": 2, "relevance": "high", "reason": "r"},
+            {"title": "Wiring", "start_index": 1, "end_index": 1, "relevance": "low", "reason": "r"}
+        ]}"#]);
+        let outcome = TreeSearcher::with_options(capture.client().unwrap(), options.clone())
+            .with_ranker(stalled)
+            .search_with_content_within(&tree, &document, "How do I stay safe?")
+            .await
+            .unwrap();
+        assert!(outcome.truncated);
+        let titles: Vec<_> = outcome.results.iter().map(|r| r.title.as_str()).collect();
+        assert_eq!(titles, vec!["Safety"]);
+        assert_eq!(
+            outcome.results[0].content.as_deref(),
+            Some("Turn off the breaker.")
+        );
+        assert_eq!(
+            serde_json::to_value(&outcome).unwrap()["truncated"],
+            json!(true)
+        );
+
+        // In time: not truncated, and not serialized as such
+        let (capture, _dir) = capture_replying(&[r#"{"relevant_sections": []}"#]);
+        let outcome = TreeSearcher::with_options(capture.client().unwrap(), options)
+            .search_within(&tree, "q")
+            .await
+            .unwrap();
+        assert!(!outcome.truncated);
+        assert!(
+            serde_json::to_value(&outcome)
+                .unwrap()
+                .get("truncated")
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_stream_keeps_formats() {
+        use tokio_stream::StreamExt;
+
+        let tree = DocumentTree::new("doc", vec![TreeNode::new("Intro", 1, 1)], 1);
+        let (capture, _dir) = capture_replying(&[r#"{"relevant_sections": []}"#]);
+        let formats = Arc::new(SearchFormats::new());
+        let searcher =
+            TreeSearcher::new(capture.client().unwrap()).with_search_formats(formats.clone());
+        let events: Vec<_> = searcher.search_stream(&tree, "intro").collect().await;
+
+        assert!(matches!(
+            events.last(),
+            Some(Ok(SearchEvent::Completed(results))) if results.is_empty()
+        ));
+        // The streamed search described the tree from the shared formats
+        assert!(formats.bytes() > 0);
+    }
+
+    #[tokio::test]
+    async fn test_content_leaves_out_hidden_subsections() {
+        use crate::document::Page;
+
+        let mut salaries = TreeNode::new("Salaries", 2, 2);
+        salaries.acl = vec!["hr".to_string()];
+        let mut staff = TreeNode::new("Staff", 1, 3);
+        staff.nodes = vec![TreeNode::new("Roles", 1, 1), salaries];
+        let tree = DocumentTree::new("handbook", vec![staff], 3);
+        let document = Document::new(
+            "handbook",
+            vec![
+                Page::new(1, "Engineers build.".to_string()),
+                Page::new(2, "The CEO earns 900k.".to_string()),
+                Page::new(3, "Managers plan.".to_string()),
+            ],
+        );
+        let reply = r#"{"relevant_sections": [
+            {"title": "Staff", "start_index": 1, "end_index": 3, "relevance": "high", "reason": "r"}
+        ]}"#;
+
+        let (capture, _dir) = capture_replying(&[reply; 2]);
+        let options = SearchOptions {
+            acl_labels: Some(Vec::new()),
+            ..SearchOptions::default()
+        };
+        let results = TreeSearcher::with_options(capture.client().unwrap(), options)
+            .search_with_content(&tree, &document, "Who works here?")
+            .await
+            .unwrap();
+        assert_eq!(
+            results[0].content.as_deref(),
+            Some("Engineers build.\n\nManagers plan.")
+        );
+
+        // Holders of the label read the whole section
+        let options = SearchOptions {
+            acl_labels: Some(vec!["hr".to_string()]),
+            ..SearchOptions::default()
+        };
+        let results = TreeSearcher::with_options(capture.client().unwrap(), options)
+            .search_with_content(&tree, &document, "Who works here?")
+            .await
+            .unwrap();
+        assert!(results[0].content.as_deref().unwrap().contains("900k"));
+
+        assert_eq!(
+            visible_ranges(1, 6, &BTreeSet::from([2, 5])),
+            vec![(1, 1), (3, 4), (6, 6)]
+        );
+        assert!(visible_ranges(2, 2, &BTreeSet::from([2])).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_search_normalizes_query() {
+        let mut secret = TreeNode::new("Pump overhaul", 2, 2);
+        secret.acl = vec!["staff".to_string()];
+        let tree = DocumentTree::new("manual", vec![TreeNode::new("Filter", 1, 1), secret], 2);
+        let (capture, _dir) = capture_replying(&[r#"{"relevant_sections": []}"#; 2]);
+        let options = SearchOptions {
+            normalizer: Some(
+                QueryNormalizer::new()
+                    .with_spell_correction(true)
+                    .with_glossary([("PM", "preventive maintenance")]),
+            ),
+            ..SearchOptions::default()
+        };
+        let searcher = TreeSearcher::with_options(capture.client().unwrap(), options.clone());
+        searcher.search(&tree, "PM of the filtr").await.unwrap();
+        assert!(
+            captured_prompts(&capture)[0].contains("preventive maintenance (PM) of the filter")
+        );
+
+        // Words are only corrected towards sections the caller may see
+        let hidden = TreeSearcher::with_options(
+            capture.client().unwrap(),
+            SearchOptions {
+                acl_labels: Some(Vec::new()),
+                ..options
+            },
+        );
+        hidden.search(&tree, "overhall the pupm").await.unwrap();
+        assert!(captured_prompts(&capture)[1].contains("overhall the pupm"));
+    }
+
+    #[tokio::test]
+    async fn test_search_defines_terms() {
+        let mut tree = DocumentTree::new("loan", vec![TreeNode::new("Covenants", 1, 1)], 1);
+        tree.terms.insert(
+            "DSCR".to_string(),
+            "Debt service coverage ratio.".to_string(),
+        );
+        let (capture, _dir) = capture_replying(&[r#"{"relevant_sections": []}"#; 2]);
+        let options = SearchOptions {
+            terms: BTreeMap::from([
+                ("dscr".to_string(), "Overridden by the tree.".to_string()),
+                ("LTV".to_string(), "Loan to value.".to_string()),
+            ]),
+            ..SearchOptions::default()
+        };
+        let searcher = TreeSearcher::with_options(capture.client().unwrap(), options);
+        searcher
+            .search(&tree, "Minimum DSCR and LTV?")
+            .await
+            .unwrap();
+        searcher.search(&tree, "Default events").await.unwrap();
+
+        let (defined, plain): (Vec<_>, Vec<_>) = captured_prompts(&capture)
+            .into_iter()
+            .partition(|p| p.contains("Minimum DSCR"));
+        assert!(defined[0].contains(
+            "User query: Minimum DSCR and LTV?\n\nDefinitions of terms used in the query:\n\
+             - DSCR: Debt service coverage ratio.\n- LTV: Loan to value."
+        ));
+        assert!(!plain[0].contains("Definitions of terms"));
+    }
+
+    #[tokio::test]
+    async fn test_search_by_effective_date() {
+        let mut old = TreeNode::new("2019 policy", 1, 1);
+        old.effective_date = Some("2019-01-01".to_string());
+        let mut new = TreeNode::new("2023 policy", 2, 2);
+        new.effective_date = Some("2023-07-01".to_string());
+        let tree = DocumentTree::new(
+            "handbook",
+            vec![old, new, TreeNode::new("Glossary", 3, 3)],
+            3,
+        );
+        let reply = r#"{"relevant_sections": [
+            {"title": "2019 policy", "start_index": 1, "end_index": 1, "relevance": "high", "reason": "r"},
+            {"title": "Glossary", "start_index": 3, "end_index": 3, "relevance": "high", "reason": "r"},
+            {"title": "2023 policy", "start_index": 2, "end_index": 2, "relevance": "high", "reason": "r"}
+        ]}"#;
+        let titles = |results: Vec<SearchResult>| -> Vec<String> {
+            results.into_iter().map(|r| r.title).collect()
+        };
+
+        // Equally relevant sections, latest first, undated last
+        let (capture, _dir) = capture_replying(&[reply]);
+        let options = SearchOptions {
+            prefer_recent: true,
+            ..SearchOptions::default()
+        };
+        let searcher = TreeSearcher::with_options(capture.client().unwrap(), options);
+        let results = searcher.search(&tree, "Leave policy").await.unwrap();
+        assert_eq!(
+            titles(results),
+            vec!["2023 policy", "2019 policy", "Glossary"]
+        );
+
+        // Sections older than the cut-off are neither shown nor returned
+        let (capture, _dir) = capture_replying(&[reply]);
+        let options = SearchOptions {
+            effective_since: Some("2020-01-01".to_string()),
+            ..SearchOptions::default()
+        };
+        let searcher = TreeSearcher::with_options(capture.client().unwrap(), options);
+        let results = searcher.search(&tree, "Leave policy").await.unwrap();
+        assert_eq!(titles(results), vec!["Glossary", "2023 policy"]);
+        let prompt = &captured_prompts(&capture)[0];
+        assert!(prompt.contains("effective 2023-07-01"));
+        assert!(!prompt.contains("2019 policy"));
+
+        // An older section kept for a current subsection is not returned
+        let mut amended = TreeNode::new("2019 policy", 1, 2);
+        amended.effective_date = Some("2019-01-01".to_string());
+        let mut amendment = TreeNode::new("2021 amendment", 2, 2);
+        amendment.effective_date = Some("2021-05-01".to_string());
+        amended.add_child(amendment);
+        let tree = DocumentTree::new("handbook", vec![amended], 2);
+        let reply = r#"{"relevant_sections": [
+            {"title": "2019 policy", "start_index": 1, "end_index": 2, "relevance": "high", "reason": "r"},
+            {"title": "2021 amendment", "start_index": 2, "end_index": 2, "relevance": "high", "reason": "r"}
+        ]}"#;
+        let (capture, _dir) = capture_replying(&[reply]);
+        let options = SearchOptions {
+            effective_since: Some("2020-01-01".to_string()),
+            ..SearchOptions::default()
+        };
+        let searcher = TreeSearcher::with_options(capture.client().unwrap(), options);
+        let results = searcher.search(&tree, "Leave policy").await.unwrap();
+        assert_eq!(titles(results), vec!["2021 amendment"]);
+    }
+
+    #[test]
+    fn test_zoom_passages() {
+        use crate::document::Page;
+
+        let document = Document::new(
+            "filing",
+            vec![
+                Page::new(1, "Cover".to_string()),
+                Page::new(
+                    2,
+                    "Revenue grew 12%.\nMostly services.\n\n  \nNet income fell.\n".to_string(),
+                ),
+                Page::new(3, "Outlook is stable.".to_string()),
+            ],
+        );
+        let paragraphs = section_paragraphs(&document, 2, 3);
+        assert_eq!(
+            paragraphs,
+            vec![
+                (2, 1, "Revenue grew 12%.\nMostly services.".to_string()),
+                (2, 2, "Net income fell.".to_string()),
+                (3, 1, "Outlook is stable.".to_string()),
+            ]
+        );
+
+        let json = r#"{"passages": [
+            {"page": 2, "paragraph": 2, "relevance": "high", "reason": "States net income"},
+            {"page": 1, "paragraph": 1, "relevance": "high", "reason": "Not in the section"},
+            {"page": 2, "paragraph": 2, "relevance": "low", "reason": "Repeated"},
+            {"page": 3, "paragraph": 1, "relevance": "Medium"}
+        ]}"#;
+        let passages = parse_passages(&paragraphs, json).unwrap();
+        let found: Vec<_> = passages
+            .iter()
+            .map(|p| (p.page, p.paragraph, p.text.as_str(), p.relevance))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                (2, 2, "Net income fell.", Relevance::High),
+                (3, 1, "Outlook is stable.", Relevance::Medium),
+            ]
+        );
+        assert!(parse_passages(&paragraphs, "not json").is_err());
     }
 
     #[test]
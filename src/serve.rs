@@ -0,0 +1,2261 @@
+//! HTTP retrieval server (`serve` feature).
+//!
+//! Serves indexes over the remote-retriever contracts RAG frameworks
+//! already speak, so a tree index can replace a vector store retriever:
+//!
+//! - `POST /retrieve`: LangChain's `RemoteLangChainRetriever`. Takes
+//!   `{"message": "<query>"}` (or `"query"`) and returns
+//!   `{"response": [Document, ...]}`.
+//! - `POST /invoke`: LangServe's runnable protocol (`RemoteRunnable`).
+//!   Takes `{"input": "<query>"}` and returns `{"output": [Document, ...]}`.
+//! - `POST /indexes/{index}/retrieve` and `/indexes/{index}/invoke`: the
+//!   same for a named index.
+//! - `POST /ask` and `/indexes/{index}/ask`: answer a question from the
+//!   sections retrieved for it. Takes `{"question": "<text>"}` (or
+//!   `"query"`) and returns `{"answer", "sources", "outcome", "cached"}`. Optional
+//!   `"length"` (`short`, `medium` or `long`), `"format"` (`prose` or
+//!   `bullets`), `"language"` and `"required_relevance"` (`high`,
+//!   `medium` or `low`) override the server's answer style. With a
+//!   required relevance, a question no retrieved section supports gets
+//!   `"outcome": "not_found"` rather than an answer from the model.
+//!   Answers are cached per normalized question, style and index version
+//!   (see [`crate::cache`]); `DELETE /answers` drops the caller's.
+//! - `GET /indexes`: the indexes a caller can query.
+//! - `GET /trees/{index}/nodes`: part of an index's tree, for rendering it
+//!   lazily. Returns the top-level sections, or the subsections of
+//!   `?parent=<node_id or structure>`, with `?depth=N` levels of
+//!   subsections below them (default 1: none) and each node's
+//!   `child_count`. Sections the caller may not see are left out.
+//! - `GET /health`: the caller's indexes and LLM usage so far.
+//! - `GET /metrics`: Prometheus metrics (see [`crate::metrics`]). With
+//!   several tenants, only for the admin key (see [Tenants](#tenants)).
+//! - `POST /jobs`: index a document in the background. Takes
+//!   `{"name": "<document>", "content": "<text>"}` and returns the queued
+//!   job (`202 Accepted`); see [`crate::jobs`].
+//! - `GET /jobs`, `GET /jobs/{id}`: the caller's jobs, with state and
+//!   progress.
+//! - `GET /jobs/{id}/tree`: a completed job's tree (`409 Conflict` until
+//!   it completes).
+//!
+//! Documents have the shape described in [`crate::retriever`]. Either
+//! request may set `top_k` to override the server's default, and `index`
+//! to pick an index (the first one otherwise). Results are cached per
+//! query and `top_k`, so repeated queries skip the LLM. A `deadline_ms`
+//! bounds the search (see [`SearchOptions::deadline`]): past it, the
+//! results found so far are returned with `"truncated": true` (in
+//! `metadata` for `/invoke`), and not cached.
+//!
+//! # Pagination
+//!
+//! Search requests may set `page_size` (and `cursor`) in the body, and
+//! `GET /indexes` and `GET /trees/{index}/nodes` take them as query
+//! parameters. A paged response carries `next_cursor` (in `metadata` for
+//! `/invoke`) to pass as `cursor` for the next page, or `null` after the
+//! last page. Search pages are cut from the `top_k` results, which are
+//! cached, so paging through them costs one search. Tree nodes are always
+//! paged, [`DEFAULT_PAGE_SIZE`] at a time unless asked otherwise; other
+//! responses only when asked.
+//!
+//! # Tenants
+//!
+//! [`ServeState::new`] serves a single index to anyone. For several teams,
+//! [`ServeState::from_tenants_file`] hosts separate [`Tenant`]s, each with
+//! its own indexes and LLM usage. A request names its tenant with one of
+//! the tenant's API keys (`Authorization: Bearer <key>` or `X-API-Key`),
+//! or, for tenants without keys (e.g. behind an authenticating proxy), with
+//! an `X-Tenant: <name>` header. Tenants never see each other's indexes
+//! or jobs.
+//!
+//! Metrics and the tree cache's stats cover every tenant, so a
+//! multi-tenant server only shows them to requests carrying its
+//! `admin_key` (set with [`ServeState::with_admin_key`]); without one,
+//! `GET /metrics` is refused. The admin key reaches no tenant's indexes.
+//!
+//! ```yaml
+//! admin_key: ops-91b3e7
+//! tenants:
+//!   - name: research
+//!     api_keys: [rk-4f8a2c]
+//!     indexes:
+//!       - path: research/report.json
+//!         document: research/report.txt
+//!   - name: legal
+//!     indexes:
+//!       - name: contracts
+//!         path: legal/contracts.json
+//! ```
+//!
+//! Relative paths are resolved against the tenants file's directory, and
+//! an index is named after its file stem unless `name` is given. An index
+//! with `stats: true` counts the sections retrieved for each query and
+//! answer in a sidecar file (see [`crate::stats`]). Query settings kept
+//! next to an index (`contracts.json.query`, see [`crate::query`]) are
+//! applied to its searches.
+//!
+//! Every tree is kept in memory unless the file sets `tree_cache_mb`: then
+//! trees are loaded from disk when queried and the least recently used are
+//! dropped once they take more than that many megabytes (see
+//! [`crate::tree_cache`]), so hundreds of indexes can be served from a
+//! modest instance. Source documents are still kept in memory. `GET
+//! /health` reports the cache's size, hits and evictions to the admin key.
+//!
+//! With [`ServeState::with_audit_log`], every successful retrieval and
+//! answer, cached or not, is appended to an audit log with its tenant,
+//! index, sections and token usage (see [`crate::audit`]). As on the
+//! command line, a request whose record can't be written fails (`500`)
+//! rather than going unrecorded.
+//!
+//! # Access labels
+//!
+//! Sections tagged with access labels (see
+//! [`TreeNode::acl`](crate::tree::TreeNode::acl)) are only shown to callers
+//! holding one of them; others' searches and answers never see them. An
+//! API key can carry labels (`{key: rk-7d1e, labels: [finance]}` in
+//! `api_keys`); a key without labels sees everything. For tenants without
+//! keys, the authenticating proxy passes the caller's labels in an
+//! `X-Acl-Labels: finance,hr` header, and must strip it from client
+//! requests; a request without the header sees only unlabeled sections.
+//! Cached results and answers are kept per set of labels.
+
+use crate::audit::{AuditLog, AuditRecord};
+use crate::cache::{AnswerCache, AnswerKey};
+use crate::config::LlmConfig;
+use crate::document::Document;
+use crate::error::{PageIndexError, Result};
+use crate::jobs::{Job, JobQueue, JobState};
+use crate::llm::LlmClient;
+use crate::metrics::Metrics;
+use crate::page_index::{Answer, AnswerFormat, AnswerLength, AnswerStyle, answer_from};
+use crate::persistence::load_tree;
+use crate::query::{QueryNormalizer, terms_in};
+use crate::retriever::{RetrieverDocument, result_documents};
+use crate::search::{Relevance, SearchOptions, SearchOutcome, SearchResult, TreeSearcher};
+use crate::stats::{StatsFile, stats_path_for};
+use crate::telemetry;
+use crate::tree::{DocumentTree, TreeNode};
+use crate::tree_cache::{LoadedTree, TreeCache, TreeCacheStats};
+use axum::extract::{MatchedPath, Path as UrlPath, Query, Request, State};
+use axum::http::{HeaderMap, StatusCode, header};
+use axum::middleware::{self, Next};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{delete, get, post};
+use axum::{Json, Router};
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::field::Empty;
+use tracing::{info, instrument, warn};
+
+/// Default number of documents returned per query.
+pub const DEFAULT_TOP_K: usize = 5;
+
+/// Default number of cached query results.
+pub const DEFAULT_CACHE_SIZE: usize = 256;
+
+/// Tenant of a single-index server.
+pub const DEFAULT_TENANT: &str = "default";
+
+/// Header with the caller's access labels, for tenants without API keys.
+pub const ACL_LABELS_HEADER: &str = "x-acl-labels";
+
+/// Default number of items per page when a request is paged.
+pub const DEFAULT_PAGE_SIZE: usize = 50;
+
+/// Most items a page may hold.
+pub const MAX_PAGE_SIZE: usize = 500;
+
+/// Tenant, index, query, `top_k` and the caller's access labels.
+type CacheKey = (String, String, String, usize, Option<Vec<String>>);
+
+/// Recent query results, evicting the oldest first.
+struct ResultCache {
+    capacity: usize,
+    entries: HashMap<CacheKey, Vec<RetrieverDocument>>,
+    order: VecDeque<CacheKey>,
+}
+
+impl ResultCache {
+    fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    fn get(&self, key: &CacheKey) -> Option<Vec<RetrieverDocument>> {
+        self.entries.get(key).cloned()
+    }
+
+    fn insert(&mut self, key: CacheKey, documents: Vec<RetrieverDocument>) {
+        if self.capacity == 0 || self.entries.contains_key(&key) {
+            return;
+        }
+        if self.order.len() >= self.capacity
+            && let Some(oldest) = self.order.pop_front()
+        {
+            self.entries.remove(&oldest);
+        }
+        self.order.push_back(key.clone());
+        self.entries.insert(key, documents);
+    }
+}
+
+/// Where a served index's tree is kept.
+enum TreeSource {
+    /// In memory for as long as it is served.
+    Resident(Arc<LoadedTree>),
+    /// Loaded from `path` through a cache shared with other indexes.
+    Cached {
+        path: PathBuf,
+        cache: Arc<TreeCache>,
+    },
+}
+
+/// A tree served under a name, with its source document if known.
+pub struct ServedIndex {
+    name: String,
+    source: TreeSource,
+    document: Option<Document>,
+    /// [`DocumentTree::content_hash`], computed once since the tree never
+    /// changes while served.
+    version: String,
+    nodes: usize,
+    pages: usize,
+    stats: Option<StatsFile>,
+    normalizer: Option<QueryNormalizer>,
+}
+
+impl ServedIndex {
+    pub fn new(name: impl Into<String>, tree: DocumentTree) -> Self {
+        let loaded = Arc::new(LoadedTree::new(tree));
+        Self::with_source(name, TreeSource::Resident(loaded.clone()), &loaded)
+    }
+
+    /// Serve the tree saved at `path`, kept in `cache` while it is used
+    /// (see [`crate::tree_cache`]). The tree is loaded once here, to check
+    /// it, and is reloaded from the same file after eviction, so the file
+    /// must not change while served.
+    pub fn cached(
+        name: impl Into<String>,
+        path: impl Into<PathBuf>,
+        cache: Arc<TreeCache>,
+    ) -> Result<Self> {
+        let path = path.into();
+        let loaded = cache.get(&path)?;
+        Ok(Self::with_source(
+            name,
+            TreeSource::Cached { path, cache },
+            &loaded,
+        ))
+    }
+
+    fn with_source(name: impl Into<String>, source: TreeSource, loaded: &LoadedTree) -> Self {
+        let tree = loaded.tree();
+        Self {
+            name: name.into(),
+            version: tree.content_hash(),
+            nodes: tree.node_count(),
+            pages: tree.total_pages,
+            source,
+            document: None,
+            stats: None,
+            normalizer: None,
+        }
+    }
+
+    /// The tree, loading it if it was evicted.
+    fn tree(&self) -> Result<Arc<LoadedTree>> {
+        match &self.source {
+            TreeSource::Resident(loaded) => Ok(loaded.clone()),
+            TreeSource::Cached { path, cache } => cache.get(path),
+        }
+    }
+
+    /// Return section text from the source document as page content.
+    pub fn with_document(mut self, document: Document) -> Self {
+        self.document = Some(document);
+        self
+    }
+
+    /// Count the sections retrieved for each query in a stats file (see
+    /// [`crate::stats`]).
+    pub fn with_stats(mut self, stats: StatsFile) -> Self {
+        self.stats = Some(stats);
+        self
+    }
+
+    /// Normalize queries before searching this index (see
+    /// [`crate::query`]).
+    pub fn with_query_normalizer(mut self, normalizer: QueryNormalizer) -> Self {
+        self.normalizer = Some(normalizer);
+        self
+    }
+
+    /// Record a query's sections in the stats file, if any. A failed save
+    /// is logged rather than failing the query.
+    fn record_stats(&self, documents: &[RetrieverDocument]) {
+        if let Some(stats) = &self.stats
+            && let Err(e) = stats.record(documents)
+        {
+            warn!(index = %self.name, path = %stats.path().display(), "failed to save retrieval stats: {}", e);
+        }
+    }
+
+    /// [`ServedIndex::record_stats`] for an answer's sources.
+    fn record_sources(&self, sources: &[SearchResult]) {
+        if self.stats.is_none() {
+            return;
+        }
+        match self.tree() {
+            Ok(loaded) => self.record_stats(&result_documents(loaded.tree(), sources)),
+            Err(e) => warn!(index = %self.name, "failed to load tree for retrieval stats: {}", e),
+        }
+    }
+
+    fn summary(&self) -> Value {
+        json!({
+            "name": self.name,
+            "nodes": self.nodes,
+            "pages": self.pages,
+        })
+    }
+}
+
+/// An API key and the access labels of whoever holds it.
+struct ApiKey {
+    key: String,
+    /// `None` sees every section.
+    labels: Option<Vec<String>>,
+}
+
+/// A team's indexes, credentials and LLM client.
+///
+/// Give each tenant its own client (not a clone of another tenant's) so
+/// usage is accounted separately.
+pub struct Tenant {
+    name: String,
+    api_keys: Vec<ApiKey>,
+    client: LlmClient,
+    indexes: Vec<ServedIndex>,
+    jobs: JobQueue,
+}
+
+impl Tenant {
+    pub fn new(name: impl Into<String>, client: LlmClient) -> Self {
+        Self {
+            name: name.into(),
+            api_keys: Vec::new(),
+            jobs: JobQueue::new(client.clone()),
+            client,
+            indexes: Vec::new(),
+        }
+    }
+
+    /// Accept this API key for the tenant. Tenants with keys can only be
+    /// reached with one of them.
+    pub fn with_api_key(mut self, key: impl Into<String>) -> Self {
+        self.api_keys.push(ApiKey {
+            key: key.into(),
+            labels: None,
+        });
+        self
+    }
+
+    /// Accept this API key for callers who may only see sections open to
+    /// everyone or tagged with one of `labels`.
+    pub fn with_labeled_api_key(
+        mut self,
+        key: impl Into<String>,
+        labels: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.api_keys.push(ApiKey {
+            key: key.into(),
+            labels: Some(normalize_labels(labels)),
+        });
+        self
+    }
+
+    /// Serve an index; the first one added is the default.
+    pub fn with_index(mut self, index: ServedIndex) -> Self {
+        self.indexes.push(index);
+        self
+    }
+
+    /// The named index, or the default one.
+    fn index(&self, name: Option<&str>) -> std::result::Result<&ServedIndex, ApiError> {
+        match name {
+            Some(name) => self.indexes.iter().find(|i| i.name == name).ok_or_else(|| {
+                ApiError(StatusCode::NOT_FOUND, format!("No index named '{}'", name))
+            }),
+            None => self
+                .indexes
+                .first()
+                .ok_or_else(|| ApiError(StatusCode::NOT_FOUND, "No indexes".to_string())),
+        }
+    }
+
+    /// One of the tenant's jobs.
+    fn job(&self, id: &str) -> std::result::Result<Job, ApiError> {
+        match self.jobs.get(id) {
+            Ok(Some(job)) => Ok(job),
+            Ok(None) => Err(ApiError(
+                StatusCode::NOT_FOUND,
+                format!("No job with ID '{}'", id),
+            )),
+            Err(e) => Err(ApiError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+        }
+    }
+
+    fn api_key(&self, key: &str) -> Option<&ApiKey> {
+        self.api_keys.iter().find(|k| keys_match(&k.key, key))
+    }
+}
+
+/// Labels sorted and without duplicates or blanks, so equal sets compare
+/// (and cache) equal.
+fn normalize_labels(labels: impl IntoIterator<Item = impl Into<String>>) -> Vec<String> {
+    let mut labels: Vec<String> = labels
+        .into_iter()
+        .map(|l| l.into().trim().to_string())
+        .filter(|l| !l.is_empty())
+        .collect();
+    labels.sort();
+    labels.dedup();
+    labels
+}
+
+/// The API key a request carries, if any.
+fn request_key(headers: &HeaderMap) -> Option<&str> {
+    let header = |name| headers.get(name).and_then(|v| v.to_str().ok());
+    header(header::AUTHORIZATION.as_str())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .or_else(|| header("x-api-key"))
+        .map(str::trim)
+}
+
+/// Compare keys in time independent of where they differ.
+fn keys_match(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |acc, (x, y)| acc | (x ^ y))
+            == 0
+}
+
+/// Tenants file (see the module docs).
+#[derive(Deserialize)]
+struct TenantsFile {
+    tenants: Vec<TenantEntry>,
+    /// Load trees on demand, keeping this many megabytes of them.
+    #[serde(default)]
+    tree_cache_mb: Option<usize>,
+    /// Key for the server-wide metrics and stats.
+    #[serde(default)]
+    admin_key: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TenantEntry {
+    name: String,
+    #[serde(default)]
+    api_keys: Vec<KeyEntry>,
+    indexes: Vec<IndexEntry>,
+}
+
+/// An API key, optionally with the access labels of its holder.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum KeyEntry {
+    Key(String),
+    Labeled { key: String, labels: Vec<String> },
+}
+
+#[derive(Deserialize)]
+struct IndexEntry {
+    #[serde(default)]
+    name: Option<String>,
+    path: PathBuf,
+    #[serde(default)]
+    document: Option<PathBuf>,
+    /// Keep retrieval stats next to the index.
+    #[serde(default)]
+    stats: bool,
+}
+
+/// Bytes in a megabyte of `tree_cache_mb`.
+const MEGABYTE: usize = 1024 * 1024;
+
+/// The tenants being served and the server-wide settings.
+pub struct ServeState {
+    tenants: Vec<Tenant>,
+    /// Serving one open tenant: requests need no credentials.
+    single: bool,
+    top_k: usize,
+    cache: Mutex<ResultCache>,
+    answers: AnswerCache,
+    answer_style: AnswerStyle,
+    metrics: Metrics,
+    tree_cache: Option<Arc<TreeCache>>,
+    audit: Option<AuditLog>,
+    admin_key: Option<String>,
+}
+
+impl ServeState {
+    /// Serve a tree to anyone, searching with the given client.
+    pub fn new(tree: DocumentTree, client: LlmClient) -> Self {
+        let index = ServedIndex::new(tree.name.clone(), tree);
+        let tenant = Tenant::new(DEFAULT_TENANT, client).with_index(index);
+        Self::with_tenants(vec![tenant], true)
+    }
+
+    /// Serve several tenants, each reachable only with its own credentials.
+    pub fn multi_tenant(tenants: Vec<Tenant>) -> Result<Self> {
+        let mut names = HashSet::new();
+        let mut keys = HashSet::new();
+        for tenant in &tenants {
+            if !names.insert(tenant.name.as_str()) {
+                return Err(PageIndexError::Config(format!(
+                    "Duplicate tenant '{}'",
+                    tenant.name
+                )));
+            }
+            if tenant.indexes.is_empty() {
+                return Err(PageIndexError::Config(format!(
+                    "Tenant '{}' has no indexes",
+                    tenant.name
+                )));
+            }
+            let mut index_names = HashSet::new();
+            for index in &tenant.indexes {
+                if !index_names.insert(index.name.as_str()) {
+                    return Err(PageIndexError::Config(format!(
+                        "Tenant '{}' has two indexes named '{}'",
+                        tenant.name, index.name
+                    )));
+                }
+            }
+            for key in &tenant.api_keys {
+                if key.key.is_empty() || !keys.insert(key.key.as_str()) {
+                    return Err(PageIndexError::Config(format!(
+                        "Tenant '{}' has an empty or shared API key",
+                        tenant.name
+                    )));
+                }
+            }
+        }
+        if tenants.is_empty() {
+            return Err(PageIndexError::Config("No tenants".to_string()));
+        }
+        Ok(Self::with_tenants(tenants, false))
+    }
+
+    /// Load tenants and their indexes from a YAML file. Each tenant gets
+    /// its own client for `llm`.
+    pub fn from_tenants_file(path: &Path, llm: &LlmConfig) -> Result<Self> {
+        let content = std::fs::read_to_string(path).map_err(|e| PageIndexError::io(path, e))?;
+        let file: TenantsFile = serde_yaml::from_str(&content)
+            .map_err(|e| PageIndexError::Config(format!("Failed to parse tenants file: {}", e)))?;
+        let base = path.parent().unwrap_or(Path::new(""));
+        let tree_cache = file
+            .tree_cache_mb
+            .map(|mb| Arc::new(TreeCache::new(mb.saturating_mul(MEGABYTE))));
+
+        let mut tenants = Vec::new();
+        for entry in file.tenants {
+            let mut tenant = Tenant::new(entry.name, LlmClient::new(llm.clone()));
+            for key in entry.api_keys {
+                tenant = match key {
+                    KeyEntry::Key(key) => tenant.with_api_key(key),
+                    KeyEntry::Labeled { key, labels } => tenant.with_labeled_api_key(key, labels),
+                };
+            }
+            for index in entry.indexes {
+                let tree_path = base.join(&index.path);
+                let name = index.name.unwrap_or_else(|| {
+                    tree_path
+                        .file_stem()
+                        .and_then(|s| s.to_str())
+                        .unwrap_or("untitled")
+                        .to_string()
+                });
+                let mut served = match &tree_cache {
+                    Some(cache) => ServedIndex::cached(name, &tree_path, cache.clone())?,
+                    None => ServedIndex::new(name, load_tree(&tree_path)?),
+                };
+                if let Some(document) = index.document {
+                    served = served.with_document(crate::loader::load(&base.join(document))?);
+                }
+                if index.stats {
+                    served = served.with_stats(StatsFile::open(stats_path_for(&tree_path))?);
+                }
+                if let Some(normalizer) = QueryNormalizer::for_index(&tree_path)? {
+                    served = served.with_query_normalizer(normalizer);
+                }
+                tenant = tenant.with_index(served);
+            }
+            tenants.push(tenant);
+        }
+        let mut state = Self::multi_tenant(tenants)?;
+        if let Some(key) = file.admin_key {
+            state = state.with_admin_key(key)?;
+        }
+        Ok(match tree_cache {
+            Some(cache) => state.with_tree_cache(cache),
+            None => state,
+        })
+    }
+
+    fn with_tenants(tenants: Vec<Tenant>, single: bool) -> Self {
+        Self {
+            tenants,
+            single,
+            top_k: DEFAULT_TOP_K,
+            cache: Mutex::new(ResultCache::new(DEFAULT_CACHE_SIZE)),
+            answers: AnswerCache::default(),
+            answer_style: AnswerStyle::default(),
+            metrics: Metrics::new(),
+            tree_cache: None,
+            audit: None,
+            admin_key: None,
+        }
+    }
+
+    /// Return section text from the source document as page content
+    /// (single-index servers).
+    pub fn with_document(mut self, document: Document) -> Self {
+        if let Some(index) = self.tenants.first_mut().and_then(|t| t.indexes.first_mut()) {
+            index.document = Some(document);
+        }
+        self
+    }
+
+    /// Count the sections retrieved for each query in a stats file
+    /// (single-index servers).
+    pub fn with_stats(mut self, stats: StatsFile) -> Self {
+        if let Some(index) = self.tenants.first_mut().and_then(|t| t.indexes.first_mut()) {
+            index.stats = Some(stats);
+        }
+        self
+    }
+
+    /// Normalize queries before searching (single-index servers).
+    pub fn with_query_normalizer(mut self, normalizer: QueryNormalizer) -> Self {
+        if let Some(index) = self.tenants.first_mut().and_then(|t| t.indexes.first_mut()) {
+            index.normalizer = Some(normalizer);
+        }
+        self
+    }
+
+    /// Set the default number of documents per query.
+    pub fn with_top_k(mut self, top_k: usize) -> Self {
+        self.top_k = top_k.max(1);
+        self
+    }
+
+    /// Set how many query results to cache (0 disables the cache).
+    pub fn with_cache_size(mut self, size: usize) -> Self {
+        self.cache = Mutex::new(ResultCache::new(size));
+        self
+    }
+
+    /// Set the answer cache (by default 256 answers for an hour).
+    pub fn with_answer_cache(mut self, cache: AnswerCache) -> Self {
+        self.answers = cache;
+        self
+    }
+
+    /// Set the default answer style, which requests may override.
+    pub fn with_answer_style(mut self, style: AnswerStyle) -> Self {
+        self.answer_style = style;
+        self
+    }
+
+    /// Report the cache the indexes built with [`ServedIndex::cached`]
+    /// share in `GET /health`.
+    pub fn with_tree_cache(mut self, cache: Arc<TreeCache>) -> Self {
+        self.tree_cache = Some(cache);
+        self
+    }
+
+    /// Append each retrieval and answer to an audit log (see
+    /// [`crate::audit`]).
+    pub fn with_audit_log(mut self, log: AuditLog) -> Self {
+        self.audit = Some(log);
+        self
+    }
+
+    /// Show `GET /metrics` and the tree cache's stats in `GET /health` to
+    /// requests carrying this key. A multi-tenant server shows them to no
+    /// one without it; a single open server shows them to anyone. The key
+    /// may not be empty or one of a tenant's.
+    pub fn with_admin_key(mut self, key: impl Into<String>) -> Result<Self> {
+        let key = key.into();
+        if key.is_empty() || self.tenants.iter().any(|t| t.api_key(&key).is_some()) {
+            return Err(PageIndexError::Config(
+                "The admin key is empty or a tenant's API key".to_string(),
+            ));
+        }
+        self.admin_key = Some(key);
+        Ok(self)
+    }
+
+    /// Whether a request may see server-wide metrics and stats.
+    fn is_admin(&self, headers: &HeaderMap) -> bool {
+        if self.single {
+            return true;
+        }
+        match (&self.admin_key, request_key(headers)) {
+            (Some(admin), Some(key)) => keys_match(admin, key),
+            _ => false,
+        }
+    }
+
+    /// The audit log, if any.
+    pub fn audit_log(&self) -> Option<&AuditLog> {
+        self.audit.as_ref()
+    }
+
+    /// Append a request to the audit log, if any. A failed write fails
+    /// the request.
+    fn audit(&self, tenant: &Tenant, index: &ServedIndex, mut record: AuditRecord) -> Result<()> {
+        let Some(log) = &self.audit else {
+            return Ok(());
+        };
+        record.tenant = Some(tenant.name.clone());
+        record.index = Some(index.name.clone());
+        log.record(&record)
+    }
+
+    /// The tree cache's size and use, if trees are loaded on demand.
+    pub fn tree_cache_stats(&self) -> Option<TreeCacheStats> {
+        self.tree_cache.as_ref().map(|cache| cache.stats())
+    }
+
+    /// Tenant names and their index counts.
+    pub fn tenants(&self) -> Vec<(&str, usize)> {
+        self.tenants
+            .iter()
+            .map(|t| (t.name.as_str(), t.indexes.len()))
+            .collect()
+    }
+
+    fn cache(&self) -> std::sync::MutexGuard<'_, ResultCache> {
+        self.cache.lock().expect("cache lock poisoned")
+    }
+
+    /// The tenant a request is for.
+    fn tenant(&self, headers: &HeaderMap) -> std::result::Result<&Tenant, ApiError> {
+        if self.single {
+            return Ok(&self.tenants[0]);
+        }
+        let header = |name| headers.get(name).and_then(|v| v.to_str().ok());
+        if let Some(key) = request_key(headers) {
+            return self
+                .tenants
+                .iter()
+                .find(|t| t.api_key(key).is_some())
+                .ok_or_else(|| ApiError(StatusCode::UNAUTHORIZED, "Unknown API key".to_string()));
+        }
+        if let Some(name) = header("x-tenant") {
+            let tenant = self
+                .tenants
+                .iter()
+                .find(|t| t.name == name)
+                .ok_or_else(|| {
+                    ApiError(StatusCode::NOT_FOUND, format!("Unknown tenant '{}'", name))
+                })?;
+            if !tenant.api_keys.is_empty() {
+                return Err(ApiError(
+                    StatusCode::UNAUTHORIZED,
+                    format!("Tenant '{}' requires an API key", name),
+                ));
+            }
+            return Ok(tenant);
+        }
+        Err(ApiError(
+            StatusCode::UNAUTHORIZED,
+            "Missing API key or X-Tenant header".to_string(),
+        ))
+    }
+
+    /// The caller's access labels (see the module docs): those of the API
+    /// key used or, for tenants without keys, the `X-Acl-Labels` header.
+    /// `None` sees every section. Without the header (or with one that
+    /// isn't text), the caller holds no labels and sees only unlabeled
+    /// sections.
+    fn labels(&self, tenant: &Tenant, headers: &HeaderMap) -> Option<Vec<String>> {
+        if tenant.api_keys.is_empty() {
+            let header = headers
+                .get(ACL_LABELS_HEADER)
+                .and_then(|h| h.to_str().ok())
+                .unwrap_or_default();
+            return Some(normalize_labels(header.split(',')));
+        }
+        tenant
+            .api_key(request_key(headers)?)
+            .and_then(|key| key.labels.clone())
+    }
+
+    /// Search and convert the results to documents. Returns whether the
+    /// deadline cut the search short.
+    #[instrument(
+        name = "serve.retrieve",
+        skip_all,
+        fields(tenant = %tenant.name, index = %index.name, query, otel.status_code = Empty)
+    )]
+    async fn retrieve(
+        &self,
+        tenant: &Tenant,
+        index: &ServedIndex,
+        query: &str,
+        top_k: Option<usize>,
+        deadline: Option<Duration>,
+        labels: Option<Vec<String>>,
+    ) -> Result<(Vec<RetrieverDocument>, bool)> {
+        let options = SearchOptions {
+            top_k: top_k.unwrap_or(self.top_k).max(1),
+            acl_labels: labels.clone(),
+            deadline,
+            normalizer: index.normalizer.clone(),
+            ..SearchOptions::default()
+        };
+        let key = (
+            tenant.name.clone(),
+            index.name.clone(),
+            query.trim().to_string(),
+            options.top_k,
+            labels,
+        );
+        let cached = self.cache().get(&key);
+        self.metrics
+            .record_cache(&tenant.name, &index.name, cached.is_some());
+        if let Some(documents) = cached {
+            index.record_stats(&documents);
+            let mut record = AuditRecord::search(query, &documents);
+            record.cached = true;
+            record.usage = Some(Default::default());
+            self.audit(tenant, index, record)?;
+            return Ok((documents, false));
+        }
+
+        self.metrics.record_search(&tenant.name, &index.name);
+        let loaded = index.tree()?;
+        let tree = loaded.tree();
+        let client = tenant.client.metered();
+        let searcher = TreeSearcher::with_options(client.clone(), options.clone())
+            .with_search_formats(loaded.formats());
+        let outcome = match &index.document {
+            Some(document) => {
+                searcher
+                    .search_with_content_within(tree, document, query)
+                    .await
+            }
+            None => searcher.search_within(tree, query).await,
+        };
+        let SearchOutcome {
+            mut results,
+            truncated,
+        } = outcome.inspect_err(|e| telemetry::record_error("serve.retrieve", e))?;
+        results.truncate(options.top_k);
+        let documents = result_documents(tree, &results);
+        index.record_stats(&documents);
+        let mut record = AuditRecord::search(query, &documents);
+        record.usage = Some(client.usage());
+        self.audit(tenant, index, record)?;
+        if !truncated {
+            self.cache().insert(key, documents.clone());
+        }
+        Ok((documents, truncated))
+    }
+}
+
+impl ServeState {
+    /// Answer a question from the index, or from the answer cache. Returns
+    /// whether the answer was cached.
+    #[instrument(
+        name = "serve.ask",
+        skip_all,
+        fields(tenant = %tenant.name, index = %index.name, cached = Empty)
+    )]
+    async fn ask(
+        &self,
+        tenant: &Tenant,
+        index: &ServedIndex,
+        question: &str,
+        style: &AnswerStyle,
+        labels: Option<Vec<String>>,
+    ) -> Result<(Answer, bool)> {
+        let key = AnswerKey::new(
+            question,
+            answer_version(tenant, index, labels.as_deref()),
+            tenant.client.model(),
+        )
+        .with_style(style.clone());
+        let cached = self.answers.get(&key);
+        tracing::Span::current().record("cached", cached.is_some());
+        if let Some(answer) = cached {
+            index.record_sources(&answer.sources);
+            if self.audit.is_some() {
+                let loaded = index.tree()?;
+                let mut record = AuditRecord::ask(question, &answer, loaded.tree());
+                record.cached = true;
+                record.usage = Some(Default::default());
+                self.audit(tenant, index, record)?;
+            }
+            return Ok((answer, true));
+        }
+
+        self.metrics.record_search(&tenant.name, &index.name);
+        let loaded = index.tree()?;
+        let tree = loaded.tree();
+        let client = tenant.client.metered();
+        // Sources carry only the pages the caller may see, so answers quote
+        // the document only when the labels hide none of it
+        let document = match &labels {
+            Some(labels) if !tree.hidden_pages(labels).is_empty() => None,
+            _ => index.document.as_ref(),
+        };
+        let searcher = TreeSearcher::with_options(
+            client.clone(),
+            SearchOptions {
+                top_k: self.top_k,
+                acl_labels: labels,
+                normalizer: index.normalizer.clone(),
+                ..SearchOptions::default()
+            },
+        )
+        .with_search_formats(loaded.formats());
+        let sources = match &index.document {
+            Some(document) => searcher.search_with_content(tree, document, question).await,
+            None => searcher.search(tree, question).await,
+        };
+        let answer = match sources {
+            Ok(sources) => {
+                let terms = terms_in(question, &[&tree.terms]);
+                answer_from(&client, question, sources, style, &terms, document).await
+            }
+            Err(e) => Err(e),
+        }
+        .inspect_err(|e| telemetry::record_error("serve.ask", e))?;
+        index.record_sources(&answer.sources);
+        let mut record = AuditRecord::ask(question, &answer, tree);
+        record.usage = Some(client.usage());
+        self.audit(tenant, index, record)?;
+        self.answers.insert(key, answer.clone());
+        Ok((answer, false))
+    }
+}
+
+/// Version under which a tenant's index caches answers. Scoped to the
+/// tenant and index, since indexes with the same tree may differ in their
+/// source documents, and to the caller's access labels, since callers
+/// with different labels see different sections.
+fn answer_version(tenant: &Tenant, index: &ServedIndex, labels: Option<&[String]>) -> String {
+    let version = format!("{}/{}@{}", tenant.name, index.name, index.version);
+    match labels {
+        Some(labels) => format!("{}#{}", version, labels.join(",")),
+        None => version,
+    }
+}
+
+/// `RemoteLangChainRetriever` request body.
+#[derive(Deserialize)]
+struct RetrieveRequest {
+    #[serde(alias = "query")]
+    message: String,
+    #[serde(default)]
+    top_k: Option<usize>,
+    #[serde(default)]
+    deadline_ms: Option<u64>,
+    #[serde(default)]
+    index: Option<String>,
+    #[serde(flatten)]
+    page: Pagination,
+}
+
+/// LangServe `/invoke` request body.
+#[derive(Deserialize)]
+struct InvokeRequest {
+    input: InvokeInput,
+    #[serde(default)]
+    top_k: Option<usize>,
+    #[serde(default)]
+    deadline_ms: Option<u64>,
+    #[serde(default)]
+    index: Option<String>,
+    #[serde(flatten)]
+    page: Pagination,
+}
+
+/// The page a request asks for (see the module docs).
+#[derive(Debug, Default, Deserialize)]
+struct Pagination {
+    #[serde(default)]
+    cursor: Option<String>,
+    #[serde(default)]
+    page_size: Option<usize>,
+}
+
+impl Pagination {
+    fn is_requested(&self) -> bool {
+        self.cursor.is_some() || self.page_size.is_some()
+    }
+
+    /// The requested page of `items` and the cursor of the next one, if
+    /// there is one. Cursors are the offset of a page's first item, but
+    /// clients should treat them as opaque.
+    fn page<T: Clone>(
+        &self,
+        items: &[T],
+    ) -> std::result::Result<(Vec<T>, Option<String>), ApiError> {
+        let start = match &self.cursor {
+            Some(cursor) => cursor
+                .parse::<usize>()
+                .ok()
+                .filter(|&offset| offset <= items.len())
+                .ok_or_else(|| {
+                    ApiError(
+                        StatusCode::BAD_REQUEST,
+                        format!("Invalid cursor '{}'", cursor),
+                    )
+                })?,
+            None => 0,
+        };
+        let size = self
+            .page_size
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE);
+        let end = (start + size).min(items.len());
+        let next = (end < items.len()).then(|| end.to_string());
+        Ok((items[start..end].to_vec(), next))
+    }
+
+    /// The requested page of `items` and the `next_cursor` to return
+    /// with it, or all of `items` and no cursor when no page was asked for.
+    fn apply<T: Clone>(
+        &self,
+        items: &[T],
+    ) -> std::result::Result<(Vec<T>, Option<Value>), ApiError> {
+        if !self.is_requested() {
+            return Ok((items.to_vec(), None));
+        }
+        let (page, next) = self.page(items)?;
+        Ok((page, Some(json!(next))))
+    }
+}
+
+/// `GET /trees/{index}/nodes` query parameters. Not flattened from
+/// [`Pagination`], which query strings can't fill with numbers.
+#[derive(Deserialize)]
+struct NodesQuery {
+    #[serde(default)]
+    parent: Option<String>,
+    #[serde(default)]
+    depth: Option<usize>,
+    #[serde(default)]
+    cursor: Option<String>,
+    #[serde(default)]
+    page_size: Option<usize>,
+}
+
+/// A retriever's input is the query string, but accept `{"query": ...}` too.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum InvokeInput {
+    Query(String),
+    Object {
+        #[serde(alias = "message")]
+        query: String,
+    },
+}
+
+/// `/ask` request body.
+#[derive(Deserialize)]
+struct AskRequest {
+    #[serde(alias = "query", alias = "message")]
+    question: String,
+    #[serde(default)]
+    index: Option<String>,
+    #[serde(default)]
+    length: Option<AnswerLength>,
+    #[serde(default)]
+    format: Option<AnswerFormat>,
+    #[serde(default)]
+    language: Option<String>,
+    #[serde(default)]
+    required_relevance: Option<Relevance>,
+}
+
+impl AskRequest {
+    /// The server's answer style with the request's overrides.
+    fn style(&self, default: &AnswerStyle) -> AnswerStyle {
+        AnswerStyle {
+            length: self.length.unwrap_or(default.length),
+            format: self.format.unwrap_or(default.format),
+            language: self.language.clone().or_else(|| default.language.clone()),
+            required_relevance: self.required_relevance.or(default.required_relevance),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct JobRequest {
+    name: String,
+    content: String,
+}
+
+/// An error response: `{"error": "<message>"}`.
+struct ApiError(StatusCode, String);
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response {
+        (self.0, Json(json!({ "error": self.1 }))).into_response()
+    }
+}
+
+impl From<PageIndexError> for ApiError {
+    /// Search failures are upstream (LLM) failures.
+    fn from(err: PageIndexError) -> Self {
+        warn!(error = %err, "retrieval failed");
+        let status = match err {
+            PageIndexError::RateLimited { .. } => StatusCode::SERVICE_UNAVAILABLE,
+            // A served tree that could not be reloaded
+            PageIndexError::IndexNotFound(_)
+            | PageIndexError::Io { .. }
+            | PageIndexError::Serialization(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            PageIndexError::Timeout(_) => StatusCode::GATEWAY_TIMEOUT,
+            _ => StatusCode::BAD_GATEWAY,
+        };
+        ApiError(status, err.to_string())
+    }
+}
+
+/// Liveness, plus the caller's indexes and usage when the request
+/// identifies a tenant, and the tree cache's stats for the admin.
+async fn health(State(state): State<Arc<ServeState>>, headers: HeaderMap) -> Json<Value> {
+    let mut body = match state.tenant(&headers) {
+        Ok(tenant) => {
+            let default = tenant.indexes.first();
+            json!({
+                "status": "ok",
+                "tenant": tenant.name,
+                "index": default.map(|i| &i.name),
+                "nodes": default.map(|i| i.nodes),
+                "indexes": tenant.indexes.iter().map(ServedIndex::summary).collect::<Vec<_>>(),
+                "usage": tenant.client.usage(),
+            })
+        }
+        Err(_) => json!({ "status": "ok" }),
+    };
+    if state.is_admin(&headers)
+        && let Some(stats) = state.tree_cache_stats()
+    {
+        body["tree_cache"] = json!(stats);
+    }
+    Json(body)
+}
+
+async fn indexes(
+    State(state): State<Arc<ServeState>>,
+    headers: HeaderMap,
+    Query(page): Query<Pagination>,
+) -> std::result::Result<Json<Value>, ApiError> {
+    let tenant = state.tenant(&headers)?;
+    let indexes: Vec<Value> = tenant.indexes.iter().map(ServedIndex::summary).collect();
+    let (indexes, next) = page.apply(&indexes)?;
+    let mut body = json!({ "indexes": indexes });
+    if let Some(next) = next {
+        body["next_cursor"] = next;
+    }
+    Ok(Json(body))
+}
+
+/// A page of an index's tree, as much of it as the caller may see.
+async fn tree_nodes(
+    State(state): State<Arc<ServeState>>,
+    UrlPath(index): UrlPath<String>,
+    headers: HeaderMap,
+    Query(query): Query<NodesQuery>,
+) -> std::result::Result<Json<Value>, ApiError> {
+    let tenant = state.tenant(&headers)?;
+    let index = tenant.index(Some(&index))?;
+    let loaded = index.tree()?;
+    let tree = match state.labels(tenant, &headers) {
+        Some(labels) if loaded.tree().has_acl() => Cow::Owned(loaded.tree().visible_to(&labels)),
+        _ => Cow::Borrowed(loaded.tree()),
+    };
+    let nodes = match &query.parent {
+        Some(parent) => {
+            &tree
+                .find_node(parent)
+                .ok_or_else(|| {
+                    ApiError(
+                        StatusCode::NOT_FOUND,
+                        format!("No node with node_id or structure '{}'", parent),
+                    )
+                })?
+                .nodes
+        }
+        None => &tree.nodes,
+    };
+
+    let page = Pagination {
+        cursor: query.cursor,
+        page_size: query.page_size,
+    };
+    let (page, next) = page.page(nodes)?;
+    let depth = query.depth.unwrap_or(1).max(1);
+    Ok(Json(json!({
+        "index": index.name,
+        "parent": query.parent,
+        "nodes": page.iter().map(|node| node_json(node, depth)).collect::<Vec<_>>(),
+        "next_cursor": next,
+    })))
+}
+
+/// A node with `depth - 1` levels of its subsections, and how many
+/// subsections it has, shown or not.
+fn node_json(node: &TreeNode, depth: usize) -> Value {
+    let mut value = json!(TreeNode {
+        nodes: Vec::new(),
+        embedding: None,
+        ..node.clone()
+    });
+    value["child_count"] = json!(node.nodes.len());
+    if depth > 1 && !node.nodes.is_empty() {
+        value["nodes"] = node
+            .nodes
+            .iter()
+            .map(|child| node_json(child, depth - 1))
+            .collect();
+    }
+    value
+}
+
+/// Server-wide metrics, for the admin only (see [`ServeState::with_admin_key`]).
+async fn metrics(
+    State(state): State<Arc<ServeState>>,
+    headers: HeaderMap,
+) -> std::result::Result<impl IntoResponse, ApiError> {
+    if !state.is_admin(&headers) {
+        return Err(ApiError(
+            StatusCode::FORBIDDEN,
+            "Metrics need the admin key".to_string(),
+        ));
+    }
+    let usage: Vec<_> = state
+        .tenants
+        .iter()
+        .map(|t| (t.name.as_str(), t.client.usage()))
+        .collect();
+    Ok((
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics.render(&usage),
+    ))
+}
+
+/// Count each request and its latency under its route.
+async fn track(State(state): State<Arc<ServeState>>, request: Request, next: Next) -> Response {
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or("unmatched", |path| path.as_str())
+        .to_string();
+    let start = Instant::now();
+    let response = next.run(request).await;
+    state
+        .metrics
+        .record_request(&route, response.status().as_u16(), start.elapsed());
+    response
+}
+
+/// Resolve the tenant and index, then search.
+async fn search(
+    state: &ServeState,
+    headers: &HeaderMap,
+    index: Option<&str>,
+    query: &str,
+    top_k: Option<usize>,
+    deadline_ms: Option<u64>,
+) -> std::result::Result<(Vec<RetrieverDocument>, bool), ApiError> {
+    let tenant = state.tenant(headers)?;
+    let index = tenant.index(index)?;
+    let labels = state.labels(tenant, headers);
+    info!(tenant = %tenant.name, index = %index.name, query = %query, "retrieve");
+    let deadline = deadline_ms.map(Duration::from_millis);
+    Ok(state
+        .retrieve(tenant, index, query, top_k, deadline, labels)
+        .await?)
+}
+
+async fn retrieve(
+    State(state): State<Arc<ServeState>>,
+    headers: HeaderMap,
+    Json(request): Json<RetrieveRequest>,
+) -> std::result::Result<Json<Value>, ApiError> {
+    let index = request.index.as_deref();
+    let (documents, truncated) = search(
+        &state,
+        &headers,
+        index,
+        &request.message,
+        request.top_k,
+        request.deadline_ms,
+    )
+    .await?;
+    retrieve_response(&documents, truncated, &request.page)
+}
+
+/// `{"response": [Document, ...]}`, paged if asked.
+fn retrieve_response(
+    documents: &[RetrieverDocument],
+    truncated: bool,
+    page: &Pagination,
+) -> std::result::Result<Json<Value>, ApiError> {
+    let (documents, next) = page.apply(documents)?;
+    let mut body = json!({ "response": documents });
+    if let Some(next) = next {
+        body["next_cursor"] = next;
+    }
+    if truncated {
+        body["truncated"] = json!(true);
+    }
+    Ok(Json(body))
+}
+
+async fn retrieve_index(
+    State(state): State<Arc<ServeState>>,
+    UrlPath(index): UrlPath<String>,
+    headers: HeaderMap,
+    Json(request): Json<RetrieveRequest>,
+) -> std::result::Result<Json<Value>, ApiError> {
+    let (documents, truncated) = search(
+        &state,
+        &headers,
+        Some(&index),
+        &request.message,
+        request.top_k,
+        request.deadline_ms,
+    )
+    .await?;
+    retrieve_response(&documents, truncated, &request.page)
+}
+
+async fn invoke(
+    State(state): State<Arc<ServeState>>,
+    headers: HeaderMap,
+    Json(request): Json<InvokeRequest>,
+) -> std::result::Result<Json<Value>, ApiError> {
+    let index = request.index.clone();
+    invoke_response(&state, &headers, index.as_deref(), request).await
+}
+
+async fn invoke_index(
+    State(state): State<Arc<ServeState>>,
+    UrlPath(index): UrlPath<String>,
+    headers: HeaderMap,
+    Json(request): Json<InvokeRequest>,
+) -> std::result::Result<Json<Value>, ApiError> {
+    invoke_response(&state, &headers, Some(&index), request).await
+}
+
+async fn invoke_response(
+    state: &ServeState,
+    headers: &HeaderMap,
+    index: Option<&str>,
+    request: InvokeRequest,
+) -> std::result::Result<Json<Value>, ApiError> {
+    let query = match request.input {
+        InvokeInput::Query(query) | InvokeInput::Object { query } => query,
+    };
+    let (documents, truncated) = search(
+        state,
+        headers,
+        index,
+        &query,
+        request.top_k,
+        request.deadline_ms,
+    )
+    .await?;
+    let (documents, next) = request.page.apply(&documents)?;
+    let mut body = json!({ "output": documents, "metadata": {} });
+    if let Some(next) = next {
+        body["metadata"]["next_cursor"] = next;
+    }
+    if truncated {
+        body["metadata"]["truncated"] = json!(true);
+    }
+    Ok(Json(body))
+}
+
+/// Resolve the tenant and index, then answer.
+async fn answer(
+    state: &ServeState,
+    headers: &HeaderMap,
+    index: Option<&str>,
+    request: &AskRequest,
+) -> std::result::Result<Json<Value>, ApiError> {
+    let tenant = state.tenant(headers)?;
+    let index = tenant.index(index)?;
+    let labels = state.labels(tenant, headers);
+    let question = &request.question;
+    info!(tenant = %tenant.name, index = %index.name, question = %question, "ask");
+    let style = request.style(&state.answer_style);
+    let (answer, cached) = state.ask(tenant, index, question, &style, labels).await?;
+    Ok(Json(json!({
+        "answer": answer.answer,
+        "sources": answer.sources,
+        "outcome": answer.outcome,
+        "cached": cached,
+    })))
+}
+
+async fn ask(
+    State(state): State<Arc<ServeState>>,
+    headers: HeaderMap,
+    Json(request): Json<AskRequest>,
+) -> std::result::Result<Json<Value>, ApiError> {
+    answer(&state, &headers, request.index.as_deref(), &request).await
+}
+
+async fn ask_index(
+    State(state): State<Arc<ServeState>>,
+    UrlPath(index): UrlPath<String>,
+    headers: HeaderMap,
+    Json(request): Json<AskRequest>,
+) -> std::result::Result<Json<Value>, ApiError> {
+    answer(&state, &headers, Some(&index), &request).await
+}
+
+/// Drop the cached answers of the caller's indexes.
+async fn clear_answers(
+    State(state): State<Arc<ServeState>>,
+    headers: HeaderMap,
+) -> std::result::Result<Json<Value>, ApiError> {
+    let tenant = state.tenant(&headers)?;
+    let invalidated: usize = tenant
+        .indexes
+        .iter()
+        .map(|index| {
+            // Every caller's answers, whatever their labels
+            let version = answer_version(tenant, index, None);
+            let scoped = format!("{}#", version);
+            state
+                .answers
+                .invalidate_versions(|v| v == version || v.starts_with(&scoped))
+        })
+        .sum();
+    info!(tenant = %tenant.name, invalidated, "answers invalidated");
+    Ok(Json(json!({ "invalidated": invalidated })))
+}
+
+async fn submit_job(
+    State(state): State<Arc<ServeState>>,
+    headers: HeaderMap,
+    Json(request): Json<JobRequest>,
+) -> std::result::Result<(StatusCode, Json<Job>), ApiError> {
+    let tenant = state.tenant(&headers)?;
+    if request.content.trim().is_empty() {
+        return Err(ApiError(
+            StatusCode::BAD_REQUEST,
+            "Document content is empty".to_string(),
+        ));
+    }
+    let document = Document::from_text(request.name, request.content);
+    let job = tenant
+        .jobs
+        .submit(document, None)
+        .map_err(|e| ApiError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    info!(tenant = %tenant.name, job = %job.id, document = %job.document, "job submitted");
+    Ok((StatusCode::ACCEPTED, Json(job)))
+}
+
+async fn jobs(
+    State(state): State<Arc<ServeState>>,
+    headers: HeaderMap,
+) -> std::result::Result<Json<Value>, ApiError> {
+    let tenant = state.tenant(&headers)?;
+    let jobs = tenant
+        .jobs
+        .list()
+        .map_err(|e| ApiError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(json!({ "jobs": jobs })))
+}
+
+async fn job(
+    State(state): State<Arc<ServeState>>,
+    UrlPath(id): UrlPath<String>,
+    headers: HeaderMap,
+) -> std::result::Result<Json<Job>, ApiError> {
+    Ok(Json(state.tenant(&headers)?.job(&id)?))
+}
+
+async fn job_tree(
+    State(state): State<Arc<ServeState>>,
+    UrlPath(id): UrlPath<String>,
+    headers: HeaderMap,
+) -> std::result::Result<Json<DocumentTree>, ApiError> {
+    let tenant = state.tenant(&headers)?;
+    let job = tenant.job(&id)?;
+    let tree = match job.state {
+        JobState::Completed => tenant
+            .jobs
+            .tree(&id)
+            .map_err(|e| ApiError(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?,
+        JobState::Failed => None,
+        JobState::Queued | JobState::Running => {
+            return Err(ApiError(
+                StatusCode::CONFLICT,
+                format!("Job '{}' has not completed", id),
+            ));
+        }
+    };
+    tree.map(Json).ok_or_else(|| {
+        ApiError(
+            StatusCode::CONFLICT,
+            format!("Job '{}' failed: {}", id, job.error.unwrap_or_default()),
+        )
+    })
+}
+
+/// Routes for the served indexes.
+pub fn router(state: ServeState) -> Router {
+    let state = Arc::new(state);
+    Router::new()
+        .route("/health", get(health))
+        .route("/metrics", get(metrics))
+        .route("/indexes", get(indexes))
+        .route("/trees/{index}/nodes", get(tree_nodes))
+        .route("/retrieve", post(retrieve))
+        .route("/invoke", post(invoke))
+        .route("/indexes/{index}/retrieve", post(retrieve_index))
+        .route("/indexes/{index}/invoke", post(invoke_index))
+        .route("/ask", post(ask))
+        .route("/indexes/{index}/ask", post(ask_index))
+        .route("/answers", delete(clear_answers))
+        .route("/jobs", get(jobs).post(submit_job))
+        .route("/jobs/{id}", get(job))
+        .route("/jobs/{id}/tree", get(job_tree))
+        .route_layer(middleware::from_fn_with_state(state.clone(), track))
+        .with_state(state)
+}
+
+/// Serve until Ctrl-C, then finish in-flight requests.
+pub async fn serve(addr: SocketAddr, state: ServeState) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(addr)
+        .await
+        .map_err(|e| PageIndexError::Server(format!("Failed to bind {}: {}", addr, e)))?;
+    info!(%addr, tenants = state.tenants.len(), "serving");
+    axum::serve(listener, router(state))
+        .with_graceful_shutdown(async {
+            let _ = tokio::signal::ctrl_c().await;
+        })
+        .await
+        .map_err(|e| PageIndexError::Server(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LlmConfig;
+    use crate::fixtures::offline_client;
+    use crate::page_index::AnswerOutcome;
+    use crate::tree::TreeNode;
+    use axum::body::{Body, to_bytes};
+    use axum::http::Request;
+    use tower::ServiceExt;
+
+    fn test_tree(name: &str) -> DocumentTree {
+        DocumentTree::new(name, vec![TreeNode::new("Introduction", 1, 1)], 1)
+    }
+
+    fn test_router() -> Router {
+        router(ServeState::new(test_tree("report"), offline_client()))
+    }
+
+    async fn send(router: Router, request: Request<Body>) -> (StatusCode, Value) {
+        let response = router.oneshot(request).await.unwrap();
+        let status = response.status();
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap_or(Value::Null))
+    }
+
+    fn post_json(uri: &str, body: Value) -> Request<Body> {
+        Request::post(uri)
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_health() {
+        let request = Request::get("/health").body(Body::empty()).unwrap();
+        let (status, body) = send(test_router(), request).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["index"], "report");
+        assert_eq!(body["nodes"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_retrieve_requests() {
+        // Well-formed requests reach the LLM, which is unreachable here
+        for (uri, body) in [
+            ("/retrieve", json!({ "message": "intro" })),
+            ("/retrieve", json!({ "query": "intro", "top_k": 2 })),
+            ("/invoke", json!({ "input": "intro" })),
+            ("/invoke", json!({ "input": { "query": "intro" } })),
+        ] {
+            let (status, body) = send(test_router(), post_json(uri, body)).await;
+            assert_eq!(status, StatusCode::BAD_GATEWAY, "{}", uri);
+            assert!(
+                body["error"]
+                    .as_str()
+                    .unwrap()
+                    .contains("HTTP request failed")
+            );
+        }
+
+        let (status, _) = send(test_router(), post_json("/retrieve", json!({}))).await;
+        assert!(status.is_client_error());
+
+        // A deadline already past: no LLM call, an empty truncated result
+        let (status, body) = send(
+            test_router(),
+            post_json("/retrieve", json!({ "message": "intro", "deadline_ms": 0 })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, json!({ "response": [], "truncated": true }));
+        let (_, body) = send(
+            test_router(),
+            post_json("/invoke", json!({ "input": "intro", "deadline_ms": 0 })),
+        )
+        .await;
+        assert_eq!(body["metadata"]["truncated"], json!(true));
+    }
+
+    #[tokio::test]
+    async fn test_ask_cache() {
+        let cache = AnswerCache::new(8);
+        let state =
+            ServeState::new(test_tree("report"), offline_client()).with_answer_cache(cache.clone());
+        let tenant = &state.tenants[0];
+        let version = answer_version(tenant, &tenant.indexes[0], Some(&[]));
+        let model = tenant.client.model().to_string();
+        let router = router(state);
+        let ask = || post_json("/ask", json!({ "question": "What is the intro?" }));
+
+        let (status, _) = send(router.clone(), ask()).await;
+        assert_eq!(status, StatusCode::BAD_GATEWAY);
+
+        // An answer to the same normalized question skips the LLM
+        let answer = Answer {
+            answer: "An overview.".to_string(),
+            sources: Vec::new(),
+            outcome: AnswerOutcome::Answered,
+        };
+        cache.insert(
+            AnswerKey::new("what is the intro", version.clone(), model.clone()),
+            answer.clone(),
+        );
+        let (status, body) = send(router.clone(), ask()).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["answer"], "An overview.");
+        assert_eq!(body["cached"], true);
+        let (status, _) = send(
+            router.clone(),
+            post_json(
+                "/indexes/report/ask",
+                json!({ "query": "what is the intro" }),
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+
+        let clear = Request::delete("/answers").body(Body::empty()).unwrap();
+        let (status, body) = send(router.clone(), clear).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["invalidated"], 1);
+        let (status, _) = send(router.clone(), ask()).await;
+        assert_eq!(status, StatusCode::BAD_GATEWAY);
+
+        // Answers in another style are cached separately
+        let style = AnswerStyle {
+            format: AnswerFormat::Bullets,
+            language: Some("German".to_string()),
+            ..AnswerStyle::default()
+        };
+        let key = AnswerKey::new("what is the intro", version, model).with_style(style);
+        cache.insert(key, answer);
+        let styled =
+            json!({ "question": "What is the intro?", "format": "bullets", "language": "German" });
+        let (status, body) = send(router.clone(), post_json("/ask", styled)).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["cached"], true);
+        let (status, _) = send(router, ask()).await;
+        assert_eq!(status, StatusCode::BAD_GATEWAY);
+    }
+
+    #[tokio::test]
+    async fn test_metrics() {
+        let router = test_router();
+        let retrieve = post_json("/retrieve", json!({ "message": "intro" }));
+        send(router.clone(), retrieve).await;
+
+        let request = Request::get("/metrics").body(Body::empty()).unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let text = String::from_utf8(body.to_vec()).unwrap();
+        for line in [
+            "page_indexer_http_requests_total{route=\"/retrieve\",status=\"502\"} 1",
+            "page_indexer_searches_total{tenant=\"default\",index=\"report\"} 1",
+            "page_indexer_search_cache_misses_total{tenant=\"default\",index=\"report\"} 1",
+            "page_indexer_llm_calls_total{tenant=\"default\"} 0",
+        ] {
+            assert!(
+                text.lines().any(|l| l == line),
+                "missing {}\n{}",
+                line,
+                text
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_jobs() {
+        let router = test_router();
+        let (status, job) = send(
+            router.clone(),
+            post_json(
+                "/jobs",
+                json!({ "name": "notes", "content": "Introduction\nSome text." }),
+            ),
+        )
+        .await;
+        assert_eq!(status, StatusCode::ACCEPTED);
+        assert_eq!(job["document"], "notes");
+        let id = job["id"].as_str().unwrap();
+
+        let get = |uri: String| Request::get(uri).body(Body::empty()).unwrap();
+        // The LLM is unreachable, so the job fails shortly
+        let job = loop {
+            let (status, job) = send(router.clone(), get(format!("/jobs/{}", id))).await;
+            assert_eq!(status, StatusCode::OK);
+            if job["state"] == "failed" {
+                break job;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        };
+        assert!(
+            job["error"]
+                .as_str()
+                .unwrap()
+                .contains("HTTP request failed")
+        );
+
+        let (status, _) = send(router.clone(), get(format!("/jobs/{}/tree", id))).await;
+        assert_eq!(status, StatusCode::CONFLICT);
+        let (status, _) = send(router.clone(), get("/jobs/123-abc".to_string())).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (_, body) = send(router.clone(), get("/jobs".to_string())).await;
+        assert_eq!(body["jobs"].as_array().unwrap().len(), 1);
+
+        let empty = post_json("/jobs", json!({ "name": "x", "content": " " }));
+        let (status, _) = send(router, empty).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_result_cache() {
+        let key = |q: &str| ("t".to_string(), "i".to_string(), q.to_string(), 5, None);
+        let mut cache = ResultCache::new(2);
+        cache.insert(key("a"), Vec::new());
+        cache.insert(key("b"), Vec::new());
+        cache.insert(key("c"), Vec::new());
+        assert!(cache.get(&key("a")).is_none());
+        assert!(cache.get(&key("c")).is_some());
+
+        let mut disabled = ResultCache::new(0);
+        disabled.insert(key("a"), Vec::new());
+        assert!(disabled.get(&key("a")).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_tenants() {
+        let research = Tenant::new("research", offline_client())
+            .with_api_key("rk-1")
+            .with_index(ServedIndex::new("report", test_tree("report")))
+            .with_index(ServedIndex::new("notes", test_tree("notes")));
+        let legal = Tenant::new("legal", offline_client())
+            .with_index(ServedIndex::new("contracts", test_tree("contracts")));
+        let router = router(ServeState::multi_tenant(vec![research, legal]).unwrap());
+
+        let get = |uri: &str, header: Option<(&str, &str)>| {
+            let mut request = Request::get(uri);
+            if let Some((name, value)) = header {
+                request = request.header(name, value);
+            }
+            request.body(Body::empty()).unwrap()
+        };
+
+        let (status, body) = send(router.clone(), get("/indexes", None)).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED, "{}", body);
+        let (status, _) = send(router.clone(), get("/indexes", Some(("x-api-key", "nope")))).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        // A keyed tenant cannot be reached by name alone
+        let (status, _) = send(
+            router.clone(),
+            get("/indexes", Some(("x-tenant", "research"))),
+        )
+        .await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+        let bearer = Some(("authorization", "Bearer rk-1"));
+        let (status, body) = send(router.clone(), get("/indexes", bearer)).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["indexes"][1]["name"], "notes");
+        let (_, body) = send(router.clone(), get("/health", bearer)).await;
+        assert_eq!(body["tenant"], "research");
+        assert_eq!(body["index"], "report");
+        let (_, body) = send(router.clone(), get("/health", None)).await;
+        assert_eq!(body, json!({ "status": "ok" }));
+
+        // Metrics cover every tenant: no tenant key reaches them
+        let (status, _) = send(router.clone(), get("/metrics", bearer)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        let (status, _) = send(router.clone(), get("/metrics", None)).await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+
+        // Another tenant's index does not exist for this one
+        let mut request = post_json("/indexes/contracts/retrieve", json!({ "message": "x" }));
+        request
+            .headers_mut()
+            .insert("x-tenant", "research".parse().unwrap());
+        let (status, _) = send(router.clone(), request).await;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        let mut request = post_json("/indexes/report/retrieve", json!({ "message": "x" }));
+        request
+            .headers_mut()
+            .insert("x-tenant", "legal".parse().unwrap());
+        let (status, _) = send(router.clone(), request).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // Found indexes reach the LLM
+        let mut request = post_json("/indexes/contracts/invoke", json!({ "input": "x" }));
+        request
+            .headers_mut()
+            .insert("x-tenant", "legal".parse().unwrap());
+        let (status, _) = send(router.clone(), request).await;
+        assert_eq!(status, StatusCode::BAD_GATEWAY);
+    }
+
+    #[tokio::test]
+    async fn test_acl_labels() {
+        let research = Tenant::new("research", offline_client())
+            .with_api_key("rk-1")
+            .with_labeled_api_key("rk-2", ["hr", " finance", "hr"])
+            .with_index(ServedIndex::new("report", test_tree("report")));
+        let legal = Tenant::new("legal", offline_client())
+            .with_index(ServedIndex::new("contracts", test_tree("contracts")));
+        let cache = AnswerCache::new(8);
+        let state = ServeState::multi_tenant(vec![research, legal])
+            .unwrap()
+            .with_answer_cache(cache.clone());
+
+        let headers = |pairs: &[(&'static str, &str)]| {
+            let mut headers = HeaderMap::new();
+            for (name, value) in pairs {
+                headers.insert(*name, value.parse().unwrap());
+            }
+            headers
+        };
+        let labels = |h: &HeaderMap| state.labels(state.tenant(h).ok().unwrap(), h);
+        let finance_hr = Some(vec!["finance".to_string(), "hr".to_string()]);
+
+        // Keyed tenants take the key's labels and ignore the header
+        assert_eq!(labels(&headers(&[("x-api-key", "rk-1")])), None);
+        assert_eq!(
+            labels(&headers(&[
+                ("x-api-key", "rk-1"),
+                (ACL_LABELS_HEADER, "hr")
+            ])),
+            None
+        );
+        assert_eq!(
+            labels(&headers(&[("authorization", "Bearer rk-2")])),
+            finance_hr
+        );
+        // Keyless tenants trust the proxy's header, and without it see
+        // only unlabeled sections
+        assert_eq!(labels(&headers(&[("x-tenant", "legal")])), Some(Vec::new()));
+        assert_eq!(
+            labels(&headers(&[
+                ("x-tenant", "legal"),
+                (ACL_LABELS_HEADER, "hr, finance")
+            ])),
+            finance_hr
+        );
+
+        // Answers are cached per set of labels
+        let tenant = &state.tenants[0];
+        let index = &tenant.indexes[0];
+        let model = tenant.client.model().to_string();
+        let scoped = answer_version(tenant, index, finance_hr.as_deref());
+        assert_eq!(
+            scoped,
+            format!("{}#finance,hr", answer_version(tenant, index, None))
+        );
+        let answer = Answer {
+            answer: "Scoped.".to_string(),
+            sources: Vec::new(),
+            outcome: AnswerOutcome::Answered,
+        };
+        cache.insert(AnswerKey::new("q", scoped, model), answer);
+        let router = router(state);
+        let ask = |key: &str| {
+            let mut request = post_json("/ask", json!({ "question": "q" }));
+            request
+                .headers_mut()
+                .insert("x-api-key", key.parse().unwrap());
+            request
+        };
+        let (status, body) = send(router.clone(), ask("rk-2")).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["answer"], "Scoped.");
+        let (status, _) = send(router.clone(), ask("rk-1")).await;
+        assert_eq!(status, StatusCode::BAD_GATEWAY);
+
+        // Clearing drops every caller's answers
+        let clear = Request::delete("/answers")
+            .header("x-api-key", "rk-1")
+            .body(Body::empty())
+            .unwrap();
+        let (_, body) = send(router, clear).await;
+        assert_eq!(body["invalidated"], 1);
+        assert!(cache.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_pagination() {
+        let mut chapter = TreeNode::new("Methods", 2, 5).with_structure("2");
+        chapter.add_child(TreeNode::new("Sampling", 2, 3).with_structure("2.1"));
+        let mut salaries = TreeNode::new("Salaries", 4, 5).with_structure("2.2");
+        salaries.acl = vec!["hr".to_string()];
+        salaries.add_child(TreeNode::new("Bands", 5, 5).with_structure("2.2.1"));
+        chapter.add_child(salaries);
+        let tree = DocumentTree::new(
+            "report",
+            vec![
+                TreeNode::new("Introduction", 1, 1).with_structure("1"),
+                chapter,
+                TreeNode::new("Results", 6, 6).with_structure("3"),
+            ],
+            6,
+        );
+        let documents: Vec<_> = crate::retriever::tree_documents(&tree, None)
+            .into_iter()
+            .take(3)
+            .collect();
+        let state = ServeState::new(tree, offline_client());
+        let key = (
+            DEFAULT_TENANT.to_string(),
+            "report".to_string(),
+            "q".to_string(),
+            DEFAULT_TOP_K,
+            Some(Vec::new()),
+        );
+        state.cache().insert(key, documents);
+        let router = router(state);
+        // As passed on by the proxy for a caller holding "hr"
+        let get = |uri: &str| {
+            Request::get(uri)
+                .header(ACL_LABELS_HEADER, "hr")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        // Top level, one page at a time
+        let (status, body) = send(router.clone(), get("/trees/report/nodes?page_size=2")).await;
+        assert_eq!(status, StatusCode::OK, "{}", body);
+        assert_eq!(body["nodes"][1]["title"], "Methods");
+        assert_eq!(body["nodes"][1]["child_count"], 2);
+        assert!(body["nodes"][1].get("nodes").is_none());
+        assert_eq!(body["next_cursor"], "2");
+        let (_, body) = send(
+            router.clone(),
+            get("/trees/report/nodes?page_size=2&cursor=2"),
+        )
+        .await;
+        assert_eq!(body["nodes"][0]["title"], "Results");
+        assert_eq!(body["next_cursor"], Value::Null);
+
+        // Subsections, deeper, as far as the caller's labels allow
+        let (_, body) = send(router.clone(), get("/trees/report/nodes?parent=2&depth=2")).await;
+        assert_eq!(body["nodes"][1]["nodes"][0]["title"], "Bands");
+        let mut request = get("/trees/report/nodes?parent=2");
+        request
+            .headers_mut()
+            .insert(ACL_LABELS_HEADER, "finance".parse().unwrap());
+        let (_, body) = send(router.clone(), request).await;
+        assert_eq!(body["nodes"].as_array().unwrap().len(), 1);
+        let request = Request::get("/trees/report/nodes?parent=2")
+            .body(Body::empty())
+            .unwrap();
+        let (_, body) = send(router.clone(), request).await;
+        assert_eq!(body["nodes"].as_array().unwrap().len(), 1);
+
+        for uri in [
+            "/trees/report/nodes?cursor=9",
+            "/trees/report/nodes?cursor=abc",
+        ] {
+            let (status, _) = send(router.clone(), get(uri)).await;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{}", uri);
+        }
+        let (status, _) = send(router.clone(), get("/trees/report/nodes?parent=9")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = send(router.clone(), get("/trees/other/nodes")).await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // Index listings and cached search results page too; unpaged
+        // responses are unchanged
+        let (_, body) = send(router.clone(), get("/indexes?page_size=1")).await;
+        assert_eq!(body["indexes"][0]["name"], "report");
+        assert_eq!(body["next_cursor"], Value::Null);
+        let (_, body) = send(
+            router.clone(),
+            post_json("/retrieve", json!({ "message": "q", "page_size": 2 })),
+        )
+        .await;
+        assert_eq!(body["response"].as_array().unwrap().len(), 2);
+        assert_eq!(body["next_cursor"], "2");
+        let (_, body) = send(
+            router.clone(),
+            post_json(
+                "/invoke",
+                json!({ "input": "q", "page_size": 2, "cursor": "2" }),
+            ),
+        )
+        .await;
+        assert_eq!(body["output"][0]["metadata"]["title"], "Sampling");
+        assert_eq!(body["metadata"]["next_cursor"], Value::Null);
+        let (_, body) = send(router, post_json("/retrieve", json!({ "message": "q" }))).await;
+        assert_eq!(body["response"].as_array().unwrap().len(), 3);
+        assert!(body.get("next_cursor").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_stats() {
+        let tree = test_tree("report");
+        let documents = crate::retriever::tree_documents(&tree, None);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("report.json.stats");
+        let state =
+            ServeState::new(tree, offline_client()).with_stats(StatsFile::open(&path).unwrap());
+        let key = (
+            DEFAULT_TENANT.to_string(),
+            "report".to_string(),
+            "q".to_string(),
+            DEFAULT_TOP_K,
+            Some(Vec::new()),
+        );
+        state.cache().insert(key, documents.clone());
+        let router = router(state);
+
+        // Cached results count too: each query is a user asking
+        for _ in 0..2 {
+            let (status, _) = send(
+                router.clone(),
+                post_json("/retrieve", json!({ "message": "q" })),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+        }
+        let stats = crate::stats::RetrievalStats::load(&path).unwrap();
+        assert_eq!(stats.queries, 2);
+        assert_eq!(stats.sections.len(), documents.len());
+        assert!(stats.sections.values().all(|s| s.retrievals == 2));
+    }
+
+    #[tokio::test]
+    async fn test_audit_log() {
+        let tree = test_tree("report");
+        let documents = crate::retriever::tree_documents(&tree, None);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("audit.jsonl");
+        let state = ServeState::new(tree.clone(), offline_client())
+            .with_audit_log(AuditLog::open(&path).unwrap());
+        let key = (
+            DEFAULT_TENANT.to_string(),
+            "report".to_string(),
+            "q".to_string(),
+            DEFAULT_TOP_K,
+            Some(Vec::new()),
+        );
+        state.cache().insert(key, documents.clone());
+        let tenant = &state.tenants[0];
+        let version = answer_version(tenant, &tenant.indexes[0], Some(&[]));
+        let answer = Answer {
+            answer: "An overview.".to_string(),
+            sources: Vec::new(),
+            outcome: AnswerOutcome::Answered,
+        };
+        state.answers.insert(
+            AnswerKey::new("what is the intro", version, tenant.client.model()),
+            answer,
+        );
+        let router = router(state);
+
+        let (status, _) = send(
+            router.clone(),
+            post_json("/retrieve", json!({ "message": "q" })),
+        )
+        .await;
+        assert_eq!(status, StatusCode::OK);
+        let ask = json!({ "question": "What is the intro?" });
+        let (status, _) = send(router.clone(), post_json("/ask", ask)).await;
+        assert_eq!(status, StatusCode::OK);
+        // Failed requests are not recorded
+        let (status, _) = send(router, post_json("/retrieve", json!({ "message": "x" }))).await;
+        assert_eq!(status, StatusCode::BAD_GATEWAY);
+
+        let records = AuditLog::read(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].operation, crate::audit::AuditOperation::Search);
+        assert_eq!(records[0].tenant.as_deref(), Some(DEFAULT_TENANT));
+        assert_eq!(records[0].index.as_deref(), Some("report"));
+        assert_eq!(records[0].node_ids.len(), documents.len());
+        assert!(records[0].cached && records[0].answer_hash.is_none());
+        assert_eq!(records[1].query, "What is the intro?");
+        assert!(records[1].answer_hash.is_some());
+        assert_eq!(records[1].usage.unwrap().calls, 0);
+
+        // A request that can't be recorded fails, even from the cache
+        let full = Path::new("/dev/full");
+        if full.exists() {
+            let state = ServeState::new(tree, offline_client())
+                .with_audit_log(AuditLog::open(full).unwrap());
+            let key = (
+                DEFAULT_TENANT.to_string(),
+                "report".to_string(),
+                "q".to_string(),
+                DEFAULT_TOP_K,
+                Some(Vec::new()),
+            );
+            state.cache().insert(key, documents);
+            let (status, _) = send(
+                super::router(state),
+                post_json("/retrieve", json!({ "message": "q" })),
+            )
+            .await;
+            assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    }
+
+    #[test]
+    fn test_tenants_file() {
+        let dir = tempfile::tempdir().unwrap();
+        crate::save_tree(&test_tree("report"), &dir.path().join("report.json")).unwrap();
+        std::fs::write(dir.path().join("report.txt"), "Intro text.").unwrap();
+        let path = dir.path().join("tenants.yaml");
+        std::fs::write(
+            &path,
+            "tenants:\n  - name: research\n    api_keys: [rk-1, {key: rk-2, labels: [hr]}]\n    indexes:\n      - path: report.json\n        document: report.txt\n      - name: copy\n        path: report.json\n        stats: true\n",
+        )
+        .unwrap();
+        let state = ServeState::from_tenants_file(&path, &LlmConfig::default()).unwrap();
+        assert_eq!(state.tenants(), vec![("research", 2)]);
+        assert!(state.tenants[0].indexes[0].document.is_some());
+        assert!(state.tenants[0].indexes[1].stats.is_some());
+        assert_eq!(
+            state.tenants[0].api_key("rk-2").unwrap().labels,
+            Some(vec!["hr".to_string()])
+        );
+
+        let duplicate = vec![
+            Tenant::new("a", offline_client()).with_index(ServedIndex::new("r", test_tree("r"))),
+            Tenant::new("a", offline_client()).with_index(ServedIndex::new("r", test_tree("r"))),
+        ];
+        assert!(matches!(
+            ServeState::multi_tenant(duplicate),
+            Err(PageIndexError::Config(_))
+        ));
+        let shared_key = vec![
+            Tenant::new("a", offline_client())
+                .with_api_key("k")
+                .with_index(ServedIndex::new("r", test_tree("r"))),
+            Tenant::new("b", offline_client())
+                .with_api_key("k")
+                .with_index(ServedIndex::new("r", test_tree("r"))),
+        ];
+        assert!(ServeState::multi_tenant(shared_key).is_err());
+
+        let tenants = vec![
+            Tenant::new("a", offline_client())
+                .with_api_key("k")
+                .with_index(ServedIndex::new("r", test_tree("r"))),
+        ];
+        let state = ServeState::multi_tenant(tenants).unwrap();
+        assert!(state.with_admin_key("k").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_tree_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["a", "b"] {
+            crate::save_tree(&test_tree(name), &dir.path().join(format!("{}.json", name))).unwrap();
+        }
+        let path = dir.path().join("tenants.yaml");
+        std::fs::write(
+            &path,
+            "tree_cache_mb: 0\nadmin_key: ops\ntenants:\n  - name: team\n    indexes:\n      - path: a.json\n      - path: b.json\n",
+        )
+        .unwrap();
+        let state = ServeState::from_tenants_file(&path, &LlmConfig::default()).unwrap();
+        // Both loaded to check them; only the last one kept
+        let stats = state.tree_cache_stats().unwrap();
+        assert_eq!((stats.trees, stats.misses, stats.evictions), (1, 2, 1));
+
+        let router = router(state);
+        let get = |uri: &str| {
+            Request::get(uri)
+                .header("x-tenant", "team")
+                .body(Body::empty())
+                .unwrap()
+        };
+        let (status, body) = send(router.clone(), get("/trees/a/nodes")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["nodes"][0]["title"], "Introduction");
+
+        // An evicted tree is reloaded from its file
+        std::fs::remove_file(dir.path().join("b.json")).unwrap();
+        let (status, _) = send(router.clone(), get("/trees/b/nodes")).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+
+        let (_, body) = send(router.clone(), get("/health")).await;
+        assert_eq!(body["indexes"][1]["nodes"], 1);
+        assert!(body.get("tree_cache").is_none());
+
+        let admin = Request::get("/health")
+            .header("x-api-key", "ops")
+            .body(Body::empty())
+            .unwrap();
+        let (_, body) = send(router.clone(), admin).await;
+        assert!(body.get("tenant").is_none());
+        assert_eq!(body["tree_cache"]["trees"], 1);
+        let admin = Request::get("/metrics")
+            .header("authorization", "Bearer ops")
+            .body(Body::empty())
+            .unwrap();
+        let (status, _) = send(router.clone(), admin).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["tree_cache"]["misses"], 4);
+        assert_eq!(body["tree_cache"]["evictions"], 2);
+    }
+}
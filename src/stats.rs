@@ -0,0 +1,296 @@
+//! Per-section retrieval statistics.
+//!
+//! A served index can count how often each section is retrieved and how
+//! relevant it was judged, so document owners can see which sections users
+//! actually ask about, and which they never do. The counts are kept in a
+//! sidecar file next to the index (`tree.json` -> `tree.json.stats`),
+//! updated as queries are answered and shown by `page-indexer info --stats`.
+//!
+//! ```no_run
+//! use rust_page_indexer::persistence::load_tree;
+//! use rust_page_indexer::stats::{RetrievalStats, stats_path_for};
+//! use std::path::Path;
+//!
+//! # fn run() -> rust_page_indexer::Result<()> {
+//! let index = Path::new("data/tree_index.json");
+//! let tree = load_tree(index)?;
+//! let stats = RetrievalStats::load(&stats_path_for(index))?;
+//! for section in stats.most_retrieved() {
+//!     println!("{:>5}  {}", section.retrievals, section.title);
+//! }
+//! println!("{} sections never retrieved", stats.never_retrieved(&tree).len());
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::{PageIndexError, Result};
+use crate::retriever::{RetrieverDocument, tree_documents};
+use crate::search::Relevance;
+use crate::tree::DocumentTree;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Default stats path for an index path (`tree.json` -> `tree.json.stats`).
+pub fn stats_path_for(index: &Path) -> PathBuf {
+    let mut name = index.as_os_str().to_owned();
+    name.push(".stats");
+    PathBuf::from(name)
+}
+
+/// How often one section was retrieved.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SectionStats {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub node_id: Option<String>,
+    pub title: String,
+    pub start_index: usize,
+    pub end_index: usize,
+    /// Times the section was among a query's results.
+    pub retrievals: u64,
+    /// Retrievals by relevance.
+    #[serde(default)]
+    pub high: u64,
+    #[serde(default)]
+    pub medium: u64,
+    #[serde(default)]
+    pub low: u64,
+    /// Last retrieval, in seconds since the Unix epoch.
+    pub last_retrieved: u64,
+    /// `retrievals` when the section's summary was last refreshed (see
+    /// [`HotSectionRefresher`](crate::summarize::HotSectionRefresher)).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refreshed_at: Option<u64>,
+}
+
+impl SectionStats {
+    /// Retrievals since the summary was last refreshed, or ever.
+    pub fn retrievals_since_refresh(&self) -> u64 {
+        self.retrievals - self.refreshed_at.unwrap_or(0)
+    }
+}
+
+/// Retrieval counts for the sections of one index.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RetrievalStats {
+    /// Queries recorded.
+    pub queries: u64,
+    /// Per section, keyed by [`section_key`].
+    pub sections: BTreeMap<String, SectionStats>,
+}
+
+/// Key identifying a section across queries: its node ID, or its title and
+/// page range for trees without IDs.
+pub fn section_key(document: &RetrieverDocument) -> String {
+    match &document.id {
+        Some(id) => id.clone(),
+        None => format!(
+            "{} ({}-{})",
+            document.metadata.title, document.metadata.start_index, document.metadata.end_index
+        ),
+    }
+}
+
+impl RetrievalStats {
+    /// Load stats from a file, or empty stats if there is none yet.
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(PageIndexError::io(path, e)),
+        };
+        serde_json::from_str(&content).map_err(|e| PageIndexError::Serialization(e.to_string()))
+    }
+
+    /// Save stats to a file, replacing it atomically.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| PageIndexError::Serialization(e.to_string()))?;
+
+        // `tree.json.stats.tmp`, not `tree.json.tmp`, which other sidecars
+        // of the same index would share
+        let mut tmp = path.as_os_str().to_owned();
+        tmp.push(".tmp");
+        let tmp = PathBuf::from(tmp);
+        std::fs::write(&tmp, json).map_err(|e| PageIndexError::io(&tmp, e))?;
+        std::fs::rename(&tmp, path).map_err(|e| PageIndexError::io(path, e))
+    }
+
+    /// Record one query and the sections retrieved for it.
+    pub fn record(&mut self, documents: &[RetrieverDocument]) {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        self.queries += 1;
+        for document in documents {
+            let metadata = &document.metadata;
+            let section = self
+                .sections
+                .entry(section_key(document))
+                .or_insert_with(|| SectionStats {
+                    node_id: document.id.clone(),
+                    ..SectionStats::default()
+                });
+            section.title = metadata.title.clone();
+            section.start_index = metadata.start_index;
+            section.end_index = metadata.end_index;
+            section.retrievals += 1;
+            match metadata.relevance {
+                Some(Relevance::High) => section.high += 1,
+                Some(Relevance::Medium) => section.medium += 1,
+                Some(Relevance::Low) => section.low += 1,
+                None => {}
+            }
+            section.last_retrieved = now;
+        }
+    }
+
+    /// Retrieved sections, most retrieved first.
+    pub fn most_retrieved(&self) -> Vec<&SectionStats> {
+        let mut sections: Vec<_> = self.sections.values().collect();
+        sections.sort_by(|a, b| {
+            b.retrievals
+                .cmp(&a.retrievals)
+                .then(a.start_index.cmp(&b.start_index))
+        });
+        sections
+    }
+
+    /// Note that a section's summary was refreshed now. Returns whether
+    /// the section has stats.
+    pub fn mark_refreshed(&mut self, key: &str) -> bool {
+        match self.sections.get_mut(key) {
+            Some(section) => {
+                section.refreshed_at = Some(section.retrievals);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Sections of the tree never retrieved, in document order.
+    pub fn never_retrieved(&self, tree: &DocumentTree) -> Vec<RetrieverDocument> {
+        tree_documents(tree, None)
+            .into_iter()
+            .filter(|d| !self.sections.contains_key(&section_key(d)))
+            .collect()
+    }
+}
+
+/// Retrieval stats saved to a file as they are recorded, shared by the
+/// requests of a server.
+pub struct StatsFile {
+    path: PathBuf,
+    stats: Mutex<RetrievalStats>,
+}
+
+impl StatsFile {
+    /// Keep stats in `path`, continuing from those already there.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let stats = RetrievalStats::load(&path)?;
+        Ok(Self {
+            path,
+            stats: Mutex::new(stats),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Record a query's sections and save the stats.
+    pub fn record(&self, documents: &[RetrieverDocument]) -> Result<()> {
+        let mut stats = self.stats.lock().expect("stats lock poisoned");
+        stats.record(documents);
+        stats.save(&self.path)
+    }
+
+    /// The stats recorded so far.
+    pub fn stats(&self) -> RetrievalStats {
+        self.stats.lock().expect("stats lock poisoned").clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::retriever::result_documents;
+    use crate::search::SearchResult;
+    use crate::tree::TreeNode;
+    use tempfile::TempDir;
+
+    fn test_tree() -> DocumentTree {
+        let mut intro = TreeNode::new("Introduction", 1, 2);
+        intro.node_id = Some("0000".to_string());
+        let mut methods = TreeNode::new("Methods", 3, 5);
+        methods.node_id = Some("0001".to_string());
+        let mut results = TreeNode::new("Results", 6, 8);
+        results.node_id = Some("0002".to_string());
+        DocumentTree::new("report", vec![intro, methods, results], 8)
+    }
+
+    fn result(title: &str, start: usize, end: usize, relevance: Relevance) -> SearchResult {
+        SearchResult {
+            title: title.to_string(),
+            start_index: start,
+            end_index: end,
+            relevance,
+            reason: String::new(),
+            content: None,
+            snippet: None,
+            snippet_span: None,
+        }
+    }
+
+    #[test]
+    fn test_retrieval_stats() {
+        let tree = test_tree();
+        let dir = TempDir::new().unwrap();
+        let path = stats_path_for(&dir.path().join("tree.json"));
+        assert!(path.ends_with("tree.json.stats"));
+
+        let file = StatsFile::open(&path).unwrap();
+        let first = [
+            result("Methods", 3, 5, Relevance::High),
+            result("Introduction", 1, 2, Relevance::Low),
+        ];
+        let second = [
+            result("Methods", 3, 5, Relevance::Medium),
+            result("Unlisted", 9, 9, Relevance::High),
+        ];
+        file.record(&result_documents(&tree, &first)).unwrap();
+        file.record(&result_documents(&tree, &second)).unwrap();
+
+        // Persisted, and picked up again on reopening
+        let stats = StatsFile::open(&path).unwrap().stats();
+        assert_eq!(stats, file.stats());
+        assert_eq!(stats.queries, 2);
+        let most = stats.most_retrieved();
+        assert_eq!(most[0].node_id.as_deref(), Some("0001"));
+        assert_eq!(
+            (
+                most[0].retrievals,
+                most[0].high,
+                most[0].medium,
+                most[0].low
+            ),
+            (2, 1, 1, 0)
+        );
+        assert!(stats.sections.contains_key("Unlisted (9-9)"));
+
+        let never: Vec<_> = stats
+            .never_retrieved(&tree)
+            .into_iter()
+            .map(|d| d.metadata.title)
+            .collect();
+        assert_eq!(never, vec!["Results"]);
+
+        assert_eq!(
+            RetrievalStats::load(&dir.path().join("missing.stats")).unwrap(),
+            RetrievalStats::default()
+        );
+    }
+}
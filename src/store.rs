@@ -0,0 +1,239 @@
+//! Where search results get their section text from.
+//!
+//! [`TreeSearcher::search_with_content`](crate::TreeSearcher::search_with_content)
+//! asks a [`DocumentStore`] for the pages each result covers, keyed by the
+//! tree's document name. Implement it to serve content from a database or
+//! object storage instead of the original file:
+//!
+//! ```no_run
+//! use async_trait::async_trait;
+//! use rust_page_indexer::{Document, Result};
+//! use rust_page_indexer::store::DocumentStore;
+//!
+//! struct Bucket;
+//!
+//! #[async_trait]
+//! impl DocumentStore for Bucket {
+//!     async fn get_document(&self, name: &str) -> Result<Document> {
+//!         let text = format!("fetched {}.txt", name); // e.g. an object store GET
+//!         Ok(Document::from_text(name, text))
+//!     }
+//! }
+//! ```
+//!
+//! A [`Document`] is itself a store serving its own text, [`MemoryStore`]
+//! holds several documents and [`FileStore`] reads them from a directory.
+
+use crate::document::Document;
+use crate::error::{PageIndexError, Result};
+use crate::search::section_content;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Source of document text for search results.
+#[async_trait]
+pub trait DocumentStore: Send + Sync {
+    /// Text of pages `start..=end` (1-indexed) of the named document.
+    ///
+    /// Defaults to slicing [`get_document`](Self::get_document); override it
+    /// when the backend can fetch a range on its own.
+    async fn get_page_range(&self, name: &str, start: usize, end: usize) -> Result<String> {
+        let document = self.get_document(name).await?;
+        Ok(section_content(&document, start, end))
+    }
+
+    /// The named document.
+    async fn get_document(&self, name: &str) -> Result<Document>;
+}
+
+/// A single document serves its own text, whatever the tree's name.
+#[async_trait]
+impl DocumentStore for Document {
+    async fn get_page_range(&self, _name: &str, start: usize, end: usize) -> Result<String> {
+        Ok(section_content(self, start, end))
+    }
+
+    async fn get_document(&self, _name: &str) -> Result<Document> {
+        Ok(self.clone())
+    }
+}
+
+/// Documents held in memory, by name.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStore {
+    documents: HashMap<String, Document>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a document under its name, replacing any with the same name.
+    pub fn insert(&mut self, document: Document) {
+        self.documents.insert(document.name.clone(), document);
+    }
+
+    /// Builder form of [`insert`](Self::insert).
+    pub fn with_document(mut self, document: Document) -> Self {
+        self.insert(document);
+        self
+    }
+
+    fn find(&self, name: &str) -> Result<&Document> {
+        self.documents
+            .get(name)
+            .ok_or_else(|| PageIndexError::DocumentNotFound(PathBuf::from(name)))
+    }
+}
+
+#[async_trait]
+impl DocumentStore for MemoryStore {
+    async fn get_page_range(&self, name: &str, start: usize, end: usize) -> Result<String> {
+        Ok(section_content(self.find(name)?, start, end))
+    }
+
+    async fn get_document(&self, name: &str) -> Result<Document> {
+        self.find(name).cloned()
+    }
+}
+
+/// Text files in a directory, found by document name (`<name>.txt` or
+/// `<name>.md`, the names [`Document::from_text_file`] gives them).
+#[derive(Debug, Clone)]
+pub struct FileStore {
+    root: PathBuf,
+    delimiter: Option<String>,
+}
+
+impl FileStore {
+    /// Documents in `root`.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            delimiter: None,
+        }
+    }
+
+    /// Split files into pages on this delimiter, as they were when indexed.
+    pub fn with_delimiter(mut self, delimiter: impl Into<String>) -> Self {
+        self.delimiter = Some(delimiter.into());
+        self
+    }
+
+    /// The file holding the named document.
+    fn path(&self, name: &str) -> Result<PathBuf> {
+        let not_found = || PageIndexError::DocumentNotFound(self.root.join(name));
+        // Names come from trees; keep them inside the root
+        if name.is_empty() || name.contains(['/', '\\']) || name == ".." {
+            return Err(not_found());
+        }
+        ["txt", "md"]
+            .iter()
+            .map(|ext| self.root.join(format!("{}.{}", name, ext)))
+            .find(|path| path.is_file())
+            .ok_or_else(not_found)
+    }
+
+    fn load(&self, path: &Path) -> Result<Document> {
+        match &self.delimiter {
+            Some(delimiter) => Document::from_text_file_with_delimiter(path, delimiter),
+            None => Document::from_text_file(path),
+        }
+    }
+}
+
+#[async_trait]
+impl DocumentStore for FileStore {
+    async fn get_document(&self, name: &str) -> Result<Document> {
+        self.load(&self.path(name)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::Page;
+    use crate::fixtures::Fixture;
+    use crate::indexer::TreeIndexer;
+    use crate::page_index::PageIndex;
+    use crate::search::TreeSearcher;
+
+    #[tokio::test]
+    async fn test_stores() {
+        let document = Document::new(
+            "manual",
+            vec![
+                Page::new(1, "One".to_string()),
+                Page::new(2, "Two".to_string()),
+                Page::new(3, "Three".to_string()),
+            ],
+        );
+        let memory = MemoryStore::new().with_document(document.clone());
+        assert_eq!(
+            memory.get_page_range("manual", 2, 3).await.unwrap(),
+            section_content(&document, 2, 3)
+        );
+        assert!(matches!(
+            memory.get_document("other").await,
+            Err(PageIndexError::DocumentNotFound(_))
+        ));
+        // A document answers for any name
+        assert_eq!(document.get_page_range("x", 1, 1).await.unwrap(), "One");
+
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("notes.md"), "A\n---\nB").unwrap();
+        let files = FileStore::new(dir.path()).with_delimiter("---");
+        assert_eq!(files.get_document("notes").await.unwrap().page_count(), 2);
+        assert_eq!(files.get_page_range("notes", 2, 2).await.unwrap(), "B");
+        for name in ["missing", "../notes", ""] {
+            assert!(files.get_document(name).await.is_err(), "{}", name);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_content_from_store() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("data/fixtures");
+        let fixture = Fixture::replay(dir.join("sample.fixture.json")).unwrap();
+        let client = fixture.client().unwrap();
+        let document = Document::from_text_file(&dir.join("sample.txt")).unwrap();
+        let tree = TreeIndexer::new(client.clone())
+            .index(&document)
+            .await
+            .unwrap();
+        let query = "How often should the filter be cleaned?";
+
+        let results = TreeSearcher::new(client.clone())
+            .search_with_content(&tree, &FileStore::new(&dir), query)
+            .await
+            .unwrap();
+        assert_eq!(results[0].title, "Cleaning the Filter");
+        assert!(
+            results[0]
+                .content
+                .as_deref()
+                .unwrap()
+                .contains("Once a week")
+        );
+
+        // A loaded tree has no document, so its content comes from the store
+        let saved = tempfile::tempdir().unwrap();
+        let path = saved.path().join("sample.json");
+        crate::save_tree(&tree, &path).unwrap();
+        let mut index = PageIndex::builder()
+            .with_client(client)
+            .with_store(MemoryStore::new().with_document(document))
+            .build()
+            .unwrap();
+        index.load(&path).unwrap();
+        let results = index.search(query).await.unwrap();
+        assert!(
+            results[0]
+                .content
+                .as_deref()
+                .unwrap()
+                .contains("Once a week")
+        );
+    }
+}
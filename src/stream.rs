@@ -0,0 +1,168 @@
+//! Event streams for indexing, search and question answering.
+//!
+//! [`TreeIndexer::index_stream`], [`TreeSearcher::search_stream`] and the
+//! [`PageIndex`](crate::PageIndex) variants return an [`EventStream`]: a `tokio_stream::Stream`
+//! of progress events ending with the outcome, as an alternative to
+//! progress callbacks.
+//!
+//! ```no_run
+//! use rust_page_indexer::PageIndex;
+//! use rust_page_indexer::stream::AskEvent;
+//! use tokio_stream::StreamExt;
+//!
+//! # async fn run(index: PageIndex) -> rust_page_indexer::Result<()> {
+//! let mut events = index.ask_stream("What were the main findings?");
+//! while let Some(event) = events.next().await {
+//!     match event? {
+//!         AskEvent::Progress(progress) => println!("{:?}", progress),
+//!         AskEvent::Sources(sources) => println!("{} sources", sources.len()),
+//!         AskEvent::Completed(answer) => println!("{}", answer.answer),
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! A stream yields at most one error, after which it ends. Dropping a
+//! stream cancels the work.
+//!
+//! [`TreeIndexer::index_stream`]: crate::indexer::TreeIndexer::index_stream
+//! [`TreeSearcher::search_stream`]: crate::search::TreeSearcher::search_stream
+
+use crate::error::Result;
+use crate::page_index::Answer;
+use crate::progress::{ProgressCallback, ProgressEvent};
+use crate::search::SearchResult;
+use crate::tree::DocumentTree;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::sync::mpsc;
+use tokio_stream::Stream;
+
+/// An event from [`TreeIndexer::index_stream`](crate::indexer::TreeIndexer::index_stream)
+/// or [`PageIndex::index_stream`](crate::PageIndex::index_stream).
+#[derive(Debug, Clone)]
+pub enum IndexEvent {
+    /// Chunk and summary progress.
+    Progress(ProgressEvent),
+    /// The finished tree (last event).
+    Completed(DocumentTree),
+}
+
+/// An event from [`TreeSearcher::search_stream`](crate::search::TreeSearcher::search_stream)
+/// or [`PageIndex::search_stream`](crate::PageIndex::search_stream).
+#[derive(Debug, Clone)]
+pub enum SearchEvent {
+    /// Search started and completed.
+    Progress(ProgressEvent),
+    /// The results (last event).
+    Completed(Vec<SearchResult>),
+}
+
+/// An event from [`PageIndex::ask_stream`](crate::PageIndex::ask_stream).
+#[derive(Debug, Clone)]
+pub enum AskEvent {
+    /// Search started and completed.
+    Progress(ProgressEvent),
+    /// The retrieved sections, before the answer is generated.
+    Sources(Vec<SearchResult>),
+    /// The answer (last event).
+    Completed(Answer),
+}
+
+type Run<'a, E> = Pin<Box<dyn Future<Output = Result<E>> + Send + 'a>>;
+
+/// Events sent while an operation runs, then its outcome.
+pub struct EventStream<'a, E> {
+    events: mpsc::UnboundedReceiver<E>,
+    run: Option<Run<'a, E>>,
+    outcome: Option<Result<E>>,
+}
+
+impl<'a, E: Send + 'a> EventStream<'a, E> {
+    /// Drive `run`, which may send events through the given sender before
+    /// resolving to the final event.
+    pub(crate) fn new<F>(run: impl FnOnce(mpsc::UnboundedSender<E>) -> F) -> Self
+    where
+        F: Future<Output = Result<E>> + Send + 'a,
+    {
+        let (tx, events) = mpsc::unbounded_channel();
+        Self {
+            events,
+            run: Some(Box::pin(run(tx))),
+            outcome: None,
+        }
+    }
+}
+
+// Events are never pinned in place, only moved out
+impl<E> Unpin for EventStream<'_, E> {}
+
+impl<E> Stream for EventStream<'_, E> {
+    type Item = Result<E>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if let Some(run) = &mut this.run {
+            if let Poll::Ready(Some(event)) = this.events.poll_recv(cx) {
+                return Poll::Ready(Some(Ok(event)));
+            }
+            match run.as_mut().poll(cx) {
+                Poll::Ready(outcome) => {
+                    this.run = None;
+                    this.outcome = Some(outcome);
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        // Events sent just before the run finished come before its outcome
+        if let Ok(event) = this.events.try_recv() {
+            return Poll::Ready(Some(Ok(event)));
+        }
+        Poll::Ready(this.outcome.take())
+    }
+}
+
+/// A progress callback that calls `existing` (if any) and forwards the
+/// event into a stream.
+pub(crate) fn forward_progress<E: Send + 'static>(
+    existing: Option<ProgressCallback>,
+    tx: mpsc::UnboundedSender<E>,
+    wrap: fn(ProgressEvent) -> E,
+) -> ProgressCallback {
+    Arc::new(move |event: &ProgressEvent| {
+        if let Some(existing) = &existing {
+            existing(event);
+        }
+        let _ = tx.send(wrap(event.clone()));
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::PageIndexError;
+    use tokio_stream::StreamExt;
+
+    #[tokio::test]
+    async fn test_event_stream_order() {
+        let stream = EventStream::new(|tx| async move {
+            tx.send(1).unwrap();
+            tokio::task::yield_now().await;
+            tx.send(2).unwrap();
+            Ok(3)
+        });
+        let events: Vec<_> = stream.map(|e| e.unwrap()).collect().await;
+        assert_eq!(events, vec![1, 2, 3]);
+
+        let stream = EventStream::new(|tx| async move {
+            tx.send(1).unwrap();
+            Err::<i32, _>(PageIndexError::LlmApi("down".to_string()))
+        });
+        let events: Vec<_> = stream.collect().await;
+        assert_eq!(events.len(), 2);
+        assert!(matches!(events[1], Err(PageIndexError::LlmApi(_))));
+    }
+}
@@ -0,0 +1,670 @@
+//! Hierarchical summary roll-up ("summarize the whole document").
+//!
+//! [`TreeSummarizer`] summarizes a tree bottom-up, map-reduce style: each
+//! leaf from its own text, each parent from its children's summaries, and
+//! finally the whole document from its top-level sections into
+//! [`DocumentTree::description`]. Nodes of the same height are summarized
+//! concurrently, with a bounded number of LLM calls in flight.
+//!
+//! [`HotSectionRefresher`] spends the other way round: it re-summarizes
+//! only the sections users retrieve most (per the index's
+//! [retrieval stats](crate::stats)), in more detail and optionally with
+//! embeddings.
+//!
+//! ```no_run
+//! use rust_page_indexer::summarize::TreeSummarizer;
+//! use rust_page_indexer::{Config, Document, LlmClient, load_tree};
+//! use std::path::Path;
+//!
+//! # async fn run() -> rust_page_indexer::Result<()> {
+//! let client = LlmClient::new(Config::load()?.llm);
+//! let document = Document::from_text_file(Path::new("report.txt"))?;
+//! let mut tree = load_tree(Path::new("data/tree_index.json"))?;
+//! let summary = TreeSummarizer::new(client).summarize(&mut tree, &document).await?;
+//! println!("{}", summary);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::document::Document;
+use crate::error::{PageIndexError, Result};
+use crate::indexer::summarize_section;
+use crate::llm::{LlmClient, Prompts};
+use crate::progress::{ProgressCallback, ProgressEvent, emit};
+use crate::retriever::tree_documents;
+use crate::stats::{RetrievalStats, section_key};
+use crate::tree::{DocumentTree, TreeNode};
+use serde::Serialize;
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tracing::{debug, info};
+
+/// Default number of LLM calls in flight at once.
+const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Default retrievals since its last refresh for a section to be hot.
+pub const DEFAULT_HOT_RETRIEVALS: u64 = 5;
+
+/// Texts sent per embeddings request by [`embed_nodes`].
+const EMBED_BATCH_SIZE: usize = 64;
+
+/// Most characters of section text a detailed summary is generated from.
+const MAX_DETAILED_CONTENT_CHARS: usize = 24_000;
+
+/// Position of a node: child indices from the root level down.
+type NodePath = Vec<usize>;
+
+/// What a node is summarized from.
+enum Source {
+    /// A leaf's own text.
+    Text(String),
+    /// A parent's children, as `(title, summary)`.
+    Children(Vec<(String, String)>),
+}
+
+/// Rolls section summaries up a tree into a document abstract.
+pub struct TreeSummarizer {
+    client: LlmClient,
+    concurrency: usize,
+    resummarize_leaves: bool,
+    progress: Option<ProgressCallback>,
+}
+
+impl TreeSummarizer {
+    /// Create a summarizer calling the given client.
+    pub fn new(client: LlmClient) -> Self {
+        Self {
+            client,
+            concurrency: DEFAULT_CONCURRENCY,
+            resummarize_leaves: false,
+            progress: None,
+        }
+    }
+
+    /// Set the maximum number of LLM calls in flight.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Summarize leaves that already have a summary again (by default
+    /// their summary from indexing is kept).
+    pub fn with_resummarize_leaves(mut self, resummarize: bool) -> Self {
+        self.resummarize_leaves = resummarize;
+        self
+    }
+
+    /// Report a `SummaryGenerated` event per summarized node and one for
+    /// the document.
+    pub fn with_progress(mut self, callback: ProgressCallback) -> Self {
+        self.progress = Some(callback);
+        self
+    }
+
+    /// Summarize every node of `tree` bottom-up, then the document, and
+    /// return the document summary (also stored in `tree.description`).
+    ///
+    /// Parents' summaries are replaced by ones rolled up from their
+    /// children. Prefix summaries are recomposed if the tree has them. On
+    /// error, nodes summarized so far keep their new summaries.
+    pub async fn summarize(&self, tree: &mut DocumentTree, document: &Document) -> Result<String> {
+        let levels = levels_by_height(&tree.nodes);
+        let total = tree.node_count() + 1;
+        let mut completed = 0;
+        let semaphore = Arc::new(Semaphore::new(self.concurrency));
+        info!(
+            nodes = total - 1,
+            levels = levels.len(),
+            "rolling up summaries"
+        );
+
+        for level in levels {
+            let mut tasks = JoinSet::new();
+            for path in level {
+                let node = node_at_mut(&mut tree.nodes, &path);
+                let source = if node.nodes.is_empty() {
+                    if node.summary.is_some() && !self.resummarize_leaves {
+                        completed += 1;
+                        self.emit(completed, total, &node.title);
+                        continue;
+                    }
+                    Source::Text(document.content_range(node.start_index, node.end_index))
+                } else {
+                    Source::Children(children_summaries(&node.nodes))
+                };
+
+                let client = self.client.clone();
+                let semaphore = semaphore.clone();
+                let title = node.title.clone();
+                tasks.spawn(async move {
+                    let _permit = semaphore.acquire_owned().await.expect("semaphore closed");
+                    let summary = match source {
+                        Source::Text(text) => summarize_section(&client, &title, &text).await,
+                        Source::Children(children) => {
+                            roll_up(
+                                &client,
+                                Prompts::roll_up_section_summary(),
+                                &title,
+                                &children,
+                            )
+                            .await
+                        }
+                    };
+                    (path, summary)
+                });
+            }
+
+            while let Some(joined) = tasks.join_next().await {
+                let (path, summary) =
+                    joined.map_err(|e| PageIndexError::TreeError(e.to_string()))?;
+                let node = node_at_mut(&mut tree.nodes, &path);
+                node.summary = Some(summary?);
+                debug!(title = %node.title, "rolled up summary");
+                completed += 1;
+                self.emit(completed, total, &node.title);
+            }
+        }
+
+        let summary = if tree.nodes.is_empty() {
+            let text = document.content_range(1, document.page_count());
+            summarize_section(&self.client, &tree.name, &text).await?
+        } else {
+            roll_up(
+                &self.client,
+                Prompts::roll_up_document_summary(),
+                &tree.name,
+                &children_summaries(&tree.nodes),
+            )
+            .await?
+        };
+        self.emit(total, total, &tree.name);
+
+        tree.description = Some(summary.clone());
+        if tree.has_prefix_summaries() {
+            tree.compose_prefix_summaries();
+        }
+        Ok(summary)
+    }
+
+    fn emit(&self, completed: usize, total: usize, title: &str) {
+        emit(
+            &self.progress,
+            ProgressEvent::SummaryGenerated {
+                completed,
+                total,
+                title: title.to_string(),
+            },
+        );
+    }
+}
+
+/// A section re-summarized by [`HotSectionRefresher`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RefreshedSection {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node_id: Option<String>,
+    pub title: String,
+    pub start_index: usize,
+    pub end_index: usize,
+    /// Retrievals since the section was last refreshed.
+    pub retrievals: u64,
+    /// The new summary.
+    pub summary: String,
+}
+
+/// Re-summarizes the sections users retrieve most, in more detail and
+/// optionally with embeddings, so summarization spend goes to the parts of
+/// a corpus that are actually queried.
+///
+/// A section is hot once it has been retrieved [`DEFAULT_HOT_RETRIEVALS`]
+/// times since its summary was last refreshed, per the index's
+/// [`RetrievalStats`]. Refreshing notes this in the stats, so a refresher
+/// run on a schedule only pays again for sections that keep being
+/// retrieved.
+///
+/// ```no_run
+/// use rust_page_indexer::stats::{RetrievalStats, stats_path_for};
+/// use rust_page_indexer::summarize::HotSectionRefresher;
+/// use rust_page_indexer::{Config, Document, LlmClient, load_tree, save_tree};
+/// use std::path::Path;
+///
+/// # async fn run() -> rust_page_indexer::Result<()> {
+/// let index = Path::new("data/tree_index.json");
+/// let client = LlmClient::new(Config::load()?.llm);
+/// let document = Document::from_text_file(Path::new("report.txt"))?;
+/// let mut tree = load_tree(index)?;
+/// let mut stats = RetrievalStats::load(&stats_path_for(index))?;
+/// let refreshed = HotSectionRefresher::new(client)
+///     .with_limit(20)
+///     .refresh(&mut tree, &document, &mut stats)
+///     .await?;
+/// println!("{} sections refreshed", refreshed.len());
+/// save_tree(&tree, index)?;
+/// stats.save(&stats_path_for(index))?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct HotSectionRefresher {
+    client: LlmClient,
+    embedder: Option<LlmClient>,
+    min_retrievals: u64,
+    limit: Option<usize>,
+    concurrency: usize,
+}
+
+impl HotSectionRefresher {
+    /// Create a refresher summarizing with the given client.
+    pub fn new(client: LlmClient) -> Self {
+        Self {
+            client,
+            embedder: None,
+            min_retrievals: DEFAULT_HOT_RETRIEVALS,
+            limit: None,
+            concurrency: DEFAULT_CONCURRENCY,
+        }
+    }
+
+    /// Also embed each refreshed section's title and summary with this
+    /// client's model, into [`TreeNode::embedding`].
+    pub fn with_embeddings(mut self, client: LlmClient) -> Self {
+        self.embedder = Some(client);
+        self
+    }
+
+    /// Set the retrievals since its last refresh for a section to be hot.
+    pub fn with_min_retrievals(mut self, retrievals: u64) -> Self {
+        self.min_retrievals = retrievals.max(1);
+        self
+    }
+
+    /// Refresh at most `limit` sections per run, the most retrieved first.
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Set the maximum number of LLM calls in flight.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Hot sections of `tree` as node paths, stats keys and retrievals
+    /// since their last refresh, most retrieved first.
+    fn hot_sections(
+        &self,
+        tree: &DocumentTree,
+        stats: &RetrievalStats,
+    ) -> Vec<(NodePath, String, u64)> {
+        let mut hot: Vec<_> = node_paths(&tree.nodes)
+            .into_iter()
+            .zip(tree_documents(tree, None))
+            .filter_map(|(path, document)| {
+                let key = section_key(&document);
+                let retrievals = stats.sections.get(&key)?.retrievals_since_refresh();
+                (retrievals >= self.min_retrievals).then_some((path, key, retrievals))
+            })
+            .collect();
+        hot.sort_by_key(|(_, _, retrievals)| std::cmp::Reverse(*retrievals));
+        if let Some(limit) = self.limit {
+            hot.truncate(limit);
+        }
+        hot
+    }
+
+    /// Re-summarize the hot sections of `tree` from `document` and note
+    /// the refresh in `stats`. Returns the refreshed sections, most
+    /// retrieved first.
+    ///
+    /// Prefix summaries are recomposed if the tree has them. On error,
+    /// sections summarized so far keep their new summaries and are noted
+    /// as refreshed.
+    pub async fn refresh(
+        &self,
+        tree: &mut DocumentTree,
+        document: &Document,
+        stats: &mut RetrievalStats,
+    ) -> Result<Vec<RefreshedSection>> {
+        let hot = self.hot_sections(tree, stats);
+        info!(sections = hot.len(), "refreshing hot sections");
+        let semaphore = Arc::new(Semaphore::new(self.concurrency));
+        let mut tasks = JoinSet::new();
+        for (i, (path, _, _)) in hot.iter().enumerate() {
+            let node = node_at_mut(&mut tree.nodes, path);
+            let text = document.content_range(node.start_index, node.end_index);
+            let client = self.client.clone();
+            let semaphore = semaphore.clone();
+            let title = node.title.clone();
+            tasks.spawn(async move {
+                let _permit = semaphore.acquire_owned().await.expect("semaphore closed");
+                (i, summarize_in_detail(&client, &title, &text).await)
+            });
+        }
+
+        let mut refreshed: Vec<Option<RefreshedSection>> = vec![None; hot.len()];
+        let mut failure = None;
+        while let Some(joined) = tasks.join_next().await {
+            let (i, summary) = joined.map_err(|e| PageIndexError::TreeError(e.to_string()))?;
+            let summary = match summary {
+                Ok(summary) => summary,
+                Err(e) => {
+                    failure.get_or_insert(e);
+                    continue;
+                }
+            };
+            let (path, key, retrievals) = &hot[i];
+            let node = node_at_mut(&mut tree.nodes, path);
+            node.summary = Some(summary.clone());
+            stats.mark_refreshed(key);
+            debug!(title = %node.title, "refreshed hot section");
+            refreshed[i] = Some(RefreshedSection {
+                node_id: node.node_id.clone(),
+                title: node.title.clone(),
+                start_index: node.start_index,
+                end_index: node.end_index,
+                retrievals: *retrievals,
+                summary,
+            });
+        }
+        if tree.has_prefix_summaries() {
+            tree.compose_prefix_summaries();
+        }
+        if let Some(e) = failure {
+            return Err(e);
+        }
+
+        if let Some(embedder) = &self.embedder {
+            let texts: Vec<String> = refreshed
+                .iter()
+                .flatten()
+                .map(|s| format!("{}\n\n{}", s.title, s.summary))
+                .collect();
+            let inputs: Vec<&str> = texts.iter().map(String::as_str).collect();
+            let embeddings = embedder.embed(&inputs).await?;
+            for ((path, _, _), embedding) in hot.iter().zip(embeddings) {
+                node_at_mut(&mut tree.nodes, path).embedding = Some(embedding);
+            }
+        }
+        Ok(refreshed.into_iter().flatten().collect())
+    }
+}
+
+/// Text a node is embedded from: its title and summary.
+pub fn embedding_text(node: &TreeNode) -> String {
+    match &node.summary {
+        Some(summary) => format!("{}\n\n{}", node.title, summary),
+        None => node.title.clone(),
+    }
+}
+
+/// Embed the title and summary of every node without an embedding (or of
+/// every node, with `overwrite`) into [`TreeNode::embedding`] with the
+/// client's model, e.g. before [exporting](crate::export::to_qdrant_jsonl)
+/// the tree to a vector database. Returns the number of nodes embedded.
+pub async fn embed_nodes(
+    client: &LlmClient,
+    tree: &mut DocumentTree,
+    overwrite: bool,
+) -> Result<usize> {
+    let paths: Vec<NodePath> = node_paths(&tree.nodes)
+        .into_iter()
+        .filter(|path| overwrite || node_at_mut(&mut tree.nodes, path).embedding.is_none())
+        .collect();
+    for batch in paths.chunks(EMBED_BATCH_SIZE) {
+        let texts: Vec<String> = batch
+            .iter()
+            .map(|path| embedding_text(node_at_mut(&mut tree.nodes, path)))
+            .collect();
+        let inputs: Vec<&str> = texts.iter().map(String::as_str).collect();
+        let embeddings = client.embed(&inputs).await?;
+        for (path, embedding) in batch.iter().zip(embeddings) {
+            node_at_mut(&mut tree.nodes, path).embedding = Some(embedding);
+        }
+        debug!(embedded = batch.len(), "embedded nodes");
+    }
+    Ok(paths.len())
+}
+
+/// Summarize a hot section in detail from its text.
+async fn summarize_in_detail(client: &LlmClient, title: &str, content: &str) -> Result<String> {
+    let content: String = match content.char_indices().nth(MAX_DETAILED_CONTENT_CHARS) {
+        Some((end, _)) => format!("{}...[truncated]", &content[..end]),
+        None => content.to_string(),
+    };
+    let prompt = Prompts::generate_detailed_node_summary()
+        .replace("{title}", title)
+        .replace("{content}", &content);
+
+    let response = client
+        .complete(Some(Prompts::system_document_analyzer()), &prompt)
+        .await?;
+    Ok(response.trim().to_string())
+}
+
+/// Summarize `title` from the summaries of its parts with a roll-up prompt.
+async fn roll_up(
+    client: &LlmClient,
+    template: &str,
+    title: &str,
+    parts: &[(String, String)],
+) -> Result<String> {
+    let summaries = parts
+        .iter()
+        .map(|(title, summary)| format!("- {}: {}", title, summary))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let prompt = template
+        .replace("{title}", title)
+        .replace("{summaries}", &summaries);
+
+    let response = client
+        .complete(Some(Prompts::system_document_analyzer()), &prompt)
+        .await?;
+    Ok(response.trim().to_string())
+}
+
+/// `(title, summary)` of each node, with an empty summary for nodes without
+/// one.
+fn children_summaries(nodes: &[TreeNode]) -> Vec<(String, String)> {
+    nodes
+        .iter()
+        .map(|n| (n.title.clone(), n.summary.clone().unwrap_or_default()))
+        .collect()
+}
+
+/// Paths of all nodes grouped by height (leaves first), so every node comes
+/// after all of its descendants.
+fn levels_by_height(nodes: &[TreeNode]) -> Vec<Vec<NodePath>> {
+    fn visit(nodes: &[TreeNode], path: &mut NodePath, levels: &mut Vec<Vec<NodePath>>) -> usize {
+        let mut max_height = 0;
+        for (i, node) in nodes.iter().enumerate() {
+            path.push(i);
+            let height = if node.nodes.is_empty() {
+                0
+            } else {
+                visit(&node.nodes, path, levels) + 1
+            };
+            if levels.len() <= height {
+                levels.resize_with(height + 1, Vec::new);
+            }
+            levels[height].push(path.clone());
+            path.pop();
+            max_height = max_height.max(height);
+        }
+        max_height
+    }
+
+    let mut levels = Vec::new();
+    visit(nodes, &mut Vec::new(), &mut levels);
+    levels
+}
+
+/// Paths of all nodes, depth first (the order of [`tree_documents`]).
+fn node_paths(nodes: &[TreeNode]) -> Vec<NodePath> {
+    fn visit(nodes: &[TreeNode], path: &mut NodePath, out: &mut Vec<NodePath>) {
+        for (i, node) in nodes.iter().enumerate() {
+            path.push(i);
+            out.push(path.clone());
+            visit(&node.nodes, path, out);
+            path.pop();
+        }
+    }
+
+    let mut out = Vec::new();
+    visit(nodes, &mut Vec::new(), &mut out);
+    out
+}
+
+fn node_at_mut<'a>(nodes: &'a mut [TreeNode], path: &[usize]) -> &'a mut TreeNode {
+    let (first, rest) = path.split_first().expect("empty node path");
+    let node = &mut nodes[*first];
+    if rest.is_empty() {
+        node
+    } else {
+        node_at_mut(&mut node.nodes, rest)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LlmConfig;
+
+    fn sample_tree() -> DocumentTree {
+        let mut methods = TreeNode::new("Methods", 1, 4);
+        let mut sampling = TreeNode::new("Sampling", 1, 2);
+        sampling.add_child(TreeNode::new("Sites", 1, 1));
+        sampling.add_child(TreeNode::new("Timing", 2, 2));
+        methods.add_child(sampling);
+        methods.add_child(TreeNode::new("Analysis", 3, 4));
+        DocumentTree::new("Report", vec![TreeNode::new("Intro", 1, 1), methods], 4)
+    }
+
+    #[test]
+    fn test_levels_by_height() {
+        let tree = sample_tree();
+        let levels = levels_by_height(&tree.nodes);
+        let titles: Vec<Vec<&str>> = levels
+            .iter()
+            .map(|level| {
+                level
+                    .iter()
+                    .map(|path| {
+                        let mut nodes = &tree.nodes;
+                        let mut node = &nodes[path[0]];
+                        for &i in &path[1..] {
+                            nodes = &node.nodes;
+                            node = &nodes[i];
+                        }
+                        node.title.as_str()
+                    })
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            titles,
+            vec![
+                vec!["Intro", "Sites", "Timing", "Analysis"],
+                vec!["Sampling"],
+                vec!["Methods"],
+            ]
+        );
+        assert!(levels_by_height(&[]).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_hot_sections() {
+        use crate::fixtures::{chat_reply, replay_interactions};
+        use crate::golden::PromptCapture;
+        use serde_json::json;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("refresh.fixture.json");
+        let chat = chat_reply("Detailed summary.");
+        let embeddings = json!({
+            "endpoint": "embeddings",
+            "request": {},
+            "status": 200,
+            "response": {"data": [{"embedding": [0.5, 0.25], "index": 0}, {"embedding": [1.0, 0.0], "index": 1}]},
+        });
+        let capture = PromptCapture::new(replay_interactions(
+            &path,
+            vec![chat.clone(), chat, embeddings],
+        ));
+        let client = capture.client().unwrap();
+
+        let mut tree = sample_tree();
+        let documents = tree_documents(&tree, None);
+        let by_title = |title: &str| {
+            documents
+                .iter()
+                .filter(|d| d.metadata.title == title)
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        let mut stats = RetrievalStats::default();
+        for _ in 0..3 {
+            stats.record(&[by_title("Analysis"), by_title("Intro")].concat());
+        }
+        for _ in 0..2 {
+            stats.record(&[by_title("Analysis"), by_title("Sites")].concat());
+        }
+
+        let document = Document::from_text("Report", "Text".to_string());
+        let refresher = HotSectionRefresher::new(client.clone())
+            .with_min_retrievals(3)
+            .with_embeddings(client);
+        let refreshed = refresher
+            .refresh(&mut tree, &document, &mut stats)
+            .await
+            .unwrap();
+        let titles: Vec<_> = refreshed.iter().map(|s| s.title.as_str()).collect();
+        assert_eq!(titles, vec!["Analysis", "Intro"]);
+        assert_eq!(refreshed[0].retrievals, 5);
+        let analysis = tree.find_by_title("Analysis").unwrap();
+        assert_eq!(analysis.summary.as_deref(), Some("Detailed summary."));
+        assert!(analysis.embedding.is_some());
+        assert!(tree.find_by_title("Sites").unwrap().summary.is_none());
+        assert_eq!(capture.prompts().len(), 3);
+        assert!(
+            capture.prompts()[0].request["messages"][1]["content"]
+                .as_str()
+                .unwrap()
+                .contains("frequently search for")
+        );
+
+        // Refreshed sections are hot again only after more retrievals
+        assert!(
+            stats
+                .sections
+                .values()
+                .all(|s| s.retrievals_since_refresh() < 3)
+        );
+        let again = refresher
+            .refresh(&mut tree, &document, &mut stats)
+            .await
+            .unwrap();
+        assert!(again.is_empty());
+        assert_eq!(capture.prompts().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_summarize_error() {
+        let client = LlmClient::new(LlmConfig {
+            api_base: "http://127.0.0.1:9".to_string(),
+            api_key: "x".to_string(),
+            model: "m".to_string(),
+            ..Default::default()
+        });
+        let document = Document::from_text("Report", "Text".to_string());
+        let mut tree = sample_tree();
+        let err = TreeSummarizer::new(client)
+            .with_concurrency(2)
+            .summarize(&mut tree, &document)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, PageIndexError::Http(_)), "{}", err);
+        assert!(tree.description.is_none());
+    }
+}
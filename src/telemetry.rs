@@ -0,0 +1,203 @@
+//! OpenTelemetry traces and metrics (`otel` feature).
+//!
+//! The library always emits `tracing` spans for its units of work:
+//!
+//! - `llm.chat` and `llm.embed` for LLM calls, with the model and the
+//!   tokens used
+//! - `index`, with `index.chunk` per structure-extraction chunk and
+//!   `index.summaries` for the summary phase
+//! - `search`, with `search.content` when section text is attached, and
+//!   `serve.retrieve` per request in serve mode
+//!
+//! With the `otel` feature, [`Telemetry`] exports those spans over
+//! OTLP/HTTP together with three counters:
+//!
+//! - `page_indexer.llm.requests` (`operation`)
+//! - `page_indexer.llm.tokens` (`operation`, `type` = `prompt`/`completion`)
+//! - `page_indexer.errors` (`operation`, `kind`)
+//!
+//! The exporters honour the standard `OTEL_EXPORTER_OTLP_*`,
+//! `OTEL_SERVICE_NAME` and `OTEL_RESOURCE_ATTRIBUTES` variables. Without
+//! the feature, recording metrics is a no-op.
+
+use crate::error::PageIndexError;
+use crate::llm::TokenUsage;
+
+#[cfg(all(feature = "otel", not(target_arch = "wasm32")))]
+pub use otel::Telemetry;
+
+/// Count a successful LLM call and the tokens it used.
+pub(crate) fn record_llm_call(operation: &'static str, usage: Option<&TokenUsage>) {
+    if let Some(usage) = usage {
+        let span = tracing::Span::current();
+        span.record("prompt_tokens", usage.prompt_tokens);
+        span.record("completion_tokens", usage.completion_tokens);
+    }
+    #[cfg(all(feature = "otel", not(target_arch = "wasm32")))]
+    otel::record_llm_call(operation, usage);
+    #[cfg(not(all(feature = "otel", not(target_arch = "wasm32"))))]
+    let _ = operation;
+}
+
+/// Count a failed operation and mark the current span as failed.
+pub(crate) fn record_error(operation: &'static str, error: &PageIndexError) {
+    tracing::Span::current().record("otel.status_code", "ERROR");
+    #[cfg(all(feature = "otel", not(target_arch = "wasm32")))]
+    otel::record_error(operation, error);
+    #[cfg(not(all(feature = "otel", not(target_arch = "wasm32"))))]
+    let _ = (operation, error);
+}
+
+#[cfg(all(feature = "otel", not(target_arch = "wasm32")))]
+mod otel {
+    use crate::error::{PageIndexError, Result};
+    use crate::llm::TokenUsage;
+    use opentelemetry::KeyValue;
+    use opentelemetry::metrics::Counter;
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_otlp::{MetricExporter, SpanExporter, WithExportConfig};
+    use opentelemetry_sdk::Resource;
+    use opentelemetry_sdk::metrics::SdkMeterProvider;
+    use opentelemetry_sdk::trace::SdkTracerProvider;
+    use std::sync::OnceLock;
+    use tracing::{Level, Subscriber};
+    use tracing_subscriber::Layer;
+    use tracing_subscriber::filter::Targets;
+    use tracing_subscriber::registry::LookupSpan;
+
+    const SCOPE: &str = "rust_page_indexer";
+
+    struct Metrics {
+        llm_requests: Counter<u64>,
+        llm_tokens: Counter<u64>,
+        errors: Counter<u64>,
+    }
+
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+    pub(super) fn record_llm_call(operation: &'static str, usage: Option<&TokenUsage>) {
+        let Some(metrics) = METRICS.get() else {
+            return;
+        };
+        let op = KeyValue::new("operation", operation);
+        metrics.llm_requests.add(1, std::slice::from_ref(&op));
+        if let Some(usage) = usage {
+            for (kind, tokens) in [
+                ("prompt", usage.prompt_tokens),
+                ("completion", usage.completion_tokens),
+            ] {
+                metrics
+                    .llm_tokens
+                    .add(tokens.into(), &[op.clone(), KeyValue::new("type", kind)]);
+            }
+        }
+    }
+
+    pub(super) fn record_error(operation: &'static str, error: &PageIndexError) {
+        if let Some(metrics) = METRICS.get() {
+            metrics.errors.add(
+                1,
+                &[
+                    KeyValue::new("operation", operation),
+                    KeyValue::new("kind", error.kind()),
+                ],
+            );
+        }
+    }
+
+    /// Installed OTLP exporters. Call [`Telemetry::shutdown`] before exiting
+    /// so buffered spans and metrics are flushed.
+    pub struct Telemetry {
+        tracer_provider: SdkTracerProvider,
+        meter_provider: SdkMeterProvider,
+    }
+
+    impl Telemetry {
+        /// Start exporting to an OTLP/HTTP collector.
+        ///
+        /// `endpoint` is the collector's base URL (e.g.
+        /// `http://localhost:4318`); when `None`, the `OTEL_EXPORTER_OTLP_*`
+        /// variables apply. `service_name` is used unless
+        /// `OTEL_SERVICE_NAME` is set.
+        pub fn init(endpoint: Option<&str>, service_name: &str) -> Result<Self> {
+            let base = endpoint.map(|e| e.trim_end_matches('/'));
+
+            let mut spans = SpanExporter::builder().with_http();
+            if let Some(base) = base {
+                spans = spans.with_endpoint(format!("{}/v1/traces", base));
+            }
+            let spans = spans
+                .build()
+                .map_err(|e| PageIndexError::Telemetry(e.to_string()))?;
+
+            let mut metrics = MetricExporter::builder().with_http();
+            if let Some(base) = base {
+                metrics = metrics.with_endpoint(format!("{}/v1/metrics", base));
+            }
+            let metrics = metrics
+                .build()
+                .map_err(|e| PageIndexError::Telemetry(e.to_string()))?;
+
+            let mut resource = Resource::builder();
+            if std::env::var_os("OTEL_SERVICE_NAME").is_none() {
+                resource = resource.with_service_name(service_name.to_string());
+            }
+            let resource = resource.build();
+
+            let tracer_provider = SdkTracerProvider::builder()
+                .with_batch_exporter(spans)
+                .with_resource(resource.clone())
+                .build();
+            let meter_provider = SdkMeterProvider::builder()
+                .with_periodic_exporter(metrics)
+                .with_resource(resource)
+                .build();
+            opentelemetry::global::set_meter_provider(meter_provider.clone());
+
+            let meter = opentelemetry::global::meter(SCOPE);
+            let _ = METRICS.set(Metrics {
+                llm_requests: meter
+                    .u64_counter("page_indexer.llm.requests")
+                    .with_description("Successful LLM API calls")
+                    .build(),
+                llm_tokens: meter
+                    .u64_counter("page_indexer.llm.tokens")
+                    .with_description("Tokens used by LLM API calls")
+                    .with_unit("{token}")
+                    .build(),
+                errors: meter
+                    .u64_counter("page_indexer.errors")
+                    .with_description("Failed LLM calls and requests")
+                    .build(),
+            });
+
+            Ok(Self {
+                tracer_provider,
+                meter_provider,
+            })
+        }
+
+        /// A `tracing` layer exporting this crate's and `binary_target`'s
+        /// spans at info level and above.
+        pub fn layer<S>(&self, binary_target: &str) -> impl Layer<S> + use<S>
+        where
+            S: Subscriber + for<'a> LookupSpan<'a>,
+        {
+            let targets = Targets::new()
+                .with_target(SCOPE, Level::INFO)
+                .with_target(binary_target, Level::INFO);
+            tracing_opentelemetry::layer()
+                .with_tracer(self.tracer_provider.tracer(SCOPE))
+                .with_filter(targets)
+        }
+
+        /// Flush and stop the exporters.
+        pub fn shutdown(self) -> Result<()> {
+            let traces = self.tracer_provider.shutdown();
+            let metrics = self.meter_provider.shutdown();
+            traces
+                .and(metrics)
+                .map_err(|e| PageIndexError::Telemetry(e.to_string()))
+        }
+    }
+}
@@ -3,8 +3,21 @@
 //! This module implements the core data structure used by PageIndex:
 //! a hierarchical tree where each node represents a section of the document.
 
+use crate::document::estimate_tokens;
+use crate::error::PageIndexError;
 use bincode::{Decode, Encode};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+/// 64-bit FNV-1a hash of some bytes, in hex.
+pub(crate) fn fnv1a_hex(bytes: &[u8]) -> String {
+    let hash = bytes.iter().fold(0xcbf2_9ce4_8422_2325_u64, |hash, &byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
 
 /// A node in the document tree structure.
 #[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
@@ -30,9 +43,45 @@ pub struct TreeNode {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub summary: Option<String>,
 
+    /// The summary prefixed with the titles of the node's ancestors (see
+    /// [`DocumentTree::compose_prefix_summaries`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefix_summary: Option<String>,
+
     /// Optional node ID for reference.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub node_id: Option<String>,
+
+    /// Non-content section (references, index, ...) left out of search
+    /// prompts unless a query asks for it (see
+    /// [`DocumentTree::mark_excluded`]).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub excluded: bool,
+
+    /// Access labels: when set, only callers holding one of them see this
+    /// section and its subsections (see [`DocumentTree::visible_to`]).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub acl: Vec<String>,
+
+    /// How sure the indexer is of the title and page range, from 0 to 1
+    /// (see [`IndexerOptions::score_confidence`](crate::indexer::IndexerOptions::score_confidence)).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<f32>,
+
+    /// The title as extracted, when [canonicalization](canonicalize_titles)
+    /// changed it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_title: Option<String>,
+
+    /// Embedding of the title and summary, for sections refreshed with
+    /// embeddings (see [`HotSectionRefresher`](crate::summarize::HotSectionRefresher)).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding: Option<Vec<f32>>,
+
+    /// Date the section took effect, as `YYYY-MM-DD`. Undated sections take
+    /// their closest dated ancestor's (see [`DocumentTree::effective_date_of`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub effective_date: Option<String>,
 }
 
 impl TreeNode {
@@ -45,7 +94,14 @@ impl TreeNode {
             end_index,
             nodes: Vec::new(),
             summary: None,
+            prefix_summary: None,
             node_id: None,
+            excluded: false,
+            acl: Vec::new(),
+            confidence: None,
+            original_title: None,
+            embedding: None,
+            effective_date: None,
         }
     }
 
@@ -109,6 +165,23 @@ impl TreeNode {
 
     /// Format the tree as a string for display.
     pub fn format_tree(&self, indent: usize) -> String {
+        self.format_tree_with(indent, true)
+    }
+
+    /// Format the tree for display, optionally including summaries.
+    pub fn format_tree_with(&self, indent: usize, summaries: bool) -> String {
+        self.format_tree_options(
+            indent,
+            &DisplayOptions {
+                summaries,
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Format the tree for display with the given details.
+    pub fn format_tree_options(&self, indent: usize, options: &DisplayOptions) -> String {
+        let summaries = options.summaries;
         let prefix = "  ".repeat(indent);
         let structure_str = self
             .structure
@@ -119,17 +192,40 @@ impl TreeNode {
         let summary_str = self
             .summary
             .as_ref()
+            .filter(|_| summaries)
             .map(|s| format!("\n{}  Summary: {}", prefix, s))
             .unwrap_or_default();
 
+        let excluded_str = if self.excluded { " (excluded)" } else { "" };
+
+        let acl_str = if self.acl.is_empty() {
+            String::new()
+        } else {
+            format!(" [acl: {}]", self.acl.join(", "))
+        };
+
+        let confidence_str = match self.confidence.filter(|_| options.confidence) {
+            Some(c) if c < LOW_CONFIDENCE => format!(" [confidence {:.2}, check]", c),
+            Some(c) => format!(" [confidence {:.2}]", c),
+            None => String::new(),
+        };
+
         let mut result = format!(
-            "{}{}{} [pages {}-{}]{}",
-            prefix, structure_str, self.title, self.start_index, self.end_index, summary_str
+            "{}{}{} [pages {}-{}]{}{}{}{}",
+            prefix,
+            structure_str,
+            self.title,
+            self.start_index,
+            self.end_index,
+            excluded_str,
+            acl_str,
+            confidence_str,
+            summary_str
         );
         result.push('\n');
 
         for child in &self.nodes {
-            result.push_str(&child.format_tree(indent + 1));
+            result.push_str(&child.format_tree_options(indent + 1, options));
         }
 
         result
@@ -137,6 +233,12 @@ impl TreeNode {
 
     /// Format for search (includes node_id and summary).
     pub fn format_for_search(&self, indent: usize) -> String {
+        self.format_for_search_with(indent, false)
+    }
+
+    /// Format for search, describing nodes by their prefix summaries where
+    /// they have one when `prefix_summaries` is set.
+    pub fn format_for_search_with(&self, indent: usize, prefix_summaries: bool) -> String {
         let prefix = "  ".repeat(indent);
         let node_id_str = self
             .node_id
@@ -144,26 +246,93 @@ impl TreeNode {
             .map(|id| format!("[{}] ", id))
             .unwrap_or_default();
 
-        let summary_str = self
-            .summary
+        let summary = match &self.prefix_summary {
+            Some(prefixed) if prefix_summaries => Some(prefixed),
+            _ => self.summary.as_ref(),
+        };
+        let summary_str = summary.map(|s| format!(" - {}", s)).unwrap_or_default();
+        let date_str = self
+            .effective_date
             .as_ref()
-            .map(|s| format!(" - {}", s))
+            .map(|d| format!(", effective {}", d))
             .unwrap_or_default();
 
         let mut result = format!(
-            "{}{}{} (pages {}-{}){}",
-            prefix, node_id_str, self.title, self.start_index, self.end_index, summary_str
+            "{}{}{} (pages {}-{}{}){}",
+            prefix,
+            node_id_str,
+            self.title,
+            self.start_index,
+            self.end_index,
+            date_str,
+            summary_str
         );
         result.push('\n');
 
         for child in &self.nodes {
-            result.push_str(&child.format_for_search(indent + 1));
+            result.push_str(&child.format_for_search_with(indent + 1, prefix_summaries));
         }
 
         result
     }
 }
 
+/// The file a tree was indexed from, as it was when indexed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
+pub struct SourceInfo {
+    /// Path the document was loaded from, as given when indexing.
+    pub path: String,
+    /// 64-bit FNV-1a hash of the file's bytes, in hex.
+    pub hash: String,
+    /// File size in bytes.
+    pub size: u64,
+    /// Modification time in seconds since the Unix epoch, where the
+    /// filesystem reports one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub modified: Option<u64>,
+}
+
+impl SourceInfo {
+    /// Record a file as it is now.
+    pub fn read(path: &Path) -> crate::error::Result<Self> {
+        let bytes = std::fs::read(path).map_err(|e| PageIndexError::io(path, e))?;
+        Ok(Self {
+            path: path.to_string_lossy().into_owned(),
+            hash: fnv1a_hex(&bytes),
+            size: bytes.len() as u64,
+            modified: modified_secs(path),
+        })
+    }
+}
+
+fn modified_secs(path: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_secs())
+}
+
+/// Whether a tree's source document changed since the tree was built (see
+/// [`DocumentTree::freshness`]).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Freshness {
+    /// The source is as it was when indexed.
+    Fresh,
+    /// The source's content changed; the tree may be out of date.
+    Changed,
+    /// The source can no longer be read.
+    Missing,
+    /// The tree does not record its source (it was not indexed from a file,
+    /// or was indexed before sources were recorded).
+    Unknown,
+}
+
+impl Freshness {
+    /// Whether the tree no longer matches its source.
+    pub fn is_stale(self) -> bool {
+        self == Freshness::Changed
+    }
+}
+
 /// A complete document tree (wrapper around root nodes).
 #[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
 pub struct DocumentTree {
@@ -179,6 +348,27 @@ pub struct DocumentTree {
     /// Optional document description.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub description: Option<String>,
+
+    /// Summaries and the description were stripped, leaving only what is
+    /// needed to navigate (see [`DocumentTree::to_navigation_only`]).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub navigation_only: bool,
+
+    /// Free-form document metadata such as `type: contract`, used to route
+    /// queries across a corpus (see [`crate::router`]).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metadata: BTreeMap<String, String>,
+
+    /// Glossary of the document's jargon, term to definition. Definitions
+    /// of the terms a query uses are added to its search and answer
+    /// prompts (see [`crate::query::terms_in`]).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub terms: BTreeMap<String, String>,
+
+    /// The file the tree was indexed from, used to detect when the index
+    /// is out of date (see [`DocumentTree::freshness`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<SourceInfo>,
 }
 
 impl DocumentTree {
@@ -189,14 +379,62 @@ impl DocumentTree {
             nodes,
             total_pages,
             description: None,
+            navigation_only: false,
+            metadata: BTreeMap::new(),
+            terms: BTreeMap::new(),
+            source: None,
         }
     }
 
+    /// Set a metadata value.
+    pub fn with_metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
+
     /// Get total node count.
     pub fn node_count(&self) -> usize {
         self.nodes.iter().map(|n| n.node_count()).sum()
     }
 
+    /// Stable hash of the tree's contents (64-bit FNV-1a of its JSON, in
+    /// hex), which changes whenever the tree is re-indexed or edited.
+    pub fn content_hash(&self) -> String {
+        fnv1a_hex(&serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    /// Check the recorded source file for changes since the tree was
+    /// built. Relative source paths resolve against the current directory.
+    pub fn freshness(&self) -> Freshness {
+        match &self.source {
+            Some(source) => self.freshness_of(Path::new(&source.path)),
+            None => Freshness::Unknown,
+        }
+    }
+
+    /// Check a file (e.g. the source at a new location) against the
+    /// recorded source. Files with the recorded size and modification time
+    /// are taken as unchanged; others are hashed.
+    pub fn freshness_of(&self, path: &Path) -> Freshness {
+        let Some(source) = &self.source else {
+            return Freshness::Unknown;
+        };
+        let Ok(metadata) = std::fs::metadata(path) else {
+            return Freshness::Missing;
+        };
+        if metadata.len() != source.size {
+            return Freshness::Changed;
+        }
+        if source.modified.is_some() && modified_secs(path) == source.modified {
+            return Freshness::Fresh;
+        }
+        match std::fs::read(path) {
+            Ok(bytes) if fnv1a_hex(&bytes) == source.hash => Freshness::Fresh,
+            Ok(_) => Freshness::Changed,
+            Err(_) => Freshness::Missing,
+        }
+    }
+
     /// Get maximum depth of the tree.
     pub fn max_depth(&self) -> usize {
         fn depth(node: &TreeNode) -> usize {
@@ -220,8 +458,389 @@ impl DocumentTree {
         None
     }
 
+    /// Find a node by address: a `node_id` (e.g. "0003"), or failing that a
+    /// structure path (e.g. "1.2").
+    pub fn find_node(&self, address: &str) -> Option<&TreeNode> {
+        let path = self.path_to(address)?;
+        let mut node = &self.nodes[path[0]];
+        for &i in &path[1..] {
+            node = &node.nodes[i];
+        }
+        Some(node)
+    }
+
+    /// Mutable version of [`DocumentTree::find_node`].
+    pub fn find_node_mut(&mut self, address: &str) -> Option<&mut TreeNode> {
+        let path = self.path_to(address)?;
+        let mut node = &mut self.nodes[path[0]];
+        for &i in &path[1..] {
+            node = &mut node.nodes[i];
+        }
+        Some(node)
+    }
+
+    /// Remove a node (and its subtree) by address, returning it.
+    pub fn remove_node(&mut self, address: &str) -> Option<TreeNode> {
+        let path = self.path_to(address)?;
+        let (last, parents) = path.split_last()?;
+        let mut siblings = &mut self.nodes;
+        for &i in parents {
+            siblings = &mut siblings[i].nodes;
+        }
+        Some(siblings.remove(*last))
+    }
+
+    /// Child-index path to the node with the given address.
+    pub(crate) fn path_to(&self, address: &str) -> Option<Vec<usize>> {
+        fn search(
+            nodes: &[TreeNode],
+            matches: &dyn Fn(&TreeNode) -> bool,
+            path: &mut Vec<usize>,
+        ) -> bool {
+            for (i, node) in nodes.iter().enumerate() {
+                path.push(i);
+                if matches(node) || search(&node.nodes, matches, path) {
+                    return true;
+                }
+                path.pop();
+            }
+            false
+        }
+
+        let by_id = |n: &TreeNode| n.node_id.as_deref() == Some(address);
+        let by_structure = |n: &TreeNode| n.structure.as_deref() == Some(address);
+
+        let mut path = Vec::new();
+        if search(&self.nodes, &by_id, &mut path) || search(&self.nodes, &by_structure, &mut path) {
+            Some(path)
+        } else {
+            None
+        }
+    }
+
+    /// Set each node's `prefix_summary` to its summary prefixed with the
+    /// titles of its ancestors (`Methods > Sampling > Field Sites: ...`),
+    /// so a node's description keeps its place in the document when read
+    /// on its own. Nodes without a summary get none.
+    pub fn compose_prefix_summaries(&mut self) {
+        fn compose(nodes: &mut [TreeNode], titles: &mut Vec<String>) {
+            for node in nodes {
+                titles.push(node.title.clone());
+                node.prefix_summary = node
+                    .summary
+                    .as_ref()
+                    .map(|summary| format!("{}: {}", titles.join(" > "), summary));
+                compose(&mut node.nodes, titles);
+                titles.pop();
+            }
+        }
+        compose(&mut self.nodes, &mut Vec::new());
+    }
+
+    /// [Canonicalize](canonical_title) every title, keeping the originals
+    /// in [`TreeNode::original_title`]. Returns how many titles changed.
+    pub fn canonicalize_titles(&mut self) -> usize {
+        canonicalize_titles(&mut self.nodes)
+    }
+
+    /// Mark the sections titled like one of `titles` as
+    /// [excluded](TreeNode::excluded) from search prompts, returning how
+    /// many were marked. Titles match case-insensitively, ignoring leading
+    /// numbering and trailing punctuation (`7. References:` matches
+    /// `references`).
+    pub fn mark_excluded(&mut self, titles: &[String]) -> usize {
+        fn mark(nodes: &mut [TreeNode], titles: &[String]) -> usize {
+            let mut marked = 0;
+            for node in nodes {
+                let title = normalize_section_title(&node.title);
+                if !node.excluded && titles.iter().any(|t| normalize_section_title(t) == title) {
+                    node.excluded = true;
+                    marked += 1;
+                }
+                marked += mark(&mut node.nodes, titles);
+            }
+            marked
+        }
+        mark(&mut self.nodes, titles)
+    }
+
+    /// Whether any node is [excluded](TreeNode::excluded).
+    pub fn has_excluded(&self) -> bool {
+        fn any(nodes: &[TreeNode]) -> bool {
+            nodes.iter().any(|n| n.excluded || any(&n.nodes))
+        }
+        any(&self.nodes)
+    }
+
+    /// Copy of the tree without [excluded](TreeNode::excluded) sections
+    /// and their subsections.
+    pub fn without_excluded(&self) -> DocumentTree {
+        fn keep(nodes: &[TreeNode]) -> Vec<TreeNode> {
+            nodes
+                .iter()
+                .filter(|n| !n.excluded)
+                .map(|n| TreeNode {
+                    nodes: keep(&n.nodes),
+                    ..n.clone()
+                })
+                .collect()
+        }
+        DocumentTree {
+            nodes: keep(&self.nodes),
+            ..self.clone()
+        }
+    }
+
+    /// Whether any node has [access labels](TreeNode::acl).
+    pub fn has_acl(&self) -> bool {
+        fn any(nodes: &[TreeNode]) -> bool {
+            nodes.iter().any(|n| !n.acl.is_empty() || any(&n.nodes))
+        }
+        any(&self.nodes)
+    }
+
+    /// Copy of the tree with only the sections a caller holding `labels`
+    /// may see: a section with [access labels](TreeNode::acl) is removed,
+    /// with its subsections, unless it shares one with `labels`.
+    pub fn visible_to(&self, labels: &[String]) -> DocumentTree {
+        fn keep(nodes: &[TreeNode], labels: &[String]) -> Vec<TreeNode> {
+            nodes
+                .iter()
+                .filter(|n| n.acl.is_empty() || n.acl.iter().any(|l| labels.contains(l)))
+                .map(|n| TreeNode {
+                    nodes: keep(&n.nodes, labels),
+                    ..n.clone()
+                })
+                .collect()
+        }
+        DocumentTree {
+            nodes: keep(&self.nodes, labels),
+            ..self.clone()
+        }
+    }
+
+    /// Pages of the sections [`visible_to`](Self::visible_to) hides from a
+    /// caller holding `labels`. A section the caller may see can span
+    /// them, so its text must be read without these pages.
+    pub fn hidden_pages(&self, labels: &[String]) -> BTreeSet<usize> {
+        fn collect(nodes: &[TreeNode], labels: &[String], pages: &mut BTreeSet<usize>) {
+            for n in nodes {
+                if n.acl.is_empty() || n.acl.iter().any(|l| labels.contains(l)) {
+                    collect(&n.nodes, labels, pages);
+                } else {
+                    pages.extend(n.start_index..=n.end_index);
+                }
+            }
+        }
+        let mut pages = BTreeSet::new();
+        collect(&self.nodes, labels, &mut pages);
+        pages
+    }
+
+    /// The effective date of the section covering exactly this page range:
+    /// its own [`effective_date`](TreeNode::effective_date), else its
+    /// closest dated ancestor's, else the document's `effective_date`
+    /// [metadata](DocumentTree::metadata).
+    pub fn effective_date_of(&self, start_index: usize, end_index: usize) -> Option<&str> {
+        fn find<'a>(
+            nodes: &'a [TreeNode],
+            range: (usize, usize),
+            inherited: Option<&'a str>,
+        ) -> Option<Option<&'a str>> {
+            nodes.iter().find_map(|n| {
+                let date = n.effective_date.as_deref().or(inherited);
+                if (n.start_index, n.end_index) == range {
+                    Some(date)
+                } else {
+                    find(&n.nodes, range, date)
+                }
+            })
+        }
+        let document = self.metadata.get(EFFECTIVE_DATE_KEY).map(String::as_str);
+        find(&self.nodes, (start_index, end_index), document).flatten()
+    }
+
+    /// Whether any node has an [effective date](TreeNode::effective_date).
+    pub fn has_effective_dates(&self) -> bool {
+        fn any(nodes: &[TreeNode]) -> bool {
+            nodes
+                .iter()
+                .any(|n| n.effective_date.is_some() || any(&n.nodes))
+        }
+        self.metadata.contains_key(EFFECTIVE_DATE_KEY) || any(&self.nodes)
+    }
+
+    /// Copy of the tree without the sections that took effect before
+    /// `date` (`YYYY-MM-DD`), by [`effective_date_of`](Self::effective_date_of).
+    /// Undated sections stay, and so do older sections with subsections
+    /// that stay, as containers only: check results against
+    /// [`is_current_since`](Self::is_current_since).
+    pub fn effective_since(&self, date: &str) -> DocumentTree {
+        fn keep(nodes: &[TreeNode], date: &str, inherited: Option<&str>) -> Vec<TreeNode> {
+            nodes
+                .iter()
+                .filter_map(|n| {
+                    let effective = n.effective_date.as_deref().or(inherited);
+                    let children = keep(&n.nodes, date, effective);
+                    let current = effective.is_none_or(|d| d >= date);
+                    (current || !children.is_empty()).then(|| TreeNode {
+                        nodes: children,
+                        ..n.clone()
+                    })
+                })
+                .collect()
+        }
+        let document = self.metadata.get(EFFECTIVE_DATE_KEY);
+        DocumentTree {
+            nodes: keep(&self.nodes, date, document.map(String::as_str)),
+            ..self.clone()
+        }
+    }
+
+    /// Whether `node`'s section, found as by [`has_section`](Self::has_section),
+    /// took effect on or after `date`, by its own or inherited effective
+    /// date. Undated sections and sections not in the tree count as
+    /// current.
+    pub fn is_current_since(&self, node: &TreeNode, date: &str) -> bool {
+        fn find<'a>(
+            nodes: &'a [TreeNode],
+            node: &TreeNode,
+            inherited: Option<&'a str>,
+        ) -> Option<Option<&'a str>> {
+            nodes.iter().find_map(|n| {
+                let effective = n.effective_date.as_deref().or(inherited);
+                if same_section(n, node) {
+                    Some(effective)
+                } else {
+                    find(&n.nodes, node, effective)
+                }
+            })
+        }
+        let document = self.metadata.get(EFFECTIVE_DATE_KEY).map(String::as_str);
+        find(&self.nodes, node, document)
+            .flatten()
+            .is_none_or(|d| d >= date)
+    }
+
+    /// The section a search result names: the node with its title and page
+    /// range, else the first node covering exactly that range.
+    pub fn find_section(
+        &self,
+        title: &str,
+        start_index: usize,
+        end_index: usize,
+    ) -> Option<&TreeNode> {
+        fn find<'a>(
+            nodes: &'a [TreeNode],
+            matches: &dyn Fn(&TreeNode) -> bool,
+        ) -> Option<&'a TreeNode> {
+            nodes.iter().find_map(|n| {
+                if matches(n) {
+                    Some(n)
+                } else {
+                    find(&n.nodes, matches)
+                }
+            })
+        }
+        let covers = |n: &TreeNode| n.start_index == start_index && n.end_index == end_index;
+        find(&self.nodes, &|n| covers(n) && n.title == title).or_else(|| find(&self.nodes, &covers))
+    }
+
+    /// Whether this tree, a copy of one `node` is from with sections left
+    /// out, still has the node: one with its `node_id` when it has one,
+    /// else one with its title and page range. Sections with the same
+    /// pages are told apart by their IDs.
+    pub fn has_section(&self, node: &TreeNode) -> bool {
+        fn any(nodes: &[TreeNode], node: &TreeNode) -> bool {
+            nodes
+                .iter()
+                .any(|n| same_section(n, node) || any(&n.nodes, node))
+        }
+        any(&self.nodes, node)
+    }
+
+    /// Whether any node has a [confidence](TreeNode::confidence) score.
+    pub fn has_confidence(&self) -> bool {
+        fn any(nodes: &[TreeNode]) -> bool {
+            nodes
+                .iter()
+                .any(|n| n.confidence.is_some() || any(&n.nodes))
+        }
+        any(&self.nodes)
+    }
+
+    /// Sections scored below `threshold` [confidence](TreeNode::confidence),
+    /// depth-first.
+    pub fn low_confidence(&self, threshold: f32) -> Vec<&TreeNode> {
+        fn collect<'a>(nodes: &'a [TreeNode], threshold: f32, out: &mut Vec<&'a TreeNode>) {
+            for node in nodes {
+                if node.confidence.is_some_and(|c| c < threshold) {
+                    out.push(node);
+                }
+                collect(&node.nodes, threshold, out);
+            }
+        }
+        let mut out = Vec::new();
+        collect(&self.nodes, threshold, &mut out);
+        out
+    }
+
+    /// Whether any node has a summary.
+    pub fn has_summaries(&self) -> bool {
+        fn any(nodes: &[TreeNode]) -> bool {
+            nodes.iter().any(|n| n.summary.is_some() || any(&n.nodes))
+        }
+        any(&self.nodes)
+    }
+
+    /// Whether any node has a prefix summary.
+    pub fn has_prefix_summaries(&self) -> bool {
+        fn any(nodes: &[TreeNode]) -> bool {
+            nodes
+                .iter()
+                .any(|n| n.prefix_summary.is_some() || any(&n.nodes))
+        }
+        any(&self.nodes)
+    }
+
+    /// Copy of the tree with only titles, structure, node IDs and page
+    /// ranges: summaries, prefix summaries, embeddings and the description
+    /// are dropped
+    /// and the tree is marked [`navigation_only`](Self::navigation_only).
+    ///
+    /// The result is a small index for client apps that fetch section text
+    /// on demand (e.g. through a [`DocumentStore`](crate::store::DocumentStore)).
+    pub fn to_navigation_only(&self) -> DocumentTree {
+        fn strip(nodes: &mut [TreeNode]) {
+            for node in nodes {
+                node.summary = None;
+                node.prefix_summary = None;
+                node.embedding = None;
+                strip(&mut node.nodes);
+            }
+        }
+        let mut tree = self.clone();
+        strip(&mut tree.nodes);
+        tree.description = None;
+        tree.navigation_only = true;
+        tree
+    }
+
     /// Format the entire tree for display.
     pub fn format(&self) -> String {
+        self.format_with(true)
+    }
+
+    /// Format the entire tree for display, optionally including summaries.
+    pub fn format_with(&self, summaries: bool) -> String {
+        self.format_options(&DisplayOptions {
+            summaries,
+            ..Default::default()
+        })
+    }
+
+    /// Format the entire tree for display with the given details.
+    pub fn format_options(&self, options: &DisplayOptions) -> String {
         let mut result = format!(
             "Document: {} ({} pages, {} sections)\n",
             self.name,
@@ -232,26 +851,107 @@ impl DocumentTree {
         result.push('\n');
 
         for node in &self.nodes {
-            result.push_str(&node.format_tree(0));
+            result.push_str(&node.format_tree_options(0, options));
         }
 
         result
     }
 
+    /// Copy of the tree with only the nodes selected by `filter`.
+    ///
+    /// Ancestors of matching nodes are kept so matches stay in context.
+    pub fn filtered(&self, filter: &TreeFilter) -> DocumentTree {
+        let mut tree = self.clone();
+        tree.nodes = filter.apply(&self.nodes, 1);
+        tree
+    }
+
     /// Format tree for search (includes node_ids and summaries).
     pub fn format_for_search(&self) -> String {
+        self.format_for_search_with(false)
+    }
+
+    /// Format tree for search, optionally with prefix summaries in place of
+    /// plain ones.
+    pub fn format_for_search_with(&self, prefix_summaries: bool) -> String {
         let mut result = format!(
             "Document: {} ({} pages)\n\nSections:\n",
             self.name, self.total_pages,
         );
 
         for node in &self.nodes {
-            result.push_str(&node.format_for_search(0));
+            result.push_str(&node.format_for_search_with(0, prefix_summaries));
         }
 
         result
     }
 
+    /// Compact outline of the tree for prompts: one line per section with
+    /// its title, page range and (optionally) summary, indented by depth.
+    ///
+    /// With a token ceiling, detail is dropped from the bottom up until the
+    /// outline fits: first the summaries of the deepest shown level, then
+    /// that level itself, and so on up to bare top-level titles. Sections
+    /// whose children were dropped say how many they have.
+    pub fn to_outline(&self, options: &OutlineOptions) -> String {
+        let max_depth = self.max_depth().max(1);
+        let mut depth = max_depth;
+        let mut summary_depth = if options.summaries { max_depth } else { 0 };
+        loop {
+            let outline = self.render_outline(depth, summary_depth, options.prefix_summaries);
+            let fits = options
+                .max_tokens
+                .is_none_or(|max| estimate_tokens(&outline) <= max);
+            if fits || (depth == 1 && summary_depth == 0) {
+                return outline;
+            }
+            if summary_depth >= depth {
+                summary_depth -= 1;
+            } else {
+                depth -= 1;
+            }
+        }
+    }
+
+    /// Outline down to `depth` levels, with summaries on the first
+    /// `summary_depth` of them.
+    fn render_outline(&self, depth: usize, summary_depth: usize, prefix: bool) -> String {
+        fn visit(nodes: &[TreeNode], level: usize, limits: (usize, usize, bool), out: &mut String) {
+            let (depth, summary_depth, prefix) = limits;
+            for node in nodes {
+                out.push_str(&"  ".repeat(level - 1));
+                out.push_str(&node.title);
+                if node.start_index == node.end_index {
+                    out.push_str(&format!(" [{}]", node.start_index));
+                } else {
+                    out.push_str(&format!(" [{}-{}]", node.start_index, node.end_index));
+                }
+                if let Some(date) = &node.effective_date {
+                    out.push_str(&format!(" (effective {})", date));
+                }
+                let summary = match &node.prefix_summary {
+                    Some(prefixed) if prefix => Some(prefixed),
+                    _ => node.summary.as_ref(),
+                };
+                if let Some(summary) = summary.filter(|_| level <= summary_depth) {
+                    out.push_str(": ");
+                    out.push_str(summary.trim());
+                }
+                if level >= depth && !node.nodes.is_empty() {
+                    out.push_str(&format!(" (+{} subsections)", node.node_count() - 1));
+                }
+                out.push('\n');
+                if level < depth {
+                    visit(&node.nodes, level + 1, limits, out);
+                }
+            }
+        }
+
+        let mut out = format!("# {} ({} pages)\n", self.name, self.total_pages);
+        visit(&self.nodes, 1, (depth, summary_depth, prefix), &mut out);
+        out
+    }
+
     /// Convert to JSON string.
     pub fn to_json(&self) -> Result<String, serde_json::Error> {
         serde_json::to_string_pretty(self)
@@ -263,8 +963,132 @@ impl DocumentTree {
     }
 }
 
+/// A section title without leading numbering or trailing punctuation, in
+/// lowercase, for matching against [`DocumentTree::mark_excluded`] titles.
+/// Read a date as `YYYY-MM-DD`, also accepting `YYYY-MM` and `YYYY` (the
+/// first day of the month or year). `None` if it is not a valid date.
+pub fn parse_effective_date(text: &str) -> Option<String> {
+    let parts: Vec<&str> = text.trim().split('-').collect();
+    let number = |part: &str, digits: usize| {
+        (part.len() == digits && part.bytes().all(|b| b.is_ascii_digit()))
+            .then(|| part.parse::<u32>().ok())
+            .flatten()
+    };
+    let (year, month, day) = match parts[..] {
+        [year] => (number(year, 4)?, 1, 1),
+        [year, month] => (number(year, 4)?, number(month, 2)?, 1),
+        [year, month, day] => (number(year, 4)?, number(month, 2)?, number(day, 2)?),
+        _ => return None,
+    };
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days = match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return None,
+    };
+    (1..=days)
+        .contains(&day)
+        .then(|| format!("{:04}-{:02}-{:02}", year, month, day))
+}
+
+fn normalize_section_title(title: &str) -> String {
+    title
+        .trim_start_matches(|c: char| c.is_ascii_digit() || c.is_whitespace() || ".)-".contains(c))
+        .trim_end_matches(|c: char| c.is_whitespace() || ".:".contains(c))
+        .to_lowercase()
+}
+
+/// [Confidence](TreeNode::confidence) below which a section is worth
+/// double-checking.
+pub const LOW_CONFIDENCE: f32 = 0.5;
+
+/// [Metadata](DocumentTree::metadata) key of the date a whole document took
+/// effect, for sections without their own.
+pub const EFFECTIVE_DATE_KEY: &str = "effective_date";
+
+/// What [`DocumentTree::format_options`] shows besides titles and pages.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DisplayOptions {
+    /// Include node summaries.
+    pub summaries: bool,
+    /// Include [confidence](TreeNode::confidence) scores, flagging low ones.
+    pub confidence: bool,
+}
+
+/// What [`DocumentTree::to_outline`] includes.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OutlineOptions {
+    /// Include node summaries.
+    pub summaries: bool,
+    /// Use prefix summaries in place of plain ones where nodes have them.
+    pub prefix_summaries: bool,
+    /// Approximate token ceiling; detail is dropped until the outline fits.
+    pub max_tokens: Option<usize>,
+}
+
+/// Whether two nodes are the same section: the same `node_id` when `b`
+/// has one, else both without one and with the same title and pages.
+fn same_section(a: &TreeNode, b: &TreeNode) -> bool {
+    match &b.node_id {
+        Some(id) => a.node_id.as_ref() == Some(id),
+        None => {
+            a.node_id.is_none()
+                && a.title == b.title
+                && (a.start_index, a.end_index) == (b.start_index, b.end_index)
+        }
+    }
+}
+
+/// Selects a subset of a tree for display (see [`DocumentTree::filtered`]).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TreeFilter {
+    /// Maximum depth to keep (1 = top-level sections only).
+    pub max_depth: Option<usize>,
+    /// Keep nodes overlapping this inclusive page range.
+    pub pages: Option<(usize, usize)>,
+    /// Keep nodes whose title contains this text (case-insensitive).
+    pub title: Option<String>,
+}
+
+impl TreeFilter {
+    fn matches(&self, node: &TreeNode) -> bool {
+        let in_pages = self
+            .pages
+            .is_none_or(|(start, end)| node.start_index <= end && node.end_index >= start);
+        let title_matches = self
+            .title
+            .as_ref()
+            .is_none_or(|t| node.title.to_lowercase().contains(&t.to_lowercase()));
+        in_pages && title_matches
+    }
+
+    /// Nodes at `depth` that match or have matching descendants within
+    /// the depth limit. Matches below the limit don't keep their
+    /// ancestors, since they would be cut from the result.
+    fn apply(&self, nodes: &[TreeNode], depth: usize) -> Vec<TreeNode> {
+        nodes
+            .iter()
+            .filter_map(|node| {
+                let children = if self.max_depth.is_some_and(|max| depth >= max) {
+                    Vec::new()
+                } else {
+                    self.apply(&node.nodes, depth + 1)
+                };
+                if !self.matches(node) && children.is_empty() {
+                    return None;
+                }
+                let mut kept = node.clone();
+                kept.nodes = children;
+                Some(kept)
+            })
+            .collect()
+    }
+}
+
 /// Raw TOC item from LLM response (before tree construction).
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RawTocItem {
     /// Section structure index (e.g., "1.2.3").
     pub structure: Option<String>,
@@ -275,6 +1099,11 @@ pub struct RawTocItem {
     /// Physical page index (may be string like "<physical_index_5>" or integer).
     #[serde(alias = "page")]
     pub physical_index: Option<serde_json::Value>,
+
+    /// The LLM's certainty in the item, when asked for (a number from 0 to
+    /// 1, or "high", "medium" or "low").
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<serde_json::Value>,
 }
 
 impl RawTocItem {
@@ -296,6 +1125,20 @@ impl RawTocItem {
             _ => None,
         }
     }
+
+    /// The LLM's certainty in the item, from 0 to 1.
+    pub fn certainty(&self) -> Option<f32> {
+        match self.confidence.as_ref()? {
+            serde_json::Value::Number(n) => n.as_f64().map(|c| c.clamp(0.0, 1.0) as f32),
+            serde_json::Value::String(s) => match s.trim().to_lowercase().as_str() {
+                "high" => Some(0.9),
+                "medium" => Some(0.6),
+                "low" => Some(0.3),
+                other => other.parse::<f32>().ok().map(|c| c.clamp(0.0, 1.0)),
+            },
+            _ => None,
+        }
+    }
 }
 
 /// Build a tree structure from flat TOC items.
@@ -323,6 +1166,7 @@ pub fn build_tree_from_toc(items: &[RawTocItem], total_pages: usize) -> Vec<Tree
             .unwrap_or(total_pages);
 
         let mut node = TreeNode::new(&item.title, start_index, end_index);
+        node.confidence = item.certainty();
 
         if let Some(ref structure) = item.structure {
             node.structure = Some(structure.clone());
@@ -395,6 +1239,118 @@ fn fix_end_indices(node: &mut TreeNode) {
     }
 }
 
+/// Words kept lowercase inside a title when fixing its case.
+const MINOR_WORDS: &[&str] = &[
+    "a", "an", "and", "as", "at", "by", "for", "in", "of", "on", "or", "the", "to", "with",
+];
+
+/// Clean up a title as extracted from a document:
+///
+/// - whitespace runs become single spaces;
+/// - trailing dot leaders and page numbers (`Scope ....... 4`) are dropped;
+/// - leading numbering that repeats `structure` (`3 . 2 Methods` in
+///   section 3.2) is dropped;
+/// - letter-spaced words (`M E T H O D S`) are joined;
+/// - all-caps titles are put in title case (`DATA AND METHODS` becomes
+///   `Data and Methods`).
+///
+/// Words split by PDF extraction (`METH ODS`) cannot be told apart from
+/// real ones and are left alone.
+pub fn canonical_title(title: &str, structure: Option<&str>) -> String {
+    let mut title = title.split_whitespace().collect::<Vec<_>>().join(" ");
+
+    // Dot leaders to a page number, as in tables of contents
+    let without_page = title
+        .trim_end_matches(|c: char| c.is_ascii_digit())
+        .trim_end();
+    if without_page.ends_with("...") || without_page.ends_with('…') {
+        title = without_page
+            .trim_end_matches(|c: char| c == '.' || c == '…' || c.is_whitespace())
+            .to_string();
+    }
+
+    if let Some(structure) = structure {
+        let number_end = title
+            .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == ' '))
+            .unwrap_or(title.len());
+        let number: String = title[..number_end].chars().filter(|c| *c != ' ').collect();
+        if !number.is_empty() && number.trim_end_matches('.') == structure.trim_end_matches('.') {
+            let rest = title[number_end..]
+                .trim_start_matches([')', ':', '-', '–'])
+                .trim_start();
+            if !rest.is_empty() {
+                title = rest.to_string();
+            }
+        }
+    }
+
+    // Runs of three or more single letters are one letter-spaced word
+    let mut words: Vec<String> = Vec::new();
+    let mut letters = String::new();
+    let mut run = 0;
+    let flush = |words: &mut Vec<String>, letters: &mut String, run: &mut usize| {
+        if *run >= 3 {
+            words.push(std::mem::take(letters));
+        } else {
+            words.extend(letters.chars().map(String::from));
+            letters.clear();
+        }
+        *run = 0;
+    };
+    for word in title.split(' ') {
+        let mut chars = word.chars();
+        if let (Some(c), None) = (chars.next(), chars.next())
+            && c.is_alphabetic()
+        {
+            letters.push(c);
+            run += 1;
+        } else {
+            flush(&mut words, &mut letters, &mut run);
+            words.push(word.to_string());
+        }
+    }
+    flush(&mut words, &mut letters, &mut run);
+    let mut title = words.join(" ");
+
+    let letters = title.chars().filter(|c| c.is_alphabetic()).count();
+    if letters >= 4 && !title.chars().any(|c| c.is_lowercase()) {
+        title = title
+            .split(' ')
+            .enumerate()
+            .map(|(i, word)| {
+                let lower = word.to_lowercase();
+                if i > 0 && MINOR_WORDS.contains(&lower.as_str()) {
+                    return lower;
+                }
+                let mut chars = lower.chars();
+                match chars.next() {
+                    Some(first) => first.to_uppercase().chain(chars).collect(),
+                    None => lower,
+                }
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+    }
+    title
+}
+
+/// [Canonicalize](canonical_title) the titles of `nodes` and their
+/// subsections, keeping the originals in [`TreeNode::original_title`].
+/// Returns how many titles changed.
+pub fn canonicalize_titles(nodes: &mut [TreeNode]) -> usize {
+    let mut changed = 0;
+    for node in nodes {
+        let title = canonical_title(&node.title, node.structure.as_deref());
+        if title != node.title {
+            let original = std::mem::replace(&mut node.title, title);
+            node.original_title.get_or_insert(original);
+            changed += 1;
+        }
+        changed += canonicalize_titles(&mut node.nodes);
+    }
+    changed
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -431,6 +1387,285 @@ mod tests {
 
         assert_eq!(tree.node_count(), 2);
         assert_eq!(tree.max_depth(), 1);
+
+        let hash = tree.content_hash();
+        assert_eq!(hash, tree.clone().content_hash());
+        assert_ne!(hash, tree.with_metadata("type", "manual").content_hash());
+    }
+
+    #[test]
+    fn test_mark_excluded() {
+        let mut body = TreeNode::new("2 Results", 2, 8);
+        body.add_child(TreeNode::new("2.1 References", 8, 8));
+        let mut tree = DocumentTree::new(
+            "Paper",
+            vec![
+                TreeNode::new("1. Introduction", 1, 1),
+                body,
+                TreeNode::new("References:", 9, 10),
+                TreeNode::new("Index of Terms", 11, 12),
+            ],
+            12,
+        );
+        assert!(!tree.has_excluded());
+
+        let titles = vec!["references".to_string(), "Index".to_string()];
+        assert_eq!(tree.mark_excluded(&titles), 2);
+        assert_eq!(tree.mark_excluded(&titles), 0);
+        assert!(tree.nodes[1].nodes[0].excluded);
+        assert!(tree.nodes[2].excluded);
+        assert!(!tree.nodes[3].excluded);
+
+        let searchable = tree.without_excluded();
+        let titles: Vec<_> = searchable.nodes.iter().map(|n| n.title.as_str()).collect();
+        assert_eq!(
+            titles,
+            vec!["1. Introduction", "2 Results", "Index of Terms"]
+        );
+        assert!(searchable.nodes[1].nodes.is_empty());
+        assert!(!searchable.has_excluded());
+        assert_eq!(tree.node_count(), 5);
+    }
+
+    #[test]
+    fn test_canonical_title() {
+        assert_eq!(canonical_title("3 . 2   METHODS", Some("3.2")), "Methods");
+        assert_eq!(
+            canonical_title("1. Introduction", Some("1")),
+            "Introduction"
+        );
+        assert_eq!(canonical_title("2) Scope", Some("2")), "Scope");
+        // Numbering that is part of the title stays
+        assert_eq!(canonical_title("2024 Results", Some("1")), "2024 Results");
+        assert_eq!(canonical_title("1", Some("1")), "1");
+        assert_eq!(canonical_title("Scope ........ 4", None), "Scope");
+        assert_eq!(canonical_title("Chapter 4", None), "Chapter 4");
+        assert_eq!(
+            canonical_title("R E S U L T S and Discussion", None),
+            "RESULTS and Discussion"
+        );
+        assert_eq!(canonical_title("Appendix A", None), "Appendix A");
+        assert_eq!(
+            canonical_title("DATA  AND\nMETHODS", None),
+            "Data and Methods"
+        );
+        assert_eq!(canonical_title("Q&A", None), "Q&A");
+
+        let mut intro = TreeNode::new("1 INTRODUCTION", 1, 1).with_structure("1");
+        intro.add_child(TreeNode::new("1.1  Background", 1, 1).with_structure("1.1"));
+        let results = TreeNode::new("Results", 2, 2).with_structure("2");
+        let mut tree = DocumentTree::new("doc", vec![intro, results], 2);
+        assert_eq!(tree.canonicalize_titles(), 2);
+        assert_eq!(tree.nodes[0].title, "Introduction");
+        assert_eq!(
+            tree.nodes[0].original_title.as_deref(),
+            Some("1 INTRODUCTION")
+        );
+        assert_eq!(tree.nodes[0].nodes[0].title, "Background");
+        assert!(tree.nodes[1].original_title.is_none());
+        // Idempotent, and the first original is kept
+        assert_eq!(tree.canonicalize_titles(), 0);
+    }
+
+    #[test]
+    fn test_confidence() {
+        let item = |title: &str, structure: &str, confidence| RawTocItem {
+            structure: Some(structure.to_string()),
+            title: title.to_string(),
+            physical_index: Some(serde_json::json!(1)),
+            confidence,
+        };
+        let items = vec![
+            item("Intro", "1", Some(serde_json::json!(0.8))),
+            item("Background", "1.1", Some(serde_json::json!("low"))),
+            item("Method", "2", Some(serde_json::json!(1.7))),
+            item("Results", "3", None),
+        ];
+        assert_eq!(items[1].certainty(), Some(0.3));
+        assert_eq!(items[2].certainty(), Some(1.0));
+        // Not asked for, not written back into prompts or checkpoints
+        assert!(
+            !serde_json::to_string(&items[3])
+                .unwrap()
+                .contains("confidence")
+        );
+
+        let tree = DocumentTree::new("doc", build_tree_from_toc(&items, 3), 3);
+        let low: Vec<_> = tree
+            .low_confidence(LOW_CONFIDENCE)
+            .iter()
+            .map(|n| n.title.as_str())
+            .collect();
+        assert_eq!(low, vec!["Background"]);
+
+        let shown = tree.format_options(&DisplayOptions {
+            confidence: true,
+            ..Default::default()
+        });
+        assert!(shown.contains("Intro [pages 1-1] [confidence 0.80]"));
+        assert!(shown.contains("Background [pages 1-1] [confidence 0.30, check]"));
+        assert!(!tree.format().contains("confidence"));
+    }
+
+    #[test]
+    fn test_visible_to() {
+        let mut finance = TreeNode::new("Finance", 2, 5);
+        finance.acl = vec!["finance".to_string()];
+        let mut payroll = TreeNode::new("Payroll", 4, 5);
+        payroll.acl = vec!["hr".to_string()];
+        finance.add_child(TreeNode::new("Budget", 2, 3));
+        finance.add_child(payroll);
+        let tree = DocumentTree::new("Handbook", vec![TreeNode::new("Welcome", 1, 1), finance], 5);
+        assert!(tree.has_acl());
+
+        let titles = |tree: &DocumentTree| {
+            fn walk(nodes: &[TreeNode], out: &mut Vec<String>) {
+                for n in nodes {
+                    out.push(n.title.clone());
+                    walk(&n.nodes, out);
+                }
+            }
+            let mut out = Vec::new();
+            walk(&tree.nodes, &mut out);
+            out
+        };
+        assert_eq!(titles(&tree.visible_to(&[])), vec!["Welcome"]);
+        assert_eq!(
+            titles(&tree.visible_to(&["finance".to_string()])),
+            vec!["Welcome", "Finance", "Budget"]
+        );
+        // A subsection's labels apply on top of its parent's
+        assert_eq!(
+            titles(&tree.visible_to(&["hr".to_string()])),
+            vec!["Welcome"]
+        );
+        assert_eq!(
+            titles(&tree.visible_to(&["finance".to_string(), "hr".to_string()])).len(),
+            4
+        );
+
+        let payroll = tree.find_section("Payroll", 4, 5).unwrap();
+        assert!(tree.has_section(payroll));
+        assert!(
+            !tree
+                .visible_to(&["finance".to_string()])
+                .has_section(payroll)
+        );
+
+        // Sections with the same pages are told apart by ID
+        let mut benefits = TreeNode::new("Benefits", 1, 1);
+        benefits.node_id = Some("0000".to_string());
+        let mut bonuses = TreeNode::new("Bonuses", 1, 1);
+        bonuses.node_id = Some("0001".to_string());
+        bonuses.acl = vec!["hr".to_string()];
+        let tree = DocumentTree::new("Handbook", vec![benefits, bonuses], 1);
+        let visible = tree.visible_to(&[]);
+        let bonuses = tree.find_section("Bonuses", 1, 1).unwrap();
+        assert_eq!(bonuses.node_id.as_deref(), Some("0001"));
+        assert!(!visible.has_section(bonuses));
+        assert!(visible.has_section(tree.find_section("Benefits", 1, 1).unwrap()));
+        assert_eq!(
+            tree.find_section("Renamed", 1, 1).unwrap().title,
+            "Benefits"
+        );
+    }
+
+    #[test]
+    fn test_effective_dates() {
+        assert_eq!(
+            parse_effective_date(" 2024-02-29 ").as_deref(),
+            Some("2024-02-29")
+        );
+        assert_eq!(
+            parse_effective_date("2023-07").as_deref(),
+            Some("2023-07-01")
+        );
+        assert_eq!(parse_effective_date("2021").as_deref(), Some("2021-01-01"));
+        for invalid in [
+            "2023-02-29",
+            "2023-13-01",
+            "2023-5-01",
+            "2023-05-x",
+            "May 2023",
+            "",
+        ] {
+            assert_eq!(parse_effective_date(invalid), None, "{}", invalid);
+        }
+
+        let mut old = TreeNode::new("Leave policy (2019)", 1, 2);
+        old.effective_date = Some("2019-01-01".to_string());
+        let mut amended = TreeNode::new("Parental leave", 2, 2);
+        amended.effective_date = Some("2024-03-01".to_string());
+        old.add_child(TreeNode::new("Annual leave", 1, 1));
+        old.add_child(amended);
+        let mut current = TreeNode::new("Leave policy (2023)", 3, 4);
+        current.effective_date = Some("2023-01-01".to_string());
+        let tree = DocumentTree::new(
+            "Handbook",
+            vec![old, current, TreeNode::new("Contacts", 5, 5)],
+            5,
+        );
+        assert!(tree.has_effective_dates());
+
+        // Undated sections inherit, from the document last
+        assert_eq!(tree.effective_date_of(1, 1), Some("2019-01-01"));
+        assert_eq!(tree.effective_date_of(2, 2), Some("2024-03-01"));
+        assert_eq!(tree.effective_date_of(5, 5), None);
+        let dated = tree.clone().with_metadata(EFFECTIVE_DATE_KEY, "2018-06-01");
+        assert_eq!(dated.effective_date_of(5, 5), Some("2018-06-01"));
+
+        // Older sections go unless a subsection stays
+        let since = tree.effective_since("2022-01-01");
+        let titles: Vec<_> = since.nodes.iter().map(|n| n.title.as_str()).collect();
+        assert_eq!(
+            titles,
+            vec!["Leave policy (2019)", "Leave policy (2023)", "Contacts"]
+        );
+        assert_eq!(since.nodes[0].nodes.len(), 1);
+        assert_eq!(dated.effective_since("2022-01-01").nodes.len(), 2);
+        // The kept container is still superseded; its subsection is not
+        assert!(!tree.is_current_since(&since.nodes[0], "2022-01-01"));
+        assert!(tree.is_current_since(&since.nodes[0].nodes[0], "2022-01-01"));
+        assert!(tree.is_current_since(&since.nodes[2], "2022-01-01"));
+
+        assert!(
+            tree.format_for_search()
+                .contains("Leave policy (2023) (pages 3-4, effective 2023-01-01)")
+        );
+        assert!(
+            tree.to_outline(&OutlineOptions::default())
+                .contains("Parental leave [2] (effective 2024-03-01)")
+        );
+    }
+
+    #[test]
+    fn test_freshness() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("doc.txt");
+        std::fs::write(&path, "Chapter 1\nText").unwrap();
+
+        let mut tree = DocumentTree::new("doc", Vec::new(), 1);
+        assert_eq!(tree.freshness(), Freshness::Unknown);
+        tree.source = Some(SourceInfo::read(&path).unwrap());
+        assert_eq!(tree.freshness(), Freshness::Fresh);
+
+        // Same size and content, different time: still fresh
+        let touched = tree.source.as_ref().unwrap().modified.map(|m| m + 60);
+        tree.source.as_mut().unwrap().modified = touched;
+        assert_eq!(tree.freshness(), Freshness::Fresh);
+
+        std::fs::write(&path, "Chapter 1\nTexT").unwrap();
+        assert!(tree.freshness().is_stale());
+        std::fs::write(&path, "Chapter 1\nMore text").unwrap();
+        assert_eq!(tree.freshness(), Freshness::Changed);
+        assert_eq!(
+            tree.freshness_of(&dir.path().join("moved.txt")),
+            Freshness::Missing
+        );
+
+        let json = serde_json::to_string(&tree).unwrap();
+        let loaded: DocumentTree = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.source, tree.source);
     }
 
     #[test]
@@ -451,6 +1686,7 @@ mod tests {
             structure: Some("1".to_string()),
             title: "Test".to_string(),
             physical_index: Some(serde_json::Value::Number(5.into())),
+            confidence: None,
         };
         assert_eq!(item1.get_page_number(), Some(5));
 
@@ -458,6 +1694,7 @@ mod tests {
             structure: Some("2".to_string()),
             title: "Test 2".to_string(),
             physical_index: Some(serde_json::Value::String("<physical_index_10>".to_string())),
+            confidence: None,
         };
         assert_eq!(item2.get_page_number(), Some(10));
     }
@@ -472,4 +1709,159 @@ mod tests {
         assert_eq!(parsed.name, tree.name);
         assert_eq!(parsed.total_pages, tree.total_pages);
     }
+
+    #[test]
+    fn test_find_and_remove_node_by_address() {
+        let mut ch1 = TreeNode::new("Chapter 1", 1, 10).with_structure("1");
+        ch1.node_id = Some("0000".to_string());
+        let mut s11 = TreeNode::new("Section 1.1", 1, 5).with_structure("1.1");
+        s11.node_id = Some("0001".to_string());
+        ch1.add_child(s11);
+        let mut tree = DocumentTree::new("Test", vec![ch1], 10);
+
+        assert_eq!(tree.find_node("0001").unwrap().title, "Section 1.1");
+        assert_eq!(tree.find_node("1.1").unwrap().title, "Section 1.1");
+        assert!(tree.find_node("9.9").is_none());
+
+        tree.find_node_mut("1").unwrap().title = "Intro".to_string();
+        assert_eq!(tree.nodes[0].title, "Intro");
+
+        let removed = tree.remove_node("0001").unwrap();
+        assert_eq!(removed.title, "Section 1.1");
+        assert_eq!(tree.node_count(), 1);
+    }
+
+    #[test]
+    fn test_filtered_tree() {
+        let mut ch1 = TreeNode::new("Chapter 1", 1, 10);
+        ch1.add_child(TreeNode::new("Ownership", 1, 5));
+        ch1.add_child(TreeNode::new("Borrowing", 6, 10));
+        let mut ch2 = TreeNode::new("Chapter 2", 11, 20);
+        ch2.add_child(TreeNode::new("Traits", 11, 20));
+        let tree = DocumentTree::new("Test", vec![ch1, ch2], 20);
+
+        let by_title = tree.filtered(&TreeFilter {
+            title: Some("owner".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(by_title.node_count(), 2);
+        assert_eq!(by_title.nodes[0].nodes[0].title, "Ownership");
+
+        let by_pages = tree.filtered(&TreeFilter {
+            pages: Some((9, 12)),
+            ..Default::default()
+        });
+        assert_eq!(by_pages.node_count(), 4);
+
+        let shallow = tree.filtered(&TreeFilter {
+            max_depth: Some(1),
+            ..Default::default()
+        });
+        assert_eq!(shallow.node_count(), 2);
+        assert!(!shallow.format_with(false).contains("Summary"));
+
+        // Matches below the depth limit don't keep their chapters
+        let shallow_by_title = tree.filtered(&TreeFilter {
+            max_depth: Some(1),
+            title: Some("owner".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(shallow_by_title.node_count(), 0);
+        let shallow_by_chapter = tree.filtered(&TreeFilter {
+            max_depth: Some(1),
+            title: Some("chapter 2".to_string()),
+            ..Default::default()
+        });
+        assert_eq!(shallow_by_chapter.node_count(), 1);
+    }
+
+    #[test]
+    fn test_prefix_summaries() {
+        let mut ch1 = TreeNode::new("Methods", 1, 10);
+        ch1.summary = Some("How data was gathered.".to_string());
+        let mut sampling = TreeNode::new("Sampling", 1, 5);
+        sampling.summary = Some("Random selection of sites.".to_string());
+        ch1.add_child(sampling);
+        ch1.add_child(TreeNode::new("Untitled", 6, 10));
+        let mut tree = DocumentTree::new("Report", vec![ch1], 10);
+        assert!(!tree.has_prefix_summaries());
+
+        tree.compose_prefix_summaries();
+        let methods = &tree.nodes[0];
+        assert_eq!(
+            methods.prefix_summary.as_deref(),
+            Some("Methods: How data was gathered.")
+        );
+        assert_eq!(
+            methods.nodes[0].prefix_summary.as_deref(),
+            Some("Methods > Sampling: Random selection of sites.")
+        );
+        assert!(methods.nodes[1].prefix_summary.is_none());
+
+        assert!(!tree.format_for_search().contains("Methods > Sampling"));
+        assert!(
+            tree.format_for_search_with(true)
+                .contains("Sampling (pages 1-5) - Methods > Sampling: Random")
+        );
+
+        tree.description = Some("A field report.".to_string());
+        tree.nodes[0].node_id = Some("0001".to_string());
+        let navigation = tree.to_navigation_only();
+        assert!(navigation.navigation_only && navigation.description.is_none());
+        assert_eq!(navigation.node_count(), tree.node_count());
+        let methods = &navigation.nodes[0];
+        assert_eq!(methods.node_id.as_deref(), Some("0001"));
+        assert_eq!((methods.start_index, methods.end_index), (1, 10));
+        assert!(methods.summary.is_none() && methods.nodes[0].prefix_summary.is_none());
+        assert!(!tree.navigation_only && tree.nodes[0].summary.is_some());
+    }
+
+    #[test]
+    fn test_outline() {
+        let summarized = |title: &str, start, end, summary: &str| {
+            let mut node = TreeNode::new(title, start, end);
+            node.node_id = Some(format!("{:04}", start));
+            node.summary = Some(summary.to_string());
+            node
+        };
+        let mut methods = summarized("Methods", 1, 10, "How data was gathered.");
+        let mut sampling = summarized("Sampling", 1, 5, "Random selection of sites.");
+        sampling.add_child(summarized("Sites", 3, 3, "Twelve coastal sites."));
+        methods.add_child(sampling);
+        methods.add_child(summarized("Analysis", 6, 10, "Statistical tests used."));
+        let tree = DocumentTree::new("Report", vec![methods], 10);
+
+        let full = tree.to_outline(&OutlineOptions {
+            summaries: true,
+            ..Default::default()
+        });
+        assert_eq!(
+            full,
+            "# Report (10 pages)\n\
+             Methods [1-10]: How data was gathered.\n\
+             \x20 Sampling [1-5]: Random selection of sites.\n\
+             \x20   Sites [3]: Twelve coastal sites.\n\
+             \x20 Analysis [6-10]: Statistical tests used.\n"
+        );
+        assert!(full.len() < tree.format_for_search().len());
+
+        // Under a ceiling, the deepest summaries go first, then that level
+        let capped = |max_tokens| {
+            tree.to_outline(&OutlineOptions {
+                summaries: true,
+                prefix_summaries: false,
+                max_tokens: Some(max_tokens),
+            })
+        };
+        let tokens = estimate_tokens(&full);
+        let fewer = capped(tokens - 1);
+        assert!(fewer.contains("Sites [3]\n") && fewer.contains("Sampling [1-5]: Random"));
+        let shallow = capped(estimate_tokens(&fewer) - 1);
+        assert!(!shallow.contains("Sites") && shallow.contains("(+1 subsections)"));
+        let minimal = capped(1);
+        assert_eq!(
+            minimal,
+            "# Report (10 pages)\nMethods [1-10] (+3 subsections)\n"
+        );
+    }
 }
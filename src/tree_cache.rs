@@ -0,0 +1,271 @@
+//! Loaded tree indexes kept in memory up to a budget.
+//!
+//! A server over hundreds of indexes can't keep every tree in memory. A
+//! [`TreeCache`] loads trees from disk when they are asked for and keeps the
+//! most recently used ones, with the descriptions search prompts are built
+//! from (see [`SearchFormats`]), evicting the least recently used once their
+//! estimated size passes the budget.
+//!
+//! ```no_run
+//! use rust_page_indexer::tree_cache::TreeCache;
+//! use std::path::Path;
+//!
+//! # fn run() -> rust_page_indexer::Result<()> {
+//! let cache = TreeCache::new(256 * 1024 * 1024);
+//! let loaded = cache.get(Path::new("data/indexes/report.json"))?;
+//! println!("{} sections", loaded.tree().node_count());
+//! println!("{} of {} bytes used", cache.stats().bytes, cache.stats().budget);
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::error::Result;
+use crate::persistence::load_tree;
+use crate::search::SearchFormats;
+use crate::tree::{DocumentTree, TreeNode};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::debug;
+
+/// A tree in memory, with its search prompt descriptions.
+#[derive(Debug)]
+pub struct LoadedTree {
+    tree: DocumentTree,
+    formats: Arc<SearchFormats>,
+    tree_bytes: usize,
+}
+
+impl LoadedTree {
+    pub fn new(tree: DocumentTree) -> Self {
+        Self {
+            tree_bytes: estimated_bytes(&tree),
+            tree,
+            formats: Arc::new(SearchFormats::new()),
+        }
+    }
+
+    pub fn tree(&self) -> &DocumentTree {
+        &self.tree
+    }
+
+    /// Descriptions of the tree for search prompts, to search it with
+    /// (see [`TreeSearcher::with_search_formats`](crate::search::TreeSearcher::with_search_formats)).
+    pub fn formats(&self) -> Arc<SearchFormats> {
+        self.formats.clone()
+    }
+
+    /// Estimated memory held by the tree and the descriptions rendered so
+    /// far.
+    pub fn bytes(&self) -> usize {
+        self.tree_bytes + self.formats.bytes()
+    }
+}
+
+/// What a [`TreeCache`] holds and how it has been used.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct TreeCacheStats {
+    /// Trees in memory.
+    pub trees: usize,
+    /// Their estimated size in bytes.
+    pub bytes: usize,
+    /// The budget in bytes.
+    pub budget: usize,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+/// Trees loaded from disk on demand, least recently used evicted first.
+pub struct TreeCache {
+    budget: usize,
+    state: Mutex<CacheState>,
+}
+
+#[derive(Default)]
+struct CacheState {
+    /// Trees by path, with when they were last used.
+    entries: HashMap<PathBuf, (Arc<LoadedTree>, u64)>,
+    clock: u64,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+impl TreeCache {
+    /// Keep trees in memory up to about `budget` bytes. The tree last asked
+    /// for is always kept, however large.
+    pub fn new(budget: usize) -> Self {
+        Self {
+            budget,
+            state: Mutex::new(CacheState::default()),
+        }
+    }
+
+    /// The tree saved at `path`, loading it on a miss. Trees evicted while
+    /// a caller still holds them stay in memory until it is done.
+    pub fn get(&self, path: &Path) -> Result<Arc<LoadedTree>> {
+        {
+            let mut state = self.lock();
+            state.clock += 1;
+            let now = state.clock;
+            if let Some((loaded, used)) = state.entries.get_mut(path) {
+                *used = now;
+                let loaded = loaded.clone();
+                state.hits += 1;
+                return Ok(loaded);
+            }
+            state.misses += 1;
+        }
+
+        // Loaded without the lock, so one slow load doesn't hold up hits
+        // on other trees; concurrent misses on one path may load it twice
+        let loaded = Arc::new(LoadedTree::new(load_tree(path)?));
+        debug!(path = %path.display(), bytes = loaded.bytes(), "loaded tree into cache");
+
+        let mut state = self.lock();
+        state.clock += 1;
+        let now = state.clock;
+        state
+            .entries
+            .insert(path.to_path_buf(), (loaded.clone(), now));
+        self.evict(&mut state, path);
+        Ok(loaded)
+    }
+
+    /// Drop a tree, so the next request loads it from disk again. Returns
+    /// whether it was in memory.
+    pub fn invalidate(&self, path: &Path) -> bool {
+        self.lock().entries.remove(path).is_some()
+    }
+
+    pub fn stats(&self) -> TreeCacheStats {
+        let state = self.lock();
+        TreeCacheStats {
+            trees: state.entries.len(),
+            bytes: total_bytes(&state),
+            budget: self.budget,
+            hits: state.hits,
+            misses: state.misses,
+            evictions: state.evictions,
+        }
+    }
+
+    /// Evict the least recently used trees other than `keep` until the
+    /// rest fit the budget.
+    fn evict(&self, state: &mut CacheState, keep: &Path) {
+        while total_bytes(state) > self.budget {
+            let oldest = state
+                .entries
+                .iter()
+                .filter(|(path, _)| path.as_path() != keep)
+                .min_by_key(|(_, (_, used))| *used)
+                .map(|(path, _)| path.clone());
+            let Some(oldest) = oldest else { break };
+            state.entries.remove(&oldest);
+            state.evictions += 1;
+            debug!(path = %oldest.display(), "evicted tree from cache");
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        self.state.lock().expect("tree cache lock poisoned")
+    }
+}
+
+fn total_bytes(state: &CacheState) -> usize {
+    state
+        .entries
+        .values()
+        .map(|(loaded, _)| loaded.bytes())
+        .sum()
+}
+
+/// Rough memory held by a tree: its nodes, their text and embeddings, and
+/// the document's metadata.
+fn estimated_bytes(tree: &DocumentTree) -> usize {
+    fn text(value: &Option<String>) -> usize {
+        value.as_ref().map_or(0, String::len)
+    }
+    fn node_bytes(node: &TreeNode) -> usize {
+        std::mem::size_of::<TreeNode>()
+            + node.title.len()
+            + text(&node.structure)
+            + text(&node.summary)
+            + text(&node.prefix_summary)
+            + text(&node.node_id)
+            + text(&node.original_title)
+            + text(&node.effective_date)
+            + node.acl.iter().map(String::len).sum::<usize>()
+            + node
+                .embedding
+                .as_ref()
+                .map_or(0, |e| std::mem::size_of_val(e.as_slice()))
+            + node.nodes.iter().map(node_bytes).sum::<usize>()
+    }
+
+    std::mem::size_of::<DocumentTree>()
+        + tree.name.len()
+        + text(&tree.description)
+        + tree
+            .metadata
+            .iter()
+            .chain(&tree.terms)
+            .map(|(k, v)| k.len() + v.len())
+            .sum::<usize>()
+        + tree.nodes.iter().map(node_bytes).sum::<usize>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::persistence::save_tree;
+    use tempfile::TempDir;
+
+    fn save(dir: &TempDir, name: &str) -> PathBuf {
+        let mut node = TreeNode::new("Introduction", 1, 2);
+        node.summary = Some("An overview of the report. ".repeat(20));
+        let path = dir.path().join(format!("{}.json", name));
+        save_tree(&DocumentTree::new(name, vec![node], 2), &path).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_tree_cache() {
+        let dir = TempDir::new().unwrap();
+        let paths: Vec<_> = ["a", "b", "c"].iter().map(|n| save(&dir, n)).collect();
+        let one = TreeCache::new(usize::MAX).get(&paths[0]).unwrap().bytes();
+
+        // Room for two trees
+        let cache = TreeCache::new(one * 2 + one / 2);
+        let a = cache.get(&paths[0]).unwrap();
+        assert_eq!(a.tree().name, "a");
+        assert!(Arc::ptr_eq(&a, &cache.get(&paths[0]).unwrap()));
+        cache.get(&paths[1]).unwrap();
+        cache.get(&paths[0]).unwrap();
+        cache.get(&paths[2]).unwrap();
+
+        // "b" was least recently used
+        let stats = cache.stats();
+        assert_eq!((stats.trees, stats.hits, stats.misses), (2, 2, 3));
+        assert_eq!(stats.evictions, 1);
+        assert!(stats.bytes <= stats.budget);
+        cache.get(&paths[0]).unwrap();
+        cache.get(&paths[1]).unwrap();
+        assert_eq!(cache.stats().misses, 4);
+
+        // Descriptions count towards the budget once rendered
+        let loaded = cache.get(&paths[1]).unwrap();
+        let before = loaded.bytes();
+        loaded.formats().get(loaded.tree(), false);
+        assert!(loaded.bytes() > before);
+
+        // A tree over budget is still served
+        let tiny = TreeCache::new(1);
+        assert!(tiny.get(&paths[0]).is_ok());
+        assert_eq!(tiny.stats().trees, 1);
+        assert!(tiny.invalidate(&paths[0]));
+        assert!(tiny.get(&dir.path().join("missing.json")).is_err());
+    }
+}
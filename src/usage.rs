@@ -0,0 +1,168 @@
+//! Post-run usage and cost reporting.
+//!
+//! A [`UsageTracker`] splits a command run into phases, recording wall time
+//! and the LLM usage accrued in each. The resulting [`UsageReport`] can be
+//! printed after the command or saved as JSON (`--usage-report`).
+
+use crate::config::ModelPricing;
+use crate::error::{PageIndexError, Result};
+use crate::llm::UsageStats;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::Instant;
+
+/// Usage and wall time for one phase of a run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PhaseUsage {
+    /// Phase name (e.g. "build_tree", "search")
+    pub name: String,
+    /// Wall time spent in the phase
+    pub wall_time_ms: u64,
+    /// LLM usage accrued during the phase
+    pub usage: UsageStats,
+}
+
+/// Usage summary for a whole command run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageReport {
+    /// Command that was run (e.g. "index", "search", "eval")
+    pub command: String,
+    /// Model the LLM calls were sent to
+    pub model: String,
+    /// Total LLM usage
+    pub usage: UsageStats,
+    /// Estimated cost in USD, if pricing is configured
+    pub estimated_cost_usd: Option<f64>,
+    /// Total wall time
+    pub wall_time_ms: u64,
+    /// Per-phase breakdown, in run order
+    pub phases: Vec<PhaseUsage>,
+}
+
+/// Records phases of a run as they complete.
+///
+/// Each phase starts where the previous one ended, so callers only mark
+/// phase boundaries with [`UsageTracker::phase`].
+pub struct UsageTracker {
+    command: String,
+    model: String,
+    start: Instant,
+    phase_start: Instant,
+    last_usage: UsageStats,
+    phases: Vec<PhaseUsage>,
+}
+
+impl UsageTracker {
+    /// Start tracking a run of `command` against `model`.
+    pub fn new(command: impl Into<String>, model: impl Into<String>) -> Self {
+        let now = Instant::now();
+        Self {
+            command: command.into(),
+            model: model.into(),
+            start: now,
+            phase_start: now,
+            last_usage: UsageStats::default(),
+            phases: Vec::new(),
+        }
+    }
+
+    /// Close the current phase, given the cumulative usage so far.
+    pub fn phase(&mut self, name: impl Into<String>, usage: UsageStats) {
+        let now = Instant::now();
+        self.phases.push(PhaseUsage {
+            name: name.into(),
+            wall_time_ms: now.duration_since(self.phase_start).as_millis() as u64,
+            usage: usage.since(&self.last_usage),
+        });
+        self.phase_start = now;
+        self.last_usage = usage;
+    }
+
+    /// Finish tracking and build the report.
+    pub fn finish(self, pricing: Option<&ModelPricing>) -> UsageReport {
+        let usage = self.last_usage;
+        UsageReport {
+            command: self.command,
+            model: self.model,
+            usage,
+            estimated_cost_usd: pricing
+                .map(|p| p.cost(usage.prompt_tokens, usage.completion_tokens)),
+            wall_time_ms: self.start.elapsed().as_millis() as u64,
+            phases: self.phases,
+        }
+    }
+}
+
+impl UsageReport {
+    /// Print a human-readable summary to stdout.
+    pub fn print_summary(&self) {
+        println!("\nUsage ({}, model {}):", self.command, self.model);
+        println!(
+            "  LLM calls: {}  prompt tokens: {}  completion tokens: {}",
+            self.usage.calls, self.usage.prompt_tokens, self.usage.completion_tokens
+        );
+        match self.estimated_cost_usd {
+            Some(cost) => println!("  Estimated cost: ${:.4}", cost),
+            None => println!("  Estimated cost: n/a (no pricing configured)"),
+        }
+        println!("  Wall time: {:.2}s", self.wall_time_ms as f64 / 1000.0);
+        for phase in &self.phases {
+            println!(
+                "    {:<14} {:>8.2}s  {} calls, {} tokens",
+                phase.name,
+                phase.wall_time_ms as f64 / 1000.0,
+                phase.usage.calls,
+                phase.usage.total_tokens
+            );
+        }
+    }
+
+    /// Save the report as pretty-printed JSON.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| PageIndexError::Serialization(e.to_string()))?;
+        std::fs::write(path, json).map_err(|e| PageIndexError::io(path, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(calls: u64, prompt: u64, completion: u64) -> UsageStats {
+        UsageStats {
+            calls,
+            prompt_tokens: prompt,
+            completion_tokens: completion,
+            total_tokens: prompt + completion,
+        }
+    }
+
+    #[test]
+    fn test_phases_record_usage_deltas() {
+        let mut tracker = UsageTracker::new("index", "gpt-4");
+        tracker.phase("load_document", UsageStats::default());
+        tracker.phase("build_tree", stats(3, 1000, 200));
+        tracker.phase("save_index", stats(3, 1000, 200));
+
+        let pricing = ModelPricing {
+            prompt_per_million: 10.0,
+            completion_per_million: 30.0,
+        };
+        let report = tracker.finish(Some(&pricing));
+
+        assert_eq!(report.usage, stats(3, 1000, 200));
+        assert_eq!(report.phases.len(), 3);
+        assert_eq!(report.phases[1].usage, stats(3, 1000, 200));
+        assert_eq!(report.phases[2].usage, UsageStats::default());
+        let cost = report.estimated_cost_usd.unwrap();
+        assert!((cost - 0.016).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_report_without_pricing() {
+        let report = UsageTracker::new("search", "gpt-4").finish(None);
+        assert!(report.estimated_cost_usd.is_none());
+        assert!(report.phases.is_empty());
+    }
+}
@@ -0,0 +1,339 @@
+//! Validation of tree index files.
+//!
+//! Structural checks catch broken page ranges, nesting, and IDs without any
+//! LLM calls. Optional spot-checks ask the LLM whether a sample of section
+//! titles actually start on their claimed pages in the source document.
+
+use crate::document::Document;
+use crate::error::Result;
+use crate::indexer::TreeIndexer;
+use crate::tree::{DocumentTree, TreeNode};
+use serde::Serialize;
+use std::collections::HashSet;
+
+/// How serious a validation issue is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// The index is broken or misleading.
+    Error,
+    /// The index is usable but suspicious.
+    Warning,
+}
+
+/// A single problem found in an index.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    /// Node ID of the offending node, if it has one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub node_id: Option<String>,
+    /// Title of the offending node (empty for tree-level issues).
+    pub title: String,
+    pub message: String,
+}
+
+impl ValidationIssue {
+    fn error(node: Option<&TreeNode>, message: impl Into<String>) -> Self {
+        Self::new(Severity::Error, node, message)
+    }
+
+    fn warning(node: Option<&TreeNode>, message: impl Into<String>) -> Self {
+        Self::new(Severity::Warning, node, message)
+    }
+
+    fn new(severity: Severity, node: Option<&TreeNode>, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            node_id: node.and_then(|n| n.node_id.clone()),
+            title: node.map(|n| n.title.clone()).unwrap_or_default(),
+            message: message.into(),
+        }
+    }
+}
+
+/// Result of validating an index.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ValidationReport {
+    /// All issues found, in tree order.
+    pub issues: Vec<ValidationIssue>,
+    /// Number of titles spot-checked against the source document.
+    pub spot_checks: usize,
+}
+
+impl ValidationReport {
+    /// Number of error-level issues.
+    pub fn error_count(&self) -> usize {
+        self.count(Severity::Error)
+    }
+
+    /// Number of warning-level issues.
+    pub fn warning_count(&self) -> usize {
+        self.count(Severity::Warning)
+    }
+
+    /// Whether any error-level issues were found.
+    pub fn has_errors(&self) -> bool {
+        self.error_count() > 0
+    }
+
+    fn count(&self, severity: Severity) -> usize {
+        self.issues
+            .iter()
+            .filter(|i| i.severity == severity)
+            .count()
+    }
+}
+
+/// Run structural checks on a tree.
+pub fn validate_tree(tree: &DocumentTree) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+
+    if tree.total_pages == 0 {
+        issues.push(ValidationIssue::error(None, "tree has zero total pages"));
+    }
+    if tree.nodes.is_empty() {
+        issues.push(ValidationIssue::warning(None, "tree has no sections"));
+    }
+
+    let mut seen_ids = HashSet::new();
+    check_siblings(
+        &tree.nodes,
+        None,
+        tree.total_pages,
+        &mut seen_ids,
+        &mut issues,
+    );
+    issues
+}
+
+fn check_siblings<'a>(
+    nodes: &'a [TreeNode],
+    parent: Option<&TreeNode>,
+    total_pages: usize,
+    seen_ids: &mut HashSet<&'a str>,
+    issues: &mut Vec<ValidationIssue>,
+) {
+    for (i, node) in nodes.iter().enumerate() {
+        check_node(node, parent, total_pages, seen_ids, issues);
+
+        // Siblings may share a boundary page, but must not go backwards
+        if i > 0 && node.start_index < nodes[i - 1].start_index {
+            issues.push(ValidationIssue::warning(
+                Some(node),
+                format!(
+                    "starts on page {} before previous sibling '{}' (page {})",
+                    node.start_index,
+                    nodes[i - 1].title,
+                    nodes[i - 1].start_index
+                ),
+            ));
+        }
+
+        check_siblings(&node.nodes, Some(node), total_pages, seen_ids, issues);
+    }
+}
+
+fn check_node<'a>(
+    node: &'a TreeNode,
+    parent: Option<&TreeNode>,
+    total_pages: usize,
+    seen_ids: &mut HashSet<&'a str>,
+    issues: &mut Vec<ValidationIssue>,
+) {
+    if node.title.trim().is_empty() {
+        issues.push(ValidationIssue::error(Some(node), "empty title"));
+    }
+
+    match node.node_id.as_deref() {
+        Some(id) if !seen_ids.insert(id) => {
+            issues.push(ValidationIssue::error(
+                Some(node),
+                format!("duplicate node_id '{}'", id),
+            ));
+        }
+        Some(_) => {}
+        None => issues.push(ValidationIssue::warning(Some(node), "missing node_id")),
+    }
+
+    if node.start_index == 0 {
+        issues.push(ValidationIssue::error(
+            Some(node),
+            "start_index is 0 (pages are 1-indexed)",
+        ));
+    }
+    if node.end_index < node.start_index {
+        issues.push(ValidationIssue::error(
+            Some(node),
+            format!(
+                "end_index {} is before start_index {}",
+                node.end_index, node.start_index
+            ),
+        ));
+    }
+    if total_pages > 0 && node.end_index > total_pages {
+        issues.push(ValidationIssue::error(
+            Some(node),
+            format!(
+                "end_index {} is past the last page ({})",
+                node.end_index, total_pages
+            ),
+        ));
+    }
+
+    if let Some(parent) = parent
+        && (node.start_index < parent.start_index || node.end_index > parent.end_index)
+    {
+        issues.push(ValidationIssue::error(
+            Some(node),
+            format!(
+                "pages {}-{} fall outside parent '{}' (pages {}-{})",
+                node.start_index,
+                node.end_index,
+                parent.title,
+                parent.start_index,
+                parent.end_index
+            ),
+        ));
+    }
+}
+
+/// Pick up to `count` nodes spread evenly across the tree, in tree order.
+pub fn sample_nodes(tree: &DocumentTree, count: usize) -> Vec<&TreeNode> {
+    fn flatten<'a>(nodes: &'a [TreeNode], out: &mut Vec<&'a TreeNode>) {
+        for node in nodes {
+            out.push(node);
+            flatten(&node.nodes, out);
+        }
+    }
+
+    let mut all = Vec::new();
+    flatten(&tree.nodes, &mut all);
+    if count == 0 || all.is_empty() {
+        return Vec::new();
+    }
+    if all.len() <= count {
+        return all;
+    }
+
+    (0..count).map(|i| all[i * all.len() / count]).collect()
+}
+
+/// Validate a tree, optionally spot-checking titles against the source document.
+///
+/// Spot-checks use one LLM call per sampled node; nodes whose start page is
+/// missing from the document are reported without an LLM call.
+pub async fn validate_index(
+    tree: &DocumentTree,
+    source: Option<(&Document, &TreeIndexer)>,
+    spot_checks: usize,
+) -> Result<ValidationReport> {
+    let mut report = ValidationReport {
+        issues: validate_tree(tree),
+        spot_checks: 0,
+    };
+
+    let Some((document, indexer)) = source else {
+        return Ok(report);
+    };
+
+    if document.page_count() != tree.total_pages {
+        report.issues.push(ValidationIssue::warning(
+            None,
+            format!(
+                "document has {} pages but the index expects {}",
+                document.page_count(),
+                tree.total_pages
+            ),
+        ));
+    }
+
+    for node in sample_nodes(tree, spot_checks) {
+        let Some(page) = document.get_page(node.start_index) else {
+            report.issues.push(ValidationIssue::error(
+                Some(node),
+                format!("start page {} is not in the document", node.start_index),
+            ));
+            continue;
+        };
+
+        report.spot_checks += 1;
+        if !indexer
+            .verify_title_on_page(&node.title, &page.content)
+            .await?
+        {
+            report.issues.push(ValidationIssue::error(
+                Some(node),
+                format!("title not found on page {}", node.start_index),
+            ));
+        }
+    }
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(title: &str, start: usize, end: usize, id: &str) -> TreeNode {
+        let mut node = TreeNode::new(title, start, end);
+        node.node_id = Some(id.to_string());
+        node
+    }
+
+    #[test]
+    fn test_valid_tree_has_no_issues() {
+        let mut ch1 = node("Chapter 1", 1, 10, "0000");
+        ch1.add_child(node("Section 1.1", 1, 5, "0001"));
+        ch1.add_child(node("Section 1.2", 5, 10, "0002"));
+        let tree = DocumentTree::new("Doc", vec![ch1, node("Chapter 2", 11, 20, "0003")], 20);
+
+        assert!(validate_tree(&tree).is_empty());
+    }
+
+    #[test]
+    fn test_structural_errors_are_reported() {
+        let mut ch1 = node("Chapter 1", 1, 10, "0000");
+        ch1.add_child(node("Escapes parent", 8, 12, "0001"));
+        let tree = DocumentTree::new(
+            "Doc",
+            vec![
+                ch1,
+                node("Backwards", 9, 3, "0002"),
+                node("", 4, 25, "0002"),
+            ],
+            20,
+        );
+
+        let report = ValidationReport {
+            issues: validate_tree(&tree),
+            spot_checks: 0,
+        };
+        let messages: Vec<_> = report.issues.iter().map(|i| i.message.as_str()).collect();
+
+        assert!(messages.iter().any(|m| m.contains("outside parent")));
+        assert!(messages.iter().any(|m| m.contains("before start_index")));
+        assert!(messages.iter().any(|m| m.contains("past the last page")));
+        assert!(messages.iter().any(|m| m.contains("duplicate node_id")));
+        assert!(messages.iter().any(|m| m.contains("empty title")));
+        assert_eq!(report.warning_count(), 1); // "Backwards"-sibling ordering
+        assert!(report.has_errors());
+    }
+
+    #[test]
+    fn test_sample_nodes_spreads_evenly() {
+        let nodes: Vec<_> = (0..10)
+            .map(|i| node(&format!("S{}", i), i + 1, i + 1, &format!("{:04}", i)))
+            .collect();
+        let tree = DocumentTree::new("Doc", nodes, 10);
+
+        let titles: Vec<_> = sample_nodes(&tree, 3)
+            .iter()
+            .map(|n| n.title.as_str())
+            .collect();
+        assert_eq!(titles, vec!["S0", "S3", "S6"]);
+        assert_eq!(sample_nodes(&tree, 20).len(), 10);
+        assert!(sample_nodes(&tree, 0).is_empty());
+    }
+}
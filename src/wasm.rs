@@ -0,0 +1,113 @@
+//! JavaScript bindings for the browser (`wasm` feature, wasm32 only).
+//!
+//! A browser UI can load a tree saved as JSON and search it client-side;
+//! LLM calls go straight from the page to an OpenAI-compatible endpoint
+//! through `fetch`, so the endpoint must allow the page's origin (CORS).
+//!
+//! ```js
+//! import init, { WasmTree } from "./pkg/rust_page_indexer.js";
+//!
+//! await init();
+//! const tree = WasmTree.fromJson(await (await fetch("tree_index.json")).text());
+//! const llm = JSON.stringify({ api_base: "https://api.openai.com", api_key: key, model: "gpt-4o" });
+//! const results = JSON.parse(await tree.search("What was revenue growth?", llm));
+//! ```
+
+use crate::config::LlmConfig;
+use crate::document::Document;
+use crate::llm::LlmClient;
+use crate::search::TreeSearcher;
+use crate::tree::DocumentTree;
+use js_sys::Promise;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::future_to_promise;
+
+/// A document tree loaded in the browser.
+#[wasm_bindgen]
+pub struct WasmTree {
+    tree: DocumentTree,
+}
+
+#[wasm_bindgen]
+impl WasmTree {
+    /// Parse a tree saved as JSON (`page-indexer index <doc> -o tree.json`).
+    #[wasm_bindgen(js_name = fromJson)]
+    pub fn from_json(json: &str) -> Result<WasmTree, JsError> {
+        Ok(Self {
+            tree: DocumentTree::from_json(json)?,
+        })
+    }
+
+    /// Serialize the tree back to JSON.
+    #[wasm_bindgen(js_name = toJson)]
+    pub fn to_json(&self) -> Result<String, JsError> {
+        Ok(self.tree.to_json()?)
+    }
+
+    /// Document name.
+    #[wasm_bindgen(getter)]
+    pub fn name(&self) -> String {
+        self.tree.name.clone()
+    }
+
+    /// Total number of nodes.
+    #[wasm_bindgen(js_name = nodeCount)]
+    pub fn node_count(&self) -> usize {
+        self.tree.node_count()
+    }
+
+    /// Indented outline of the tree, as printed by the CLI.
+    pub fn format(&self) -> String {
+        self.tree.format()
+    }
+
+    /// Search the tree. `llm_config` is a JSON [`LlmConfig`] (`api_base`,
+    /// `api_key`, `model`, ...). Resolves to a JSON array of results.
+    pub fn search(&self, query: String, llm_config: &str) -> Result<Promise, JsError> {
+        let client = client(llm_config)?;
+        let tree = self.tree.clone();
+        Ok(future_to_promise(async move {
+            let results = TreeSearcher::new(client)
+                .search(&tree, &query)
+                .await
+                .map_err(to_js)?;
+            Ok(JsValue::from_str(
+                &serde_json::to_string(&results).map_err(to_js)?,
+            ))
+        }))
+    }
+
+    /// Search the tree and attach each section's text from the document it
+    /// was built from (pages tagged as in `Document::from_text`).
+    #[wasm_bindgen(js_name = searchWithContent)]
+    pub fn search_with_content(
+        &self,
+        query: String,
+        document: String,
+        llm_config: &str,
+    ) -> Result<Promise, JsError> {
+        let client = client(llm_config)?;
+        let tree = self.tree.clone();
+        let document = Document::from_text(tree.name.clone(), document);
+        Ok(future_to_promise(async move {
+            let results = TreeSearcher::new(client)
+                .search_with_content(&tree, &document, &query)
+                .await
+                .map_err(to_js)?;
+            Ok(JsValue::from_str(
+                &serde_json::to_string(&results).map_err(to_js)?,
+            ))
+        }))
+    }
+}
+
+/// Build a client from a JSON [`LlmConfig`].
+fn client(llm_config: &str) -> Result<LlmClient, JsError> {
+    let config: LlmConfig = serde_json::from_str(llm_config)?;
+    Ok(LlmClient::new(config))
+}
+
+/// Convert an error into a rejected promise's value.
+fn to_js(err: impl std::fmt::Display) -> JsValue {
+    JsError::new(&err.to_string()).into()
+}
@@ -0,0 +1,330 @@
+//! Watch a document directory and keep its indexes up to date.
+//!
+//! Each document under the watched directory gets its own index file in the
+//! target index directory. A document is re-indexed only when it is newer
+//! than its index, so restarts and bursts of edits stay cheap.
+
+use crate::error::{PageIndexError, Result};
+use crate::indexer::TreeIndexer;
+use crate::outcome::PartialResult;
+use crate::persistence::{long_path, save_tree};
+use notify::{Event, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+use walkdir::WalkDir;
+
+/// File extensions treated as documents out of the box; extensions
+/// registered with [`crate::loader`] are watched too.
+pub const DOCUMENT_EXTENSIONS: &[&str] = crate::loader::TEXT_EXTENSIONS;
+
+/// Default time to wait for a burst of file events to settle.
+const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// Check whether a path looks like an indexable document.
+pub fn is_document(path: &Path) -> bool {
+    crate::loader::supports(path)
+}
+
+/// List all documents under a directory, sorted by path.
+pub fn scan_documents(dir: &Path) -> Result<Vec<PathBuf>> {
+    if !dir.is_dir() {
+        return Err(PageIndexError::InvalidCorpusPath(dir.to_path_buf()));
+    }
+
+    let mut documents: Vec<PathBuf> = WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file() && is_document(entry.path()))
+        .map(|entry| entry.into_path())
+        .collect();
+    documents.sort();
+    Ok(documents)
+}
+
+/// Index file path for a document under `root`.
+///
+/// The document's relative path is flattened into the file name
+/// (`notes/a.md` -> `notes__a.md.json`) so documents with the same name in
+/// different subdirectories don't collide.
+pub fn index_path_for(root: &Path, document: &Path, index_dir: &Path) -> PathBuf {
+    let relative = document.strip_prefix(root).unwrap_or(document);
+    let flattened = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("__");
+    index_dir.join(format!("{}.json", flattened))
+}
+
+/// Check whether a document changed since its index was written.
+pub fn needs_reindex(document: &Path, index: &Path) -> bool {
+    let modified = |path: &Path| fs::metadata(path).and_then(|m| m.modified()).ok();
+    match (modified(document), modified(&long_path(index))) {
+        (Some(doc_time), Some(index_time)) => doc_time > index_time,
+        _ => true,
+    }
+}
+
+/// Outcome of syncing a directory with its indexes.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SyncStats {
+    /// Documents that were (re-)indexed.
+    pub indexed: usize,
+    /// Documents whose index was already up to date.
+    pub unchanged: usize,
+    /// Documents that failed to index.
+    pub failed: usize,
+}
+
+/// Per-document outcome of syncing a directory with its indexes.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SyncReport {
+    /// Documents that were (re-)indexed, and those that failed to index.
+    pub indexed: PartialResult<PathBuf>,
+    /// Documents whose index was already up to date.
+    pub unchanged: Vec<PathBuf>,
+}
+
+impl SyncReport {
+    /// Document counts.
+    pub fn stats(&self) -> SyncStats {
+        SyncStats {
+            indexed: self.indexed.succeeded.len(),
+            unchanged: self.unchanged.len(),
+            failed: self.indexed.failed.len(),
+        }
+    }
+}
+
+/// Keeps a directory of documents indexed into a target index directory.
+pub struct DirectoryWatcher {
+    root: PathBuf,
+    index_dir: PathBuf,
+    indexer: TreeIndexer,
+    debounce: Duration,
+}
+
+impl DirectoryWatcher {
+    /// Create a watcher for `root`, writing indexes into `index_dir`.
+    pub fn new(
+        root: impl Into<PathBuf>,
+        index_dir: impl Into<PathBuf>,
+        indexer: TreeIndexer,
+    ) -> Self {
+        Self {
+            root: root.into(),
+            index_dir: index_dir.into(),
+            indexer,
+            debounce: DEFAULT_DEBOUNCE,
+        }
+    }
+
+    /// Set how long to wait for file events to settle before re-indexing.
+    pub fn with_debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// Index every document that is new or changed since its last index.
+    ///
+    /// A document that fails to index is recorded in the report instead of
+    /// stopping the sync; only an unreadable directory fails it.
+    pub async fn sync(&self) -> Result<SyncReport> {
+        fs::create_dir_all(&self.index_dir).map_err(|e| PageIndexError::io(&self.index_dir, e))?;
+
+        let mut report = SyncReport::default();
+        for document in scan_documents(&self.root)? {
+            let index = index_path_for(&self.root, &document, &self.index_dir);
+            if !needs_reindex(&document, &index) {
+                report.unchanged.push(document);
+                continue;
+            }
+            let result = self.reindex(&document).await;
+            if let Err(e) = &result {
+                warn!(document = %document.display(), kind = e.kind(), "failed to index: {}", e);
+            }
+            let item = document.display().to_string();
+            report.indexed.push(item, result.map(|()| document));
+        }
+        Ok(report)
+    }
+
+    /// Build and save the index for a single document.
+    pub async fn reindex(&self, document: &Path) -> Result<()> {
+        let index = index_path_for(&self.root, document, &self.index_dir);
+        info!(document = %document.display(), index = %index.display(), "indexing");
+
+        let doc = crate::loader::load(document)?;
+        let tree = self.indexer.index(&doc).await?;
+        save_tree(&tree, &index)
+    }
+
+    /// Delete the index of a document that no longer exists.
+    pub fn remove(&self, document: &Path) -> Result<()> {
+        let index = index_path_for(&self.root, document, &self.index_dir);
+        if index.exists() {
+            info!(document = %document.display(), "removing index");
+            fs::remove_file(&index).map_err(|e| PageIndexError::io(&index, e))?;
+        }
+        Ok(())
+    }
+
+    /// Sync once, then re-index documents as they change until the watcher fails.
+    pub async fn watch(&self) -> Result<()> {
+        let stats = self.sync().await?.stats();
+        info!(
+            indexed = stats.indexed,
+            unchanged = stats.unchanged,
+            failed = stats.failed,
+            "initial sync complete"
+        );
+
+        let (tx, mut rx) = mpsc::unbounded_channel::<notify::Result<Event>>();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = tx.send(event);
+        })
+        .map_err(|e| PageIndexError::Watch(e.to_string()))?;
+        watcher
+            .watch(&self.root, RecursiveMode::Recursive)
+            .map_err(|e| PageIndexError::Watch(e.to_string()))?;
+
+        // Events carry absolute paths; map them back under `root` as given
+        let canonical_root =
+            fs::canonicalize(&self.root).map_err(|e| PageIndexError::io(&self.root, e))?;
+
+        info!(root = %self.root.display(), "watching for changes");
+
+        while let Some(event) = rx.recv().await {
+            let mut changed = BTreeSet::new();
+            collect_paths(event, &mut changed);
+
+            // Let a burst of events (e.g. editor save sequences) settle
+            while let Ok(Some(event)) = tokio::time::timeout(self.debounce, rx.recv()).await {
+                collect_paths(event, &mut changed);
+            }
+
+            for path in changed {
+                if !is_document(&path) {
+                    continue;
+                }
+                let path = match path.strip_prefix(&canonical_root) {
+                    Ok(relative) => self.root.join(relative),
+                    Err(_) => path,
+                };
+                let result = if path.exists() {
+                    self.reindex(&path).await
+                } else {
+                    self.remove(&path)
+                };
+                if let Err(e) = result {
+                    warn!(document = %path.display(), "failed to update index: {}", e);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Add the paths touched by a watcher event to `paths`.
+fn collect_paths(event: notify::Result<Event>, paths: &mut BTreeSet<PathBuf>) {
+    match event {
+        Ok(event) if !event.kind.is_access() => {
+            debug!(kind = ?event.kind, paths = ?event.paths, "file event");
+            paths.extend(event.paths);
+        }
+        Ok(_) => {}
+        Err(e) => warn!("watch error: {}", e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::LlmConfig;
+    use crate::llm::LlmClient;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_is_document() {
+        assert!(is_document(Path::new("notes/readme.md")));
+        assert!(is_document(Path::new("report.TXT")));
+        assert!(!is_document(Path::new("image.png")));
+        assert!(!is_document(Path::new("Makefile")));
+    }
+
+    #[test]
+    fn test_index_path_for_flattens_subdirectories() {
+        let root = Path::new("/kb");
+        let index_dir = Path::new("/indexes");
+        assert_eq!(
+            index_path_for(root, Path::new("/kb/notes/a.md"), index_dir),
+            PathBuf::from("/indexes/notes__a.md.json")
+        );
+        assert_eq!(
+            index_path_for(root, Path::new("/kb/a.md"), index_dir),
+            PathBuf::from("/indexes/a.md.json")
+        );
+    }
+
+    #[test]
+    fn test_scan_and_needs_reindex() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir(dir.path().join("sub")).unwrap();
+        let doc = dir.path().join("sub/doc.txt");
+        fs::write(&doc, "hello").unwrap();
+        fs::write(dir.path().join("skip.bin"), "x").unwrap();
+
+        assert_eq!(scan_documents(dir.path()).unwrap(), vec![doc.clone()]);
+
+        let index = dir.path().join("doc.json");
+        assert!(needs_reindex(&doc, &index));
+
+        fs::write(&index, "{}").unwrap();
+        let later = fs::metadata(&doc).unwrap().modified().unwrap() + Duration::from_secs(5);
+        fs::File::options()
+            .write(true)
+            .open(&index)
+            .unwrap()
+            .set_modified(later)
+            .unwrap();
+        assert!(!needs_reindex(&doc, &index));
+    }
+
+    #[tokio::test]
+    async fn test_sync_records_failures() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("a.txt"), "Alpha").unwrap();
+        fs::write(dir.path().join("b.md"), "Beta").unwrap();
+        let client = LlmClient::new(LlmConfig {
+            api_base: "http://127.0.0.1:9".to_string(),
+            api_key: "x".to_string(),
+            model: "m".to_string(),
+            ..Default::default()
+        });
+        let watcher = DirectoryWatcher::new(
+            dir.path(),
+            dir.path().join("indexes"),
+            TreeIndexer::new(client),
+        );
+
+        let report = watcher.sync().await.unwrap();
+        assert!(report.indexed.succeeded.is_empty());
+        assert!(report.indexed.failed[0].item.ends_with("a.txt"));
+        assert_eq!(report.indexed.summary().by_kind["http"], 2);
+        assert_eq!(report.stats().failed, 2);
+    }
+
+    #[test]
+    fn test_scan_rejects_missing_dir() {
+        assert!(matches!(
+            scan_documents(Path::new("/nonexistent/kb")),
+            Err(PageIndexError::InvalidCorpusPath(_))
+        ));
+    }
+}